@@ -3,9 +3,20 @@ fn main() {
       "load",
       "execute",
       "execute_transaction",
+      "enable_write_batching",
+      "disable_write_batching",
+      "set_decode_options",
       "begin_interruptible_transaction",
       "transaction_continue",
       "transaction_read",
+      "transaction_fetch_one",
+      "transaction_heartbeat",
+      "transaction_abort",
+      "list_transactions",
+      "begin_read_snapshot",
+      "snapshot_fetch_all",
+      "snapshot_fetch_one",
+      "release_read_snapshot",
       "fetch_all",
       "fetch_one",
       "fetch_page",
@@ -17,6 +28,8 @@ fn main() {
       "subscribe",
       "unsubscribe",
       "unobserve",
+      "watch_query",
+      "unwatch_query",
    ])
    .build();
 }
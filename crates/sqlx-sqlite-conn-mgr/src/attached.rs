@@ -1,13 +1,15 @@
 //! Attached database support for cross-database queries
 
 use crate::Result;
+use crate::config::SqliteDatabaseConfig;
 use crate::database::SqliteDatabase;
 use crate::error::Error;
+use crate::interrupt::InterruptHandle;
+use crate::read_guard::ReadGuard;
 use crate::write_guard::WriteGuard;
-use sqlx::Sqlite;
-use sqlx::pool::PoolConnection;
 use sqlx::sqlite::SqliteConnection;
 use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 /// Specification for attaching a database to a connection
@@ -21,10 +23,142 @@ pub struct AttachedSpec {
    pub mode: AttachedMode,
 }
 
+impl AttachedSpec {
+   /// Create a spec that attaches `database` as `schema_name` in read-only mode
+   pub fn read_only(database: Arc<SqliteDatabase>, schema_name: impl Into<String>) -> Self {
+      Self {
+         database,
+         schema_name: schema_name.into(),
+         mode: AttachedMode::ReadOnly,
+      }
+   }
+
+   /// Create a spec that attaches `database` as `schema_name` in read-write mode
+   pub fn read_write(database: Arc<SqliteDatabase>, schema_name: impl Into<String>) -> Self {
+      Self {
+         database,
+         schema_name: schema_name.into(),
+         mode: AttachedMode::ReadWrite,
+      }
+   }
+
+   /// Start building a spec with validation up front
+   ///
+   /// Unlike constructing the struct directly, [`AttachedSpecBuilder::build`]
+   /// checks the schema name, verifies that the database file exists (so a typo
+   /// doesn't silently attach a new, empty database) and canonicalizes paths
+   /// before opening them.
+   pub fn builder() -> AttachedSpecBuilder {
+      AttachedSpecBuilder::default()
+   }
+}
+
+/// Where an [`AttachedSpecBuilder`] gets its database from
+enum AttachedSource {
+   Database(Arc<SqliteDatabase>),
+   Path(PathBuf, Option<SqliteDatabaseConfig>),
+}
+
+/// Builder for [`AttachedSpec`], created by [`AttachedSpec::builder`]
+///
+/// Attaches read-write unless [`read_only`](Self::read_only) is set.
+#[derive(Default)]
+#[must_use = "call build() to create the AttachedSpec"]
+pub struct AttachedSpecBuilder {
+   source: Option<AttachedSource>,
+   schema_name: Option<String>,
+   read_only: bool,
+}
+
+impl AttachedSpecBuilder {
+   /// Attach an already connected database
+   pub fn database(mut self, database: Arc<SqliteDatabase>) -> Self {
+      self.source = Some(AttachedSource::Database(database));
+      self
+   }
+
+   /// Attach the existing database file at `path`, connecting to it with the
+   /// default configuration if it isn't connected yet
+   pub fn path(self, path: impl AsRef<Path>) -> Self {
+      self.path_with_config(path, None)
+   }
+
+   /// Like [`path`](Self::path), with a custom configuration for the connection
+   pub fn path_with_config(
+      mut self,
+      path: impl AsRef<Path>,
+      config: Option<SqliteDatabaseConfig>,
+   ) -> Self {
+      self.source = Some(AttachedSource::Path(path.as_ref().to_path_buf(), config));
+      self
+   }
+
+   /// Schema name (alias) the database is attached as, e.g. `orders` for
+   /// `SELECT * FROM orders.items`
+   pub fn schema_name(mut self, schema_name: impl Into<String>) -> Self {
+      self.schema_name = Some(schema_name.into());
+      self
+   }
+
+   /// Attach read-only; SQLite then rejects writes to the attached schema
+   pub fn read_only(mut self) -> Self {
+      self.read_only = true;
+      self
+   }
+
+   /// Validate the spec, opening the database when given a path
+   pub async fn build(self) -> Result<AttachedSpec> {
+      let schema_name = self
+         .schema_name
+         .ok_or_else(|| Error::InvalidAttachedSpec("no schema name given".into()))?;
+      if !is_valid_schema_name(&schema_name) {
+         return Err(Error::InvalidSchemaName(schema_name));
+      }
+
+      let database = match self.source {
+         None => return Err(Error::InvalidAttachedSpec("no database given".into())),
+         Some(AttachedSource::Database(database)) => {
+            if !database.path().exists() {
+               return Err(Error::AttachedDatabaseNotFound(
+                  database.path().to_path_buf(),
+               ));
+            }
+            database
+         }
+         Some(AttachedSource::Path(path, config)) => {
+            let path = std::fs::canonicalize(&path).map_err(|err| match err.kind() {
+               std::io::ErrorKind::NotFound => Error::AttachedDatabaseNotFound(path),
+               _ => Error::Io(err),
+            })?;
+            if !path.is_file() {
+               return Err(Error::InvalidAttachedSpec(format!(
+                  "'{}' is not a file",
+                  path.display()
+               )));
+            }
+            SqliteDatabase::connect(&path, config).await?
+         }
+      };
+
+      Ok(AttachedSpec {
+         database,
+         schema_name,
+         mode: if self.read_only {
+            AttachedMode::ReadOnly
+         } else {
+            AttachedMode::ReadWrite
+         },
+      })
+   }
+}
+
 /// Mode for attaching a database
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AttachedMode {
    /// Attach database as read-only
+   ///
+   /// Enforced by SQLite: the database is attached with a `mode=ro` URI, so writes to
+   /// its schema fail even on a writer connection.
    ReadOnly,
    /// Attach database as read-write (requires acquiring the database's writer)
    ReadWrite,
@@ -38,7 +172,7 @@ pub enum AttachedMode {
 #[must_use = "if unused, the attached connection and locks are immediately dropped"]
 #[derive(Debug)]
 pub struct AttachedReadConnection {
-   conn: PoolConnection<Sqlite>,
+   conn: ReadGuard,
    /// Write locks for attached databases in ReadWrite mode.
    /// These are never read directly but must be held for their entire lifetime
    /// to prevent other operations from writing to attached databases.
@@ -52,7 +186,7 @@ pub struct AttachedReadConnection {
 
 impl AttachedReadConnection {
    pub(crate) fn new(
-      conn: PoolConnection<Sqlite>,
+      conn: ReadGuard,
       held_writers: Vec<WriteGuard>,
       schema_names: Vec<String>,
    ) -> Self {
@@ -75,6 +209,17 @@ impl AttachedReadConnection {
       }
       Ok(())
    }
+
+   /// Get a handle that interrupts statements running on this connection only
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      self.conn.interrupt_handle()
+   }
+
+   /// Close the read connection when the guard is dropped instead of returning
+   /// it to the pool; see [`ReadGuard::close_on_drop`]
+   pub fn close_on_drop(&mut self) {
+      self.conn.close_on_drop();
+   }
 }
 
 impl Deref for AttachedReadConnection {
@@ -145,6 +290,11 @@ impl AttachedWriteGuard {
       }
       Ok(())
    }
+
+   /// Get a handle that interrupts statements running on this writer
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      self.writer.interrupt_handle()
+   }
 }
 
 impl Deref for AttachedWriteGuard {
@@ -176,6 +326,7 @@ impl Drop for AttachedWriteGuard {
 /// - Must not be empty
 /// - Must contain only ASCII alphanumeric characters and underscores
 /// - Must not start with a digit
+/// - Must not be one of SQLite's built-in schema names (`main`, `temp`)
 ///
 /// This prevents SQL injection by ensuring the schema name can only be used
 /// as an identifier and cannot:
@@ -187,6 +338,94 @@ fn is_valid_schema_name(name: &str) -> bool {
    !name.is_empty()
       && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
       && !name.chars().next().unwrap().is_ascii_digit()
+      && !name.eq_ignore_ascii_case("main")
+      && !name.eq_ignore_ascii_case("temp")
+}
+
+/// Validates every schema name and rejects names used more than once
+///
+/// SQLite schema names are case-insensitive, so `Logs` and `logs` collide.
+fn validate_schema_names(specs: &[AttachedSpec]) -> Result<()> {
+   use std::collections::HashSet;
+   let mut seen_names = HashSet::new();
+
+   for spec in specs {
+      if !is_valid_schema_name(&spec.schema_name) {
+         return Err(Error::InvalidSchemaName(spec.schema_name.clone()));
+      }
+
+      if !seen_names.insert(spec.schema_name.to_ascii_lowercase()) {
+         return Err(Error::DuplicateSchemaName(spec.schema_name.clone()));
+      }
+   }
+
+   Ok(())
+}
+
+/// Build the `ATTACH DATABASE` statement for a spec
+///
+/// Read-only attachments use a `file:` URI with `mode=ro` so SQLite itself rejects
+/// writes to the attached schema. The schema name must already be validated.
+fn attach_sql(spec: &AttachedSpec) -> String {
+   let path = spec.database.path_str();
+
+   let target = match spec.mode {
+      AttachedMode::ReadOnly => format!("file:{}?mode=ro", uri_escape_path(&path)),
+      AttachedMode::ReadWrite => path,
+   };
+
+   let escaped_target = target.replace("'", "''");
+   format!(
+      "ATTACH DATABASE '{}' AS \"{}\"",
+      escaped_target, spec.schema_name
+   )
+}
+
+/// Run the ATTACH statement for `spec`, naming the database and schema in the
+/// error if SQLite rejects it (e.g. the file is not a database or is locked)
+async fn attach(conn: &mut SqliteConnection, spec: &AttachedSpec) -> Result<()> {
+   sqlx::query(&attach_sql(spec))
+      .execute(conn)
+      .await
+      .map_err(|source| Error::AttachFailed {
+         schema_name: spec.schema_name.clone(),
+         path: spec.database.path_str(),
+         source,
+      })?;
+   Ok(())
+}
+
+/// Escape a filesystem path for use in the path component of a SQLite `file:` URI
+///
+/// See: https://www.sqlite.org/uri.html
+pub(crate) fn uri_escape_path(path: &str) -> String {
+   let mut escaped = String::with_capacity(path.len());
+
+   // URIs always use forward slashes, and absolute Windows paths need a leading slash
+   // before the drive letter (file:/C:/...)
+   let path = if cfg!(windows) {
+      let path = path.replace('\\', "/");
+      if path.starts_with('/') {
+         path
+      } else if std::path::Path::new(&path).is_absolute() {
+         format!("/{path}")
+      } else {
+         path
+      }
+   } else {
+      path.to_string()
+   };
+
+   for c in path.chars() {
+      match c {
+         '%' => escaped.push_str("%25"),
+         '?' => escaped.push_str("%3f"),
+         '#' => escaped.push_str("%23"),
+         _ => escaped.push(c),
+      }
+   }
+
+   escaped
 }
 
 /// Acquire a read connection with attached database(s)
@@ -214,8 +453,11 @@ pub async fn acquire_reader_with_attached(
    main_db: &SqliteDatabase,
    mut specs: Vec<AttachedSpec>,
 ) -> Result<AttachedReadConnection> {
+   // Validate schema names before touching the pool to prevent SQL injection
+   validate_schema_names(&specs)?;
+
    // Acquire read connection from main database
-   let mut conn = main_db.read_pool()?.acquire().await?;
+   let mut conn = main_db.acquire_reader().await?;
 
    // Sort specs by database path to prevent deadlocks when multiple callers
    // attach the same databases in different orders.
@@ -238,11 +480,6 @@ pub async fn acquire_reader_with_attached(
    let mut schema_names = Vec::new();
 
    for spec in specs {
-      // Validate schema name to prevent SQL injection
-      if !is_valid_schema_name(&spec.schema_name) {
-         return Err(Error::InvalidSchemaName(spec.schema_name.clone()));
-      }
-
       // Read connections can only attach as read-only
       if spec.mode == AttachedMode::ReadWrite {
          return Err(Error::CannotAttachReadWriteToReader);
@@ -250,13 +487,7 @@ pub async fn acquire_reader_with_attached(
 
       // Execute ATTACH DATABASE
       // Schema name is validated above to contain only safe identifier characters
-      let path = spec.database.path_str();
-      let escaped_path = path.replace("'", "''");
-      let attach_sql = format!(
-         "ATTACH DATABASE '{}' AS \"{}\"",
-         escaped_path, spec.schema_name
-      );
-      sqlx::query(&attach_sql).execute(&mut *conn).await?;
+      attach(&mut conn, &spec).await?;
 
       schema_names.push(spec.schema_name);
    }
@@ -293,11 +524,7 @@ pub async fn acquire_writer_with_attached(
    specs: Vec<AttachedSpec>,
 ) -> Result<AttachedWriteGuard> {
    // Validate schema names first
-   for spec in &specs {
-      if !is_valid_schema_name(&spec.schema_name) {
-         return Err(Error::InvalidSchemaName(spec.schema_name.clone()));
-      }
-   }
+   validate_schema_names(&specs)?;
 
    // CRITICAL: To prevent deadlocks, we must acquire locks in a consistent global order.
    // Example deadlock without global ordering:
@@ -350,13 +577,7 @@ pub async fn acquire_writer_with_attached(
    let mut schema_names = Vec::new();
 
    for spec in specs {
-      let path = spec.database.path_str();
-      let escaped_path = path.replace("'", "''");
-      let attach_sql = format!(
-         "ATTACH DATABASE '{}' AS \"{}\"",
-         escaped_path, spec.schema_name
-      );
-      sqlx::query(&attach_sql).execute(&mut *writer).await?;
+      attach(&mut writer, &spec).await?;
 
       schema_names.push(spec.schema_name);
    }
@@ -754,6 +975,8 @@ mod tests {
          "schema;DROP TABLE users", // SQL injection attempt
          "schema'--",               // SQL injection attempt
          "schema/*comment*/",       // Contains special chars
+         "main",                    // Built-in schema
+         "TEMP",                    // Built-in schema (case-insensitive)
       ];
 
       for invalid_name in invalid_names {
@@ -772,6 +995,71 @@ mod tests {
       }
    }
 
+   #[tokio::test]
+   async fn test_duplicate_schema_name_rejected() {
+      let temp_dir = TempDir::new().unwrap();
+      let main_db = create_test_db("main.db", &temp_dir).await;
+      let db1 = create_test_db("db1.db", &temp_dir).await;
+      let db2 = create_test_db("db2.db", &temp_dir).await;
+
+      // Schema names are case-insensitive in SQLite
+      let specs = vec![
+         AttachedSpec::read_only(db1, "shared"),
+         AttachedSpec::read_only(db2, "SHARED"),
+      ];
+
+      let result = acquire_reader_with_attached(&main_db, specs).await;
+      assert!(
+         matches!(result, Err(Error::DuplicateSchemaName(_))),
+         "Should reject duplicate schema names"
+      );
+   }
+
+   #[tokio::test]
+   async fn test_readonly_attach_rejects_writes_on_writer() {
+      let temp_dir = TempDir::new().unwrap();
+      let main_db = create_test_db("main.db", &temp_dir).await;
+      let other_db = create_test_db("other.db", &temp_dir).await;
+
+      let specs = vec![AttachedSpec::read_only(other_db.clone(), "other")];
+      let mut conn = acquire_writer_with_attached(&main_db, specs).await.unwrap();
+
+      // Reads work
+      let row = sqlx::query("SELECT value FROM other.other LIMIT 1")
+         .fetch_one(&mut *conn)
+         .await
+         .unwrap();
+      let value: String = row.get(0);
+      assert_eq!(value, "test_data");
+
+      // Writes to the read-only schema are rejected by SQLite
+      let result = sqlx::query("INSERT INTO other.other (value) VALUES ('nope')")
+         .execute(&mut *conn)
+         .await;
+      let err = result.expect_err("write to read-only attached database should fail");
+      assert!(
+         err.to_string().contains("readonly"),
+         "Expected read-only error, got: {err}"
+      );
+
+      // Writes to the main schema still work
+      sqlx::query("INSERT INTO main.main (value) VALUES ('ok')")
+         .execute(&mut *conn)
+         .await
+         .unwrap();
+
+      conn.detach_all().await.unwrap();
+   }
+
+   #[test]
+   fn test_uri_escape_path() {
+      assert_eq!(uri_escape_path("/data/app.db"), "/data/app.db");
+      assert_eq!(
+         uri_escape_path("/data/what?#100%.db"),
+         "/data/what%3f%23100%25.db"
+      );
+   }
+
    #[tokio::test]
    async fn test_duplicate_attached_database_rejected() {
       let temp_dir = TempDir::new().unwrap();
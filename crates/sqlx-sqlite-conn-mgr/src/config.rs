@@ -1,13 +1,15 @@
 //! Configuration for SQLite database connection pools
 
+use crate::recovery::CorruptionRecovery;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Configuration for SqliteDatabase connection pools
 ///
 /// # Examples
 ///
 /// ```
-/// use sqlx_sqlite_conn_mgr::SqliteDatabaseConfig;
+/// use sqlx_sqlite_conn_mgr::{CorruptionRecovery, SqliteDatabaseConfig, TempStore};
 ///
 /// // Use defaults
 /// let config = SqliteDatabaseConfig::default();
@@ -16,6 +18,16 @@ use serde::{Deserialize, Serialize};
 /// let config = SqliteDatabaseConfig {
 ///     max_read_connections: 3,
 ///     idle_timeout_secs: 60,
+///     foreign_keys: true,
+///     eager_wal_init: false,
+///     warm_read_connections: 0,
+///     read_statement_timeout_ms: Some(5_000),
+///     quick_check_on_connect: false,
+///     corruption_recovery: CorruptionRecovery::Fail,
+///     temp_store: TempStore::Memory,
+///     temp_directory: None,
+///     page_size: Some(16_384),
+///     secure_delete: false,
 /// };
 ///
 /// // Override just one field
@@ -25,6 +37,7 @@ use serde::{Deserialize, Serialize};
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SqliteDatabaseConfig {
    /// Maximum number of concurrent read connections
    ///
@@ -41,6 +54,137 @@ pub struct SqliteDatabaseConfig {
    ///
    /// Default: 30
    pub idle_timeout_secs: u64,
+
+   /// Whether foreign key constraints are enforced
+   ///
+   /// SQLite leaves foreign key enforcement off unless `PRAGMA foreign_keys = ON` is
+   /// issued on every connection. This is applied to each read and write connection
+   /// when it is opened.
+   ///
+   /// Default: true
+   pub foreign_keys: bool,
+
+   /// Whether to enable WAL mode when the database is connected
+   ///
+   /// By default WAL mode is enabled lazily on the first `acquire_writer()` call, so the
+   /// first write pays the journal mode switch and its brief exclusive lock. Set this to
+   /// `true` to do that work during `connect()` instead, e.g. at app startup before the
+   /// first user interaction. Has the same effect as calling
+   /// `SqliteDatabase::initialize()` right after connecting.
+   ///
+   /// Default: false
+   pub eager_wal_init: bool,
+
+   /// Number of read connections to open during `connect()`
+   ///
+   /// Read connections are normally opened lazily, so the first burst of concurrent
+   /// reads all pay connection-establishment latency at once. Warmed connections sit
+   /// idle in the pool and are still closed after `idle_timeout_secs` like any other.
+   /// Values larger than `max_read_connections` are capped to it.
+   ///
+   /// Default: 0
+   pub warm_read_connections: u32,
+
+   /// Maximum time (in milliseconds) SQLite may spend executing on a read connection
+   /// per acquisition from the read pool
+   ///
+   /// Enforced inside SQLite via a progress handler, so a pathological query is
+   /// interrupted (failing with `SQLITE_INTERRUPT`) even if the future driving it is
+   /// never dropped. The clock restarts each time a connection is acquired from the
+   /// read pool. `None` or `0` disables the limit. The write connection is never
+   /// subject to this timeout.
+   ///
+   /// Default: None
+   pub read_statement_timeout_ms: Option<u64>,
+
+   /// Whether to run `PRAGMA quick_check` when opening an existing database file
+   ///
+   /// A damaged database fails `connect()` with `Error::CorruptDatabase` instead of
+   /// surfacing as a random failure part way through a session, giving the app a
+   /// chance to restore from a backup. The check reads the whole database, so it adds
+   /// startup time proportional to the database size. Newly created and in-memory
+   /// databases are never checked.
+   ///
+   /// Default: false
+   pub quick_check_on_connect: bool,
+
+   /// What to do when the integrity check finds a damaged database
+   ///
+   /// With `CorruptionRecovery::Recover`, the damaged file is moved aside as a
+   /// timestamped backup and rebuilt from whatever rows can still be read;
+   /// `SqliteDatabase::recovery_report()` describes what was salvaged. Choosing
+   /// `Recover` runs the integrity check even if `quick_check_on_connect` is `false`.
+   ///
+   /// Default: `CorruptionRecovery::Fail`
+   pub corruption_recovery: CorruptionRecovery,
+
+   /// Where SQLite keeps temporary tables and indices (`PRAGMA temp_store`)
+   ///
+   /// Large sorts, `GROUP BY`, and some joins spill to temporary storage. `Memory`
+   /// avoids temp files entirely at the cost of RAM. Applied to every read and write
+   /// connection. Builds compiled with a fixed `SQLITE_TEMP_STORE` (e.g. Android)
+   /// ignore this setting.
+   ///
+   /// Default: `TempStore::Default`
+   pub temp_store: TempStore,
+
+   /// Directory SQLite uses for temporary files
+   ///
+   /// The platform default can be unwritable or short on space inside iOS/Android
+   /// sandboxes. This is a process-wide SQLite setting (`PRAGMA temp_store_directory`),
+   /// so it affects every database in the process and should be given the same value
+   /// for all of them. `None` leaves the current setting alone.
+   ///
+   /// Default: None
+   pub temp_directory: Option<PathBuf>,
+
+   /// Page size in bytes for newly created database files
+   ///
+   /// Larger pages suit blob-heavy workloads. Only applied when `connect()` creates
+   /// the database file; an existing database keeps its page size, since changing it
+   /// requires rewriting the whole file with `VACUUM`. Must be a power of two between
+   /// 512 and 65536; SQLite ignores other values. `None` uses SQLite's default
+   /// (4096).
+   ///
+   /// Default: None
+   pub page_size: Option<u32>,
+
+   /// Whether deleted content is overwritten with zeros (`PRAGMA secure_delete`)
+   ///
+   /// By default SQLite only unlinks deleted rows, leaving their content readable in
+   /// free pages until reused. Privacy-sensitive apps can turn this on so deletes
+   /// zero the data, at the cost of extra write I/O. Content freed before this was
+   /// enabled can be scrubbed with `SqliteDatabase::overwrite_free_pages()`. Applied
+   /// to the write connection.
+   ///
+   /// Default: false
+   pub secure_delete: bool,
+}
+
+/// Storage location for SQLite temporary tables and indices
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TempStore {
+   /// Use the compile-time default (normally files)
+   #[default]
+   Default,
+
+   /// Always use temporary files
+   File,
+
+   /// Keep temporary storage in memory
+   Memory,
+}
+
+impl TempStore {
+   /// Value for `PRAGMA temp_store`
+   pub(crate) fn pragma_value(self) -> &'static str {
+      match self {
+         TempStore::Default => "DEFAULT",
+         TempStore::File => "FILE",
+         TempStore::Memory => "MEMORY",
+      }
+   }
 }
 
 impl Default for SqliteDatabaseConfig {
@@ -48,6 +192,16 @@ impl Default for SqliteDatabaseConfig {
       Self {
          max_read_connections: 6,
          idle_timeout_secs: 30,
+         foreign_keys: true,
+         eager_wal_init: false,
+         warm_read_connections: 0,
+         read_statement_timeout_ms: None,
+         quick_check_on_connect: false,
+         corruption_recovery: CorruptionRecovery::Fail,
+         temp_store: TempStore::Default,
+         temp_directory: None,
+         page_size: None,
+         secure_delete: false,
       }
    }
 }
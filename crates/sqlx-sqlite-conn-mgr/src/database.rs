@@ -3,13 +3,18 @@
 use crate::Result;
 use crate::config::SqliteDatabaseConfig;
 use crate::error::Error;
+use crate::interrupt::{InterruptHandle, install_progress_handler};
+use crate::read_guard::ReadGuard;
+use crate::recovery::{CorruptionRecovery, RecoveryReport, recover_database};
 use crate::registry::{get_or_open_database, is_memory_database, uncache_database};
 use crate::write_guard::WriteGuard;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
-use sqlx::{ConnectOptions, Pool, Sqlite};
+use crate::write_queue::{WritePriority, WriteQueue};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Connection, Pool, Sqlite};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use tracing::{error, warn};
 
 /// Analysis limit for PRAGMA optimize on close.
@@ -17,6 +22,127 @@ use tracing::{error, warn};
 /// See: https://www.sqlite.org/lang_analyze.html#recommended_usage_pattern
 const OPTIMIZE_ANALYSIS_LIMIT: u32 = 400;
 
+/// Primary result codes SQLite uses for a damaged file (`SQLITE_CORRUPT`) and for a
+/// file that isn't a database at all (`SQLITE_NOTADB`)
+const SQLITE_CORRUPT: i32 = 11;
+const SQLITE_NOTADB: i32 = 26;
+
+/// Whether `err` is SQLite reporting that the database file is damaged
+fn is_corruption_error(err: &sqlx::Error) -> bool {
+   let sqlx::Error::Database(db_err) = err else {
+      return false;
+   };
+
+   // Extended result codes carry the primary code in their low byte
+   db_err
+      .code()
+      .and_then(|code| code.parse::<i32>().ok())
+      .is_some_and(|code| matches!(code & 0xff, SQLITE_CORRUPT | SQLITE_NOTADB))
+}
+
+/// Run `PRAGMA quick_check` on an existing database file
+///
+/// Uses a dedicated read-only connection so the check isn't subject to the read pool's
+/// statement timeout.
+async fn quick_check(path: &Path) -> Result<()> {
+   let to_corrupt = |err: sqlx::Error| {
+      if is_corruption_error(&err) {
+         Error::CorruptDatabase(err.to_string())
+      } else {
+         Error::Sqlx(err)
+      }
+   };
+
+   let mut conn = SqliteConnectOptions::new()
+      .filename(path)
+      .read_only(true)
+      .connect()
+      .await
+      .map_err(to_corrupt)?;
+
+   let problems: Vec<String> = sqlx::query_scalar("PRAGMA quick_check")
+      .fetch_all(&mut conn)
+      .await
+      .map_err(to_corrupt)?;
+
+   conn.close().await?;
+
+   if problems.len() == 1 && problems[0] == "ok" {
+      return Ok(());
+   }
+
+   Err(Error::CorruptDatabase(problems.join("; ")))
+}
+
+/// Largest zero-filled blob written per row by `overwrite_free_pages()`, kept well
+/// under SQLite's default maximum blob length
+const OVERWRITE_CHUNK_BYTES: i64 = 64 * 1024 * 1024;
+
+/// Fill `bytes` worth of free pages with zeros, then free them again
+///
+/// Runs in one transaction so a failure leaves the database unchanged.
+async fn scrub_free_pages(conn: &mut SqliteConnection, bytes: i64) -> Result<()> {
+   sqlx::query("BEGIN IMMEDIATE").execute(&mut *conn).await?;
+
+   let result = async {
+      sqlx::query("CREATE TABLE main.__conn_mgr_scrub (data BLOB)")
+         .execute(&mut *conn)
+         .await?;
+
+      let mut remaining = bytes;
+      while remaining > 0 {
+         let chunk = remaining.min(OVERWRITE_CHUNK_BYTES);
+         sqlx::query("INSERT INTO main.__conn_mgr_scrub (data) VALUES (zeroblob(?))")
+            .bind(chunk)
+            .execute(&mut *conn)
+            .await?;
+         remaining -= chunk;
+      }
+
+      sqlx::query("DROP TABLE main.__conn_mgr_scrub")
+         .execute(&mut *conn)
+         .await?;
+      sqlx::query("COMMIT").execute(&mut *conn).await?;
+      Ok(())
+   }
+   .await;
+
+   if result.is_err() {
+      let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+   }
+
+   result
+}
+
+/// Point SQLite's process-wide temporary file directory at `dir`
+///
+/// `PRAGMA temp_store_directory` isn't safe to change while other connections are
+/// using temp files, so it is only issued when the value actually changes.
+async fn set_temp_directory(dir: &Path) -> Result<()> {
+   let dir = dir.to_string_lossy();
+
+   // Default options open a private in-memory database
+   let mut conn = SqliteConnectOptions::new().connect().await?;
+
+   let current: String = sqlx::query_scalar("PRAGMA temp_store_directory")
+      .fetch_optional(&mut conn)
+      .await?
+      .unwrap_or_default();
+
+   if current != dir {
+      // Pragma arguments can't be bound as parameters
+      sqlx::query(&format!(
+         "PRAGMA temp_store_directory = '{}'",
+         dir.replace('\'', "''")
+      ))
+      .execute(&mut conn)
+      .await?;
+   }
+
+   conn.close().await?;
+   Ok(())
+}
+
 /// SQLite database with connection pooling for concurrent reads and optional exclusive writes.
 ///
 /// Once the database is opened it can be used for read-only operations by calling `read_pool()`.
@@ -56,6 +182,19 @@ pub struct SqliteDatabase {
    /// Single read-write connection pool (max_connections=1) for serialized writes
    write_conn: Pool<Sqlite>,
 
+   /// Fair queue that orders waiting writers by priority, then by request order
+   write_queue: Arc<WriteQueue>,
+
+   /// Interrupts statements running on checked-out read connections
+   read_interrupt: InterruptHandle,
+
+   /// Deadline for each read statement, restarted whenever a read connection is
+   /// handed out
+   read_statement_timeout: Option<Duration>,
+
+   /// Interrupts the statement running on the write connection
+   write_interrupt: InterruptHandle,
+
    /// Tracks if WAL mode has been initialized (set on first write)
    wal_initialized: AtomicBool,
 
@@ -64,9 +203,26 @@ pub struct SqliteDatabase {
 
    /// Path to database file (used for cleanup and registry lookups)
    path: PathBuf,
+
+   /// Set when `connect()` rebuilt the database from a corrupt file
+   recovery_report: Option<RecoveryReport>,
 }
 
 impl SqliteDatabase {
+   /// What was salvaged if `connect()` rebuilt this database from a corrupt file
+   ///
+   /// Only set when `corruption_recovery` is `CorruptionRecovery::Recover` and the
+   /// integrity check failed on connect. Check `RecoveryReport::is_complete()` to find
+   /// out whether any data was lost.
+   pub fn recovery_report(&self) -> Option<&RecoveryReport> {
+      self.recovery_report.as_ref()
+   }
+
+   /// Get the database file path
+   pub(crate) fn path(&self) -> &Path {
+      &self.path
+   }
+
    /// Get the database file path as a string
    ///
    /// Used internally (crate-private) for ATTACH DATABASE statements
@@ -80,7 +236,8 @@ impl SqliteDatabase {
    /// Multiple calls with the same path will return the same database instance.
    ///
    /// The database is created if it doesn't exist. WAL mode is enabled when
-   /// `acquire_writer()` is first called.
+   /// `acquire_writer()` is first called, or during connect when
+   /// `SqliteDatabaseConfig::eager_wal_init` is set.
    ///
    /// # Arguments
    ///
@@ -111,6 +268,16 @@ impl SqliteDatabase {
    /// let custom_config = SqliteDatabaseConfig {
    ///    max_read_connections: 10,
    ///    idle_timeout_secs: 60,
+   ///    foreign_keys: true,
+   ///    eager_wal_init: false,
+   ///    warm_read_connections: 0,
+   ///    read_statement_timeout_ms: None,
+   ///    quick_check_on_connect: false,
+   ///    corruption_recovery: Default::default(),
+   ///    temp_store: Default::default(),
+   ///    temp_directory: None,
+   ///    page_size: None,
+   ///    secure_delete: false,
    /// };
    /// let db = SqliteDatabase::connect("test.db", Some(custom_config)).await?;
    /// # Ok(())
@@ -152,29 +319,111 @@ impl SqliteDatabase {
                .read_only(false);
 
             // Create database file with a temporary connection
-            let conn = create_options.connect().await?;
+            let mut conn = create_options.connect().await?;
+
+            // The page size only sticks once the first page is written, so VACUUM the
+            // still-empty database to write its header now. This must happen before
+            // WAL mode is enabled, after which the page size is fixed.
+            if let Some(page_size) = config.page_size {
+               sqlx::query(&format!("PRAGMA page_size = {page_size}"))
+                  .execute(&mut conn)
+                  .await?;
+               sqlx::query("VACUUM").execute(&mut conn).await?;
+            }
+
             drop(conn); // Close immediately after creating the file
          }
 
+         if let Some(dir) = &config.temp_directory {
+            set_temp_directory(dir).await?;
+         }
+
+         let recover = config.corruption_recovery == CorruptionRecovery::Recover;
+         let mut recovery_report = None;
+
+         if db_exists && !is_memory_database(&path) && (config.quick_check_on_connect || recover) {
+            match quick_check(&path).await {
+               Err(Error::CorruptDatabase(details)) if recover => {
+                  warn!(
+                     "Database {} is corrupt, attempting recovery: {details}",
+                     path.display()
+                  );
+                  match recover_database(&path, details.clone()).await {
+                     Ok(report) => recovery_report = Some(report),
+                     Err(err) => {
+                        error!("Recovery of {} failed: {err}", path.display());
+                        return Err(Error::CorruptDatabase(details));
+                     }
+                  }
+               }
+               result => result?,
+            }
+         }
+
          // Create read pool with read-only connections
          let read_options = SqliteConnectOptions::new()
             .filename(&path)
             .read_only(true)
+            .foreign_keys(config.foreign_keys)
+            .pragma("temp_store", config.temp_store.pragma_value())
             .optimize_on_close(true, OPTIMIZE_ANALYSIS_LIMIT);
 
-         let read_pool = SqlitePoolOptions::new()
+         let read_pool_options = SqlitePoolOptions::new()
             .max_connections(config.max_read_connections)
             .min_connections(0)
-            .idle_timeout(Some(std::time::Duration::from_secs(
-               config.idle_timeout_secs,
-            )))
+            .idle_timeout(Some(Duration::from_secs(config.idle_timeout_secs)));
+
+         // Interrupts and the statement timeout share one progress handler. New
+         // connections start their clock when opened, reused connections restart it
+         // (and drop stale interrupts) each time they are handed out by the pool.
+         let read_interrupt = InterruptHandle::default();
+         let statement_timeout = config
+            .read_statement_timeout_ms
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+         let connect_interrupt = read_interrupt.clone();
+         let acquire_interrupt = read_interrupt.clone();
+         let read_pool = read_pool_options
+            .after_connect(move |conn, _meta| {
+               let interrupt = connect_interrupt.clone();
+               Box::pin(async move {
+                  install_progress_handler(conn, &[&interrupt], statement_timeout).await
+               })
+            })
+            .before_acquire(move |conn, _meta| {
+               let interrupt = acquire_interrupt.clone();
+               Box::pin(async move {
+                  install_progress_handler(conn, &[&interrupt], statement_timeout).await?;
+                  Ok(true)
+               })
+            })
             .connect_with(read_options)
             .await?;
 
+         // Pre-establish read connections. They must all be held at once, otherwise the
+         // pool would just hand back the same idle connection each time.
+         let warm_count = config
+            .warm_read_connections
+            .min(config.max_read_connections);
+         if warm_count > 0 {
+            let mut warmed = Vec::with_capacity(warm_count as usize);
+            for _ in 0..warm_count {
+               warmed.push(read_pool.acquire().await?);
+            }
+            // Returning the connections leaves them idle in the pool
+            drop(warmed);
+         }
+
          // Create write pool with a single read-write connection
          let write_options = SqliteConnectOptions::new()
             .filename(&path)
             .read_only(false)
+            .foreign_keys(config.foreign_keys)
+            .pragma("temp_store", config.temp_store.pragma_value())
+            .pragma(
+               "secure_delete",
+               if config.secure_delete { "ON" } else { "OFF" },
+            )
             .optimize_on_close(true, OPTIMIZE_ANALYSIS_LIMIT);
 
          // Defense-in-depth: when any writer is returned to the pool, issue
@@ -189,12 +438,27 @@ impl SqliteDatabase {
          // Anything else means ROLLBACK itself failed or the connection is
          // wedged; tell the pool not to recycle so a broken connection isn't
          // handed to the next caller.
+         //
+         // The writer never has a statement timeout, but it is interruptible through
+         // `WriteGuard::interrupt()`.
+         let write_interrupt = InterruptHandle::default();
+         let connect_interrupt = write_interrupt.clone();
+         let acquire_interrupt = write_interrupt.clone();
          let write_conn = SqlitePoolOptions::new()
             .max_connections(1)
             .min_connections(0)
-            .idle_timeout(Some(std::time::Duration::from_secs(
-               config.idle_timeout_secs,
-            )))
+            .idle_timeout(Some(Duration::from_secs(config.idle_timeout_secs)))
+            .after_connect(move |conn, _meta| {
+               let interrupt = connect_interrupt.clone();
+               Box::pin(async move { install_progress_handler(conn, &[&interrupt], None).await })
+            })
+            .before_acquire(move |conn, _meta| {
+               let interrupt = acquire_interrupt.clone();
+               Box::pin(async move {
+                  install_progress_handler(conn, &[&interrupt], None).await?;
+                  Ok(true)
+               })
+            })
             .after_release(|conn, _meta| {
                Box::pin(async move {
                   match sqlx::query("ROLLBACK").execute(&mut *conn).await {
@@ -214,17 +478,53 @@ impl SqliteDatabase {
             .connect_with(write_options)
             .await?;
 
-         Ok(Self {
+         let db = Self {
             read_pool,
             write_conn,
+            write_queue: Arc::new(WriteQueue::default()),
+            read_interrupt,
+            read_statement_timeout: statement_timeout,
+            write_interrupt,
             wal_initialized: AtomicBool::new(false),
             closed: AtomicBool::new(false),
             path: path.clone(),
-         })
+            recovery_report,
+         };
+
+         if config.eager_wal_init {
+            db.initialize().await?;
+         }
+
+         Ok(db)
       })
       .await
    }
 
+   /// Enable WAL mode and apply the write connection pragmas now
+   ///
+   /// This is normally done lazily by the first `acquire_writer()` call. Call this
+   /// during app startup to move that cost (a journal mode switch and a brief exclusive
+   /// lock) out of the first user-visible write. Idempotent: subsequent calls only
+   /// briefly acquire and release the writer.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::SqliteDatabase;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   /// db.initialize().await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn initialize(&self) -> Result<()> {
+      // WAL setup happens inside acquire_writer; the guard is dropped immediately,
+      // returning the connection to the pool
+      let _writer = self.acquire_writer().await?;
+      Ok(())
+   }
+
    /// Get a reference to the connection pool for executing read queries
    ///
    /// Use this for concurrent read operations. Multiple readers can access
@@ -252,10 +552,30 @@ impl SqliteDatabase {
       Ok(&self.read_pool)
    }
 
+   /// Acquire a read connection that can be interrupted without affecting other reads
+   ///
+   /// The returned `ReadGuard` has its own `InterruptHandle` in addition to the
+   /// pool-wide one used by `interrupt_all_reads()`, so a caller can cancel just its
+   /// own query, e.g. when it exceeds a caller-specific time limit.
+   pub async fn acquire_reader(&self) -> Result<ReadGuard> {
+      let mut conn = self.read_pool()?.acquire().await?;
+      let interrupt = InterruptHandle::default();
+
+      install_progress_handler(
+         &mut conn,
+         &[&self.read_interrupt, &interrupt],
+         self.read_statement_timeout,
+      )
+      .await?;
+
+      Ok(ReadGuard::new(conn, interrupt))
+   }
+
    /// Acquire exclusive write access to the database
    ///
    /// This method returns a `WriteGuard` that provides exclusive access to
    /// the single write connection. Only one writer can exist at a time.
+   /// Waiting writers are served in the order they called this method.
    ///
    /// On the first call, this method will enable WAL mode on the database.
    /// Subsequent calls reuse the same write connection.
@@ -278,12 +598,106 @@ impl SqliteDatabase {
    /// # }
    /// ```
    pub async fn acquire_writer(&self) -> Result<WriteGuard> {
+      self.acquire_writer_priority(WritePriority::Normal).await
+   }
+
+   /// Acquire exclusive write access to the database with the given priority
+   ///
+   /// Behaves like `acquire_writer()`, but `WritePriority::High` requests are served
+   /// ahead of every queued `WritePriority::Normal` request. Requests of equal
+   /// priority are served in FIFO order. The writer currently holding the connection
+   /// is never preempted.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::{SqliteDatabase, WritePriority};
+   /// use sqlx::query;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   ///
+   /// // User-initiated save jumps ahead of queued background writes
+   /// let mut writer = db.acquire_writer_priority(WritePriority::High).await?;
+   /// query("UPDATE documents SET body = ? WHERE id = ?")
+   ///     .bind("...")
+   ///     .bind(1)
+   ///     .execute(&mut *writer)
+   ///     .await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn acquire_writer_priority(&self, priority: WritePriority) -> Result<WriteGuard> {
+      self.acquire_writer_with(priority, None).await
+   }
+
+   /// Acquire exclusive write access, giving up after `timeout`
+   ///
+   /// Behaves like `acquire_writer()`, but returns `Error::WriterAcquireTimeout` if the
+   /// write connection could not be obtained within `timeout`. The limit applies per
+   /// call, so callers with different latency budgets (e.g. a UI save vs. a background
+   /// sync) can each choose their own. A request that times out leaves the write queue
+   /// without affecting the writers queued behind it.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::{Error, SqliteDatabase};
+   /// use std::time::Duration;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   ///
+   /// match db.acquire_writer_timeout(Duration::from_millis(250)).await {
+   ///     Ok(mut writer) => {
+   ///         sqlx::query("DELETE FROM drafts").execute(&mut *writer).await?;
+   ///     }
+   ///     Err(Error::WriterAcquireTimeout(_)) => {
+   ///         // Database is busy - retry later
+   ///     }
+   ///     Err(e) => return Err(e),
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn acquire_writer_timeout(&self, timeout: Duration) -> Result<WriteGuard> {
+      self
+         .acquire_writer_with(WritePriority::Normal, Some(timeout))
+         .await
+   }
+
+   /// Shared implementation for the `acquire_writer*` variants
+   async fn acquire_writer_with(
+      &self,
+      priority: WritePriority,
+      timeout: Option<Duration>,
+   ) -> Result<WriteGuard> {
       if self.closed.load(Ordering::SeqCst) {
          return Err(Error::DatabaseClosed);
       }
 
-      // Acquire connection from pool (max=1 ensures exclusive access)
-      let mut conn = self.write_conn.acquire().await?;
+      let wait = async {
+         // Wait for our turn in the queue before touching the pool
+         let ticket = self.write_queue.acquire(priority).await;
+
+         // The database may have been closed while we were queued
+         if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::DatabaseClosed);
+         }
+
+         // Acquire connection from pool (max=1 ensures exclusive access)
+         let conn = self.write_conn.acquire().await?;
+         Ok((ticket, conn))
+      };
+
+      // Only the wait is subject to the timeout, so WAL initialization below is never
+      // cancelled part-way through
+      let (ticket, mut conn) = match timeout {
+         Some(limit) => tokio::time::timeout(limit, wait)
+            .await
+            .map_err(|_| Error::WriterAcquireTimeout(limit))??,
+         None => wait.await?,
+      };
 
       // Initialize WAL mode on first use (atomic check-and-set)
       if self
@@ -302,7 +716,7 @@ impl SqliteDatabase {
       }
 
       // Return WriteGuard wrapping the pool connection
-      Ok(WriteGuard::new(conn))
+      Ok(WriteGuard::new(conn, ticket, self.write_interrupt.clone()))
    }
 
    /// Run database migrations using the provided migrator
@@ -346,6 +760,156 @@ impl SqliteDatabase {
       Ok(())
    }
 
+   /// Interrupt every statement currently running on a read connection
+   ///
+   /// Each interrupted statement fails with `SQLITE_INTERRUPT`. Connections idle in the
+   /// pool are unaffected, and the write connection is never interrupted by this; use
+   /// `WriteGuard::interrupt()` for that. Safe to call from any task while reads are
+   /// in progress.
+   pub fn interrupt_all_reads(&self) {
+      self.read_interrupt.interrupt();
+   }
+
+   /// Reclaim up to `pages` free pages from an `auto_vacuum = INCREMENTAL` database
+   ///
+   /// Runs `PRAGMA incremental_vacuum(pages)` on the write connection and returns the
+   /// number of pages actually released back to the file system. Holding the writer
+   /// only for a small step at a time lets apps reclaim space in the background
+   /// instead of blocking all writes for a full `VACUUM`. A `pages` of `0` reclaims
+   /// every free page at once.
+   ///
+   /// Does nothing (and returns `0`) unless the database uses
+   /// `auto_vacuum = INCREMENTAL`. Because WAL mode is enabled before any other
+   /// statement runs on the writer, switching a database over takes
+   /// `PRAGMA auto_vacuum = INCREMENTAL` followed by a one-time `VACUUM`.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::SqliteDatabase;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   ///
+   /// // Release free pages 100 at a time, letting other writers in between steps
+   /// while db.incremental_vacuum(100).await? > 0 {
+   ///     tokio::task::yield_now().await;
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn incremental_vacuum(&self, pages: u32) -> Result<u64> {
+      let mut writer = self.acquire_writer().await?;
+
+      let before: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+         .fetch_one(&mut *writer)
+         .await?;
+
+      // Pragma arguments can't be bound as parameters; `pages` is an integer
+      sqlx::query(&format!("PRAGMA incremental_vacuum({pages})"))
+         .execute(&mut *writer)
+         .await?;
+
+      let after: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+         .fetch_one(&mut *writer)
+         .await?;
+
+      Ok(before.saturating_sub(after).max(0) as u64)
+   }
+
+   /// Overwrite the content of every free page in the database file with zeros
+   ///
+   /// Rows deleted without `secure_delete` leave their content in free pages until
+   /// SQLite reuses them. This claims all free pages with zero-filled data and then
+   /// releases them again with `secure_delete` on, so no old content remains in the
+   /// database file. The WAL is then checkpointed and truncated so no copies linger
+   /// there either. Returns the number of free pages that were overwritten.
+   ///
+   /// Holds the writer for the duration, and writes roughly as much data as the free
+   /// pages occupy.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::SqliteDatabase;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   /// let scrubbed = db.overwrite_free_pages().await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn overwrite_free_pages(&self) -> Result<u64> {
+      let mut writer = self.acquire_writer().await?;
+
+      let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+         .fetch_one(&mut *writer)
+         .await?;
+
+      if free_pages > 0 {
+         let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&mut *writer)
+            .await?;
+         let secure_delete: i64 = sqlx::query_scalar("PRAGMA secure_delete")
+            .fetch_one(&mut *writer)
+            .await?;
+
+         sqlx::query("PRAGMA secure_delete = ON")
+            .execute(&mut *writer)
+            .await?;
+
+         let scrubbed = scrub_free_pages(&mut writer, free_pages * page_size).await;
+
+         // Restore the configured setting even if scrubbing failed
+         sqlx::query(&format!("PRAGMA secure_delete = {secure_delete}"))
+            .execute(&mut *writer)
+            .await?;
+         scrubbed?;
+      }
+
+      sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+         .execute(&mut *writer)
+         .await?;
+
+      Ok(free_pages as u64)
+   }
+
+   /// Write a compact, consistent copy of the database to `path`
+   ///
+   /// Uses `VACUUM INTO` on a read connection, so the snapshot reflects a single point
+   /// in time while other readers and the writer keep working. The copy is
+   /// defragmented and contains no free pages, which also makes it a convenient
+   /// source for backups and test fixtures. Fails if `path` already exists.
+   ///
+   /// The read statement timeout does not apply to snapshots.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_conn_mgr::SqliteDatabase;
+   ///
+   /// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+   /// let db = SqliteDatabase::connect("test.db", None).await?;
+   /// db.snapshot_to("backups/test-2024-01-01.db").await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn snapshot_to(&self, path: impl AsRef<Path>) -> Result<()> {
+      let target = path.as_ref().to_string_lossy().to_string();
+      let mut conn = self.read_pool()?.acquire().await?;
+
+      // A snapshot of a large database can legitimately outlast the statement
+      // timeout, so keep it interruptible but without a deadline
+      install_progress_handler(&mut conn, &[&self.read_interrupt], None).await?;
+
+      sqlx::query("VACUUM INTO ?")
+         .bind(target)
+         .execute(&mut *conn)
+         .await?;
+
+      Ok(())
+   }
+
    /// Close the database and clean up resources
    ///
    /// This closes all connections in the pool and removes the database from the cache.
@@ -398,6 +962,12 @@ impl SqliteDatabase {
    /// This closes all connections and then deletes the database file,
    /// WAL file, and SHM file from disk. Use with caution!
    ///
+   /// Refuses with `Error::DatabaseInUse` if any other `Arc<SqliteDatabase>` handle
+   /// (for example a clone held by an observer or another task) is still alive, since
+   /// those handles could otherwise keep writing into deleted files. The handle passed
+   /// in is dropped either way; the database stays open for the remaining handles.
+   /// Use `force_remove()` to delete regardless.
+   ///
    /// Note: Takes `Arc<Self>` to consume ownership, preventing use-after-close at compile time.
    /// The registry stores `Weak` references, so when this Arc is dropped, the database is freed.
    ///
@@ -414,6 +984,19 @@ impl SqliteDatabase {
    /// # }
    /// ```
    pub async fn remove(self: Arc<Self>) -> Result<()> {
+      let other_handles = Arc::strong_count(&self) - 1;
+      if other_handles > 0 {
+         return Err(Error::DatabaseInUse(other_handles));
+      }
+
+      self.force_remove().await
+   }
+
+   /// Close the database and delete all database files, even if other handles exist
+   ///
+   /// Like `remove()`, but skips the check for other live handles. Any remaining
+   /// handles see `Error::DatabaseClosed` on subsequent operations.
+   pub async fn force_remove(self: Arc<Self>) -> Result<()> {
       // Clone path before closing (since close consumes self)
       let path = self.path.clone();
 
@@ -18,23 +18,70 @@ pub enum Error {
    #[error("Migration error: {0}")]
    Migration(#[from] sqlx::migrate::MigrateError),
 
+   /// Timed out waiting for the write connection
+   #[error("Timed out after {0:?} waiting for the write connection")]
+   WriterAcquireTimeout(std::time::Duration),
+
+   /// The database file is damaged or is not a SQLite database
+   ///
+   /// Contains the problems reported by SQLite's integrity check, or the error
+   /// encountered while reading the file.
+   #[error("Database is corrupt: {0}")]
+   CorruptDatabase(String),
+
    /// Database has been closed and cannot be used
    #[error("Database has been closed")]
    DatabaseClosed,
 
+   /// Database files cannot be removed while other handles to the database exist
+   #[error(
+      "Database is still in use by {0} other handle(s); drop them first or use force_remove()"
+   )]
+   DatabaseInUse(usize),
+
+   /// The database manager already holds its maximum number of databases
+   #[error("Cannot open more than {0} databases")]
+   TooManyDatabases(usize),
+
    /// Cannot attach a database as read-write to a read-only connection
    #[error("Cannot attach database as read-write to a read-only connection")]
    CannotAttachReadWriteToReader,
 
    /// Invalid schema name provided for attached database
    #[error(
-      "Invalid schema name '{0}': must contain only alphanumeric characters and underscores, and cannot start with a digit"
+      "Invalid schema name '{0}': must contain only alphanumeric characters and underscores, cannot start with a digit, and cannot be 'main' or 'temp'"
    )]
    InvalidSchemaName(String),
 
+   /// The same schema name was used for more than one attached database
+   #[error("Schema name '{0}' is used by more than one attached database")]
+   DuplicateSchemaName(String),
+
    /// Attempted to attach the same database multiple times
    #[error(
       "Database '{0}' appears multiple times in attached database list (would cause deadlock)"
    )]
    DuplicateAttachedDatabase(String),
+
+   /// An attached database spec is incomplete or inconsistent
+   #[error("Invalid attached database spec: {0}")]
+   InvalidAttachedSpec(String),
+
+   /// The file given for an attached database does not exist
+   #[error("Attached database file not found: {}", .0.display())]
+   AttachedDatabaseNotFound(std::path::PathBuf),
+
+   /// SQLite's session extension failed to record or apply a changeset
+   #[cfg(feature = "session")]
+   #[error("Session error: {0}")]
+   Session(String),
+
+   /// SQLite rejected the ATTACH statement for an attached database
+   #[error("Failed to attach '{path}' as '{schema_name}': {source}")]
+   AttachFailed {
+      schema_name: String,
+      path: String,
+      #[source]
+      source: sqlx::Error,
+   },
 }
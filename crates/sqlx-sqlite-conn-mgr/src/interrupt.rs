@@ -0,0 +1,78 @@
+//! Interruption of running statements via SQLite progress handlers
+
+use sqlx::sqlite::SqliteConnection;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Approximate number of SQLite VM instructions executed between interrupt and
+/// statement timeout checks. Low enough to interrupt promptly, high enough to keep the
+/// handler's overhead negligible.
+const PROGRESS_CHECK_OPS: i32 = 1000;
+
+/// Handle for interrupting statements running on one or more connections
+///
+/// Cheap to clone and safe to use from any task or thread, including while the
+/// connection it belongs to is busy executing a query. An interrupted statement fails
+/// with `SQLITE_INTERRUPT`, exactly as with `sqlite3_interrupt()`; if it was part of
+/// an explicit transaction, SQLite rolls that transaction back.
+///
+/// Each interrupt is consumed by the first statement it stops. If a connection is
+/// checked out but idle when interrupted, its next statement is the one that fails.
+/// Connections sitting idle in the pool are never affected.
+#[derive(Debug, Clone, Default)]
+pub struct InterruptHandle {
+   /// Bumped on every interrupt; progress handlers compare against the value they
+   /// last saw
+   epoch: Arc<AtomicU64>,
+}
+
+impl InterruptHandle {
+   /// Interrupt the statement currently running on the connection(s) behind this
+   /// handle
+   pub fn interrupt(&self) {
+      self.epoch.fetch_add(1, Ordering::AcqRel);
+   }
+}
+
+/// Install (or replace) the progress handler that makes `conn` interruptible through
+/// any of `handles`, and that stops execution once `timeout` has elapsed from now
+///
+/// Interrupts issued before this call are ignored, so reinstalling the handler each
+/// time a connection is handed out keeps stale interrupts from reaching a new borrower.
+pub(crate) async fn install_progress_handler(
+   conn: &mut SqliteConnection,
+   handles: &[&InterruptHandle],
+   timeout: Option<Duration>,
+) -> std::result::Result<(), sqlx::Error> {
+   let mut epochs: Vec<(Arc<AtomicU64>, u64)> = handles
+      .iter()
+      .map(|handle| {
+         let epoch = Arc::clone(&handle.epoch);
+         let seen = epoch.load(Ordering::Acquire);
+         (epoch, seen)
+      })
+      .collect();
+   let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+   conn
+      .lock_handle()
+      .await?
+      .set_progress_handler(PROGRESS_CHECK_OPS, move || {
+         let mut interrupted = false;
+         for (epoch, seen) in &mut epochs {
+            let current = epoch.load(Ordering::Acquire);
+            if current != *seen {
+               *seen = current;
+               interrupted = true;
+            }
+         }
+         if interrupted {
+            return false;
+         }
+
+         deadline.is_none_or(|deadline| Instant::now() < deadline)
+      });
+
+   Ok(())
+}
@@ -7,15 +7,22 @@
 //!
 //! - **[`SqliteDatabase`]**: Main database type with separate read and write connection pools
 //! - **[`SqliteDatabaseConfig`]**: Configuration for connection pool settings
+//! - **[`SqliteDatabaseManager`]**: Owns many databases keyed by path with shared defaults
+//! - **[`RecoveryReport`]**: What was salvaged when a corrupt database was rebuilt
 //! - **[`WriteGuard`]**: RAII guard ensuring exclusive write access
+//! - **[`WritePriority`]**: Queue priority for `acquire_writer_priority()`
+//! - **[`InterruptHandle`]**: Cancels running statements from another task
+//! - **`Session`**: Records changesets on the writer (requires the `session` feature)
 //! - **[`Migrator`]**: Re-exported from sqlx for running database migrations
 //! - **[`Error`]**: Error type for database operations
 //!
 //! ## Architecture
 //!
 //! - **Connection pooling**: Separate read-only pool and write pool with a max of 1 connection
-//! - **Lazy WAL mode**: Write-Ahead Logging enabled automatically on first write
+//! - **Lazy WAL mode**: Write-Ahead Logging enabled automatically on first write, or eagerly
+//!   via `SqliteDatabase::initialize()`
 //! - **Exclusive writes**: Single-connection write pool enforces serialized write access
+//! - **Fair write queue**: Waiting writers are served in request order, with optional priority
 //! - **Concurrent reads**: Multiple readers can query simultaneously via the read pool
 //!
 //! ## Usage
@@ -64,18 +71,34 @@ mod attached;
 mod config;
 mod database;
 mod error;
+mod interrupt;
+mod manager;
+mod read_guard;
+mod recovery;
 mod registry;
+#[cfg(feature = "session")]
+mod session;
 mod write_guard;
+mod write_queue;
 
 // Re-export public types
 pub use attached::{
-   AttachedMode, AttachedReadConnection, AttachedSpec, AttachedWriteGuard,
+   AttachedMode, AttachedReadConnection, AttachedSpec, AttachedSpecBuilder, AttachedWriteGuard,
    acquire_reader_with_attached, acquire_writer_with_attached,
 };
-pub use config::SqliteDatabaseConfig;
+pub use config::{SqliteDatabaseConfig, TempStore};
 pub use database::SqliteDatabase;
 pub use error::Error;
+pub use interrupt::InterruptHandle;
+pub use manager::SqliteDatabaseManager;
+pub use read_guard::ReadGuard;
+pub use recovery::{CorruptionRecovery, RecoveryReport, TableRecovery};
+#[cfg(feature = "session")]
+pub use session::{
+   ChangesetOperation, Conflict, ConflictAction, ConflictKind, Session, apply_changeset,
+};
 pub use write_guard::WriteGuard;
+pub use write_queue::WritePriority;
 
 // Re-export sqlx migrate types for convenience
 pub use sqlx::migrate::Migrator;
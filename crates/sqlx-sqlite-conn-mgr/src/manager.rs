@@ -0,0 +1,238 @@
+//! Manager that owns many databases keyed by path
+
+use crate::Result;
+use crate::config::SqliteDatabaseConfig;
+use crate::database::SqliteDatabase;
+use crate::error::Error;
+use crate::registry::{canonicalize_path, is_memory_database};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+/// Owns a set of open `SqliteDatabase`s keyed by path
+///
+/// Keeps every database it opens alive until it is explicitly closed or removed,
+/// applies a shared default configuration, and can optionally cap how many databases
+/// are open at once. This is the same bookkeeping the Tauri plugin does for its loaded
+/// databases, usable from plain Rust applications.
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlx_sqlite_conn_mgr::{SqliteDatabaseConfig, SqliteDatabaseManager};
+///
+/// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+/// let manager = SqliteDatabaseManager::new(SqliteDatabaseConfig {
+///     max_read_connections: 3,
+///     ..Default::default()
+/// })
+/// .with_max_databases(10);
+///
+/// let main_db = manager.get_or_connect("main.db", None).await?;
+/// let cache_db = manager.get_or_connect("cache.db", None).await?;
+///
+/// // ... use databases ...
+///
+/// manager.close_all().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SqliteDatabaseManager {
+   /// Open databases, keyed by canonical path (in-memory paths are kept verbatim)
+   databases: RwLock<HashMap<PathBuf, Arc<SqliteDatabase>>>,
+
+   /// Configuration used when `get_or_connect` is called without one
+   default_config: SqliteDatabaseConfig,
+
+   /// Maximum number of databases that may be open at once (unlimited if `None`)
+   max_databases: Option<usize>,
+}
+
+impl SqliteDatabaseManager {
+   /// Create a manager that opens databases with `default_config` unless overridden
+   pub fn new(default_config: SqliteDatabaseConfig) -> Self {
+      Self {
+         databases: RwLock::new(HashMap::new()),
+         default_config,
+         max_databases: None,
+      }
+   }
+
+   /// Limit how many databases may be open at once
+   ///
+   /// Once the limit is reached, `get_or_connect` for a new path returns
+   /// `Error::TooManyDatabases` until another database is closed or removed.
+   pub fn with_max_databases(mut self, max: usize) -> Self {
+      self.max_databases = Some(max);
+      self
+   }
+
+   /// The configuration used for databases opened without an explicit one
+   pub fn default_config(&self) -> &SqliteDatabaseConfig {
+      &self.default_config
+   }
+
+   /// Get an open database, or connect to it if this manager hasn't opened it yet
+   ///
+   /// `config` overrides the manager's default configuration. It only applies when
+   /// the database is actually opened; it is ignored if the database is already open.
+   pub async fn get_or_connect(
+      &self,
+      path: impl AsRef<Path>,
+      config: Option<SqliteDatabaseConfig>,
+   ) -> Result<Arc<SqliteDatabase>> {
+      let path = path.as_ref();
+      let key = manager_key(path)?;
+
+      {
+         let databases = self.databases.read().await;
+         if let Some(db) = databases.get(&key) {
+            return Ok(Arc::clone(db));
+         }
+         if let Some(max) = self.max_databases
+            && databases.len() >= max
+         {
+            return Err(Error::TooManyDatabases(max));
+         }
+      }
+
+      // Connect without holding the lock so other databases stay available while
+      // this one opens
+      let config = config.unwrap_or_else(|| self.default_config.clone());
+      let db = SqliteDatabase::connect(path, Some(config)).await?;
+
+      let mut databases = self.databases.write().await;
+      let full = self.max_databases.filter(|&max| databases.len() >= max);
+
+      // Re-check under the write lock in case another caller won the race, and
+      // keep the database it stored
+      let (result, unused) = match databases.entry(key) {
+         Entry::Occupied(entry) => (Ok(Arc::clone(entry.get())), Some(db)),
+         Entry::Vacant(entry) => match full {
+            Some(max) => (Err(Error::TooManyDatabases(max)), Some(db)),
+            None => {
+               entry.insert(Arc::clone(&db));
+               (Ok(db), None)
+            }
+         },
+      };
+      drop(databases);
+
+      // File databases are shared through the global registry, so a losing
+      // caller usually got the same instance; only close one nobody else holds
+      if let Some(db) = unused
+         && Arc::strong_count(&db) == 1
+         && let Err(e) = db.close().await
+      {
+         warn!("Error closing unused database: {}", e);
+      }
+
+      result
+   }
+
+   /// Get a database this manager has already opened
+   pub async fn get(&self, path: impl AsRef<Path>) -> Option<Arc<SqliteDatabase>> {
+      let key = manager_key(path.as_ref()).ok()?;
+      self.databases.read().await.get(&key).cloned()
+   }
+
+   /// Number of databases currently open through this manager
+   pub async fn len(&self) -> usize {
+      self.databases.read().await.len()
+   }
+
+   /// Whether no databases are currently open through this manager
+   pub async fn is_empty(&self) -> bool {
+      self.databases.read().await.is_empty()
+   }
+
+   /// Paths of all databases currently open through this manager
+   pub async fn paths(&self) -> Vec<PathBuf> {
+      self.databases.read().await.keys().cloned().collect()
+   }
+
+   /// Close a database and stop managing it
+   ///
+   /// Returns `true` if the database was open, `false` if this manager didn't have it.
+   pub async fn close(&self, path: impl AsRef<Path>) -> Result<bool> {
+      let key = manager_key(path.as_ref())?;
+      let db = self.databases.write().await.remove(&key);
+
+      match db {
+         Some(db) => {
+            db.close().await?;
+            Ok(true)
+         }
+         None => Ok(false),
+      }
+   }
+
+   /// Close a database, delete its files, and stop managing it
+   ///
+   /// Returns `true` if the database was open and removed, `false` if this manager
+   /// didn't have it. Returns `Error::DatabaseInUse` (and keeps managing the database)
+   /// if handles obtained from `get_or_connect` are still alive elsewhere.
+   pub async fn remove(&self, path: impl AsRef<Path>) -> Result<bool> {
+      let key = manager_key(path.as_ref())?;
+      let mut databases = self.databases.write().await;
+
+      let Some(db) = databases.get(&key) else {
+         return Ok(false);
+      };
+
+      // Check before taking it out of the map so a refused removal leaves the
+      // manager's state unchanged
+      let other_handles = Arc::strong_count(db) - 1;
+      if other_handles > 0 {
+         return Err(Error::DatabaseInUse(other_handles));
+      }
+
+      let db = databases.remove(&key).expect("entry checked above");
+      drop(databases);
+
+      db.remove().await?;
+      Ok(true)
+   }
+
+   /// Close every managed database
+   ///
+   /// Attempts to close all databases even if some fail, returning the last error.
+   pub async fn close_all(&self) -> Result<()> {
+      let databases: Vec<Arc<SqliteDatabase>> = self
+         .databases
+         .write()
+         .await
+         .drain()
+         .map(|(_, db)| db)
+         .collect();
+
+      let mut last_error = None;
+      for db in databases {
+         if let Err(e) = db.close().await {
+            warn!("Error closing database: {}", e);
+            last_error = Some(e);
+         }
+      }
+
+      match last_error {
+         Some(e) => Err(e),
+         None => Ok(()),
+      }
+   }
+}
+
+/// Key used to identify a database in the manager
+///
+/// File paths are canonicalized so different spellings of the same file share one
+/// entry, matching the global registry's lookups. In-memory paths are used verbatim.
+fn manager_key(path: &Path) -> Result<PathBuf> {
+   if is_memory_database(path) {
+      return Ok(path.to_path_buf());
+   }
+
+   Ok(canonicalize_path(path)?)
+}
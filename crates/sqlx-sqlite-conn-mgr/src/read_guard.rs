@@ -0,0 +1,84 @@
+//! ReadGuard for a read connection that can be interrupted on its own
+
+use sqlx::Sqlite;
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::SqliteConnection;
+use std::ops::{Deref, DerefMut};
+
+use crate::interrupt::InterruptHandle;
+
+/// RAII guard for a connection checked out of the read pool
+///
+/// Unlike `SqliteDatabase::interrupt_all_reads()`, which stops every running read,
+/// `interrupt()` only stops the statement running on this connection. The connection
+/// still honors `interrupt_all_reads()` and the read statement timeout.
+///
+/// The guard derefs to `SqliteConnection` and returns the connection to the pool on
+/// drop.
+///
+/// # Example
+///
+/// ```no_run
+/// use sqlx_sqlite_conn_mgr::SqliteDatabase;
+/// use sqlx::query;
+///
+/// # async fn example() -> Result<(), sqlx_sqlite_conn_mgr::Error> {
+/// let db = SqliteDatabase::connect("test.db", None).await?;
+/// let mut reader = db.acquire_reader().await?;
+/// let cancel = reader.interrupt_handle();
+/// // Call cancel.interrupt() from another task to stop the query below
+/// let rows = query("SELECT * FROM users").fetch_all(&mut *reader).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "if unused, the read connection is immediately returned to the pool"]
+#[derive(Debug)]
+pub struct ReadGuard {
+   conn: PoolConnection<Sqlite>,
+
+   /// Interrupts the statement running on `conn` only
+   interrupt: InterruptHandle,
+}
+
+impl ReadGuard {
+   pub(crate) fn new(conn: PoolConnection<Sqlite>, interrupt: InterruptHandle) -> Self {
+      Self { conn, interrupt }
+   }
+
+   /// Interrupt the statement currently running on this connection
+   ///
+   /// The statement fails with `SQLITE_INTERRUPT`. Since a running query holds `&mut`
+   /// access to the guard, cancelling from another task requires a handle obtained
+   /// beforehand with `interrupt_handle()`.
+   pub fn interrupt(&self) {
+      self.interrupt.interrupt();
+   }
+
+   /// Get a handle that can interrupt this connection's statements from another task
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      self.interrupt.clone()
+   }
+
+   /// Close the connection when the guard is dropped instead of returning it
+   /// to the pool
+   ///
+   /// Use this after changing connection state (such as a PRAGMA) that could
+   /// not be reset, so later readers don't inherit it.
+   pub fn close_on_drop(&mut self) {
+      self.conn.close_on_drop();
+   }
+}
+
+impl Deref for ReadGuard {
+   type Target = SqliteConnection;
+
+   fn deref(&self) -> &Self::Target {
+      &self.conn
+   }
+}
+
+impl DerefMut for ReadGuard {
+   fn deref_mut(&mut self) -> &mut Self::Target {
+      &mut self.conn
+   }
+}
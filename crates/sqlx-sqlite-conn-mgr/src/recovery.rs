@@ -0,0 +1,406 @@
+//! Salvage of readable data from a corrupt database file
+
+use crate::Result;
+use crate::attached::uri_escape_path;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqliteSynchronous};
+use sqlx::{ConnectOptions, Connection};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// What `connect()` does when the integrity check finds a damaged database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CorruptionRecovery {
+   /// Fail `connect()` with `Error::CorruptDatabase`, leaving the file untouched
+   #[default]
+   Fail,
+
+   /// Move the damaged file aside as a backup and rebuild the database from whatever
+   /// rows can still be read. See `RecoveryReport` for what was salvaged.
+   Recover,
+}
+
+/// Outcome of salvaging a corrupt database during `connect()`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+   /// Where the damaged database file was moved (its `-wal`/`-shm` files, if any, sit
+   /// next to it with the usual suffixes)
+   pub backup_path: PathBuf,
+
+   /// Problems reported by the integrity check that triggered recovery
+   pub corruption: String,
+
+   /// Per-table salvage results, in schema order
+   pub tables: Vec<TableRecovery>,
+
+   /// Schema objects (tables, indexes, triggers, views) that could not be recreated
+   pub schema_errors: Vec<String>,
+}
+
+impl RecoveryReport {
+   /// Whether every table was copied in full and every schema object was recreated
+   pub fn is_complete(&self) -> bool {
+      self.schema_errors.is_empty() && self.tables.iter().all(|table| table.complete)
+   }
+}
+
+/// Salvage result for a single table
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRecovery {
+   /// Table name
+   pub name: String,
+
+   /// Number of rows copied into the rebuilt database
+   pub rows_recovered: u64,
+
+   /// Whether the whole table was read without errors. When `false`, rows stored in
+   /// the damaged part of the file were lost.
+   pub complete: bool,
+
+   /// The last error hit while reading the table, if any
+   pub error: Option<String>,
+}
+
+/// A schema object read from the damaged database's `sqlite_master`
+struct SchemaObject {
+   kind: String,
+   name: String,
+   sql: String,
+}
+
+/// Rebuild the database at `path` from the readable parts of its current (corrupt) file
+///
+/// The rebuilt database is written to a scratch file first, so if salvage fails
+/// outright the original file is left exactly where it was. On success the original
+/// is renamed to a timestamped backup and the rebuilt file takes its place.
+pub(crate) async fn recover_database(path: &Path, corruption: String) -> Result<RecoveryReport> {
+   let scratch_path = sibling_path(path, ".recovering");
+   remove_if_exists(&scratch_path)?;
+
+   let salvage = salvage_into(path, &scratch_path).await;
+
+   let (tables, schema_errors) = match salvage {
+      Ok(result) => result,
+      Err(err) => {
+         let _ = remove_if_exists(&scratch_path);
+         return Err(err);
+      }
+   };
+
+   // Salvage ran with syncing disabled; make the rebuilt file durable before it
+   // replaces the original
+   std::fs::File::open(&scratch_path)?.sync_all()?;
+
+   let millis = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_millis())
+      .unwrap_or_default();
+   let backup_path = sibling_path(path, &format!(".corrupt-{millis}"));
+
+   // Move the damaged file and its journal files aside together so a stale WAL can
+   // never be replayed into the rebuilt database
+   std::fs::rename(path, &backup_path)?;
+   for suffix in ["-wal", "-shm"] {
+      let journal = sibling_path(path, suffix);
+      if journal.exists() {
+         std::fs::rename(&journal, sibling_path(&backup_path, suffix))?;
+      }
+   }
+   std::fs::rename(&scratch_path, path)?;
+
+   let report = RecoveryReport {
+      backup_path,
+      corruption,
+      tables,
+      schema_errors,
+   };
+
+   if !report.is_complete() {
+      warn!(
+         "Recovered corrupt database {} with data loss; original kept at {}",
+         path.display(),
+         report.backup_path.display()
+      );
+   }
+
+   Ok(report)
+}
+
+/// Copy schema and readable rows from `damaged_path` into a new database at `target_path`
+async fn salvage_into(
+   damaged_path: &Path,
+   target_path: &Path,
+) -> Result<(Vec<TableRecovery>, Vec<String>)> {
+   // Constraints and triggers are deliberately not enforced while copying: the goal
+   // is to keep every row that can be read, not to re-validate them
+   let mut conn = SqliteConnectOptions::new()
+      .filename(target_path)
+      .create_if_missing(true)
+      .foreign_keys(false)
+      // The scratch file is thrown away if salvage fails, so durability is pointless.
+      // Statements also can't share a transaction: a corruption error while reading
+      // the damaged file poisons the whole transaction.
+      .journal_mode(SqliteJournalMode::Off)
+      .synchronous(SqliteSynchronous::Off)
+      .connect()
+      .await?;
+
+   let damaged_uri = format!(
+      "file:{}?mode=ro",
+      uri_escape_path(&damaged_path.to_string_lossy())
+   );
+   sqlx::query(&format!(
+      "ATTACH DATABASE '{}' AS damaged",
+      damaged_uri.replace('\'', "''")
+   ))
+   .execute(&mut conn)
+   .await?;
+
+   // If the schema itself can't be read there is nothing to salvage
+   let objects: Vec<SchemaObject> = sqlx::query_as::<_, (String, String, String)>(
+      "SELECT type, name, sql FROM damaged.sqlite_master
+       WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\'
+       ORDER BY rowid",
+   )
+   .fetch_all(&mut conn)
+   .await?
+   .into_iter()
+   .map(|(kind, name, sql)| SchemaObject { kind, name, sql })
+   .collect();
+
+   let mut tables = Vec::new();
+   let mut schema_errors = Vec::new();
+
+   // Tables and their data first, then indexes, triggers, and views, so triggers
+   // don't fire during the copy and indexes are built once
+   for object in objects.iter().filter(|object| object.kind == "table") {
+      // Shadow tables were already created along with their virtual table
+      let exists: Option<i64> =
+         sqlx::query_scalar("SELECT 1 FROM main.sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(&object.name)
+            .fetch_optional(&mut conn)
+            .await?;
+      if exists.is_some() {
+         continue;
+      }
+
+      if let Err(err) = sqlx::query(&object.sql).execute(&mut conn).await {
+         schema_errors.push(format!("{} {}: {}", object.kind, object.name, err));
+         continue;
+      }
+
+      // Virtual tables manage their own storage through shadow tables, so they are
+      // recreated empty rather than copied
+      if object
+         .sql
+         .to_ascii_uppercase()
+         .starts_with("CREATE VIRTUAL")
+      {
+         continue;
+      }
+
+      tables.push(copy_table(&mut conn, object).await);
+   }
+
+   for object in objects.iter().filter(|object| object.kind != "table") {
+      if let Err(err) = sqlx::query(&object.sql).execute(&mut conn).await {
+         schema_errors.push(format!("{} {}: {}", object.kind, object.name, err));
+      }
+   }
+
+   // Preserve AUTOINCREMENT counters where they can be read (best effort). The copy
+   // above already seeded them from the salvaged rows, so replace rather than append.
+   if sqlx::query("DELETE FROM main.sqlite_sequence")
+      .execute(&mut conn)
+      .await
+      .is_ok()
+   {
+      let _ = sqlx::query("INSERT INTO main.sqlite_sequence SELECT * FROM damaged.sqlite_sequence")
+         .execute(&mut conn)
+         .await;
+   }
+
+   sqlx::query("DETACH DATABASE damaged")
+      .execute(&mut conn)
+      .await?;
+   conn.close().await?;
+
+   Ok((tables, schema_errors))
+}
+
+/// Copy as many rows of one table as can be read from the damaged database
+async fn copy_table(conn: &mut SqliteConnection, table: &SchemaObject) -> TableRecovery {
+   let name = quote_identifier(&table.name);
+
+   // Fast path: an undamaged table copies in a single statement
+   let bulk = sqlx::query(&format!(
+      "INSERT INTO main.{name} SELECT * FROM damaged.{name}"
+   ))
+   .execute(&mut *conn)
+   .await;
+
+   let bulk_error = match bulk {
+      Ok(result) => {
+         return TableRecovery {
+            name: table.name.clone(),
+            rows_recovered: result.rows_affected(),
+            complete: true,
+            error: None,
+         };
+      }
+      Err(err) => err.to_string(),
+   };
+
+   // WITHOUT ROWID tables can't be walked row by row, so a failed bulk copy loses them
+   if table.sql.to_ascii_uppercase().contains("WITHOUT ROWID") {
+      return TableRecovery {
+         name: table.name.clone(),
+         rows_recovered: 0,
+         complete: false,
+         error: Some(bulk_error),
+      };
+   }
+
+   copy_rows_individually(conn, table, &name).await
+}
+
+/// Walk a rowid table one row at a time, from both ends, skipping unreadable rows
+///
+/// A damaged b-tree page stops the walk in that direction, so walking down from the
+/// highest rowid as well salvages the rows that sit beyond the damage.
+async fn copy_rows_individually(
+   conn: &mut SqliteConnection,
+   table: &SchemaObject,
+   name: &str,
+) -> TableRecovery {
+   let next_sql = format!(
+      "SELECT rowid FROM damaged.{name} WHERE ?1 IS NULL OR rowid > ?1 ORDER BY rowid LIMIT 1"
+   );
+   let prev_sql = format!(
+      "SELECT rowid FROM damaged.{name} WHERE ?1 IS NULL OR rowid < ?1 ORDER BY rowid DESC LIMIT 1"
+   );
+   let copy_sql = format!("INSERT INTO main.{name} SELECT * FROM damaged.{name} WHERE rowid = ?");
+
+   let mut rows_recovered = 0;
+   let mut last_error = None;
+   let mut lowest_unread: Option<i64> = None;
+   let mut walked_to_end = false;
+
+   // Walk up from the lowest rowid
+   loop {
+      match sqlx::query_scalar::<_, i64>(&next_sql)
+         .bind(lowest_unread)
+         .fetch_optional(&mut *conn)
+         .await
+      {
+         Ok(Some(rowid)) => {
+            match sqlx::query(&copy_sql).bind(rowid).execute(&mut *conn).await {
+               Ok(_) => rows_recovered += 1,
+               Err(err) => last_error = Some(err.to_string()),
+            }
+            lowest_unread = Some(rowid);
+         }
+         Ok(None) => {
+            walked_to_end = true;
+            break;
+         }
+         Err(err) => {
+            last_error = Some(err.to_string());
+            break;
+         }
+      }
+   }
+
+   // Walk down from the highest rowid until reaching rows already copied
+   if !walked_to_end {
+      let mut highest_unread: Option<i64> = None;
+
+      loop {
+         match sqlx::query_scalar::<_, i64>(&prev_sql)
+            .bind(highest_unread)
+            .fetch_optional(&mut *conn)
+            .await
+         {
+            Ok(Some(rowid)) if lowest_unread.is_none_or(|low| rowid > low) => {
+               match sqlx::query(&copy_sql).bind(rowid).execute(&mut *conn).await {
+                  Ok(_) => rows_recovered += 1,
+                  Err(err) => last_error = Some(err.to_string()),
+               }
+               highest_unread = Some(rowid);
+            }
+            Ok(_) => break,
+            Err(err) => {
+               last_error = Some(err.to_string());
+               break;
+            }
+         }
+      }
+   }
+
+   TableRecovery {
+      name: table.name.clone(),
+      rows_recovered,
+      complete: last_error.is_none(),
+      error: last_error,
+   }
+}
+
+/// Quote an identifier for use in SQL
+fn quote_identifier(name: &str) -> String {
+   format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `path` with `suffix` appended to its file name
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+   let mut name = path.as_os_str().to_owned();
+   name.push(suffix);
+   PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+   match std::fs::remove_file(path) {
+      Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+      _ => Ok(()),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_sibling_path_appends_suffix() {
+      assert_eq!(
+         sibling_path(Path::new("/data/app.db"), "-wal"),
+         PathBuf::from("/data/app.db-wal")
+      );
+   }
+
+   #[test]
+   fn test_quote_identifier_escapes_quotes() {
+      assert_eq!(quote_identifier("weird\"name"), "\"weird\"\"name\"");
+   }
+
+   #[test]
+   fn test_report_completeness() {
+      let mut report = RecoveryReport {
+         backup_path: PathBuf::from("app.db.corrupt-1"),
+         corruption: "damaged".into(),
+         tables: vec![TableRecovery {
+            name: "items".into(),
+            rows_recovered: 10,
+            complete: true,
+            error: None,
+         }],
+         schema_errors: Vec::new(),
+      };
+      assert!(report.is_complete());
+
+      report.tables[0].complete = false;
+      assert!(!report.is_complete());
+   }
+}
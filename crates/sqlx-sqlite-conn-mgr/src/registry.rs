@@ -97,7 +97,7 @@ where
 ///   least until the file is created and can be canonicalized properly.
 /// - Symlinks in filename: If the filename itself will be a symlink (rare for SQLite),
 ///   different symlink names won't be resolved until the file exists.
-fn canonicalize_path(path: &Path) -> std::io::Result<PathBuf> {
+pub(crate) fn canonicalize_path(path: &Path) -> std::io::Result<PathBuf> {
    match path.canonicalize() {
       Ok(p) => Ok(p),
       Err(_) => {
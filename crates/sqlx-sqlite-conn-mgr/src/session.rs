@@ -0,0 +1,362 @@
+//! Changesets recorded with SQLite's session extension
+//!
+//! A [`Session`] records the changes made through a writer to a set of tables,
+//! which can then be taken as a changeset (or the more compact patchset) and
+//! applied to another copy of the database with [`apply_changeset`]. This is
+//! the building block for syncing databases between devices.
+
+use libsqlite3_sys as ffi;
+use sqlx::sqlite::SqliteConnection;
+use std::any::Any;
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::{self, NonNull};
+
+use crate::Result;
+use crate::error::Error;
+
+/// Records the changes made through a connection for later export as a changeset
+///
+/// Created with [`WriteGuard::start_session()`](crate::WriteGuard::start_session).
+/// The session derefs to the writer's `SqliteConnection`, so writes are made
+/// through it as usual. Only changes to attached tables are recorded, and only
+/// tables with a declared primary key can be recorded.
+///
+/// A changeset holds, for each changed row, its net change since the session
+/// started, including the original values of updated and deleted rows. A
+/// patchset leaves those out, so it's smaller but can't detect as many
+/// conflicts when applied.
+///
+/// Recording stops when the session is dropped.
+pub struct Session<'c> {
+   conn: &'c mut SqliteConnection,
+   session: NonNull<ffi::sqlite3_session>,
+}
+
+// The session is only used while holding exclusive access to its connection
+unsafe impl Send for Session<'_> {}
+
+impl<'c> Session<'c> {
+   /// Start a session recording changes to the main database of `conn`
+   pub(crate) async fn start(conn: &'c mut SqliteConnection) -> Result<Session<'c>> {
+      let mut handle = conn.lock_handle().await?;
+      let db = handle.as_raw_handle().as_ptr();
+
+      let mut session = ptr::null_mut();
+      // SAFETY: `db` is a live connection we hold the lock on
+      let rc = unsafe { ffi::sqlite3session_create(db, c"main".as_ptr(), &mut session) };
+      drop(handle);
+      let session = match NonNull::new(session) {
+         Some(session) if rc == ffi::SQLITE_OK => session,
+         _ => return Err(session_error("start session", rc)),
+      };
+
+      Ok(Session { conn, session })
+   }
+
+   /// Record changes to `table`
+   ///
+   /// Tables without a declared primary key are silently ignored by SQLite.
+   pub async fn attach_table(&mut self, table: &str) -> Result<()> {
+      let name = CString::new(table)
+         .map_err(|_| Error::Session(format!("invalid table name '{}'", table)))?;
+      self.attach(name.as_ptr()).await
+   }
+
+   /// Record changes to every table, including ones created later
+   pub async fn attach_all(&mut self) -> Result<()> {
+      self.attach(ptr::null()).await
+   }
+
+   async fn attach(&mut self, table: *const c_char) -> Result<()> {
+      let _handle = self.conn.lock_handle().await?;
+      // SAFETY: the session is live and its connection locked; a null table
+      // attaches all tables
+      let rc = unsafe { ffi::sqlite3session_attach(self.session.as_ptr(), table) };
+      if rc != ffi::SQLITE_OK {
+         return Err(session_error("attach table", rc));
+      }
+      Ok(())
+   }
+
+   /// Whether no changes to attached tables have been recorded
+   pub async fn is_empty(&mut self) -> Result<bool> {
+      let _handle = self.conn.lock_handle().await?;
+      // SAFETY: the session is live and its connection locked
+      Ok(unsafe { ffi::sqlite3session_isempty(self.session.as_ptr()) } != 0)
+   }
+
+   /// The changes recorded so far as a changeset
+   ///
+   /// Recording continues, and a later changeset includes these changes again.
+   pub async fn changeset(&mut self) -> Result<Vec<u8>> {
+      self
+         .take(ffi::sqlite3session_changeset, "create changeset")
+         .await
+   }
+
+   /// The changes recorded so far as a patchset
+   ///
+   /// Like [`changeset()`](Self::changeset), but without the original values
+   /// of changed rows.
+   pub async fn patchset(&mut self) -> Result<Vec<u8>> {
+      self
+         .take(ffi::sqlite3session_patchset, "create patchset")
+         .await
+   }
+
+   async fn take(
+      &mut self,
+      generate: unsafe extern "C" fn(
+         *mut ffi::sqlite3_session,
+         *mut c_int,
+         *mut *mut c_void,
+      ) -> c_int,
+      action: &str,
+   ) -> Result<Vec<u8>> {
+      let _handle = self.conn.lock_handle().await?;
+      let mut len: c_int = 0;
+      let mut buf: *mut c_void = ptr::null_mut();
+      // SAFETY: the session is live and its connection locked. SQLite
+      // allocates the buffer, which is copied and then freed.
+      unsafe {
+         let rc = generate(self.session.as_ptr(), &mut len, &mut buf);
+         if rc != ffi::SQLITE_OK {
+            ffi::sqlite3_free(buf);
+            return Err(session_error(action, rc));
+         }
+         let bytes = if buf.is_null() || len <= 0 {
+            Vec::new()
+         } else {
+            std::slice::from_raw_parts(buf as *const u8, len as usize).to_vec()
+         };
+         ffi::sqlite3_free(buf);
+         Ok(bytes)
+      }
+   }
+}
+
+impl Deref for Session<'_> {
+   type Target = SqliteConnection;
+
+   fn deref(&self) -> &Self::Target {
+      self.conn
+   }
+}
+
+impl DerefMut for Session<'_> {
+   fn deref_mut(&mut self) -> &mut Self::Target {
+      self.conn
+   }
+}
+
+impl Drop for Session<'_> {
+   fn drop(&mut self) {
+      // SAFETY: the session was created by `start` and is deleted only here,
+      // while the connection it belongs to is still borrowed
+      unsafe { ffi::sqlite3session_delete(self.session.as_ptr()) };
+   }
+}
+
+/// Why a change in a changeset couldn't be applied as-is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+   /// The row to update or delete exists but its values differ from the
+   /// original values in the changeset
+   Data,
+   /// The row to update or delete doesn't exist
+   NotFound,
+   /// A row with the primary key being inserted already exists
+   Conflict,
+   /// Applying the change would violate a constraint other than the primary key
+   Constraint,
+   /// Applying the changeset left foreign key violations
+   ///
+   /// Reported once for the whole changeset; [`ConflictAction::Omit`] commits
+   /// it with the violations.
+   ForeignKey,
+}
+
+/// What a change in a changeset did to its row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangesetOperation {
+   Insert,
+   Update,
+   Delete,
+}
+
+/// A change that conflicts with the database it's being applied to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+   pub kind: ConflictKind,
+   /// Table the change is to, or `None` for [`ConflictKind::ForeignKey`]
+   pub table: Option<String>,
+   /// `None` for [`ConflictKind::ForeignKey`]
+   pub operation: Option<ChangesetOperation>,
+}
+
+/// How to resolve a [`Conflict`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictAction {
+   /// Skip the conflicting change and carry on
+   Omit,
+   /// Apply the change anyway, overwriting or replacing the existing row
+   ///
+   /// Only valid for [`ConflictKind::Data`] and [`ConflictKind::Conflict`];
+   /// SQLite fails the whole apply if it's returned for other conflicts.
+   Replace,
+   /// Stop and roll back every change applied so far
+   Abort,
+}
+
+struct ApplyContext<F> {
+   on_conflict: F,
+   panic: Option<Box<dyn Any + Send>>,
+}
+
+/// Apply a changeset or patchset to the main database of `conn`
+///
+/// `on_conflict` is called for each change that conflicts with the database
+/// and decides how to resolve it. The changeset is applied in a savepoint, so
+/// if the apply fails or is aborted no changes are made. A panic in
+/// `on_conflict` aborts the apply and is then resumed.
+///
+/// Applying through a writer that has a recording [`Session`] records the
+/// applied changes too.
+pub async fn apply_changeset<F>(
+   conn: &mut SqliteConnection,
+   changeset: &[u8],
+   on_conflict: F,
+) -> Result<()>
+where
+   F: FnMut(&Conflict) -> ConflictAction + Send,
+{
+   let len = c_int::try_from(changeset.len())
+      .map_err(|_| Error::Session("changeset is too large".to_string()))?;
+   let mut ctx = ApplyContext {
+      on_conflict,
+      panic: None,
+   };
+
+   let mut handle = conn.lock_handle().await?;
+   let db = handle.as_raw_handle().as_ptr();
+   // SAFETY: `db` is a live connection we hold the lock on. SQLite only reads
+   // the changeset despite taking a mutable pointer, and `ctx` outlives the
+   // call.
+   let rc = unsafe {
+      ffi::sqlite3changeset_apply(
+         db,
+         len,
+         changeset.as_ptr() as *mut c_void,
+         None,
+         Some(conflict_callback::<F>),
+         &mut ctx as *mut ApplyContext<F> as *mut c_void,
+      )
+   };
+   let message = (rc != ffi::SQLITE_OK).then(|| {
+      // SAFETY: the connection is still locked; the message is copied before
+      // anything else can replace it
+      unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(db)) }
+         .to_string_lossy()
+         .into_owned()
+   });
+   drop(handle);
+
+   if let Some(payload) = ctx.panic {
+      panic::resume_unwind(payload);
+   }
+   match message {
+      None => Ok(()),
+      Some(_) if rc == ffi::SQLITE_ABORT => {
+         Err(Error::Session("changeset apply was aborted".to_string()))
+      }
+      Some(message) => Err(Error::Session(format!(
+         "failed to apply changeset: {}",
+         message
+      ))),
+   }
+}
+
+unsafe extern "C" fn conflict_callback<F>(
+   ctx: *mut c_void,
+   kind: c_int,
+   iter: *mut ffi::sqlite3_changeset_iter,
+) -> c_int
+where
+   F: FnMut(&Conflict) -> ConflictAction,
+{
+   // SAFETY: `ctx` is the `ApplyContext<F>` passed by `apply_changeset`
+   let ctx = unsafe { &mut *(ctx as *mut ApplyContext<F>) };
+   if ctx.panic.is_some() {
+      return ffi::SQLITE_CHANGESET_ABORT;
+   }
+
+   // SAFETY: `iter` points at the conflicting change for the duration of the
+   // callback
+   let Some(conflict) = (unsafe { read_conflict(kind, iter) }) else {
+      return ffi::SQLITE_CHANGESET_ABORT;
+   };
+
+   match panic::catch_unwind(AssertUnwindSafe(|| (ctx.on_conflict)(&conflict))) {
+      Ok(ConflictAction::Omit) => ffi::SQLITE_CHANGESET_OMIT,
+      Ok(ConflictAction::Replace) => ffi::SQLITE_CHANGESET_REPLACE,
+      Ok(ConflictAction::Abort) => ffi::SQLITE_CHANGESET_ABORT,
+      Err(payload) => {
+         ctx.panic = Some(payload);
+         ffi::SQLITE_CHANGESET_ABORT
+      }
+   }
+}
+
+unsafe fn read_conflict(kind: c_int, iter: *mut ffi::sqlite3_changeset_iter) -> Option<Conflict> {
+   let kind = match kind {
+      ffi::SQLITE_CHANGESET_DATA => ConflictKind::Data,
+      ffi::SQLITE_CHANGESET_NOTFOUND => ConflictKind::NotFound,
+      ffi::SQLITE_CHANGESET_CONFLICT => ConflictKind::Conflict,
+      ffi::SQLITE_CHANGESET_CONSTRAINT => ConflictKind::Constraint,
+      // Not about any one change, so the iterator has nothing to report
+      ffi::SQLITE_CHANGESET_FOREIGN_KEY => {
+         return Some(Conflict {
+            kind: ConflictKind::ForeignKey,
+            table: None,
+            operation: None,
+         });
+      }
+      _ => return None,
+   };
+
+   let mut table: *const c_char = ptr::null();
+   let mut columns: c_int = 0;
+   let mut op: c_int = 0;
+   let mut indirect: c_int = 0;
+   // SAFETY: guaranteed by the caller
+   let rc =
+      unsafe { ffi::sqlite3changeset_op(iter, &mut table, &mut columns, &mut op, &mut indirect) };
+   if rc != ffi::SQLITE_OK || table.is_null() {
+      return None;
+   }
+   let operation = match op {
+      ffi::SQLITE_INSERT => ChangesetOperation::Insert,
+      ffi::SQLITE_UPDATE => ChangesetOperation::Update,
+      ffi::SQLITE_DELETE => ChangesetOperation::Delete,
+      _ => return None,
+   };
+
+   Some(Conflict {
+      kind,
+      // SAFETY: SQLite returns a nul-terminated table name valid for the callback
+      table: Some(
+         unsafe { CStr::from_ptr(table) }
+            .to_string_lossy()
+            .into_owned(),
+      ),
+      operation: Some(operation),
+   })
+}
+
+fn session_error(action: &str, rc: c_int) -> Error {
+   // SAFETY: sqlite3_errstr returns a static string for any result code
+   let reason = unsafe { CStr::from_ptr(ffi::sqlite3_errstr(rc)) }.to_string_lossy();
+   Error::Session(format!("failed to {}: {}", action, reason))
+}
@@ -5,6 +5,11 @@ use sqlx::pool::PoolConnection;
 use sqlx::sqlite::SqliteConnection;
 use std::ops::{Deref, DerefMut};
 
+use crate::interrupt::InterruptHandle;
+#[cfg(feature = "session")]
+use crate::session::{Conflict, ConflictAction, Session};
+use crate::write_queue::WriteTicket;
+
 /// RAII guard for exclusive write access to a database connection
 ///
 /// This guard wraps a pool connection and returns it to the pool on drop.
@@ -35,12 +40,64 @@ use std::ops::{Deref, DerefMut};
 #[derive(Debug)]
 pub struct WriteGuard {
    conn: PoolConnection<Sqlite>,
+
+   /// Interrupts the statement running on `conn`
+   interrupt: InterruptHandle,
+
+   /// Write queue slot, released to the next waiting writer after `conn` is dropped
+   _ticket: WriteTicket,
 }
 
 impl WriteGuard {
-   /// Create a new WriteGuard by taking ownership of a pool connection
-   pub(crate) fn new(conn: PoolConnection<Sqlite>) -> Self {
-      Self { conn }
+   /// Create a new WriteGuard by taking ownership of a pool connection and the
+   /// write queue slot that was granted for it
+   pub(crate) fn new(
+      conn: PoolConnection<Sqlite>,
+      ticket: WriteTicket,
+      interrupt: InterruptHandle,
+   ) -> Self {
+      Self {
+         conn,
+         interrupt,
+         _ticket: ticket,
+      }
+   }
+
+   /// Interrupt the statement currently running on this writer
+   ///
+   /// The statement fails with `SQLITE_INTERRUPT`, and SQLite rolls back any explicit
+   /// transaction it was part of. Since a running query holds `&mut` access to the
+   /// guard, cancelling from another task requires a handle obtained beforehand with
+   /// `interrupt_handle()`.
+   pub fn interrupt(&self) {
+      self.interrupt.interrupt();
+   }
+
+   /// Get a handle that can interrupt this writer's statements from another task
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      self.interrupt.clone()
+   }
+
+   /// Start recording changes made through this writer with SQLite's session
+   /// extension
+   ///
+   /// Attach the tables to record to the returned [`Session`], then write
+   /// through it and take the changes as a changeset or patchset.
+   #[cfg(feature = "session")]
+   pub async fn start_session(&mut self) -> crate::Result<Session<'_>> {
+      Session::start(&mut self.conn).await
+   }
+
+   /// Apply a changeset or patchset recorded elsewhere, resolving conflicts
+   /// with `on_conflict`
+   ///
+   /// See [`apply_changeset()`](crate::apply_changeset).
+   #[cfg(feature = "session")]
+   pub async fn apply_changeset<F>(&mut self, changeset: &[u8], on_conflict: F) -> crate::Result<()>
+   where
+      F: FnMut(&Conflict) -> ConflictAction + Send,
+   {
+      crate::session::apply_changeset(&mut self.conn, changeset, on_conflict).await
    }
 }
 
@@ -58,6 +115,7 @@ impl DerefMut for WriteGuard {
    }
 }
 
-// Drop is automatically implemented - PoolConnection returns itself to the pool
+// Drop is automatically implemented - PoolConnection returns itself to the pool, then
+// the ticket hands the write slot to the next queued writer
 
 // WriteGuard is automatically Send because PoolConnection<Sqlite> is Send
@@ -0,0 +1,199 @@
+//! Fair, priority-aware queue for access to the write connection
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::oneshot;
+
+/// Priority used when waiting for the write connection
+///
+/// Writers are served strictly in request order within a priority level. Any waiting
+/// `High` priority writer is served before all waiting `Normal` priority writers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WritePriority {
+   /// Default priority, used by `acquire_writer()`
+   #[default]
+   Normal,
+
+   /// Latency-critical writes (e.g. user-initiated saves) that should jump ahead of
+   /// queued background work
+   High,
+}
+
+/// FIFO queue granting exclusive write access, one ticket at a time
+///
+/// Ownership of the write slot is handed directly from the releasing ticket to the
+/// next waiter, so a newly arriving writer can never barge ahead of writers that are
+/// already queued.
+#[derive(Debug, Default)]
+pub(crate) struct WriteQueue {
+   state: Mutex<QueueState>,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+   /// Whether a ticket is currently outstanding. Waiters are only ever queued while
+   /// this is `true`.
+   held: bool,
+   high: VecDeque<oneshot::Sender<()>>,
+   normal: VecDeque<oneshot::Sender<()>>,
+}
+
+impl WriteQueue {
+   fn lock_state(&self) -> MutexGuard<'_, QueueState> {
+      // The state is only mutated by short, non-panicking critical sections, so a
+      // poisoned lock still holds consistent data
+      self.state.lock().unwrap_or_else(PoisonError::into_inner)
+   }
+
+   /// Wait for exclusive write access at the given priority
+   ///
+   /// Cancel-safe: if the returned future is dropped while waiting, the waiter leaves
+   /// the queue, and if the slot had already been handed to it, the slot is passed on
+   /// to the next waiter.
+   pub(crate) async fn acquire(self: &Arc<Self>, priority: WritePriority) -> WriteTicket {
+      let rx = {
+         let mut state = self.lock_state();
+
+         if !state.held {
+            state.held = true;
+            return WriteTicket {
+               queue: Arc::clone(self),
+            };
+         }
+
+         let (tx, rx) = oneshot::channel();
+
+         match priority {
+            WritePriority::High => state.high.push_back(tx),
+            WritePriority::Normal => state.normal.push_back(tx),
+         }
+
+         rx
+      };
+
+      let mut waiter = Waiter {
+         queue: Arc::clone(self),
+         rx,
+         granted: false,
+      };
+
+      // Senders are only dropped unsent when their receiver is already gone, and the
+      // queue cannot be dropped while this waiter holds a reference to it
+      (&mut waiter.rx)
+         .await
+         .expect("write queue sender dropped without granting access");
+      waiter.granted = true;
+
+      WriteTicket {
+         queue: Arc::clone(self),
+      }
+   }
+
+   /// Hand the write slot to the next live waiter, or mark it free if none remain
+   fn release(&self) {
+      let mut state = self.lock_state();
+
+      while let Some(tx) = state.high.pop_front().or_else(|| state.normal.pop_front()) {
+         // A failed send means the waiter was cancelled - skip to the next one
+         if tx.send(()).is_ok() {
+            return;
+         }
+      }
+
+      state.held = false;
+   }
+}
+
+/// A queued writer that has not yet been granted the write slot
+struct Waiter {
+   queue: Arc<WriteQueue>,
+   rx: oneshot::Receiver<()>,
+   granted: bool,
+}
+
+impl Drop for Waiter {
+   fn drop(&mut self) {
+      if self.granted {
+         return;
+      }
+
+      // Prevent any further hand-off to this waiter, then check whether one already
+      // happened. If so, this waiter owns the slot and must pass it on.
+      self.rx.close();
+      if self.rx.try_recv().is_ok() {
+         self.queue.release();
+      }
+   }
+}
+
+/// Proof of exclusive write access; releases the slot to the next waiter on drop
+#[derive(Debug)]
+pub(crate) struct WriteTicket {
+   queue: Arc<WriteQueue>,
+}
+
+impl Drop for WriteTicket {
+   fn drop(&mut self) {
+      self.queue.release();
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use std::time::Duration;
+
+   #[tokio::test]
+   async fn test_uncontended_acquire_is_immediate() {
+      let queue = Arc::new(WriteQueue::default());
+
+      let ticket = queue.acquire(WritePriority::Normal).await;
+      assert!(queue.lock_state().held);
+
+      drop(ticket);
+      assert!(!queue.lock_state().held);
+   }
+
+   #[tokio::test]
+   async fn test_cancelled_waiter_does_not_block_queue() {
+      let queue = Arc::new(WriteQueue::default());
+      let ticket = queue.acquire(WritePriority::Normal).await;
+
+      // Waiter gives up before the slot is released
+      let timed_out = tokio::time::timeout(
+         Duration::from_millis(20),
+         queue.acquire(WritePriority::High),
+      )
+      .await;
+      assert!(timed_out.is_err());
+
+      drop(ticket);
+
+      let next = tokio::time::timeout(Duration::from_secs(1), queue.acquire(WritePriority::Normal))
+         .await
+         .expect("slot should be free after cancelled waiter");
+      drop(next);
+      assert!(!queue.lock_state().held);
+   }
+
+   #[tokio::test]
+   async fn test_granted_but_unpolled_waiter_passes_slot_on() {
+      let queue = Arc::new(WriteQueue::default());
+      let ticket = queue.acquire(WritePriority::Normal).await;
+
+      let mut pending = Box::pin(queue.acquire(WritePriority::Normal));
+      assert!(poll_once(pending.as_mut()).await.is_none());
+
+      // Hand the slot to the pending waiter, then drop it without polling again
+      drop(ticket);
+      drop(pending);
+
+      assert!(!queue.lock_state().held);
+   }
+
+   async fn poll_once<F: std::future::Future + Unpin>(fut: F) -> Option<F::Output> {
+      tokio::time::timeout(Duration::ZERO, fut).await.ok()
+   }
+}
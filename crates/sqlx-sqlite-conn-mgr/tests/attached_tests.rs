@@ -419,3 +419,119 @@ async fn test_attach_lock_ordering_prevents_deadlock() {
 
    guard2.detach_all().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_attached_spec_builder() {
+   let temp_dir = TempDir::new().unwrap();
+   let main_path = temp_dir.path().join("builder_main.db");
+   let orders_path = temp_dir.path().join("builder_orders.db");
+
+   let main_db = SqliteDatabase::connect(&main_path, None).await.unwrap();
+   let orders_db = SqliteDatabase::connect(&orders_path, None).await.unwrap();
+   let mut writer = orders_db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   // A relative, non-canonical path resolves to the already connected database
+   let indirect = temp_dir.path().join(".").join("builder_orders.db");
+   let spec = AttachedSpec::builder()
+      .path(&indirect)
+      .schema_name("orders")
+      .read_only()
+      .build()
+      .await
+      .unwrap();
+
+   assert!(Arc::ptr_eq(&spec.database, &orders_db));
+   assert_eq!(spec.mode, AttachedMode::ReadOnly);
+
+   let mut conn = acquire_reader_with_attached(&main_db, vec![spec])
+      .await
+      .unwrap();
+   let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM orders.orders")
+      .fetch_one(&mut *conn)
+      .await
+      .unwrap();
+   assert_eq!(count.0, 0);
+   conn.detach_all().await.unwrap();
+
+   // Defaults to read-write when given a connected database
+   let spec = AttachedSpec::builder()
+      .database(Arc::clone(&orders_db))
+      .schema_name("orders")
+      .build()
+      .await
+      .unwrap();
+   assert_eq!(spec.mode, AttachedMode::ReadWrite);
+
+   // A missing file is reported instead of creating an empty database
+   let missing = temp_dir.path().join("missing.db");
+   let err = AttachedSpec::builder()
+      .path(&missing)
+      .schema_name("missing")
+      .build()
+      .await
+      .err()
+      .unwrap();
+   assert!(matches!(err, Error::AttachedDatabaseNotFound(path) if path == missing));
+   assert!(!missing.exists());
+
+   let err = AttachedSpec::builder()
+      .database(Arc::clone(&orders_db))
+      .schema_name("main")
+      .build()
+      .await
+      .err()
+      .unwrap();
+   assert!(matches!(err, Error::InvalidSchemaName(_)));
+
+   let err = AttachedSpec::builder()
+      .schema_name("orders")
+      .build()
+      .await
+      .err()
+      .unwrap();
+   assert!(matches!(err, Error::InvalidAttachedSpec(_)));
+
+   main_db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_attach_failure_names_database() {
+   let temp_dir = TempDir::new().unwrap();
+   let main_db = SqliteDatabase::connect(temp_dir.path().join("fail_main.db"), None)
+      .await
+      .unwrap();
+   let other_db = SqliteDatabase::connect(temp_dir.path().join("fail_other.db"), None)
+      .await
+      .unwrap();
+
+   // Attaching the same schema name that is already attached on this connection fails
+   let mut writer = main_db.acquire_writer().await.unwrap();
+   sqlx::query("ATTACH DATABASE ':memory:' AS other")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   let err = acquire_writer_with_attached(
+      &main_db,
+      vec![AttachedSpec::read_write(Arc::clone(&other_db), "other")],
+   )
+   .await
+   .err()
+   .unwrap();
+
+   match err {
+      Error::AttachFailed {
+         schema_name, path, ..
+      } => {
+         assert_eq!(schema_name, "other");
+         assert!(path.ends_with("fail_other.db"));
+      }
+      other => panic!("expected AttachFailed, got {other:?}"),
+   }
+}
@@ -1,5 +1,5 @@
 use sqlx::migrate::Migrator;
-use sqlx_sqlite_conn_mgr::{Error, SqliteDatabase, SqliteDatabaseConfig};
+use sqlx_sqlite_conn_mgr::{Error, SqliteDatabase, SqliteDatabaseConfig, WritePriority};
 use std::sync::Arc;
 use tempfile::TempDir;
 
@@ -188,6 +188,49 @@ async fn test_remove() {
    assert!(!shm_path.exists(), "SHM file should be removed");
 }
 
+#[tokio::test]
+async fn test_remove_refuses_while_other_handles_exist() {
+   let temp_dir = TempDir::new().unwrap();
+   let test_path = temp_dir.path().join("remove_in_use.db");
+
+   let db = SqliteDatabase::connect(&test_path, None).await.unwrap();
+   let other = Arc::clone(&db);
+
+   let result = db.remove().await;
+   assert!(matches!(result, Err(Error::DatabaseInUse(1))));
+   assert!(test_path.exists(), "Files must not be deleted while in use");
+
+   // The remaining handle is still fully usable
+   let mut writer = other.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE t (id INTEGER)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   // Once it is the last handle, remove succeeds
+   other.remove().await.unwrap();
+   assert!(!test_path.exists());
+}
+
+#[tokio::test]
+async fn test_force_remove_with_other_handles() {
+   let temp_dir = TempDir::new().unwrap();
+   let test_path = temp_dir.path().join("force_remove.db");
+
+   let db = SqliteDatabase::connect(&test_path, None).await.unwrap();
+   let other = Arc::clone(&db);
+
+   db.force_remove().await.unwrap();
+   assert!(!test_path.exists());
+
+   // Remaining handles observe the closed database instead of writing to deleted files
+   assert!(matches!(
+      other.acquire_writer().await,
+      Err(Error::DatabaseClosed)
+   ));
+}
+
 #[tokio::test]
 async fn test_custom_config() {
    let test_path = std::env::current_dir()
@@ -197,6 +240,16 @@ async fn test_custom_config() {
    let custom_config = SqliteDatabaseConfig {
       max_read_connections: 10,
       idle_timeout_secs: 60,
+      foreign_keys: true,
+      eager_wal_init: false,
+      warm_read_connections: 0,
+      read_statement_timeout_ms: None,
+      quick_check_on_connect: false,
+      corruption_recovery: Default::default(),
+      temp_store: Default::default(),
+      temp_directory: None,
+      page_size: None,
+      secure_delete: false,
    };
 
    // Verify custom config is accepted and connection works
@@ -207,6 +260,958 @@ async fn test_custom_config() {
    db.remove().await.unwrap();
 }
 
+#[tokio::test]
+async fn test_foreign_keys_enabled_by_default() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("fk_default.db"), None)
+      .await
+      .unwrap();
+
+   let (read_fk,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(read_fk, 1, "Readers should enforce foreign keys by default");
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   let (write_fk,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(write_fk, 1, "Writer should enforce foreign keys by default");
+
+   sqlx::query("CREATE TABLE parent (id INTEGER PRIMARY KEY)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query(
+      "CREATE TABLE child (id INTEGER PRIMARY KEY, parent_id INTEGER REFERENCES parent(id))",
+   )
+   .execute(&mut *writer)
+   .await
+   .unwrap();
+
+   let result = sqlx::query("INSERT INTO child (parent_id) VALUES (42)")
+      .execute(&mut *writer)
+      .await;
+   assert!(result.is_err(), "Orphaned child row should be rejected");
+
+   drop(writer);
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_foreign_keys_disabled() {
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      foreign_keys: false,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("fk_disabled.db"), Some(config))
+      .await
+      .unwrap();
+
+   let (read_fk,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(read_fk, 0);
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   let (write_fk,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(write_fk, 0);
+
+   drop(writer);
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_writers_served_in_fifo_order_with_priority() {
+   use std::time::Duration;
+   use tokio::sync::Mutex;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("write_queue.db"), None)
+      .await
+      .unwrap();
+
+   let order = Arc::new(Mutex::new(Vec::new()));
+
+   // Hold the writer so every subsequent request has to queue
+   let writer = db.acquire_writer().await.unwrap();
+
+   let requests = [
+      ("normal-1", WritePriority::Normal),
+      ("normal-2", WritePriority::Normal),
+      ("high-1", WritePriority::High),
+      ("normal-3", WritePriority::Normal),
+      ("high-2", WritePriority::High),
+   ];
+
+   let mut handles = Vec::new();
+   for (label, priority) in requests {
+      let (db, order) = (Arc::clone(&db), Arc::clone(&order));
+      handles.push(tokio::spawn(async move {
+         let _writer = db.acquire_writer_priority(priority).await.unwrap();
+         order.lock().await.push(label);
+      }));
+
+      // Give each task time to enqueue before spawning the next one
+      tokio::time::sleep(Duration::from_millis(20)).await;
+   }
+
+   drop(writer);
+
+   for handle in handles {
+      handle.await.unwrap();
+   }
+
+   assert_eq!(
+      *order.lock().await,
+      vec!["high-1", "high-2", "normal-1", "normal-2", "normal-3"]
+   );
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_acquire_writer_timeout() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("writer_timeout.db"), None)
+      .await
+      .unwrap();
+
+   // Uncontended acquisition succeeds well within the limit
+   let writer = db
+      .acquire_writer_timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+
+   // While the writer is held, a short timeout expires
+   let result = db.acquire_writer_timeout(Duration::from_millis(50)).await;
+   assert!(matches!(result, Err(Error::WriterAcquireTimeout(d)) if d == Duration::from_millis(50)));
+
+   // A timed-out request does not block subsequent writers
+   drop(writer);
+   let writer = db
+      .acquire_writer_timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+   drop(writer);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_queued_writer_sees_close() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("write_queue_close.db"), None)
+      .await
+      .unwrap();
+
+   let writer = db.acquire_writer().await.unwrap();
+
+   let db_clone = Arc::clone(&db);
+   let waiter = tokio::spawn(async move { db_clone.acquire_writer().await.map(|_| ()) });
+   tokio::time::sleep(Duration::from_millis(20)).await;
+
+   drop(writer);
+   let closing = tokio::spawn(Arc::clone(&db).close());
+
+   let result = waiter.await.unwrap();
+   closing.await.unwrap().unwrap();
+
+   // The queued writer either finished before close() or observed the closed flag
+   assert!(matches!(result, Ok(()) | Err(Error::DatabaseClosed)));
+}
+
+#[tokio::test]
+async fn test_eager_wal_init_on_connect() {
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      eager_wal_init: true,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("eager_wal.db"), Some(config))
+      .await
+      .unwrap();
+
+   // WAL is visible to readers without any write having happened
+   let (mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(mode.to_lowercase(), "wal");
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_initialize_enables_wal() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("initialize.db"), None)
+      .await
+      .unwrap();
+
+   db.initialize().await.unwrap();
+   // Idempotent
+   db.initialize().await.unwrap();
+
+   let (mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(mode.to_lowercase(), "wal");
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_warm_read_connections() {
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      max_read_connections: 4,
+      warm_read_connections: 3,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("warm.db"), Some(config))
+      .await
+      .unwrap();
+
+   assert_eq!(
+      db.read_pool().unwrap().size(),
+      3,
+      "Warmed connections should be open"
+   );
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_warm_read_connections_capped_to_max() {
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      max_read_connections: 2,
+      warm_read_connections: 10,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("warm_capped.db"), Some(config))
+      .await
+      .unwrap();
+
+   assert_eq!(db.read_pool().unwrap().size(), 2);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_read_statement_timeout_interrupts_runaway_query() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      max_read_connections: 1,
+      read_statement_timeout_ms: Some(100),
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("stmt_timeout.db"), Some(config))
+      .await
+      .unwrap();
+
+   // Unbounded recursive CTE never finishes on its own
+   let runaway = sqlx::query_scalar::<_, i64>(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c",
+   )
+   .fetch_one(db.read_pool().unwrap());
+
+   let result = tokio::time::timeout(Duration::from_secs(10), runaway)
+      .await
+      .expect("progress handler should interrupt the query");
+   let err = result.unwrap_err();
+   assert!(
+      err.to_string().contains("interrupt"),
+      "Expected SQLITE_INTERRUPT, got: {err}"
+   );
+
+   // The same (only) connection gets a fresh deadline on its next acquisition
+   let value: i64 = sqlx::query_scalar("SELECT 42")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(value, 42);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_quick_check_passes_for_healthy_database() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("healthy.db");
+
+   let db = SqliteDatabase::connect(&path, None).await.unwrap();
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+   db.close().await.unwrap();
+
+   let config = SqliteDatabaseConfig {
+      quick_check_on_connect: true,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(&path, Some(config)).await.unwrap();
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_quick_check_rejects_non_database_file() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("garbage.db");
+   std::fs::write(&path, vec![0xAB; 8192]).unwrap();
+
+   let config = SqliteDatabaseConfig {
+      quick_check_on_connect: true,
+      ..Default::default()
+   };
+   let result = SqliteDatabase::connect(&path, Some(config)).await;
+   assert!(
+      matches!(result, Err(Error::CorruptDatabase(_))),
+      "Expected CorruptDatabase, got: {result:?}"
+   );
+}
+
+#[tokio::test]
+async fn test_quick_check_rejects_damaged_database() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("damaged.db");
+
+   let db = SqliteDatabase::connect(&path, None).await.unwrap();
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 2000)
+       INSERT INTO items (name) SELECT printf('item-%d', x) FROM c",
+   )
+   .execute(&mut *writer)
+   .await
+   .unwrap();
+   drop(writer);
+   db.close().await.unwrap();
+
+   // Scribble over the table's pages, leaving the header and schema page intact
+   let mut bytes = std::fs::read(&path).unwrap();
+   assert!(bytes.len() > 3 * 4096);
+   for byte in &mut bytes[4096..3 * 4096] {
+      *byte = 0x5A;
+   }
+   std::fs::write(&path, bytes).unwrap();
+
+   let config = SqliteDatabaseConfig {
+      quick_check_on_connect: true,
+      ..Default::default()
+   };
+   let result = SqliteDatabase::connect(&path, Some(config)).await;
+   assert!(
+      matches!(result, Err(Error::CorruptDatabase(_))),
+      "Expected CorruptDatabase, got: {result:?}"
+   );
+}
+
+#[tokio::test]
+async fn test_corruption_recovery_salvages_readable_rows() {
+   use sqlx_sqlite_conn_mgr::CorruptionRecovery;
+
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("recoverable.db");
+
+   let db = SqliteDatabase::connect(&path, None).await.unwrap();
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE intact (id INTEGER PRIMARY KEY, name TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO intact (name) VALUES ('a'), ('b'), ('c')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 2000)
+       INSERT INTO items (name) SELECT printf('item-%d', x) FROM c",
+   )
+   .execute(&mut *writer)
+   .await
+   .unwrap();
+   drop(writer);
+   db.close().await.unwrap();
+
+   // Damage a leaf page in the middle of the items table
+   let mut bytes = std::fs::read(&path).unwrap();
+   let page_count = bytes.len() / 4096;
+   assert!(page_count > 8);
+   let damaged_page = page_count / 2;
+   for byte in &mut bytes[damaged_page * 4096..(damaged_page + 1) * 4096] {
+      *byte = 0x5A;
+   }
+   std::fs::write(&path, bytes).unwrap();
+
+   let config = SqliteDatabaseConfig {
+      corruption_recovery: CorruptionRecovery::Recover,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(&path, Some(config)).await.unwrap();
+
+   let report = db
+      .recovery_report()
+      .expect("database should have been rebuilt");
+   assert!(
+      report.backup_path.exists(),
+      "Damaged file should be kept as a backup"
+   );
+   assert!(!report.is_complete());
+
+   let intact = report.tables.iter().find(|t| t.name == "intact").unwrap();
+   assert!(intact.complete);
+   assert_eq!(intact.rows_recovered, 3);
+
+   let items = report.tables.iter().find(|t| t.name == "items").unwrap();
+   assert!(!items.complete);
+   assert!(items.rows_recovered > 0 && items.rows_recovered < 2000);
+
+   // The rebuilt database is healthy and holds the salvaged rows
+   let (check,): (String,) = sqlx::query_as("PRAGMA quick_check")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(check, "ok");
+
+   let count: i64 = sqlx::query_scalar("SELECT count(*) FROM items")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(count as u64, items.rows_recovered);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_corruption_recovery_leaves_unsalvageable_file_in_place() {
+   use sqlx_sqlite_conn_mgr::CorruptionRecovery;
+
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("hopeless.db");
+   std::fs::write(&path, vec![0xAB; 8192]).unwrap();
+
+   let config = SqliteDatabaseConfig {
+      corruption_recovery: CorruptionRecovery::Recover,
+      ..Default::default()
+   };
+   let result = SqliteDatabase::connect(&path, Some(config)).await;
+   assert!(matches!(result, Err(Error::CorruptDatabase(_))));
+
+   // Nothing was moved or left behind
+   let entries: Vec<_> = std::fs::read_dir(temp_dir.path()).unwrap().collect();
+   assert_eq!(entries.len(), 1);
+   assert_eq!(std::fs::read(&path).unwrap(), vec![0xAB; 8192]);
+}
+
+#[tokio::test]
+async fn test_incremental_vacuum_reclaims_pages_in_steps() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("incremental.db"), None)
+      .await
+      .unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   // The writer is already in WAL mode, so the auto_vacuum change needs a VACUUM
+   sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("VACUUM").execute(&mut *writer).await.unwrap();
+   sqlx::query("CREATE TABLE blobs (id INTEGER PRIMARY KEY, data BLOB)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100)
+       INSERT INTO blobs (data) SELECT randomblob(4000) FROM c",
+   )
+   .execute(&mut *writer)
+   .await
+   .unwrap();
+   sqlx::query("DELETE FROM blobs")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+   assert!(free_pages > 10);
+
+   assert_eq!(db.incremental_vacuum(10).await.unwrap(), 10);
+
+   let remaining = db.incremental_vacuum(0).await.unwrap();
+   assert_eq!(remaining, free_pages as u64 - 10);
+   assert_eq!(db.incremental_vacuum(0).await.unwrap(), 0);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_incremental_vacuum_is_noop_without_auto_vacuum() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("no_auto_vacuum.db"), None)
+      .await
+      .unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE blobs (id INTEGER PRIMARY KEY, data BLOB)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO blobs (data) VALUES (randomblob(20000))")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("DELETE FROM blobs")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   assert_eq!(db.incremental_vacuum(0).await.unwrap(), 0);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_snapshot_to_copies_committed_data() {
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      read_statement_timeout_ms: Some(60_000),
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("source.db"), Some(config))
+      .await
+      .unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO items (name) VALUES ('a'), ('b')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   // Uncommitted work on the writer must not block or leak into the snapshot
+   sqlx::query("BEGIN").execute(&mut *writer).await.unwrap();
+   sqlx::query("INSERT INTO items (name) VALUES ('uncommitted')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   let snapshot_path = temp_dir.path().join("snapshot.db");
+   db.snapshot_to(&snapshot_path).await.unwrap();
+
+   sqlx::query("ROLLBACK").execute(&mut *writer).await.unwrap();
+   drop(writer);
+
+   let snapshot = SqliteDatabase::connect(&snapshot_path, None).await.unwrap();
+   let names: Vec<String> = sqlx::query_scalar("SELECT name FROM items ORDER BY id")
+      .fetch_all(snapshot.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(names, vec!["a", "b"]);
+
+   // Refuses to overwrite an existing file
+   assert!(db.snapshot_to(&snapshot_path).await.is_err());
+
+   snapshot.close().await.unwrap();
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_temp_store_applied_to_all_connections() {
+   use sqlx_sqlite_conn_mgr::TempStore;
+
+   let temp_dir = TempDir::new().unwrap();
+   let config = SqliteDatabaseConfig {
+      temp_store: TempStore::Memory,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("temp_store.db"), Some(config))
+      .await
+      .unwrap();
+
+   // 2 = MEMORY
+   let read_value: i64 = sqlx::query_scalar("PRAGMA temp_store")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(read_value, 2);
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   let write_value: i64 = sqlx::query_scalar("PRAGMA temp_store")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(write_value, 2);
+   drop(writer);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_temp_directory_sets_process_wide_pragma() {
+   let temp_dir = TempDir::new().unwrap();
+
+   // The setting is process-wide, so use a directory that outlives this test in case
+   // other tests create temp files concurrently
+   let shared_temp = std::env::temp_dir();
+   let config = SqliteDatabaseConfig {
+      temp_directory: Some(shared_temp.clone()),
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(temp_dir.path().join("temp_dir.db"), Some(config))
+      .await
+      .unwrap();
+
+   let value: String = sqlx::query_scalar("PRAGMA temp_store_directory")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(value, shared_temp.to_string_lossy());
+
+   // Restore SQLite's default lookup
+   sqlx::query("PRAGMA temp_store_directory = ''")
+      .execute(db.read_pool().unwrap())
+      .await
+      .unwrap();
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_page_size_applied_to_new_database_only() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("page_size.db");
+   let config = SqliteDatabaseConfig {
+      page_size: Some(16_384),
+      ..Default::default()
+   };
+
+   let db = SqliteDatabase::connect(&path, Some(config.clone()))
+      .await
+      .unwrap();
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE items (id INTEGER PRIMARY KEY)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(page_size, 16_384);
+   drop(writer);
+   db.close().await.unwrap();
+
+   // An existing database keeps the page size it was created with
+   let existing_path = temp_dir.path().join("existing.db");
+   let db = SqliteDatabase::connect(&existing_path, None).await.unwrap();
+   db.initialize().await.unwrap();
+   db.close().await.unwrap();
+
+   let db = SqliteDatabase::connect(&existing_path, Some(config))
+      .await
+      .unwrap();
+   let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(page_size, 4096);
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_secure_delete_zeroes_deleted_content() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("secure_delete.db");
+   let config = SqliteDatabaseConfig {
+      secure_delete: true,
+      ..Default::default()
+   };
+   let db = SqliteDatabase::connect(&path, Some(config)).await.unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   let enabled: i64 = sqlx::query_scalar("PRAGMA secure_delete")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(enabled, 1);
+
+   sqlx::query("CREATE TABLE secrets (data TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO secrets VALUES ('TOP-SECRET-MARKER')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("DELETE FROM secrets")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   assert!(!file_contains(&path, b"TOP-SECRET-MARKER"));
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_overwrite_free_pages_scrubs_previously_deleted_content() {
+   let temp_dir = TempDir::new().unwrap();
+   let path = temp_dir.path().join("overwrite.db");
+   let db = SqliteDatabase::connect(&path, None).await.unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE secrets (data TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query(
+      "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 200)
+       INSERT INTO secrets SELECT 'TOP-SECRET-MARKER-' || x FROM c",
+   )
+   .execute(&mut *writer)
+   .await
+   .unwrap();
+   sqlx::query("DROP TABLE secrets")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   // Without secure_delete, the dropped rows are still in the file
+   assert!(file_contains(&path, b"TOP-SECRET-MARKER"));
+
+   let scrubbed = db.overwrite_free_pages().await.unwrap();
+   assert!(scrubbed > 0);
+   assert!(!file_contains(&path, b"TOP-SECRET-MARKER"));
+
+   // secure_delete is restored to the configured (off) setting
+   let mut writer = db.acquire_writer().await.unwrap();
+   let enabled: i64 = sqlx::query_scalar("PRAGMA secure_delete")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(enabled, 0);
+   drop(writer);
+
+   db.close().await.unwrap();
+}
+
+fn file_contains(path: &std::path::Path, needle: &[u8]) -> bool {
+   let bytes = std::fs::read(path).unwrap();
+   bytes.windows(needle.len()).any(|window| window == needle)
+}
+
+const RUNAWAY_QUERY: &str =
+   "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c";
+
+#[tokio::test]
+async fn test_interrupt_all_reads() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("interrupt_reads.db"), None)
+      .await
+      .unwrap();
+
+   let runaway = {
+      let db = Arc::clone(&db);
+      tokio::spawn(async move {
+         sqlx::query_scalar::<_, i64>(RUNAWAY_QUERY)
+            .fetch_one(db.read_pool().unwrap())
+            .await
+      })
+   };
+
+   // Give the query time to start running
+   tokio::time::sleep(Duration::from_millis(100)).await;
+   db.interrupt_all_reads();
+
+   let result = tokio::time::timeout(Duration::from_secs(10), runaway)
+      .await
+      .expect("interrupt should stop the query")
+      .unwrap();
+   let err = result.unwrap_err();
+   assert!(
+      err.to_string().contains("interrupt"),
+      "Expected SQLITE_INTERRUPT, got: {err}"
+   );
+
+   // Later reads are unaffected
+   let value: i64 = sqlx::query_scalar("SELECT 42")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(value, 42);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_read_guard_interrupt_handle() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("interrupt_reader.db"), None)
+      .await
+      .unwrap();
+
+   // A second long read on another connection must survive the targeted interrupt
+   let bystander = {
+      let db = Arc::clone(&db);
+      tokio::spawn(async move {
+         let mut reader = db.acquire_reader().await.unwrap();
+         sqlx::query_scalar::<_, i64>(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 2000000) \
+             SELECT count(*) FROM c",
+         )
+         .fetch_one(&mut *reader)
+         .await
+      })
+   };
+
+   let mut reader = db.acquire_reader().await.unwrap();
+   let handle = reader.interrupt_handle();
+
+   let canceller = tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      handle.interrupt();
+   });
+
+   let result = tokio::time::timeout(
+      Duration::from_secs(10),
+      sqlx::query_scalar::<_, i64>(RUNAWAY_QUERY).fetch_one(&mut *reader),
+   )
+   .await
+   .expect("interrupt should stop the query");
+   assert!(result.unwrap_err().to_string().contains("interrupt"));
+   canceller.await.unwrap();
+
+   assert_eq!(bystander.await.unwrap().unwrap(), 2_000_000);
+
+   // The pool-wide interrupt still reaches a reader acquired this way
+   let runaway = {
+      let db = Arc::clone(&db);
+      tokio::spawn(async move {
+         let mut reader = db.acquire_reader().await.unwrap();
+         sqlx::query_scalar::<_, i64>(RUNAWAY_QUERY)
+            .fetch_one(&mut *reader)
+            .await
+      })
+   };
+   tokio::time::sleep(Duration::from_millis(100)).await;
+   db.interrupt_all_reads();
+   let result = tokio::time::timeout(Duration::from_secs(10), runaway)
+      .await
+      .expect("interrupt should stop the query")
+      .unwrap();
+   assert!(result.unwrap_err().to_string().contains("interrupt"));
+
+   drop(reader);
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_write_guard_interrupt_handle() {
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("interrupt_writer.db"), None)
+      .await
+      .unwrap();
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   let handle = writer.interrupt_handle();
+
+   let canceller = tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      handle.interrupt();
+   });
+
+   let result = tokio::time::timeout(
+      Duration::from_secs(10),
+      sqlx::query_scalar::<_, i64>(RUNAWAY_QUERY).fetch_one(&mut *writer),
+   )
+   .await
+   .expect("interrupt should stop the query");
+   assert!(result.unwrap_err().to_string().contains("interrupt"));
+   canceller.await.unwrap();
+
+   // The interrupt was consumed; the writer keeps working
+   let value: i64 = sqlx::query_scalar("SELECT 42")
+      .fetch_one(&mut *writer)
+      .await
+      .unwrap();
+   assert_eq!(value, 42);
+   drop(writer);
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stale_interrupt_does_not_reach_next_writer() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = SqliteDatabase::connect(temp_dir.path().join("stale_interrupt.db"), None)
+      .await
+      .unwrap();
+
+   let writer = db.acquire_writer().await.unwrap();
+   writer.interrupt();
+   drop(writer);
+
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE t (x INTEGER)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   db.close().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_wal_mode_initialization() {
    let test_path = std::env::current_dir().unwrap().join("test_wal_mode.db");
@@ -0,0 +1,149 @@
+use sqlx_sqlite_conn_mgr::{Error, SqliteDatabaseConfig, SqliteDatabaseManager};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_get_or_connect_returns_same_instance() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::default();
+   let path = temp_dir.path().join("managed.db");
+
+   let db1 = manager.get_or_connect(&path, None).await.unwrap();
+   let db2 = manager.get_or_connect(&path, None).await.unwrap();
+   assert!(Arc::ptr_eq(&db1, &db2));
+
+   // Different spellings of the same file share one entry
+   let dotted = temp_dir.path().join(".").join("managed.db");
+   let db3 = manager.get_or_connect(&dotted, None).await.unwrap();
+   assert!(Arc::ptr_eq(&db1, &db3));
+   assert_eq!(manager.len().await, 1);
+
+   drop((db1, db2, db3));
+   manager.close_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_concurrent_get_or_connect_keeps_one_instance() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::default().with_max_databases(2);
+   let path = temp_dir.path().join("raced.db");
+
+   // Callers racing to open the same path all get the instance that was stored
+   let (a, b) = tokio::join!(
+      manager.get_or_connect(&path, None),
+      manager.get_or_connect(&path, None)
+   );
+   assert!(Arc::ptr_eq(&a.unwrap(), &b.unwrap()));
+   let (a, b) = tokio::join!(
+      manager.get_or_connect(":memory:", None),
+      manager.get_or_connect(":memory:", None)
+   );
+   assert!(Arc::ptr_eq(&a.unwrap(), &b.unwrap()));
+   assert_eq!(manager.len().await, 2);
+
+   // The limit still holds for callers that connected at the same time
+   manager.close(":memory:").await.unwrap();
+   let (a, b) = tokio::join!(
+      manager.get_or_connect(temp_dir.path().join("a.db"), None),
+      manager.get_or_connect(temp_dir.path().join("b.db"), None)
+   );
+   assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+   assert_eq!(manager.len().await, 2);
+
+   manager.close_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_default_config_applied() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::new(SqliteDatabaseConfig {
+      foreign_keys: false,
+      ..Default::default()
+   });
+   assert!(!manager.default_config().foreign_keys);
+
+   let db = manager
+      .get_or_connect(temp_dir.path().join("defaults.db"), None)
+      .await
+      .unwrap();
+
+   let (fk,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+      .fetch_one(db.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(fk, 0, "Manager default config should be used");
+
+   drop(db);
+   manager.close_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_max_databases_enforced() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::default().with_max_databases(1);
+
+   let first = temp_dir.path().join("first.db");
+   manager.get_or_connect(&first, None).await.unwrap();
+
+   // Re-fetching an open database doesn't count against the limit
+   manager.get_or_connect(&first, None).await.unwrap();
+
+   let result = manager
+      .get_or_connect(temp_dir.path().join("second.db"), None)
+      .await;
+   assert!(matches!(result, Err(Error::TooManyDatabases(1))));
+
+   // Closing frees a slot
+   assert!(manager.close(&first).await.unwrap());
+   manager
+      .get_or_connect(temp_dir.path().join("second.db"), None)
+      .await
+      .unwrap();
+
+   manager.close_all().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close_and_close_all() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::default();
+
+   let a = manager
+      .get_or_connect(temp_dir.path().join("a.db"), None)
+      .await
+      .unwrap();
+   manager
+      .get_or_connect(temp_dir.path().join("b.db"), None)
+      .await
+      .unwrap();
+   assert_eq!(manager.paths().await.len(), 2);
+
+   assert!(manager.close(temp_dir.path().join("a.db")).await.unwrap());
+   assert!(!manager.close(temp_dir.path().join("a.db")).await.unwrap());
+   assert!(manager.get(temp_dir.path().join("a.db")).await.is_none());
+   assert!(matches!(a.read_pool(), Err(Error::DatabaseClosed)));
+
+   manager.close_all().await.unwrap();
+   assert!(manager.is_empty().await);
+}
+
+#[tokio::test]
+async fn test_remove_refuses_while_handles_exist() {
+   let temp_dir = TempDir::new().unwrap();
+   let manager = SqliteDatabaseManager::default();
+   let path = temp_dir.path().join("removable.db");
+
+   let db = manager.get_or_connect(&path, None).await.unwrap();
+
+   let result = manager.remove(&path).await;
+   assert!(matches!(result, Err(Error::DatabaseInUse(1))));
+   assert!(
+      manager.get(&path).await.is_some(),
+      "Refused removal keeps the database managed"
+   );
+
+   drop(db);
+   assert!(manager.remove(&path).await.unwrap());
+   assert!(!path.exists());
+   assert!(manager.is_empty().await);
+}
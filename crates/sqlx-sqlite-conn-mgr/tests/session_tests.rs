@@ -0,0 +1,145 @@
+//! Tests for changesets recorded with the session extension.
+//!
+//! Run with: cargo test --features session
+
+#![cfg(feature = "session")]
+
+use sqlx_sqlite_conn_mgr::{
+   ChangesetOperation, Conflict, ConflictAction, ConflictKind, Error, SqliteDatabase,
+};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+async fn create_db(temp_dir: &TempDir, name: &str) -> Arc<SqliteDatabase> {
+   let db = SqliteDatabase::connect(temp_dir.path().join(name), None)
+      .await
+      .unwrap();
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT NOT NULL)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("CREATE TABLE scratch (id INTEGER PRIMARY KEY, body TEXT)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   db
+}
+
+async fn bodies(db: &SqliteDatabase) -> Vec<String> {
+   sqlx::query_scalar("SELECT body FROM notes ORDER BY id")
+      .fetch_all(db.read_pool().unwrap())
+      .await
+      .unwrap()
+}
+
+#[tokio::test]
+async fn test_changeset_replays_on_another_database() {
+   let temp_dir = TempDir::new().unwrap();
+   let source = create_db(&temp_dir, "source.db").await;
+   let replica = create_db(&temp_dir, "replica.db").await;
+
+   let changeset = {
+      let mut writer = source.acquire_writer().await.unwrap();
+      let mut session = writer.start_session().await.unwrap();
+      session.attach_table("notes").await.unwrap();
+      assert!(session.is_empty().await.unwrap());
+
+      for body in ["first", "second"] {
+         sqlx::query("INSERT INTO notes (body) VALUES (?)")
+            .bind(body)
+            .execute(&mut *session)
+            .await
+            .unwrap();
+      }
+      sqlx::query("UPDATE notes SET body = 'SECOND' WHERE id = 2")
+         .execute(&mut *session)
+         .await
+         .unwrap();
+      sqlx::query("INSERT INTO scratch (body) VALUES ('not attached')")
+         .execute(&mut *session)
+         .await
+         .unwrap();
+
+      assert!(!session.is_empty().await.unwrap());
+      let patchset = session.patchset().await.unwrap();
+      let changeset = session.changeset().await.unwrap();
+      assert!(!patchset.is_empty());
+      assert!(!changeset.is_empty());
+      changeset
+   };
+
+   let mut writer = replica.acquire_writer().await.unwrap();
+   writer
+      .apply_changeset(&changeset, |conflict| {
+         panic!("unexpected conflict: {:?}", conflict)
+      })
+      .await
+      .unwrap();
+   drop(writer);
+
+   assert_eq!(bodies(&replica).await, vec!["first", "SECOND"]);
+   let scratch: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scratch")
+      .fetch_one(replica.read_pool().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(scratch, 0, "changes to unattached tables are not recorded");
+}
+
+#[tokio::test]
+async fn test_apply_changeset_conflict_handler() {
+   let temp_dir = TempDir::new().unwrap();
+   let source = create_db(&temp_dir, "source.db").await;
+   let replica = create_db(&temp_dir, "replica.db").await;
+
+   let changeset = {
+      let mut writer = source.acquire_writer().await.unwrap();
+      let mut session = writer.start_session().await.unwrap();
+      session.attach_all().await.unwrap();
+      sqlx::query("INSERT INTO notes (id, body) VALUES (1, 'from source')")
+         .execute(&mut *session)
+         .await
+         .unwrap();
+      session.changeset().await.unwrap()
+   };
+
+   let mut writer = replica.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO notes (id, body) VALUES (1, 'local')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   // Aborting leaves the replica untouched
+   let result = writer
+      .apply_changeset(&changeset, |_| ConflictAction::Abort)
+      .await;
+   assert!(matches!(result, Err(Error::Session(_))));
+   assert_eq!(bodies(&replica).await, vec!["local"]);
+
+   // Omitting keeps the local row
+   let mut seen: Vec<Conflict> = Vec::new();
+   writer
+      .apply_changeset(&changeset, |conflict| {
+         seen.push(conflict.clone());
+         ConflictAction::Omit
+      })
+      .await
+      .unwrap();
+   assert_eq!(
+      seen,
+      vec![Conflict {
+         kind: ConflictKind::Conflict,
+         table: Some("notes".to_string()),
+         operation: Some(ChangesetOperation::Insert),
+      }]
+   );
+   assert_eq!(bodies(&replica).await, vec!["local"]);
+
+   // Replacing takes the incoming row
+   writer
+      .apply_changeset(&changeset, |_| ConflictAction::Replace)
+      .await
+      .unwrap();
+   drop(writer);
+   assert_eq!(bodies(&replica).await, vec!["from source"]);
+}
@@ -33,16 +33,25 @@
 //! (explicit or implicit) completes. On commit, buffered changes are published
 //! to subscribers. On rollback, they are discarded without notification.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 use parking_lot::{Mutex, RwLock};
-use tokio::sync::broadcast;
+use sqlx::{Pool, Sqlite};
+use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, trace};
 
-use crate::change::{ChangeOperation, ColumnValue, TableChange, TableInfo};
+use crate::change::{
+   ChangeOperation, ColumnValue, CommitSummary, DebouncedChange, ResyncEvent, TableChange,
+   TableInfo, TransactionChangeSet,
+};
+use crate::changelog::CHANGELOG_TABLE;
+use crate::debounce::Debouncer;
 use crate::hooks::{PreUpdateEvent, SqliteValue};
+use crate::resolve;
+use crate::subscription::{ChangeReceiver, SubscriberSender, SubscriptionOptions};
 
 /// Transaction-aware observation broker.
 ///
@@ -52,7 +61,27 @@ use crate::hooks::{PreUpdateEvent, SqliteValue};
 pub struct ObservationBroker {
    buffer: Mutex<Vec<PreUpdateEvent>>,
    change_tx: broadcast::Sender<TableChange>,
+   transaction_tx: broadcast::Sender<TransactionChangeSet>,
+   summary_tx: broadcast::Sender<CommitSummary>,
+   resync_tx: broadcast::Sender<ResyncEvent>,
+   commit_seq: AtomicU64,
+   /// Changes held back while paused, or `None` when publishing
+   pause: Mutex<Option<PauseState>>,
+   filtered_subscribers: Mutex<Vec<FilteredSubscriber>>,
+   own_channel_subscribers: Mutex<Vec<SubscriberSender>>,
+   debouncer: Arc<Debouncer>,
+   channel_capacity: usize,
    observed_tables: RwLock<HashSet<String>>,
+   observe_all: AtomicBool,
+   changelog: AtomicBool,
+   /// Whether primary keys are looked up by rowid instead of captured
+   resolve_keys: AtomicBool,
+   /// Hands commits to the task looking up their keys, once started
+   key_resolver: Mutex<Option<mpsc::UnboundedSender<Vec<PreUpdateEvent>>>>,
+   /// Logical name of the database, overriding its path in changes
+   database_name: RwLock<Option<Arc<str>>>,
+   /// Schema version table info was last loaded at, or -1 if never
+   schema_version: AtomicI64,
    table_info: RwLock<HashMap<String, TableInfo>>,
    capture_values: bool,
 }
@@ -70,10 +99,28 @@ impl ObservationBroker {
       // already validates before reaching this point.
       assert!(channel_capacity > 0, "channel_capacity must be at least 1");
       let (change_tx, _) = broadcast::channel(channel_capacity);
+      let (transaction_tx, _) = broadcast::channel(channel_capacity);
+      let (summary_tx, _) = broadcast::channel(channel_capacity);
+      let (resync_tx, _) = broadcast::channel(channel_capacity);
       Arc::new(Self {
          buffer: Mutex::new(Vec::new()),
          change_tx,
+         transaction_tx,
+         summary_tx,
+         resync_tx,
+         commit_seq: AtomicU64::new(0),
+         pause: Mutex::new(None),
+         filtered_subscribers: Mutex::new(Vec::new()),
+         own_channel_subscribers: Mutex::new(Vec::new()),
+         debouncer: Debouncer::new(channel_capacity),
+         channel_capacity,
          observed_tables: RwLock::new(HashSet::new()),
+         observe_all: AtomicBool::new(false),
+         changelog: AtomicBool::new(false),
+         resolve_keys: AtomicBool::new(false),
+         key_resolver: Mutex::new(None),
+         database_name: RwLock::new(None),
+         schema_version: AtomicI64::new(-1),
          table_info: RwLock::new(HashMap::new()),
          capture_values,
       })
@@ -81,7 +128,88 @@ impl ObservationBroker {
 
    /// Checks if a table is being observed.
    pub fn is_table_observed(&self, table: &str) -> bool {
-      self.observed_tables.read().contains(table)
+      (self.observes_all_tables() && !is_internal_table(table))
+         || self.observed_tables.read().contains(table)
+   }
+
+   /// Observes every table except SQLite's internal `sqlite_*` tables.
+   pub fn observe_all_tables(&self) {
+      trace!("Observing all tables");
+      self.observe_all.store(true, Ordering::Relaxed);
+   }
+
+   /// Whether every non-internal table is observed.
+   pub fn observes_all_tables(&self) -> bool {
+      self.observe_all.load(Ordering::Relaxed)
+   }
+
+   /// Logs committed changes to observed tables to the durable changelog.
+   ///
+   /// Takes effect for each table when its info is next loaded, which
+   /// installs the logging triggers.
+   pub fn enable_changelog(&self) {
+      trace!("Enabling changelog");
+      self.changelog.store(true, Ordering::Relaxed);
+   }
+
+   /// Looks up primary keys by rowid before publishing, instead of reading
+   /// column values in the preupdate hook.
+   ///
+   /// Has no effect while values are captured. Keys are only looked up once
+   /// `start_key_resolver` has been called.
+   pub fn enable_key_resolution(&self) {
+      trace!("Enabling primary key resolution");
+      self.resolve_keys.store(true, Ordering::Relaxed);
+   }
+
+   /// Whether primary keys are looked up by rowid rather than read from
+   /// captured values.
+   pub fn resolves_primary_keys(&self) -> bool {
+      !self.capture_values && self.resolve_keys.load(Ordering::Relaxed)
+   }
+
+   /// Starts the task looking up primary keys on `pool`, unless it's running
+   /// or keys aren't resolved.
+   ///
+   /// Must be called within a Tokio runtime.
+   pub(crate) fn start_key_resolver(self: &Arc<Self>, pool: &Pool<Sqlite>) {
+      if !self.resolves_primary_keys() {
+         return;
+      }
+      let mut key_resolver = self.key_resolver.lock();
+      if key_resolver.as_ref().is_none_or(|tx| tx.is_closed()) {
+         *key_resolver = Some(resolve::spawn(Arc::downgrade(self), pool.clone()));
+      }
+   }
+
+   /// Sets the name changes identify their database by, in place of the
+   /// database's path.
+   ///
+   /// Only affects connections whose hooks are registered afterward.
+   pub fn set_database_name(&self, name: &str) {
+      trace!(name, "Setting database name");
+      *self.database_name.write() = Some(Arc::from(name));
+   }
+
+   /// The name set with `set_database_name`, if any.
+   pub fn database_name(&self) -> Option<Arc<str>> {
+      self.database_name.read().clone()
+   }
+
+   /// Whether committed changes are logged to the durable changelog.
+   pub fn changelog_enabled(&self) -> bool {
+      self.changelog.load(Ordering::Relaxed)
+   }
+
+   /// Whether the schema changed since table info was last loaded, judging
+   /// by SQLite's `PRAGMA schema_version`.
+   pub fn schema_changed(&self, schema_version: i64) -> bool {
+      self.schema_version.load(Ordering::Relaxed) != schema_version
+   }
+
+   /// Records the schema version table info was loaded at.
+   pub fn set_schema_version(&self, schema_version: i64) {
+      self.schema_version.store(schema_version, Ordering::Relaxed);
    }
 
    /// Registers a table for observation with its schema information.
@@ -125,6 +253,37 @@ impl ObservationBroker {
       }
    }
 
+   /// Stops observing a table.
+   ///
+   /// Discards the table's schema info and any of its changes buffered in the
+   /// current transaction, and drops its row, predicate, and column
+   /// subscriptions, whose receivers then see the channel close. Tables
+   /// observed by [`observe_all_tables`] stay observed.
+   ///
+   /// Returns whether the table was observed explicitly.
+   ///
+   /// [`observe_all_tables`]: Self::observe_all_tables
+   pub fn unobserve_table(&self, table: &str) -> bool {
+      let was_observed = self.observed_tables.write().remove(table);
+      self.table_info.write().remove(table);
+      self.buffer.lock().retain(|event| event.table != table);
+      self
+         .filtered_subscribers
+         .lock()
+         .retain(|subscriber| subscriber.table != table);
+      trace!(table = %table, was_observed, "Unobserved table");
+      was_observed
+   }
+
+   /// Debounces notifications for a table.
+   ///
+   /// Commits to `table` within `window` of the first one are merged into a
+   /// single `DebouncedChange` for `subscribe_debounced` subscribers.
+   pub fn set_debounce(&self, table: &str, window: Duration) {
+      trace!(table = %table, window = ?window, "Debouncing table");
+      self.debouncer.set_window(table, window);
+   }
+
    /// Sets the schema information for an observed table.
    ///
    /// This information is used to extract primary key values and determine
@@ -134,6 +293,17 @@ impl ObservationBroker {
       self.table_info.write().insert(table.to_string(), info);
    }
 
+   /// Discards the schema information for a table whose schema has changed.
+   ///
+   /// Until it is queried again, changes to the table are published with an
+   /// empty `primary_key` and no column names rather than ones extracted with
+   /// a stale layout.
+   pub fn invalidate_table_info(&self, table: &str) {
+      if self.table_info.write().remove(table).is_some() {
+         debug!(table = %table, "Invalidated table info after schema change");
+      }
+   }
+
    /// Gets the schema information for an observed table.
    pub fn get_table_info(&self, table: &str) -> Option<TableInfo> {
       self.table_info.read().get(table).cloned()
@@ -144,6 +314,13 @@ impl ObservationBroker {
       self.observed_tables.read().iter().cloned().collect()
    }
 
+   /// Sequence number of the last commit published, or 0 before the first.
+   ///
+   /// Only commits that changed observed tables are counted.
+   pub fn last_commit_seq(&self) -> u64 {
+      self.commit_seq.load(Ordering::Relaxed)
+   }
+
    /// Called by preupdate_hook - buffers the event for later processing.
    ///
    /// Events are held in the buffer until either `on_commit()` (publish)
@@ -157,25 +334,208 @@ impl ObservationBroker {
       self.buffer.lock().push(event);
    }
 
+   /// Called by preupdate_hook when a changelog trigger logs a change to
+   /// `table` - tags the change with its changelog sequence number.
+   ///
+   /// The trigger runs right after the change it logs, so the change is the
+   /// latest buffered one for `table` that isn't tagged yet.
+   pub fn on_changelog_insert(&self, table: &str, seq: i64) {
+      let mut buffer = self.buffer.lock();
+      if let Some(event) = buffer
+         .iter_mut()
+         .rev()
+         .find(|event| event.table == table && event.changelog_seq.is_none())
+      {
+         event.changelog_seq = Some(seq);
+      }
+   }
+
    /// Called by commit_hook - flushes buffered events to subscribers.
    ///
    /// Converts all buffered `PreUpdateEvent`s to `TableChange`s and sends
-   /// them through the broadcast channel. The buffer is cleared afterward.
+   /// them through the broadcast channel, then sends them together as one
+   /// `TransactionChangeSet`. The buffer is cleared afterward. While paused,
+   /// the events are held back instead. When primary keys are resolved, the
+   /// events are handed to the resolver, which publishes them once their
+   /// keys are looked up.
    pub fn on_commit(&self) {
-      let events: Vec<PreUpdateEvent> = {
-         let mut buffer = self.buffer.lock();
-         std::mem::take(&mut *buffer)
+      if let Some(events) = self.take_buffered() {
+         self.on_committed(events);
+      }
+   }
+
+   /// Takes the buffered events of a transaction being committed, if any.
+   pub(crate) fn take_buffered(&self) -> Option<Vec<PreUpdateEvent>> {
+      let events = std::mem::take(&mut *self.buffer.lock());
+      (!events.is_empty()).then_some(events)
+   }
+
+   /// Puts the events of a commit that failed back in front of any buffered
+   /// since, to be committed again or rolled back with their transaction.
+   pub(crate) fn restore_buffered(&self, events: Vec<PreUpdateEvent>) {
+      let mut buffer = self.buffer.lock();
+      buffer.splice(0..0, events);
+   }
+
+   /// Publishes the events of a commit that landed, through the resolver
+   /// when primary keys are resolved.
+   pub(crate) fn on_committed(&self, events: Vec<PreUpdateEvent>) {
+      // Every commit goes through the resolver, so they stay in order
+      let events = match self.key_resolver.lock().as_ref() {
+         Some(tx) => match tx.send(events) {
+            Ok(()) => return,
+            Err(mpsc::error::SendError(events)) => events,
+         },
+         None => events,
       };
+      self.deliver(events);
+   }
 
-      if events.is_empty() {
+   /// Publishes one commit's events, or holds them back while paused.
+   pub(crate) fn deliver(&self, events: Vec<PreUpdateEvent>) {
+      {
+         // Held until publishing starts so that `resume` can't publish the
+         // paused commits after this one
+         let mut pause = self.pause.lock();
+         if let Some(state) = pause.as_mut() {
+            trace!(count = events.len(), "Holding back changes while paused");
+            state.hold(events);
+            return;
+         }
+      }
+
+      self.publish(events);
+   }
+
+   /// Stops publishing changes until `resume` is called.
+   ///
+   /// Up to `buffer_limit` changes committed in the meantime are kept and
+   /// published on resume. Beyond that, or with a limit of 0, they are
+   /// discarded and resuming sends one `ResyncEvent` instead. Pausing while
+   /// already paused has no effect.
+   pub fn pause(&self, buffer_limit: usize) {
+      let mut pause = self.pause.lock();
+      if pause.is_none() {
+         debug!(buffer_limit, "Pausing change publishing");
+         *pause = Some(PauseState::new(buffer_limit));
+      }
+   }
+
+   /// Resumes publishing changes after `pause`.
+   ///
+   /// Publishes the changes buffered during the pause, commit by commit, or
+   /// sends a `ResyncEvent` if any were discarded. Does nothing if not paused.
+   pub fn resume(&self) {
+      let mut pause = self.pause.lock();
+      let Some(state) = pause.take() else {
          return;
+      };
+      debug!(
+         commits = state.commit_count,
+         changes = state.change_count,
+         overflowed = state.overflowed,
+         "Resuming change publishing"
+      );
+
+      if state.overflowed {
+         let _ = self.resync_tx.send(ResyncEvent {
+            tables: state.tables.into_iter().collect(),
+            change_count: state.change_count,
+            commit_count: state.commit_count,
+         });
+         return;
+      }
+      // Still holding the lock, so commits made meanwhile wait their turn
+      for events in state.held {
+         self.publish(events);
       }
+   }
+
+   /// Whether publishing is paused.
+   pub fn is_paused(&self) -> bool {
+      self.pause.lock().is_some()
+   }
 
+   /// Publishes one commit's events to subscribers.
+   fn publish(&self, events: Vec<PreUpdateEvent>) {
       debug!(count = events.len(), "Flushing buffered changes on commit");
 
-      for event in events {
-         match self.event_to_change(event) {
+      let commit_seq = self.commit_seq.fetch_add(1, Ordering::Relaxed) + 1;
+
+      if self.summary_tx.receiver_count() > 0 {
+         let mut summary = CommitSummary {
+            commit_seq,
+            tables: BTreeMap::new(),
+         };
+         for event in &events {
+            summary
+               .tables
+               .entry(event.table.clone())
+               .or_default()
+               .add(event.operation);
+         }
+         let _ = self.summary_tx.send(summary);
+      }
+
+      let mut transaction_changes =
+         (self.transaction_tx.receiver_count() > 0).then(|| Vec::with_capacity(events.len()));
+
+      let mut filtered_subscribers = self.filtered_subscribers.lock();
+      filtered_subscribers.retain(|subscriber| subscriber.tx.receiver_count() > 0);
+      let mut own_channel_subscribers = self.own_channel_subscribers.lock();
+
+      // Keys and change counts per debounced table, merged after the loop
+      let debouncing = self.debouncer.has_subscribers();
+
+      // With only summary subscribers listening, there is no need to build
+      // the individual changes
+      if self.change_tx.receiver_count() == 0
+         && transaction_changes.is_none()
+         && filtered_subscribers.is_empty()
+         && own_channel_subscribers.is_empty()
+         && !debouncing
+      {
+         return;
+      }
+      let mut debounced: HashMap<String, (Vec<Vec<ColumnValue>>, usize)> = HashMap::new();
+
+      for (commit_index, event) in events.into_iter().enumerate() {
+         let table_info = self.get_table_info(&event.table);
+
+         // An UPDATE can change the primary key, so subscribers to the old key
+         // need to hear about it too
+         let (old_key, changed_columns) = match event.operation {
+            ChangeOperation::Update if !filtered_subscribers.is_empty() => (
+               old_row_key(&event, table_info.as_ref()),
+               changed_columns(&event),
+            ),
+            _ => (None, None),
+         };
+
+         match self.event_to_change(event, table_info.as_ref(), commit_seq, commit_index) {
             Ok(table_change) => {
+               if !filtered_subscribers.is_empty() {
+                  let context = ChangeContext {
+                     table_info: table_info.as_ref(),
+                     new_key: row_key(&table_change),
+                     old_key,
+                     changed_columns,
+                  };
+                  for subscriber in filtered_subscribers.iter() {
+                     if subscriber.wants(&table_change, &context) {
+                        let _ = subscriber.tx.send(table_change.clone());
+                     }
+                  }
+               }
+               if debouncing && self.debouncer.is_debounced(&table_change.table) {
+                  let (keys, count) = debounced.entry(table_change.table.clone()).or_default();
+                  keys.extend(row_key(&table_change));
+                  *count += 1;
+               }
+               if let Some(changes) = &mut transaction_changes {
+                  changes.push(table_change.clone());
+               }
+               own_channel_subscribers.retain_mut(|subscriber| subscriber.send(&table_change));
                let _ = self.change_tx.send(table_change);
             }
             Err(e) => {
@@ -183,6 +543,19 @@ impl ObservationBroker {
             }
          }
       }
+
+      drop(filtered_subscribers);
+      drop(own_channel_subscribers);
+      for (table, (keys, count)) in debounced {
+         self.debouncer.add(&table, keys, count);
+      }
+
+      if let Some(changes) = transaction_changes.filter(|changes| !changes.is_empty()) {
+         let _ = self.transaction_tx.send(TransactionChangeSet {
+            changes,
+            commit_seq,
+         });
+      }
    }
 
    /// Called by rollback_hook - discards all buffered events.
@@ -209,12 +582,127 @@ impl ObservationBroker {
       self.change_tx.subscribe()
    }
 
-   /// Converts a PreUpdateEvent to a TableChange for broadcast.
-   fn event_to_change(&self, event: PreUpdateEvent) -> crate::Result<TableChange> {
-      let table_info = self.table_info.read().get(&event.table).cloned();
+   /// Subscribes to changes to `tables` (or to every observed table if empty)
+   /// through a channel of the subscriber's own.
+   ///
+   /// The channel's capacity and what happens when it fills up come from
+   /// `options`, independent of other subscribers.
+   ///
+   /// # Panics
+   ///
+   /// Panics if `options` sets a capacity of 0.
+   pub fn subscribe_with(
+      &self,
+      tables: Vec<String>,
+      options: SubscriptionOptions,
+   ) -> ChangeReceiver {
+      let capacity = options.capacity.unwrap_or(self.channel_capacity);
+      trace!(tables = ?tables, capacity, lag_policy = ?options.lag_policy, "Subscribing with own channel");
+      let (sender, rx) = SubscriberSender::new(tables, capacity, options.lag_policy);
+      self.own_channel_subscribers.lock().push(sender);
+      rx
+   }
+
+   /// Subscribes to the resyncs sent on resume when a pause discarded
+   /// changes.
+   pub fn subscribe_resyncs(&self) -> broadcast::Receiver<ResyncEvent> {
+      self.resync_tx.subscribe()
+   }
 
+   /// Subscribes to per-commit summaries.
+   ///
+   /// Returns a broadcast receiver that gets one `CommitSummary` per committed
+   /// transaction, counting its inserts, updates, and deletes per observed
+   /// table.
+   pub fn subscribe_summaries(&self) -> broadcast::Receiver<CommitSummary> {
+      self.summary_tx.subscribe()
+   }
+
+   /// Subscribes to per-transaction change sets.
+   ///
+   /// Returns a broadcast receiver that gets one `TransactionChangeSet` per
+   /// committed transaction, holding all of its changes to observed tables.
+   pub fn subscribe_transactions(&self) -> broadcast::Receiver<TransactionChangeSet> {
+      self.transaction_tx.subscribe()
+   }
+
+   /// Subscribes to merged notifications for debounced tables.
+   ///
+   /// Must be called within a Tokio runtime, which publishes each table's
+   /// merged changes when its debounce window ends.
+   pub fn subscribe_debounced(&self) -> broadcast::Receiver<DebouncedChange> {
+      self.debouncer.subscribe()
+   }
+
+   /// Subscribes to changes of specific rows of one table.
+   ///
+   /// Each key holds a row's primary key value(s) in declaration order, or its
+   /// rowid for tables without a declared primary key. Changes are filtered
+   /// here before they are sent, so the receiver only sees changes to those
+   /// rows, including updates that move a row to or from one of the keys.
+   pub fn subscribe_rows(
+      &self,
+      table: &str,
+      keys: Vec<Vec<ColumnValue>>,
+   ) -> broadcast::Receiver<TableChange> {
+      trace!(table = %table, keys = keys.len(), "Subscribing to rows");
+      self.subscribe_filtered(table, ChangeFilter::Rows(keys))
+   }
+
+   /// Subscribes to the changes of one table that satisfy `predicate`.
+   ///
+   /// The predicate runs inside SQLite's commit hook for every committed change
+   /// to `table`, before anything is sent, so it should be cheap and must not
+   /// use the database. `old_values` and `new_values` are only available to it
+   /// when value capture is enabled.
+   pub fn subscribe_where(
+      &self,
+      table: &str,
+      predicate: ChangePredicate,
+   ) -> broadcast::Receiver<TableChange> {
+      trace!(table = %table, "Subscribing with predicate");
+      self.subscribe_filtered(table, ChangeFilter::Predicate(predicate))
+   }
+
+   /// Subscribes to the changes of one table that can affect `columns`.
+   ///
+   /// Inserts and deletes are always delivered; an UPDATE is only delivered
+   /// when it changes the value of at least one of the named columns, compared
+   /// against the row's previous values. Column names come from the table's
+   /// schema info, so until it is set every UPDATE is delivered.
+   pub fn subscribe_columns(
+      &self,
+      table: &str,
+      columns: Vec<String>,
+   ) -> broadcast::Receiver<TableChange> {
+      trace!(table = %table, columns = ?columns, "Subscribing to columns");
+      self.subscribe_filtered(table, ChangeFilter::Columns(columns))
+   }
+
+   fn subscribe_filtered(
+      &self,
+      table: &str,
+      filter: ChangeFilter,
+   ) -> broadcast::Receiver<TableChange> {
+      let (tx, rx) = broadcast::channel(self.channel_capacity);
+      self.filtered_subscribers.lock().push(FilteredSubscriber {
+         table: table.to_string(),
+         filter,
+         tx,
+      });
+      rx
+   }
+
+   /// Converts a PreUpdateEvent to a TableChange for broadcast.
+   fn event_to_change(
+      &self,
+      event: PreUpdateEvent,
+      table_info: Option<&TableInfo>,
+      commit_seq: u64,
+      commit_index: usize,
+   ) -> crate::Result<TableChange> {
       // For WITHOUT ROWID tables, the rowid from preupdate hook is not meaningful
-      let rowid = match &table_info {
+      let rowid = match table_info {
          Some(info) if info.without_rowid => None,
          _ => match event.operation {
             ChangeOperation::Insert => Some(event.new_rowid),
@@ -224,15 +712,18 @@ impl ObservationBroker {
       };
 
       // Extract primary key values from the appropriate column values
-      let primary_key = self.extract_primary_key(&event, table_info.as_ref())?;
+      let primary_key = self.extract_primary_key(&event, table_info)?;
 
-      let (old_values, new_values) = if self.capture_values {
+      let (old_values, new_values, columns) = if self.capture_values {
          (
             event.old_values.map(Self::values_to_vec),
             event.new_values.map(Self::values_to_vec),
+            table_info
+               .map(|info| Arc::clone(&info.columns))
+               .unwrap_or_default(),
          )
       } else {
-         (None, None)
+         (None, None, Arc::default())
       };
 
       Ok(TableChange {
@@ -242,7 +733,13 @@ impl ObservationBroker {
          primary_key,
          old_values,
          new_values,
+         columns,
          timestamp: Instant::now(),
+         committed_at: SystemTime::now(),
+         database: event.database.to_string(),
+         commit_seq,
+         commit_index,
+         changelog_seq: event.changelog_seq,
       })
    }
 
@@ -255,6 +752,10 @@ impl ObservationBroker {
       event: &PreUpdateEvent,
       table_info: Option<&TableInfo>,
    ) -> crate::Result<Vec<ColumnValue>> {
+      if let Some(primary_key) = &event.primary_key {
+         return Ok(primary_key.clone());
+      }
+
       let Some(info) = table_info else {
          return Ok(Vec::new());
       };
@@ -296,11 +797,176 @@ impl ObservationBroker {
    }
 }
 
+/// Predicate deciding whether a committed change is delivered to a subscriber
+pub type ChangePredicate = Arc<dyn Fn(&TableChange) -> bool + Send + Sync>;
+
+/// Which changes of its table a filtered subscriber receives
+enum ChangeFilter {
+   /// Changes to rows with one of these primary keys
+   Rows(Vec<Vec<ColumnValue>>),
+   /// Changes the predicate accepts
+   Predicate(ChangePredicate),
+   /// Inserts, deletes, and updates that change one of these columns
+   Columns(Vec<String>),
+}
+
+/// Commits held back while publishing is paused
+struct PauseState {
+   /// Changes to keep for publishing on resume
+   buffer_limit: usize,
+   /// Each held commit's events, oldest first
+   held: Vec<Vec<PreUpdateEvent>>,
+   /// Whether changes were discarded, so resuming must resync instead
+   overflowed: bool,
+   tables: BTreeSet<String>,
+   change_count: usize,
+   commit_count: usize,
+}
+
+impl PauseState {
+   fn new(buffer_limit: usize) -> Self {
+      Self {
+         buffer_limit,
+         held: Vec::new(),
+         overflowed: false,
+         tables: BTreeSet::new(),
+         change_count: 0,
+         commit_count: 0,
+      }
+   }
+
+   /// Holds one commit's events, discarding everything held once more
+   /// changes are committed than fit in the buffer.
+   fn hold(&mut self, events: Vec<PreUpdateEvent>) {
+      self.change_count += events.len();
+      self.commit_count += 1;
+      for event in &events {
+         if !self.tables.contains(&event.table) {
+            self.tables.insert(event.table.clone());
+         }
+      }
+
+      if self.overflowed {
+         return;
+      }
+      if self.change_count > self.buffer_limit {
+         self.overflowed = true;
+         self.held = Vec::new();
+      } else {
+         self.held.push(events);
+      }
+   }
+}
+
+/// What filters know about a committed change beyond the change itself
+struct ChangeContext<'a> {
+   table_info: Option<&'a TableInfo>,
+   /// Key of the row after the change
+   new_key: Option<Vec<ColumnValue>>,
+   /// Key of the row before an UPDATE
+   old_key: Option<Vec<ColumnValue>>,
+   /// Indices of the columns whose values an UPDATE changed
+   changed_columns: Option<Vec<usize>>,
+}
+
+/// A subscription to a filtered subset of one table's changes
+struct FilteredSubscriber {
+   table: String,
+   filter: ChangeFilter,
+   tx: broadcast::Sender<TableChange>,
+}
+
+impl FilteredSubscriber {
+   /// Whether `change` should be sent to this subscriber
+   fn wants(&self, change: &TableChange, context: &ChangeContext<'_>) -> bool {
+      if self.table != change.table {
+         return false;
+      }
+      match &self.filter {
+         ChangeFilter::Rows(keys) => [&context.new_key, &context.old_key]
+            .into_iter()
+            .flatten()
+            .any(|key| keys.contains(key)),
+         ChangeFilter::Predicate(predicate) => predicate(change),
+         ChangeFilter::Columns(columns) => {
+            let Some(changed) = &context.changed_columns else {
+               return true;
+            };
+            // Without column names there is nothing to compare against, so
+            // err on the side of notifying
+            match context.table_info {
+               Some(info) if !info.columns.is_empty() => columns
+                  .iter()
+                  .filter_map(|name| info.column_index(name))
+                  .any(|idx| changed.contains(&idx)),
+               _ => true,
+            }
+         }
+      }
+   }
+}
+
+/// Primary key (or rowid) a row had before an UPDATE.
+fn old_row_key(event: &PreUpdateEvent, table_info: Option<&TableInfo>) -> Option<Vec<ColumnValue>> {
+   match table_info {
+      Some(info) if !info.pk_columns.is_empty() => {
+         let old_values = event.old_values.as_ref()?;
+         info
+            .pk_columns
+            .iter()
+            .map(|&idx| old_values.get(idx).cloned().map(Into::into))
+            .collect()
+      }
+      Some(info) if info.without_rowid => None,
+      _ => Some(vec![ColumnValue::Integer(event.old_rowid)]),
+   }
+}
+
+/// Indices of the columns whose values differ between the old and new row of
+/// an UPDATE. Values are captured by the preupdate hook even when value capture
+/// is disabled, so this is always available.
+fn changed_columns(event: &PreUpdateEvent) -> Option<Vec<usize>> {
+   let (old_values, new_values) = (event.old_values.as_ref()?, event.new_values.as_ref()?);
+   Some(
+      old_values
+         .iter()
+         .zip(new_values)
+         .enumerate()
+         .filter(|(_, (old, new))| old != new)
+         .map(|(idx, _)| idx)
+         .collect(),
+   )
+}
+
+/// Whether `table` is one of SQLite's internal tables, such as `sqlite_sequence`,
+/// or the changelog
+fn is_internal_table(table: &str) -> bool {
+   table
+      .get(..7)
+      .is_some_and(|prefix| prefix.eq_ignore_ascii_case("sqlite_"))
+      || table.eq_ignore_ascii_case(CHANGELOG_TABLE)
+}
+
+/// Key identifying the changed row: its primary key, or its rowid when the
+/// table has no declared primary key
+fn row_key(change: &TableChange) -> Option<Vec<ColumnValue>> {
+   if !change.primary_key.is_empty() {
+      Some(change.primary_key.clone())
+   } else {
+      change.rowid.map(|rowid| vec![ColumnValue::Integer(rowid)])
+   }
+}
+
 impl std::fmt::Debug for ObservationBroker {
    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
       f.debug_struct("ObservationBroker")
          .field("buffer_len", &self.buffer.lock().len())
          .field("observed_tables", &self.observed_tables.read().len())
+         .field("paused", &self.is_paused())
+         .field(
+            "filtered_subscribers",
+            &self.filtered_subscribers.lock().len(),
+         )
          .finish()
    }
 }
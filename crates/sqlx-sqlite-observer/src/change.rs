@@ -1,4 +1,8 @@
-use std::time::Instant;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Serializer};
 
 use crate::hooks::SqliteValue;
 
@@ -16,6 +20,9 @@ pub struct TableInfo {
    /// of the PRIMARY KEY (coerced to i64), which may not be meaningful/correct for
    /// non-integer or composite primary keys.
    pub without_rowid: bool,
+   /// Column names, ordered by column index. Empty if the names are unknown.
+   /// Shared with every `TableChange` for the table.
+   pub columns: Arc<[String]>,
 }
 
 impl TableInfo {
@@ -24,11 +31,27 @@ impl TableInfo {
       Self {
          pk_columns,
          without_rowid,
+         columns: Arc::default(),
       }
    }
+
+   /// Sets the table's column names, ordered by column index.
+   pub fn with_columns(mut self, columns: Vec<String>) -> Self {
+      self.columns = columns.into();
+      self
+   }
+
+   /// Index of the column named `name`, compared case-insensitively as SQLite does.
+   pub fn column_index(&self, name: &str) -> Option<usize> {
+      self
+         .columns
+         .iter()
+         .position(|column| column.eq_ignore_ascii_case(name))
+   }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ChangeOperation {
    Insert,
    Update,
@@ -40,13 +63,17 @@ pub enum ChangeOperation {
 /// Represents a single column's value with its native SQLite type.
 /// This replaces the previous JSON string representation for better
 /// type safety and performance.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Serializes as `{"type": "integer", "value": 42}`, with blobs as base64
+/// strings and `null` as `{"type": "null"}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
 pub enum ColumnValue {
    Null,
    Integer(i64),
    Real(f64),
    Text(String),
-   Blob(Vec<u8>),
+   Blob(#[serde(serialize_with = "serialize_base64")] Vec<u8>),
 }
 
 impl From<SqliteValue> for ColumnValue {
@@ -61,6 +88,36 @@ impl From<SqliteValue> for ColumnValue {
    }
 }
 
+impl From<i64> for ColumnValue {
+   fn from(value: i64) -> Self {
+      ColumnValue::Integer(value)
+   }
+}
+
+impl From<f64> for ColumnValue {
+   fn from(value: f64) -> Self {
+      ColumnValue::Real(value)
+   }
+}
+
+impl From<String> for ColumnValue {
+   fn from(value: String) -> Self {
+      ColumnValue::Text(value)
+   }
+}
+
+impl From<&str> for ColumnValue {
+   fn from(value: &str) -> Self {
+      ColumnValue::Text(value.to_string())
+   }
+}
+
+impl From<Vec<u8>> for ColumnValue {
+   fn from(value: Vec<u8>) -> Self {
+      ColumnValue::Blob(value)
+   }
+}
+
 impl ColumnValue {
    /// Returns true if this value is null.
    pub fn is_null(&self) -> bool {
@@ -106,6 +163,9 @@ impl ColumnValue {
 /// A `Lagged` event indicates the consumer fell behind and missed some
 /// notifications — consider increasing
 /// [`channel_capacity`](crate::config::ObserverConfig::channel_capacity).
+// Changes are far more common than lag, so boxing them to shrink `Lagged`
+// would cost an allocation per change for nothing
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum TableChangeEvent {
    /// A table change notification.
@@ -129,8 +189,17 @@ pub enum TableChangeEvent {
 /// Contains the table name, operation type, affected rowid, and the
 /// old/new column values (when available). Changes are only sent after
 /// the transaction commits successfully.
-#[derive(Debug, Clone)]
+///
+/// Serializes with camelCase field names. `timestamp` is skipped since an
+/// `Instant` only means something within this process; `committed_at` is
+/// serialized instead, as milliseconds since the Unix epoch.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct TableChange {
+   /// The database the change was made in: the observer's
+   /// `database_name` if set, otherwise the path of the database file, or
+   /// `":memory:"` for an in-memory database.
+   pub database: String,
    pub table: String,
    pub operation: Option<ChangeOperation>,
    /// The SQLite internal rowid. This is `None` for WITHOUT ROWID tables
@@ -143,9 +212,238 @@ pub struct TableChange {
    pub primary_key: Vec<ColumnValue>,
    /// Column values before the change (for UPDATE and DELETE).
    /// Values are ordered by column index as defined in the table schema.
+   #[serde(skip_serializing_if = "Option::is_none")]
    pub old_values: Option<Vec<ColumnValue>>,
    /// Column values after the change (for INSERT and UPDATE).
    /// Values are ordered by column index as defined in the table schema.
+   #[serde(skip_serializing_if = "Option::is_none")]
    pub new_values: Option<Vec<ColumnValue>>,
+   /// Names of the columns in `old_values`/`new_values`, in the same order.
+   /// Empty when values aren't captured or the table's schema info isn't
+   /// known yet.
+   #[serde(skip_serializing_if = "is_empty")]
+   pub columns: Arc<[String]>,
+   /// When the change was published, for measuring elapsed time.
+   #[serde(skip)]
    pub timestamp: Instant,
+   /// Wall-clock time the change was published, for logging and IPC.
+   #[serde(serialize_with = "serialize_unix_millis")]
+   pub committed_at: SystemTime,
+   /// Position of the commit that made the change among those seen by this
+   /// observer, starting at 1 and matching
+   /// `TransactionChangeSet::commit_seq`. Changes sharing it were made by the
+   /// same transaction. 0 for changes replayed from the changelog.
+   pub commit_seq: u64,
+   /// Position of the change within its commit, starting at 0, in the order
+   /// the changes were made.
+   pub commit_index: usize,
+   /// Sequence number of the change in the durable changelog, when the
+   /// changelog is enabled. Replayed changes carry it too.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub changelog_seq: Option<i64>,
+}
+
+impl TableChange {
+   /// Value of the named column before the change, if values were captured.
+   ///
+   /// Names are compared case-insensitively, as SQLite does.
+   pub fn old_value(&self, column: &str) -> Option<&ColumnValue> {
+      self.old_values.as_ref()?.get(self.column_index(column)?)
+   }
+
+   /// Value of the named column after the change, if values were captured.
+   ///
+   /// Names are compared case-insensitively, as SQLite does.
+   pub fn new_value(&self, column: &str) -> Option<&ColumnValue> {
+      self.new_values.as_ref()?.get(self.column_index(column)?)
+   }
+
+   /// Column values before the change, paired with their column names.
+   pub fn named_old_values(&self) -> Option<Vec<(&str, &ColumnValue)>> {
+      self.named(self.old_values.as_deref()?)
+   }
+
+   /// Column values after the change, paired with their column names.
+   pub fn named_new_values(&self) -> Option<Vec<(&str, &ColumnValue)>> {
+      self.named(self.new_values.as_deref()?)
+   }
+
+   fn column_index(&self, column: &str) -> Option<usize> {
+      self
+         .columns
+         .iter()
+         .position(|name| name.eq_ignore_ascii_case(column))
+   }
+
+   fn named<'a>(&'a self, values: &'a [ColumnValue]) -> Option<Vec<(&'a str, &'a ColumnValue)>> {
+      if self.columns.len() != values.len() {
+         return None;
+      }
+      Some(
+         self
+            .columns
+            .iter()
+            .map(String::as_str)
+            .zip(values)
+            .collect(),
+      )
+   }
+}
+
+/// All changes committed by one transaction, delivered together.
+///
+/// Changes are in the order they were made. Subscribe with
+/// `subscribe_transactions` to apply each transaction's changes atomically
+/// instead of one notification at a time.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionChangeSet {
+   /// Changes made by the transaction to observed tables.
+   pub changes: Vec<TableChange>,
+   /// Position of the transaction among the commits seen by this observer,
+   /// starting at 1. Only commits that changed observed tables are counted.
+   pub commit_seq: u64,
+}
+
+/// How many rows one commit inserted, updated, and deleted in each table.
+///
+/// Delivered by `subscribe_summaries` for UIs that only need to know which
+/// lists to refresh. Serializes as `{"commitSeq": 1, "tables": {"users":
+/// {"insert": 3, "update": 1}}}`, leaving out zero counts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSummary {
+   /// Position of the commit among those seen by this observer, matching
+   /// `TransactionChangeSet::commit_seq`.
+   pub commit_seq: u64,
+   /// Counts per observed table the commit changed.
+   pub tables: BTreeMap<String, OperationCounts>,
+}
+
+/// Number of rows changed by each kind of operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct OperationCounts {
+   #[serde(skip_serializing_if = "is_zero")]
+   pub insert: usize,
+   #[serde(skip_serializing_if = "is_zero")]
+   pub update: usize,
+   #[serde(skip_serializing_if = "is_zero")]
+   pub delete: usize,
+}
+
+impl OperationCounts {
+   /// Total number of rows changed.
+   pub fn total(&self) -> usize {
+      self.insert + self.update + self.delete
+   }
+
+   pub(crate) fn add(&mut self, operation: ChangeOperation) {
+      match operation {
+         ChangeOperation::Insert => self.insert += 1,
+         ChangeOperation::Update => self.update += 1,
+         ChangeOperation::Delete => self.delete += 1,
+      }
+   }
+}
+
+/// Changes to a debounced table, merged over its debounce window.
+///
+/// Delivered by `subscribe_debounced` in place of the individual changes of
+/// every commit made to the table during the window.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DebouncedChange {
+   pub table: String,
+   /// Keys of the affected rows, each listed once in no particular order.
+   /// A key holds the row's primary key value(s), or its rowid if the table
+   /// has no declared primary key.
+   pub primary_keys: Vec<Vec<ColumnValue>>,
+   /// Number of individual changes merged into this notification.
+   pub change_count: usize,
+   /// Number of commits merged into this notification.
+   pub commit_count: usize,
+}
+
+/// Changes committed while the observer was paused that weren't published.
+///
+/// Emitted by `subscribe_resyncs` on `resume` when the pause discarded
+/// changes, either because it didn't buffer them or because there were more
+/// than it could buffer. Subscribers should re-query the listed tables.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResyncEvent {
+   /// Tables changed during the pause, sorted by name.
+   pub tables: Vec<String>,
+   /// Number of changes committed during the pause.
+   pub change_count: usize,
+   /// Number of commits made during the pause.
+   pub commit_count: usize,
+}
+
+/// A write to the database that the observer's hooks may not have seen.
+///
+/// Emitted by `subscribe_external` when `PRAGMA data_version` advances, which
+/// happens after a commit from any other connection or process. It doesn't
+/// say what changed, so subscribers should re-query whatever they display.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExternalChange {
+   /// Number of commits this observer published since the previous poll.
+   ///
+   /// Commits through observed connections advance `data_version` too, so
+   /// a nonzero count means the change may have been one of them rather than
+   /// an external write.
+   pub observed_commits: u64,
+}
+
+/// A change read back from the durable changelog.
+///
+/// Logged by triggers inside the writing transaction when
+/// [`ObserverConfig::with_changelog`](crate::ObserverConfig::with_changelog)
+/// is enabled, so it survives even if nobody was subscribed when it committed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangelogEntry {
+   /// Position in the changelog, increasing with every logged change.
+   pub seq: i64,
+   pub table: String,
+   pub operation: ChangeOperation,
+   /// The primary key value(s), or the rowid if the table has no declared
+   /// primary key.
+   pub primary_key: Vec<ColumnValue>,
+   /// Column values before the change (for UPDATE and DELETE), ordered by
+   /// column index as of when the change was made.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub old_values: Option<Vec<ColumnValue>>,
+   /// Column values after the change (for INSERT and UPDATE).
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub new_values: Option<Vec<ColumnValue>>,
+   /// Wall-clock time the change was made, to millisecond precision.
+   #[serde(serialize_with = "serialize_unix_millis")]
+   pub changed_at: SystemTime,
+}
+
+fn serialize_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+   use base64::Engine;
+   serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+fn serialize_unix_millis<S: Serializer>(
+   time: &SystemTime,
+   serializer: S,
+) -> Result<S::Ok, S::Error> {
+   // Times before the epoch can only come from a badly set clock
+   let millis = time
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_millis() as u64)
+      .unwrap_or(0);
+   serializer.serialize_u64(millis)
+}
+
+fn is_empty(columns: &Arc<[String]>) -> bool {
+   columns.is_empty()
+}
+
+fn is_zero(count: &usize) -> bool {
+   *count == 0
 }
@@ -0,0 +1,349 @@
+//! Durable changelog of committed changes.
+//!
+//! When enabled, every observed table gets `AFTER INSERT/UPDATE/DELETE`
+//! triggers that record each change in the [`CHANGELOG_TABLE`]. The triggers
+//! run inside the writing transaction, so the log holds exactly the committed
+//! changes, even ones made while no subscriber was listening or on connections
+//! without observation hooks.
+//!
+//! Values are stored as JSON: numbers and strings as themselves, NULL as
+//! `null`, and blobs as `{"blob": "<hex>"}`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use sqlx::{Connection, Pool, Row, Sqlite, SqliteConnection, SqliteExecutor};
+use tracing::debug;
+
+use crate::broker::ObservationBroker;
+use crate::change::{ChangeOperation, ChangelogEntry, ColumnValue, TableChange, TableInfo};
+use crate::error::Error;
+use crate::hooks;
+
+/// Name of the table changes are logged to.
+pub const CHANGELOG_TABLE: &str = "_changes";
+
+const CREATE_CHANGELOG: &str = r#"
+   CREATE TABLE IF NOT EXISTS "_changes" (
+      seq INTEGER PRIMARY KEY AUTOINCREMENT,
+      table_name TEXT NOT NULL,
+      op TEXT NOT NULL,
+      pk TEXT NOT NULL,
+      payload TEXT NOT NULL,
+      changed_at INTEGER NOT NULL
+         DEFAULT (CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER))
+   )
+"#;
+
+/// Creates the changelog table if needed and (re)creates the triggers that
+/// log changes to `table`, matching its current layout.
+pub(crate) async fn install_triggers(
+   conn: &mut SqliteConnection,
+   table: &str,
+   info: &TableInfo,
+) -> crate::Result<()> {
+   if info.columns.is_empty() {
+      return Ok(());
+   }
+
+   // IMMEDIATE so connections installing triggers at the same time wait for
+   // each other instead of failing to upgrade to a write lock
+   let mut tx = conn.begin_with("BEGIN IMMEDIATE").await?;
+   sqlx::query(CREATE_CHANGELOG).execute(&mut *tx).await?;
+   for statement in trigger_statements(table, info) {
+      sqlx::query(&statement).execute(&mut *tx).await?;
+   }
+   tx.commit().await?;
+
+   debug!(table = %table, "Installed changelog triggers");
+   Ok(())
+}
+
+/// Reads up to `limit` logged changes with a sequence number above `after`,
+/// oldest first.
+pub(crate) async fn read_entries<'e>(
+   executor: impl SqliteExecutor<'e>,
+   after: i64,
+   limit: u32,
+) -> crate::Result<Vec<ChangelogEntry>> {
+   let rows = sqlx::query(
+      r#"SELECT seq, table_name, op, pk, payload, changed_at FROM "_changes"
+         WHERE seq > ?1 ORDER BY seq LIMIT ?2"#,
+   )
+   .bind(after)
+   .bind(limit)
+   .fetch_all(executor)
+   .await?;
+
+   rows
+      .iter()
+      .map(|row| {
+         let op: String = row.get("op");
+         let operation = match op.as_str() {
+            "insert" => ChangeOperation::Insert,
+            "update" => ChangeOperation::Update,
+            "delete" => ChangeOperation::Delete,
+            other => {
+               return Err(Error::Database(format!(
+                  "unknown changelog operation '{}'",
+                  other
+               )));
+            }
+         };
+         let pk: String = row.get("pk");
+         let payload: String = row.get("payload");
+         let payload: serde_json::Value = serde_json::from_str(&payload)
+            .map_err(|e| Error::Database(format!("invalid changelog payload: {}", e)))?;
+         let changed_at: i64 = row.get("changed_at");
+
+         Ok(ChangelogEntry {
+            seq: row.get("seq"),
+            table: row.get("table_name"),
+            operation,
+            primary_key: decode_values(
+               &serde_json::from_str(&pk)
+                  .map_err(|e| Error::Database(format!("invalid changelog primary key: {}", e)))?,
+            )?,
+            old_values: payload.get("old").map(decode_values).transpose()?,
+            new_values: payload.get("new").map(decode_values).transpose()?,
+            changed_at: UNIX_EPOCH + Duration::from_millis(changed_at.max(0) as u64),
+         })
+      })
+      .collect()
+}
+
+/// Reads every logged change after `after` to one of `tables` (or to any table
+/// if `tables` is empty) as `TableChange`s, oldest first.
+pub(crate) async fn replay(
+   pool: &Pool<Sqlite>,
+   broker: &ObservationBroker,
+   tables: &[String],
+   after: i64,
+) -> crate::Result<Vec<TableChange>> {
+   const PAGE_SIZE: u32 = 1000;
+
+   let database = match broker.database_name() {
+      Some(name) => name,
+      None => {
+         let file: Option<String> =
+            sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+               .fetch_one(pool)
+               .await?;
+         hooks::database_display_name(file.as_deref().unwrap_or_default())
+      }
+   };
+
+   let mut changes = Vec::new();
+   let mut last_seq = after;
+   loop {
+      let entries = read_entries(pool, last_seq, PAGE_SIZE).await?;
+      let Some(last) = entries.last() else {
+         break;
+      };
+      last_seq = last.seq;
+      let full_page = entries.len() == PAGE_SIZE as usize;
+
+      changes.extend(
+         entries
+            .into_iter()
+            .filter(|entry| tables.is_empty() || tables.contains(&entry.table))
+            .map(|entry| {
+               let table_info = broker.get_table_info(&entry.table);
+               entry_to_change(entry, &database, table_info.as_ref())
+            }),
+      );
+      if !full_page {
+         break;
+      }
+   }
+
+   debug!(after, count = changes.len(), "Replayed changelog");
+   Ok(changes)
+}
+
+/// Converts a logged change to the `TableChange` it was published as.
+///
+/// Column names are only filled in if the table's current layout has as many
+/// columns as were logged.
+fn entry_to_change(
+   entry: ChangelogEntry,
+   database: &str,
+   table_info: Option<&TableInfo>,
+) -> TableChange {
+   let logged_columns = entry
+      .new_values
+      .as_ref()
+      .or(entry.old_values.as_ref())
+      .map_or(0, Vec::len);
+   let columns = table_info
+      .filter(|info| info.columns.len() == logged_columns)
+      .map(|info| Arc::clone(&info.columns))
+      .unwrap_or_default();
+
+   // Without a declared primary key, the logged key is the rowid
+   let rowid = match (table_info, entry.primary_key.as_slice()) {
+      (Some(info), [ColumnValue::Integer(rowid)]) if info.pk_columns.is_empty() => Some(*rowid),
+      _ => None,
+   };
+
+   TableChange {
+      database: database.to_string(),
+      table: entry.table,
+      operation: Some(entry.operation),
+      rowid,
+      primary_key: entry.primary_key,
+      old_values: entry.old_values,
+      new_values: entry.new_values,
+      columns,
+      timestamp: Instant::now(),
+      committed_at: entry.changed_at,
+      commit_seq: 0,
+      commit_index: 0,
+      changelog_seq: Some(entry.seq),
+   }
+}
+
+/// Deletes logged changes with a sequence number up to and including
+/// `through`, returning how many were deleted.
+pub(crate) async fn prune_entries<'e>(
+   executor: impl SqliteExecutor<'e>,
+   through: i64,
+) -> crate::Result<u64> {
+   let result = sqlx::query(r#"DELETE FROM "_changes" WHERE seq <= ?1"#)
+      .bind(through)
+      .execute(executor)
+      .await?;
+   Ok(result.rows_affected())
+}
+
+/// Statements replacing the changelog triggers of `table`
+fn trigger_statements(table: &str, info: &TableInfo) -> Vec<String> {
+   let quoted = quote_identifier(table);
+   let literal = table.replace('\'', "''");
+   let key = |row: &str| {
+      if info.pk_columns.is_empty() {
+         format!("json_array({})", json_value(&format!("{}.rowid", row)))
+      } else {
+         let values: Vec<String> = info
+            .pk_columns
+            .iter()
+            .map(|&idx| json_value(&column_ref(row, &info.columns[idx])))
+            .collect();
+         format!("json_array({})", values.join(", "))
+      }
+   };
+   let values = |row: &str| {
+      let values: Vec<String> = info
+         .columns
+         .iter()
+         .map(|column| json_value(&column_ref(row, column)))
+         .collect();
+      format!("json_array({})", values.join(", "))
+   };
+
+   let mut statements = Vec::with_capacity(6);
+   for (op, event, pk, payload) in [
+      (
+         "insert",
+         "INSERT",
+         key("NEW"),
+         format!("json_object('new', {})", values("NEW")),
+      ),
+      (
+         "update",
+         "UPDATE",
+         key("NEW"),
+         format!(
+            "json_object('old', {}, 'new', {})",
+            values("OLD"),
+            values("NEW")
+         ),
+      ),
+      (
+         "delete",
+         "DELETE",
+         key("OLD"),
+         format!("json_object('old', {})", values("OLD")),
+      ),
+   ] {
+      let trigger = quote_identifier(&format!("{}_{}_{}", CHANGELOG_TABLE, table, op));
+      statements.push(format!("DROP TRIGGER IF EXISTS {}", trigger));
+      statements.push(format!(
+         "CREATE TRIGGER {trigger} AFTER {event} ON {quoted} BEGIN \
+          INSERT INTO \"_changes\" (table_name, op, pk, payload) \
+          VALUES ('{literal}', '{op}', {pk}, {payload}); END"
+      ));
+   }
+   statements
+}
+
+/// JSON encoding of a column value, with blobs as `{"blob": "<hex>"}`
+pub(crate) fn json_value(expr: &str) -> String {
+   format!(
+      "json(CASE typeof({expr}) WHEN 'blob' THEN json_object('blob', hex({expr})) \
+       ELSE json_quote({expr}) END)"
+   )
+}
+
+fn column_ref(row: &str, column: &str) -> String {
+   format!("{}.{}", row, quote_identifier(column))
+}
+
+pub(crate) fn quote_identifier(name: &str) -> String {
+   format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub(crate) fn decode_values(json: &serde_json::Value) -> crate::Result<Vec<ColumnValue>> {
+   let invalid = || Error::Database(format!("invalid changelog values: {}", json));
+   json
+      .as_array()
+      .ok_or_else(invalid)?
+      .iter()
+      .map(|value| match value {
+         serde_json::Value::Null => Ok(ColumnValue::Null),
+         serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(ColumnValue::Integer(i)),
+            None => n.as_f64().map(ColumnValue::Real).ok_or_else(invalid),
+         },
+         serde_json::Value::String(s) => Ok(ColumnValue::Text(s.clone())),
+         serde_json::Value::Object(obj) => obj
+            .get("blob")
+            .and_then(serde_json::Value::as_str)
+            .and_then(decode_hex)
+            .map(ColumnValue::Blob)
+            .ok_or_else(invalid),
+         _ => Err(invalid()),
+      })
+      .collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+   if hex.len() % 2 != 0 {
+      return None;
+   }
+   (0..hex.len())
+      .step_by(2)
+      .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+      .collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn decodes_logged_values() {
+      let json = serde_json::json!([null, 1, 1.5, "a", {"blob": "00ff"}]);
+      assert_eq!(
+         decode_values(&json).unwrap(),
+         vec![
+            ColumnValue::Null,
+            ColumnValue::Integer(1),
+            ColumnValue::Real(1.5),
+            ColumnValue::Text("a".into()),
+            ColumnValue::Blob(vec![0x00, 0xff]),
+         ]
+      );
+      assert!(decode_values(&serde_json::json!([{"blob": "0"}])).is_err());
+   }
+}
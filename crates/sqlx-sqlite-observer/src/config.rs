@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
 /// Configuration for the SQLite observer.
 ///
@@ -10,6 +11,17 @@ pub struct ObserverConfig {
    /// Tables to observe for changes.
    pub tables: HashSet<String>,
 
+   /// Whether to observe every table in the database.
+   ///
+   /// When `true`, changes to every table except SQLite's internal `sqlite_*`
+   /// tables are observed, in addition to [`tables`](Self::tables). Tables are
+   /// discovered from `sqlite_master` and rediscovered when a connection is
+   /// acquired after the schema has changed, so tables created later are picked
+   /// up too.
+   ///
+   /// Default: `false`.
+   pub observe_all: bool,
+
    /// Capacity of the broadcast channel for change notifications.
    ///
    /// **Important:** All changes in a transaction are delivered at once on commit.
@@ -56,14 +68,78 @@ pub struct ObserverConfig {
    ///
    /// [`TableChange`]: crate::TableChange
    pub capture_values: bool,
+
+   /// Whether to look up primary keys by rowid instead of reading column
+   /// values in the hook.
+   ///
+   /// Only takes effect while [`capture_values`](Self::capture_values) is
+   /// disabled. Column values are then not read at all for tables with a
+   /// rowid, and before a commit is published, the primary key of each
+   /// inserted or updated row is read with `WHERE rowid = ?` on a pooled
+   /// (read) connection. A deleted row's key is still read in the hook, as
+   /// the row is gone by then. Keys are read once the commit has landed (a
+   /// commit that rolls back after the commit hook is never published), so a
+   /// row changed again in the meantime gets its later key, and one deleted
+   /// in the meantime an empty `primary_key`. Commits are published in order,
+   /// after their lookups finish. `WITHOUT ROWID` tables are unaffected.
+   ///
+   /// Default: `false`.
+   pub resolve_primary_keys: bool,
+
+   /// Debounce windows for noisy tables.
+   ///
+   /// Changes to these tables are also merged per table and delivered to
+   /// `subscribe_debounced` subscribers as one
+   /// [`DebouncedChange`](crate::DebouncedChange) per window, which protects
+   /// UIs from refresh storms during bulk imports. Individual changes are
+   /// still delivered to other subscribers.
+   ///
+   /// Default: empty.
+   pub debounce: HashMap<String, Duration>,
+
+   /// How often `PRAGMA data_version` is polled to detect writes made outside
+   /// the observer, such as by other processes.
+   ///
+   /// Polling only runs while someone is subscribed to
+   /// [`ExternalChange`](crate::ExternalChange)s, and holds one pooled
+   /// connection while it does.
+   ///
+   /// Default: 1 second.
+   pub external_poll_interval: Duration,
+
+   /// Whether to also log every committed change to observed tables to the
+   /// [`CHANGELOG_TABLE`](crate::changelog::CHANGELOG_TABLE).
+   ///
+   /// Changes are written by triggers inside the same transaction, so they
+   /// are kept even when no subscriber is connected and can be read back
+   /// later. The triggers are installed when a connection is acquired.
+   ///
+   /// Default: `false`.
+   pub changelog: bool,
+
+   /// Name identifying the database in [`TableChange::database`].
+   ///
+   /// When `None`, changes carry the path of the database file their
+   /// connection has open, or `":memory:"` for in-memory databases.
+   ///
+   /// Default: `None`.
+   ///
+   /// [`TableChange::database`]: crate::TableChange::database
+   pub database_name: Option<String>,
 }
 
 impl Default for ObserverConfig {
    fn default() -> Self {
       Self {
          tables: HashSet::new(),
+         observe_all: false,
          channel_capacity: 256,
          capture_values: true,
+         resolve_primary_keys: false,
+         debounce: HashMap::new(),
+         external_poll_interval: Duration::from_secs(1),
+         changelog: false,
+         database_name: None,
       }
    }
 }
@@ -88,6 +164,15 @@ impl ObserverConfig {
       self
    }
 
+   /// Observes every non-internal table in the database.
+   ///
+   /// Useful for apps with dynamic schemas that can't enumerate their tables up
+   /// front. See [`observe_all`](Self::observe_all).
+   pub fn all_tables(mut self) -> Self {
+      self.observe_all = true;
+      self
+   }
+
    /// Sets the broadcast channel capacity for change notifications.
    ///
    /// Capacity must be at least 1. A capacity of 0 will cause a panic when the
@@ -108,4 +193,49 @@ impl ObserverConfig {
       self.capture_values = capture;
       self
    }
+
+   /// Controls whether primary keys are looked up by rowid instead of being
+   /// read from captured column values.
+   ///
+   /// Use with `with_capture_values(false)` for cheap hooks that still
+   /// deliver usable keys. See [`resolve_primary_keys`](Self::resolve_primary_keys).
+   pub fn with_resolve_primary_keys(mut self, resolve: bool) -> Self {
+      self.resolve_primary_keys = resolve;
+      self
+   }
+
+   /// Debounces notifications for a table.
+   ///
+   /// Commits to `table` made within `window` of the first one are merged
+   /// into a single notification carrying the set of affected primary keys.
+   /// See [`debounce`](Self::debounce).
+   pub fn with_debounce(mut self, table: impl Into<String>, window: Duration) -> Self {
+      self.debounce.insert(table.into(), window);
+      self
+   }
+
+   /// Sets how often writes made outside the observer are polled for.
+   ///
+   /// See [`external_poll_interval`](Self::external_poll_interval).
+   pub fn with_external_poll_interval(mut self, interval: Duration) -> Self {
+      self.external_poll_interval = interval;
+      self
+   }
+
+   /// Controls whether committed changes are also logged to a durable
+   /// changelog table.
+   ///
+   /// See [`changelog`](Self::changelog).
+   pub fn with_changelog(mut self, enabled: bool) -> Self {
+      self.changelog = enabled;
+      self
+   }
+
+   /// Sets the logical name changes identify the database by.
+   ///
+   /// See [`database_name`](Self::database_name).
+   pub fn with_database_name(mut self, name: impl Into<String>) -> Self {
+      self.database_name = Some(name.into());
+      self
+   }
 }
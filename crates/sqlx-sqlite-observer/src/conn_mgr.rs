@@ -43,21 +43,28 @@
 
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::Duration;
 
 use libsqlite3_sys::sqlite3;
 use sqlx::sqlite::SqliteConnection;
 use sqlx::{Pool, Sqlite};
-use sqlx_sqlite_conn_mgr::{SqliteDatabase, WriteGuard};
+use sqlx_sqlite_conn_mgr::{InterruptHandle, SqliteDatabase, WriteGuard};
 use tokio::sync::broadcast;
-use tracing::{debug, trace, warn};
+use tracing::{debug, trace};
 
 use crate::Result;
 use crate::broker::ObservationBroker;
-use crate::change::TableChange;
+use crate::change::{
+   ChangelogEntry, ColumnValue, CommitSummary, DebouncedChange, ExternalChange, ResyncEvent,
+   TableChange, TransactionChangeSet,
+};
+use crate::changelog;
 use crate::config::ObserverConfig;
+use crate::external::ExternalPoller;
 use crate::hooks;
-use crate::schema::query_table_info;
-use crate::stream::TableChangeStream;
+use crate::schema::refresh_table_info;
+use crate::stream::{ReplayStream, TableChangeStream};
+use crate::subscription::{self, ChangeReceiver, SubscriptionHandle, SubscriptionOptions};
 
 /// Wrapper around `SqliteDatabase` that provides change observation.
 ///
@@ -67,6 +74,7 @@ use crate::stream::TableChangeStream;
 pub struct ObservableSqliteDatabase {
    db: Arc<SqliteDatabase>,
    broker: Arc<ObservationBroker>,
+   external: Arc<ExternalPoller>,
 }
 
 impl ObservableSqliteDatabase {
@@ -82,8 +90,33 @@ impl ObservableSqliteDatabase {
       if !config.tables.is_empty() {
          broker.observe_tables(config.tables.iter().map(String::as_str));
       }
+      if config.observe_all {
+         broker.observe_all_tables();
+      }
+      if config.changelog {
+         broker.enable_changelog();
+      }
+      if config.resolve_primary_keys {
+         broker.enable_key_resolution();
+      }
+      if let Some(name) = &config.database_name {
+         broker.set_database_name(name);
+      }
+      for (table, window) in &config.debounce {
+         broker.set_debounce(table, *window);
+      }
 
-      Self { db, broker }
+      let external = ExternalPoller::new(
+         config.external_poll_interval,
+         Arc::clone(&broker),
+         config.channel_capacity,
+      );
+
+      Self {
+         db,
+         broker,
+         external,
+      }
    }
 
    /// Subscribe to change notifications.
@@ -104,6 +137,218 @@ impl ObservableSqliteDatabase {
       self.broker.subscribe()
    }
 
+   /// Subscribe to changes through a channel with its own capacity and lag
+   /// policy.
+   ///
+   /// Unlike `subscribe`, whose receivers share one channel sized by
+   /// `channel_capacity` and always drop their oldest changes when behind,
+   /// this subscriber's channel is sized and handles overflow as `options`
+   /// says, so a critical consumer isn't affected by slow ones.
+   pub fn subscribe_with<I, S>(&self, tables: I, options: SubscriptionOptions) -> ChangeReceiver
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_with(tables, options)
+   }
+
+   /// Subscribe to changes with a callback instead of a receiver.
+   ///
+   /// Spawns a task that calls `callback` with every change to `tables` (or
+   /// to every observed table if empty) until the returned handle is
+   /// cancelled. The callback runs on the task, so it should not block. Must
+   /// be called within a Tokio runtime.
+   pub fn on_change<I, S, F>(&self, tables: I, callback: F) -> SubscriptionHandle
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+      F: FnMut(TableChange) + Send + 'static,
+   {
+      SubscriptionHandle::spawn(self.subscribe_stream(tables), callback)
+   }
+
+   /// Wait for the first change to `table` matching `predicate`.
+   ///
+   /// Subscribes before returning, so a change made after this call but
+   /// before the future is awaited is still seen: start waiting, make the
+   /// change, then await. Fails with `Error::Timeout` if no matching change
+   /// arrives within `timeout`. Must be awaited within a Tokio runtime.
+   pub fn wait_for_change<P>(
+      &self,
+      table: impl Into<String>,
+      predicate: P,
+      timeout: Duration,
+   ) -> impl Future<Output = Result<TableChange>> + Send + 'static
+   where
+      P: FnMut(&TableChange) -> bool + Send + 'static,
+   {
+      let table = table.into();
+      let stream = self.subscribe_stream([table.clone()]);
+      subscription::wait_for_change(stream, table, predicate, timeout)
+   }
+
+   /// Subscribe to per-commit counts of the rows changed in each table.
+   ///
+   /// The receiver gets one `CommitSummary` per commit instead of the changes
+   /// themselves, which is all a "refresh this list" UI needs. When nothing
+   /// else is subscribed, individual changes and their column values aren't
+   /// even built.
+   pub fn subscribe_summaries<I, S>(&self, tables: I) -> broadcast::Receiver<CommitSummary>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_summaries()
+   }
+
+   /// Pause publishing changes, for bulk operations such as imports or
+   /// migrations that would otherwise send a notification per row.
+   ///
+   /// Changes committed during the pause are discarded, and `resume` sends
+   /// subscribers of `subscribe_resyncs` one `ResyncEvent` naming the tables
+   /// that changed. Pausing while already paused has no effect.
+   pub fn pause(&self) {
+      self.broker.pause(0);
+   }
+
+   /// Pause publishing changes, keeping up to `buffer_limit` of those
+   /// committed meanwhile.
+   ///
+   /// If no more than `buffer_limit` changes are committed during the pause,
+   /// `resume` publishes them as usual. Otherwise they are all discarded and
+   /// `resume` sends one `ResyncEvent` instead.
+   pub fn pause_with_buffer(&self, buffer_limit: usize) {
+      self.broker.pause(buffer_limit);
+   }
+
+   /// Resume publishing changes after `pause`.
+   pub fn resume(&self) {
+      self.broker.resume();
+   }
+
+   /// Whether publishing is paused.
+   pub fn is_paused(&self) -> bool {
+      self.broker.is_paused()
+   }
+
+   /// Subscribe to the `ResyncEvent`s sent on `resume` when a pause
+   /// discarded changes.
+   pub fn subscribe_resyncs(&self) -> broadcast::Receiver<ResyncEvent> {
+      self.broker.subscribe_resyncs()
+   }
+
+   /// Subscribe to the changes of whole transactions.
+   ///
+   /// The receiver gets a single `TransactionChangeSet` per commit with all
+   /// changes the transaction made to observed tables.
+   pub fn subscribe_transactions<I, S>(
+      &self,
+      tables: I,
+   ) -> broadcast::Receiver<TransactionChangeSet>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_transactions()
+   }
+
+   /// Subscribe to merged notifications for debounced tables.
+   ///
+   /// Commits to each table configured with
+   /// [`ObserverConfig::with_debounce`] are merged over the table's window and
+   /// delivered as one `DebouncedChange`. Must be called within a Tokio runtime.
+   pub fn subscribe_debounced<I, S>(&self, tables: I) -> broadcast::Receiver<DebouncedChange>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_debounced()
+   }
+
+   /// Subscribe to changes of specific rows of a table.
+   ///
+   /// Each key holds a row's primary key value(s) in declaration order (or its
+   /// rowid if the table has no declared primary key). Changes to other rows
+   /// are filtered out before they are sent.
+   pub fn subscribe_rows<I, K>(
+      &self,
+      table: impl Into<String>,
+      keys: I,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      I: IntoIterator<Item = K>,
+      K: IntoIterator<Item = ColumnValue>,
+   {
+      let table = table.into();
+      let keys = keys
+         .into_iter()
+         .map(|key| key.into_iter().collect())
+         .collect();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_rows(&table, keys)
+   }
+
+   /// Subscribe to the changes of a table that satisfy `predicate`.
+   ///
+   /// The predicate is evaluated for every committed change to the table
+   /// before it is sent. It runs inside SQLite's commit hook, so it should be
+   /// cheap and must not use the database.
+   pub fn subscribe_where<F>(
+      &self,
+      table: impl Into<String>,
+      predicate: F,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      F: Fn(&TableChange) -> bool + Send + Sync + 'static,
+   {
+      let table = table.into();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_where(&table, Arc::new(predicate))
+   }
+
+   /// Subscribe to the changes of a table that can affect the given columns.
+   ///
+   /// Inserts and deletes are always delivered; an UPDATE is only delivered if
+   /// it actually changed one of `columns`.
+   pub fn subscribe_columns<I, S>(
+      &self,
+      table: impl Into<String>,
+      columns: I,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let table = table.into();
+      let columns = columns.into_iter().map(Into::into).collect();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_columns(&table, columns)
+   }
+
    /// Subscribe and get a `Stream` for easier async iteration.
    pub fn subscribe_stream<I, S>(&self, tables: I) -> TableChangeStream
    where
@@ -127,12 +372,43 @@ impl ObservableSqliteDatabase {
       }
    }
 
+   /// Subscribe to changes as a Stream that first replays every change logged to
+   /// the durable changelog after sequence number `after_seq`, then continues
+   /// with live changes.
+   ///
+   /// Pass the `changelog_seq` of the last change processed before a restart
+   /// to pick up where it left off, or 0 to replay the whole log. The live
+   /// subscription starts before the log is read, so nothing committed in
+   /// between is missed, and changes delivered by both are only yielded once.
+   /// Requires [`ObserverConfig::with_changelog`].
+   pub async fn subscribe_from<I, S>(&self, tables: I, after_seq: i64) -> Result<ReplayStream>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      let live = self.subscribe_stream(tables.clone());
+      let pool = self.db.read_pool().map_err(crate::error::Error::ConnMgr)?;
+      let replayed = changelog::replay(pool, &self.broker, &tables, after_seq).await?;
+      Ok(ReplayStream::new(replayed, after_seq, live))
+   }
+
+   /// Subscribe to writes made outside the observable writer.
+   ///
+   /// Commits from other processes, or through `inner()` without the
+   /// observer, don't reach the hooks. While anyone is subscribed, a read
+   /// connection polls `PRAGMA data_version` every
+   /// [`ObserverConfig::external_poll_interval`] and an `ExternalChange` is
+   /// sent whenever another connection has committed, including the
+   /// observable writer. Must be called within a Tokio runtime.
+   pub fn subscribe_external(&self) -> Result<broadcast::Receiver<ExternalChange>> {
+      let pool = self.db.read_pool().map_err(crate::error::Error::ConnMgr)?;
+      Ok(self.external.subscribe(pool))
+   }
+
    /// Get a reference to the read-only connection pool.
    ///
    /// Read operations don't need observation since they don't modify data.
-   /// However, this pool is also used internally to query table schema
-   /// information (primary key columns, WITHOUT ROWID status) when tables
-   /// are first observed.
    pub fn read_pool(&self) -> sqlx_sqlite_conn_mgr::Result<&Pool<Sqlite>> {
       self.db.read_pool()
    }
@@ -142,9 +418,15 @@ impl ObservableSqliteDatabase {
    /// The returned `ObservableWriteGuard` has observation hooks registered.
    /// Changes are published to subscribers when transactions commit.
    ///
-   /// On first acquisition for each table, queries the schema to determine
-   /// primary key columns and WITHOUT ROWID status.
+   /// On first acquisition for each table, and again after the schema changes,
+   /// queries the schema on the writer to determine primary key columns and
+   /// WITHOUT ROWID status.
    pub async fn acquire_writer(&self) -> Result<ObservableWriteGuard> {
+      if self.broker.resolves_primary_keys() {
+         let pool = self.db.read_pool().map_err(crate::error::Error::ConnMgr)?;
+         self.broker.start_key_resolver(pool);
+      }
+
       let writer = self
          .db
          .acquire_writer()
@@ -157,50 +439,35 @@ impl ObservableSqliteDatabase {
          raw_db: None,
       };
 
-      // Query table info for any observed tables that don't have it yet
-      self.ensure_table_info().await?;
+      // Query table info for observed tables that don't have it yet, or for
+      // every observed table if the schema changed since it was loaded
+      refresh_table_info(&mut observable, &self.broker).await?;
 
       observable.register_hooks(Arc::clone(&self.broker)).await?;
       Ok(observable)
    }
 
-   /// Ensures TableInfo is set for all observed tables.
+   /// Read up to `limit` changes from the durable changelog with a sequence
+   /// number above `after`, oldest first.
    ///
-   /// Uses the read pool to query schema information, respecting conn-mgr's
-   /// requirement that all connections be acquired through it.
-   async fn ensure_table_info(&self) -> Result<()> {
-      let observed = self.broker.get_observed_tables();
-
-      // Collect tables that need schema info
-      let tables_to_query: Vec<String> = observed
-         .into_iter()
-         .filter(|table| self.broker.get_table_info(table).is_none())
-         .collect();
-
-      if tables_to_query.is_empty() {
-         return Ok(());
-      }
-
-      // Use read pool to query schema
+   /// Requires [`ObserverConfig::with_changelog`] and the writer to have been
+   /// acquired through this wrapper at least once, which creates the table.
+   pub async fn changelog_since(&self, after: i64, limit: u32) -> Result<Vec<ChangelogEntry>> {
       let pool = self.db.read_pool().map_err(crate::error::Error::ConnMgr)?;
-      let mut conn = pool.acquire().await.map_err(crate::error::Error::Sqlx)?;
-
-      for table in tables_to_query {
-         match query_table_info(&mut conn, &table).await {
-            Ok(Some(info)) => {
-               debug!(table = %table, pk_columns = ?info.pk_columns, without_rowid = info.without_rowid, "Queried table info");
-               self.broker.set_table_info(&table, info);
-            }
-            Ok(None) => {
-               warn!(table = %table, "Table not found in schema");
-            }
-            Err(e) => {
-               warn!(table = %table, error = %e, "Failed to query table info");
-            }
-         }
-      }
+      changelog::read_entries(pool, after, limit).await
+   }
 
-      Ok(())
+   /// Delete changes from the durable changelog up to and including sequence
+   /// number `through`, returning how many were deleted.
+   ///
+   /// Uses the writer, waiting for it if it's checked out.
+   pub async fn prune_changelog(&self, through: i64) -> Result<u64> {
+      let mut writer = self
+         .db
+         .acquire_writer()
+         .await
+         .map_err(crate::error::Error::ConnMgr)?;
+      changelog::prune_entries(&mut *writer, through).await
    }
 
    /// Get the underlying `SqliteDatabase`.
@@ -208,6 +475,25 @@ impl ObservableSqliteDatabase {
       &self.db
    }
 
+   /// Starts observing a table at runtime.
+   ///
+   /// Its schema info is queried on the next `acquire_writer`, so connections already
+   /// checked out only publish its changes with an empty `primary_key` and
+   /// `columns`.
+   pub fn observe_table(&self, table: impl AsRef<str>) {
+      self.broker.observe_tables([table.as_ref()]);
+   }
+
+   /// Stops observing a table at runtime.
+   ///
+   /// Row, predicate, and column subscriptions to the table are closed, and
+   /// changes to it are no longer published. Returns whether the table had
+   /// been added explicitly; with `all_tables()` every non-internal table
+   /// stays observed.
+   pub fn unobserve_table(&self, table: &str) -> bool {
+      self.broker.unobserve_table(table)
+   }
+
    /// Get the list of currently observed tables.
    pub fn observed_tables(&self) -> Vec<String> {
       self.broker.get_observed_tables()
@@ -224,6 +510,7 @@ impl Clone for ObservableSqliteDatabase {
       Self {
          db: Arc::clone(&self.db),
          broker: Arc::clone(&self.broker),
+         external: Arc::clone(&self.external),
       }
    }
 }
@@ -283,6 +570,15 @@ impl ObservableWriteGuard {
       Ok(())
    }
 
+   /// Get a handle that can interrupt this writer's statements from another task
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      self
+         .writer
+         .as_ref()
+         .expect("writer already taken")
+         .interrupt_handle()
+   }
+
    /// Consumes this wrapper and returns the underlying write guard.
    ///
    /// Hooks are unregistered before returning the guard, so it can be
@@ -0,0 +1,197 @@
+//! Coalescing rapid successive commits to a table into one notification.
+//!
+//! The broker hands each commit's changes to debounced tables to the
+//! [`Debouncer`], which merges them into a pending batch per table. A
+//! background task publishes each batch as a [`DebouncedChange`] once the
+//! table's debounce window has passed since the batch's first change.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{Notify, broadcast};
+use tracing::trace;
+
+use crate::change::{ColumnValue, DebouncedChange};
+
+/// Merges changes to debounced tables and publishes them in batches.
+pub(crate) struct Debouncer {
+   windows: RwLock<HashMap<String, Duration>>,
+   state: Mutex<DebounceState>,
+   notify: Notify,
+   tx: broadcast::Sender<DebouncedChange>,
+}
+
+#[derive(Default)]
+struct DebounceState {
+   pending: HashMap<String, Batch>,
+   /// Whether the task publishing batches is running
+   task_running: bool,
+}
+
+/// Changes to one table waiting to be published
+struct Batch {
+   deadline: Instant,
+   keys: HashSet<RowKey>,
+   change_count: usize,
+   commit_count: usize,
+}
+
+/// A row's key, hashable despite `ColumnValue::Real` holding an `f64`
+#[derive(Debug)]
+struct RowKey(Vec<ColumnValue>);
+
+impl PartialEq for RowKey {
+   fn eq(&self, other: &Self) -> bool {
+      self.0.len() == other.0.len()
+         && self.0.iter().zip(&other.0).all(|(a, b)| match (a, b) {
+            (ColumnValue::Real(a), ColumnValue::Real(b)) => a.to_bits() == b.to_bits(),
+            _ => a == b,
+         })
+   }
+}
+
+impl Eq for RowKey {}
+
+impl Hash for RowKey {
+   fn hash<H: Hasher>(&self, state: &mut H) {
+      for value in &self.0 {
+         match value {
+            ColumnValue::Null => 0u8.hash(state),
+            ColumnValue::Integer(i) => (1u8, i).hash(state),
+            ColumnValue::Real(r) => (2u8, r.to_bits()).hash(state),
+            ColumnValue::Text(s) => (3u8, s).hash(state),
+            ColumnValue::Blob(b) => (4u8, b).hash(state),
+         }
+      }
+   }
+}
+
+impl Debouncer {
+   pub(crate) fn new(channel_capacity: usize) -> Arc<Self> {
+      let (tx, _) = broadcast::channel(channel_capacity);
+      Arc::new(Self {
+         windows: RwLock::new(HashMap::new()),
+         state: Mutex::new(DebounceState::default()),
+         notify: Notify::new(),
+         tx,
+      })
+   }
+
+   /// Sets the debounce window for a table.
+   pub(crate) fn set_window(&self, table: &str, window: Duration) {
+      self.windows.write().insert(table.to_string(), window);
+   }
+
+   /// Whether commits to `table` are debounced.
+   pub(crate) fn is_debounced(&self, table: &str) -> bool {
+      self.windows.read().contains_key(table)
+   }
+
+   /// Whether anyone is listening for debounced changes.
+   pub(crate) fn has_subscribers(&self) -> bool {
+      self.tx.receiver_count() > 0
+   }
+
+   /// Merges one commit's changes to `table` into the table's pending batch.
+   pub(crate) fn add(&self, table: &str, keys: Vec<Vec<ColumnValue>>, change_count: usize) {
+      let Some(window) = self.windows.read().get(table).copied() else {
+         return;
+      };
+
+      let mut state = self.state.lock();
+      let batch = state
+         .pending
+         .entry(table.to_string())
+         .or_insert_with(|| Batch {
+            deadline: Instant::now() + window,
+            keys: HashSet::new(),
+            change_count: 0,
+            commit_count: 0,
+         });
+      batch.keys.extend(keys.into_iter().map(RowKey));
+      batch.change_count += change_count;
+      batch.commit_count += 1;
+      drop(state);
+
+      self.notify.notify_one();
+   }
+
+   /// Subscribes to debounced changes, starting the publishing task if needed.
+   ///
+   /// Must be called within a Tokio runtime.
+   pub(crate) fn subscribe(self: &Arc<Self>) -> broadcast::Receiver<DebouncedChange> {
+      // Subscribe before checking the task so that a task about to exit for
+      // lack of receivers sees this one
+      let rx = self.tx.subscribe();
+      let mut state = self.state.lock();
+      if !state.task_running {
+         state.task_running = true;
+         tokio::spawn(Arc::clone(self).run());
+      }
+      rx
+   }
+
+   /// Publishes batches as their deadlines pass, exiting once nothing is
+   /// pending and nobody is subscribed.
+   async fn run(self: Arc<Self>) {
+      loop {
+         let next_deadline = {
+            let mut state = self.state.lock();
+            let now = Instant::now();
+            let due: Vec<String> = state
+               .pending
+               .iter()
+               .filter(|(_, batch)| batch.deadline <= now)
+               .map(|(table, _)| table.clone())
+               .collect();
+            for table in due {
+               let batch = state.pending.remove(&table).expect("due batch is pending");
+               trace!(table = %table, changes = batch.change_count, "Publishing debounced changes");
+               let _ = self.tx.send(DebouncedChange {
+                  table,
+                  primary_keys: batch.keys.into_iter().map(|key| key.0).collect(),
+                  change_count: batch.change_count,
+                  commit_count: batch.commit_count,
+               });
+            }
+
+            if state.pending.is_empty() && self.tx.receiver_count() == 0 {
+               state.task_running = false;
+               return;
+            }
+            state.pending.values().map(|batch| batch.deadline).min()
+         };
+
+         match next_deadline {
+            Some(deadline) => {
+               let wait = deadline.saturating_duration_since(Instant::now());
+               let _ = tokio::time::timeout(wait, self.notify.notified()).await;
+            }
+            None => self.notify.notified().await,
+         }
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_row_key_treats_equal_reals_as_one_key() {
+      let mut keys = HashSet::new();
+      keys.insert(RowKey(vec![
+         ColumnValue::Real(1.5),
+         ColumnValue::Text("a".into()),
+      ]));
+      keys.insert(RowKey(vec![
+         ColumnValue::Real(1.5),
+         ColumnValue::Text("a".into()),
+      ]));
+      keys.insert(RowKey(vec![ColumnValue::Integer(1)]));
+      assert_eq!(keys.len(), 2);
+   }
+}
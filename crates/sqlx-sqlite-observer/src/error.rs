@@ -1,5 +1,7 @@
 //! Error types for the sqlx-sqlite-observer crate.
 
+use std::time::Duration;
+
 /// Errors that can occur during observation operations.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -33,4 +35,8 @@ pub enum Error {
       expected: usize,
       actual: usize,
    },
+
+   /// No matching change arrived in time.
+   #[error("Timed out after {timeout:?} waiting for a change to table '{table}'")]
+   Timeout { table: String, timeout: Duration },
 }
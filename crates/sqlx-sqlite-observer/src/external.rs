@@ -0,0 +1,124 @@
+//! Detecting writes the observer's hooks don't see.
+//!
+//! Hooks only fire on connections the observer registered them on, so commits
+//! from other connections or processes go unnoticed. [`ExternalPoller`] holds
+//! a pooled connection and polls its `PRAGMA data_version`, which SQLite
+//! changes whenever another connection commits, publishing an
+//! [`ExternalChange`] each time it does.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use sqlx::pool::PoolConnection;
+use sqlx::{Pool, Sqlite};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, trace};
+
+use crate::broker::ObservationBroker;
+use crate::change::ExternalChange;
+
+/// Polls `PRAGMA data_version` while anyone is subscribed to external changes.
+pub(crate) struct ExternalPoller {
+   interval: Duration,
+   broker: Arc<ObservationBroker>,
+   tx: broadcast::Sender<ExternalChange>,
+   /// Whether the polling task is running
+   running: Mutex<bool>,
+}
+
+impl ExternalPoller {
+   pub(crate) fn new(
+      interval: Duration,
+      broker: Arc<ObservationBroker>,
+      channel_capacity: usize,
+   ) -> Arc<Self> {
+      let (tx, _) = broadcast::channel(channel_capacity);
+      Arc::new(Self {
+         interval,
+         broker,
+         tx,
+         running: Mutex::new(false),
+      })
+   }
+
+   /// Subscribes to external changes, starting to poll `pool` if needed.
+   ///
+   /// Must be called within a Tokio runtime.
+   pub(crate) fn subscribe(
+      self: &Arc<Self>,
+      pool: &Pool<Sqlite>,
+   ) -> broadcast::Receiver<ExternalChange> {
+      // Subscribe before checking the task so that a task about to exit for
+      // lack of receivers sees this one
+      let rx = self.tx.subscribe();
+      let mut running = self.running.lock();
+      if !*running {
+         *running = true;
+         tokio::spawn(Arc::clone(self).run(pool.clone()));
+      }
+      rx
+   }
+
+   /// Polls until nobody is subscribed, releasing the connection on exit.
+   async fn run(self: Arc<Self>, pool: Pool<Sqlite>) {
+      debug!(interval = ?self.interval, "Polling for external changes");
+      let mut ticker = tokio::time::interval(self.interval);
+      ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+      let mut conn: Option<PoolConnection<Sqlite>> = None;
+      // data_version values are only comparable on the same connection
+      let mut last_version: Option<i64> = None;
+      let mut last_commit_seq = self.broker.last_commit_seq();
+
+      loop {
+         ticker.tick().await;
+         {
+            let mut running = self.running.lock();
+            if self.tx.receiver_count() == 0 {
+               *running = false;
+               debug!("Stopped polling for external changes");
+               return;
+            }
+         }
+
+         if conn.is_none() {
+            match pool.acquire().await {
+               Ok(acquired) => {
+                  conn = Some(acquired);
+                  last_version = None;
+               }
+               Err(e) => {
+                  debug!(error = %e, "Failed to acquire connection for data_version polling");
+                  continue;
+               }
+            }
+         }
+         let Some(polling) = conn.as_mut() else {
+            continue;
+         };
+
+         let version: i64 = match sqlx::query_scalar("PRAGMA data_version")
+            .fetch_one(&mut **polling)
+            .await
+         {
+            Ok(version) => version,
+            Err(e) => {
+               debug!(error = %e, "Failed to poll data_version; reconnecting");
+               conn = None;
+               continue;
+            }
+         };
+
+         let commit_seq = self.broker.last_commit_seq();
+         if last_version.is_some_and(|last| last != version) {
+            let observed_commits = commit_seq - last_commit_seq;
+            trace!(version, observed_commits, "data_version advanced");
+            let _ = self.tx.send(ExternalChange { observed_commits });
+         }
+         last_version = Some(version);
+         last_commit_seq = commit_seq;
+      }
+   }
+}
@@ -1,7 +1,12 @@
 //! SQLite native hook registration for support observing changes to the database.
 //!
 //! This module provides low-level bindings to SQLite's preupdate_hook, commit_hook,
-//! and rollback_hook APIs for transaction-aware change tracking.
+//! and rollback_hook APIs for transaction-aware change tracking. An authorizer is
+//! also installed to notice `ALTER TABLE` and `DROP TABLE` statements, which make
+//! cached table schema information stale. It allows every statement, but replaces
+//! any authorizer previously set on the connection. Likewise, a statement profile
+//! callback (`sqlite3_trace_v2`) replaces any trace callback: it tells when a
+//! commit whose keys are resolved lazily has actually landed.
 //!
 //! # SQLite Requirements
 //!
@@ -15,16 +20,20 @@ use std::ptr;
 use std::sync::Arc;
 
 use libsqlite3_sys::{
-   SQLITE_BLOB, SQLITE_DELETE, SQLITE_FLOAT, SQLITE_INSERT, SQLITE_INTEGER, SQLITE_NULL,
-   SQLITE_TEXT, SQLITE_UPDATE, sqlite3, sqlite3_commit_hook, sqlite3_compileoption_used,
-   sqlite3_preupdate_count, sqlite3_preupdate_hook, sqlite3_preupdate_new, sqlite3_preupdate_old,
-   sqlite3_rollback_hook, sqlite3_value, sqlite3_value_blob, sqlite3_value_bytes,
+   SQLITE_ALTER_TABLE, SQLITE_BLOB, SQLITE_DELETE, SQLITE_DROP_TABLE, SQLITE_FLOAT, SQLITE_INSERT,
+   SQLITE_INTEGER, SQLITE_NULL, SQLITE_OK, SQLITE_TEXT, SQLITE_TRACE_PROFILE, SQLITE_TXN_WRITE,
+   SQLITE_UPDATE, sqlite3, sqlite3_commit_hook, sqlite3_compileoption_used, sqlite3_db_filename,
+   sqlite3_db_handle, sqlite3_preupdate_count, sqlite3_preupdate_hook, sqlite3_preupdate_new,
+   sqlite3_preupdate_old, sqlite3_rollback_hook, sqlite3_set_authorizer, sqlite3_stmt,
+   sqlite3_trace_v2, sqlite3_txn_state, sqlite3_value, sqlite3_value_blob, sqlite3_value_bytes,
    sqlite3_value_double, sqlite3_value_int64, sqlite3_value_text, sqlite3_value_type,
 };
+use parking_lot::Mutex;
 use tracing::{debug, error, trace};
 
 use crate::broker::ObservationBroker;
-use crate::change::ChangeOperation;
+use crate::change::{ChangeOperation, ColumnValue};
+use crate::changelog::CHANGELOG_TABLE;
 
 /// A SQLite value extracted from preupdate hooks.
 ///
@@ -84,12 +93,19 @@ impl SqliteValue {
 /// Raw change event captured by the preupdate hook before commit decision.
 #[derive(Debug, Clone)]
 pub struct PreUpdateEvent {
+   /// Name of the database the connection has open
+   pub database: Arc<str>,
    pub table: String,
    pub operation: ChangeOperation,
    pub old_rowid: i64,
    pub new_rowid: i64,
    pub old_values: Option<Vec<SqliteValue>>,
    pub new_values: Option<Vec<SqliteValue>>,
+   /// Primary key read on its own, in place of the column values, when keys
+   /// are resolved lazily
+   pub primary_key: Option<Vec<ColumnValue>>,
+   /// Sequence number of the change in the durable changelog, if logged
+   pub changelog_seq: Option<i64>,
 }
 
 /// Context data passed to SQLite hook callbacks.
@@ -98,6 +114,11 @@ pub struct PreUpdateEvent {
 /// stays alive as long as hooks are registered.
 struct HookContext {
    broker: Arc<ObservationBroker>,
+   /// Name changes made through this connection identify their database by
+   database: Arc<str>,
+   /// Changes of a commit whose statement hasn't finished yet, held when
+   /// primary keys are resolved so they are only looked up once it landed
+   committing: Mutex<Option<Vec<PreUpdateEvent>>>,
 }
 
 /// Checks if the linked SQLite library was compiled with `SQLITE_ENABLE_PREUPDATE_HOOK`.
@@ -153,10 +174,28 @@ pub unsafe fn register_hooks(
 
    // Heap-allocate the context so it outlives this function. SQLite's C API
    // requires a raw pointer to pass user data to callbacks.
-   let context = Box::new(HookContext { broker });
+   let database = match broker.database_name() {
+      Some(name) => name,
+      // SAFETY: db is a valid sqlite3 pointer (guaranteed by caller). The
+      // returned string is owned by SQLite and copied before it can change.
+      None => unsafe {
+         let filename = sqlite3_db_filename(db, c"main".as_ptr());
+         let filename = if filename.is_null() {
+            ""
+         } else {
+            CStr::from_ptr(filename).to_str().unwrap_or_default()
+         };
+         database_display_name(filename)
+      },
+   };
+   let context = Box::new(HookContext {
+      broker,
+      database,
+      committing: Mutex::new(None),
+   });
    // Transfer ownership out of Rust's memory management.
    //
-   // NOTE: This pointer is shared across all three hooks and is intentionally
+   // NOTE: This pointer is shared across all hooks and is intentionally
    // leaked. SQLite does NOT free user_data - it simply passes the pointer back
    // to callbacks. The memory is reclaimed when hooks are replaced via
    // `unregister_hooks`, which reconstructs the Box from the raw pointer returned
@@ -172,12 +211,29 @@ pub unsafe fn register_hooks(
       sqlite3_preupdate_hook(db, Some(preupdate_callback), context_ptr);
       sqlite3_commit_hook(db, Some(commit_callback), context_ptr);
       sqlite3_rollback_hook(db, Some(rollback_callback), context_ptr);
+      sqlite3_set_authorizer(db, Some(authorizer_callback), context_ptr);
+      sqlite3_trace_v2(
+         db,
+         SQLITE_TRACE_PROFILE as u32,
+         Some(trace_callback),
+         context_ptr,
+      );
    }
 
    trace!("SQLite hooks registered successfully");
    Ok(())
 }
 
+/// Name a database is identified by when no name is configured: the path of
+/// its file, or `":memory:"` for an in-memory or temporary database.
+pub(crate) fn database_display_name(filename: &str) -> Arc<str> {
+   if filename.is_empty() {
+      Arc::from(":memory:")
+   } else {
+      Arc::from(filename)
+   }
+}
+
 /// Unregisters all observation hooks and reclaims the context memory.
 ///
 /// # Safety
@@ -193,12 +249,22 @@ pub unsafe fn unregister_hooks(db: *mut sqlite3) {
    unsafe {
       sqlite3_commit_hook(db, None, ptr::null_mut());
       sqlite3_rollback_hook(db, None, ptr::null_mut());
+      sqlite3_set_authorizer(db, None, ptr::null_mut());
+      sqlite3_trace_v2(db, 0, None, ptr::null_mut());
    }
 
    // Reclaim the HookContext we leaked in register_hooks
    if !prev_user_data.is_null() {
       // SAFETY: prev_user_data was created by Box::into_raw in register_hooks
-      let _ = unsafe { Box::from_raw(prev_user_data as *mut HookContext) };
+      let context = unsafe { Box::from_raw(prev_user_data as *mut HookContext) };
+      // A commit still held here never finished, since its statement would
+      // have settled it
+      if let Some(events) = context.committing.lock().take() {
+         debug!(
+            count = events.len(),
+            "Discarding changes of a commit that never finished"
+         );
+      }
       trace!("SQLite hooks unregistered and context freed");
    }
 }
@@ -235,6 +301,22 @@ unsafe extern "C" fn preupdate_callback(
          Err(_) => return,
       };
 
+      // A changelog trigger logging a change; tag the change with its sequence
+      // number rather than publishing the log entry itself
+      if table_name.eq_ignore_ascii_case(CHANGELOG_TABLE) {
+         if op == SQLITE_INSERT && context.broker.changelog_enabled() {
+            let mut value: *mut sqlite3_value = ptr::null_mut();
+            // SAFETY: db is valid and column 1 (table_name) exists in the changelog
+            if unsafe { sqlite3_preupdate_new(db, 1, &mut value) } == 0
+               // SAFETY: value was populated by sqlite3_preupdate_new
+               && let SqliteValue::Text(logged_table) = unsafe { SqliteValue::from_raw(value) }
+            {
+               context.broker.on_changelog_insert(&logged_table, new_rowid);
+            }
+         }
+         return;
+      }
+
       // Check if this table is being observed
       if !context.broker.is_table_observed(&table_name) {
          return;
@@ -257,6 +339,43 @@ unsafe extern "C" fn preupdate_callback(
       }
       let column_count = column_count as usize;
 
+      // Resolving keys by rowid later, so skip reading column values, except
+      // for a deleted row's key, which can't be looked up once it's gone
+      if context.broker.resolves_primary_keys()
+         && let Some(info) = context.broker.get_table_info(&table_name)
+         && !info.without_rowid
+      {
+         let primary_key = (operation == ChangeOperation::Delete).then(|| {
+            info
+               .pk_columns
+               .iter()
+               .map(|&idx| {
+                  let mut value: *mut sqlite3_value = ptr::null_mut();
+                  // SAFETY: db is valid; SQLite rejects an out-of-range idx
+                  if unsafe { sqlite3_preupdate_old(db, idx as c_int, &mut value) } == 0 {
+                     // SAFETY: value was populated by sqlite3_preupdate_old
+                     unsafe { SqliteValue::from_raw(value) }.into()
+                  } else {
+                     ColumnValue::Null
+                  }
+               })
+               .collect()
+         });
+
+         context.broker.on_preupdate(PreUpdateEvent {
+            database: Arc::clone(&context.database),
+            table: table_name,
+            operation,
+            old_rowid,
+            new_rowid,
+            old_values: None,
+            new_values: None,
+            primary_key,
+            changelog_seq: None,
+         });
+         return;
+      }
+
       // Capture old values (for UPDATE and DELETE)
       let old_values = if matches!(operation, ChangeOperation::Update | ChangeOperation::Delete) {
          let mut values = Vec::with_capacity(column_count);
@@ -294,12 +413,15 @@ unsafe extern "C" fn preupdate_callback(
       };
 
       let event = PreUpdateEvent {
+         database: Arc::clone(&context.database),
          table: table_name,
          operation,
          old_rowid,
          new_rowid,
          old_values,
          new_values,
+         primary_key: None,
+         changelog_seq: None,
       };
 
       context.broker.on_preupdate(event);
@@ -329,7 +451,20 @@ unsafe extern "C" fn commit_callback(user_data: *mut c_void) -> c_int {
       // SAFETY: user_data is a valid HookContext pointer created in register_hooks.
       let context = unsafe { &*(user_data as *const HookContext) };
       trace!("Commit hook fired - flushing changes");
-      context.broker.on_commit();
+      // The commit can still fail, and its rows aren't visible to the
+      // connections keys are looked up on yet, so hold the changes until the
+      // statement finishes
+      if context.broker.resolves_primary_keys() {
+         if let Some(events) = context.broker.take_buffered() {
+            context
+               .committing
+               .lock()
+               .get_or_insert_with(Vec::new)
+               .extend(events);
+         }
+      } else {
+         context.broker.on_commit();
+      }
    });
 
    if result.is_err() {
@@ -355,6 +490,12 @@ unsafe extern "C" fn rollback_callback(user_data: *mut c_void) {
       // SAFETY: user_data is a valid HookContext pointer created in register_hooks.
       let context = unsafe { &*(user_data as *const HookContext) };
       trace!("Rollback hook fired - discarding changes");
+      if let Some(events) = context.committing.lock().take() {
+         debug!(
+            count = events.len(),
+            "Discarding changes of a commit that rolled back"
+         );
+      }
       context.broker.on_rollback();
    });
 
@@ -363,6 +504,108 @@ unsafe extern "C" fn rollback_callback(user_data: *mut c_void) {
    }
 }
 
+/// Trace callback - settles a held commit once the statement running it
+/// finishes.
+///
+/// Registered for `SQLITE_TRACE_PROFILE`, which SQLite invokes when a statement
+/// finishes, after any commit it ran has landed or failed. `stmt` is the
+/// finished statement.
+///
+/// Note: `user_data` is SQLite's C API term for callback context (our HookContext),
+/// unrelated to application-level user data.
+unsafe extern "C" fn trace_callback(
+   mask: u32,
+   user_data: *mut c_void,
+   stmt: *mut c_void,
+   _elapsed: *mut c_void,
+) -> c_int {
+   if mask != SQLITE_TRACE_PROFILE as u32 || user_data.is_null() || stmt.is_null() {
+      return 0;
+   }
+
+   // Catch any panics to prevent unwinding across the FFI boundary (which is UB).
+   let result = catch_unwind(|| {
+      // SAFETY: user_data is a valid HookContext pointer created in register_hooks.
+      let context = unsafe { &*(user_data as *const HookContext) };
+      let Some(events) = context.committing.lock().take() else {
+         return;
+      };
+      // SAFETY: stmt is the statement that just finished, valid for this callback.
+      unsafe {
+         let db = sqlite3_db_handle(stmt as *mut sqlite3_stmt);
+         settle_commit(&context.broker, db, events);
+      }
+   });
+
+   if result.is_err() {
+      eprintln!("sqlx-sqlite-observer: panic in trace_callback (absorbed to prevent UB)");
+   }
+
+   0
+}
+
+/// Hands a held commit's changes on if it landed, or back to the buffer if
+/// its transaction is still open because the commit failed (e.g. with
+/// `SQLITE_BUSY`), so a retried commit or a rollback covers them.
+///
+/// A commit that rolled back instead was discarded by the rollback hook.
+///
+/// # Safety
+///
+/// `db` must be a valid sqlite3 pointer not in use on another thread.
+unsafe fn settle_commit(broker: &ObservationBroker, db: *mut sqlite3, events: Vec<PreUpdateEvent>) {
+   // SAFETY: upheld by the caller
+   if unsafe { sqlite3_txn_state(db, ptr::null()) } == SQLITE_TXN_WRITE {
+      trace!("Commit failed with its transaction still open - keeping changes");
+      broker.restore_buffered(events);
+   } else {
+      broker.on_committed(events);
+   }
+}
+
+/// Authorizer callback - invalidates cached schema info for altered or dropped tables.
+///
+/// Called by SQLite while preparing each statement. `ALTER TABLE` passes the table
+/// name as the second argument and `DROP TABLE` as the first. Every action is
+/// allowed.
+///
+/// Note: `user_data` is SQLite's C API term for callback context (our HookContext),
+/// unrelated to application-level user data.
+unsafe extern "C" fn authorizer_callback(
+   user_data: *mut c_void,
+   action: c_int,
+   arg1: *const c_char,
+   arg2: *const c_char,
+   _database: *const c_char,
+   _trigger: *const c_char,
+) -> c_int {
+   let table = match action {
+      SQLITE_ALTER_TABLE => arg2,
+      SQLITE_DROP_TABLE => arg1,
+      _ => return SQLITE_OK,
+   };
+   if user_data.is_null() || table.is_null() {
+      return SQLITE_OK;
+   }
+
+   // Catch any panics to prevent unwinding across the FFI boundary (which is UB).
+   let result = catch_unwind(|| {
+      // SAFETY: user_data is a valid HookContext pointer created in register_hooks.
+      let context = unsafe { &*(user_data as *const HookContext) };
+
+      // SAFETY: table is a non-null C string provided by SQLite, valid for this callback.
+      if let Ok(table_name) = unsafe { CStr::from_ptr(table) }.to_str() {
+         context.broker.invalidate_table_info(table_name);
+      }
+   });
+
+   if result.is_err() {
+      eprintln!("sqlx-sqlite-observer: panic in authorizer_callback (absorbed to prevent UB)");
+   }
+
+   SQLITE_OK
+}
+
 #[cfg(test)]
 mod tests {
    use super::*;
@@ -123,25 +123,34 @@
 
 pub mod broker;
 pub mod change;
+pub mod changelog;
 pub mod config;
 pub mod connection;
+mod debounce;
 pub mod error;
+mod external;
 pub mod hooks;
 pub mod observer;
+mod resolve;
 pub mod schema;
 pub mod stream;
+pub mod subscription;
 
 #[cfg(feature = "conn-mgr")]
 pub mod conn_mgr;
 
-pub use broker::ObservationBroker;
-pub use change::{ChangeOperation, ColumnValue, TableChange, TableChangeEvent, TableInfo};
+pub use broker::{ChangePredicate, ObservationBroker};
+pub use change::{
+   ChangeOperation, ChangelogEntry, ColumnValue, CommitSummary, DebouncedChange, ExternalChange,
+   OperationCounts, ResyncEvent, TableChange, TableChangeEvent, TableInfo, TransactionChangeSet,
+};
 pub use config::ObserverConfig;
 pub use connection::ObservableConnection;
 pub use error::Error;
 pub use hooks::{SqliteValue, is_preupdate_hook_enabled, unregister_hooks};
 pub use observer::SqliteObserver;
-pub use stream::{TableChangeStream, TableChangeStreamExt};
+pub use stream::{ReplayStream, TableChangeStream, TableChangeStreamExt};
+pub use subscription::{ChangeReceiver, LagPolicy, SubscriptionHandle, SubscriptionOptions};
 
 #[cfg(feature = "conn-mgr")]
 pub use conn_mgr::{ObservableSqliteDatabase, ObservableWriteGuard};
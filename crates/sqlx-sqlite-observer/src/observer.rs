@@ -3,18 +3,26 @@
 //! Uses SQLite's native hooks for change detection.
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use sqlx::SqlitePool;
 use tokio::sync::broadcast;
-use tracing::{debug, warn};
+use tracing::debug;
 
 use crate::Result;
 use crate::broker::ObservationBroker;
-use crate::change::TableChange;
+use crate::change::{
+   ChangelogEntry, ColumnValue, CommitSummary, DebouncedChange, ExternalChange, ResyncEvent,
+   TableChange, TransactionChangeSet,
+};
+use crate::changelog;
 use crate::config::ObserverConfig;
 use crate::connection::ObservableConnection;
 use crate::error::Error;
-use crate::schema::query_table_info;
+use crate::external::ExternalPoller;
+use crate::schema::refresh_table_info;
+use crate::stream::ReplayStream;
+use crate::subscription::{self, ChangeReceiver, SubscriptionHandle, SubscriptionOptions};
 
 /// SQLite database observer with transaction-safe change notifications.
 ///
@@ -29,6 +37,7 @@ use crate::schema::query_table_info;
 pub struct SqliteObserver {
    pool: SqlitePool,
    broker: Arc<ObservationBroker>,
+   external: Arc<ExternalPoller>,
    config: ObserverConfig,
 }
 
@@ -42,10 +51,32 @@ impl SqliteObserver {
       if !config.tables.is_empty() {
          broker.observe_tables(config.tables.iter().map(String::as_str));
       }
+      if config.observe_all {
+         broker.observe_all_tables();
+      }
+      if config.changelog {
+         broker.enable_changelog();
+      }
+      if config.resolve_primary_keys {
+         broker.enable_key_resolution();
+      }
+      if let Some(name) = &config.database_name {
+         broker.set_database_name(name);
+      }
+      for (table, window) in &config.debounce {
+         broker.set_debounce(table, *window);
+      }
+
+      let external = ExternalPoller::new(
+         config.external_poll_interval,
+         Arc::clone(&broker),
+         config.channel_capacity,
+      );
 
       Self {
          pool,
          broker,
+         external,
          config,
       }
    }
@@ -69,6 +100,229 @@ impl SqliteObserver {
       self.broker.subscribe()
    }
 
+   /// Subscribes to changes through a channel with its own capacity and lag
+   /// policy.
+   ///
+   /// Unlike `subscribe`, whose receivers share one channel sized by
+   /// `channel_capacity` and always drop their oldest changes when behind,
+   /// this subscriber's channel is sized and handles overflow as `options`
+   /// says, so a critical consumer isn't affected by slow ones.
+   pub fn subscribe_with<I, S>(&self, tables: I, options: SubscriptionOptions) -> ChangeReceiver
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_with(tables, options)
+   }
+
+   /// Subscribes to changes with a callback instead of a receiver.
+   ///
+   /// Spawns a task that calls `callback` with every change to `tables` (or
+   /// to every observed table if empty) until the returned handle is
+   /// cancelled. The callback runs on the task, so it should not block. Must
+   /// be called within a Tokio runtime.
+   pub fn on_change<I, S, F>(&self, tables: I, callback: F) -> SubscriptionHandle
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+      F: FnMut(TableChange) + Send + 'static,
+   {
+      SubscriptionHandle::spawn(self.subscribe_stream(tables), callback)
+   }
+
+   /// Waits for the first change to `table` matching `predicate`.
+   ///
+   /// Subscribes before returning, so a change made after this call but
+   /// before the future is awaited is still seen: start waiting, make the
+   /// change, then await. Fails with `Error::Timeout` if no matching change
+   /// arrives within `timeout`. Must be awaited within a Tokio runtime.
+   pub fn wait_for_change<P>(
+      &self,
+      table: impl Into<String>,
+      predicate: P,
+      timeout: Duration,
+   ) -> impl Future<Output = Result<TableChange>> + Send + 'static
+   where
+      P: FnMut(&TableChange) -> bool + Send + 'static,
+   {
+      let table = table.into();
+      let stream = self.subscribe_stream([table.clone()]);
+      subscription::wait_for_change(stream, table, predicate, timeout)
+   }
+
+   /// Subscribes to per-commit counts of the rows changed in each table.
+   ///
+   /// The receiver gets one `CommitSummary` per commit instead of the changes
+   /// themselves, which is all a "refresh this list" UI needs. When nothing
+   /// else is subscribed, individual changes and their column values aren't
+   /// even built.
+   pub fn subscribe_summaries<I, S>(&self, tables: I) -> broadcast::Receiver<CommitSummary>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_summaries()
+   }
+
+   /// Pauses publishing changes, for bulk operations such as imports or
+   /// migrations that would otherwise send a notification per row.
+   ///
+   /// Changes committed during the pause are discarded, and `resume` sends
+   /// subscribers of `subscribe_resyncs` one `ResyncEvent` naming the tables
+   /// that changed. Pausing while already paused has no effect.
+   pub fn pause(&self) {
+      self.broker.pause(0);
+   }
+
+   /// Pauses publishing changes, keeping up to `buffer_limit` of those
+   /// committed meanwhile.
+   ///
+   /// If no more than `buffer_limit` changes are committed during the pause,
+   /// `resume` publishes them as usual. Otherwise they are all discarded and
+   /// `resume` sends one `ResyncEvent` instead.
+   pub fn pause_with_buffer(&self, buffer_limit: usize) {
+      self.broker.pause(buffer_limit);
+   }
+
+   /// Resumes publishing changes after `pause`.
+   pub fn resume(&self) {
+      self.broker.resume();
+   }
+
+   /// Whether publishing is paused.
+   pub fn is_paused(&self) -> bool {
+      self.broker.is_paused()
+   }
+
+   /// Subscribes to the `ResyncEvent`s sent on `resume` when a pause
+   /// discarded changes.
+   pub fn subscribe_resyncs(&self) -> broadcast::Receiver<ResyncEvent> {
+      self.broker.subscribe_resyncs()
+   }
+
+   /// Subscribes to the changes of whole transactions.
+   ///
+   /// If additional tables are provided, they will be added to the observed set.
+   /// Instead of one `TableChange` per statement, the receiver gets a single
+   /// `TransactionChangeSet` per commit with all changes the transaction made to
+   /// observed tables, so they can be applied atomically.
+   pub fn subscribe_transactions<I, S>(
+      &self,
+      tables: I,
+   ) -> broadcast::Receiver<TransactionChangeSet>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_transactions()
+   }
+
+   /// Subscribes to merged notifications for debounced tables.
+   ///
+   /// If additional tables are provided, they will be added to the observed set.
+   /// Commits to each table configured with
+   /// [`ObserverConfig::with_debounce`] are merged over the table's window and
+   /// delivered as one `DebouncedChange` with the set of affected primary keys.
+   /// Tables without a debounce window produce no notifications here.
+   ///
+   /// Must be called within a Tokio runtime.
+   pub fn subscribe_debounced<I, S>(&self, tables: I) -> broadcast::Receiver<DebouncedChange>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      if !tables.is_empty() {
+         self
+            .broker
+            .observe_tables(tables.iter().map(String::as_str));
+      }
+      self.broker.subscribe_debounced()
+   }
+
+   /// Subscribes to changes of specific rows of a table.
+   ///
+   /// Each key holds a row's primary key value(s) in declaration order (or its
+   /// rowid if the table has no declared primary key). The table is added to the
+   /// observed set, and changes to other rows are filtered out before they are
+   /// sent, so a view showing a few rows isn't woken by every write to the table.
+   pub fn subscribe_rows<I, K>(
+      &self,
+      table: impl Into<String>,
+      keys: I,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      I: IntoIterator<Item = K>,
+      K: IntoIterator<Item = ColumnValue>,
+   {
+      let table = table.into();
+      let keys = keys
+         .into_iter()
+         .map(|key| key.into_iter().collect())
+         .collect();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_rows(&table, keys)
+   }
+
+   /// Subscribes to the changes of a table that satisfy `predicate`.
+   ///
+   /// The table is added to the observed set. The predicate is evaluated for
+   /// every committed change to the table before it is sent, typically against
+   /// `new_values`/`old_values` (e.g. only rows whose `status` is `'active'`).
+   /// It runs inside SQLite's commit hook, so it should be cheap and must not
+   /// use the database.
+   pub fn subscribe_where<F>(
+      &self,
+      table: impl Into<String>,
+      predicate: F,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      F: Fn(&TableChange) -> bool + Send + Sync + 'static,
+   {
+      let table = table.into();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_where(&table, Arc::new(predicate))
+   }
+
+   /// Subscribes to the changes of a table that can affect the given columns.
+   ///
+   /// The table is added to the observed set. Inserts and deletes are always
+   /// delivered, but an UPDATE is only delivered if it actually changed one of
+   /// `columns`, so writes that only touch bookkeeping columns such as
+   /// `updated_at` don't wake the subscriber.
+   pub fn subscribe_columns<I, S>(
+      &self,
+      table: impl Into<String>,
+      columns: I,
+   ) -> broadcast::Receiver<TableChange>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let table = table.into();
+      let columns = columns.into_iter().map(Into::into).collect();
+      self.broker.observe_tables([table.as_str()]);
+      self.broker.subscribe_columns(&table, columns)
+   }
+
    /// Subscribes to change notifications as a Stream.
    ///
    /// Returns a `TableChangeStream` that implements `futures::Stream`.
@@ -95,49 +349,60 @@ impl SqliteObserver {
       }
    }
 
+   /// Subscribes to changes as a Stream that first replays every change logged to
+   /// the durable changelog after sequence number `after_seq`, then continues
+   /// with live changes.
+   ///
+   /// Pass the `changelog_seq` of the last change processed before a restart
+   /// to pick up where it left off, or 0 to replay the whole log. The live
+   /// subscription starts before the log is read, so nothing committed in
+   /// between is missed, and changes delivered by both are only yielded once.
+   /// Requires [`ObserverConfig::with_changelog`].
+   pub async fn subscribe_from<I, S>(&self, tables: I, after_seq: i64) -> Result<ReplayStream>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let tables: Vec<String> = tables.into_iter().map(Into::into).collect();
+      let live = self.subscribe_stream(tables.clone());
+      let replayed = changelog::replay(&self.pool, &self.broker, &tables, after_seq).await?;
+      Ok(ReplayStream::new(replayed, after_seq, live))
+   }
+
+   /// Subscribes to writes made outside the observer.
+   ///
+   /// Commits from connections that weren't acquired through the observer,
+   /// including other processes, don't reach the hooks. While anyone is
+   /// subscribed, a pooled connection polls `PRAGMA data_version` every
+   /// [`ObserverConfig::external_poll_interval`] and an `ExternalChange` is
+   /// sent whenever another connection has committed, including connections
+   /// acquired through the observer. Must be called within a Tokio runtime.
+   pub fn subscribe_external(&self) -> broadcast::Receiver<ExternalChange> {
+      self.external.subscribe(&self.pool)
+   }
+
    /// Acquires a connection from the pool with observation hooks registered.
    ///
    /// The returned connection will track changes to observed tables. Changes
    /// are buffered during transactions and published to subscribers after commit.
    ///
-   /// On first acquisition for each table, queries the schema to determine
-   /// primary key columns and WITHOUT ROWID status.
+   /// On first acquisition for each table, and again after the schema changes,
+   /// queries the schema to determine primary key columns and WITHOUT ROWID
+   /// status.
    pub async fn acquire(&self) -> Result<ObservableConnection> {
+      self.broker.start_key_resolver(&self.pool);
       let conn = self.pool.acquire().await.map_err(|_| Error::PoolAcquire)?;
       let mut observable = ObservableConnection::new(conn, Arc::clone(&self.broker));
 
-      // Query table info for any observed tables that don't have it yet
-      self.ensure_table_info(&mut observable).await?;
+      // Query table info for observed tables that don't have it yet, or for
+      // every observed table if the schema changed since it was loaded
+      refresh_table_info(&mut observable, &self.broker).await?;
 
       observable.register_hooks().await?;
       debug!("Acquired observable connection with hooks registered");
       Ok(observable)
    }
 
-   /// Ensures TableInfo is set for all observed tables.
-   async fn ensure_table_info(&self, conn: &mut ObservableConnection) -> Result<()> {
-      let observed = self.broker.get_observed_tables();
-
-      for table in observed {
-         if self.broker.get_table_info(&table).is_none() {
-            match query_table_info(conn, &table).await {
-               Ok(Some(info)) => {
-                  debug!(table = %table, pk_columns = ?info.pk_columns, without_rowid = info.without_rowid, "Queried table info");
-                  self.broker.set_table_info(&table, info);
-               }
-               Ok(None) => {
-                  warn!(table = %table, "Table not found in schema");
-               }
-               Err(e) => {
-                  warn!(table = %table, error = %e, "Failed to query table info");
-               }
-            }
-         }
-      }
-
-      Ok(())
-   }
-
    /// Acquires a connection and registers additional tables for observation.
    ///
    /// The specified tables are added to the observed set before acquiring.
@@ -146,6 +411,21 @@ impl SqliteObserver {
       self.acquire().await
    }
 
+   /// Reads up to `limit` changes from the durable changelog with a sequence
+   /// number above `after`, oldest first.
+   ///
+   /// Requires [`ObserverConfig::with_changelog`] and at least one acquired
+   /// connection to have created the changelog table.
+   pub async fn changelog_since(&self, after: i64, limit: u32) -> Result<Vec<ChangelogEntry>> {
+      changelog::read_entries(&self.pool, after, limit).await
+   }
+
+   /// Deletes changes from the durable changelog up to and including
+   /// sequence number `through`, returning how many were deleted.
+   pub async fn prune_changelog(&self, through: i64) -> Result<u64> {
+      changelog::prune_entries(&self.pool, through).await
+   }
+
    /// Returns a reference to the underlying connection pool.
    pub fn pool(&self) -> &SqlitePool {
       &self.pool
@@ -156,6 +436,25 @@ impl SqliteObserver {
       &self.config
    }
 
+   /// Starts observing a table at runtime.
+   ///
+   /// Its schema info is queried on the next `acquire`, so connections already
+   /// checked out only publish its changes with an empty `primary_key` and
+   /// `columns`.
+   pub fn observe_table(&self, table: impl AsRef<str>) {
+      self.broker.observe_tables([table.as_ref()]);
+   }
+
+   /// Stops observing a table at runtime.
+   ///
+   /// Row, predicate, and column subscriptions to the table are closed, and
+   /// changes to it are no longer published. Returns whether the table had
+   /// been added explicitly; with `all_tables()` every non-internal table
+   /// stays observed.
+   pub fn unobserve_table(&self, table: &str) -> bool {
+      self.broker.unobserve_table(table)
+   }
+
    /// Returns a list of tables currently being observed.
    pub fn observed_tables(&self) -> Vec<String> {
       self.broker.get_observed_tables()
@@ -172,6 +471,7 @@ impl Clone for SqliteObserver {
       Self {
          pool: self.pool.clone(),
          broker: Arc::clone(&self.broker),
+         external: Arc::clone(&self.external),
          config: self.config.clone(),
       }
    }
@@ -0,0 +1,136 @@
+//! Looking up primary keys the preupdate hook didn't capture.
+//!
+//! With value capture disabled and
+//! [`ObserverConfig::resolve_primary_keys`](crate::ObserverConfig::resolve_primary_keys)
+//! enabled, the hook doesn't read column values for tables with a rowid.
+//! Each commit is instead handed to a task once the statement that ran it
+//! has finished and the commit has landed, so its rows are visible to other
+//! connections; a commit that fails or rolls back after the commit hook is
+//! never handed over. The task reads the primary key of every inserted or
+//! updated row with `WHERE rowid = ?` on a pooled connection, then publishes
+//! the commit. Commits are handled one at a time, so they are still
+//! published in order.
+
+use std::sync::Weak;
+
+use sqlx::pool::PoolConnection;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tokio::sync::mpsc;
+use tracing::{debug, trace};
+
+use crate::broker::ObservationBroker;
+use crate::change::{ChangeOperation, ColumnValue, TableInfo};
+use crate::changelog::{decode_values, json_value, quote_identifier};
+use crate::hooks::PreUpdateEvent;
+use crate::{Error, Result};
+
+/// Spawns the task looking up keys on `pool`, returning the sender commits
+/// are handed to it through.
+///
+/// The task stops once the broker is dropped. Must be called within a Tokio
+/// runtime.
+pub(crate) fn spawn(
+   broker: Weak<ObservationBroker>,
+   pool: Pool<Sqlite>,
+) -> mpsc::UnboundedSender<Vec<PreUpdateEvent>> {
+   let (tx, rx) = mpsc::unbounded_channel();
+   tokio::spawn(run(broker, pool, rx));
+   tx
+}
+
+async fn run(
+   broker: Weak<ObservationBroker>,
+   pool: Pool<Sqlite>,
+   mut rx: mpsc::UnboundedReceiver<Vec<PreUpdateEvent>>,
+) {
+   debug!("Resolving primary keys by rowid");
+
+   while let Some(mut events) = rx.recv().await {
+      let Some(broker) = broker.upgrade() else {
+         break;
+      };
+      // Acquired for the first lookup and released with the commit
+      let mut conn: Option<PoolConnection<Sqlite>> = None;
+      for event in events.iter_mut() {
+         let Some(info) = broker
+            .get_table_info(&event.table)
+            .filter(|info| needs_lookup(event, info))
+         else {
+            continue;
+         };
+
+         if conn.is_none() {
+            match pool.acquire().await {
+               Ok(acquired) => conn = Some(acquired),
+               Err(e) => {
+                  debug!(error = %e, "Failed to acquire connection for primary key lookup");
+                  break;
+               }
+            }
+         }
+         let Some(lookup) = conn.as_mut() else {
+            break;
+         };
+
+         match lookup_key(lookup, &event.table, &info, event.new_rowid).await {
+            Ok(Some(key)) => event.primary_key = Some(key),
+            // The commit has landed, so the row is only missing if a later
+            // one removed it
+            Ok(None) => {
+               trace!(table = %event.table, rowid = event.new_rowid, "Row was deleted before its key was looked up");
+            }
+            Err(e) => {
+               debug!(table = %event.table, rowid = event.new_rowid, error = %e, "Primary key lookup failed");
+            }
+         }
+      }
+      broker.deliver(events);
+   }
+
+   debug!("Stopped resolving primary keys");
+}
+
+/// Whether `event`'s primary key has to be looked up: an insert or update
+/// of a row in a rowid table with a declared primary key, whose values
+/// weren't captured.
+fn needs_lookup(event: &PreUpdateEvent, info: &TableInfo) -> bool {
+   matches!(
+      event.operation,
+      ChangeOperation::Insert | ChangeOperation::Update
+   ) && event.primary_key.is_none()
+      && event.new_values.is_none()
+      && !info.without_rowid
+      && !info.pk_columns.is_empty()
+      && info.pk_columns.iter().all(|&idx| idx < info.columns.len())
+}
+
+/// Reads the primary key of the row of `table` with `rowid`, or `None` if
+/// there is no such row.
+async fn lookup_key(
+   conn: &mut SqliteConnection,
+   table: &str,
+   info: &TableInfo,
+   rowid: i64,
+) -> Result<Option<Vec<ColumnValue>>> {
+   let key: Vec<String> = info
+      .pk_columns
+      .iter()
+      .map(|&idx| json_value(&quote_identifier(&info.columns[idx])))
+      .collect();
+   let sql = format!(
+      "SELECT json_array({}) FROM {} WHERE rowid = ?1",
+      key.join(", "),
+      quote_identifier(table)
+   );
+
+   let Some(json) = sqlx::query_scalar::<_, String>(&sql)
+      .bind(rowid)
+      .fetch_optional(&mut *conn)
+      .await?
+   else {
+      return Ok(None);
+   };
+   let json = serde_json::from_str(&json)
+      .map_err(|e| Error::Database(format!("invalid primary key {json}: {e}")))?;
+   decode_values(&json).map(Some)
+}
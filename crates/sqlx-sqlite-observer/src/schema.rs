@@ -7,7 +7,67 @@ use regex::Regex;
 use sqlx::{Row, SqliteConnection};
 use std::sync::OnceLock;
 
+use tracing::{debug, warn};
+
+use crate::broker::ObservationBroker;
 use crate::change::TableInfo;
+use crate::changelog::{CHANGELOG_TABLE, install_triggers};
+
+/// Brings the broker's schema information up to date.
+///
+/// Queries `TableInfo` for observed tables that don't have it yet. If the schema
+/// version changed since table info was last loaded (for example because a table
+/// was altered on a connection without observation hooks), info for every observed
+/// table is queried again, and when observing all tables, new tables are discovered.
+/// With the changelog enabled, its triggers are (re)installed for every table whose
+/// info was queried.
+pub(crate) async fn refresh_table_info(
+   conn: &mut SqliteConnection,
+   broker: &ObservationBroker,
+) -> crate::Result<()> {
+   let schema_version = query_schema_version(conn).await?;
+   let schema_changed = broker.schema_changed(schema_version);
+
+   if schema_changed && broker.observes_all_tables() {
+      let tables = query_table_names(conn).await?;
+      debug!(count = tables.len(), "Discovered tables");
+      broker.observe_tables(tables);
+   }
+
+   let mut installed_triggers = false;
+   for table in broker.get_observed_tables() {
+      if !schema_changed && broker.get_table_info(&table).is_some() {
+         continue;
+      }
+      match query_table_info(conn, &table).await {
+         Ok(Some(info)) => {
+            debug!(table = %table, pk_columns = ?info.pk_columns, without_rowid = info.without_rowid, "Queried table info");
+            if broker.changelog_enabled() && !table.eq_ignore_ascii_case(CHANGELOG_TABLE) {
+               install_triggers(conn, &table, &info).await?;
+               installed_triggers = true;
+            }
+            broker.set_table_info(&table, info);
+         }
+         Ok(None) => {
+            warn!(table = %table, "Table not found in schema");
+            broker.invalidate_table_info(&table);
+         }
+         Err(e) => {
+            warn!(table = %table, error = %e, "Failed to query table info");
+         }
+      }
+   }
+
+   // Installing triggers changes the schema itself, which shouldn't make the
+   // next acquire load everything again
+   let schema_version = if installed_triggers {
+      query_schema_version(conn).await?
+   } else {
+      schema_version
+   };
+   broker.set_schema_version(schema_version);
+   Ok(())
+}
 
 /// Queries the schema information for a table.
 ///
@@ -20,21 +80,46 @@ pub async fn query_table_info(
    // Check if table exists and get WITHOUT ROWID status
    let without_rowid = is_without_rowid(conn, table_name).await?;
 
-   // Get primary key columns using pragma_table_info()
-   let pk_columns = query_pk_columns(conn, table_name).await?;
+   // Get column names and primary key columns using pragma_table_info()
+   let columns = query_columns(conn, table_name).await?;
 
    // Determine if table exists:
-   // - If pk_columns is None, pragma_table_info returned no rows (table doesn't exist)
+   // - If columns is None, pragma_table_info returned no rows (table doesn't exist)
    // - If without_rowid is true, the table must exist (we found it in sqlite_master)
-   // - A table with no explicit PK returns Some([]), not None
-   if pk_columns.is_none() && !without_rowid {
+   // - A table with no explicit PK returns Some((names, [])), not None
+   if columns.is_none() && !without_rowid {
       return Ok(None);
    }
 
-   Ok(Some(TableInfo::new(
-      pk_columns.unwrap_or_default(),
-      without_rowid,
-   )))
+   let (names, pk_columns) = columns.unwrap_or_default();
+   Ok(Some(
+      TableInfo::new(pk_columns, without_rowid).with_columns(names),
+   ))
+}
+
+/// Queries the names of all tables except SQLite's internal `sqlite_*` tables
+/// and the changelog.
+pub async fn query_table_names(conn: &mut SqliteConnection) -> crate::Result<Vec<String>> {
+   let sql = r#"
+        SELECT name FROM sqlite_master
+        WHERE type = 'table' AND name NOT LIKE 'sqlite\_%' ESCAPE '\'
+            AND name <> ?1 COLLATE NOCASE
+        ORDER BY name
+    "#;
+
+   sqlx::query_scalar(sql)
+      .bind(CHANGELOG_TABLE)
+      .fetch_all(&mut *conn)
+      .await
+      .map_err(crate::Error::Sqlx)
+}
+
+/// Queries the schema version, which SQLite increments on every schema change.
+pub async fn query_schema_version(conn: &mut SqliteConnection) -> crate::Result<i64> {
+   sqlx::query_scalar("PRAGMA schema_version")
+      .fetch_one(&mut *conn)
+      .await
+      .map_err(crate::Error::Sqlx)
 }
 
 /// Checks if a table was created with WITHOUT ROWID.
@@ -73,19 +158,20 @@ fn has_without_rowid_clause(create_sql: &str) -> bool {
    re.is_match(create_sql)
 }
 
-/// Queries the primary key column indices for a table.
+/// Queries the column names and primary key column indices for a table.
 ///
-/// Returns column indices in the order they appear in the PRIMARY KEY definition.
+/// Names are ordered by column index. PK column indices are returned in the
+/// order they appear in the PRIMARY KEY definition.
 /// For composite primary keys, the `pk` column in PRAGMA table_info indicates
 /// the position (1-indexed) within the PK.
 ///
 /// Uses the `pragma_table_info()` table-valued function (available since SQLite
 /// 3.16.0) so the table name can be bound as a parameter instead of interpolated
 /// into the SQL string.
-async fn query_pk_columns(
+async fn query_columns(
    conn: &mut SqliteConnection,
    table_name: &str,
-) -> crate::Result<Option<Vec<usize>>> {
+) -> crate::Result<Option<(Vec<String>, Vec<usize>)>> {
    // pragma_table_info returns: cid, name, type, notnull, dflt_value, pk
    // pk is 0 for non-PK columns, or 1-indexed position for PK columns
    let sql = "SELECT cid, name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1)";
@@ -100,6 +186,9 @@ async fn query_pk_columns(
       return Ok(None); // Table doesn't exist
    }
 
+   // Rows come back in cid order
+   let names: Vec<String> = rows.iter().map(|row| row.get("name")).collect();
+
    // Collect (cid, pk_position) for columns that are part of the PK
    let mut pk_columns: Vec<(usize, i32)> = rows
       .iter()
@@ -118,7 +207,10 @@ async fn query_pk_columns(
    pk_columns.sort_by_key(|(_, pk_pos)| *pk_pos);
 
    // Return just the column indices
-   Ok(Some(pk_columns.into_iter().map(|(cid, _)| cid).collect()))
+   Ok(Some((
+      names,
+      pk_columns.into_iter().map(|(cid, _)| cid).collect(),
+   )))
 }
 
 #[cfg(test)]
@@ -1,3 +1,4 @@
+use std::collections::VecDeque;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -6,7 +7,7 @@ use tokio_stream::Stream;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::warn;
 
-use crate::change::{TableChange, TableChangeEvent};
+use crate::change::{ChangeOperation, TableChange, TableChangeEvent};
 
 /// A filtered stream of table change notifications.
 ///
@@ -15,6 +16,7 @@ use crate::change::{TableChange, TableChangeEvent};
 pub struct TableChangeStream {
    inner: BroadcastStream<TableChange>,
    filter_tables: Option<Vec<String>>,
+   filter_operations: Option<Vec<ChangeOperation>>,
 }
 
 impl TableChangeStream {
@@ -22,6 +24,7 @@ impl TableChangeStream {
       Self {
          inner: BroadcastStream::new(rx),
          filter_tables: None,
+         filter_operations: None,
       }
    }
 
@@ -29,6 +32,18 @@ impl TableChangeStream {
       self.filter_tables = Some(tables);
       self
    }
+
+   /// Only yields changes made by one of `operations`, e.g. inserts and
+   /// deletes for a list that doesn't show updated values.
+   ///
+   /// Lag is still reported.
+   pub fn filter_operations(
+      mut self,
+      operations: impl IntoIterator<Item = ChangeOperation>,
+   ) -> Self {
+      self.filter_operations = Some(operations.into_iter().collect());
+      self
+   }
 }
 
 impl Stream for TableChangeStream {
@@ -46,6 +61,13 @@ impl Stream for TableChangeStream {
                {
                   continue;
                }
+               if let Some(ref operations) = self.filter_operations
+                  && !change
+                     .operation
+                     .is_some_and(|operation| operations.contains(&operation))
+               {
+                  continue;
+               }
                return Poll::Ready(Some(TableChangeEvent::Change(change)));
             }
             Poll::Ready(Some(Err(
@@ -65,6 +87,55 @@ impl Stream for TableChangeStream {
    }
 }
 
+/// A stream that replays changes from the durable changelog, then continues
+/// with live changes.
+///
+/// Live changes already delivered by the replay, judged by their
+/// `changelog_seq`, are skipped. Live changes without a `changelog_seq` (to
+/// tables the changelog doesn't cover) are always delivered.
+pub struct ReplayStream {
+   replayed: VecDeque<TableChange>,
+   /// Highest changelog sequence number delivered by the replay
+   last_replayed: i64,
+   live: TableChangeStream,
+}
+
+impl ReplayStream {
+   pub fn new(replayed: Vec<TableChange>, after: i64, live: TableChangeStream) -> Self {
+      let last_replayed = replayed
+         .iter()
+         .filter_map(|change| change.changelog_seq)
+         .fold(after, i64::max);
+      Self {
+         replayed: replayed.into(),
+         last_replayed,
+         live,
+      }
+   }
+}
+
+impl Stream for ReplayStream {
+   type Item = TableChangeEvent;
+
+   fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+      if let Some(change) = self.replayed.pop_front() {
+         return Poll::Ready(Some(TableChangeEvent::Change(change)));
+      }
+      loop {
+         match Pin::new(&mut self.live).poll_next(cx) {
+            Poll::Ready(Some(TableChangeEvent::Change(change)))
+               if change
+                  .changelog_seq
+                  .is_some_and(|seq| seq <= self.last_replayed) =>
+            {
+               continue;
+            }
+            other => return other,
+         }
+      }
+   }
+}
+
 /// Extension trait for converting broadcast receivers into table change streams.
 ///
 /// Provides a convenient way to convert a `broadcast::Receiver<TableChange>` into
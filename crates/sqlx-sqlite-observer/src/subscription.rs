@@ -0,0 +1,309 @@
+//! Subscriptions with their own channel capacity and lag policy,
+//! callback-based subscriptions, and waiting for a single change.
+//!
+//! Subscribers from `subscribe` share one broadcast channel, so they all get
+//! [`ObserverConfig::channel_capacity`](crate::ObserverConfig::channel_capacity)
+//! and lose the oldest changes when they fall behind. A subscription created
+//! with [`SubscriptionOptions`] gets a channel of its own instead, sized and
+//! handling overflow as it needs, so a slow consumer can't cost a critical
+//! one any changes.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use crate::change::{TableChange, TableChangeEvent};
+use crate::error::Error;
+use crate::stream::TableChangeStream;
+
+/// What happens when a subscriber's channel is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+   /// Discard the oldest queued change to make room for the new one, as
+   /// `subscribe` does.
+   #[default]
+   DropOldest,
+   /// Discard the new change, keeping the queued ones.
+   DropNewest,
+   /// Close the subscription, so the consumer knows to resynchronize.
+   CloseSubscriber,
+}
+
+/// Options for a subscription with its own channel.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionOptions {
+   /// Channel capacity, or `None` for the observer's `channel_capacity`.
+   pub capacity: Option<usize>,
+   /// What to do when the channel is full.
+   pub lag_policy: LagPolicy,
+}
+
+impl SubscriptionOptions {
+   /// Creates options with the observer's channel capacity and
+   /// [`LagPolicy::DropOldest`].
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   /// Sets the channel capacity, which must be at least 1.
+   pub fn with_capacity(mut self, capacity: usize) -> Self {
+      self.capacity = Some(capacity);
+      self
+   }
+
+   /// Sets what happens when the channel is full.
+   pub fn with_lag_policy(mut self, lag_policy: LagPolicy) -> Self {
+      self.lag_policy = lag_policy;
+      self
+   }
+}
+
+/// Receives the changes of a subscription created with [`SubscriptionOptions`].
+///
+/// Whatever the lag policy, missed changes are reported the way a
+/// `broadcast::Receiver` reports them: `recv` returns
+/// [`RecvError::Lagged`] with the number missed, at the point they were missed.
+/// With [`LagPolicy::CloseSubscriber`], the queued changes are delivered, then
+/// `Lagged` once, then [`RecvError::Closed`].
+pub struct ChangeReceiver {
+   inner: Inner,
+}
+
+enum Inner {
+   Broadcast(broadcast::Receiver<TableChange>),
+   Queue {
+      rx: mpsc::Receiver<Queued>,
+      /// Change to return after reporting the lag before it
+      pending: Option<Box<TableChange>>,
+      closed_for_lag: Arc<AtomicBool>,
+   },
+}
+
+impl ChangeReceiver {
+   /// Receives the next change.
+   pub async fn recv(&mut self) -> Result<TableChange, RecvError> {
+      match &mut self.inner {
+         Inner::Broadcast(rx) => rx.recv().await,
+         Inner::Queue {
+            rx,
+            pending,
+            closed_for_lag,
+         } => {
+            if let Some(change) = pending.take() {
+               return Ok(*change);
+            }
+            match rx.recv().await {
+               Some(Queued {
+                  missed_before: 0,
+                  change,
+               }) => Ok(change),
+               Some(Queued {
+                  missed_before,
+                  change,
+               }) => {
+                  *pending = Some(Box::new(change));
+                  Err(RecvError::Lagged(missed_before))
+               }
+               None if closed_for_lag.swap(false, Ordering::Relaxed) => Err(RecvError::Lagged(1)),
+               None => Err(RecvError::Closed),
+            }
+         }
+      }
+   }
+}
+
+/// A change queued for a subscriber with the count of changes dropped before it
+struct Queued {
+   missed_before: u64,
+   change: TableChange,
+}
+
+/// The sending side of a subscription created with [`SubscriptionOptions`]
+pub(crate) struct SubscriberSender {
+   tables: Vec<String>,
+   kind: SenderKind,
+}
+
+enum SenderKind {
+   Broadcast(broadcast::Sender<TableChange>),
+   DropNewest {
+      tx: mpsc::Sender<Queued>,
+      /// Changes dropped since the last one queued
+      missed: u64,
+   },
+   CloseSubscriber {
+      tx: mpsc::Sender<Queued>,
+      closed_for_lag: Arc<AtomicBool>,
+   },
+}
+
+impl SubscriberSender {
+   /// Creates a subscription to changes to `tables`, or to every observed table
+   /// if `tables` is empty.
+   ///
+   /// # Panics
+   ///
+   /// Panics if `capacity` is 0.
+   pub(crate) fn new(
+      tables: Vec<String>,
+      capacity: usize,
+      lag_policy: LagPolicy,
+   ) -> (Self, ChangeReceiver) {
+      assert!(capacity > 0, "subscription capacity must be at least 1");
+      let (kind, inner) = match lag_policy {
+         LagPolicy::DropOldest => {
+            let (tx, rx) = broadcast::channel(capacity);
+            (SenderKind::Broadcast(tx), Inner::Broadcast(rx))
+         }
+         LagPolicy::DropNewest => {
+            let (tx, rx) = mpsc::channel(capacity);
+            let closed_for_lag = Arc::new(AtomicBool::new(false));
+            (
+               SenderKind::DropNewest { tx, missed: 0 },
+               Inner::Queue {
+                  rx,
+                  pending: None,
+                  closed_for_lag,
+               },
+            )
+         }
+         LagPolicy::CloseSubscriber => {
+            let (tx, rx) = mpsc::channel(capacity);
+            let closed_for_lag = Arc::new(AtomicBool::new(false));
+            (
+               SenderKind::CloseSubscriber {
+                  tx,
+                  closed_for_lag: Arc::clone(&closed_for_lag),
+               },
+               Inner::Queue {
+                  rx,
+                  pending: None,
+                  closed_for_lag,
+               },
+            )
+         }
+      };
+      (Self { tables, kind }, ChangeReceiver { inner })
+   }
+
+   /// Sends `change` if the subscriber wants it, returning `false` once the
+   /// subscription is closed and should be dropped.
+   pub(crate) fn send(&mut self, change: &TableChange) -> bool {
+      if !self.tables.is_empty() && !self.tables.contains(&change.table) {
+         return !self.is_closed();
+      }
+      match &mut self.kind {
+         SenderKind::Broadcast(tx) => tx.send(change.clone()).is_ok(),
+         SenderKind::DropNewest { tx, missed } => match tx.try_send(Queued {
+            missed_before: *missed,
+            change: change.clone(),
+         }) {
+            Ok(()) => {
+               *missed = 0;
+               true
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+               *missed += 1;
+               true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+         },
+         SenderKind::CloseSubscriber { tx, closed_for_lag } => match tx.try_send(Queued {
+            missed_before: 0,
+            change: change.clone(),
+         }) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(_)) => {
+               closed_for_lag.store(true, Ordering::Relaxed);
+               false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+         },
+      }
+   }
+
+   /// Whether the receiver has been dropped
+   fn is_closed(&self) -> bool {
+      match &self.kind {
+         SenderKind::Broadcast(tx) => tx.receiver_count() == 0,
+         SenderKind::DropNewest { tx, .. } | SenderKind::CloseSubscriber { tx, .. } => {
+            tx.is_closed()
+         }
+      }
+   }
+}
+
+/// Handle to a callback subscription created with `on_change`.
+///
+/// Dropping the handle leaves the callback running until the observer is
+/// dropped; call [`cancel`](Self::cancel) to stop it sooner.
+#[derive(Debug)]
+pub struct SubscriptionHandle {
+   task: JoinHandle<()>,
+}
+
+impl SubscriptionHandle {
+   /// Runs `callback` for every change `stream` yields on a spawned task.
+   ///
+   /// Lag is logged by the stream and otherwise skipped.
+   pub(crate) fn spawn<F>(mut stream: TableChangeStream, mut callback: F) -> Self
+   where
+      F: FnMut(TableChange) + Send + 'static,
+   {
+      let task = tokio::spawn(async move {
+         while let Some(event) = stream.next().await {
+            if let TableChangeEvent::Change(change) = event {
+               callback(change);
+            }
+         }
+      });
+      Self { task }
+   }
+
+   /// Stops invoking the callback.
+   ///
+   /// A callback already running finishes first.
+   pub fn cancel(&self) {
+      self.task.abort();
+   }
+
+   /// Whether the subscription has stopped, because it was cancelled, the
+   /// observer was dropped, or the callback panicked.
+   pub fn is_finished(&self) -> bool {
+      self.task.is_finished()
+   }
+}
+
+/// Waits for the first change `stream` yields that matches `predicate`.
+///
+/// Fails with [`Error::Timeout`] if none arrives within `timeout`, or if the
+/// stream ends first because the observer was dropped.
+pub(crate) async fn wait_for_change<P>(
+   mut stream: TableChangeStream,
+   table: String,
+   mut predicate: P,
+   timeout: Duration,
+) -> crate::Result<TableChange>
+where
+   P: FnMut(&TableChange) -> bool + Send + 'static,
+{
+   let matching = async {
+      while let Some(event) = stream.next().await {
+         if let TableChangeEvent::Change(change) = event
+            && predicate(&change)
+         {
+            return Some(change);
+         }
+      }
+      None
+   };
+   match tokio::time::timeout(timeout, matching).await {
+      Ok(Some(change)) => Ok(change),
+      Ok(None) | Err(_) => Err(Error::Timeout { table, timeout }),
+   }
+}
@@ -9,7 +9,9 @@
 
 use futures::StreamExt;
 use sqlx_sqlite_conn_mgr::SqliteDatabase;
-use sqlx_sqlite_observer::{ChangeOperation, ObservableSqliteDatabase, ObserverConfig};
+use sqlx_sqlite_observer::{
+   ChangeOperation, ColumnValue, ObservableSqliteDatabase, ObserverConfig,
+};
 use std::time::Duration;
 use tokio::time::timeout;
 
@@ -75,6 +77,50 @@ async fn test_observable_starts_with_configured_tables() {
    assert!(observable.observed_tables().contains(&"users".to_string()));
 }
 
+#[tokio::test]
+async fn test_observe_and_unobserve_tables_at_runtime() {
+   let test_db = setup_test_db().await;
+   let config = ObserverConfig::new().with_tables(["users"]);
+   let observable = ObservableSqliteDatabase::new(test_db.db.clone(), config);
+
+   observable.observe_table("posts");
+   let mut rx = observable.subscribe(Vec::<String>::new());
+   let mut rows_rx = observable.subscribe_rows("users", [[ColumnValue::Integer(1)]]);
+   drop(observable.acquire_writer().await.unwrap());
+   assert!(observable.broker().get_table_info("users").is_some());
+
+   assert!(observable.unobserve_table("users"));
+   assert!(!observable.unobserve_table("users"));
+   assert_eq!(observable.observed_tables(), vec!["posts".to_string()]);
+   assert!(observable.broker().get_table_info("users").is_none());
+   assert!(matches!(
+      rows_rx.recv().await,
+      Err(tokio::sync::broadcast::error::RecvError::Closed)
+   ));
+
+   let mut writer = observable.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'Hello')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.table, "posts");
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(
+      result.is_err(),
+      "Unobserved table changes are not published"
+   );
+}
+
 // ============================================================================
 // Transaction Semantics
 // ============================================================================
@@ -307,6 +353,34 @@ async fn test_cloned_observable_shares_state() {
    assert!(result.is_ok(), "Receives notification through clone");
 }
 
+// ============================================================================
+// Row Subscriptions
+// ============================================================================
+
+#[tokio::test]
+async fn test_row_subscription_ignores_other_rows() {
+   let test_db = setup_test_db().await;
+   let observable = ObservableSqliteDatabase::new(test_db.db.clone(), ObserverConfig::new());
+
+   let mut rx = observable.subscribe_rows("users", [[ColumnValue::Integer(2)]]);
+   let mut writer = observable.acquire_writer().await.unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Insert));
+   assert_eq!(change.primary_key, vec![ColumnValue::Integer(2)]);
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err(), "Changes to other rows are filtered out");
+}
+
 // ============================================================================
 // Stream API
 // ============================================================================
@@ -341,3 +415,121 @@ async fn test_stream_receives_notifications() {
       }
    }
 }
+
+// ============================================================================
+// External Changes
+// ============================================================================
+
+#[tokio::test]
+async fn test_external_write_detected_by_polling() {
+   let test_db = setup_test_db().await;
+   let config = ObserverConfig::new()
+      .with_tables(["users"])
+      .with_external_poll_interval(Duration::from_millis(10));
+   let observable = ObservableSqliteDatabase::new(test_db.db.clone(), config);
+   let mut rx = observable.subscribe_external().unwrap();
+
+   // Let the poller read its baseline data_version
+   tokio::time::sleep(Duration::from_millis(50)).await;
+
+   // Written without the observer's hooks
+   let mut writer = test_db.db.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   let change = timeout(Duration::from_millis(500), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.observed_commits, 0);
+
+   let mut writer = observable.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Bob')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   let change = timeout(Duration::from_millis(500), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.observed_commits, 1);
+}
+
+// ============================================================================
+// Callback Subscriptions
+// ============================================================================
+
+#[tokio::test]
+async fn test_on_change_invokes_callback_until_cancelled() {
+   let test_db = setup_test_db().await;
+   let observable = ObservableSqliteDatabase::new(test_db.db.clone(), ObserverConfig::new());
+
+   let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+   let handle = observable.on_change(["users"], move |change| {
+      let _ = tx.send(change.primary_key);
+   });
+
+   let mut writer = observable.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   let key = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(key, vec![ColumnValue::Integer(1)]);
+
+   handle.cancel();
+   tokio::task::yield_now().await;
+   assert!(handle.is_finished());
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Bob')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   // The callback (and its sender) was dropped with the task
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(matches!(result, Ok(None)));
+}
+
+// ============================================================================
+// Database Identity
+// ============================================================================
+
+#[tokio::test]
+async fn test_changes_identify_database_by_path() {
+   let test_db = setup_test_db().await;
+   let file_name = test_db
+      ._temp_file
+      .path()
+      .file_name()
+      .unwrap()
+      .to_str()
+      .unwrap()
+      .to_string();
+   let config = ObserverConfig::new().with_tables(["users"]);
+   let observable = ObservableSqliteDatabase::new(test_db.db.clone(), config);
+
+   let wait = observable.wait_for_change("users", |_| true, Duration::from_secs(1));
+   let mut writer = observable.acquire_writer().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Eve')")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+
+   let change = wait.await.unwrap();
+   assert!(
+      change.database.ends_with(&file_name),
+      "expected a path ending in {}, got {}",
+      file_name,
+      change.database
+   );
+}
@@ -9,8 +9,12 @@
 
 use futures::StreamExt;
 use sqlx::SqlitePool;
-use sqlx_sqlite_observer::{ChangeOperation, ColumnValue, ObserverConfig, SqliteObserver};
+use sqlx_sqlite_observer::{
+   ChangeOperation, ColumnValue, Error, LagPolicy, ObserverConfig, SqliteObserver,
+   SubscriptionOptions, TableChangeEvent,
+};
 use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::time::timeout;
 
 async fn setup_test_db() -> SqlitePool {
@@ -182,6 +186,48 @@ async fn test_multiple_changes_in_transaction() {
    }
 }
 
+#[tokio::test]
+async fn test_transaction_change_set_groups_commit() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new().with_tables(["users", "posts"]);
+   let observer = SqliteObserver::new(pool, config);
+
+   let mut rx = observer.subscribe_transactions(["users"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("BEGIN").execute(&mut **conn).await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'Hello')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("COMMIT").execute(&mut **conn).await.unwrap();
+
+   sqlx::query("UPDATE users SET name = 'Alicia' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let first = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(first.commit_seq, 1);
+   let tables: Vec<&str> = first.changes.iter().map(|c| c.table.as_str()).collect();
+   assert_eq!(tables, vec!["users", "posts"]);
+
+   let second = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(second.commit_seq, 2);
+   assert_eq!(second.changes.len(), 1);
+   assert_eq!(second.changes[0].operation, Some(ChangeOperation::Update));
+}
+
 // ============================================================================
 // CRUD Operations
 // ============================================================================
@@ -431,6 +477,37 @@ async fn test_stream_filters_tables() {
    assert!(result.is_err(), "Stream filters out non-subscribed tables");
 }
 
+#[tokio::test]
+async fn test_stream_filters_operations() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new().with_tables(["users"]);
+   let observer = SqliteObserver::new(pool, config);
+
+   let mut stream = observer
+      .subscribe_stream(["users"])
+      .filter_operations([ChangeOperation::Insert, ChangeOperation::Delete]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   for sql in [
+      "INSERT INTO users (name) VALUES ('Alice')",
+      "UPDATE users SET name = 'Alicia' WHERE id = 1",
+      "DELETE FROM users WHERE id = 1",
+   ] {
+      sqlx::query(sql).execute(&mut **conn).await.unwrap();
+   }
+
+   let mut operations = Vec::new();
+   while let Ok(Some(TableChangeEvent::Change(change))) =
+      timeout(Duration::from_millis(50), stream.next()).await
+   {
+      operations.push(change.operation);
+   }
+   assert_eq!(
+      operations,
+      vec![Some(ChangeOperation::Insert), Some(ChangeOperation::Delete)]
+   );
+}
+
 #[tokio::test]
 async fn test_stream_lag_when_capacity_exceeded() {
    let pool = setup_test_db().await;
@@ -549,6 +626,92 @@ async fn test_capture_values_disabled() {
    );
 }
 
+#[tokio::test]
+async fn test_change_serializes_to_json() {
+   let pool = setup_test_db().await;
+   sqlx::query("CREATE TABLE files (id INTEGER PRIMARY KEY, data BLOB, size REAL)")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe(["files"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO files (id, data, size) VALUES (7, x'CAFE', NULL)")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   let mut json = serde_json::to_value(&change).unwrap();
+
+   assert!(json["committedAt"].as_u64().unwrap() > 0);
+   json.as_object_mut().unwrap().remove("committedAt");
+   assert_eq!(
+      json,
+      serde_json::json!({
+         "database": ":memory:",
+         "table": "files",
+         "operation": "insert",
+         "rowid": 7,
+         "primaryKey": [{ "type": "integer", "value": 7 }],
+         "newValues": [
+            { "type": "integer", "value": 7 },
+            { "type": "blob", "value": "yv4=" },
+            { "type": "null" },
+         ],
+         "columns": ["id", "data", "size"],
+         "commitSeq": 1,
+         "commitIndex": 0,
+      })
+   );
+}
+
+#[tokio::test]
+async fn test_values_addressable_by_column_name() {
+   let pool = setup_test_db().await;
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe(["users"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("UPDATE users SET name = 'Alicia' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(&*change.columns, ["id", "name"]);
+   assert_eq!(
+      change.old_value("NAME"),
+      Some(&ColumnValue::Text("Alice".into()))
+   );
+   assert_eq!(
+      change.new_value("name"),
+      Some(&ColumnValue::Text("Alicia".into()))
+   );
+   assert_eq!(change.new_value("missing"), None);
+   assert_eq!(
+      change.named_new_values().unwrap(),
+      vec![
+         ("id", &ColumnValue::Integer(1)),
+         ("name", &ColumnValue::Text("Alicia".into())),
+      ]
+   );
+}
+
 // ============================================================================
 // Primary Key Extraction
 // ============================================================================
@@ -765,3 +928,977 @@ async fn test_delete_returns_old_primary_key() {
       "DELETE should return old PK value"
    );
 }
+
+#[tokio::test]
+async fn test_resolve_primary_keys_without_value_capture() {
+   let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+   sqlx::query("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT)")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let config = ObserverConfig::new()
+      .with_tables(["settings"])
+      .with_capture_values(false)
+      .with_resolve_primary_keys(true);
+   let observer = SqliteObserver::new(pool, config);
+
+   let mut rx = observer.subscribe(["settings"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   let theme = vec![ColumnValue::Text("theme".to_string())];
+   for (sql, operation) in [
+      (
+         "INSERT INTO settings (key, value) VALUES ('theme', 'dark')",
+         ChangeOperation::Insert,
+      ),
+      (
+         "UPDATE settings SET value = 'light' WHERE key = 'theme'",
+         ChangeOperation::Update,
+      ),
+      // The deleted row's key is read in the hook instead
+      (
+         "DELETE FROM settings WHERE key = 'theme'",
+         ChangeOperation::Delete,
+      ),
+   ] {
+      sqlx::query(sql).execute(&mut **conn).await.unwrap();
+
+      let change = timeout(Duration::from_millis(500), rx.recv())
+         .await
+         .unwrap()
+         .unwrap();
+      assert_eq!(change.operation, Some(operation));
+      assert_eq!(
+         change.primary_key, theme,
+         "{:?} should carry the key",
+         operation
+      );
+      assert!(change.old_values.is_none());
+      assert!(change.new_values.is_none());
+   }
+}
+
+#[tokio::test]
+async fn test_resolve_primary_keys_waits_for_failed_commit() {
+   use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+
+   let temp = tempfile::NamedTempFile::new().unwrap();
+   // Without WAL, a commit can't land while another connection reads
+   let options = SqliteConnectOptions::new()
+      .filename(temp.path())
+      .journal_mode(SqliteJournalMode::Delete)
+      .busy_timeout(Duration::ZERO);
+   let pool = SqlitePoolOptions::new()
+      .max_connections(3)
+      .connect_with(options)
+      .await
+      .unwrap();
+   sqlx::query("CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT)")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let config = ObserverConfig::new()
+      .with_tables(["settings"])
+      .with_capture_values(false)
+      .with_resolve_primary_keys(true);
+   let observer = SqliteObserver::new(pool.clone(), config);
+   let mut rx = observer.subscribe(["settings"]);
+   let mut conn = observer.acquire().await.unwrap();
+   let mut reader = pool.acquire().await.unwrap();
+
+   for (key, retry) in [("rolled_back", false), ("retried", true)] {
+      // The commit hook fires, but the reader's lock makes the commit fail
+      // and leaves the transaction open
+      sqlx::query("BEGIN; SELECT * FROM settings")
+         .execute(&mut *reader)
+         .await
+         .unwrap();
+      sqlx::query("BEGIN").execute(&mut **conn).await.unwrap();
+      sqlx::query("INSERT INTO settings (key, value) VALUES (?, 'x')")
+         .bind(key)
+         .execute(&mut **conn)
+         .await
+         .unwrap();
+      assert!(sqlx::query("COMMIT").execute(&mut **conn).await.is_err());
+      // Long enough for a key lookup to run if it weren't held back
+      tokio::time::sleep(Duration::from_millis(50)).await;
+
+      if retry {
+         sqlx::query("COMMIT").execute(&mut *reader).await.unwrap();
+         sqlx::query("COMMIT").execute(&mut **conn).await.unwrap();
+      } else {
+         sqlx::query("ROLLBACK").execute(&mut **conn).await.unwrap();
+         sqlx::query("COMMIT").execute(&mut *reader).await.unwrap();
+      }
+   }
+
+   // Only the retried commit is delivered
+   let change = timeout(Duration::from_millis(500), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Insert));
+   assert_eq!(
+      change.primary_key,
+      vec![ColumnValue::Text("retried".to_string())]
+   );
+   assert!(
+      timeout(Duration::from_millis(100), rx.recv())
+         .await
+         .is_err()
+   );
+}
+
+// ============================================================================
+// Row Subscriptions
+// ============================================================================
+
+#[tokio::test]
+async fn test_row_subscription_ignores_other_rows() {
+   let pool = setup_test_db().await;
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob')")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe_rows("users", [[ColumnValue::Integer(2)]]);
+   assert!(observer.observed_tables().contains(&"users".to_string()));
+
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("UPDATE users SET name = 'Alicia' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("UPDATE users SET name = 'Robert' WHERE id = 2")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Update));
+   assert_eq!(change.primary_key, vec![ColumnValue::Integer(2)]);
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err(), "Changes to other rows are filtered out");
+}
+
+#[tokio::test]
+async fn test_row_subscription_matches_composite_and_changed_keys() {
+   let pool = setup_test_db().await;
+
+   sqlx::query("CREATE TABLE memberships (org TEXT, member INTEGER, PRIMARY KEY (org, member))")
+      .execute(&pool)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO memberships VALUES ('acme', 1), ('acme', 2)")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe_rows("memberships", [["acme".into(), 1.into()]]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("DELETE FROM memberships WHERE member = 2")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   // Moving the row to a new key still notifies subscribers to the old one
+   sqlx::query("UPDATE memberships SET member = 3 WHERE member = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Update));
+   assert_eq!(
+      change.primary_key,
+      vec![ColumnValue::Text("acme".into()), ColumnValue::Integer(3)]
+   );
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err(), "Changes to other rows are filtered out");
+}
+
+// ============================================================================
+// Predicate Subscriptions
+// ============================================================================
+
+#[tokio::test]
+async fn test_predicate_subscription_filters_on_values() {
+   let pool = setup_test_db().await;
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe_where("posts", |change| {
+      change
+         .new_values
+         .as_deref()
+         .is_some_and(|values| has_text_value(values, "active"))
+   });
+   let mut all = observer.subscribe(["posts"]);
+   assert!(observer.observed_tables().contains(&"posts".to_string()));
+
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'draft'), (1, 'active')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.primary_key, vec![ColumnValue::Integer(2)]);
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(
+      result.is_err(),
+      "Changes rejected by the predicate are not sent"
+   );
+
+   // Unfiltered subscribers still see every change
+   for _ in 0..2 {
+      timeout(Duration::from_millis(100), all.recv())
+         .await
+         .unwrap()
+         .unwrap();
+   }
+}
+
+// ============================================================================
+// Column Subscriptions
+// ============================================================================
+
+#[tokio::test]
+async fn test_column_subscription_skips_updates_to_other_columns() {
+   let pool = setup_test_db().await;
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob')")
+      .execute(&pool)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'Hello')")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new().with_capture_values(false));
+   let mut rx = observer.subscribe_columns("posts", ["TITLE"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("UPDATE posts SET user_id = 2 WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   // Assigning the same value isn't a change
+   sqlx::query("UPDATE posts SET title = 'Hello' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("UPDATE posts SET title = 'Hello, world' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("DELETE FROM posts WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Update));
+   assert_eq!(change.rowid, Some(1));
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.operation, Some(ChangeOperation::Delete));
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err(), "Updates to other columns are filtered out");
+}
+
+// ============================================================================
+// Debouncing
+// ============================================================================
+
+#[tokio::test]
+async fn test_debounce_merges_successive_commits() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new()
+      .with_tables(["users", "posts"])
+      .with_debounce("users", Duration::from_millis(100));
+   let observer = SqliteObserver::new(pool, config);
+
+   let mut rx = observer.subscribe_debounced(["users"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Bob')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("UPDATE users SET name = 'Alicia' WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   // Tables without a debounce window aren't delivered here
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'Hello')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let merged = timeout(Duration::from_millis(500), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(merged.table, "users");
+   assert_eq!(merged.change_count, 3);
+   assert_eq!(merged.commit_count, 3);
+   let mut keys: Vec<i64> = merged
+      .primary_keys
+      .iter()
+      .map(|key| key[0].as_integer().unwrap())
+      .collect();
+   keys.sort();
+   assert_eq!(keys, vec![1, 2]);
+
+   let result = timeout(Duration::from_millis(200), rx.recv()).await;
+   assert!(
+      result.is_err(),
+      "All commits are merged into one notification"
+   );
+}
+
+// ============================================================================
+// Observing All Tables
+// ============================================================================
+
+#[tokio::test]
+async fn test_all_tables_discovers_tables_created_later() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new().all_tables());
+   let mut rx = observer.subscribe(Vec::<String>::new());
+
+   let mut conn = observer.acquire().await.unwrap();
+   let mut observed = observer.observed_tables();
+   observed.sort();
+   assert_eq!(observed, vec!["posts", "users"]);
+
+   sqlx::query("CREATE TABLE tags (name TEXT PRIMARY KEY)")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   drop(conn);
+
+   // Tables created since the last discovery are found on the next acquire
+   let mut conn = observer.acquire().await.unwrap();
+   assert!(observer.observed_tables().contains(&"tags".to_string()));
+
+   sqlx::query("INSERT INTO tags (name) VALUES ('rust')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.table, "tags");
+   assert_eq!(change.primary_key, vec![ColumnValue::Text("rust".into())]);
+}
+
+#[tokio::test]
+async fn test_all_tables_skips_internal_tables() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new().all_tables());
+   let mut rx = observer.subscribe(Vec::<String>::new());
+   let mut conn = observer.acquire().await.unwrap();
+
+   // AUTOINCREMENT also writes to sqlite_sequence
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.table, "users");
+
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err(), "sqlite_sequence changes are not observed");
+   assert!(
+      !observer
+         .observed_tables()
+         .iter()
+         .any(|table| table.starts_with("sqlite_"))
+   );
+}
+
+// ============================================================================
+// Schema Changes
+// ============================================================================
+
+#[tokio::test]
+async fn test_alter_table_invalidates_table_info() {
+   let pool = setup_test_db().await;
+   sqlx::query("CREATE TABLE items (note TEXT, code TEXT PRIMARY KEY)")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let observer = SqliteObserver::new(pool, ObserverConfig::new().with_tables(["items"]));
+   let mut rx = observer.subscribe(["items"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   // Moves the PK from column 1 to column 0
+   sqlx::query("ALTER TABLE items DROP COLUMN note")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO items (code) VALUES ('a')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   // The stale layout is discarded rather than used to extract the wrong PK
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert!(change.primary_key.is_empty());
+   drop(conn);
+
+   // The next acquire queries the new layout
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("INSERT INTO items (code) VALUES ('b')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.primary_key, vec![ColumnValue::Text("b".into())]);
+   assert_eq!(&*change.columns, ["code"]);
+}
+
+#[tokio::test]
+async fn test_schema_change_elsewhere_refreshes_table_info() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool.clone(), ObserverConfig::new().with_tables(["users"]));
+   let mut rx = observer.subscribe(["users"]);
+   drop(observer.acquire().await.unwrap());
+
+   // Altered on a connection without observation hooks
+   sqlx::query("ALTER TABLE users ADD COLUMN email TEXT")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("INSERT INTO users (name, email) VALUES ('Alice', 'a@example.com')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(&*change.columns, ["id", "name", "email"]);
+   assert_eq!(
+      change.new_value("email"),
+      Some(&ColumnValue::Text("a@example.com".into()))
+   );
+}
+
+// ============================================================================
+// Changelog
+// ============================================================================
+
+#[tokio::test]
+async fn test_changelog_records_committed_changes() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new()
+      .with_tables(["users"])
+      .with_changelog(true);
+   let observer = SqliteObserver::new(pool.clone(), config);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("BEGIN").execute(&mut **conn).await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Rolled back')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("ROLLBACK").execute(&mut **conn).await.unwrap();
+   drop(conn);
+
+   // Logged even without observation hooks or subscribers
+   sqlx::query("UPDATE users SET name = 'Alicia' WHERE id = 1")
+      .execute(&pool)
+      .await
+      .unwrap();
+   sqlx::query("DELETE FROM users WHERE id = 1")
+      .execute(&pool)
+      .await
+      .unwrap();
+
+   let entries = observer.changelog_since(0, 100).await.unwrap();
+   let operations: Vec<_> = entries.iter().map(|entry| entry.operation).collect();
+   assert_eq!(
+      operations,
+      vec![
+         ChangeOperation::Insert,
+         ChangeOperation::Update,
+         ChangeOperation::Delete
+      ]
+   );
+   assert!(entries.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+   assert!(entries.iter().all(|entry| entry.table == "users"));
+   assert!(
+      entries
+         .iter()
+         .all(|entry| entry.primary_key == vec![ColumnValue::Integer(1)])
+   );
+   assert_eq!(
+      entries[1].old_values,
+      Some(vec![
+         ColumnValue::Integer(1),
+         ColumnValue::Text("Alice".into())
+      ])
+   );
+   assert_eq!(
+      entries[1].new_values,
+      Some(vec![
+         ColumnValue::Integer(1),
+         ColumnValue::Text("Alicia".into())
+      ])
+   );
+   assert_eq!(entries[2].new_values, None);
+
+   let later = observer.changelog_since(entries[0].seq, 100).await.unwrap();
+   assert_eq!(later.len(), 2);
+
+   assert_eq!(observer.prune_changelog(entries[1].seq).await.unwrap(), 2);
+   let remaining = observer.changelog_since(0, 100).await.unwrap();
+   assert_eq!(remaining.len(), 1);
+   assert_eq!(remaining[0].seq, entries[2].seq);
+}
+
+#[tokio::test]
+async fn test_changelog_round_trips_value_types() {
+   let pool = setup_test_db().await;
+   sqlx::query("CREATE TABLE files (path TEXT PRIMARY KEY, size REAL, data BLOB)")
+      .execute(&pool)
+      .await
+      .unwrap();
+   let config = ObserverConfig::new()
+      .with_tables(["files"])
+      .with_changelog(true);
+   let observer = SqliteObserver::new(pool, config);
+   let mut rx = observer.subscribe(["files"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO files VALUES ('a.bin', 1.5, x'00ff'), ('b.bin', NULL, NULL)")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let entries = observer.changelog_since(0, 100).await.unwrap();
+   assert_eq!(
+      entries[0].new_values,
+      Some(vec![
+         ColumnValue::Text("a.bin".into()),
+         ColumnValue::Real(1.5),
+         ColumnValue::Blob(vec![0x00, 0xff]),
+      ])
+   );
+   assert_eq!(
+      entries[1].primary_key,
+      vec![ColumnValue::Text("b.bin".into())]
+   );
+   assert_eq!(
+      entries[1].new_values,
+      Some(vec![
+         ColumnValue::Text("b.bin".into()),
+         ColumnValue::Null,
+         ColumnValue::Null,
+      ])
+   );
+
+   // Only the observed table's changes are published, not the changelog's
+   let change = rx.recv().await.unwrap();
+   assert_eq!(change.table, "files");
+   let change = rx.recv().await.unwrap();
+   assert_eq!(change.table, "files");
+   let result = timeout(Duration::from_millis(50), rx.recv()).await;
+   assert!(result.is_err());
+}
+
+// ============================================================================
+// Changelog Replay
+// ============================================================================
+
+#[tokio::test]
+async fn test_subscribe_from_replays_then_continues_live() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new()
+      .with_tables(["users"])
+      .with_changelog(true);
+   let observer = SqliteObserver::new(pool, config);
+   let mut rx = observer.subscribe(["users"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Bob')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   // Live changes carry their changelog sequence numbers
+   let entries = observer.changelog_since(0, 100).await.unwrap();
+   let alice = rx.recv().await.unwrap();
+   assert_eq!(alice.changelog_seq, Some(entries[0].seq));
+   let bob = rx.recv().await.unwrap();
+   assert_eq!(bob.changelog_seq, Some(entries[1].seq));
+
+   let mut stream = observer
+      .subscribe_from(["users"], entries[0].seq)
+      .await
+      .unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Carol')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let mut names = Vec::new();
+   while let Ok(Some(TableChangeEvent::Change(change))) =
+      timeout(Duration::from_millis(100), stream.next()).await
+   {
+      names.push(change.new_value("name").cloned());
+   }
+   assert_eq!(
+      names,
+      vec![
+         Some(ColumnValue::Text("Bob".into())),
+         Some(ColumnValue::Text("Carol".into()))
+      ]
+   );
+}
+
+// ============================================================================
+// Subscription Options
+// ============================================================================
+
+async fn insert_users(observer: &SqliteObserver, names: &[&str]) {
+   let values: Vec<String> = names.iter().map(|name| format!("('{}')", name)).collect();
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query(&format!(
+      "INSERT INTO users (name) VALUES {}",
+      values.join(", ")
+   ))
+   .execute(&mut **conn)
+   .await
+   .unwrap();
+}
+
+fn name_of(change: &sqlx_sqlite_observer::TableChange) -> ColumnValue {
+   change.new_value("name").cloned().unwrap()
+}
+
+#[tokio::test]
+async fn test_subscription_drop_oldest() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let options = SubscriptionOptions::new().with_capacity(2);
+   let mut rx = observer.subscribe_with(["users"], options);
+   let mut critical_rx = observer.subscribe(["users"]);
+
+   insert_users(&observer, &["a", "b", "c", "d"]).await;
+
+   assert!(matches!(rx.recv().await, Err(RecvError::Lagged(2))));
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "c".into());
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "d".into());
+
+   // Other subscribers are unaffected by this one's small channel
+   for _ in 0..4 {
+      critical_rx.recv().await.unwrap();
+   }
+}
+
+#[tokio::test]
+async fn test_subscription_drop_newest() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let options = SubscriptionOptions::new()
+      .with_capacity(2)
+      .with_lag_policy(LagPolicy::DropNewest);
+   let mut rx = observer.subscribe_with(["users"], options);
+
+   insert_users(&observer, &["a", "b", "c", "d"]).await;
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "a".into());
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "b".into());
+
+   // The gap is reported where it happened, before the next change
+   insert_users(&observer, &["e"]).await;
+   assert!(matches!(rx.recv().await, Err(RecvError::Lagged(2))));
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "e".into());
+}
+
+#[tokio::test]
+async fn test_subscription_close_subscriber() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let options = SubscriptionOptions::new()
+      .with_capacity(2)
+      .with_lag_policy(LagPolicy::CloseSubscriber);
+   let mut rx = observer.subscribe_with(Vec::<String>::new(), options);
+   observer.broker().observe_tables(["users"]);
+
+   insert_users(&observer, &["a", "b", "c"]).await;
+
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "a".into());
+   assert_eq!(name_of(&rx.recv().await.unwrap()), "b".into());
+   assert!(matches!(rx.recv().await, Err(RecvError::Lagged(1))));
+   assert!(matches!(rx.recv().await, Err(RecvError::Closed)));
+}
+
+// ============================================================================
+// Commit Summaries
+// ============================================================================
+
+#[tokio::test]
+async fn test_commit_summary_counts_operations_per_table() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe_summaries(["users", "posts"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("BEGIN").execute(&mut **conn).await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob'), ('Carol')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("UPDATE users SET name = 'Bobby' WHERE id = 2")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("INSERT INTO posts (user_id, title) VALUES (1, 'Hello')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("COMMIT").execute(&mut **conn).await.unwrap();
+
+   let summary = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(summary.commit_seq, 1);
+   assert_eq!(summary.tables["users"].insert, 3);
+   assert_eq!(summary.tables["users"].update, 1);
+   assert_eq!(summary.tables["users"].total(), 4);
+   assert_eq!(summary.tables["posts"].insert, 1);
+
+   assert_eq!(
+      serde_json::to_value(&summary).unwrap(),
+      serde_json::json!({
+         "commitSeq": 1,
+         "tables": {
+            "posts": {"insert": 1},
+            "users": {"insert": 3, "update": 1}
+         }
+      })
+   );
+}
+
+// ============================================================================
+// Commit Sequence Numbers
+// ============================================================================
+
+#[tokio::test]
+async fn test_changes_carry_commit_seq_and_index() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe(["users"]);
+   let mut conn = observer.acquire().await.unwrap();
+
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+   sqlx::query("DELETE FROM users WHERE id = 1")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let mut positions = Vec::new();
+   for _ in 0..3 {
+      let change = rx.recv().await.unwrap();
+      positions.push((change.commit_seq, change.commit_index));
+   }
+   assert_eq!(positions, vec![(1, 0), (1, 1), (2, 0)]);
+}
+
+// ============================================================================
+// Waiting for Changes
+// ============================================================================
+
+#[tokio::test]
+async fn test_wait_for_change_returns_first_match() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let wait = observer.wait_for_change(
+      "users",
+      |change| {
+         change.new_values.as_ref().and_then(|v| v.get(1)) == Some(&ColumnValue::Text("Bob".into()))
+      },
+      Duration::from_secs(5),
+   );
+
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice'), ('Bob')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   let change = wait.await.unwrap();
+   assert_eq!(change.rowid, Some(2));
+}
+
+#[tokio::test]
+async fn test_wait_for_change_times_out() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let wait = observer.wait_for_change(
+      "users",
+      |change| change.operation == Some(ChangeOperation::Delete),
+      Duration::from_millis(50),
+   );
+
+   let mut conn = observer.acquire().await.unwrap();
+   sqlx::query("INSERT INTO users (name) VALUES ('Alice')")
+      .execute(&mut **conn)
+      .await
+      .unwrap();
+
+   match wait.await {
+      Err(Error::Timeout { table, .. }) => assert_eq!(table, "users"),
+      other => panic!("expected a timeout, got {:?}", other),
+   }
+}
+
+// ============================================================================
+// Pausing and Resuming
+// ============================================================================
+
+#[tokio::test]
+async fn test_pause_discards_changes_and_resyncs_on_resume() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe(["users"]);
+   let mut resyncs = observer.subscribe_resyncs();
+
+   observer.pause();
+   assert!(observer.is_paused());
+   insert_users(&observer, &["Alice", "Bob", "Carol"]).await;
+   observer.resume();
+   assert!(!observer.is_paused());
+
+   let resync = timeout(Duration::from_millis(100), resyncs.recv())
+      .await
+      .expect("resync should be sent")
+      .unwrap();
+   assert_eq!(resync.tables, vec!["users".to_string()]);
+   assert_eq!(resync.change_count, 3);
+   assert!(
+      rx.try_recv().is_err(),
+      "paused changes should not be published"
+   );
+
+   // Publishing continues after resuming
+   insert_users(&observer, &["Dave"]).await;
+   let change = timeout(Duration::from_millis(100), rx.recv())
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(change.rowid, Some(4));
+}
+
+#[tokio::test]
+async fn test_pause_with_buffer_publishes_buffered_changes() {
+   let pool = setup_test_db().await;
+   let observer = SqliteObserver::new(pool, ObserverConfig::new());
+   let mut rx = observer.subscribe(["users"]);
+   let mut resyncs = observer.subscribe_resyncs();
+
+   observer.pause_with_buffer(2);
+   insert_users(&observer, &["Alice", "Bob"]).await;
+   assert!(
+      rx.try_recv().is_err(),
+      "changes should be held while paused"
+   );
+   observer.resume();
+
+   for rowid in [1, 2] {
+      let change = rx.try_recv().unwrap();
+      assert_eq!(change.rowid, Some(rowid));
+   }
+   assert!(resyncs.try_recv().is_err());
+
+   // Overflowing the buffer falls back to a resync
+   observer.pause_with_buffer(2);
+   insert_users(&observer, &["Carol", "Dave", "Eve"]).await;
+   observer.resume();
+   assert!(rx.try_recv().is_err());
+   assert_eq!(resyncs.try_recv().unwrap().change_count, 3);
+}
+
+// ============================================================================
+// Database Identity
+// ============================================================================
+
+#[tokio::test]
+async fn test_changes_carry_configured_database_name() {
+   let pool = setup_test_db().await;
+   let config = ObserverConfig::new().with_database_name("main-db");
+   let observer = SqliteObserver::new(pool, config);
+   let wait = observer.wait_for_change("users", |_| true, Duration::from_secs(1));
+
+   insert_users(&observer, &["Alice"]).await;
+
+   assert_eq!(wait.await.unwrap().database, "main-db");
+}
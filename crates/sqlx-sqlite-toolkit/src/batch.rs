@@ -0,0 +1,249 @@
+//! Write batching: coalescing many small `execute` calls into one transaction
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value as JsonValue;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::wrapper::{DatabaseWrapper, WriteQueryResult, WriterGuard, bind_value};
+use crate::{Error, Result};
+
+/// Default time a batch stays open for more writes after its first one.
+pub const DEFAULT_BATCH_WINDOW: Duration = Duration::from_millis(5);
+
+/// Default number of writes that closes a batch early.
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Configuration for [`DatabaseWrapper::enable_write_batching`].
+#[derive(Debug, Clone, Copy)]
+pub struct WriteBatchConfig {
+   window: Duration,
+   max_batch_size: usize,
+}
+
+impl Default for WriteBatchConfig {
+   fn default() -> Self {
+      Self {
+         window: DEFAULT_BATCH_WINDOW,
+         max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+      }
+   }
+}
+
+impl WriteBatchConfig {
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   /// How long a batch waits for more writes after its first one arrives.
+   /// Each write can be delayed by up to this long.
+   pub fn with_window(mut self, window: Duration) -> Self {
+      self.window = window;
+      self
+   }
+
+   /// Commit a batch as soon as it holds this many writes, without waiting
+   /// out the window. Values below 1 are treated as 1.
+   pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+      self.max_batch_size = max_batch_size.max(1);
+      self
+   }
+
+   pub fn window(&self) -> Duration {
+      self.window
+   }
+
+   pub fn max_batch_size(&self) -> usize {
+      self.max_batch_size
+   }
+}
+
+/// Receives a batched write's result once its batch has committed
+type BatchReply = oneshot::Receiver<Result<WriteQueryResult>>;
+
+/// A write waiting for its batch to commit
+struct BatchedWrite {
+   db: DatabaseWrapper,
+   query: String,
+   values: Vec<JsonValue>,
+   reply: oneshot::Sender<Result<WriteQueryResult>>,
+}
+
+/// Background task that commits queued writes in batches.
+///
+/// Each write runs inside its own savepoint, so one failing statement is
+/// rolled back and reported to its caller alone while the rest of the batch
+/// commits. Callers get their result only once the batch has committed.
+pub(crate) struct WriteBatcher {
+   sender: Mutex<Option<mpsc::UnboundedSender<BatchedWrite>>>,
+   task: tokio::sync::Mutex<Option<JoinHandle<()>>>,
+}
+
+impl WriteBatcher {
+   pub(crate) fn spawn(config: WriteBatchConfig) -> Self {
+      let (sender, receiver) = mpsc::unbounded_channel();
+      let task = tokio::spawn(run_batches(receiver, config));
+
+      Self {
+         sender: Mutex::new(Some(sender)),
+         task: tokio::sync::Mutex::new(Some(task)),
+      }
+   }
+
+   /// Queue a write, handing the query and values back once the batcher has
+   /// been shut down
+   pub(crate) fn submit(
+      &self,
+      db: DatabaseWrapper,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> std::result::Result<BatchReply, (String, Vec<JsonValue>)> {
+      let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner());
+      let Some(sender) = sender.as_ref() else {
+         return Err((query, values));
+      };
+
+      let (reply, receiver) = oneshot::channel();
+      let write = BatchedWrite {
+         db,
+         query,
+         values,
+         reply,
+      };
+      match sender.send(write) {
+         Ok(()) => Ok(receiver),
+         Err(mpsc::error::SendError(write)) => Err((write.query, write.values)),
+      }
+   }
+
+   /// Stop accepting writes and wait for the queued ones to commit
+   pub(crate) async fn shutdown(&self) {
+      self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+      if let Some(task) = self.task.lock().await.take()
+         && let Err(err) = task.await
+      {
+         warn!("write batching task failed: {err}");
+      }
+   }
+}
+
+async fn run_batches(
+   mut receiver: mpsc::UnboundedReceiver<BatchedWrite>,
+   config: WriteBatchConfig,
+) {
+   while let Some(first) = receiver.recv().await {
+      let mut batch = vec![first];
+      let deadline = tokio::time::Instant::now() + config.window;
+
+      while batch.len() < config.max_batch_size {
+         match tokio::time::timeout_at(deadline, receiver.recv()).await {
+            Ok(Some(write)) => batch.push(write),
+            // Window over, or every sender gone
+            _ => break,
+         }
+      }
+
+      commit_batch(batch).await;
+   }
+
+   debug!("Write batching stopped");
+}
+
+async fn commit_batch(batch: Vec<BatchedWrite>) {
+   debug!("Committing batch of {} write(s)", batch.len());
+
+   // Every write in the batch targets the same database; the first one's
+   // wrapper also carries its current observer, if any.
+   let mut writer = match batch[0].db.acquire_writer().await {
+      Ok(writer) => writer,
+      Err(err) => return fail_all(batch, &err),
+   };
+
+   if let Err(err) = sqlx::query("BEGIN IMMEDIATE").execute(&mut *writer).await {
+      return fail_all(batch, &err.into());
+   }
+
+   let mut pending = Vec::with_capacity(batch.len());
+   for write in batch {
+      match run_isolated(&mut writer, write.query, write.values).await {
+         Ok(result) => pending.push((write.reply, result)),
+         Err(err) => {
+            // The savepoint itself failed, so the transaction's state is
+            // unknown; give up on the whole batch.
+            let _ = sqlx::query("ROLLBACK").execute(&mut *writer).await;
+            let _ = write.reply.send(Err(batch_failed(&err)));
+            for (reply, _) in pending {
+               let _ = reply.send(Err(batch_failed(&err)));
+            }
+            return;
+         }
+      }
+   }
+
+   match sqlx::query("COMMIT").execute(&mut *writer).await {
+      Ok(_) => {
+         // A caller that stopped waiting just misses its result; its write
+         // has still committed.
+         for (reply, result) in pending {
+            let _ = reply.send(result);
+         }
+      }
+      Err(err) => {
+         let _ = sqlx::query("ROLLBACK").execute(&mut *writer).await;
+         let err = Error::from(err);
+         for (reply, _) in pending {
+            let _ = reply.send(Err(batch_failed(&err)));
+         }
+      }
+   }
+}
+
+/// Run one write inside a savepoint so its failure leaves the rest of the
+/// batch intact. The outer error means the savepoint couldn't be managed.
+async fn run_isolated(
+   writer: &mut WriterGuard,
+   query: String,
+   values: Vec<JsonValue>,
+) -> Result<Result<WriteQueryResult>> {
+   sqlx::query("SAVEPOINT batched_write")
+      .execute(&mut **writer)
+      .await?;
+
+   let mut q = sqlx::query(&query);
+   for value in values {
+      q = bind_value(q, value);
+   }
+
+   let result = match q.execute(&mut **writer).await {
+      Ok(result) => Ok(WriteQueryResult {
+         rows_affected: result.rows_affected(),
+         last_insert_id: result.last_insert_rowid(),
+      }),
+      Err(err) => {
+         sqlx::query("ROLLBACK TO batched_write")
+            .execute(&mut **writer)
+            .await?;
+         Err(err.into())
+      }
+   };
+
+   sqlx::query("RELEASE batched_write")
+      .execute(&mut **writer)
+      .await?;
+   Ok(result)
+}
+
+fn batch_failed(err: &Error) -> Error {
+   Error::WriteBatchFailed(err.to_string())
+}
+
+fn fail_all(batch: Vec<BatchedWrite>, err: &Error) {
+   warn!("write batch failed: {err}");
+   for write in batch {
+      let _ = write.reply.send(Err(batch_failed(err)));
+   }
+}
@@ -0,0 +1,132 @@
+//! Expansion of array binds used as `IN` lists
+//!
+//! SQLite has no array type, so `WHERE id IN (…)` with a dynamic list normally
+//! means building the placeholder list by hand. Instead, a single placeholder may
+//! follow `IN` directly, without parentheses:
+//!
+//! ```sql
+//! SELECT * FROM users WHERE id IN $1
+//! ```
+//!
+//! Such placeholders are rewritten to `IN (SELECT value FROM json_each($1))`.
+//! Binding a JSON array to `$1` (arrays are bound as JSON text) then matches any
+//! of its elements. Numbering and order of all placeholders stay unchanged, so the
+//! rewrite is transparent to the caller's bind values.
+
+use crate::pagination::{is_keyword_at, skip_block_comment, skip_line_comment, skip_quoted};
+
+/// Rewrite every `IN <placeholder>` in `query` to select from `json_each`.
+///
+/// Placeholders may be `?`, `?NNN`, `$NNN`, `:name`, `@name` or `$name`.
+/// Quoted literals, identifiers and comments are left alone. Returns the query
+/// as-is when there is nothing to expand.
+pub(crate) fn expand_in_lists(query: String) -> String {
+   let upper = query.to_ascii_uppercase();
+   let bytes = upper.as_bytes();
+   let len = bytes.len();
+   let mut expanded = String::new();
+   let mut copied = 0;
+   let mut i = 0;
+
+   while i < len {
+      match bytes[i] {
+         b'\'' | b'"' => i = skip_quoted(bytes, len, i, bytes[i]),
+         b'-' if i + 1 < len && bytes[i + 1] == b'-' => i = skip_line_comment(bytes, len, i),
+         b'/' if i + 1 < len && bytes[i + 1] == b'*' => i = skip_block_comment(bytes, len, i),
+         _ if is_keyword_at(bytes, len, i, b"IN") => {
+            let mut start = i + 2;
+            while start < len && bytes[start].is_ascii_whitespace() {
+               start += 1;
+            }
+
+            if let Some(end) = placeholder_end(bytes, start) {
+               expanded.push_str(&query[copied..start]);
+               expanded.push_str("(SELECT value FROM json_each(");
+               expanded.push_str(&query[start..end]);
+               expanded.push_str("))");
+               copied = end;
+               i = end;
+               continue;
+            }
+         }
+         _ => {}
+      }
+      i += 1;
+   }
+
+   if copied == 0 {
+      return query;
+   }
+
+   expanded.push_str(&query[copied..]);
+   expanded
+}
+
+/// If a bind placeholder starts at `start`, return the offset just past it.
+fn placeholder_end(bytes: &[u8], start: usize) -> Option<usize> {
+   let len = bytes.len();
+   let is_name_char = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+
+   let (first, rest_ok): (u8, fn(u8) -> bool) = match bytes.get(start)? {
+      b'?' => (b'?', |b| b.is_ascii_digit()),
+      b'$' | b':' | b'@' => (bytes[start], |b| b.is_ascii_alphanumeric() || b == b'_'),
+      _ => return None,
+   };
+
+   let mut end = start + 1;
+   while end < len && rest_ok(bytes[end]) {
+      end += 1;
+   }
+
+   // Named and numbered placeholders need at least one character after the
+   // prefix; a bare `?` stands on its own
+   if first != b'?' && end == start + 1 {
+      return None;
+   }
+
+   // Reject things like `?1abc` that aren't a complete placeholder
+   if end < len && is_name_char(bytes[end]) {
+      return None;
+   }
+
+   Some(end)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn expand(query: &str) -> String {
+      expand_in_lists(query.to_string())
+   }
+
+   #[test]
+   fn expands_placeholders_after_in() {
+      assert_eq!(
+         expand("SELECT * FROM t WHERE id IN $1 AND kind NOT IN ?2"),
+         "SELECT * FROM t WHERE id IN (SELECT value FROM json_each($1)) \
+          AND kind NOT IN (SELECT value FROM json_each(?2))"
+      );
+      assert_eq!(
+         expand("DELETE FROM t WHERE id in\n  :ids"),
+         "DELETE FROM t WHERE id in\n  (SELECT value FROM json_each(:ids))"
+      );
+      assert_eq!(
+         expand("SELECT * FROM t WHERE a = ? AND b IN ?"),
+         "SELECT * FROM t WHERE a = ? AND b IN (SELECT value FROM json_each(?))"
+      );
+   }
+
+   #[test]
+   fn leaves_other_queries_untouched() {
+      for query in [
+         "SELECT * FROM t WHERE id IN ($1, $2)",
+         "SELECT * FROM t WHERE id IN (SELECT id FROM u)",
+         "SELECT 'IN $1' AS s, \"IN ?\" FROM t -- IN $2",
+         "SELECT * FROM t /* IN $1 */ WHERE x = $1",
+         "SELECT * FROM login WHERE id = $1",
+      ] {
+         assert_eq!(expand(query), query);
+      }
+   }
+}
@@ -1,23 +1,53 @@
 //! Query builders with attached database support
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::{Future, IntoFuture};
+use std::hash::Hash;
+use std::marker::PhantomData;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
 use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
 use sqlx_sqlite_conn_mgr::AttachedSpec;
+use tokio::sync::mpsc;
+use tracing::warn;
 
 use crate::Error;
-use crate::pagination::{KeysetColumn, KeysetPage, build_paginated_query};
-use crate::wrapper::{DatabaseWrapper, WriteQueryResult, bind_value};
+use crate::binds::expand_in_lists;
+use crate::decode::DecodeOptions;
+use crate::describe::{DeclaredTypes, DescribedColumn, describe_columns};
+use crate::fts::{FtsIndex, RANK_COLUMN, ROWID_COLUMN};
+use crate::pagination::{
+   Cursor, KeysetColumn, KeysetPage, WINDOW_TOTAL_COLUMN, build_count_query, build_paginated_query,
+   build_preceding_queries, build_row_lookup_query, build_window_total_query, decode_cursor_token,
+   encode_cursor_token,
+};
+use crate::pragma::{ReaderConnection, with_pragmas};
+use crate::retry::{Retried, RetryPolicy, with_retry};
+use crate::select::{CompareOp, Condition, column_sql, condition_sql, unqualified_sql};
+use crate::timeout::QueryDeadline;
+use crate::wrapper::{
+   ColumnType, ColumnarRows, DatabaseWrapper, RowsWithColumnTypes, WriteQueryResult,
+   WriteReturningResult, bind_value,
+};
 
 /// Builder for SELECT queries returning multiple rows
+#[derive(Clone)]
 pub struct FetchAllBuilder {
    db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
    query: String,
+   /// The query as given, when `query` has soft-deleted rows filtered out
+   unfiltered: Option<String>,
    values: Vec<JsonValue>,
    attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+   pragmas: Vec<(String, String)>,
+   decode: DecodeOptions,
 }
 
 impl FetchAllBuilder {
@@ -28,9 +58,13 @@ impl FetchAllBuilder {
    ) -> Self {
       Self {
          db,
-         query,
+         query: expand_in_lists(query),
+         unfiltered: None,
          values,
          attached: Vec::new(),
+         timeout: None,
+         pragmas: Vec::new(),
+         decode: DecodeOptions::default(),
       }
    }
 
@@ -40,28 +74,101 @@ impl FetchAllBuilder {
       self
    }
 
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Run the query on a dedicated read connection with PRAGMA `name` set to
+   /// `value`, e.g. a larger `cache_size` for an analytics query
+   ///
+   /// The pragma's previous value is restored before the connection goes back
+   /// to the pool. Call again to override several pragmas.
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.pragmas.push((name.into(), value.to_string()));
+      self
+   }
+
+   /// Decode this query's rows with `options` instead of the database's
+   /// [`DecodeOptions`]. The database's decoders still apply after any in
+   /// `options`.
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.decode = options.with_decoders_from(&self.decode);
+      self
+   }
+
+   /// Return soft-deleted rows too, skipping the filter set up with
+   /// [`DatabaseWrapper::enable_soft_delete`]
+   pub fn include_deleted(mut self) -> Self {
+      if let Some(query) = self.unfiltered.take() {
+         self.query = query;
+      }
+      self
+   }
+
+   /// Run `filtered` instead of the query given, keeping the original for
+   /// [`include_deleted`](Self::include_deleted)
+   pub(crate) fn filtered(mut self, filtered: Option<String>) -> Self {
+      if let Some(filtered) = filtered {
+         self.unfiltered = Some(std::mem::replace(
+            &mut self.query,
+            expand_in_lists(filtered),
+         ));
+      }
+      self
+   }
+
    /// Execute the query and return all matching rows
    pub async fn execute(self) -> Result<Vec<IndexMap<String, JsonValue>>, Error> {
+      let deadline = QueryDeadline::start(self.timeout);
+
       if self.attached.is_empty() {
-         // No attached databases - use regular read pool
-         let pool = self.db.read_pool()?;
          let mut q = sqlx::query(&self.query);
          for value in self.values {
             q = bind_value(q, value);
          }
-         let rows = q.fetch_all(pool).await?;
-         Ok(decode_rows(rows)?)
+         let (rows, types) = if self.timeout.is_none() && self.pragmas.is_empty() {
+            // No attached databases, time limit or pragmas - use regular read pool
+            let mut conn = self.db.read_pool()?.acquire().await?;
+            let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+            (q.fetch_all(&mut *conn).await?, types)
+         } else {
+            // Use a dedicated reader so a timeout interrupts only this query
+            // and pragma overrides affect only this query
+            let mut conn = deadline.acquire(self.db.acquire_reader()).await?;
+            let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+            let interrupt = conn.interrupt_handle();
+            let rows = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+               deadline.run(interrupt, q.fetch_all(conn)).await
+            })
+            .await?;
+            (rows, types)
+         };
+         Ok(decode_rows(&rows, &self.decode, &types)?)
       } else {
          // With attached database(s) - acquire reader with attached database(s)
-         let mut conn =
-            sqlx_sqlite_conn_mgr::acquire_reader_with_attached(&self.db, self.attached).await?;
+         let mut conn = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+               &self.db,
+               self.attached,
+            ))
+            .await?;
 
+         let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
          let mut q = sqlx::query(&self.query);
          for value in self.values {
             q = bind_value(q, value);
          }
-         let rows = sqlx::Executor::fetch_all(&mut *conn, q).await?;
-         let result = decode_rows(rows)?;
+         let interrupt = conn.interrupt_handle();
+         let rows = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+            deadline
+               .run(interrupt, sqlx::Executor::fetch_all(conn, q))
+               .await
+         })
+         .await?;
+         let result = decode_rows(&rows, &self.decode, &types)?;
 
          // Explicit cleanup
          conn.detach_all().await?;
@@ -70,6 +177,131 @@ impl FetchAllBuilder {
    }
 }
 
+impl FetchAllBuilder {
+   /// Stream matching rows as they are read instead of buffering them all
+   ///
+   /// Rows are decoded one at a time on a background task and handed over
+   /// through a small buffer, so memory stays flat for large exports. The query
+   /// starts on the first poll; dropping the stream stops it and releases the
+   /// connection.
+   ///
+   /// A [`timeout`](Self::timeout) limits the whole stream, including time the
+   /// consumer spends between rows: once it passes, the query is interrupted
+   /// and the stream ends with [`Error::QueryTimeout`].
+   pub fn fetch_stream(self) -> BoxStream<'static, Result<IndexMap<String, JsonValue>, Error>> {
+      stream::once(async move {
+         let (tx, rx) = mpsc::channel(FETCH_STREAM_BUFFER);
+         tokio::spawn(async move {
+            if let Err(err) = self.stream_into(&tx).await {
+               let _ = tx.send(Err(err)).await;
+            }
+         });
+         rx
+      })
+      .flat_map(|rx| {
+         stream::unfold(
+            rx,
+            |mut rx| async move { rx.recv().await.map(|row| (row, rx)) },
+         )
+      })
+      .boxed()
+   }
+
+   async fn stream_into(self, tx: &RowSender) -> Result<(), Error> {
+      let deadline = QueryDeadline::start(self.timeout);
+      let mut q = sqlx::query(&self.query);
+      for value in self.values {
+         q = bind_value(q, value);
+      }
+
+      if self.attached.is_empty() && self.timeout.is_none() && self.pragmas.is_empty() {
+         let mut conn = self.db.read_pool()?.acquire().await?;
+         let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+         forward_rows(q.fetch(&mut *conn), &self.decode, &types, tx).await
+      } else if self.attached.is_empty() {
+         let mut conn = deadline.acquire(self.db.acquire_reader()).await?;
+         let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+         let interrupt = conn.interrupt_handle();
+         with_pragmas(&mut conn, &self.pragmas, async |conn| {
+            deadline
+               .run(
+                  interrupt,
+                  forward_rows(q.fetch(conn), &self.decode, &types, tx),
+               )
+               .await
+         })
+         .await
+      } else {
+         let mut conn = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+               &self.db,
+               self.attached,
+            ))
+            .await?;
+         let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+         let interrupt = conn.interrupt_handle();
+         let result = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+            deadline
+               .run(
+                  interrupt,
+                  forward_rows(sqlx::Executor::fetch(conn, q), &self.decode, &types, tx),
+               )
+               .await
+         })
+         .await;
+
+         // Explicit cleanup, also when the consumer stopped early
+         conn.detach_all().await?;
+         result
+      }
+   }
+}
+
+/// Number of decoded rows `fetch_stream` buffers ahead of the consumer
+const FETCH_STREAM_BUFFER: usize = 64;
+
+type RowSender = mpsc::Sender<Result<IndexMap<String, JsonValue>, Error>>;
+
+/// Decode rows from `rows` into `tx` until the rows run out, a row fails to
+/// decode, or the receiving stream is dropped
+async fn forward_rows(
+   mut rows: impl Stream<Item = Result<sqlx::sqlite::SqliteRow, sqlx::Error>> + Unpin,
+   options: &DecodeOptions,
+   types: &DeclaredTypes,
+   tx: &RowSender,
+) -> Result<(), Error> {
+   let mut lossy_columns = Vec::new();
+   while let Some(row) = rows.try_next().await? {
+      let decoded = decode_row_checked(&row, options, types, &mut lossy_columns);
+      let failed = decoded.is_err();
+      if tx.send(decoded).await.is_err() || failed {
+         break;
+      }
+   }
+   Ok(())
+}
+
+impl FetchAllBuilder {
+   /// Return the rows as a [`ColumnarRows`] table instead of one map per row
+   pub fn columnar(self) -> FetchColumnarBuilder {
+      FetchColumnarBuilder { inner: self }
+   }
+
+   /// Return the rows along with each column's declared type and affinity
+   pub fn with_column_types(self) -> FetchWithColumnTypesBuilder {
+      FetchWithColumnTypesBuilder { inner: self }
+   }
+
+   /// Retry the query with backoff while it fails with `SQLITE_BUSY` or
+   /// `SQLITE_LOCKED`
+   pub fn retry(self, policy: RetryPolicy) -> RetryFetchAllBuilder {
+      RetryFetchAllBuilder {
+         inner: self,
+         policy,
+      }
+   }
+}
+
 impl IntoFuture for FetchAllBuilder {
    type Output = Result<Vec<IndexMap<String, JsonValue>>, Error>;
    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
@@ -79,12 +311,261 @@ impl IntoFuture for FetchAllBuilder {
    }
 }
 
+/// Builder for SELECT queries returning their rows in columnar form
+///
+/// Created by [`FetchAllBuilder::columnar`].
+pub struct FetchColumnarBuilder {
+   inner: FetchAllBuilder,
+}
+
+impl FetchColumnarBuilder {
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner = self.inner.attach(attached);
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner = self.inner.timeout(timeout);
+      self
+   }
+
+   /// Override a PRAGMA for this query only; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.inner = self.inner.pragma(name, value);
+      self
+   }
+
+   /// Decode this query's rows with `options`; see
+   /// [`FetchAllBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.inner = self.inner.decode_options(options);
+      self
+   }
+
+   /// Execute the query and return its column names and row values
+   ///
+   /// Column names are reported even when no rows match.
+   pub async fn execute(self) -> Result<ColumnarRows, Error> {
+      use sqlx::Row;
+
+      let (rows, columns, decode) = fetch_described(self.inner).await?;
+      let types = DeclaredTypes::of(&columns);
+      let columns: Vec<String> = columns
+         .iter()
+         .map(|c| decode.column_name_format().apply(&c.name))
+         .collect();
+
+      let mut lossy_columns = Vec::new();
+      let rows = rows
+         .iter()
+         .map(|row| {
+            row.columns()
+               .iter()
+               .enumerate()
+               .map(|(i, column)| {
+                  let (value, lossy) = crate::decode::to_json_checked(
+                     row.try_get_raw(i)?,
+                     column,
+                     types.get(i),
+                     &decode,
+                  )?;
+                  if lossy && let Some(name) = columns.get(i) {
+                     note_lossy(&mut lossy_columns, name);
+                  }
+                  Ok(value)
+               })
+               .collect::<Result<Vec<_>, Error>>()
+         })
+         .collect::<Result<_, _>>()?;
+
+      Ok(ColumnarRows {
+         columns,
+         rows,
+         lossy_columns,
+      })
+   }
+}
+
+impl IntoFuture for FetchColumnarBuilder {
+   type Output = Result<ColumnarRows, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for SELECT queries returning their rows along with each column's
+/// type
+///
+/// Created by [`FetchAllBuilder::with_column_types`].
+pub struct FetchWithColumnTypesBuilder {
+   inner: FetchAllBuilder,
+}
+
+impl FetchWithColumnTypesBuilder {
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner = self.inner.attach(attached);
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner = self.inner.timeout(timeout);
+      self
+   }
+
+   /// Override a PRAGMA for this query only; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.inner = self.inner.pragma(name, value);
+      self
+   }
+
+   /// Decode this query's rows with `options`; see
+   /// [`FetchAllBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.inner = self.inner.decode_options(options);
+      self
+   }
+
+   /// Execute the query and return its rows and column types
+   ///
+   /// Column types are reported even when no rows match.
+   pub async fn execute(self) -> Result<RowsWithColumnTypes, Error> {
+      let (rows, columns, decode) = fetch_described(self.inner).await?;
+      let types = DeclaredTypes::of(&columns);
+
+      let mut lossy_columns = Vec::new();
+      let rows = rows
+         .iter()
+         .map(|row| decode_row_checked(row, &decode, &types, &mut lossy_columns))
+         .collect::<Result<_, _>>()?;
+
+      Ok(RowsWithColumnTypes {
+         columns: columns
+            .iter()
+            .map(|c| ColumnType::new(c, &decode))
+            .collect(),
+         rows,
+         lossy_columns,
+      })
+   }
+}
+
+impl IntoFuture for FetchWithColumnTypesBuilder {
+   type Output = Result<RowsWithColumnTypes, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Run a [`FetchAllBuilder`]'s query and return its rows along with the
+/// result columns, which are described even when no rows match
+async fn fetch_described(
+   inner: FetchAllBuilder,
+) -> Result<
+   (
+      Vec<sqlx::sqlite::SqliteRow>,
+      Vec<DescribedColumn>,
+      DecodeOptions,
+   ),
+   Error,
+> {
+   let FetchAllBuilder {
+      db,
+      query,
+      values,
+      attached,
+      timeout,
+      pragmas,
+      decode,
+      ..
+   } = inner;
+   let deadline = QueryDeadline::start(timeout);
+
+   let mut q = sqlx::query(&query);
+   for value in values {
+      q = bind_value(q, value);
+   }
+
+   if attached.is_empty() {
+      let mut conn = deadline.acquire(db.acquire_reader()).await?;
+      let interrupt = conn.interrupt_handle();
+      let rows = with_pragmas(&mut conn, &pragmas, async |conn| {
+         deadline.run(interrupt, q.fetch_all(conn)).await
+      })
+      .await?;
+      let columns = describe_columns(&mut conn, &query).await?;
+      Ok((rows, columns, decode))
+   } else {
+      // With attached database(s) - acquire reader with attached database(s)
+      let mut conn = deadline
+         .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+            &db, attached,
+         ))
+         .await?;
+      let interrupt = conn.interrupt_handle();
+      let rows = with_pragmas(&mut conn, &pragmas, async |conn| {
+         deadline
+            .run(interrupt, sqlx::Executor::fetch_all(conn, q))
+            .await
+      })
+      .await?;
+      let columns = describe_columns(&mut conn, &query).await?;
+
+      // Explicit cleanup
+      conn.detach_all().await?;
+      Ok((rows, columns, decode))
+   }
+}
+
+/// Builder for SELECT queries retried while the database is busy
+///
+/// Created by [`FetchAllBuilder::retry`].
+pub struct RetryFetchAllBuilder {
+   inner: FetchAllBuilder,
+   policy: RetryPolicy,
+}
+
+impl RetryFetchAllBuilder {
+   /// Execute the query, retrying per the policy, and return all matching rows
+   /// along with the number of attempts made
+   ///
+   /// Fails with [`Error::RetriesExhausted`] if the database is still busy
+   /// after the last attempt; other errors are returned immediately.
+   pub async fn execute(self) -> Result<Retried<Vec<IndexMap<String, JsonValue>>>, Error> {
+      let inner = self.inner;
+      with_retry(self.policy, || inner.clone().execute()).await
+   }
+}
+
+impl IntoFuture for RetryFetchAllBuilder {
+   type Output = Result<Retried<Vec<IndexMap<String, JsonValue>>>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
 /// Builder for SELECT queries returning zero or one row
 pub struct FetchOneBuilder {
    db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
    query: String,
+   /// The query as given, when `query` has soft-deleted rows filtered out
+   unfiltered: Option<String>,
    values: Vec<JsonValue>,
    attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+   pragmas: Vec<(String, String)>,
+   decode: DecodeOptions,
 }
 
 impl FetchOneBuilder {
@@ -95,9 +576,13 @@ impl FetchOneBuilder {
    ) -> Self {
       Self {
          db,
-         query,
+         query: expand_in_lists(query),
+         unfiltered: None,
          values,
          attached: Vec::new(),
+         timeout: None,
+         pragmas: Vec::new(),
+         decode: DecodeOptions::default(),
       }
    }
 
@@ -107,38 +592,111 @@ impl FetchOneBuilder {
       self
    }
 
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Run the query on a dedicated read connection with PRAGMA `name` set to
+   /// `value`, e.g. a larger `cache_size` for an analytics query
+   ///
+   /// The pragma's previous value is restored before the connection goes back
+   /// to the pool. Call again to override several pragmas.
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.pragmas.push((name.into(), value.to_string()));
+      self
+   }
+
+   /// Decode this query's rows with `options` instead of the database's
+   /// [`DecodeOptions`]. The database's decoders still apply after any in
+   /// `options`.
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.decode = options.with_decoders_from(&self.decode);
+      self
+   }
+
+   /// Return soft-deleted rows too, skipping the filter set up with
+   /// [`DatabaseWrapper::enable_soft_delete`]
+   pub fn include_deleted(mut self) -> Self {
+      if let Some(query) = self.unfiltered.take() {
+         self.query = query;
+      }
+      self
+   }
+
+   /// Run `filtered` instead of the query given, keeping the original for
+   /// [`include_deleted`](Self::include_deleted)
+   pub(crate) fn filtered(mut self, filtered: Option<String>) -> Self {
+      if let Some(filtered) = filtered {
+         self.unfiltered = Some(std::mem::replace(
+            &mut self.query,
+            expand_in_lists(filtered),
+         ));
+      }
+      self
+   }
+
    /// Execute the query and return zero or one row
    pub async fn execute(self) -> Result<Option<IndexMap<String, JsonValue>>, Error> {
-      let rows = if self.attached.is_empty() {
-         // No attached databases - use regular read pool
-         let pool = self.db.read_pool()?;
+      let deadline = QueryDeadline::start(self.timeout);
+
+      let (rows, types) = if self.attached.is_empty() {
          let mut q = sqlx::query(&self.query);
          for value in self.values {
             q = bind_value(q, value);
          }
-         q.fetch_all(pool).await?
+         if self.timeout.is_none() && self.pragmas.is_empty() {
+            // No attached databases, time limit or pragmas - use regular read pool
+            let mut conn = self.db.read_pool()?.acquire().await?;
+            let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+            (q.fetch_all(&mut *conn).await?, types)
+         } else {
+            // Use a dedicated reader so a timeout interrupts only this query
+            // and pragma overrides affect only this query
+            let mut conn = deadline.acquire(self.db.acquire_reader()).await?;
+            let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
+            let interrupt = conn.interrupt_handle();
+            let rows = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+               deadline.run(interrupt, q.fetch_all(conn)).await
+            })
+            .await?;
+            (rows, types)
+         }
       } else {
          // With attached database(s) - acquire reader with attached database(s)
-         let mut conn =
-            sqlx_sqlite_conn_mgr::acquire_reader_with_attached(&self.db, self.attached).await?;
+         let mut conn = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+               &self.db,
+               self.attached,
+            ))
+            .await?;
 
+         let types = DeclaredTypes::read(&mut conn, &self.query, &self.decode).await?;
          let mut q = sqlx::query(&self.query);
          for value in self.values {
             q = bind_value(q, value);
          }
-         let rows = sqlx::Executor::fetch_all(&mut *conn, q).await?;
+         let interrupt = conn.interrupt_handle();
+         let rows = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+            deadline
+               .run(interrupt, sqlx::Executor::fetch_all(conn, q))
+               .await
+         })
+         .await?;
 
          // Explicit cleanup
          conn.detach_all().await?;
-         rows
+         (rows, types)
       };
 
       // Validate row count
       match rows.len() {
          0 => Ok(None),
          1 => {
-            let decoded = decode_rows(vec![rows.into_iter().next().unwrap()])?;
-            Ok(Some(decoded.into_iter().next().unwrap()))
+            let decoded = decode_rows(&rows, &self.decode, &types)?;
+            Ok(decoded.into_iter().next())
          }
          count => Err(Error::MultipleRowsReturned(count)),
       }
@@ -154,181 +712,1954 @@ impl IntoFuture for FetchOneBuilder {
    }
 }
 
-/// Internal cursor position for forward vs backward pagination.
-enum CursorPosition {
-   Forward(Vec<JsonValue>),
-   Backward(Vec<JsonValue>),
-}
-
-/// Builder for paginated SELECT queries using keyset (cursor-based) pagination
-pub struct FetchPageBuilder {
-   db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
-   query: String,
-   values: Vec<JsonValue>,
-   keyset: Vec<KeysetColumn>,
-   page_size: usize,
-   cursor: Option<CursorPosition>,
-   attached: Vec<AttachedSpec>,
+/// Builder for SELECT queries returning multiple rows deserialized into `T`
+///
+/// Created by [`DatabaseWrapper::fetch_all_as`]. Rows are decoded exactly as by
+/// [`FetchAllBuilder`], then deserialized by column name.
+pub struct FetchAllAsBuilder<T> {
+   inner: FetchAllBuilder,
+   _row: PhantomData<fn() -> T>,
 }
 
-impl FetchPageBuilder {
-   pub(crate) fn new(
-      db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
-      query: String,
-      values: Vec<JsonValue>,
-      keyset: Vec<KeysetColumn>,
-      page_size: usize,
-   ) -> Self {
+impl<T> FetchAllAsBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   pub(crate) fn new(inner: FetchAllBuilder) -> Self {
       Self {
-         db,
-         query,
-         values,
-         keyset,
-         page_size,
-         cursor: None,
-         attached: Vec::new(),
+         inner,
+         _row: PhantomData,
       }
    }
 
-   /// Set the cursor for fetching the next page (forward pagination).
-   ///
-   /// Pass the `next_cursor` from a previous `KeysetPage` to fetch the page
-   /// that follows it in the original sort order.
-   pub fn after(mut self, cursor: Vec<JsonValue>) -> Self {
-      self.cursor = Some(CursorPosition::Forward(cursor));
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner = self.inner.attach(attached);
       self
    }
 
-   /// Set the cursor for fetching the previous page (backward pagination).
-   ///
-   /// Pass a cursor to fetch the page that precedes it in the original sort
-   /// order. Rows are returned in the original sort order (not reversed).
-   pub fn before(mut self, cursor: Vec<JsonValue>) -> Self {
-      self.cursor = Some(CursorPosition::Backward(cursor));
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner = self.inner.timeout(timeout);
       self
    }
 
-   /// Attach additional databases for this query
-   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
-      self.attached = attached;
+   /// Override a PRAGMA for this query only; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.inner = self.inner.pragma(name, value);
       self
    }
 
-   /// Execute the paginated query and return a page of results
-   pub async fn execute(self) -> Result<KeysetPage, Error> {
-      // Validate inputs
-      if self.keyset.is_empty() {
-         return Err(Error::EmptyKeysetColumns);
-      }
-      if self.page_size == 0 {
-         return Err(Error::InvalidPageSize);
-      }
+   /// Decode this query's rows with `options`; see
+   /// [`FetchAllBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.inner = self.inner.decode_options(options);
+      self
+   }
 
-      // Extract cursor values and direction
-      let (cursor_values, backward) = match self.cursor {
-         Some(CursorPosition::Forward(vals)) => (Some(vals), false),
-         Some(CursorPosition::Backward(vals)) => (Some(vals), true),
-         None => (None, false),
-      };
+   /// Return soft-deleted rows too, as with
+   /// [`FetchAllBuilder::include_deleted`]
+   pub fn include_deleted(mut self) -> Self {
+      self.inner = self.inner.include_deleted();
+      self
+   }
 
-      if let Some(ref vals) = cursor_values
-         && vals.len() != self.keyset.len()
-      {
-         return Err(Error::CursorLengthMismatch {
-            cursor_len: vals.len(),
-            keyset_len: self.keyset.len(),
-         });
-      }
+   /// Execute the query and return all matching rows as `T`
+   pub async fn execute(self) -> Result<Vec<T>, Error> {
+      self
+         .inner
+         .execute()
+         .await?
+         .into_iter()
+         .map(deserialize_row)
+         .collect()
+   }
+}
 
-      // Build paginated SQL — pass the user's bind count so cursor
-      // placeholders are numbered $N+1, $N+2, … and never collide with
-      // the user's $1, $2, … (or positional ?) parameters.
-      let (sql, cursor_bind_values) = build_paginated_query(
-         &self.query,
-         &self.keyset,
-         cursor_values.as_deref(),
-         self.page_size,
-         backward,
-         self.values.len(),
-      )?;
+impl<T> IntoFuture for FetchAllAsBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   type Output = Result<Vec<T>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
 
-      // Combine user values + cursor bind values
-      let mut all_values = self.values;
-      all_values.extend(cursor_bind_values);
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
 
-      // Execute query
-      let rows = if self.attached.is_empty() {
-         let pool = self.db.read_pool()?;
-         let mut q = sqlx::query(&sql);
-         for value in all_values {
-            q = bind_value(q, value);
-         }
-         q.fetch_all(pool).await?
-      } else {
-         let mut conn =
-            sqlx_sqlite_conn_mgr::acquire_reader_with_attached(&self.db, self.attached).await?;
+/// Builder for SELECT queries returning zero or one row deserialized into `T`
+///
+/// Created by [`DatabaseWrapper::fetch_one_as`]. Like [`FetchOneBuilder`], fails if
+/// the query returns more than one row.
+pub struct FetchOneAsBuilder<T> {
+   inner: FetchOneBuilder,
+   _row: PhantomData<fn() -> T>,
+}
 
+impl<T> FetchOneAsBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   pub(crate) fn new(inner: FetchOneBuilder) -> Self {
+      Self {
+         inner,
+         _row: PhantomData,
+      }
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner = self.inner.attach(attached);
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner = self.inner.timeout(timeout);
+      self
+   }
+
+   /// Override a PRAGMA for this query only; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.inner = self.inner.pragma(name, value);
+      self
+   }
+
+   /// Decode this query's rows with `options`; see
+   /// [`FetchAllBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.inner = self.inner.decode_options(options);
+      self
+   }
+
+   /// Return soft-deleted rows too, as with
+   /// [`FetchAllBuilder::include_deleted`]
+   pub fn include_deleted(mut self) -> Self {
+      self.inner = self.inner.include_deleted();
+      self
+   }
+
+   /// Execute the query and return zero or one row as `T`
+   pub async fn execute(self) -> Result<Option<T>, Error> {
+      self.inner.execute().await?.map(deserialize_row).transpose()
+   }
+}
+
+impl<T> IntoFuture for FetchOneAsBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   type Output = Result<Option<T>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for SELECT queries returning rows keyed by one of their columns
+///
+/// Created by [`DatabaseWrapper::fetch_map`]. The key column stays in each row.
+pub struct FetchMapBuilder<K> {
+   inner: FetchAllBuilder,
+   key_column: String,
+   _key: PhantomData<fn() -> K>,
+}
+
+impl<K> FetchMapBuilder<K>
+where
+   K: DeserializeOwned + Eq + Hash + Send + 'static,
+{
+   pub(crate) fn new(inner: FetchAllBuilder, key_column: String) -> Self {
+      Self {
+         inner,
+         key_column,
+         _key: PhantomData,
+      }
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner = self.inner.attach(attached);
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner = self.inner.timeout(timeout);
+      self
+   }
+
+   /// Override a PRAGMA for this query only; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.inner = self.inner.pragma(name, value);
+      self
+   }
+
+   /// Decode this query's rows with `options`; see
+   /// [`FetchAllBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.inner = self.inner.decode_options(options);
+      self
+   }
+
+   /// Return soft-deleted rows too, as with
+   /// [`FetchAllBuilder::include_deleted`]
+   pub fn include_deleted(mut self) -> Self {
+      self.inner = self.inner.include_deleted();
+      self
+   }
+
+   /// Execute the query and return the rows keyed by the key column
+   ///
+   /// Fails with [`Error::DuplicateMapKey`] if two rows share a key.
+   pub async fn execute(self) -> Result<HashMap<K, IndexMap<String, JsonValue>>, Error> {
+      // Rows are keyed as the decode options name their columns
+      let key_column = self
+         .inner
+         .decode
+         .column_name_format()
+         .apply(&self.key_column);
+      let rows = self.inner.execute().await?;
+      let mut map = HashMap::with_capacity(rows.len());
+
+      for row in rows {
+         let raw_key =
+            row.get(&key_column)
+               .cloned()
+               .ok_or_else(|| Error::MapKeyColumnNotFound {
+                  column: self.key_column.clone(),
+               })?;
+         let key = serde_json::from_value(raw_key.clone()).map_err(Error::RowDeserialization)?;
+
+         if map.insert(key, row).is_some() {
+            return Err(Error::DuplicateMapKey {
+               column: self.key_column,
+               key: raw_key.to_string(),
+            });
+         }
+      }
+
+      Ok(map)
+   }
+}
+
+impl<K> IntoFuture for FetchMapBuilder<K>
+where
+   K: DeserializeOwned + Eq + Hash + Send + 'static,
+{
+   type Output = Result<HashMap<K, IndexMap<String, JsonValue>>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for SELECT queries returning a single value of type `T`
+///
+/// Created by [`DatabaseWrapper::fetch_scalar`], [`DatabaseWrapper::count`] and
+/// [`DatabaseWrapper::exists`]. Reads the first column of the first row; any
+/// further rows or columns are ignored.
+pub struct FetchScalarBuilder<T> {
+   db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+   /// The query to run, or the error from building it, reported on execute
+   query: Result<String, Error>,
+   /// The query as given, when `query` has soft-deleted rows filtered out
+   unfiltered: Option<Result<String, Error>>,
+   values: Vec<JsonValue>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+   pragmas: Vec<(String, String)>,
+   decode: DecodeOptions,
+   _value: PhantomData<fn() -> T>,
+}
+
+impl<T> FetchScalarBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   pub(crate) fn new(
+      db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Self {
+      Self::wrapping(db, Ok(query), values)
+   }
+
+   /// Create a builder for a query generated from the caller's base query
+   ///
+   /// A failure to generate the query is returned from `execute`.
+   pub(crate) fn wrapping(
+      db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+      query: Result<String, Error>,
+      values: Vec<JsonValue>,
+   ) -> Self {
+      Self {
+         db,
+         query: query.map(expand_in_lists),
+         unfiltered: None,
+         values,
+         attached: Vec::new(),
+         timeout: None,
+         pragmas: Vec::new(),
+         decode: DecodeOptions::default(),
+         _value: PhantomData,
+      }
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Run the query on a dedicated read connection with PRAGMA `name` set to
+   /// `value`; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.pragmas.push((name.into(), value.to_string()));
+      self
+   }
+
+   /// Decode the value with `options` instead of the database's
+   /// [`DecodeOptions`]. The database's decoders still apply after any in
+   /// `options`.
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.decode = options.with_decoders_from(&self.decode);
+      self
+   }
+
+   /// Return soft-deleted rows too, as with
+   /// [`FetchAllBuilder::include_deleted`]
+   pub fn include_deleted(mut self) -> Self {
+      if let Some(query) = self.unfiltered.take() {
+         self.query = query;
+      }
+      self
+   }
+
+   /// Run `filtered` instead of the query given, keeping the original for
+   /// [`include_deleted`](Self::include_deleted)
+   pub(crate) fn filtered(mut self, filtered: Option<Result<String, Error>>) -> Self {
+      if let Some(filtered) = filtered {
+         self.unfiltered = Some(std::mem::replace(
+            &mut self.query,
+            filtered.map(expand_in_lists),
+         ));
+      }
+      self
+   }
+
+   /// Execute the query and return the first column of the first row
+   ///
+   /// Fails with [`Error::NoRowsReturned`] if the query returns no rows and
+   /// with [`Error::RowDeserialization`] if the value does not fit `T`.
+   pub async fn execute(self) -> Result<T, Error> {
+      let query = self.query?;
+      let deadline = QueryDeadline::start(self.timeout);
+      let mut q = sqlx::query(&query);
+      for value in self.values {
+         q = bind_value(q, value);
+      }
+
+      let (row, types) =
+         if self.attached.is_empty() && self.timeout.is_none() && self.pragmas.is_empty() {
+            let mut conn = self.db.read_pool()?.acquire().await?;
+            let types = DeclaredTypes::read(&mut conn, &query, &self.decode).await?;
+            (q.fetch_optional(&mut *conn).await?, types)
+         } else if self.attached.is_empty() {
+            // Use a dedicated reader so a timeout interrupts only this query
+            // and pragma overrides affect only this query
+            let mut conn = deadline.acquire(self.db.acquire_reader()).await?;
+            let types = DeclaredTypes::read(&mut conn, &query, &self.decode).await?;
+            let interrupt = conn.interrupt_handle();
+            let row = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+               deadline.run(interrupt, q.fetch_optional(conn)).await
+            })
+            .await?;
+            (row, types)
+         } else {
+            let mut conn = deadline
+               .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+                  &self.db,
+                  self.attached,
+               ))
+               .await?;
+            let types = DeclaredTypes::read(&mut conn, &query, &self.decode).await?;
+            let interrupt = conn.interrupt_handle();
+            let row = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+               deadline
+                  .run(interrupt, sqlx::Executor::fetch_optional(conn, q))
+                  .await
+            })
+            .await;
+
+            // Explicit cleanup
+            conn.detach_all().await?;
+            (row?, types)
+         };
+
+      let row = row.ok_or(Error::NoRowsReturned)?;
+      let value = {
+         use sqlx::{Column, Row};
+         let column = &row.columns()[0];
+         let (value, lossy) = crate::decode::to_json_checked(
+            row.try_get_raw(0)?,
+            column,
+            types.get(0),
+            &self.decode,
+         )?;
+         if lossy {
+            note_lossy(&mut Vec::new(), column.name());
+         }
+         value
+      };
+
+      deserialize_scalar(value)
+   }
+}
+
+impl<T> IntoFuture for FetchScalarBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   type Output = Result<T, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Deserialize a single decoded value into `T`
+///
+/// SQLite has no boolean type, so `EXISTS(...)` and comparisons yield 0 or 1;
+/// those integers are also accepted when `T` expects a boolean.
+fn deserialize_scalar<T: DeserializeOwned>(value: JsonValue) -> Result<T, Error> {
+   let flag = match value.as_i64() {
+      Some(0) => Some(false),
+      Some(1) => Some(true),
+      _ => None,
+   };
+
+   serde_json::from_value(value)
+      .or_else(|err| match flag {
+         Some(flag) => serde_json::from_value(JsonValue::Bool(flag)).map_err(|_| err),
+         None => Err(err),
+      })
+      .map_err(Error::RowDeserialization)
+}
+
+/// Deserialize a decoded row into `T`, matching struct fields to column names
+fn deserialize_row<T: DeserializeOwned>(row: IndexMap<String, JsonValue>) -> Result<T, Error> {
+   serde_json::from_value(JsonValue::Object(row.into_iter().collect()))
+      .map_err(Error::RowDeserialization)
+}
+
+/// Internal cursor position for forward vs backward pagination, or the row
+/// whose page should be fetched.
+#[derive(Clone)]
+enum CursorPosition {
+   Forward(Cursor),
+   Backward(Cursor),
+   Containing(Vec<(String, JsonValue)>),
+}
+
+/// How a page's `total` is computed
+#[derive(Clone, Copy, PartialEq)]
+enum TotalCount {
+   /// A separate `SELECT COUNT(*)` over the base query
+   Query,
+   /// `COUNT(*) OVER ()` in a hidden column of the page query
+   Window,
+}
+
+/// Builder for paginated SELECT queries using keyset (cursor-based) pagination
+#[derive(Clone)]
+pub struct FetchPageBuilder {
+   db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+   query: String,
+   /// The query as given, when `query` has soft-deleted rows filtered out
+   unfiltered: Option<String>,
+   values: Vec<JsonValue>,
+   keyset: Vec<KeysetColumn>,
+   page_size: usize,
+   cursor: Option<CursorPosition>,
+   opaque_cursors: bool,
+   cursor_key: Option<Vec<u8>>,
+   total_count: Option<TotalCount>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+   pragmas: Vec<(String, String)>,
+   decode: DecodeOptions,
+}
+
+impl FetchPageBuilder {
+   pub(crate) fn new(
+      db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+      query: String,
+      values: Vec<JsonValue>,
+      keyset: Vec<KeysetColumn>,
+      page_size: usize,
+   ) -> Self {
+      Self {
+         db,
+         query: expand_in_lists(query),
+         unfiltered: None,
+         values,
+         keyset,
+         page_size,
+         cursor: None,
+         opaque_cursors: false,
+         cursor_key: None,
+         total_count: None,
+         attached: Vec::new(),
+         timeout: None,
+         pragmas: Vec::new(),
+         decode: DecodeOptions::default(),
+      }
+   }
+
+   /// The base query and its bind values
+   #[cfg(feature = "observer")]
+   pub(crate) fn query_and_values(&self) -> (&str, &[JsonValue]) {
+      (&self.query, &self.values)
+   }
+
+   /// Set the cursor for fetching the next page (forward pagination).
+   ///
+   /// Pass the `next_cursor` (or `next_cursor_token`) from a previous
+   /// `KeysetPage` to fetch the page that follows it in the original sort order.
+   pub fn after(mut self, cursor: impl Into<Cursor>) -> Self {
+      self.cursor = Some(CursorPosition::Forward(cursor.into()));
+      self
+   }
+
+   /// Set the cursor for fetching the previous page (backward pagination).
+   ///
+   /// Pass a cursor to fetch the page that precedes it in the original sort
+   /// order. Rows are returned in the original sort order (not reversed).
+   pub fn before(mut self, cursor: impl Into<Cursor>) -> Self {
+      self.cursor = Some(CursorPosition::Backward(cursor.into()));
+      self
+   }
+
+   /// Fetch the page that contains the row whose `key` columns equal the
+   /// given values, for "jump to item" navigation.
+   ///
+   /// Pages are aligned to the start of the result set, so the returned page
+   /// is the same one forward pagination from the first page would reach, and
+   /// its `offset` reports how many rows precede it. The key should identify a
+   /// single row (typically its primary key) and its columns must be result
+   /// columns of the base query. Replaces any `.after()`/`.before()` cursor.
+   ///
+   /// Locating the row counts every row before it, so the cost grows with its
+   /// position like an OFFSET query.
+   pub fn containing<K: Into<String>>(
+      mut self,
+      key: impl IntoIterator<Item = (K, JsonValue)>,
+   ) -> Self {
+      let key = key
+         .into_iter()
+         .map(|(name, value)| (name.into(), value))
+         .collect();
+      self.cursor = Some(CursorPosition::Containing(key));
+      self
+   }
+
+   /// Return the continuation cursor as an opaque string token in
+   /// `next_cursor_token` instead of raw values in `next_cursor`.
+   ///
+   /// The token is base64-encoded and bound to this keyset, but not signed;
+   /// use [`signed_cursors`](Self::signed_cursors) if clients must not be able
+   /// to forge cursors.
+   pub fn opaque_cursors(mut self) -> Self {
+      self.opaque_cursors = true;
+      self
+   }
+
+   /// Return opaque cursor tokens signed with HMAC-SHA256 under `key`.
+   ///
+   /// Implies [`opaque_cursors`](Self::opaque_cursors). Cursors passed to
+   /// `.after()`/`.before()` must then be tokens carrying a valid signature for
+   /// the same key; raw cursor values are rejected.
+   pub fn signed_cursors(mut self, key: impl Into<Vec<u8>>) -> Self {
+      self.opaque_cursors = true;
+      self.cursor_key = Some(key.into());
+      self
+   }
+
+   /// Also count every row matched by the base query and return it as
+   /// `total` on the page.
+   ///
+   /// The count runs as a separate `SELECT COUNT(*)` over the base query with
+   /// the same bind values, concurrently with the page query on another read
+   /// connection (or after it, on the same connection, when databases are
+   /// attached, a timeout is set or pragmas are overridden). It scans the full result set, so it costs as much as an
+   /// OFFSET query to the last page.
+   pub fn with_total_count(mut self) -> Self {
+      self.total_count = Some(TotalCount::Query);
+      self
+   }
+
+   /// Return `total` like [`with_total_count`](Self::with_total_count), but
+   /// compute it with `COUNT(*) OVER ()` in the page query itself instead of a
+   /// second query.
+   ///
+   /// SQLite still evaluates the whole base query to count it, but in a single
+   /// pass on one connection. The base query is wrapped in a subquery, so
+   /// keyset columns must be named as they appear in its result rows. When a
+   /// page positioned by a cursor comes back empty, the total is taken from a
+   /// separate count query instead.
+   pub fn with_window_total(mut self) -> Self {
+      self.total_count = Some(TotalCount::Window);
+      self
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the page's queries and fail with [`Error::QueryTimeout`] if
+   /// they haven't finished within `timeout`, counting time spent waiting for
+   /// a connection
+   ///
+   /// The limit covers locating a `.containing()` row and the total count too.
+   /// With [`into_page_stream`](Self::into_page_stream) it applies to each
+   /// page separately.
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Run the page's queries on a dedicated read connection with PRAGMA
+   /// `name` set to `value`; see [`FetchAllBuilder::pragma`]
+   pub fn pragma(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+      self.pragmas.push((name.into(), value.to_string()));
+      self
+   }
+
+   /// Decode the page's rows with `options` instead of the database's
+   /// [`DecodeOptions`]. The database's decoders still apply after any in
+   /// `options`.
+   ///
+   /// Cursors always hold the keyset values as stored, so they can be bound
+   /// back into the next page's query.
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.decode = options.with_decoders_from(&self.decode);
+      self
+   }
+
+   /// Return soft-deleted rows too, skipping the filter set up with
+   /// [`DatabaseWrapper::enable_soft_delete`]
+   pub fn include_deleted(mut self) -> Self {
+      if let Some(query) = self.unfiltered.take() {
+         self.query = query;
+      }
+      self
+   }
+
+   /// Run `filtered` instead of the query given, keeping the original for
+   /// [`include_deleted`](Self::include_deleted)
+   pub(crate) fn filtered(mut self, filtered: Option<String>) -> Self {
+      if let Some(filtered) = filtered {
+         self.unfiltered = Some(std::mem::replace(
+            &mut self.query,
+            expand_in_lists(filtered),
+         ));
+      }
+      self
+   }
+
+   /// Stream every page from the builder's starting position to the end of
+   /// the result set, chaining cursors internally.
+   ///
+   /// Continues in the direction the builder was set up for: forward by
+   /// default or after `.after()`/`.containing()`, backward after `.before()`.
+   /// Each page is a separate query, so rows are never all held in memory
+   /// and the read connection is released between pages. With
+   /// `.with_total_count()` or `.with_window_total()`, only the first page
+   /// carries `total`.
+   pub fn into_page_stream(self) -> BoxStream<'static, Result<KeysetPage, Error>> {
+      stream::try_unfold(Some(self), |state| async move {
+         let Some(builder) = state else {
+            return Ok(None);
+         };
+
+         let mut next = builder.clone();
+         next.total_count = None;
+         let backward = matches!(builder.cursor, Some(CursorPosition::Backward(_)));
+
+         let page = builder.execute().await?;
+
+         let next_cursor = page
+            .next_cursor
+            .clone()
+            .map(Cursor::Values)
+            .or_else(|| page.next_cursor_token.clone().map(Cursor::Token));
+         let next = next_cursor.map(|cursor| {
+            next.cursor = Some(if backward {
+               CursorPosition::Backward(cursor)
+            } else {
+               CursorPosition::Forward(cursor)
+            });
+            next
+         });
+
+         Ok(Some((page, next)))
+      })
+      .boxed()
+   }
+
+   /// Stream every row from the builder's starting position to the end of the
+   /// result set, fetching one page at a time.
+   ///
+   /// Rows arrive in page order; when paginating backward, pages are yielded
+   /// from the cursor toward the start but rows within each page keep the
+   /// original sort order. See [`into_page_stream`](Self::into_page_stream).
+   pub fn into_stream(self) -> BoxStream<'static, Result<IndexMap<String, JsonValue>, Error>> {
+      self
+         .into_page_stream()
+         .map_ok(|page| stream::iter(page.rows.into_iter().map(Ok)))
+         .try_flatten()
+         .boxed()
+   }
+
+   /// Execute the paginated query and return a page of results
+   pub async fn execute(self) -> Result<KeysetPage, Error> {
+      use sqlx::Row;
+
+      // Validate inputs
+      if self.keyset.is_empty() {
+         return Err(Error::EmptyKeysetColumns);
+      }
+      if self.page_size == 0 {
+         return Err(Error::InvalidPageSize);
+      }
+
+      // Extract cursor and direction
+      let (cursor, backward, containing) = match self.cursor {
+         Some(CursorPosition::Forward(cursor)) => (Some(cursor), false, None),
+         Some(CursorPosition::Backward(cursor)) => (Some(cursor), true, None),
+         Some(CursorPosition::Containing(row_key)) => (None, false, Some(row_key)),
+         None => (None, false, None),
+      };
+
+      // Resolve tokens to cursor values, verifying signatures when required
+      let key = self.cursor_key.as_deref();
+      let mut cursor_values = match cursor {
+         Some(Cursor::Token(token)) => Some(decode_cursor_token(&token, &self.keyset, key)?),
+         Some(Cursor::Values(_)) if key.is_some() => {
+            return Err(Error::InvalidCursorToken(
+               "signed cursors require a cursor token".to_string(),
+            ));
+         }
+         Some(Cursor::Values(vals)) => Some(vals),
+         None => None,
+      };
+
+      if let Some(ref vals) = cursor_values
+         && vals.len() != self.keyset.len()
+      {
+         return Err(Error::CursorLengthMismatch {
+            cursor_len: vals.len(),
+            keyset_len: self.keyset.len(),
+         });
+      }
+
+      // Acquire a dedicated connection up front so locating a row sees the
+      // same attached databases as the page query, and a timeout or pragma
+      // overrides affect only this page's queries
+      let deadline = QueryDeadline::start(self.timeout);
+      let mut dedicated_conn = if !self.attached.is_empty() {
+         Some(DedicatedReader::Attached(
+            deadline
+               .acquire(sqlx_sqlite_conn_mgr::acquire_reader_with_attached(
+                  &self.db,
+                  self.attached,
+               ))
+               .await?,
+         ))
+      } else if self.timeout.is_some() || !self.pragmas.is_empty() {
+         Some(DedicatedReader::Plain(
+            deadline.acquire(self.db.acquire_reader()).await?,
+         ))
+      } else {
+         None
+      };
+
+      // Turn a `.containing()` row into the cursor that starts its page
+      let mut offset = None;
+      if let Some(row_key) = containing {
+         let (page_cursor, page_offset) = match dedicated_conn.as_mut() {
+            Some(conn) => {
+               let interrupt = conn.interrupt_handle();
+               with_pragmas(conn, &self.pragmas, async |conn| {
+                  deadline
+                     .run(
+                        interrupt,
+                        locate_page(
+                           conn,
+                           &self.query,
+                           &self.values,
+                           &self.keyset,
+                           &row_key,
+                           self.page_size,
+                        ),
+                     )
+                     .await
+               })
+               .await?
+            }
+            None => {
+               let mut conn = self.db.read_pool()?.acquire().await?;
+               locate_page(
+                  &mut conn,
+                  &self.query,
+                  &self.values,
+                  &self.keyset,
+                  &row_key,
+                  self.page_size,
+               )
+               .await?
+            }
+         };
+         cursor_values = page_cursor;
+         offset = Some(page_offset);
+      }
+
+      // Build paginated SQL — pass the user's bind count so cursor
+      // placeholders are numbered $N+1, $N+2, … and never collide with
+      // the user's $1, $2, … (or positional ?) parameters.
+      let window_total = self.total_count == Some(TotalCount::Window);
+      let base_query = if window_total {
+         Cow::Owned(build_window_total_query(&self.query)?)
+      } else {
+         Cow::Borrowed(self.query.as_str())
+      };
+      let (sql, cursor_bind_values) = build_paginated_query(
+         &base_query,
+         &self.keyset,
+         cursor_values.as_deref(),
+         self.page_size,
+         backward,
+         self.values.len(),
+      )?;
+
+      let count = if self.total_count.is_some() {
+         Some((build_count_query(&self.query)?, self.values.clone()))
+      } else {
+         None
+      };
+      let positioned = cursor_values.is_some();
+      // A window total is read from the page's rows, so it only needs the
+      // count query when a cursor leaves the page empty
+      let (count, fallback_count) = if window_total {
+         (None, count.filter(|_| positioned))
+      } else {
+         (count, None)
+      };
+
+      // Combine user values + cursor bind values
+      let mut all_values = self.values;
+      all_values.extend(cursor_bind_values);
+
+      // Execute query
+      let (rows, types, total) = if let Some(mut conn) = dedicated_conn {
+         let types = DeclaredTypes::read(&mut conn, &sql, &self.decode).await?;
          let mut q = sqlx::query(&sql);
          for value in all_values {
             q = bind_value(q, value);
          }
-         let rows = sqlx::Executor::fetch_all(&mut *conn, q).await?;
+         let interrupt = conn.interrupt_handle();
+         let (rows, total) = with_pragmas(&mut conn, &self.pragmas, async |conn| {
+            let rows = deadline
+               .run(interrupt.clone(), sqlx::Executor::fetch_all(&mut *conn, q))
+               .await?;
+
+            let count = count.or(fallback_count.filter(|_| rows.is_empty()));
+            let total = match count {
+               Some((count_sql, count_values)) => {
+                  let mut q = sqlx::query(&count_sql);
+                  for value in count_values {
+                     q = bind_value(q, value);
+                  }
+                  let row = deadline
+                     .run(interrupt, sqlx::Executor::fetch_one(&mut *conn, q))
+                     .await?;
+                  Some(row.try_get::<i64, _>(0)?)
+               }
+               None => None,
+            };
+            Ok((rows, total))
+         })
+         .await?;
+
+         // Explicit cleanup
+         conn.release().await?;
+         (rows, types, total)
+      } else {
+         let pool = self.db.read_pool()?;
+
+         // Start the count first so it runs alongside the page query
+         let count_task = count.map(|(count_sql, count_values)| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+               let mut q = sqlx::query(&count_sql);
+               for value in count_values {
+                  q = bind_value(q, value);
+               }
+               let row = q.fetch_one(&pool).await?;
+               Ok::<i64, Error>(row.try_get(0)?)
+            })
+         });
+
+         let mut conn = pool.acquire().await?;
+         let types = DeclaredTypes::read(&mut conn, &sql, &self.decode).await?;
+         let mut q = sqlx::query(&sql);
+         for value in all_values {
+            q = bind_value(q, value);
+         }
+         let rows = q.fetch_all(&mut *conn).await?;
+         // Release the connection before the fallback count takes one
+         drop(conn);
+
+         let total = match count_task {
+            Some(task) => Some(
+               task
+                  .await
+                  .map_err(|e| Error::Other(format!("total count task failed: {}", e)))??,
+            ),
+            None => match fallback_count.filter(|_| rows.is_empty()) {
+               Some((count_sql, count_values)) => {
+                  let mut q = sqlx::query(&count_sql);
+                  for value in count_values {
+                     q = bind_value(q, value);
+                  }
+                  let row = q.fetch_one(pool).await?;
+                  Some(row.try_get::<i64, _>(0)?)
+               }
+               None => None,
+            },
+         };
+         (rows, types, total)
+      };
+
+      // Decode rows, keeping the raw rows to take cursors from
+      let mut decoded = decode_rows(&rows, &self.decode, &types)?;
+
+      // Strip the hidden window total column, taking the total from it
+      let total = if window_total {
+         let window_total_key = self.decode.column_name_format().apply(WINDOW_TOTAL_COLUMN);
+         let mut window_count = None;
+         for row in &mut decoded {
+            if let Some(value) = row.shift_remove(&window_total_key) {
+               window_count = window_count.or(value.as_i64());
+            }
+         }
+         // An empty page without a cursor means the base query has no rows
+         window_count.or(total).or(Some(0))
+      } else {
+         total
+      };
+
+      // Determine has_more by checking if we got more rows than page_size
+      let has_more = decoded.len() > self.page_size;
+      if has_more {
+         decoded.truncate(self.page_size);
+      }
+
+      // Reverse rows when paginating backward to restore original sort order
+      if backward {
+         decoded.reverse();
+      }
+
+      // Cursors of the boundary rows, in original sort order. They are bound
+      // back into the query, so they come from the values as stored.
+      let raw_cursor = |i: usize| -> Result<Vec<JsonValue>, Error> {
+         let row = decode_row(
+            &rows[i],
+            &DecodeOptions::default(),
+            &DeclaredTypes::default(),
+         )?;
+         row_cursor(&row, &self.keyset)
+      };
+      let (first, last) = match decoded.len() {
+         0 => (None, None),
+         len if backward => (Some(len - 1), Some(0)),
+         len => (Some(0), Some(len - 1)),
+      };
+      let start_vals = first.map(raw_cursor).transpose()?;
+      let end_vals = last.map(raw_cursor).transpose()?;
+
+      // Continuation cursor: first row if backward, last row if forward
+      let next_cursor = if !has_more {
+         None
+      } else if backward {
+         start_vals.clone()
+      } else {
+         end_vals.clone()
+      };
+
+      let (has_previous, has_next) = if backward {
+         (has_more, positioned)
+      } else {
+         (positioned, has_more)
+      };
+
+      let to_cursor = |vals: Vec<JsonValue>| {
+         if self.opaque_cursors {
+            Cursor::Token(encode_cursor_token(&self.keyset, &vals, key))
+         } else {
+            Cursor::Values(vals)
+         }
+      };
+      let start_cursor = start_vals.map(to_cursor);
+      let end_cursor = end_vals.map(to_cursor);
+
+      let (next_cursor, next_cursor_token) = if self.opaque_cursors {
+         let token = next_cursor.map(|vals| encode_cursor_token(&self.keyset, &vals, key));
+         (None, token)
+      } else {
+         (next_cursor, None)
+      };
+
+      Ok(KeysetPage {
+         rows: decoded,
+         next_cursor,
+         next_cursor_token,
+         has_more,
+         start_cursor,
+         end_cursor,
+         has_previous,
+         has_next,
+         offset,
+         total: total.map(|n| n as u64),
+      })
+   }
+}
+
+/// Extract the keyset values of a decoded row, in keyset order.
+fn row_cursor(
+   row: &IndexMap<String, JsonValue>,
+   keyset: &[KeysetColumn],
+) -> Result<Vec<JsonValue>, Error> {
+   keyset
+      .iter()
+      .map(|col| {
+         row.get(&col.name)
+            .cloned()
+            .ok_or_else(|| Error::CursorColumnNotFound {
+               column: col.name.clone(),
+            })
+      })
+      .collect()
+}
+
+/// Find the page of `page_size` rows, aligned to the start of the result set,
+/// that contains the row matching `row_key`.
+///
+/// Returns the cursor to fetch that page with (forward), or `None` when it is
+/// the first page, along with the number of rows preceding the page.
+/// A read connection held for one builder's statements rather than borrowed
+/// from the pool per statement
+enum DedicatedReader {
+   Plain(sqlx_sqlite_conn_mgr::ReadGuard),
+   Attached(sqlx_sqlite_conn_mgr::AttachedReadConnection),
+}
+
+impl DedicatedReader {
+   fn interrupt_handle(&self) -> sqlx_sqlite_conn_mgr::InterruptHandle {
+      match self {
+         Self::Plain(conn) => conn.interrupt_handle(),
+         Self::Attached(conn) => conn.interrupt_handle(),
+      }
+   }
+
+   /// Return the connection, detaching any attached databases first
+   async fn release(self) -> Result<(), Error> {
+      match self {
+         Self::Plain(_) => Ok(()),
+         Self::Attached(conn) => Ok(conn.detach_all().await?),
+      }
+   }
+}
+
+impl ReaderConnection for DedicatedReader {
+   fn close_on_drop(&mut self) {
+      match self {
+         Self::Plain(conn) => conn.close_on_drop(),
+         Self::Attached(conn) => conn.close_on_drop(),
+      }
+   }
+}
+
+impl std::ops::Deref for DedicatedReader {
+   type Target = sqlx::SqliteConnection;
+
+   fn deref(&self) -> &Self::Target {
+      match self {
+         Self::Plain(conn) => conn,
+         Self::Attached(conn) => conn,
+      }
+   }
+}
+
+impl std::ops::DerefMut for DedicatedReader {
+   fn deref_mut(&mut self) -> &mut Self::Target {
+      match self {
+         Self::Plain(conn) => conn,
+         Self::Attached(conn) => conn,
+      }
+   }
+}
+
+async fn locate_page(
+   conn: &mut sqlx::SqliteConnection,
+   query: &str,
+   values: &[JsonValue],
+   keyset: &[KeysetColumn],
+   row_key: &[(String, JsonValue)],
+   page_size: usize,
+) -> Result<(Option<Vec<JsonValue>>, u64), Error> {
+   use sqlx::Row;
+
+   if row_key.is_empty() {
+      return Err(Error::Other(
+         "containing() requires at least one key column".to_string(),
+      ));
+   }
+
+   // Keyset values of the target row
+   let (lookup_sql, lookup_values) = build_row_lookup_query(query, keyset, row_key, values.len())?;
+   let mut q = sqlx::query(&lookup_sql);
+   for value in values.iter().cloned().chain(lookup_values) {
+      q = bind_value(q, value);
+   }
+   let rows = decode_rows(
+      &sqlx::Executor::fetch_all(&mut *conn, q).await?,
+      &DecodeOptions::default(),
+      &DeclaredTypes::default(),
+   )?;
+   let target: Vec<JsonValue> = match rows.as_slice() {
+      [row] => row.values().cloned().collect(),
+      [] => return Err(Error::RowNotFound),
+      _ => return Err(Error::AmbiguousRowKey),
+   };
+
+   // Count the rows before it to find where its page starts
+   let (count_sql, _, preceding_values) =
+      build_preceding_queries(query, keyset, &target, 0, values.len())?;
+   let mut q = sqlx::query(&count_sql);
+   for value in values.iter().chain(&preceding_values).cloned() {
+      q = bind_value(q, value);
+   }
+   let position = sqlx::Executor::fetch_one(&mut *conn, q)
+      .await?
+      .try_get::<i64, _>(0)? as u64;
+   let page_offset = position - position % page_size as u64;
+
+   if page_offset == 0 {
+      return Ok((None, 0));
+   }
+
+   // The page's cursor is the last row of the previous page, found by walking
+   // back from the target row rather than forward from the start
+   let skip = (position - page_offset) as usize;
+   let (_, nth_sql, preceding_values) =
+      build_preceding_queries(query, keyset, &target, skip, values.len())?;
+   let mut q = sqlx::query(&nth_sql);
+   for value in values.iter().chain(&preceding_values).cloned() {
+      q = bind_value(q, value);
+   }
+   let rows = decode_rows(
+      &[sqlx::Executor::fetch_one(&mut *conn, q).await?],
+      &DecodeOptions::default(),
+      &DeclaredTypes::default(),
+   )?;
+   let cursor = rows[0].values().cloned().collect();
+
+   Ok((Some(cursor), page_offset))
+}
+
+impl IntoFuture for FetchPageBuilder {
+   type Output = Result<KeysetPage, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for ranked full-text searches over an [`FtsIndex`]
+///
+/// Created by [`DatabaseWrapper::search`]. Rows of the content table matching
+/// the FTS5 query are returned best match first, paginated like
+/// [`FetchPageBuilder`] with a keyset of their bm25 rank and rowid. Each row
+/// carries those as the `rank` and `rowid` columns, next to the selected
+/// columns and any requested snippets or highlights.
+///
+/// Unqualified names passed to [`columns`](Self::columns) refer to the content
+/// table. Conditions may refer to both tables, so columns that are also indexed
+/// must be qualified with the table name there.
+pub struct SearchBuilder {
+   index: FtsIndex,
+   query: String,
+   columns: Vec<String>,
+   snippets: Vec<(String, usize)>,
+   highlights: Vec<String>,
+   markers: (String, String),
+   ellipsis: String,
+   weights: Vec<f64>,
+   conditions: Vec<Condition>,
+   page: FetchPageBuilder,
+}
+
+impl SearchBuilder {
+   pub(crate) fn new(
+      db: Arc<sqlx_sqlite_conn_mgr::SqliteDatabase>,
+      index: FtsIndex,
+      query: String,
+      page_size: usize,
+   ) -> Self {
+      Self {
+         index,
+         query,
+         columns: Vec::new(),
+         snippets: Vec::new(),
+         highlights: Vec::new(),
+         markers: ("<b>".to_string(), "</b>".to_string()),
+         ellipsis: "…".to_string(),
+         weights: Vec::new(),
+         conditions: Vec::new(),
+         page: FetchPageBuilder::new(
+            db,
+            String::new(),
+            Vec::new(),
+            vec![
+               KeysetColumn::asc(RANK_COLUMN),
+               KeysetColumn::asc(ROWID_COLUMN),
+            ],
+            page_size,
+         ),
+      }
+   }
+
+   /// Return only these columns of the content table instead of all of them
+   pub fn columns<I, S>(mut self, columns: I) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      self.columns.extend(columns.into_iter().map(Into::into));
+      self
+   }
+
+   /// Add a `<column>_snippet` column: a fragment of an indexed column of at
+   /// most `max_tokens` tokens (1 to 64) around the matches, with matches
+   /// wrapped in the [`markers`](Self::markers)
+   pub fn snippet(mut self, column: impl Into<String>, max_tokens: usize) -> Self {
+      self.snippets.push((column.into(), max_tokens));
+      self
+   }
+
+   /// Add a `<column>_highlight` column: the full text of an indexed column
+   /// with matches wrapped in the [`markers`](Self::markers)
+   pub fn highlight(mut self, column: impl Into<String>) -> Self {
+      self.highlights.push(column.into());
+      self
+   }
+
+   /// Text inserted before and after each match in snippets and highlights
+   /// (default `<b>` and `</b>`)
+   pub fn markers(mut self, open: impl Into<String>, close: impl Into<String>) -> Self {
+      self.markers = (open.into(), close.into());
+      self
+   }
+
+   /// Text marking where a snippet was cut off (default `…`)
+   pub fn ellipsis(mut self, ellipsis: impl Into<String>) -> Self {
+      self.ellipsis = ellipsis.into();
+      self
+   }
+
+   /// Weight matches in each indexed column, in index column order, when
+   /// computing the bm25 rank. Columns without a weight count as 1.0.
+   pub fn weights(mut self, weights: impl IntoIterator<Item = f64>) -> Self {
+      self.weights = weights.into_iter().collect();
+      self
+   }
+
+   /// Only return rows where `column = value`
+   pub fn where_eq(self, column: impl Into<String>, value: JsonValue) -> Self {
+      self.where_cmp(column, CompareOp::Eq, value)
+   }
+
+   /// Only return rows where `column <op> value`
+   pub fn where_cmp(mut self, column: impl Into<String>, op: CompareOp, value: JsonValue) -> Self {
+      self.conditions.push(Condition::Compare {
+         column: column.into(),
+         op,
+         value,
+      });
+      self
+   }
+
+   /// Only return rows matching a hand-written condition using `?`
+   /// placeholders, as with [`SelectBuilder::where_sql`](crate::SelectBuilder::where_sql)
+   pub fn where_sql(mut self, fragment: impl Into<String>, values: Vec<JsonValue>) -> Self {
+      self.conditions.push(Condition::Sql {
+         fragment: fragment.into(),
+         values,
+      });
+      self
+   }
+
+   /// Interrupt the search and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, as with [`FetchPageBuilder::timeout`]
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.page = self.page.timeout(timeout);
+      self
+   }
+
+   /// Fetch the page after `cursor`, as with [`FetchPageBuilder::after`]
+   pub fn after(mut self, cursor: impl Into<Cursor>) -> Self {
+      self.page = self.page.after(cursor);
+      self
+   }
+
+   /// Fetch the page before `cursor`, as with [`FetchPageBuilder::before`]
+   pub fn before(mut self, cursor: impl Into<Cursor>) -> Self {
+      self.page = self.page.before(cursor);
+      self
+   }
+
+   /// Return opaque cursor tokens, as with [`FetchPageBuilder::opaque_cursors`]
+   pub fn opaque_cursors(mut self) -> Self {
+      self.page = self.page.opaque_cursors();
+      self
+   }
+
+   /// Return signed cursor tokens, as with [`FetchPageBuilder::signed_cursors`]
+   pub fn signed_cursors(mut self, key: impl Into<Vec<u8>>) -> Self {
+      self.page = self.page.signed_cursors(key);
+      self
+   }
+
+   /// Also count every matching row, as with
+   /// [`FetchPageBuilder::with_total_count`]
+   pub fn with_total_count(mut self) -> Self {
+      self.page = self.page.with_total_count();
+      self
+   }
+
+   /// Count every matching row within the page query, as with
+   /// [`FetchPageBuilder::with_window_total`]
+   pub fn with_window_total(mut self) -> Self {
+      self.page = self.page.with_window_total();
+      self
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.page = self.page.attach(attached);
+      self
+   }
+
+   /// Decode the matching rows with `options`, as with
+   /// [`FetchPageBuilder::decode_options`]
+   pub fn decode_options(mut self, options: DecodeOptions) -> Self {
+      self.page = self.page.decode_options(options);
+      self
+   }
+
+   /// Render the search as a pagination base query and its bind values,
+   /// e.g. to pass to [`DatabaseWrapper::fetch_page`] with a keyset of `rank`
+   /// and `rowid`
+   pub fn build(&self) -> Result<(String, Vec<JsonValue>), Error> {
+      let (table, fts, rowid) = self.index.quoted_names()?;
+      let mut values = vec![JsonValue::String(self.query.clone())];
+
+      let mut columns = if self.columns.is_empty() {
+         vec![format!("{}.*", table)]
+      } else {
+         self
+            .columns
+            .iter()
+            .map(|column| match column.contains('.') {
+               true => column_sql(column),
+               false => Ok(format!("{}.{}", table, unqualified_sql(column)?)),
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+      };
+
+      let mut bm25 = fts.clone();
+      for weight in &self.weights {
+         values.push(JsonValue::from(*weight));
+         bm25.push_str(&format!(", ${}", values.len()));
+      }
+      columns.push(format!("bm25({}) AS \"{}\"", bm25, RANK_COLUMN));
+      columns.push(format!("{}.{} AS \"{}\"", table, rowid, ROWID_COLUMN));
+
+      if !self.snippets.is_empty() || !self.highlights.is_empty() {
+         values.push(JsonValue::String(self.markers.0.clone()));
+         values.push(JsonValue::String(self.markers.1.clone()));
+         let markers = format!("${}, ${}", values.len() - 1, values.len());
+
+         if !self.snippets.is_empty() {
+            values.push(JsonValue::String(self.ellipsis.clone()));
+            let ellipsis = values.len();
+            for (column, max_tokens) in &self.snippets {
+               if !(1..=64).contains(max_tokens) {
+                  return Err(Error::Other(format!(
+                     "snippet length must be between 1 and 64 tokens, got {}",
+                     max_tokens
+                  )));
+               }
+               columns.push(format!(
+                  "snippet({}, {}, {}, ${}, {}) AS {}",
+                  fts,
+                  self.index.column_index(column)?,
+                  markers,
+                  ellipsis,
+                  max_tokens,
+                  unqualified_sql(&format!("{}_snippet", column))?
+               ));
+            }
+         }
+
+         for column in &self.highlights {
+            columns.push(format!(
+               "highlight({}, {}, {}) AS {}",
+               fts,
+               self.index.column_index(column)?,
+               markers,
+               unqualified_sql(&format!("{}_highlight", column))?
+            ));
+         }
+      }
+
+      let mut conditions = vec![format!("{} MATCH $1", fts)];
+      for condition in self.conditions.iter().cloned() {
+         conditions.push(condition_sql(condition, &mut values)?);
+      }
+
+      // Wrapped so pagination's cursor condition can refer to the rank and
+      // rowid result columns
+      let query = format!(
+         "SELECT * FROM (SELECT {} FROM {} JOIN {} ON {}.{} = {}.rowid WHERE {})",
+         columns.join(", "),
+         fts,
+         table,
+         table,
+         rowid,
+         fts,
+         conditions.join(" AND ")
+      );
+
+      Ok((query, values))
+   }
+
+   /// Execute the search and return the requested page of matches
+   pub async fn execute(self) -> Result<KeysetPage, Error> {
+      let (query, values) = self.build()?;
+      let mut page = self.page;
+      page.query = expand_in_lists(query);
+      page.values = values;
+      page.execute().await
+   }
+}
+
+impl IntoFuture for SearchBuilder {
+   type Output = Result<KeysetPage, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for write queries (INSERT/UPDATE/DELETE)
+#[derive(Clone)]
+pub struct ExecuteBuilder {
+   db: DatabaseWrapper,
+   query: String,
+   values: Vec<JsonValue>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+}
+
+impl ExecuteBuilder {
+   pub(crate) fn new(db: DatabaseWrapper, query: String, values: Vec<JsonValue>) -> Self {
+      Self {
+         db,
+         query: expand_in_lists(query),
+         values,
+         attached: Vec::new(),
+         timeout: None,
+      }
+   }
+
+   /// Attach additional databases for this write operation
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the statement and fail with [`Error::QueryTimeout`] if it
+   /// hasn't finished within `timeout`, counting time spent waiting for the
+   /// writer
+   ///
+   /// An interrupted statement is rolled back, so it has no partial effects.
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Execute the write operation
+   ///
+   /// With write batching enabled, a write without attached databases or a
+   /// timeout is queued for the next batch instead.
+   pub async fn execute(mut self) -> Result<WriteQueryResult, Error> {
+      if self.attached.is_empty()
+         && self.timeout.is_none()
+         && let Some(batcher) = self.db.write_batcher()
+      {
+         match batcher.submit(self.db.unbatched(), self.query, self.values) {
+            Ok(reply) => {
+               return reply.await.unwrap_or_else(|_| {
+                  Err(Error::WriteBatchFailed("write batching stopped".into()))
+               });
+            }
+            // Batching was disabled on another handle; write directly
+            Err((query, values)) => {
+               self.query = query;
+               self.values = values;
+            }
+         }
+      }
+
+      let deadline = QueryDeadline::start(self.timeout);
+
+      if self.attached.is_empty() {
+         // No attached databases - use wrapper's writer (routes through observer when in use)
+         let mut writer = deadline.acquire(self.db.acquire_writer()).await?;
+         let mut q = sqlx::query(&self.query);
+         for value in self.values {
+            q = bind_value(q, value);
+         }
+         let result = deadline
+            .run(writer.interrupt_handle(), q.execute(&mut *writer))
+            .await?;
+         Ok(WriteQueryResult {
+            rows_affected: result.rows_affected(),
+            last_insert_id: result.last_insert_rowid(),
+         })
+      } else {
+         // With attached database(s) - acquire writer with attached database(s)
+         let mut conn = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_writer_with_attached(
+               self.db.inner(),
+               self.attached,
+            ))
+            .await?;
+
+         let mut q = sqlx::query(&self.query);
+         for value in self.values {
+            q = bind_value(q, value);
+         }
+         let result = deadline
+            .run(
+               conn.interrupt_handle(),
+               sqlx::Executor::execute(&mut *conn, q),
+            )
+            .await?;
+         let write_result = WriteQueryResult {
+            rows_affected: result.rows_affected(),
+            last_insert_id: result.last_insert_rowid(),
+         };
+
+         // Explicit cleanup
+         conn.detach_all().await?;
+         Ok(write_result)
+      }
+   }
+}
+
+impl ExecuteBuilder {
+   /// Return the rows produced by the statement's `RETURNING` clause along
+   /// with the usual write counters
+   pub fn returning(self) -> ExecuteReturningBuilder {
+      ExecuteReturningBuilder { inner: self }
+   }
+
+   /// Retry the statement with backoff while it fails with `SQLITE_BUSY` or
+   /// `SQLITE_LOCKED`
+   ///
+   /// A busy statement has made no changes, so retrying it is safe.
+   pub fn retry(self, policy: RetryPolicy) -> RetryExecuteBuilder {
+      RetryExecuteBuilder {
+         inner: self,
+         policy,
+      }
+   }
+}
+
+impl IntoFuture for ExecuteBuilder {
+   type Output = Result<WriteQueryResult, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for write queries retried while the database is busy
+///
+/// Created by [`ExecuteBuilder::retry`].
+pub struct RetryExecuteBuilder {
+   inner: ExecuteBuilder,
+   policy: RetryPolicy,
+}
+
+impl RetryExecuteBuilder {
+   /// Execute the write operation, retrying per the policy, and return its
+   /// result along with the number of attempts made
+   ///
+   /// Fails with [`Error::RetriesExhausted`] if the database is still busy
+   /// after the last attempt; other errors are returned immediately.
+   pub async fn execute(self) -> Result<Retried<WriteQueryResult>, Error> {
+      let inner = self.inner;
+      with_retry(self.policy, || inner.clone().execute()).await
+   }
+}
+
+impl IntoFuture for RetryExecuteBuilder {
+   type Output = Result<Retried<WriteQueryResult>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder for INSERT/UPDATE/DELETE statements with a `RETURNING` clause
+///
+/// Created by [`ExecuteBuilder::returning`].
+pub struct ExecuteReturningBuilder {
+   inner: ExecuteBuilder,
+}
+
+impl ExecuteReturningBuilder {
+   /// Attach additional databases for this write operation
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.inner.attached = attached;
+      self
+   }
+
+   /// Interrupt the statement and fail with [`Error::QueryTimeout`] if it
+   /// hasn't finished within `timeout`, as with [`ExecuteBuilder::timeout`]
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.inner.timeout = Some(timeout);
+      self
+   }
+
+   /// Execute the write operation and decode the returned rows
+   pub async fn execute(self) -> Result<WriteReturningResult, Error> {
+      let ExecuteBuilder {
+         db,
+         query,
+         values,
+         attached,
+         timeout,
+      } = self.inner;
+      let deadline = QueryDeadline::start(timeout);
+
+      if attached.is_empty() {
+         // No attached databases - use wrapper's writer (routes through observer when in use)
+         let mut writer = deadline.acquire(db.acquire_writer()).await?;
+         deadline
+            .run(
+               writer.interrupt_handle(),
+               execute_returning(&mut writer, &query, values, db.decode_options()),
+            )
+            .await
+      } else {
+         // With attached database(s) - acquire writer with attached database(s)
+         let mut conn = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_writer_with_attached(
+               db.inner(),
+               attached,
+            ))
+            .await?;
+         let result = deadline
+            .run(
+               conn.interrupt_handle(),
+               execute_returning(&mut conn, &query, values, db.decode_options()),
+            )
+            .await?;
 
          // Explicit cleanup
          conn.detach_all().await?;
-         rows
-      };
+         Ok(result)
+      }
+   }
+}
 
-      // Decode rows
-      let mut decoded = decode_rows(rows)?;
+impl IntoFuture for ExecuteReturningBuilder {
+   type Output = Result<WriteReturningResult, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
 
-      // Determine has_more by checking if we got more rows than page_size
-      let has_more = decoded.len() > self.page_size;
-      if has_more {
-         decoded.truncate(self.page_size);
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Run a write statement, collecting both its RETURNING rows and its counters
+async fn execute_returning(
+   conn: &mut sqlx::SqliteConnection,
+   query: &str,
+   values: Vec<JsonValue>,
+   options: &DecodeOptions,
+) -> Result<WriteReturningResult, Error> {
+   let mut q = sqlx::query(query);
+   for value in values {
+      q = bind_value(q, value);
+   }
+
+   let types = DeclaredTypes::read(conn, query, options).await?;
+   let mut lossy_columns = Vec::new();
+   let mut results = sqlx::Executor::fetch_many(conn, q);
+   let mut result = WriteReturningResult {
+      rows_affected: 0,
+      last_insert_id: 0,
+      rows: Vec::new(),
+   };
+
+   while let Some(item) = results.try_next().await? {
+      match item {
+         sqlx::Either::Left(done) => {
+            result.rows_affected += done.rows_affected();
+            result.last_insert_id = done.last_insert_rowid();
+         }
+         sqlx::Either::Right(row) => {
+            let row = decode_row_checked(&row, options, &types, &mut lossy_columns)?;
+            result.rows.push(row);
+         }
       }
+   }
 
-      // Reverse rows when paginating backward to restore original sort order
-      if backward {
-         decoded.reverse();
+   Ok(result)
+}
+
+/// What an [`InsertBuilder`] does when the row conflicts with a uniqueness constraint
+#[derive(Debug, Clone)]
+enum OnConflict {
+   DoNothing,
+   DoUpdate {
+      target: Vec<String>,
+      columns: Option<Vec<String>>,
+   },
+}
+
+/// Builder for a single-row INSERT, optionally turned into an upsert
+///
+/// Table and column names are validated and emitted as quoted identifiers, and
+/// every value is bound as a parameter. Executes through the writer exactly like
+/// [`ExecuteBuilder`].
+pub struct InsertBuilder {
+   db: DatabaseWrapper,
+   table: String,
+   values: IndexMap<String, JsonValue>,
+   on_conflict: Option<OnConflict>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+}
+
+impl InsertBuilder {
+   pub(crate) fn new(db: DatabaseWrapper, table: String) -> Self {
+      Self {
+         db,
+         table,
+         values: IndexMap::new(),
+         on_conflict: None,
+         attached: Vec::new(),
+         timeout: None,
       }
+   }
 
-      // Extract continuation cursor: first row if backward, last row if forward
-      let cursor_row = if backward {
-         decoded.first()
-      } else {
-         decoded.last()
-      };
+   /// Set the value of one column. Setting a column again replaces its value.
+   pub fn value(mut self, column: impl Into<String>, value: JsonValue) -> Self {
+      self.values.insert(column.into(), value);
+      self
+   }
+
+   /// Set several column values, e.g. from an `IndexMap` or a JSON object
+   pub fn values<I, K>(mut self, values: I) -> Self
+   where
+      I: IntoIterator<Item = (K, JsonValue)>,
+      K: Into<String>,
+   {
+      self.values.extend(
+         values
+            .into_iter()
+            .map(|(column, value)| (column.into(), value)),
+      );
+      self
+   }
+
+   /// Skip the insert if it would violate any uniqueness constraint
+   /// (`ON CONFLICT DO NOTHING`)
+   pub fn on_conflict_do_nothing(mut self) -> Self {
+      self.on_conflict = Some(OnConflict::DoNothing);
+      self
+   }
+
+   /// Turn the insert into an upsert: when a row with the same `target` columns
+   /// exists, overwrite every other inserted column with the new value
+   pub fn on_conflict_update<I, S>(mut self, target: I) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      self.on_conflict = Some(OnConflict::DoUpdate {
+         target: target.into_iter().map(Into::into).collect(),
+         columns: None,
+      });
+      self
+   }
+
+   /// Like [`on_conflict_update`](Self::on_conflict_update), but only overwrite
+   /// the given `columns` of the existing row
+   pub fn on_conflict_update_columns<I, S, J, T>(mut self, target: I, columns: J) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+      J: IntoIterator<Item = T>,
+      T: Into<String>,
+   {
+      self.on_conflict = Some(OnConflict::DoUpdate {
+         target: target.into_iter().map(Into::into).collect(),
+         columns: Some(columns.into_iter().map(Into::into).collect()),
+      });
+      self
+   }
+
+   /// Attach additional databases for this write operation
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the insert and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, as with [`ExecuteBuilder::timeout`]
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
 
-      let next_cursor = if has_more {
-         if let Some(row) = cursor_row {
-            let mut cursor_vals = Vec::with_capacity(self.keyset.len());
-            for col in &self.keyset {
-               let value = row
-                  .get(&col.name)
-                  .ok_or_else(|| Error::CursorColumnNotFound {
-                     column: col.name.clone(),
-                  })?;
-               cursor_vals.push(value.clone());
+   /// Render the INSERT statement and its bind values without executing it,
+   /// e.g. to run it inside a transaction
+   pub fn build(&self) -> Result<(String, Vec<JsonValue>), Error> {
+      let table = column_sql(&self.table)?;
+
+      if self.values.is_empty() {
+         if self.on_conflict.is_some() {
+            return Err(Error::Other(
+               "ON CONFLICT requires at least one inserted column".into(),
+            ));
+         }
+         return Ok((format!("INSERT INTO {} DEFAULT VALUES", table), Vec::new()));
+      }
+
+      let columns = self
+         .values
+         .keys()
+         .map(|column| unqualified_sql(column))
+         .collect::<Result<Vec<_>, Error>>()?;
+      let placeholders = (1..=columns.len())
+         .map(|n| format!("${}", n))
+         .collect::<Vec<_>>();
+
+      let mut query = format!(
+         "INSERT INTO {} ({}) VALUES ({})",
+         table,
+         columns.join(", "),
+         placeholders.join(", ")
+      );
+
+      match &self.on_conflict {
+         None => {}
+         Some(OnConflict::DoNothing) => query.push_str(" ON CONFLICT DO NOTHING"),
+         Some(OnConflict::DoUpdate { target, columns }) => {
+            if target.is_empty() {
+               return Err(Error::Other(
+                  "ON CONFLICT DO UPDATE requires at least one conflict target column".into(),
+               ));
             }
-            Some(cursor_vals)
-         } else {
-            None
+
+            let updated = match columns {
+               Some(columns) => columns.clone(),
+               None => self
+                  .values
+                  .keys()
+                  .filter(|column| !target.contains(column))
+                  .cloned()
+                  .collect(),
+            };
+            if updated.is_empty() {
+               return Err(Error::Other(
+                  "ON CONFLICT DO UPDATE has no columns to update".into(),
+               ));
+            }
+
+            let target = target
+               .iter()
+               .map(|column| unqualified_sql(column))
+               .collect::<Result<Vec<_>, Error>>()?;
+            let assignments = updated
+               .iter()
+               .map(|column| {
+                  let quoted = unqualified_sql(column)?;
+                  Ok(format!("{} = excluded.{}", quoted, quoted))
+               })
+               .collect::<Result<Vec<_>, Error>>()?;
+
+            query.push_str(&format!(
+               " ON CONFLICT ({}) DO UPDATE SET {}",
+               target.join(", "),
+               assignments.join(", ")
+            ));
          }
-      } else {
-         None
-      };
+      }
 
-      Ok(KeysetPage {
-         rows: decoded,
-         next_cursor,
-         has_more,
-      })
+      Ok((query, self.values.values().cloned().collect()))
+   }
+
+   /// Execute the insert
+   pub async fn execute(self) -> Result<WriteQueryResult, Error> {
+      let (query, values) = self.build()?;
+      let mut builder = self.db.execute(query, values).attach(self.attached);
+      builder.timeout = self.timeout;
+      builder.execute().await
    }
 }
 
-impl IntoFuture for FetchPageBuilder {
-   type Output = Result<KeysetPage, Error>;
+impl IntoFuture for InsertBuilder {
+   type Output = Result<WriteQueryResult, Error>;
    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
 
    fn into_future(self) -> Self::IntoFuture {
@@ -336,68 +2667,408 @@ impl IntoFuture for FetchPageBuilder {
    }
 }
 
-/// Builder for write queries (INSERT/UPDATE/DELETE)
-pub struct ExecuteBuilder {
+/// Highest number of bind parameters SQLite accepts in one statement by default
+/// since 3.32 (`SQLITE_MAX_VARIABLE_NUMBER`); older builds allow only 999
+pub const DEFAULT_MAX_BIND_PARAMS: usize = 32766;
+
+/// Builder for inserting many rows, split into multi-row INSERT statements that
+/// stay under SQLite's bind parameter limit
+///
+/// All chunks run inside a single write transaction, so either every row is
+/// inserted or none are.
+pub struct InsertManyBuilder {
    db: DatabaseWrapper,
-   query: String,
-   values: Vec<JsonValue>,
+   table: String,
+   columns: Vec<String>,
+   rows: Vec<Vec<JsonValue>>,
+   max_bind_params: usize,
    attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
 }
 
-impl ExecuteBuilder {
-   pub(crate) fn new(db: DatabaseWrapper, query: String, values: Vec<JsonValue>) -> Self {
+impl InsertManyBuilder {
+   pub(crate) fn new(
+      db: DatabaseWrapper,
+      table: String,
+      columns: Vec<String>,
+      rows: Vec<Vec<JsonValue>>,
+   ) -> Self {
       Self {
          db,
-         query,
-         values,
+         table,
+         columns,
+         rows,
+         max_bind_params: DEFAULT_MAX_BIND_PARAMS,
          attached: Vec::new(),
+         timeout: None,
       }
    }
 
+   /// Override the bind parameter limit used to size chunks (e.g. `999` for
+   /// SQLite builds older than 3.32)
+   pub fn max_bind_params(mut self, max_bind_params: usize) -> Self {
+      self.max_bind_params = max_bind_params;
+      self
+   }
+
    /// Attach additional databases for this write operation
    pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
       self.attached = attached;
       self
    }
 
-   /// Execute the write operation
-   pub async fn execute(self) -> Result<WriteQueryResult, Error> {
-      if self.attached.is_empty() {
-         // No attached databases - use wrapper's writer (routes through observer when in use)
-         let mut writer = self.db.acquire_writer().await?;
-         let mut q = sqlx::query(&self.query);
-         for value in self.values {
-            q = bind_value(q, value);
-         }
-         let result = q.execute(&mut *writer).await?;
-         Ok(WriteQueryResult {
-            rows_affected: result.rows_affected(),
-            last_insert_id: result.last_insert_rowid(),
+   /// Roll back every chunk and fail with [`Error::QueryTimeout`] if the
+   /// inserts haven't committed within `timeout`; see
+   /// [`TransactionExecutionBuilder::timeout`](crate::TransactionExecutionBuilder::timeout)
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Render one INSERT statement per chunk along with its bind values
+   pub fn build(self) -> Result<Vec<(String, Vec<JsonValue>)>, Error> {
+      if self.columns.is_empty() {
+         return Err(Error::Other(
+            "insert_many requires at least one column".into(),
+         ));
+      }
+
+      let rows_per_chunk = self.max_bind_params / self.columns.len();
+      if rows_per_chunk == 0 {
+         return Err(Error::Other(format!(
+            "{} columns exceed the limit of {} bind parameters per statement",
+            self.columns.len(),
+            self.max_bind_params
+         )));
+      }
+
+      if let Some((index, row)) = self
+         .rows
+         .iter()
+         .enumerate()
+         .find(|(_, row)| row.len() != self.columns.len())
+      {
+         return Err(Error::Other(format!(
+            "row {} has {} values but {} columns were given",
+            index,
+            row.len(),
+            self.columns.len()
+         )));
+      }
+
+      let prefix = format!(
+         "INSERT INTO {} ({}) VALUES ",
+         column_sql(&self.table)?,
+         self
+            .columns
+            .iter()
+            .map(|column| unqualified_sql(column))
+            .collect::<Result<Vec<_>, Error>>()?
+            .join(", ")
+      );
+
+      let columns = self.columns.len();
+      let mut statements = Vec::new();
+      let mut rows = self.rows.into_iter().peekable();
+
+      while rows.peek().is_some() {
+         let chunk: Vec<Vec<JsonValue>> = rows.by_ref().take(rows_per_chunk).collect();
+         let tuples = (0..chunk.len())
+            .map(|row| {
+               let placeholders = (1..=columns)
+                  .map(|column| format!("${}", row * columns + column))
+                  .collect::<Vec<_>>();
+               format!("({})", placeholders.join(", "))
+            })
+            .collect::<Vec<_>>();
+
+         statements.push((
+            format!("{}{}", prefix, tuples.join(", ")),
+            chunk.into_iter().flatten().collect(),
+         ));
+      }
+
+      Ok(statements)
+   }
+
+   /// Insert all rows and return the total number of rows inserted
+   pub async fn execute(mut self) -> Result<u64, Error> {
+      let db = self.db.clone();
+      let attached = std::mem::take(&mut self.attached);
+      let timeout = self.timeout;
+      let (queries, values): (Vec<String>, Vec<Vec<JsonValue>>) = self.build()?.into_iter().unzip();
+      if queries.is_empty() {
+         return Ok(0);
+      }
+
+      let mut transaction = db
+         .execute_transaction(queries.iter().map(String::as_str).zip(values).collect())
+         .attach(attached);
+      if let Some(timeout) = timeout {
+         transaction = transaction.timeout(timeout);
+      }
+      let results = transaction.execute().await?;
+
+      Ok(results.iter().map(|result| result.rows_affected).sum())
+   }
+}
+
+impl IntoFuture for InsertManyBuilder {
+   type Output = Result<u64, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
+
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder reading one path out of a JSON-typed TEXT column
+///
+/// Created by [`DatabaseWrapper::json_get`]. Generates
+/// `SELECT json_extract(column, path) ... FROM table WHERE ...` and yields one
+/// value per matching row. Objects and arrays are parsed back into JSON and
+/// JSON booleans come back as `bool` rather than SQLite's 0/1, so the values
+/// deserialize into `T` as they appear in the document. A row whose column is
+/// NULL or lacks the path yields `null`; use `Option<T>` to accept it.
+pub struct JsonGetBuilder<T> {
+   db: DatabaseWrapper,
+   table: String,
+   column: String,
+   path: String,
+   conditions: Vec<Condition>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+   _value: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonGetBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   pub(crate) fn new(db: DatabaseWrapper, table: String, column: String, path: String) -> Self {
+      Self {
+         db,
+         table,
+         column,
+         path,
+         conditions: Vec::new(),
+         attached: Vec::new(),
+         timeout: None,
+         _value: PhantomData,
+      }
+   }
+
+   /// Only read rows where `column = value`
+   pub fn where_eq(self, column: impl Into<String>, value: JsonValue) -> Self {
+      self.where_cmp(column, CompareOp::Eq, value)
+   }
+
+   /// Only read rows where `column <op> value`
+   pub fn where_cmp(mut self, column: impl Into<String>, op: CompareOp, value: JsonValue) -> Self {
+      self.conditions.push(Condition::Compare {
+         column: column.into(),
+         op,
+         value,
+      });
+      self
+   }
+
+   /// Only read rows matching a hand-written condition using `?` placeholders,
+   /// as with [`SelectBuilder::where_sql`](crate::SelectBuilder::where_sql)
+   pub fn where_sql(mut self, fragment: impl Into<String>, values: Vec<JsonValue>) -> Self {
+      self.conditions.push(Condition::Sql {
+         fragment: fragment.into(),
+         values,
+      });
+      self
+   }
+
+   /// Attach additional databases for this query
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the query and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, counting time spent waiting for a connection
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Render the SELECT statement and its bind values without executing it
+   ///
+   /// The statement returns the extracted value as `value` and its
+   /// `json_type()` as `type`.
+   pub fn build(&self) -> Result<(String, Vec<JsonValue>), Error> {
+      let column = column_sql(&self.column)?;
+      let mut values = vec![JsonValue::String(self.path.clone())];
+
+      let mut query = format!(
+         "SELECT json_extract({column}, $1) AS \"value\", json_type({column}, $1) AS \"type\" FROM {}",
+         column_sql(&self.table)?
+      );
+      push_where(&mut query, self.conditions.clone(), &mut values)?;
+
+      Ok((query, values))
+   }
+
+   /// Execute the query and return the value at the path for every matching row
+   pub async fn execute(self) -> Result<Vec<T>, Error> {
+      let (query, values) = self.build()?;
+      // Read like any other fetch, so the wrapper's decode options and
+      // soft-delete filter apply
+      let mut rows = self.db.fetch_all(query, values).attach(self.attached);
+      rows.timeout = self.timeout;
+
+      rows
+         .execute()
+         .await?
+         .into_iter()
+         .map(|mut row| {
+            let value = row.shift_remove("value").unwrap_or(JsonValue::Null);
+            let value = match row.get("type").and_then(JsonValue::as_str) {
+               Some("object" | "array") => match value {
+                  JsonValue::String(text) => {
+                     serde_json::from_str(&text).map_err(Error::RowDeserialization)?
+                  }
+                  value => value,
+               },
+               Some("true") => JsonValue::Bool(true),
+               Some("false") => JsonValue::Bool(false),
+               _ => value,
+            };
+            serde_json::from_value(value).map_err(Error::RowDeserialization)
          })
-      } else {
-         // With attached database(s) - acquire writer with attached database(s)
-         let mut conn =
-            sqlx_sqlite_conn_mgr::acquire_writer_with_attached(self.db.inner(), self.attached)
-               .await?;
+         .collect()
+   }
+}
 
-         let mut q = sqlx::query(&self.query);
-         for value in self.values {
-            q = bind_value(q, value);
-         }
-         let result = sqlx::Executor::execute(&mut *conn, q).await?;
-         let write_result = WriteQueryResult {
-            rows_affected: result.rows_affected(),
-            last_insert_id: result.last_insert_rowid(),
-         };
+impl<T> IntoFuture for JsonGetBuilder<T>
+where
+   T: DeserializeOwned + Send + 'static,
+{
+   type Output = Result<Vec<T>, Error>;
+   type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
 
-         // Explicit cleanup
-         conn.detach_all().await?;
-         Ok(write_result)
+   fn into_future(self) -> Self::IntoFuture {
+      Box::pin(self.execute())
+   }
+}
+
+/// Builder updating paths inside a JSON-typed TEXT column
+///
+/// Created by [`DatabaseWrapper::json_set`]. Generates
+/// `UPDATE table SET column = json_set(column, path, value, ...) WHERE ...`,
+/// creating or replacing each path. Values are stored as JSON, so objects and
+/// arrays are nested rather than saved as strings. A NULL column is treated as
+/// an empty object. Executes through the writer exactly like [`ExecuteBuilder`].
+pub struct JsonSetBuilder {
+   db: DatabaseWrapper,
+   table: String,
+   column: String,
+   assignments: Vec<(String, JsonValue)>,
+   conditions: Vec<Condition>,
+   attached: Vec<AttachedSpec>,
+   timeout: Option<Duration>,
+}
+
+impl JsonSetBuilder {
+   pub(crate) fn new(db: DatabaseWrapper, table: String, column: String) -> Self {
+      Self {
+         db,
+         table,
+         column,
+         assignments: Vec::new(),
+         conditions: Vec::new(),
+         attached: Vec::new(),
+         timeout: None,
+      }
+   }
+
+   /// Set the value at `path`, e.g. `$.theme` or `$.tags[0]`. Paths are applied
+   /// in the order they are set.
+   pub fn set(mut self, path: impl Into<String>, value: JsonValue) -> Self {
+      self.assignments.push((path.into(), value));
+      self
+   }
+
+   /// Only update rows where `column = value`
+   pub fn where_eq(self, column: impl Into<String>, value: JsonValue) -> Self {
+      self.where_cmp(column, CompareOp::Eq, value)
+   }
+
+   /// Only update rows where `column <op> value`
+   pub fn where_cmp(mut self, column: impl Into<String>, op: CompareOp, value: JsonValue) -> Self {
+      self.conditions.push(Condition::Compare {
+         column: column.into(),
+         op,
+         value,
+      });
+      self
+   }
+
+   /// Only update rows matching a hand-written condition using `?` placeholders,
+   /// as with [`SelectBuilder::where_sql`](crate::SelectBuilder::where_sql)
+   pub fn where_sql(mut self, fragment: impl Into<String>, values: Vec<JsonValue>) -> Self {
+      self.conditions.push(Condition::Sql {
+         fragment: fragment.into(),
+         values,
+      });
+      self
+   }
+
+   /// Attach additional databases for this write operation
+   pub fn attach(mut self, attached: Vec<AttachedSpec>) -> Self {
+      self.attached = attached;
+      self
+   }
+
+   /// Interrupt the update and fail with [`Error::QueryTimeout`] if it hasn't
+   /// finished within `timeout`, as with [`ExecuteBuilder::timeout`]
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
+   /// Render the UPDATE statement and its bind values without executing it,
+   /// e.g. to run it inside a transaction
+   pub fn build(&self) -> Result<(String, Vec<JsonValue>), Error> {
+      if self.assignments.is_empty() {
+         return Err(Error::Other(
+            "json_set requires at least one path to set".into(),
+         ));
+      }
+
+      let column = column_sql(&self.column)?;
+      let mut values = Vec::with_capacity(self.assignments.len() * 2);
+      let mut arguments = Vec::with_capacity(self.assignments.len());
+      for (path, value) in &self.assignments {
+         values.push(JsonValue::String(path.clone()));
+         values.push(JsonValue::String(value.to_string()));
+         arguments.push(format!("${}, json(${})", values.len() - 1, values.len()));
       }
+
+      let mut query = format!(
+         "UPDATE {} SET {column} = json_set(COALESCE({column}, '{{}}'), {})",
+         column_sql(&self.table)?,
+         arguments.join(", ")
+      );
+      push_where(&mut query, self.conditions.clone(), &mut values)?;
+
+      Ok((query, values))
+   }
+
+   /// Execute the update
+   pub async fn execute(self) -> Result<WriteQueryResult, Error> {
+      let (query, values) = self.build()?;
+      let mut builder = ExecuteBuilder::new(self.db, query, values).attach(self.attached);
+      builder.timeout = self.timeout;
+      builder.execute().await
    }
 }
 
-impl IntoFuture for ExecuteBuilder {
+impl IntoFuture for JsonSetBuilder {
    type Output = Result<WriteQueryResult, Error>;
    type IntoFuture = Pin<Box<dyn Future<Output = Self::Output> + Send>>;
 
@@ -406,21 +3077,89 @@ impl IntoFuture for ExecuteBuilder {
    }
 }
 
+/// Append ` WHERE ...` for `conditions`, combined with `AND`, numbering their
+/// binds after those already in `values`
+fn push_where(
+   query: &mut String,
+   conditions: Vec<Condition>,
+   values: &mut Vec<JsonValue>,
+) -> Result<(), Error> {
+   if conditions.is_empty() {
+      return Ok(());
+   }
+
+   let conditions = conditions
+      .into_iter()
+      .map(|condition| condition_sql(condition, values))
+      .collect::<Result<Vec<_>, Error>>()?;
+   query.push_str(" WHERE ");
+   query.push_str(&conditions.join(" AND "));
+   Ok(())
+}
+
 /// Helper to decode SQLite rows to JSON
 pub(crate) fn decode_rows(
-   rows: Vec<sqlx::sqlite::SqliteRow>,
+   rows: &[sqlx::sqlite::SqliteRow],
+   options: &DecodeOptions,
+   types: &DeclaredTypes,
 ) -> Result<Vec<IndexMap<String, JsonValue>>, Error> {
+   let mut lossy_columns = Vec::new();
+   rows
+      .iter()
+      .map(|row| decode_row_checked(row, options, types, &mut lossy_columns))
+      .collect()
+}
+
+/// Helper to decode a single SQLite row to JSON
+pub(crate) fn decode_row(
+   row: &sqlx::sqlite::SqliteRow,
+   options: &DecodeOptions,
+   types: &DeclaredTypes,
+) -> Result<IndexMap<String, JsonValue>, Error> {
+   decode_row_checked(row, options, types, &mut Vec::new())
+}
+
+/// Add `key` to a query's `lossy_columns`, logging a warning the first time
+fn note_lossy(lossy_columns: &mut Vec<String>, key: &str) {
+   if !lossy_columns.iter().any(|column| column == key) {
+      warn!("Column '{key}' holds invalid UTF-8, decoded lossily");
+      lossy_columns.push(key.to_string());
+   }
+}
+
+/// [`decode_row`], adding the keys of columns with TEXT decoded lossily to
+/// `lossy_columns`, which is shared by the rows of one query
+pub(crate) fn decode_row_checked(
+   row: &sqlx::sqlite::SqliteRow,
+   options: &DecodeOptions,
+   types: &DeclaredTypes,
+   lossy_columns: &mut Vec<String>,
+) -> Result<IndexMap<String, JsonValue>, Error> {
    use sqlx::{Column, Row};
 
-   let mut values = Vec::new();
-   for row in rows {
-      let mut value = IndexMap::default();
-      for (i, column) in row.columns().iter().enumerate() {
-         let v = row.try_get_raw(i)?;
-         let v = crate::decode::to_json(v)?;
-         value.insert(column.name().to_string(), v);
+   let mut value = IndexMap::default();
+   // The column each key came from, by key index, to tell a repeated column
+   // name (whose last value wins) from two names converted to the same key
+   let mut sources: Vec<&str> = Vec::new();
+   for (i, column) in row.columns().iter().enumerate() {
+      let (v, lossy) =
+         crate::decode::to_json_checked(row.try_get_raw(i)?, column, types.get(i), options)?;
+      let key = options.column_name_format().apply(column.name());
+      if let Some(index) = value.get_index_of(&key)
+         && sources[index] != column.name()
+      {
+         return Err(Error::ColumnKeyCollision {
+            key,
+            first: sources[index].to_string(),
+            second: column.name().to_string(),
+         });
+      }
+      if lossy {
+         note_lossy(lossy_columns, &key);
+      }
+      if value.insert(key, v).is_none() {
+         sources.push(column.name());
       }
-      values.push(value);
    }
-   Ok(values)
+   Ok(value)
 }
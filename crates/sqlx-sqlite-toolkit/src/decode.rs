@@ -1,10 +1,397 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
 use serde_json::Value as JsonValue;
-use sqlx::sqlite::SqliteValueRef;
-use sqlx::{TypeInfo, Value, ValueRef};
-use time::PrimitiveDateTime;
+use sqlx::sqlite::{SqliteColumn, SqliteValueRef};
+use sqlx::{Column, TypeInfo, Value, ValueRef};
+use time::format_description::well_known::Rfc3339;
+use time::{OffsetDateTime, PrimitiveDateTime};
+use uuid::Uuid;
 
 use crate::Error;
 
+/// How values in columns declared `DATETIME` or `TIMESTAMP` are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DatetimeFormat {
+   /// As stored: TEXT as a string, INTEGER as a number
+   #[default]
+   Raw,
+   /// As an RFC 3339 string in UTC, e.g. `2024-01-15T10:30:00Z`
+   Iso8601,
+   /// As milliseconds since the Unix epoch
+   EpochMillis,
+}
+
+/// Largest integer a JavaScript number holds exactly, `2^53 - 1`
+pub const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// Key of the object [`BigIntFormat::Tagged`] wraps an integer in
+pub const BIGINT_TAG: &str = "$bigint";
+
+/// How integers outside JavaScript's safe range (±`2^53 - 1`) are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BigIntFormat {
+   /// As a JSON number, which JavaScript rounds to the nearest double
+   #[default]
+   Number,
+   /// As a decimal string, e.g. `"9007199254740993"`
+   String,
+   /// As `{ "$bigint": "9007199254740993" }`, which [`bind_value`] binds back
+   /// as an integer
+   ///
+   /// [`bind_value`]: crate::bind_value
+   Tagged,
+}
+
+/// How TEXT values holding JSON documents are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonFormat {
+   /// As stored, a string
+   #[default]
+   Text,
+   /// Parsed, when the value is valid JSON in a column declared `JSON`, or a
+   /// valid JSON object or array in any other column
+   Parsed,
+}
+
+/// How integers in columns declared `BOOLEAN` or `BOOL` are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BooleanFormat {
+   /// As stored, `0` or `1`
+   #[default]
+   Integer,
+   /// As `false` for `0` and `true` for anything else
+   Boolean,
+}
+
+/// How NaN and infinite REAL values, which JSON can't represent, are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NonFiniteFormat {
+   /// As `null`
+   #[default]
+   Null,
+   /// As `"NaN"`, `"Infinity"` or `"-Infinity"`, which JavaScript's
+   /// `Number()` parses back
+   String,
+   /// Fail the query with [`Error::NonFiniteFloat`]
+   Error,
+}
+
+/// How TEXT values that aren't valid UTF-8, e.g. in imported legacy data, are
+/// returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidUtf8Format {
+   /// As `null`
+   #[default]
+   Null,
+   /// Decoded with U+FFFD replacement characters substituted for invalid
+   /// bytes, logging a warning once per column per query. Only
+   /// [`ColumnarRows`](crate::ColumnarRows) and
+   /// [`RowsWithColumnTypes`](crate::RowsWithColumnTypes) also list such
+   /// columns, in their `lossy_columns`; other results report them only in
+   /// the log.
+   Lossy,
+   /// Fail the query with [`Error::InvalidUtf8`]
+   Error,
+}
+
+/// How column names are turned into row keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ColumnNameFormat {
+   /// As named in the result set
+   #[default]
+   AsIs,
+   /// With `snake_case` names converted to `camelCase`, e.g. `created_at` to
+   /// `createdAt`. Rows with two names converted to the same key fail with
+   /// [`Error::ColumnKeyCollision`]
+   CamelCase,
+}
+
+impl ColumnNameFormat {
+   /// The row key for a column named `name`
+   pub fn apply(self, name: &str) -> String {
+      match self {
+         Self::AsIs => name.to_string(),
+         Self::CamelCase => to_camel_case(name),
+      }
+   }
+}
+
+/// Convert `snake_case` to `camelCase`. Leading underscores are kept, so
+/// `_rowid` stays as it is.
+fn to_camel_case(name: &str) -> String {
+   let body = name.trim_start_matches('_');
+   let mut out = String::with_capacity(name.len());
+   out.push_str(&name[..name.len() - body.len()]);
+
+   let mut upper_next = false;
+   for c in body.chars() {
+      if c == '_' {
+         upper_next = true;
+      } else if upper_next {
+         out.extend(c.to_uppercase());
+         upper_next = false;
+      } else {
+         out.push(c);
+      }
+   }
+   out
+}
+
+/// Key of the bind value object whose UUID string is bound as a 16-byte BLOB
+pub const UUID_TAG: &str = "$uuid";
+
+/// Key of the bind value object whose array of byte values is bound as a BLOB
+pub const BLOB_TAG: &str = "$blob";
+
+/// Key of the bind value object whose base64 string is bound as a BLOB
+pub const BASE64_TAG: &str = "$base64";
+
+/// How 16-byte BLOBs in columns declared `UUID` are returned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UuidFormat {
+   /// Base64-encoded, like any other BLOB
+   #[default]
+   Base64,
+   /// As a canonical UUID string, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8`
+   String,
+}
+
+/// Custom conversion of a column's value to JSON, registered with
+/// [`DecodeOptions::with_decoder`] or [`DatabaseWrapper::add_decoder`].
+///
+/// [`DatabaseWrapper::add_decoder`]: crate::DatabaseWrapper::add_decoder
+pub type Decoder = Arc<dyn Fn(SqliteValueRef<'_>) -> Result<JsonValue, Error> + Send + Sync>;
+
+/// The values a [`Decoder`] applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecoderTarget {
+   /// Result columns with this name (or alias)
+   Column(String),
+   /// Columns declared with this type as written in the schema, e.g. `MONEY`
+   /// or `VARCHAR(80)`. Compared case-insensitively; columns without a
+   /// declared type, such as expressions, match only by name.
+   DeclaredType(String),
+}
+
+/// Registered decoders, earliest first
+#[derive(Clone, Default)]
+struct Decoders(Arc<Vec<(DecoderTarget, Decoder)>>);
+
+impl Decoders {
+   /// The decoder for `column`: the first registered for its name, else the
+   /// first registered for its declared type
+   fn find(&self, column: &SqliteColumn, declared_type: Option<&str>) -> Option<&Decoder> {
+      let by_name = self.0.iter().find(
+         |(target, _)| matches!(target, DecoderTarget::Column(name) if name == column.name()),
+      );
+      by_name
+         .or_else(|| {
+            self.0.iter().find(|(target, _)| {
+               matches!(target, DecoderTarget::DeclaredType(ty) if is_declared(declared_type, ty))
+            })
+         })
+         .map(|(_, decoder)| decoder)
+   }
+
+   /// Whether any decoder is registered for a declared type
+   fn by_declared_type(&self) -> bool {
+      self
+         .0
+         .iter()
+         .any(|(target, _)| matches!(target, DecoderTarget::DeclaredType(_)))
+   }
+}
+
+impl std::fmt::Debug for Decoders {
+   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      f.debug_list()
+         .entries(self.0.iter().map(|(target, _)| target))
+         .finish()
+   }
+}
+
+// Decoders are closures, so two sets are equal only if they are the same set
+impl PartialEq for Decoders {
+   fn eq(&self, other: &Self) -> bool {
+      Arc::ptr_eq(&self.0, &other.0) || (self.0.is_empty() && other.0.is_empty())
+   }
+}
+
+impl Eq for Decoders {}
+
+/// Options controlling how column values are converted to JSON.
+///
+/// Set per database with [`DatabaseWrapper::set_decode_options`] or per query
+/// with the fetch builders' `decode_options`.
+///
+/// [`DatabaseWrapper::set_decode_options`]: crate::DatabaseWrapper::set_decode_options
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+   datetime: DatetimeFormat,
+   bigint: BigIntFormat,
+   json: JsonFormat,
+   uuid: UuidFormat,
+   boolean: BooleanFormat,
+   non_finite: NonFiniteFormat,
+   invalid_utf8: InvalidUtf8Format,
+   column_names: ColumnNameFormat,
+   decoders: Decoders,
+}
+
+impl DecodeOptions {
+   pub fn new() -> Self {
+      Self::default()
+   }
+
+   /// Convert values in columns declared `DATETIME` or `TIMESTAMP` to
+   /// `format`. TEXT values are parsed as SQLite date-time strings (without an
+   /// offset they are taken as UTC) and INTEGER values as Unix seconds; values
+   /// that can't be parsed are returned as stored.
+   pub fn with_datetime_format(mut self, format: DatetimeFormat) -> Self {
+      self.datetime = format;
+      self
+   }
+
+   pub fn datetime_format(&self) -> DatetimeFormat {
+      self.datetime
+   }
+
+   /// Return integers beyond ±`2^53 - 1` in `format` so they survive being
+   /// parsed by JavaScript. Integers in the safe range stay numbers.
+   pub fn with_bigint_format(mut self, format: BigIntFormat) -> Self {
+      self.bigint = format;
+      self
+   }
+
+   pub fn bigint_format(&self) -> BigIntFormat {
+      self.bigint
+   }
+
+   /// Return TEXT values holding JSON in `format`: any valid JSON in columns
+   /// declared `JSON`, and objects or arrays (e.g. those written with
+   /// `json_object()`) in other columns, where scalars such as `"true"` stay
+   /// strings.
+   ///
+   /// `JSON` has NUMERIC affinity, so SQLite already stores numeric text such
+   /// as `'42'` in such a column as a number.
+   pub fn with_json_format(mut self, format: JsonFormat) -> Self {
+      self.json = format;
+      self
+   }
+
+   pub fn json_format(&self) -> JsonFormat {
+      self.json
+   }
+
+   /// Return 16-byte BLOBs in columns declared `UUID` in `format`. Other
+   /// BLOBs, such as digests or random keys, stay base64.
+   ///
+   /// Bind a `{ "$uuid": "..." }` object to store a UUID string as a 16-byte
+   /// BLOB.
+   pub fn with_uuid_format(mut self, format: UuidFormat) -> Self {
+      self.uuid = format;
+      self
+   }
+
+   pub fn uuid_format(&self) -> UuidFormat {
+      self.uuid
+   }
+
+   /// Return integers in columns declared `BOOLEAN` or `BOOL` in `format`.
+   /// Values stored as anything other than an integer are returned as stored.
+   pub fn with_boolean_format(mut self, format: BooleanFormat) -> Self {
+      self.boolean = format;
+      self
+   }
+
+   pub fn boolean_format(&self) -> BooleanFormat {
+      self.boolean
+   }
+
+   /// Return NaN and infinite REAL values, e.g. from `SELECT 9e999`, in
+   /// `format`
+   pub fn with_non_finite_format(mut self, format: NonFiniteFormat) -> Self {
+      self.non_finite = format;
+      self
+   }
+
+   pub fn non_finite_format(&self) -> NonFiniteFormat {
+      self.non_finite
+   }
+
+   /// Return TEXT values that aren't valid UTF-8 in `format` rather than as
+   /// `null`
+   pub fn with_invalid_utf8_format(mut self, format: InvalidUtf8Format) -> Self {
+      self.invalid_utf8 = format;
+      self
+   }
+
+   pub fn invalid_utf8_format(&self) -> InvalidUtf8Format {
+      self.invalid_utf8
+   }
+
+   /// Key rows by column names converted with `format`. Only the keys change:
+   /// SQL still names columns as they are, and decoders registered for a
+   /// column still match its name in the result set.
+   pub fn with_column_name_format(mut self, format: ColumnNameFormat) -> Self {
+      self.column_names = format;
+      self
+   }
+
+   pub fn column_name_format(&self) -> ColumnNameFormat {
+      self.column_names
+   }
+
+   /// Whether any conversion goes by a column's declared type as written in
+   /// the schema, which has to be read separately from the rows
+   pub(crate) fn uses_declared_types(&self) -> bool {
+      self.json == JsonFormat::Parsed
+         || self.uuid == UuidFormat::String
+         || self.decoders.by_declared_type()
+   }
+
+   /// Convert non-NULL values matching `target` with `decoder` instead, e.g.
+   /// to map money, enums or encrypted cells to a domain representation.
+   ///
+   /// A decoder registered for a column name wins over one for a declared
+   /// type; among decoders for the same target, the first registered wins.
+   /// Other options don't apply to values a decoder handles.
+   pub fn with_decoder<F>(mut self, target: DecoderTarget, decoder: F) -> Self
+   where
+      F: Fn(SqliteValueRef<'_>) -> Result<JsonValue, Error> + Send + Sync + 'static,
+   {
+      let decoder: Decoder = Arc::new(decoder);
+      Arc::make_mut(&mut self.decoders.0).push((target, decoder));
+      self
+   }
+
+   /// Keep `base`'s decoders after this set's own, so they still apply to
+   /// values none of this set's decoders match
+   pub(crate) fn with_decoders_from(mut self, base: &DecodeOptions) -> Self {
+      if Arc::ptr_eq(&self.decoders.0, &base.decoders.0) {
+         return self;
+      }
+
+      let decoders = Arc::make_mut(&mut self.decoders.0);
+      for (target, decoder) in base.decoders.0.iter() {
+         // Skip ones already carried over from `base` earlier
+         if !decoders.iter().any(|(_, own)| Arc::ptr_eq(own, decoder)) {
+            decoders.push((target.clone(), Arc::clone(decoder)));
+         }
+      }
+      self
+   }
+}
+
 /// Convert a SQLite value to a JSON value.
 ///
 /// This function handles the type conversion from SQLite's native types
@@ -110,6 +497,256 @@ pub fn to_json(value: SqliteValueRef) -> Result<JsonValue, Error> {
    Ok(result)
 }
 
+/// Convert a value of `column` to a JSON value per `options`.
+///
+/// The column's name and declared type pick the conversion, such as a custom
+/// [`Decoder`] or [`DecodeOptions::with_datetime_format`]; without options
+/// this is the same as [`to_json`]. `declared_type` is the column's type as
+/// written in the schema (from `sqlite3_column_decltype`), which sqlx only
+/// reports for the few types it knows.
+pub fn to_json_with(
+   value: SqliteValueRef,
+   column: &SqliteColumn,
+   declared_type: Option<&str>,
+   options: &DecodeOptions,
+) -> Result<JsonValue, Error> {
+   to_json_checked(value, column, declared_type, options).map(|(json, _)| json)
+}
+
+/// [`to_json_with`], also telling whether the value was TEXT that isn't
+/// valid UTF-8 and was decoded lossily
+pub(crate) fn to_json_checked(
+   value: SqliteValueRef,
+   column: &SqliteColumn,
+   declared_type: Option<&str>,
+   options: &DecodeOptions,
+) -> Result<(JsonValue, bool), Error> {
+   if value.is_null() {
+      return Ok((JsonValue::Null, false));
+   }
+
+   if let Some(decoder) = options.decoders.find(column, declared_type) {
+      return Ok((decoder(value)?, false));
+   }
+
+   let known_type = column.type_info().name();
+
+   // sqlx reports both DATETIME and TIMESTAMP declarations as DATETIME
+   if known_type == "DATETIME"
+      && options.datetime != DatetimeFormat::Raw
+      && let Some(converted) = convert_datetime(&value, options.datetime)
+   {
+      return Ok((converted, false));
+   }
+
+   if known_type == "BOOLEAN"
+      && options.boolean == BooleanFormat::Boolean
+      && value.type_info().name() == "INTEGER"
+      && let Ok(v) = value.to_owned().try_decode::<i64>()
+   {
+      return Ok((JsonValue::Bool(v != 0), false));
+   }
+
+   if options.bigint != BigIntFormat::Number
+      && value.type_info().name() == "INTEGER"
+      && let Ok(v) = value.to_owned().try_decode::<i64>()
+   {
+      return Ok((integer_to_json(v, options.bigint), false));
+   }
+
+   if value.type_info().name() == "REAL"
+      && let Ok(v) = value.to_owned().try_decode::<f64>()
+      && !v.is_finite()
+   {
+      return Ok((
+         non_finite_to_json(v, column.name(), options.non_finite)?,
+         false,
+      ));
+   }
+
+   if options.json == JsonFormat::Parsed
+      && value.type_info().name() == "TEXT"
+      && let Ok(text) = value.to_owned().try_decode::<String>()
+      && let Some(parsed) = if is_declared(declared_type, "JSON") {
+         serde_json::from_str(&text).ok()
+      } else {
+         parse_json_document(&text)
+      }
+   {
+      return Ok((parsed, false));
+   }
+
+   if options.uuid == UuidFormat::String
+      && is_declared(declared_type, "UUID")
+      && value.type_info().name() == "BLOB"
+      && let Ok(bytes) = value.to_owned().try_decode::<Vec<u8>>()
+      && let Ok(uuid) = Uuid::from_slice(&bytes)
+   {
+      return Ok((JsonValue::String(uuid.hyphenated().to_string()), false));
+   }
+
+   // Conversions above skip text that isn't valid UTF-8, so it is caught
+   // here, checking the bytes borrowed from the row
+   if value.type_info().name() == "TEXT" {
+      let bytes =
+         <&[u8] as sqlx::Decode<sqlx::Sqlite>>::decode(value).map_err(sqlx::Error::Decode)?;
+      return match std::str::from_utf8(bytes) {
+         Ok(text) => Ok((JsonValue::String(text.to_string()), false)),
+         Err(_) => Ok((
+            invalid_utf8_to_json(bytes, column.name(), options.invalid_utf8)?,
+            options.invalid_utf8 == InvalidUtf8Format::Lossy,
+         )),
+      };
+   }
+
+   Ok((to_json(value)?, false))
+}
+
+/// Convert an integer to JSON, representing it per `format` if JavaScript
+/// can't hold it exactly
+fn integer_to_json(v: i64, format: BigIntFormat) -> JsonValue {
+   if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+      return JsonValue::from(v);
+   }
+
+   match format {
+      BigIntFormat::Number => JsonValue::from(v),
+      BigIntFormat::String => JsonValue::String(v.to_string()),
+      BigIntFormat::Tagged => {
+         let mut tagged = serde_json::Map::new();
+         tagged.insert(BIGINT_TAG.to_string(), JsonValue::String(v.to_string()));
+         JsonValue::Object(tagged)
+      }
+   }
+}
+
+/// SQLite's type affinity for a declared type, by its rules: a type
+/// containing `INT` is INTEGER; `CHAR`, `CLOB` or `TEXT`, TEXT; `BLOB` or no
+/// type, BLOB; `REAL`, `FLOA` or `DOUB`, REAL; anything else, NUMERIC
+pub(crate) fn affinity(declared_type: Option<&str>) -> &'static str {
+   let declared_type = declared_type.unwrap_or_default().to_ascii_uppercase();
+   let contains_any = |names: &[&str]| names.iter().any(|name| declared_type.contains(name));
+
+   if declared_type.contains("INT") {
+      "INTEGER"
+   } else if contains_any(&["CHAR", "CLOB", "TEXT"]) {
+      "TEXT"
+   } else if declared_type.contains("BLOB") || declared_type.trim().is_empty() {
+      "BLOB"
+   } else if contains_any(&["REAL", "FLOA", "DOUB"]) {
+      "REAL"
+   } else {
+      "NUMERIC"
+   }
+}
+
+/// Represent a NaN or infinite float per `format`
+fn non_finite_to_json(v: f64, column: &str, format: NonFiniteFormat) -> Result<JsonValue, Error> {
+   let text = if v.is_nan() {
+      "NaN"
+   } else if v.is_sign_positive() {
+      "Infinity"
+   } else {
+      "-Infinity"
+   };
+
+   match format {
+      NonFiniteFormat::Null => Ok(JsonValue::Null),
+      NonFiniteFormat::String => Ok(JsonValue::String(text.to_string())),
+      NonFiniteFormat::Error => Err(Error::NonFiniteFloat {
+         column: column.to_string(),
+         value: text.to_string(),
+      }),
+   }
+}
+
+/// Represent TEXT that isn't valid UTF-8 per `format`
+fn invalid_utf8_to_json(
+   bytes: &[u8],
+   column: &str,
+   format: InvalidUtf8Format,
+) -> Result<JsonValue, Error> {
+   match format {
+      InvalidUtf8Format::Null => Ok(JsonValue::Null),
+      InvalidUtf8Format::Lossy => Ok(JsonValue::String(
+         String::from_utf8_lossy(bytes).into_owned(),
+      )),
+      InvalidUtf8Format::Error => Err(Error::InvalidUtf8 {
+         column: column.to_string(),
+      }),
+   }
+}
+
+/// Whether a column declared `declared_type` is declared `name`, ignoring case
+fn is_declared(declared_type: Option<&str>, name: &str) -> bool {
+   declared_type.is_some_and(|ty| ty.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+/// Parse `text` if it is a JSON object or array
+fn parse_json_document(text: &str) -> Option<JsonValue> {
+   // Skip the parse for text that can't be a document
+   if !text.trim_start().starts_with(['{', '[']) {
+      return None;
+   }
+
+   serde_json::from_str::<JsonValue>(text)
+      .ok()
+      .filter(|parsed| parsed.is_object() || parsed.is_array())
+}
+
+/// The string in a `{ "<tag>": "..." }` bind value, if `value` is one
+fn tagged_str<'a>(value: &'a JsonValue, tag: &str) -> Option<&'a str> {
+   let object = value.as_object().filter(|object| object.len() == 1)?;
+   object.get(tag)?.as_str()
+}
+
+/// The integer in a `{ "$bigint": "..." }` bind value, if `value` is one
+pub(crate) fn tagged_bigint(value: &JsonValue) -> Option<i64> {
+   tagged_str(value, BIGINT_TAG)?.parse().ok()
+}
+
+/// The bytes in a `{ "$blob": [1, 2, 3] }` or `{ "$base64": "AQID" }` bind
+/// value, if `value` is one
+pub(crate) fn tagged_blob(value: &JsonValue) -> Option<Vec<u8>> {
+   use base64::Engine;
+
+   if let Some(text) = tagged_str(value, BASE64_TAG) {
+      return base64::engine::general_purpose::STANDARD.decode(text).ok();
+   }
+
+   let object = value.as_object().filter(|object| object.len() == 1)?;
+   object
+      .get(BLOB_TAG)?
+      .as_array()?
+      .iter()
+      .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+      .collect()
+}
+
+/// The UUID in a `{ "$uuid": "..." }` bind value, if `value` is one
+pub(crate) fn tagged_uuid(value: &JsonValue) -> Option<Uuid> {
+   Uuid::parse_str(tagged_str(value, UUID_TAG)?).ok()
+}
+
+/// Parse a TEXT or INTEGER date-time and return it in `format`, or `None` if
+/// it isn't one
+fn convert_datetime(value: &SqliteValueRef, format: DatetimeFormat) -> Option<JsonValue> {
+   let dt = value.to_owned().try_decode::<OffsetDateTime>().ok()?;
+
+   match format {
+      DatetimeFormat::Raw => None,
+      DatetimeFormat::Iso8601 => dt
+         .to_offset(time::UtcOffset::UTC)
+         .format(&Rfc3339)
+         .ok()
+         .map(JsonValue::String),
+      DatetimeFormat::EpochMillis => {
+         let millis = dt.unix_timestamp_nanos() / 1_000_000;
+         i64::try_from(millis).ok().map(JsonValue::from)
+      }
+   }
+}
+
 /// Base64 encode binary data for JSON serialization.
 ///
 /// SQLite BLOB columns are encoded as base64 strings when serialized to JSON,
@@ -123,6 +760,194 @@ fn base64_encode(data: &[u8]) -> String {
 mod tests {
    use super::*;
 
+   #[test]
+   fn test_integer_to_json_keeps_safe_integers() {
+      for format in [
+         BigIntFormat::Number,
+         BigIntFormat::String,
+         BigIntFormat::Tagged,
+      ] {
+         assert_eq!(integer_to_json(42, format), serde_json::json!(42));
+         assert_eq!(
+            integer_to_json(-MAX_SAFE_INTEGER, format),
+            serde_json::json!(-MAX_SAFE_INTEGER)
+         );
+      }
+   }
+
+   #[test]
+   fn test_integer_to_json_unsafe_integers() {
+      let big = MAX_SAFE_INTEGER + 2;
+      assert_eq!(
+         integer_to_json(big, BigIntFormat::Number),
+         serde_json::json!(big)
+      );
+      assert_eq!(
+         integer_to_json(big, BigIntFormat::String),
+         serde_json::json!("9007199254740993")
+      );
+      assert_eq!(
+         integer_to_json(i64::MIN, BigIntFormat::Tagged),
+         serde_json::json!({ "$bigint": "-9223372036854775808" })
+      );
+   }
+
+   #[test]
+   fn test_parse_json_document() {
+      assert_eq!(
+         parse_json_document(r#"{"a": [1, 2]}"#),
+         Some(serde_json::json!({ "a": [1, 2] }))
+      );
+      assert_eq!(
+         parse_json_document(" [true, null]"),
+         Some(serde_json::json!([true, null]))
+      );
+      assert_eq!(parse_json_document("{not json"), None);
+      assert_eq!(parse_json_document("42"), None);
+      assert_eq!(parse_json_document(r#""quoted""#), None);
+      assert_eq!(parse_json_document("plain text"), None);
+   }
+
+   #[test]
+   fn test_with_decoders_from_skips_inherited_decoders() {
+      let base = DecodeOptions::new()
+         .with_decoder(DecoderTarget::Column("a".into()), |_| Ok(JsonValue::Null));
+      let once = DecodeOptions::new().with_decoders_from(&base);
+      let twice = DecodeOptions::new()
+         .with_decoder(DecoderTarget::Column("b".into()), |_| Ok(JsonValue::Null))
+         .with_decoders_from(&once)
+         .with_decoders_from(&base);
+
+      assert_eq!(once.decoders.0.len(), 1);
+      assert_eq!(
+         twice.decoders.0.iter().map(|(t, _)| t).collect::<Vec<_>>(),
+         [
+            &DecoderTarget::Column("b".into()),
+            &DecoderTarget::Column("a".into()),
+         ]
+      );
+   }
+
+   #[test]
+   fn test_tagged_bigint() {
+      assert_eq!(
+         tagged_bigint(&serde_json::json!({ "$bigint": "9007199254740993" })),
+         Some(9_007_199_254_740_993)
+      );
+      assert_eq!(
+         tagged_bigint(&serde_json::json!({ "$bigint": "1.5" })),
+         None
+      );
+      assert_eq!(tagged_bigint(&serde_json::json!({ "$bigint": 1 })), None);
+      assert_eq!(
+         tagged_bigint(&serde_json::json!({ "$bigint": "1", "other": true })),
+         None
+      );
+      assert_eq!(tagged_bigint(&serde_json::json!("1")), None);
+   }
+
+   #[test]
+   fn test_tagged_blob() {
+      assert_eq!(
+         tagged_blob(&serde_json::json!({ "$blob": [0, 1, 255] })),
+         Some(vec![0, 1, 255])
+      );
+      assert_eq!(
+         tagged_blob(&serde_json::json!({ "$base64": "AQID" })),
+         Some(vec![1, 2, 3])
+      );
+      assert_eq!(
+         tagged_blob(&serde_json::json!({ "$blob": [] })),
+         Some(vec![])
+      );
+      assert_eq!(tagged_blob(&serde_json::json!({ "$blob": [256] })), None);
+      assert_eq!(tagged_blob(&serde_json::json!({ "$blob": [-1] })), None);
+      assert_eq!(
+         tagged_blob(&serde_json::json!({ "$base64": "not base64!" })),
+         None
+      );
+      assert_eq!(tagged_blob(&serde_json::json!([1, 2, 3])), None);
+   }
+
+   #[test]
+   fn test_tagged_uuid() {
+      let uuid = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+      assert_eq!(
+         tagged_uuid(&serde_json::json!({ "$uuid": uuid })),
+         Some(Uuid::parse_str(uuid).unwrap())
+      );
+      assert_eq!(
+         tagged_uuid(&serde_json::json!({ "$uuid": "not-a-uuid" })),
+         None
+      );
+      assert_eq!(tagged_uuid(&serde_json::json!(uuid)), None);
+   }
+
+   #[test]
+   fn test_affinity() {
+      assert_eq!(affinity(Some("INTEGER")), "INTEGER");
+      assert_eq!(affinity(Some("unsigned big int")), "INTEGER");
+      assert_eq!(affinity(Some("VARCHAR(80)")), "TEXT");
+      assert_eq!(affinity(Some("CLOB")), "TEXT");
+      assert_eq!(affinity(Some("BLOB")), "BLOB");
+      assert_eq!(affinity(None), "BLOB");
+      assert_eq!(affinity(Some("DOUBLE PRECISION")), "REAL");
+      assert_eq!(affinity(Some("FLOAT")), "REAL");
+      assert_eq!(affinity(Some("BOOLEAN")), "NUMERIC");
+      assert_eq!(affinity(Some("DATETIME")), "NUMERIC");
+      assert_eq!(affinity(Some("DECIMAL(10,5)")), "NUMERIC");
+      // INT wins over the other rules, so "POINT" is INTEGER
+      assert_eq!(affinity(Some("POINT")), "INTEGER");
+   }
+
+   #[test]
+   fn test_non_finite_to_json() {
+      assert_eq!(
+         non_finite_to_json(f64::NAN, "x", NonFiniteFormat::Null).unwrap(),
+         JsonValue::Null
+      );
+      assert_eq!(
+         non_finite_to_json(f64::NAN, "x", NonFiniteFormat::String).unwrap(),
+         serde_json::json!("NaN")
+      );
+      assert_eq!(
+         non_finite_to_json(f64::NEG_INFINITY, "x", NonFiniteFormat::String).unwrap(),
+         serde_json::json!("-Infinity")
+      );
+      assert!(matches!(
+         non_finite_to_json(f64::INFINITY, "x", NonFiniteFormat::Error),
+         Err(Error::NonFiniteFloat { column, value }) if column == "x" && value == "Infinity"
+      ));
+   }
+
+   #[test]
+   fn test_invalid_utf8_to_json() {
+      let bytes = b"caf\xe9";
+      assert_eq!(
+         invalid_utf8_to_json(bytes, "x", InvalidUtf8Format::Null).unwrap(),
+         JsonValue::Null
+      );
+      assert_eq!(
+         invalid_utf8_to_json(bytes, "x", InvalidUtf8Format::Lossy).unwrap(),
+         serde_json::json!("caf\u{fffd}")
+      );
+      assert!(matches!(
+         invalid_utf8_to_json(bytes, "x", InvalidUtf8Format::Error),
+         Err(Error::InvalidUtf8 { column }) if column == "x"
+      ));
+   }
+
+   #[test]
+   fn test_column_name_format() {
+      let camel = ColumnNameFormat::CamelCase;
+      assert_eq!(camel.apply("created_at"), "createdAt");
+      assert_eq!(camel.apply("address_line_2"), "addressLine2");
+      assert_eq!(camel.apply("userId"), "userId");
+      assert_eq!(camel.apply("__window_total"), "__windowTotal");
+      assert_eq!(camel.apply("COUNT(*)"), "COUNT(*)");
+      assert_eq!(ColumnNameFormat::AsIs.apply("created_at"), "created_at");
+   }
+
    #[test]
    fn test_base64_encode() {
       assert_eq!(base64_encode(b"hello"), "aGVsbG8=");
@@ -0,0 +1,118 @@
+//! Result column descriptions read from a prepared statement
+
+use std::ffi::{CStr, c_char, c_int};
+use std::ptr;
+
+use libsqlite3_sys as ffi;
+use sqlx::SqliteConnection;
+
+use crate::decode::DecodeOptions;
+use crate::{Error, Result};
+
+/// A result column's name and the type it was declared with
+pub(crate) struct DescribedColumn {
+   pub(crate) name: String,
+   /// As written in the schema, e.g. `VARCHAR(80)`; `None` for columns that
+   /// aren't a table column, such as expressions
+   pub(crate) declared_type: Option<String>,
+}
+
+/// Declared types of a query's result columns, by column index
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeclaredTypes(Vec<Option<String>>);
+
+impl DeclaredTypes {
+   /// Read the declared types of `query`'s result columns on `conn`, or none
+   /// when `options` has no conversion that goes by declared type, sparing
+   /// the extra prepare
+   pub(crate) async fn read(
+      conn: &mut SqliteConnection,
+      query: &str,
+      options: &DecodeOptions,
+   ) -> Result<Self> {
+      if !options.uses_declared_types() {
+         return Ok(Self::default());
+      }
+      Ok(Self::of(&describe_columns(conn, query).await?))
+   }
+
+   /// The declared types of already described columns
+   pub(crate) fn of(columns: &[DescribedColumn]) -> Self {
+      Self(columns.iter().map(|c| c.declared_type.clone()).collect())
+   }
+
+   /// The declared type of the column at `index`, if it has one
+   pub(crate) fn get(&self, index: usize) -> Option<&str> {
+      self.0.get(index)?.as_deref()
+   }
+}
+
+/// Describe the result columns of `query`'s first statement by preparing it
+/// on `conn`, without running it.
+///
+/// sqlx reports declared types only as the few it knows, so this asks SQLite
+/// for them directly.
+pub(crate) async fn describe_columns(
+   conn: &mut SqliteConnection,
+   query: &str,
+) -> Result<Vec<DescribedColumn>> {
+   let len = c_int::try_from(query.len())
+      .map_err(|_| Error::Other("query is too long to describe".into()))?;
+   let mut handle = conn.lock_handle().await?;
+   let db = handle.as_raw_handle().as_ptr();
+
+   let mut stmt = ptr::null_mut();
+   // SAFETY: `db` is a live connection we hold the lock on, and `query`
+   // outlives the call
+   let rc = unsafe {
+      ffi::sqlite3_prepare_v2(
+         db,
+         query.as_ptr().cast::<c_char>(),
+         len,
+         &mut stmt,
+         ptr::null_mut(),
+      )
+   };
+   if rc != ffi::SQLITE_OK {
+      // SAFETY: the connection is still locked, so its message is this error's
+      let message = unsafe { text(ffi::sqlite3_errmsg(db)) }.unwrap_or_default();
+      // SAFETY: finalizing a failed prepare's (possibly null) statement is a no-op
+      unsafe { ffi::sqlite3_finalize(stmt) };
+      return Err(Error::Other(format!("failed to describe query: {message}")));
+   }
+   // A query of only whitespace or comments prepares to no statement
+   if stmt.is_null() {
+      return Ok(Vec::new());
+   }
+
+   // SAFETY: `stmt` was prepared above and is finalized only once its column
+   // strings, which live until then, have been copied
+   let columns = unsafe {
+      let columns = (0..ffi::sqlite3_column_count(stmt))
+         .map(|i| DescribedColumn {
+            name: text(ffi::sqlite3_column_name(stmt, i)).unwrap_or_default(),
+            declared_type: text(ffi::sqlite3_column_decltype(stmt, i)),
+         })
+         .collect();
+      ffi::sqlite3_finalize(stmt);
+      columns
+   };
+   Ok(columns)
+}
+
+/// Copy a string returned by SQLite, if any
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn text(ptr: *const c_char) -> Option<String> {
+   if ptr.is_null() {
+      return None;
+   }
+   // SAFETY: upheld by the caller
+   Some(
+      unsafe { CStr::from_ptr(ptr) }
+         .to_string_lossy()
+         .into_owned(),
+   )
+}
@@ -18,10 +18,37 @@ pub enum Error {
    #[error("unsupported datatype: {0}")]
    UnsupportedDatatype(String),
 
+   /// A REAL value was NaN or infinite while [`NonFiniteFormat::Error`] is set.
+   ///
+   /// [`NonFiniteFormat::Error`]: crate::NonFiniteFormat::Error
+   #[error("column '{column}' holds {value}, which JSON can't represent")]
+   NonFiniteFloat { column: String, value: String },
+
+   /// A TEXT value wasn't valid UTF-8 while [`InvalidUtf8Format::Error`] is set.
+   ///
+   /// [`InvalidUtf8Format::Error`]: crate::InvalidUtf8Format::Error
+   #[error("column '{column}' holds text that isn't valid UTF-8")]
+   InvalidUtf8 { column: String },
+
+   /// Two differently named result columns map to the same row key under
+   /// [`ColumnNameFormat::CamelCase`], e.g. `user_id` and `userId`.
+   ///
+   /// [`ColumnNameFormat::CamelCase`]: crate::ColumnNameFormat::CamelCase
+   #[error("columns '{first}' and '{second}' both map to row key '{key}'")]
+   ColumnKeyCollision {
+      key: String,
+      first: String,
+      second: String,
+   },
+
    /// Multiple rows returned from fetchOne query.
    #[error("fetchOne() query returned {0} rows, expected 0 or 1")]
    MultipleRowsReturned(usize),
 
+   /// A query expected to return a row returned none.
+   #[error("query returned no rows, expected at least 1")]
+   NoRowsReturned,
+
    /// Transaction failed and rollback also failed.
    #[error("transaction failed: {transaction_error}; rollback also failed: {rollback_error}")]
    TransactionRollbackFailed {
@@ -49,11 +76,39 @@ pub enum Error {
    #[error("transaction timed out for database: {0}")]
    TransactionTimedOut(String),
 
+   /// Waited the full queue timeout without the database's interruptible
+   /// transaction ending.
+   #[error("timed out waiting for the active transaction on database: {0}")]
+   TransactionWaitTimeout(String),
+
+   /// No open read snapshot with this ID for this database.
+   #[error("no active read snapshot for database: {0}")]
+   NoActiveSnapshot(String),
+
+   /// Savepoint name is not a plain identifier.
+   #[error("invalid savepoint name '{0}': must match [a-zA-Z_][a-zA-Z0-9_]*")]
+   InvalidSavepointName(String),
+
+   /// No savepoint with this name is active in the transaction.
+   #[error("no active savepoint named '{0}'")]
+   SavepointNotFound(String),
+
+   /// Nested `BEGIN`/`COMMIT`/`ROLLBACK` markers in a transaction's
+   /// statements don't pair up.
+   #[error("unbalanced nested transaction markers: {0}")]
+   UnbalancedNesting(String),
+
    /// Error from the observer (change notifications).
    #[cfg(feature = "observer")]
    #[error(transparent)]
    Observer(#[from] sqlx_sqlite_observer::Error),
 
+   /// An operation needing change notifications was used before
+   /// `enable_observation()`.
+   #[cfg(feature = "observer")]
+   #[error("observation is not enabled on this database")]
+   ObservationNotEnabled,
+
    /// I/O error when accessing database files.
    #[error("io error: {0}")]
    Io(#[from] std::io::Error),
@@ -73,9 +128,10 @@ pub enum Error {
       keyset_len: usize,
    },
 
-   /// Pagination base query must not contain top-level ORDER BY or LIMIT clauses.
+   /// Pagination base query must be a SELECT (optionally preceded by CTEs)
+   /// without top-level ORDER BY or LIMIT clauses.
    #[error(
-      "pagination base query must not contain top-level ORDER BY or LIMIT clauses (these are added automatically; subquery usage is fine)"
+      "pagination base query must be a SELECT and must not contain top-level ORDER BY or LIMIT clauses (these are added automatically; subquery usage is fine)"
    )]
    InvalidPaginationQuery,
 
@@ -90,10 +146,71 @@ pub enum Error {
    #[error("invalid keyset column name '{name}': must match [a-zA-Z_][a-zA-Z0-9_.]*")]
    InvalidColumnName { name: String },
 
+   /// Keyset collation name contains invalid characters.
+   #[error("invalid keyset collation name '{name}': must match [a-zA-Z_][a-zA-Z0-9_]*")]
+   InvalidCollationName { name: String },
+
+   /// No row of the paginated query matches the key given to `containing()`.
+   #[error("no row in the paginated query matches the given key")]
+   RowNotFound,
+
+   /// The key given to `containing()` matches more than one row.
+   #[error("the given key matches more than one row in the paginated query")]
+   AmbiguousRowKey,
+
    /// Cannot provide both `after` and `before` cursors.
    #[error("cannot provide both 'after' and 'before' cursors")]
    ConflictingCursors,
 
+   /// Opaque pagination cursor token could not be decoded or verified.
+   #[error("invalid cursor token: {0}")]
+   InvalidCursorToken(String),
+
+   /// A SQL fragment's `?` placeholders don't match the number of values given.
+   #[error("SQL fragment has {placeholders} placeholders but {values} values were given")]
+   BindCountMismatch { placeholders: usize, values: usize },
+
+   /// The key column given to `fetch_map()` is not in the query results.
+   #[error("map key column '{column}' not found in query results")]
+   MapKeyColumnNotFound { column: String },
+
+   /// Two rows returned to `fetch_map()` share the same key.
+   #[error("duplicate key {key} in map key column '{column}'")]
+   DuplicateMapKey { column: String, key: String },
+
+   /// A decoded row could not be deserialized into the requested type.
+   #[error("failed to deserialize row: {0}")]
+   RowDeserialization(#[source] serde_json::Error),
+
+   /// A statement kept failing with `SQLITE_BUSY`/`SQLITE_LOCKED` until its
+   /// retry policy ran out of attempts.
+   #[error("database still busy after {attempts} attempts: {source}")]
+   RetriesExhausted {
+      attempts: u32,
+      #[source]
+      source: Box<Error>,
+   },
+
+   /// A statement ran longer than the limit set with a builder's `timeout()` and
+   /// was interrupted.
+   #[error("query timed out after {0:?}")]
+   QueryTimeout(std::time::Duration),
+
+   /// A pre-commit hook refused to let a transaction commit, so it was rolled
+   /// back. Hooks return this to report a broken invariant.
+   #[error("commit rejected: {0}")]
+   PreCommitRejected(String),
+
+   /// A batched write ran, but the batch's transaction couldn't be started or
+   /// committed, so none of the batch's writes took effect.
+   #[error("write batch failed: {0}")]
+   WriteBatchFailed(String),
+
+   /// A PRAGMA override given to a builder's `pragma()` is malformed or can't
+   /// be restored afterwards.
+   #[error("invalid pragma override: {0}")]
+   InvalidPragma(String),
+
    /// Generic error for operations that don't fit other categories.
    #[error("{0}")]
    Other(String),
@@ -111,17 +228,43 @@ impl Error {
             }
             "SQLX_ERROR".to_string()
          }
+         Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::WriterAcquireTimeout(_)) => {
+            "WRITER_ACQUIRE_TIMEOUT".to_string()
+         }
+         Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::DatabaseInUse(_)) => {
+            "DATABASE_IN_USE".to_string()
+         }
+         Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::CorruptDatabase(_)) => {
+            "CORRUPT_DATABASE".to_string()
+         }
+         Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::AttachedDatabaseNotFound(_)) => {
+            "ATTACHED_DATABASE_NOT_FOUND".to_string()
+         }
+         Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::AttachFailed { .. }) => {
+            "ATTACH_FAILED".to_string()
+         }
          Error::ConnectionManager(_) => "CONNECTION_ERROR".to_string(),
          Error::UnsupportedDatatype(_) => "UNSUPPORTED_DATATYPE".to_string(),
+         Error::NonFiniteFloat { .. } => "NON_FINITE_FLOAT".to_string(),
+         Error::InvalidUtf8 { .. } => "INVALID_UTF8".to_string(),
+         Error::ColumnKeyCollision { .. } => "COLUMN_KEY_COLLISION".to_string(),
          Error::MultipleRowsReturned(_) => "MULTIPLE_ROWS_RETURNED".to_string(),
+         Error::NoRowsReturned => "NO_ROWS_RETURNED".to_string(),
          Error::TransactionRollbackFailed { .. } => "TRANSACTION_ROLLBACK_FAILED".to_string(),
          Error::TransactionAlreadyFinalized => "TRANSACTION_ALREADY_FINALIZED".to_string(),
          Error::TransactionAlreadyActive(_) => "TRANSACTION_ALREADY_ACTIVE".to_string(),
          Error::NoActiveTransaction(_) => "NO_ACTIVE_TRANSACTION".to_string(),
          Error::InvalidTransactionToken => "INVALID_TRANSACTION_TOKEN".to_string(),
          Error::TransactionTimedOut(_) => "TRANSACTION_TIMED_OUT".to_string(),
+         Error::TransactionWaitTimeout(_) => "TRANSACTION_WAIT_TIMEOUT".to_string(),
+         Error::NoActiveSnapshot(_) => "NO_ACTIVE_SNAPSHOT".to_string(),
+         Error::InvalidSavepointName(_) => "INVALID_SAVEPOINT_NAME".to_string(),
+         Error::SavepointNotFound(_) => "SAVEPOINT_NOT_FOUND".to_string(),
+         Error::UnbalancedNesting(_) => "UNBALANCED_NESTING".to_string(),
          #[cfg(feature = "observer")]
          Error::Observer(_) => "OBSERVER_ERROR".to_string(),
+         #[cfg(feature = "observer")]
+         Error::ObservationNotEnabled => "OBSERVATION_NOT_ENABLED".to_string(),
          Error::Io(_) => "IO_ERROR".to_string(),
          Error::EmptyKeysetColumns => "EMPTY_KEYSET_COLUMNS".to_string(),
          Error::InvalidPageSize => "INVALID_PAGE_SIZE".to_string(),
@@ -129,7 +272,20 @@ impl Error {
          Error::InvalidPaginationQuery => "INVALID_PAGINATION_QUERY".to_string(),
          Error::CursorColumnNotFound { .. } => "CURSOR_COLUMN_NOT_FOUND".to_string(),
          Error::InvalidColumnName { .. } => "INVALID_COLUMN_NAME".to_string(),
+         Error::InvalidCollationName { .. } => "INVALID_COLLATION_NAME".to_string(),
+         Error::RowNotFound => "ROW_NOT_FOUND".to_string(),
+         Error::AmbiguousRowKey => "AMBIGUOUS_ROW_KEY".to_string(),
          Error::ConflictingCursors => "CONFLICTING_CURSORS".to_string(),
+         Error::InvalidCursorToken(_) => "INVALID_CURSOR_TOKEN".to_string(),
+         Error::BindCountMismatch { .. } => "BIND_COUNT_MISMATCH".to_string(),
+         Error::MapKeyColumnNotFound { .. } => "MAP_KEY_COLUMN_NOT_FOUND".to_string(),
+         Error::DuplicateMapKey { .. } => "DUPLICATE_MAP_KEY".to_string(),
+         Error::RowDeserialization(_) => "ROW_DESERIALIZATION_FAILED".to_string(),
+         Error::RetriesExhausted { .. } => "BUSY_RETRIES_EXHAUSTED".to_string(),
+         Error::QueryTimeout(_) => "QUERY_TIMEOUT".to_string(),
+         Error::PreCommitRejected(_) => "PRE_COMMIT_REJECTED".to_string(),
+         Error::WriteBatchFailed(_) => "WRITE_BATCH_FAILED".to_string(),
+         Error::InvalidPragma(_) => "INVALID_PRAGMA".to_string(),
          Error::Other(_) => "ERROR".to_string(),
       }
    }
@@ -139,12 +295,82 @@ impl Error {
 mod tests {
    use super::*;
 
+   #[test]
+   fn test_error_code_writer_acquire_timeout() {
+      let err = Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::WriterAcquireTimeout(
+         std::time::Duration::from_millis(250),
+      ));
+      assert_eq!(err.error_code(), "WRITER_ACQUIRE_TIMEOUT");
+   }
+
+   #[test]
+   fn test_error_code_database_in_use() {
+      let err = Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::DatabaseInUse(2));
+      assert_eq!(err.error_code(), "DATABASE_IN_USE");
+   }
+
+   #[test]
+   fn test_error_code_corrupt_database() {
+      let err = Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::CorruptDatabase(
+         "file is not a database".into(),
+      ));
+      assert_eq!(err.error_code(), "CORRUPT_DATABASE");
+   }
+
+   #[test]
+   fn test_error_code_attached_database_not_found() {
+      let err = Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::AttachedDatabaseNotFound(
+         "/tmp/missing.db".into(),
+      ));
+      assert_eq!(err.error_code(), "ATTACHED_DATABASE_NOT_FOUND");
+   }
+
+   #[test]
+   fn test_error_code_attach_failed() {
+      let err = Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::AttachFailed {
+         schema_name: "orders".into(),
+         path: "/tmp/orders.db".into(),
+         source: sqlx::Error::PoolClosed,
+      });
+      assert_eq!(err.error_code(), "ATTACH_FAILED");
+   }
+
    #[test]
    fn test_error_code_unsupported_datatype() {
       let err = Error::UnsupportedDatatype("WEIRD".into());
       assert_eq!(err.error_code(), "UNSUPPORTED_DATATYPE");
    }
 
+   #[test]
+   fn test_error_code_non_finite_float() {
+      let err = Error::NonFiniteFloat {
+         column: "ratio".into(),
+         value: "Infinity".into(),
+      };
+      assert_eq!(err.error_code(), "NON_FINITE_FLOAT");
+      assert!(err.to_string().contains("'ratio' holds Infinity"));
+   }
+
+   #[test]
+   fn test_error_code_invalid_utf8() {
+      let err = Error::InvalidUtf8 {
+         column: "name".into(),
+      };
+      assert_eq!(err.error_code(), "INVALID_UTF8");
+      assert!(err.to_string().contains("'name'"));
+   }
+
+   #[test]
+   fn test_error_code_column_key_collision() {
+      let err = Error::ColumnKeyCollision {
+         key: "userId".into(),
+         first: "user_id".into(),
+         second: "userId".into(),
+      };
+      assert_eq!(err.error_code(), "COLUMN_KEY_COLLISION");
+      assert!(err.to_string().contains("row key 'userId'"));
+   }
+
    #[test]
    fn test_error_code_multiple_rows_returned() {
       let err = Error::MultipleRowsReturned(5);
@@ -206,6 +432,56 @@ mod tests {
       assert!(err.to_string().contains("test.db"));
    }
 
+   #[test]
+   fn test_error_code_no_active_snapshot() {
+      let err = Error::NoActiveSnapshot("test.db".into());
+      assert_eq!(err.error_code(), "NO_ACTIVE_SNAPSHOT");
+      assert!(err.to_string().contains("test.db"));
+   }
+
+   #[test]
+   fn test_error_code_transaction_wait_timeout() {
+      let err = Error::TransactionWaitTimeout("test.db".into());
+      assert_eq!(err.error_code(), "TRANSACTION_WAIT_TIMEOUT");
+      assert!(err.to_string().contains("test.db"));
+   }
+
+   #[test]
+   fn test_error_code_retries_exhausted() {
+      let err = Error::RetriesExhausted {
+         attempts: 5,
+         source: Box::new(Error::Other("database is locked".into())),
+      };
+      assert_eq!(err.error_code(), "BUSY_RETRIES_EXHAUSTED");
+   }
+
+   #[test]
+   fn test_error_code_query_timeout() {
+      let err = Error::QueryTimeout(std::time::Duration::from_millis(120));
+      assert_eq!(err.error_code(), "QUERY_TIMEOUT");
+   }
+
+   #[test]
+   fn test_error_code_pre_commit_rejected() {
+      let err = Error::PreCommitRejected("balance below zero".into());
+      assert_eq!(err.error_code(), "PRE_COMMIT_REJECTED");
+      assert!(err.to_string().contains("balance below zero"));
+   }
+
+   #[test]
+   fn test_error_code_write_batch_failed() {
+      let err = Error::WriteBatchFailed("database is locked".into());
+      assert_eq!(err.error_code(), "WRITE_BATCH_FAILED");
+      assert!(err.to_string().contains("database is locked"));
+   }
+
+   #[test]
+   fn test_error_code_invalid_pragma() {
+      let err = Error::InvalidPragma("'query_only = 1; --'".into());
+      assert_eq!(err.error_code(), "INVALID_PRAGMA");
+      assert!(err.to_string().contains("query_only"));
+   }
+
    #[test]
    fn test_error_code_other() {
       let err = Error::Other("something went wrong".into());
@@ -270,6 +546,29 @@ mod tests {
       assert!(err.to_string().contains("bad;name"));
    }
 
+   #[test]
+   fn test_error_code_invalid_collation_name() {
+      let err = Error::InvalidCollationName {
+         name: "NOCASE --".into(),
+      };
+      assert_eq!(err.error_code(), "INVALID_COLLATION_NAME");
+      assert!(err.to_string().contains("NOCASE --"));
+   }
+
+   #[test]
+   fn test_error_code_row_not_found() {
+      let err = Error::RowNotFound;
+      assert_eq!(err.error_code(), "ROW_NOT_FOUND");
+      assert!(err.to_string().contains("matches the given key"));
+   }
+
+   #[test]
+   fn test_error_code_ambiguous_row_key() {
+      let err = Error::AmbiguousRowKey;
+      assert_eq!(err.error_code(), "AMBIGUOUS_ROW_KEY");
+      assert!(err.to_string().contains("more than one row"));
+   }
+
    #[test]
    fn test_error_code_conflicting_cursors() {
       let err = Error::ConflictingCursors;
@@ -277,4 +576,45 @@ mod tests {
       assert!(err.to_string().contains("after"));
       assert!(err.to_string().contains("before"));
    }
+
+   #[test]
+   fn test_error_code_invalid_cursor_token() {
+      let err = Error::InvalidCursorToken("signature mismatch".into());
+      assert_eq!(err.error_code(), "INVALID_CURSOR_TOKEN");
+      assert!(err.to_string().contains("signature mismatch"));
+   }
+
+   #[test]
+   fn test_error_code_no_rows_returned() {
+      let err = Error::NoRowsReturned;
+      assert_eq!(err.error_code(), "NO_ROWS_RETURNED");
+   }
+
+   #[test]
+   fn test_error_code_bind_count_mismatch() {
+      let err = Error::BindCountMismatch {
+         placeholders: 2,
+         values: 1,
+      };
+      assert_eq!(err.error_code(), "BIND_COUNT_MISMATCH");
+      assert!(err.to_string().contains("2 placeholders but 1 values"));
+   }
+
+   #[test]
+   fn test_error_code_duplicate_map_key() {
+      let err = Error::DuplicateMapKey {
+         column: "id".into(),
+         key: "7".into(),
+      };
+      assert_eq!(err.error_code(), "DUPLICATE_MAP_KEY");
+      assert!(err.to_string().contains("duplicate key 7"));
+   }
+
+   #[test]
+   fn test_error_code_row_deserialization() {
+      let json_err = serde_json::from_str::<u32>("\"x\"").unwrap_err();
+      let err = Error::RowDeserialization(json_err);
+      assert_eq!(err.error_code(), "ROW_DESERIALIZATION_FAILED");
+      assert!(err.to_string().starts_with("failed to deserialize row:"));
+   }
 }
@@ -0,0 +1,276 @@
+//! Full-text search over a table with an FTS5 index
+//!
+//! [`FtsIndex`] describes an external-content FTS5 table that indexes some TEXT
+//! columns of an ordinary table. [`DatabaseWrapper::create_fts_index`] creates it
+//! together with triggers that keep it in sync with the table, and
+//! [`DatabaseWrapper::search`] runs ranked MATCH queries against it, paginated
+//! with the same keyset pagination as `fetch_page`:
+//!
+//! ```no_run
+//! # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+//! use sqlx_sqlite_toolkit::FtsIndex;
+//!
+//! let index = FtsIndex::new("posts", ["title", "body"]).tokenize("porter unicode61");
+//! db.create_fts_index(&index).await?;
+//!
+//! let page = db
+//!    .search(&index, "sqlite AND pagin*", 20)
+//!    .columns(["id", "title"])
+//!    .snippet("body", 12)
+//!    .await?;
+//!
+//! for row in &page.rows {
+//!    println!("{} {}", row["title"], row["body_snippet"]);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`DatabaseWrapper::create_fts_index`]: crate::DatabaseWrapper::create_fts_index
+//! [`DatabaseWrapper::search`]: crate::DatabaseWrapper::search
+
+use crate::Error;
+use crate::select::unqualified_sql;
+
+/// Result column holding a row's bm25 rank; lower ranks are better matches
+pub const RANK_COLUMN: &str = "rank";
+
+/// Result column holding a row's rowid in the content table
+pub const ROWID_COLUMN: &str = "rowid";
+
+/// An FTS5 index over some columns of a table
+///
+/// The FTS5 table stores only the index and reads column values from the
+/// content table (`content=`), so the text isn't duplicated. It is named
+/// `<table>_fts` unless [`name`](Self::name) is set.
+#[derive(Debug, Clone)]
+pub struct FtsIndex {
+   table: String,
+   columns: Vec<String>,
+   name: Option<String>,
+   tokenize: Option<String>,
+   content_rowid: Option<String>,
+}
+
+impl FtsIndex {
+   /// Index the given TEXT `columns` of `table`
+   pub fn new<I, S>(table: impl Into<String>, columns: I) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      Self {
+         table: table.into(),
+         columns: columns.into_iter().map(Into::into).collect(),
+         name: None,
+         tokenize: None,
+         content_rowid: None,
+      }
+   }
+
+   /// Name the FTS5 table instead of using `<table>_fts`
+   pub fn name(mut self, name: impl Into<String>) -> Self {
+      self.name = Some(name.into());
+      self
+   }
+
+   /// Set the FTS5 tokenizer, e.g. `porter unicode61` or `trigram`
+   pub fn tokenize(mut self, tokenize: impl Into<String>) -> Self {
+      self.tokenize = Some(tokenize.into());
+      self
+   }
+
+   /// Use an `INTEGER PRIMARY KEY` column other than `rowid` to link index
+   /// entries to table rows
+   pub fn content_rowid(mut self, column: impl Into<String>) -> Self {
+      self.content_rowid = Some(column.into());
+      self
+   }
+
+   /// The content table's name
+   pub fn table(&self) -> &str {
+      &self.table
+   }
+
+   /// Name of the FTS5 table
+   pub fn fts_table(&self) -> String {
+      self
+         .name
+         .clone()
+         .unwrap_or_else(|| format!("{}_fts", self.table))
+   }
+
+   /// Position of `column` among the indexed columns, as expected by FTS5
+   /// auxiliary functions
+   pub(crate) fn column_index(&self, column: &str) -> Result<usize, Error> {
+      self
+         .columns
+         .iter()
+         .position(|indexed| indexed == column)
+         .ok_or_else(|| {
+            Error::Other(format!(
+               "column '{}' is not indexed by FTS table '{}'",
+               column,
+               self.fts_table()
+            ))
+         })
+   }
+
+   /// Quoted content table, FTS table and content rowid column
+   pub(crate) fn quoted_names(&self) -> Result<(String, String, String), Error> {
+      Ok((
+         unqualified_sql(&self.table)?,
+         unqualified_sql(&self.fts_table())?,
+         unqualified_sql(self.content_rowid.as_deref().unwrap_or("rowid"))?,
+      ))
+   }
+
+   /// Statements creating the FTS table and its sync triggers, then indexing
+   /// the rows already in the table
+   pub(crate) fn create_statements(&self) -> Result<Vec<String>, Error> {
+      if self.columns.is_empty() {
+         return Err(Error::Other(
+            "an FTS index needs at least one column".into(),
+         ));
+      }
+
+      let (table, fts, rowid) = self.quoted_names()?;
+      let columns = self
+         .columns
+         .iter()
+         .map(|column| unqualified_sql(column))
+         .collect::<Result<Vec<_>, Error>>()?;
+      let prefixed = |prefix: &str| {
+         columns
+            .iter()
+            .map(|column| format!("{}.{}", prefix, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+      };
+      let column_list = columns.join(", ");
+
+      let mut options = format!(
+         "content={}, content_rowid={}",
+         sql_literal(&self.table),
+         sql_literal(self.content_rowid.as_deref().unwrap_or("rowid"))
+      );
+      if let Some(tokenize) = &self.tokenize {
+         options.push_str(&format!(", tokenize={}", sql_literal(tokenize)));
+      }
+
+      let trigger = |suffix: &str| unqualified_sql(&format!("{}_{}", self.fts_table(), suffix));
+      let insert = format!(
+         "INSERT INTO {fts}(rowid, {column_list}) VALUES (new.{rowid}, {})",
+         prefixed("new")
+      );
+      let delete = format!(
+         "INSERT INTO {fts}({fts}, rowid, {column_list}) VALUES ('delete', old.{rowid}, {})",
+         prefixed("old")
+      );
+
+      Ok(vec![
+         format!("CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5({column_list}, {options})"),
+         format!(
+            "CREATE TRIGGER IF NOT EXISTS {} AFTER INSERT ON {table} BEGIN {insert}; END",
+            trigger("ai")?
+         ),
+         format!(
+            "CREATE TRIGGER IF NOT EXISTS {} AFTER DELETE ON {table} BEGIN {delete}; END",
+            trigger("ad")?
+         ),
+         format!(
+            "CREATE TRIGGER IF NOT EXISTS {} AFTER UPDATE ON {table} BEGIN {delete}; {insert}; END",
+            trigger("au")?
+         ),
+         self.rebuild_statement()?,
+      ])
+   }
+
+   /// Statement re-indexing every row of the content table
+   pub(crate) fn rebuild_statement(&self) -> Result<String, Error> {
+      let fts = unqualified_sql(&self.fts_table())?;
+      Ok(format!("INSERT INTO {fts}({fts}) VALUES ('rebuild')"))
+   }
+
+   /// Statements dropping the sync triggers and the FTS table
+   pub(crate) fn drop_statements(&self) -> Result<Vec<String>, Error> {
+      let fts = unqualified_sql(&self.fts_table())?;
+      let mut statements = ["ai", "ad", "au"]
+         .iter()
+         .map(|suffix| {
+            Ok(format!(
+               "DROP TRIGGER IF EXISTS {}",
+               unqualified_sql(&format!("{}_{}", self.fts_table(), suffix))?
+            ))
+         })
+         .collect::<Result<Vec<_>, Error>>()?;
+      statements.push(format!("DROP TABLE IF EXISTS {fts}"));
+      Ok(statements)
+   }
+}
+
+/// Render `value` as a single-quoted SQL string literal
+fn sql_literal(value: &str) -> String {
+   format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn create_statements_keep_index_in_sync() {
+      let statements = FtsIndex::new("posts", ["title", "body"])
+         .tokenize("porter 'x'")
+         .content_rowid("id")
+         .create_statements()
+         .unwrap();
+
+      assert_eq!(
+         statements,
+         vec![
+            "CREATE VIRTUAL TABLE IF NOT EXISTS \"posts_fts\" USING fts5(\"title\", \"body\", \
+             content='posts', content_rowid='id', tokenize='porter ''x''')",
+            "CREATE TRIGGER IF NOT EXISTS \"posts_fts_ai\" AFTER INSERT ON \"posts\" BEGIN \
+             INSERT INTO \"posts_fts\"(rowid, \"title\", \"body\") \
+             VALUES (new.\"id\", new.\"title\", new.\"body\"); END",
+            "CREATE TRIGGER IF NOT EXISTS \"posts_fts_ad\" AFTER DELETE ON \"posts\" BEGIN \
+             INSERT INTO \"posts_fts\"(\"posts_fts\", rowid, \"title\", \"body\") \
+             VALUES ('delete', old.\"id\", old.\"title\", old.\"body\"); END",
+            "CREATE TRIGGER IF NOT EXISTS \"posts_fts_au\" AFTER UPDATE ON \"posts\" BEGIN \
+             INSERT INTO \"posts_fts\"(\"posts_fts\", rowid, \"title\", \"body\") \
+             VALUES ('delete', old.\"id\", old.\"title\", old.\"body\"); \
+             INSERT INTO \"posts_fts\"(rowid, \"title\", \"body\") \
+             VALUES (new.\"id\", new.\"title\", new.\"body\"); END",
+            "INSERT INTO \"posts_fts\"(\"posts_fts\") VALUES ('rebuild')",
+         ]
+      );
+   }
+
+   #[test]
+   fn create_statements_reject_bad_names() {
+      assert!(
+         FtsIndex::new("posts", Vec::<String>::new())
+            .create_statements()
+            .is_err()
+      );
+      assert!(
+         FtsIndex::new("main.posts", ["title"])
+            .create_statements()
+            .is_err()
+      );
+      assert!(
+         FtsIndex::new("posts", ["title\"); DROP TABLE posts; --"])
+            .create_statements()
+            .is_err()
+      );
+   }
+
+   #[test]
+   fn column_index_follows_declaration_order() {
+      let index = FtsIndex::new("posts", ["title", "body"]).name("search");
+      assert_eq!(index.fts_table(), "search");
+      assert_eq!(index.column_index("body").unwrap(), 1);
+      assert!(index.column_index("author").is_err());
+   }
+}
@@ -4,8 +4,10 @@
 //! and application-level code (e.g., a Tauri plugin). It provides:
 //!
 //! - [`DatabaseWrapper`] — main entry point wrapping a connection-managed database
-//! - Builder-pattern APIs for queries ([`ExecuteBuilder`], [`FetchAllBuilder`], [`FetchOneBuilder`], [`FetchPageBuilder`])
+//! - Builder-pattern APIs for queries ([`ExecuteBuilder`], [`InsertBuilder`], [`FetchAllBuilder`], [`FetchOneBuilder`], [`FetchPageBuilder`])
 //! - Transaction support ([`TransactionExecutionBuilder`], [`InterruptibleTransactionBuilder`])
+//! - [`SelectBuilder`] for assembling simple SELECT queries without hand-numbered binds
+//! - FTS5 full-text search with ranked, paginated results ([`FtsIndex`], [`SearchBuilder`])
 //! - JSON type decoding for SQLite values
 //!
 //! # Example
@@ -34,26 +36,62 @@
 //! # }
 //! ```
 
+pub mod batch;
+mod binds;
 pub mod builders;
 pub mod decode;
+mod describe;
 pub mod error;
+pub mod fts;
 pub mod pagination;
+mod pragma;
+pub mod retry;
+mod script;
+pub mod select;
+pub mod snapshot;
+mod soft_delete;
+mod timeout;
 pub mod transactions;
+#[cfg(feature = "observer")]
+mod undo;
+#[cfg(feature = "observer")]
+mod watch;
 pub mod wrapper;
 
-pub use builders::{ExecuteBuilder, FetchAllBuilder, FetchOneBuilder, FetchPageBuilder};
+pub use batch::WriteBatchConfig;
+pub use builders::{
+   ExecuteBuilder, ExecuteReturningBuilder, FetchAllAsBuilder, FetchAllBuilder,
+   FetchColumnarBuilder, FetchMapBuilder, FetchOneAsBuilder, FetchOneBuilder, FetchPageBuilder,
+   FetchScalarBuilder, FetchWithColumnTypesBuilder, InsertBuilder, InsertManyBuilder,
+   JsonGetBuilder, JsonSetBuilder, RetryExecuteBuilder, RetryFetchAllBuilder, SearchBuilder,
+};
+pub use decode::{
+   BigIntFormat, BooleanFormat, ColumnNameFormat, DatetimeFormat, DecodeOptions, Decoder,
+   DecoderTarget, InvalidUtf8Format, JsonFormat, NonFiniteFormat, UuidFormat,
+};
 pub use error::{Error, Result};
-pub use pagination::{KeysetColumn, KeysetPage, SortDirection};
+pub use fts::FtsIndex;
+pub use pagination::{Cursor, KeysetColumn, KeysetPage, NullsOrder, SortDirection};
+pub use retry::{Retried, RetryPolicy};
+pub use select::{CompareOp, SelectBuilder};
+pub use snapshot::{ActiveReadSnapshots, ReadSnapshot};
 pub use transactions::{
    ActiveInterruptibleTransaction, ActiveInterruptibleTransactions, ActiveRegularTransactions,
-   Statement, TransactionWriter, cleanup_all_transactions,
+   InterruptibleTransactionInfo, PreCommitHook, RegularTransactionInfo, Statement, TransactionMode,
+   TransactionSlot, TransactionStats, TransactionWriter, cleanup_all_transactions,
 };
+#[cfg(feature = "observer")]
+pub use undo::UndoManager;
+#[cfg(feature = "observer")]
+pub use watch::{PageWatch, QueryWatch};
 pub use wrapper::{
-   DatabaseWrapper, InterruptibleTransaction, InterruptibleTransactionBuilder,
-   TransactionExecutionBuilder, WriteQueryResult, WriterGuard, bind_value,
+   ColumnType, ColumnarRows, DatabaseWrapper, InterruptibleTransaction,
+   InterruptibleTransactionBuilder, RowsWithColumnTypes, TransactionExecutionBuilder,
+   WriteQueryResult, WriteReturningResult, WriterGuard, bind_value,
 };
 
 // Re-export commonly used types from dependencies
 pub use sqlx_sqlite_conn_mgr::{
-   AttachedMode, AttachedSpec, Migrator, SqliteDatabase, SqliteDatabaseConfig,
+   AttachedMode, AttachedSpec, CorruptionRecovery, InterruptHandle, Migrator, RecoveryReport,
+   SqliteDatabase, SqliteDatabaseConfig, TempStore, WritePriority,
 };
@@ -26,13 +26,20 @@
 //! ];
 //! ```
 
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use sha2::Sha256;
 
 use crate::Error;
 
 /// Sort direction for a keyset column.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum SortDirection {
    /// Ascending order (smallest first)
@@ -51,13 +58,49 @@ impl SortDirection {
    }
 }
 
+/// Placement of NULL values relative to non-NULL values in a keyset column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NullsOrder {
+   /// NULLs sort before all other values
+   First,
+   /// NULLs sort after all other values
+   Last,
+}
+
+impl NullsOrder {
+   /// Return the opposite NULL placement.
+   pub fn reversed(self) -> Self {
+      match self {
+         NullsOrder::First => NullsOrder::Last,
+         NullsOrder::Last => NullsOrder::First,
+      }
+   }
+}
+
 /// A column in the keyset used for cursor-based pagination.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct KeysetColumn {
    /// Column name as it appears in the query result set
    pub name: String,
    /// Sort direction for this column
    pub direction: SortDirection,
+   /// Where NULLs sort in this column.
+   ///
+   /// `None` keeps SQLite's default, where NULL is the smallest value: first
+   /// when ascending, last when descending.
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub nulls: Option<NullsOrder>,
+   /// Declares that the column never contains NULL (e.g. a primary key), so
+   /// cursor conditions can skip the `IS NULL` terms that otherwise keep
+   /// index range scans from being used when NULLs sort last.
+   #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+   pub not_null: bool,
+   /// Collating sequence (e.g. `NOCASE`) applied to both the ORDER BY and the
+   /// cursor comparisons for this column, or `None` for the column's own.
+   #[serde(default, skip_serializing_if = "Option::is_none")]
+   pub collation: Option<String>,
 }
 
 impl KeysetColumn {
@@ -66,6 +109,9 @@ impl KeysetColumn {
       Self {
          name: name.into(),
          direction: SortDirection::Asc,
+         nulls: None,
+         not_null: false,
+         collation: None,
       }
    }
 
@@ -74,8 +120,64 @@ impl KeysetColumn {
       Self {
          name: name.into(),
          direction: SortDirection::Desc,
+         nulls: None,
+         not_null: false,
+         collation: None,
+      }
+   }
+
+   /// Sort NULLs before all other values in this column.
+   pub fn nulls_first(mut self) -> Self {
+      self.nulls = Some(NullsOrder::First);
+      self
+   }
+
+   /// Sort NULLs after all other values in this column.
+   pub fn nulls_last(mut self) -> Self {
+      self.nulls = Some(NullsOrder::Last);
+      self
+   }
+
+   /// Declare that the column never contains NULL.
+   ///
+   /// Keeps cursor conditions on descending (or `nulls_last`) columns as plain
+   /// comparisons. Rows with NULL in such a column would be skipped.
+   pub fn not_null(mut self) -> Self {
+      self.not_null = true;
+      self
+   }
+
+   /// Sort and compare this column using the named collating sequence, such as
+   /// `NOCASE`, `RTRIM` or a collation registered on the connection.
+   ///
+   /// Needed whenever the sort should differ from the column's declared
+   /// collation, e.g. a case-insensitive sort of a `BINARY` text column.
+   pub fn with_collation(mut self, collation: impl Into<String>) -> Self {
+      self.collation = Some(collation.into());
+      self
+   }
+
+   /// The column reference used in ORDER BY and comparisons, including any
+   /// COLLATE clause.
+   fn sql_expr(&self) -> String {
+      match &self.collation {
+         Some(collation) => format!("{} COLLATE {}", quote_identifier(&self.name), collation),
+         None => quote_identifier(&self.name),
       }
    }
+
+   /// Where NULLs actually sort, resolving SQLite's default when unset.
+   fn effective_nulls(&self) -> NullsOrder {
+      self.nulls.unwrap_or(match self.direction {
+         SortDirection::Asc => NullsOrder::First,
+         SortDirection::Desc => NullsOrder::Last,
+      })
+   }
+
+   /// Whether rows with NULL in this column can sort after a non-NULL cursor.
+   fn nulls_follow_values(&self) -> bool {
+      !self.not_null && self.effective_nulls() == NullsOrder::Last
+   }
 }
 
 /// Validate that a column name is safe for SQL interpolation.
@@ -116,6 +218,26 @@ pub(crate) fn validate_column_name(name: &str) -> Result<(), Error> {
    Ok(())
 }
 
+/// Validate that a collation name is safe for SQL interpolation.
+///
+/// Accepts plain identifiers matching `[a-zA-Z_][a-zA-Z0-9_]*`, which covers
+/// the built-in `BINARY`, `NOCASE` and `RTRIM` as well as custom collations.
+pub(crate) fn validate_collation_name(name: &str) -> Result<(), Error> {
+   let mut chars = name.chars();
+   let valid = chars
+      .next()
+      .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+      && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+   if !valid {
+      return Err(Error::InvalidCollationName {
+         name: name.to_string(),
+      });
+   }
+
+   Ok(())
+}
+
 /// Quote a column name with double-quote identifiers for defense-in-depth.
 ///
 /// Qualified names (e.g., `table.column`) are split on `.` and each part is
@@ -130,7 +252,7 @@ pub(crate) fn quote_identifier(name: &str) -> String {
 }
 
 /// A page of results from keyset pagination.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct KeysetPage {
    /// The rows in this page
@@ -141,8 +263,159 @@ pub struct KeysetPage {
    /// After `.after()`, pass to another `.after()` for the next page.
    /// After `.before()`, pass to another `.before()` to keep going backward.
    pub next_cursor: Option<Vec<JsonValue>>,
+   /// Opaque token encoding `next_cursor`, returned instead of it when the
+   /// builder was configured with `.opaque_cursors()` or `.signed_cursors()`.
+   ///
+   /// Pass it back to `.after()` or `.before()` exactly like `next_cursor`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub next_cursor_token: Option<String>,
    /// Whether there are more rows in the current pagination direction
    pub has_more: bool,
+   /// Cursor of the first row on this page, or `None` if the page is empty.
+   ///
+   /// Pass to `.before()` for the previous page. Same form as
+   /// `next_cursor`/`next_cursor_token`: raw values, or a token with
+   /// `.opaque_cursors()`.
+   pub start_cursor: Option<Cursor>,
+   /// Cursor of the last row on this page, or `None` if the page is empty.
+   ///
+   /// Pass to `.after()` for the next page.
+   pub end_cursor: Option<Cursor>,
+   /// Whether rows precede this page in the original sort order.
+   ///
+   /// Exact when paginating backward. Going forward, no query is spent on
+   /// it: `true` whenever the page was positioned past the start (by an
+   /// `.after()` cursor or `.containing()`).
+   pub has_previous: bool,
+   /// Whether rows follow this page in the original sort order.
+   ///
+   /// Exact when paginating forward. Going backward, `true` since the
+   /// `.before()` cursor row follows the page.
+   pub has_next: bool,
+   /// Number of rows that precede this page in the full ordering.
+   ///
+   /// Only populated for pages fetched with `.containing()`; divide by the
+   /// page size for the zero-based page number.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub offset: Option<u64>,
+   /// Total number of rows matched by the base query, ignoring the cursor.
+   ///
+   /// Only populated when the builder was configured with `.with_total_count()`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub total: Option<u64>,
+}
+
+/// A pagination cursor accepted by `.after()` and `.before()`.
+///
+/// Either the raw keyset values from [`KeysetPage::next_cursor`] or the opaque
+/// token from [`KeysetPage::next_cursor_token`]. Deserializes from a JSON array
+/// or a string respectively.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Cursor {
+   /// Opaque (optionally signed) cursor token
+   Token(String),
+   /// Raw keyset values, one per keyset column
+   Values(Vec<JsonValue>),
+}
+
+impl From<Vec<JsonValue>> for Cursor {
+   fn from(values: Vec<JsonValue>) -> Self {
+      Cursor::Values(values)
+   }
+}
+
+impl From<String> for Cursor {
+   fn from(token: String) -> Self {
+      Cursor::Token(token)
+   }
+}
+
+impl From<&str> for Cursor {
+   fn from(token: &str) -> Self {
+      Cursor::Token(token.to_string())
+   }
+}
+
+/// Contents of an opaque cursor token. The keyset is included so a token
+/// issued for one sort order is rejected when replayed against another.
+#[derive(Serialize, Deserialize)]
+struct CursorPayload {
+   k: Vec<KeysetColumn>,
+   v: Vec<JsonValue>,
+}
+
+fn cursor_mac(key: &[u8]) -> Hmac<Sha256> {
+   Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+/// Encode cursor values as an opaque token.
+///
+/// The token is the URL-safe base64 encoding of a JSON payload holding the
+/// keyset and the values. With a `key`, a `.`-separated HMAC-SHA256 signature
+/// over the encoded payload is appended.
+pub(crate) fn encode_cursor_token(
+   keyset: &[KeysetColumn],
+   values: &[JsonValue],
+   key: Option<&[u8]>,
+) -> String {
+   let payload = CursorPayload {
+      k: keyset.to_vec(),
+      v: values.to_vec(),
+   };
+   // Serializing plain JSON values and strings cannot fail
+   let json = serde_json::to_vec(&payload).expect("cursor payload is serializable");
+   let encoded = URL_SAFE_NO_PAD.encode(json);
+
+   match key {
+      Some(key) => {
+         let mut mac = cursor_mac(key);
+         mac.update(encoded.as_bytes());
+         let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+         format!("{encoded}.{signature}")
+      }
+      None => encoded,
+   }
+}
+
+/// Decode a token produced by [`encode_cursor_token`] back into cursor values.
+///
+/// With a `key`, the token must carry a valid signature. The token's keyset
+/// must match `keyset` exactly.
+pub(crate) fn decode_cursor_token(
+   token: &str,
+   keyset: &[KeysetColumn],
+   key: Option<&[u8]>,
+) -> Result<Vec<JsonValue>, Error> {
+   let invalid = |reason: &str| Error::InvalidCursorToken(reason.to_string());
+
+   let (encoded, signature) = match token.split_once('.') {
+      Some((encoded, signature)) => (encoded, Some(signature)),
+      None => (token, None),
+   };
+
+   if let Some(key) = key {
+      let signature = signature.ok_or_else(|| invalid("token is not signed"))?;
+      let signature = URL_SAFE_NO_PAD
+         .decode(signature)
+         .map_err(|_| invalid("malformed signature"))?;
+      let mut mac = cursor_mac(key);
+      mac.update(encoded.as_bytes());
+      mac.verify_slice(&signature)
+         .map_err(|_| invalid("signature mismatch"))?;
+   }
+
+   let json = URL_SAFE_NO_PAD
+      .decode(encoded)
+      .map_err(|_| invalid("malformed encoding"))?;
+   let payload: CursorPayload =
+      serde_json::from_slice(&json).map_err(|_| invalid("malformed payload"))?;
+
+   if payload.k != keyset {
+      return Err(invalid("token was issued for a different keyset"));
+   }
+
+   Ok(payload.v)
 }
 
 /// Check whether `keyword` appears as a standalone keyword at position `i`
@@ -150,7 +423,7 @@ pub struct KeysetPage {
 ///
 /// "Standalone" means the character before and after the keyword (if present)
 /// is not an identifier character (`[A-Z0-9_]`).
-fn is_keyword_at(bytes: &[u8], len: usize, i: usize, keyword: &[u8]) -> bool {
+pub(crate) fn is_keyword_at(bytes: &[u8], len: usize, i: usize, keyword: &[u8]) -> bool {
    let klen = keyword.len();
    if i + klen > len {
       return false;
@@ -165,24 +438,36 @@ fn is_keyword_at(bytes: &[u8], len: usize, i: usize, keyword: &[u8]) -> bool {
    before_ok && after_ok
 }
 
-/// Check whether `ORDER BY` starts at position `i`, allowing any amount of
-/// whitespace (spaces, tabs, newlines) between `ORDER` and `BY`.
-fn is_order_by_at(bytes: &[u8], len: usize, i: usize) -> bool {
-   if !is_keyword_at(bytes, len, i, b"ORDER") {
+/// Check whether the two-word keyword `first second` (e.g. `ORDER BY`) starts
+/// at position `i`, allowing any amount of whitespace (spaces, tabs,
+/// newlines) between the words.
+pub(crate) fn is_keyword_pair_at(
+   bytes: &[u8],
+   len: usize,
+   i: usize,
+   first: &[u8],
+   second: &[u8],
+) -> bool {
+   if !is_keyword_at(bytes, len, i, first) {
       return false;
    }
-   let mut j = i + 5; // skip "ORDER"
+   let mut j = i + first.len();
    while j < len && bytes[j].is_ascii_whitespace() {
       j += 1;
    }
-   is_keyword_at(bytes, len, j, b"BY")
+   is_keyword_at(bytes, len, j, second)
+}
+
+/// Check whether `ORDER BY` starts at position `i`.
+pub(crate) fn is_order_by_at(bytes: &[u8], len: usize, i: usize) -> bool {
+   is_keyword_pair_at(bytes, len, i, b"ORDER", b"BY")
 }
 
 /// Advance the scanner index past a quoted literal or identifier.
 ///
 /// `quote` is the opening quote character (`'` or `"`). The scanner handles
 /// SQL-standard doubled-quote escaping (`''` or `""`).
-fn skip_quoted(bytes: &[u8], len: usize, i: usize, quote: u8) -> usize {
+pub(crate) fn skip_quoted(bytes: &[u8], len: usize, i: usize, quote: u8) -> usize {
    let mut j = i + 1;
    while j < len {
       if bytes[j] == quote {
@@ -200,7 +485,7 @@ fn skip_quoted(bytes: &[u8], len: usize, i: usize, quote: u8) -> usize {
 }
 
 /// Advance the scanner index past a `--` line comment (until newline or end).
-fn skip_line_comment(bytes: &[u8], len: usize, i: usize) -> usize {
+pub(crate) fn skip_line_comment(bytes: &[u8], len: usize, i: usize) -> usize {
    let mut j = i + 2; // skip the `--`
    while j < len && bytes[j] != b'\n' {
       j += 1;
@@ -209,7 +494,7 @@ fn skip_line_comment(bytes: &[u8], len: usize, i: usize) -> usize {
 }
 
 /// Advance the scanner index past a `/* … */` block comment.
-fn skip_block_comment(bytes: &[u8], len: usize, i: usize) -> usize {
+pub(crate) fn skip_block_comment(bytes: &[u8], len: usize, i: usize) -> usize {
    let mut j = i + 2; // skip the `/*`
    while j + 1 < len {
       if bytes[j] == b'*' && bytes[j + 1] == b'/' {
@@ -225,11 +510,13 @@ fn skip_block_comment(bytes: &[u8], len: usize, i: usize) -> usize {
 ///
 /// `on_keyword` receives `(uppercased_bytes, len, position)` and returns
 /// `Some(T)` to short-circuit or `None` to keep scanning.
-fn scan_top_level<T>(
+pub(crate) fn scan_top_level<T>(
    query: &str,
    mut on_keyword: impl FnMut(&[u8], usize, usize) -> Option<T>,
 ) -> Option<T> {
-   let upper = query.to_uppercase();
+   // ASCII-only uppercasing keeps byte positions aligned with `query`, so
+   // callers can use them to slice the original string
+   let upper = query.to_ascii_uppercase();
    let bytes = upper.as_bytes();
    let len = bytes.len();
    let mut depth: i32 = 0;
@@ -272,13 +559,35 @@ fn scan_top_level<T>(
    None
 }
 
+/// Find the byte offset of the main SELECT of a base query.
+///
+/// For a plain query this is the leading `SELECT`. For a query starting with
+/// `WITH [RECURSIVE]`, the CTE definitions are skipped: their bodies are
+/// parenthesized, so the first `SELECT` at paren depth 0 is the statement the
+/// CTEs feed into. Returns `None` if there is no top-level `SELECT` (e.g. a
+/// CTE followed by `DELETE`).
+pub(crate) fn main_select_offset(query: &str) -> Option<usize> {
+   scan_top_level(query, |bytes, len, i| {
+      if is_keyword_at(bytes, len, i, b"SELECT") {
+         Some(i)
+      } else {
+         None
+      }
+   })
+}
+
 /// Validate that a base query does not contain top-level ORDER BY or LIMIT.
 ///
 /// These clauses conflict with the pagination logic, which appends its own
 /// ORDER BY and LIMIT automatically. Clauses inside parenthesized
-/// subexpressions (e.g., subqueries), comments, and string literals are
-/// allowed.
+/// subexpressions (e.g., subqueries or CTE bodies), comments, and string
+/// literals are allowed. A query must contain a top-level SELECT for the
+/// cursor condition to apply to.
 pub(crate) fn validate_base_query(query: &str) -> Result<(), Error> {
+   if main_select_offset(query).is_none() {
+      return Err(Error::InvalidPaginationQuery);
+   }
+
    let found_forbidden = scan_top_level(query, |bytes, len, i| {
       if is_order_by_at(bytes, len, i) {
          return Some(());
@@ -308,6 +617,60 @@ pub(crate) fn has_top_level_where(query: &str) -> bool {
    .is_some()
 }
 
+/// Detect whether a SELECT aggregates its rows at paren depth 0, via
+/// `GROUP BY` or `HAVING`.
+///
+/// A cursor condition cannot simply be appended to such a query: after
+/// `GROUP BY` it would land in the grouping expression or produce invalid
+/// SQL, and it must compare the aggregated result columns rather than the
+/// underlying rows anyway.
+pub(crate) fn has_top_level_grouping(query: &str) -> bool {
+   scan_top_level(query, |bytes, len, i| {
+      if is_keyword_pair_at(bytes, len, i, b"GROUP", b"BY")
+         || is_keyword_at(bytes, len, i, b"HAVING")
+      {
+         Some(())
+      } else {
+         None
+      }
+   })
+   .is_some()
+}
+
+/// Detect whether a query combines SELECTs at paren depth 0 with `UNION`,
+/// `INTERSECT` or `EXCEPT`.
+///
+/// A cursor condition appended to such a query would only filter its last
+/// arm, so the other arms would return every row on every page.
+pub(crate) fn has_top_level_compound(query: &str) -> bool {
+   scan_top_level(query, |bytes, len, i| {
+      if is_keyword_at(bytes, len, i, b"UNION")
+         || is_keyword_at(bytes, len, i, b"INTERSECT")
+         || is_keyword_at(bytes, len, i, b"EXCEPT")
+      {
+         Some(())
+      } else {
+         None
+      }
+   })
+   .is_some()
+}
+
+/// Detect whether `select` (starting at its `SELECT` keyword) is a
+/// `SELECT DISTINCT`.
+///
+/// Its keyset columns are result columns that may be expressions or aliases,
+/// which a cursor condition in its WHERE clause could not refer to.
+pub(crate) fn is_distinct_select(select: &str) -> bool {
+   let upper = select.to_ascii_uppercase();
+   let bytes = upper.as_bytes();
+   let mut i = "SELECT".len();
+   while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+      i += 1;
+   }
+   is_keyword_at(bytes, bytes.len(), i, b"DISTINCT")
+}
+
 /// Build the cursor WHERE condition for seeking past the previous page.
 ///
 /// `param_offset` is the number of user-supplied bind values that precede
@@ -317,71 +680,115 @@ pub(crate) fn has_top_level_where(query: &str) -> bool {
 ///
 /// Returns the SQL fragment and the bind values to use.
 ///
-/// For uniform direction (all ASC or all DESC), uses row-value comparison:
-/// `(col1, col2) > ($3, $4)` or `(col1, col2) < ($3, $4)`
+/// When all columns share a direction, none can hold NULLs that sort after the
+/// cursor (NULLs first, or declared `not_null`), and no cursor value is NULL,
+/// uses row-value comparison:
+/// `(col1, col2) > ($3, $4)` or `(col1, col2) < ($3, $4)`. A NULL column then
+/// makes the comparison NULL, which correctly excludes rows that sort before
+/// the cursor.
 ///
-/// For mixed directions, uses expanded OR form:
-/// `(a > $3) OR (a = $4 AND b < $5) OR (a = $6 AND b = $7 AND c > $8)`
+/// Otherwise uses the expanded OR form, with NULL-aware terms for each column:
+/// `(a > $3) OR (a = $4 AND (b < $5 OR b IS NULL)) OR (a = $6 AND b IS NULL)`
 pub(crate) fn build_cursor_condition(
    keyset: &[KeysetColumn],
    cursor_values: &[JsonValue],
    param_offset: usize,
 ) -> (String, Vec<JsonValue>) {
+   let (sql, binds) = cursor_condition_binds(keyset, cursor_values, param_offset);
+   let values = binds.iter().map(|&i| cursor_values[i].clone()).collect();
+   (sql, values)
+}
+
+/// Like [`build_cursor_condition`], but return, for each placeholder, the
+/// index of the cursor value bound to it.
+///
+/// The SQL only depends on which cursor values are NULL, so it can be reused
+/// for any cursor with the same NULLs.
+fn cursor_condition_binds(
+   keyset: &[KeysetColumn],
+   cursor_values: &[JsonValue],
+   param_offset: usize,
+) -> (String, Vec<usize>) {
    let n = keyset.len();
    let mut next_param = param_offset + 1;
 
    // Check if all directions are the same (uniform)
    let all_asc = keyset.iter().all(|k| k.direction == SortDirection::Asc);
    let all_desc = keyset.iter().all(|k| k.direction == SortDirection::Desc);
+   let no_trailing_nulls = keyset.iter().all(|k| !k.nulls_follow_values());
+   let no_null_values = cursor_values.iter().all(|v| !v.is_null());
 
-   if all_asc || all_desc {
+   if (all_asc || all_desc) && no_trailing_nulls && no_null_values {
       // Uniform direction: use row-value comparison
-      let cols: Vec<String> = keyset.iter().map(|k| quote_identifier(&k.name)).collect();
+      let cols: Vec<String> = keyset.iter().map(KeysetColumn::sql_expr).collect();
       let placeholders: Vec<String> = (0..n).map(|i| format!("${}", next_param + i)).collect();
       let op = if all_asc { ">" } else { "<" };
 
       let sql = format!("({}) {} ({})", cols.join(", "), op, placeholders.join(", "));
-      let values = cursor_values.to_vec();
-      return (sql, values);
+      return (sql, (0..n).collect());
    }
 
-   // Mixed directions: expanded OR form
+   // Expanded OR form
    let mut clauses = Vec::new();
-   let mut values = Vec::new();
+   let mut binds = Vec::new();
 
    for level in 0..n {
+      let col = &keyset[level];
+      let value = &cursor_values[level];
+
+      // Nothing sorts after a NULL when NULLs sort last, so this level can
+      // never match
+      if value.is_null() && col.effective_nulls() == NullsOrder::Last {
+         continue;
+      }
+
       let mut parts = Vec::new();
 
       // Equality conditions for all columns before this level
       for eq_idx in 0..level {
-         parts.push(format!(
-            "{} = ${}",
-            quote_identifier(&keyset[eq_idx].name),
-            next_param
-         ));
-         next_param += 1;
-         values.push(cursor_values[eq_idx].clone());
+         let eq_col = &keyset[eq_idx];
+         if cursor_values[eq_idx].is_null() {
+            parts.push(format!("{} IS NULL", quote_identifier(&eq_col.name)));
+         } else {
+            parts.push(format!("{} = ${}", eq_col.sql_expr(), next_param));
+            next_param += 1;
+            binds.push(eq_idx);
+         }
       }
 
       // Inequality condition for the column at this level
-      let op = match keyset[level].direction {
-         SortDirection::Asc => ">",
-         SortDirection::Desc => "<",
-      };
-      parts.push(format!(
-         "{} {} ${}",
-         quote_identifier(&keyset[level].name),
-         op,
-         next_param
-      ));
-      next_param += 1;
-      values.push(cursor_values[level].clone());
+      let name = quote_identifier(&col.name);
+      if value.is_null() {
+         // Cursor is NULL and NULLs sort first: every non-NULL value follows
+         parts.push(format!("{} IS NOT NULL", name));
+      } else {
+         let op = match col.direction {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+         };
+         let term = format!("{} {} ${}", col.sql_expr(), op, next_param);
+         if !col.nulls_follow_values() {
+            parts.push(term);
+         } else if level == 0 {
+            // Sole part of its clause, which is parenthesized already
+            parts.push(format!("{} OR {} IS NULL", term, name));
+         } else {
+            parts.push(format!("({} OR {} IS NULL)", term, name));
+         }
+         next_param += 1;
+         binds.push(level);
+      }
 
       clauses.push(format!("({})", parts.join(" AND ")));
    }
 
+   if clauses.is_empty() {
+      // The cursor is at the very end of the ordering
+      return ("0".to_string(), binds);
+   }
+
    let sql = clauses.join(" OR ");
-   (sql, values)
+   (sql, binds)
 }
 
 /// Build the ORDER BY clause from the keyset definition.
@@ -393,7 +800,11 @@ pub(crate) fn build_order_by(keyset: &[KeysetColumn]) -> String {
             SortDirection::Asc => "ASC",
             SortDirection::Desc => "DESC",
          };
-         format!("{} {}", quote_identifier(&k.name), dir)
+         match k.nulls {
+            Some(NullsOrder::First) => format!("{} {} NULLS FIRST", k.sql_expr(), dir),
+            Some(NullsOrder::Last) => format!("{} {} NULLS LAST", k.sql_expr(), dir),
+            None => format!("{} {}", k.sql_expr(), dir),
+         }
       })
       .collect();
 
@@ -407,6 +818,11 @@ fn reversed_keyset(keyset: &[KeysetColumn]) -> Vec<KeysetColumn> {
       .map(|k| KeysetColumn {
          name: k.name.clone(),
          direction: k.direction.reversed(),
+         // An unset placement stays unset: SQLite's default for the reversed
+         // direction is already the reverse of its default for the original
+         nulls: k.nulls.map(NullsOrder::reversed),
+         not_null: k.not_null,
+         collation: k.collation.clone(),
       })
       .collect()
 }
@@ -424,6 +840,10 @@ fn reversed_keyset(keyset: &[KeysetColumn]) -> Vec<KeysetColumn> {
 ///
 /// Returns the final SQL and all cursor bind values (which should be appended
 /// after the user's own bind values).
+///
+/// The SQL is cached, so fetching further pages of the same query neither
+/// re-scans the base query nor produces new SQL text, and SQLx's per-connection
+/// statement cache can reuse the statement it prepared for the previous page.
 pub(crate) fn build_paginated_query(
    base_query: &str,
    keyset: &[KeysetColumn],
@@ -432,11 +852,110 @@ pub(crate) fn build_paginated_query(
    backward: bool,
    user_param_count: usize,
 ) -> Result<(String, Vec<JsonValue>), Error> {
+   let page_query = cached_page_query(
+      base_query,
+      keyset,
+      cursor,
+      page_size,
+      backward,
+      user_param_count,
+   )?;
+   let cursor_bind_values = match cursor {
+      Some(cursor_vals) => page_query
+         .cursor_binds
+         .iter()
+         .map(|&i| cursor_vals[i].clone())
+         .collect(),
+      None => Vec::new(),
+   };
+
+   Ok((page_query.sql.clone(), cursor_bind_values))
+}
+
+/// Most paginated queries kept in [`PAGE_QUERY_CACHE`]; it is emptied when
+/// full
+const PAGE_QUERY_CACHE_CAPACITY: usize = 256;
+
+/// Generated paginated queries by everything their SQL depends on
+static PAGE_QUERY_CACHE: LazyLock<Mutex<HashMap<PageQueryKey, Arc<PageQuery>>>> =
+   LazyLock::new(Default::default);
+
+/// Everything the SQL of a paginated query depends on
+#[derive(PartialEq, Eq, Hash)]
+struct PageQueryKey {
+   base_query: String,
+   keyset: Vec<KeysetColumn>,
+   /// Which cursor values are NULL, or `None` without a cursor
+   cursor_nulls: Option<Vec<bool>>,
+   page_size: usize,
+   backward: bool,
+   user_param_count: usize,
+}
+
+/// The SQL of a paginated query
+struct PageQuery {
+   sql: String,
+   /// Index of the cursor value bound to each cursor placeholder, in order
+   cursor_binds: Vec<usize>,
+}
+
+/// Look up the paginated query for these arguments, generating and caching
+/// it on a miss. Invalid queries are not cached.
+fn cached_page_query(
+   base_query: &str,
+   keyset: &[KeysetColumn],
+   cursor: Option<&[JsonValue]>,
+   page_size: usize,
+   backward: bool,
+   user_param_count: usize,
+) -> Result<Arc<PageQuery>, Error> {
+   let key = PageQueryKey {
+      base_query: base_query.to_string(),
+      keyset: keyset.to_vec(),
+      cursor_nulls: cursor.map(|vals| vals.iter().map(JsonValue::is_null).collect()),
+      page_size,
+      backward,
+      user_param_count,
+   };
+
+   let mut cache = PAGE_QUERY_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+   if let Some(page_query) = cache.get(&key) {
+      return Ok(Arc::clone(page_query));
+   }
+
+   let page_query = Arc::new(generate_page_query(
+      base_query,
+      keyset,
+      cursor,
+      page_size,
+      backward,
+      user_param_count,
+   )?);
+   if cache.len() >= PAGE_QUERY_CACHE_CAPACITY {
+      cache.clear();
+   }
+   cache.insert(key, Arc::clone(&page_query));
+
+   Ok(page_query)
+}
+
+/// Generate the SQL of a paginated query; see [`build_paginated_query`]
+fn generate_page_query(
+   base_query: &str,
+   keyset: &[KeysetColumn],
+   cursor: Option<&[JsonValue]>,
+   page_size: usize,
+   backward: bool,
+   user_param_count: usize,
+) -> Result<PageQuery, Error> {
    validate_base_query(base_query)?;
 
-   // Validate all column names before interpolating into SQL
+   // Validate all column and collation names before interpolating into SQL
    for col in keyset {
       validate_column_name(&col.name)?;
+      if let Some(collation) = &col.collation {
+         validate_collation_name(collation)?;
+      }
    }
 
    let effective;
@@ -448,14 +967,27 @@ pub(crate) fn build_paginated_query(
    };
 
    let mut sql = base_query.trim_end().trim_end_matches(';').to_string();
-   let mut cursor_bind_values = Vec::new();
+   let mut cursor_binds = Vec::new();
+
+   // Only the main SELECT can receive the cursor condition; any leading CTE
+   // definitions are left untouched
+   let main_select = main_select_offset(&sql).unwrap_or(0);
+
+   // Grouped, DISTINCT and compound queries are wrapped so the cursor
+   // condition and ORDER BY apply to their result rows
+   let wrapped = has_top_level_grouping(&sql[main_select..])
+      || has_top_level_compound(&sql[main_select..])
+      || is_distinct_select(&sql[main_select..]);
+   if wrapped {
+      sql = format!("SELECT * FROM ({})", sql);
+   }
 
    if let Some(cursor_vals) = cursor {
-      let (condition, values) =
-         build_cursor_condition(effective_keyset, cursor_vals, user_param_count);
-      cursor_bind_values = values;
+      let (condition, binds) =
+         cursor_condition_binds(effective_keyset, cursor_vals, user_param_count);
+      cursor_binds = binds;
 
-      if has_top_level_where(&sql) {
+      if !wrapped && has_top_level_where(&sql[main_select..]) {
          sql = format!("{} AND ({})", sql, condition);
       } else {
          sql = format!("{} WHERE ({})", sql, condition);
@@ -466,7 +998,130 @@ pub(crate) fn build_paginated_query(
    let limit = page_size.checked_add(1).ok_or(Error::InvalidPageSize)?;
    sql = format!("{} {} LIMIT {}", sql, order_by, limit);
 
-   Ok((sql, cursor_bind_values))
+   Ok(PageQuery { sql, cursor_binds })
+}
+
+/// Select the keyset columns of the base query's result rows, wrapping the
+/// base query as a subquery.
+fn select_keyset_from(base_query: &str, keyset: &[KeysetColumn]) -> String {
+   let cols: Vec<String> = keyset.iter().map(|k| quote_identifier(&k.name)).collect();
+   let base = base_query.trim_end().trim_end_matches(';');
+   format!("SELECT {} FROM ({})", cols.join(", "), base)
+}
+
+/// Build a query fetching the keyset values of the row whose `key` columns
+/// equal the given values.
+///
+/// Key values are compared with `IS`, so a NULL key value matches NULL.
+/// Placeholders start at `param_offset + 1`. Returns the SQL and the bind
+/// values to append after the caller's own.
+pub(crate) fn build_row_lookup_query(
+   base_query: &str,
+   keyset: &[KeysetColumn],
+   key: &[(String, JsonValue)],
+   param_offset: usize,
+) -> Result<(String, Vec<JsonValue>), Error> {
+   validate_base_query(base_query)?;
+   for col in keyset {
+      validate_column_name(&col.name)?;
+   }
+   for (name, _) in key {
+      validate_column_name(name)?;
+   }
+
+   let conditions: Vec<String> = key
+      .iter()
+      .enumerate()
+      .map(|(i, (name, _))| format!("{} IS ${}", quote_identifier(name), param_offset + 1 + i))
+      .collect();
+   let values = key.iter().map(|(_, value)| value.clone()).collect();
+
+   let sql = format!(
+      "{} WHERE {} LIMIT 2",
+      select_keyset_from(base_query, keyset),
+      conditions.join(" AND ")
+   );
+   Ok((sql, values))
+}
+
+/// Build the queries that locate the rows preceding `target` (given as
+/// keyset values) in the keyset ordering.
+///
+/// Returns `(count_sql, nth_sql, bind_values)`: `count_sql` counts the
+/// preceding rows, and `nth_sql` selects the keyset values of the preceding
+/// row `skip` positions before the one directly ahead of `target`. Both use
+/// the same bind values, appended after the caller's own.
+pub(crate) fn build_preceding_queries(
+   base_query: &str,
+   keyset: &[KeysetColumn],
+   target: &[JsonValue],
+   skip: usize,
+   param_offset: usize,
+) -> Result<(String, String, Vec<JsonValue>), Error> {
+   validate_base_query(base_query)?;
+   for col in keyset {
+      validate_column_name(&col.name)?;
+      if let Some(collation) = &col.collation {
+         validate_collation_name(collation)?;
+      }
+   }
+
+   // "Preceding" is "following" in the reversed ordering
+   let reversed = reversed_keyset(keyset);
+   let (condition, values) = build_cursor_condition(&reversed, target, param_offset);
+   let base = base_query.trim_end().trim_end_matches(';');
+
+   let count_sql = format!("SELECT COUNT(*) FROM ({}) WHERE ({})", base, condition);
+   let nth_sql = format!(
+      "{} WHERE ({}) {} LIMIT 1 OFFSET {}",
+      select_keyset_from(base_query, keyset),
+      condition,
+      build_order_by(&reversed),
+      skip
+   );
+   Ok((count_sql, nth_sql, values))
+}
+
+/// Build a query counting every row the base query returns.
+///
+/// The base query is wrapped as a subquery so DISTINCT, GROUP BY and compound
+/// selects are counted by their result rows. The count uses exactly the
+/// caller's bind values; no cursor condition is applied.
+pub(crate) fn build_count_query(base_query: &str) -> Result<String, Error> {
+   validate_base_query(base_query)?;
+
+   let base = base_query.trim_end().trim_end_matches(';');
+   Ok(format!("SELECT COUNT(*) FROM ({})", base))
+}
+
+/// Hidden result column carrying the window-function total of a page query
+pub(crate) const WINDOW_TOTAL_COLUMN: &str = "__window_total";
+
+/// Wrap the base query so every result row also carries the number of rows
+/// it returns, as [`WINDOW_TOTAL_COLUMN`].
+///
+/// The window is computed in a subquery, so a cursor condition added by
+/// [`build_paginated_query`] filters rows after they have been counted.
+pub(crate) fn build_window_total_query(base_query: &str) -> Result<String, Error> {
+   validate_base_query(base_query)?;
+
+   let base = base_query.trim_end().trim_end_matches(';');
+   Ok(format!(
+      "SELECT * FROM (SELECT *, COUNT(*) OVER () AS {} FROM ({}))",
+      quote_identifier(WINDOW_TOTAL_COLUMN),
+      base
+   ))
+}
+
+/// Build a query returning whether the base query yields at least one row.
+///
+/// Validated like [`build_count_query`]; SQLite stops evaluating the base
+/// query at its first row.
+pub(crate) fn build_exists_query(base_query: &str) -> Result<String, Error> {
+   validate_base_query(base_query)?;
+
+   let base = base_query.trim_end().trim_end_matches(';');
+   Ok(format!("SELECT EXISTS ({})", base))
 }
 
 #[cfg(test)]
@@ -637,45 +1292,338 @@ mod tests {
       assert!(validate_column_name(".column").is_err()); // leading dot
    }
 
-   // ─── build_cursor_condition ───
+   // ─── CTE base queries ───
 
    #[test]
-   fn cursor_condition_uniform_asc() {
-      let keyset = vec![KeysetColumn::asc("a"), KeysetColumn::asc("b")];
-      let cursor = vec![json!(1), json!(2)];
-
-      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
-
-      assert_eq!(sql, r#"("a", "b") > ($1, $2)"#);
-      assert_eq!(values, vec![json!(1), json!(2)]);
+   fn main_select_offset_plain_query() {
+      assert_eq!(main_select_offset("SELECT * FROM posts"), Some(0));
+      assert_eq!(main_select_offset("  select * FROM posts"), Some(2));
    }
 
    #[test]
-   fn cursor_condition_uniform_asc_with_offset() {
-      let keyset = vec![KeysetColumn::asc("a"), KeysetColumn::asc("b")];
-      let cursor = vec![json!(1), json!(2)];
-
-      // Simulate 2 user parameters ($1, $2) preceding the cursor
-      let (sql, values) = build_cursor_condition(&keyset, &cursor, 2);
-
-      assert_eq!(sql, r#"("a", "b") > ($3, $4)"#);
-      assert_eq!(values, vec![json!(1), json!(2)]);
+   fn main_select_offset_skips_cte_definitions() {
+      let query = "WITH recent AS (SELECT * FROM posts WHERE score > 50), \
+                   \"select\" (n) AS (SELECT 1) SELECT * FROM recent";
+      let offset = main_select_offset(query).unwrap();
+      assert_eq!(&query[offset..], "SELECT * FROM recent");
    }
 
    #[test]
-   fn cursor_condition_uniform_desc() {
-      let keyset = vec![KeysetColumn::desc("a"), KeysetColumn::desc("b")];
-      let cursor = vec![json!(10), json!(20)];
+   fn main_select_offset_with_non_ascii_before_select() {
+      let query = "WITH t AS (SELECT 'straße' AS s) SELECT s FROM t";
+      let offset = main_select_offset(query).unwrap();
+      assert_eq!(&query[offset..], "SELECT s FROM t");
+   }
 
-      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+   #[test]
+   fn validate_accepts_cte_with_order_by_and_limit_in_body() {
+      let result = validate_base_query(
+         "WITH top AS (SELECT * FROM posts ORDER BY score DESC LIMIT 10) SELECT * FROM top",
+      );
+      assert!(result.is_ok());
+   }
 
-      assert_eq!(sql, r#"("a", "b") < ($1, $2)"#);
-      assert_eq!(values, vec![json!(10), json!(20)]);
+   #[test]
+   fn validate_rejects_cte_without_select() {
+      let result = validate_base_query(
+         "WITH old AS (SELECT id FROM posts) DELETE FROM posts WHERE id IN old",
+      );
+      assert!(matches!(result, Err(Error::InvalidPaginationQuery)));
    }
 
    #[test]
-   fn cursor_condition_mixed_directions() {
-      let keyset = vec![
+   fn paginated_query_cte_where_only_in_body() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let cursor = vec![json!(3)];
+
+      let (sql, _) = build_paginated_query(
+         "WITH recent AS (SELECT * FROM posts WHERE score > 50) SELECT * FROM recent",
+         &keyset,
+         Some(&cursor),
+         10,
+         false,
+         0,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"WITH recent AS (SELECT * FROM posts WHERE score > 50) SELECT * FROM recent WHERE (("id") > ($1)) ORDER BY "id" ASC LIMIT 11"#
+      );
+   }
+
+   #[test]
+   fn paginated_query_recursive_cte_with_main_where() {
+      let keyset = vec![KeysetColumn::asc("n")];
+      let cursor = vec![json!(5)];
+
+      let (sql, _) = build_paginated_query(
+         "WITH RECURSIVE cnt(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM cnt WHERE n < 100) \
+          SELECT n FROM cnt WHERE n % 2 = 0",
+         &keyset,
+         Some(&cursor),
+         10,
+         false,
+         0,
+      )
+      .unwrap();
+
+      assert!(sql.ends_with(
+         r#"SELECT n FROM cnt WHERE n % 2 = 0 AND (("n") > ($1)) ORDER BY "n" ASC LIMIT 11"#
+      ));
+   }
+
+   // ─── GROUP BY / HAVING base queries ───
+
+   #[test]
+   fn detects_top_level_grouping() {
+      assert!(has_top_level_grouping(
+         "SELECT a, COUNT(*) FROM t GROUP  BY a"
+      ));
+      assert!(has_top_level_grouping(
+         "SELECT COUNT(*) AS n FROM t HAVING n > 1"
+      ));
+      assert!(!has_top_level_grouping(
+         "SELECT * FROM (SELECT a FROM t GROUP BY a) WHERE a > 1"
+      ));
+      assert!(!has_top_level_grouping(
+         "SELECT group_id, \"having\" FROM t"
+      ));
+   }
+
+   #[test]
+   fn paginated_query_wraps_group_by() {
+      let keyset = vec![
+         KeysetColumn::desc("total").not_null(),
+         KeysetColumn::asc("category"),
+      ];
+      let cursor = vec![json!(10), json!("art")];
+
+      let (sql, values) = build_paginated_query(
+         "SELECT category, SUM(score) AS total FROM posts WHERE score > $1 GROUP BY category",
+         &keyset,
+         Some(&cursor),
+         5,
+         false,
+         1,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (SELECT category, SUM(score) AS total FROM posts WHERE score > $1 GROUP BY category) WHERE (("total" < $2) OR ("total" = $3 AND "category" > $4)) ORDER BY "total" DESC, "category" ASC LIMIT 6"#
+      );
+      assert_eq!(values, vec![json!(10), json!(10), json!("art")]);
+   }
+
+   #[test]
+   fn paginated_query_wraps_having_without_cursor() {
+      let keyset = vec![KeysetColumn::asc("category")];
+
+      let (sql, _) = build_paginated_query(
+         "SELECT category, COUNT(*) AS n FROM posts GROUP BY category HAVING n > 1",
+         &keyset,
+         None,
+         5,
+         false,
+         0,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (SELECT category, COUNT(*) AS n FROM posts GROUP BY category HAVING n > 1) ORDER BY "category" ASC LIMIT 6"#
+      );
+   }
+
+   #[test]
+   fn paginated_query_wraps_grouped_cte_main_select() {
+      let keyset = vec![KeysetColumn::asc("category")];
+      let cursor = vec![json!("art")];
+
+      let (sql, _) = build_paginated_query(
+         "WITH p AS (SELECT * FROM posts GROUP BY id) SELECT category FROM p GROUP BY category",
+         &keyset,
+         Some(&cursor),
+         5,
+         false,
+         0,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (WITH p AS (SELECT * FROM posts GROUP BY id) SELECT category FROM p GROUP BY category) WHERE (("category") > ($1)) ORDER BY "category" ASC LIMIT 6"#
+      );
+   }
+
+   // ─── DISTINCT / compound base queries ───
+
+   #[test]
+   fn detects_compound_and_distinct_selects() {
+      assert!(has_top_level_compound(
+         "SELECT id FROM a UNION ALL SELECT id FROM b"
+      ));
+      assert!(has_top_level_compound(
+         "SELECT id FROM a\nEXCEPT SELECT id FROM b"
+      ));
+      assert!(!has_top_level_compound(
+         "SELECT * FROM (SELECT id FROM a UNION SELECT id FROM b) WHERE id > 1"
+      ));
+      assert!(!has_top_level_compound(
+         "SELECT 'union' AS \"except\" FROM t"
+      ));
+
+      assert!(is_distinct_select("SELECT DISTINCT a FROM t"));
+      assert!(is_distinct_select("select\n  distinct a FROM t"));
+      assert!(!is_distinct_select("SELECT distinct_count FROM t"));
+      assert!(!is_distinct_select("SELECT COUNT(DISTINCT a) FROM t"));
+   }
+
+   #[test]
+   fn paginated_query_wraps_union() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let cursor = vec![json!(5)];
+
+      let (sql, values) = build_paginated_query(
+         "SELECT id, title FROM posts WHERE score > $1 UNION SELECT id, title FROM drafts",
+         &keyset,
+         Some(&cursor),
+         10,
+         false,
+         1,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (SELECT id, title FROM posts WHERE score > $1 UNION SELECT id, title FROM drafts) WHERE (("id") > ($2)) ORDER BY "id" ASC LIMIT 11"#
+      );
+      assert_eq!(values, vec![json!(5)]);
+   }
+
+   #[test]
+   fn paginated_query_wraps_distinct() {
+      let keyset = vec![KeysetColumn::desc("tag")];
+      let cursor = vec![json!("m")];
+
+      let (sql, _) = build_paginated_query(
+         "WITH t AS (SELECT lower(name) AS tag FROM tags) SELECT DISTINCT tag FROM t",
+         &keyset,
+         Some(&cursor),
+         3,
+         true,
+         0,
+      )
+      .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (WITH t AS (SELECT lower(name) AS tag FROM tags) SELECT DISTINCT tag FROM t) WHERE (("tag") > ($1)) ORDER BY "tag" ASC LIMIT 4"#
+      );
+   }
+
+   // ─── build_cursor_condition ───
+
+   #[test]
+   fn cursor_condition_uniform_asc() {
+      let keyset = vec![KeysetColumn::asc("a"), KeysetColumn::asc("b")];
+      let cursor = vec![json!(1), json!(2)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(sql, r#"("a", "b") > ($1, $2)"#);
+      assert_eq!(values, vec![json!(1), json!(2)]);
+   }
+
+   #[test]
+   fn cursor_condition_uniform_asc_with_offset() {
+      let keyset = vec![KeysetColumn::asc("a"), KeysetColumn::asc("b")];
+      let cursor = vec![json!(1), json!(2)];
+
+      // Simulate 2 user parameters ($1, $2) preceding the cursor
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 2);
+
+      assert_eq!(sql, r#"("a", "b") > ($3, $4)"#);
+      assert_eq!(values, vec![json!(1), json!(2)]);
+   }
+
+   #[test]
+   fn cursor_condition_uniform_desc() {
+      let keyset = vec![KeysetColumn::desc("a"), KeysetColumn::desc("b")];
+      let cursor = vec![json!(10), json!(20)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(
+         sql,
+         r#"("a" < $1 OR "a" IS NULL) OR ("a" = $2 AND ("b" < $3 OR "b" IS NULL))"#
+      );
+      assert_eq!(values, vec![json!(10), json!(10), json!(20)]);
+   }
+
+   #[test]
+   fn cursor_condition_uniform_desc_not_null() {
+      let keyset = vec![
+         KeysetColumn::desc("a").not_null(),
+         KeysetColumn::desc("b").not_null(),
+      ];
+      let cursor = vec![json!(10), json!(20)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(sql, r#"("a", "b") < ($1, $2)"#);
+      assert_eq!(values, vec![json!(10), json!(20)]);
+   }
+
+   #[test]
+   fn cursor_condition_null_value_nulls_first() {
+      let keyset = vec![KeysetColumn::asc("a"), KeysetColumn::asc("id")];
+      let cursor = vec![json!(null), json!(5)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(sql, r#"("a" IS NOT NULL) OR ("a" IS NULL AND "id" > $1)"#);
+      assert_eq!(values, vec![json!(5)]);
+   }
+
+   #[test]
+   fn cursor_condition_null_value_nulls_last() {
+      let keyset = vec![KeysetColumn::desc("a"), KeysetColumn::desc("id")];
+      let cursor = vec![json!(null), json!(5)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      // Nothing sorts after NULL in "a", only later ids within the NULL group
+      assert_eq!(sql, r#"("a" IS NULL AND ("id" < $1 OR "id" IS NULL))"#);
+      assert_eq!(values, vec![json!(5)]);
+   }
+
+   #[test]
+   fn cursor_condition_null_value_at_end() {
+      let keyset = vec![KeysetColumn::asc("a").nulls_last()];
+      let cursor = vec![json!(null)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(sql, "0");
+      assert!(values.is_empty());
+   }
+
+   #[test]
+   fn cursor_condition_explicit_nulls_last_asc() {
+      let keyset = vec![KeysetColumn::asc("a").nulls_last()];
+      let cursor = vec![json!(3)];
+
+      let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
+
+      assert_eq!(sql, r#"("a" > $1 OR "a" IS NULL)"#);
+      assert_eq!(values, vec![json!(3)]);
+   }
+
+   #[test]
+   fn cursor_condition_mixed_directions() {
+      let keyset = vec![
          KeysetColumn::asc("a"),
          KeysetColumn::desc("b"),
          KeysetColumn::asc("c"),
@@ -686,7 +1634,7 @@ mod tests {
 
       assert_eq!(
          sql,
-         r#"("a" > $1) OR ("a" = $2 AND "b" < $3) OR ("a" = $4 AND "b" = $5 AND "c" > $6)"#
+         r#"("a" > $1) OR ("a" = $2 AND ("b" < $3 OR "b" IS NULL)) OR ("a" = $4 AND "b" = $5 AND "c" > $6)"#
       );
       assert_eq!(
          values,
@@ -715,7 +1663,7 @@ mod tests {
 
       assert_eq!(
          sql,
-         r#"("a" > $2) OR ("a" = $3 AND "b" < $4) OR ("a" = $5 AND "b" = $6 AND "c" > $7)"#
+         r#"("a" > $2) OR ("a" = $3 AND ("b" < $4 OR "b" IS NULL)) OR ("a" = $5 AND "b" = $6 AND "c" > $7)"#
       );
       assert_eq!(
          values,
@@ -748,7 +1696,8 @@ mod tests {
 
       let (sql, values) = build_cursor_condition(&keyset, &cursor, 0);
 
-      assert_eq!(sql, r#"("id") < ($1)"#);
+      // NULLs sort last when descending, so they still follow the cursor
+      assert_eq!(sql, r#"("id" < $1 OR "id" IS NULL)"#);
       assert_eq!(values, vec![json!(42)]);
    }
 
@@ -859,7 +1808,7 @@ mod tests {
 
       assert_eq!(
          sql,
-         r#"SELECT * FROM posts WHERE (("category" > $1) OR ("category" = $2 AND "score" < $3) OR ("category" = $4 AND "score" = $5 AND "id" > $6)) ORDER BY "category" ASC, "score" DESC, "id" ASC LIMIT 26"#
+         r#"SELECT * FROM posts WHERE (("category" > $1) OR ("category" = $2 AND ("score" < $3 OR "score" IS NULL)) OR ("category" = $4 AND "score" = $5 AND "id" > $6)) ORDER BY "category" ASC, "score" DESC, "id" ASC LIMIT 26"#
       );
       assert_eq!(
          values,
@@ -874,6 +1823,25 @@ mod tests {
       );
    }
 
+   #[test]
+   fn paginated_query_explicit_nulls_order() {
+      let keyset = vec![KeysetColumn::asc("a").nulls_last(), KeysetColumn::asc("id")];
+
+      let (forward, _) =
+         build_paginated_query("SELECT * FROM posts", &keyset, None, 10, false, 0).unwrap();
+      let (backward, _) =
+         build_paginated_query("SELECT * FROM posts", &keyset, None, 10, true, 0).unwrap();
+
+      assert_eq!(
+         forward,
+         r#"SELECT * FROM posts ORDER BY "a" ASC NULLS LAST, "id" ASC LIMIT 11"#
+      );
+      assert_eq!(
+         backward,
+         r#"SELECT * FROM posts ORDER BY "a" DESC NULLS FIRST, "id" DESC LIMIT 11"#
+      );
+   }
+
    // ─── SortDirection::reversed ───
 
    #[test]
@@ -904,12 +1872,13 @@ mod tests {
       let (sql, values) =
          build_paginated_query("SELECT * FROM posts", &keyset, Some(&cursor), 20, true, 0).unwrap();
 
-      // Reversed ASC→DESC: uses < operator
+      // Reversed ASC→DESC: uses < operator, and NULLs (first in the original
+      // order) now follow the cursor
       assert_eq!(
          sql,
-         r#"SELECT * FROM posts WHERE (("a", "b") < ($1, $2)) ORDER BY "a" DESC, "b" DESC LIMIT 21"#
+         r#"SELECT * FROM posts WHERE (("a" < $1 OR "a" IS NULL) OR ("a" = $2 AND ("b" < $3 OR "b" IS NULL))) ORDER BY "a" DESC, "b" DESC LIMIT 21"#
       );
-      assert_eq!(values, vec![json!(10), json!(20)]);
+      assert_eq!(values, vec![json!(10), json!(10), json!(20)]);
    }
 
    #[test]
@@ -943,7 +1912,7 @@ mod tests {
       // Reversed: ASC→DESC (uses <), DESC→ASC (uses >), ASC→DESC (uses <)
       assert_eq!(
          sql,
-         r#"SELECT * FROM posts WHERE (("a" < $1) OR ("a" = $2 AND "b" > $3) OR ("a" = $4 AND "b" = $5 AND "c" < $6)) ORDER BY "a" DESC, "b" ASC, "c" DESC LIMIT 26"#
+         r#"SELECT * FROM posts WHERE (("a" < $1 OR "a" IS NULL) OR ("a" = $2 AND "b" > $3) OR ("a" = $4 AND "b" = $5 AND ("c" < $6 OR "c" IS NULL))) ORDER BY "a" DESC, "b" ASC, "c" DESC LIMIT 26"#
       );
       assert_eq!(
          values,
@@ -976,7 +1945,7 @@ mod tests {
 
       assert_eq!(
          sql,
-         r#"SELECT * FROM posts WHERE category = $1 AND (("id") < ($2)) ORDER BY "id" DESC LIMIT 21"#
+         r#"SELECT * FROM posts WHERE category = $1 AND (("id" < $2 OR "id" IS NULL)) ORDER BY "id" DESC LIMIT 21"#
       );
       assert_eq!(values, vec![json!(100)]);
    }
@@ -992,6 +1961,42 @@ mod tests {
       assert!(matches!(result, Err(Error::InvalidColumnName { .. })));
    }
 
+   // ─── paginated query cache ───
+
+   #[test]
+   fn page_query_cache_reuses_sql_for_cursors_with_same_nulls() {
+      let base = "SELECT * FROM posts WHERE author = $1 /* cache test */";
+      let keyset = vec![
+         KeysetColumn::desc("score").nulls_last(),
+         KeysetColumn::asc("id"),
+      ];
+
+      let first =
+         cached_page_query(base, &keyset, Some(&[json!(80), json!(2)]), 10, false, 1).unwrap();
+      let second =
+         cached_page_query(base, &keyset, Some(&[json!(60), json!(7)]), 10, false, 1).unwrap();
+      assert!(Arc::ptr_eq(&first, &second));
+
+      let null_score =
+         cached_page_query(base, &keyset, Some(&[json!(null), json!(7)]), 10, false, 1).unwrap();
+      assert!(!Arc::ptr_eq(&first, &null_score));
+
+      let (_, values) =
+         build_paginated_query(base, &keyset, Some(&[json!(60), json!(7)]), 10, false, 1).unwrap();
+      assert_eq!(values, vec![json!(60), json!(60), json!(7)]);
+   }
+
+   #[test]
+   fn page_query_cache_skips_invalid_queries() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      for _ in 0..2 {
+         assert!(matches!(
+            build_paginated_query("SELECT * FROM posts LIMIT 3", &keyset, None, 10, false, 0),
+            Err(Error::InvalidPaginationQuery)
+         ));
+      }
+   }
+
    // ─── quote_identifier ───
 
    #[test]
@@ -1012,6 +2017,80 @@ mod tests {
       );
    }
 
+   // ─── collation ───
+
+   #[test]
+   fn collation_name_validation() {
+      assert!(validate_collation_name("NOCASE").is_ok());
+      assert!(validate_collation_name("my_collation2").is_ok());
+      assert!(validate_collation_name("").is_err());
+      assert!(validate_collation_name("2x").is_err());
+      assert!(validate_collation_name("NOCASE; DROP TABLE posts").is_err());
+   }
+
+   #[test]
+   fn cursor_condition_applies_collation() {
+      let keyset = vec![
+         KeysetColumn::asc("title").with_collation("NOCASE"),
+         KeysetColumn::asc("id"),
+      ];
+      let cursor = vec![json!("b"), json!(2)];
+
+      let (sql, _) = build_cursor_condition(&keyset, &cursor, 0);
+      assert_eq!(sql, r#"("title" COLLATE NOCASE, "id") > ($1, $2)"#);
+
+      let keyset = vec![
+         KeysetColumn::asc("title").with_collation("NOCASE"),
+         KeysetColumn::desc("id").not_null(),
+      ];
+      let (sql, _) = build_cursor_condition(&keyset, &cursor, 0);
+      assert_eq!(
+         sql,
+         r#"("title" COLLATE NOCASE > $1) OR ("title" COLLATE NOCASE = $2 AND "id" < $3)"#
+      );
+   }
+
+   #[test]
+   fn paginated_query_applies_collation_to_order_by() {
+      let keyset = vec![
+         KeysetColumn::desc("title")
+            .with_collation("NOCASE")
+            .nulls_first(),
+      ];
+
+      let (sql, _) =
+         build_paginated_query("SELECT * FROM posts", &keyset, None, 10, true, 0).unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM posts ORDER BY "title" COLLATE NOCASE ASC NULLS LAST LIMIT 11"#
+      );
+   }
+
+   #[test]
+   fn paginated_query_rejects_invalid_collation() {
+      let keyset = vec![KeysetColumn::asc("title").with_collation("NOCASE --")];
+
+      let result = build_paginated_query("SELECT * FROM posts", &keyset, None, 10, false, 0);
+
+      assert!(matches!(result, Err(Error::InvalidCollationName { .. })));
+   }
+
+   // ─── KeysetColumn serde ───
+
+   #[test]
+   fn keyset_column_deserializes_optional_null_handling() {
+      let plain: KeysetColumn =
+         serde_json::from_str(r#"{"name": "id", "direction": "asc"}"#).unwrap();
+      let full: KeysetColumn = serde_json::from_str(
+         r#"{"name": "rank", "direction": "desc", "nulls": "first", "notNull": false}"#,
+      )
+      .unwrap();
+
+      assert_eq!(plain, KeysetColumn::asc("id"));
+      assert_eq!(full, KeysetColumn::desc("rank").nulls_first());
+   }
+
    // ─── SortDirection serde ───
 
    #[test]
@@ -1033,4 +2112,163 @@ mod tests {
       assert_eq!(asc, SortDirection::Asc);
       assert_eq!(desc, SortDirection::Desc);
    }
+
+   // ─── build_count_query ───
+
+   #[test]
+   fn count_query_wraps_base_query() {
+      let sql = build_count_query("SELECT id FROM posts WHERE category = $1;").unwrap();
+      assert_eq!(
+         sql,
+         "SELECT COUNT(*) FROM (SELECT id FROM posts WHERE category = $1)"
+      );
+   }
+
+   #[test]
+   fn count_query_rejects_order_by() {
+      assert!(build_count_query("SELECT id FROM posts ORDER BY id").is_err());
+   }
+
+   #[test]
+   fn exists_query_wraps_base_query() {
+      let sql = build_exists_query("SELECT 1 FROM posts WHERE author = $1;\n").unwrap();
+      assert_eq!(sql, "SELECT EXISTS (SELECT 1 FROM posts WHERE author = $1)");
+   }
+
+   #[test]
+   fn exists_query_rejects_limit() {
+      assert!(build_exists_query("SELECT 1 FROM posts LIMIT 1").is_err());
+      assert!(build_exists_query("DELETE FROM posts").is_err());
+   }
+
+   #[test]
+   fn window_total_counts_before_cursor_condition() {
+      let base = build_window_total_query("SELECT id FROM posts WHERE category = $1").unwrap();
+      let keyset = vec![KeysetColumn::asc("id")];
+      let (sql, _) =
+         build_paginated_query(&base, &keyset, Some(&[json!(5)]), 10, false, 1).unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT * FROM (SELECT *, COUNT(*) OVER () AS "__window_total" FROM (SELECT id FROM posts WHERE category = $1)) WHERE (("id") > ($2)) ORDER BY "id" ASC LIMIT 11"#
+      );
+   }
+
+   #[test]
+   fn window_total_query_rejects_limit() {
+      assert!(build_window_total_query("SELECT id FROM posts LIMIT 5").is_err());
+   }
+
+   // ─── containing() lookups ───
+
+   #[test]
+   fn row_lookup_query_matches_key_with_is() {
+      let keyset = vec![KeysetColumn::desc("score"), KeysetColumn::asc("id")];
+      let key = vec![("id".to_string(), json!(7))];
+
+      let (sql, values) =
+         build_row_lookup_query("SELECT * FROM posts WHERE category = $1", &keyset, &key, 1)
+            .unwrap();
+
+      assert_eq!(
+         sql,
+         r#"SELECT "score", "id" FROM (SELECT * FROM posts WHERE category = $1) WHERE "id" IS $2 LIMIT 2"#
+      );
+      assert_eq!(values, vec![json!(7)]);
+   }
+
+   #[test]
+   fn row_lookup_query_rejects_invalid_key_column() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let key = vec![("id = 1 OR 1".to_string(), json!(7))];
+
+      let result = build_row_lookup_query("SELECT * FROM posts", &keyset, &key, 0);
+      assert!(matches!(result, Err(Error::InvalidColumnName { .. })));
+   }
+
+   #[test]
+   fn preceding_queries_use_reversed_keyset() {
+      let keyset = vec![KeysetColumn::asc("id")];
+
+      let (count_sql, nth_sql, values) =
+         build_preceding_queries("SELECT * FROM posts", &keyset, &[json!(42)], 3, 0).unwrap();
+
+      assert_eq!(
+         count_sql,
+         r#"SELECT COUNT(*) FROM (SELECT * FROM posts) WHERE (("id" < $1 OR "id" IS NULL))"#
+      );
+      assert_eq!(
+         nth_sql,
+         r#"SELECT "id" FROM (SELECT * FROM posts) WHERE (("id" < $1 OR "id" IS NULL)) ORDER BY "id" DESC LIMIT 1 OFFSET 3"#
+      );
+      assert_eq!(values, vec![json!(42)]);
+   }
+
+   // ─── cursor tokens ───
+
+   #[test]
+   fn cursor_token_round_trip() {
+      let keyset = vec![KeysetColumn::desc("score"), KeysetColumn::asc("id")];
+      let values = vec![json!(9.5), json!("a/b+c")];
+      let token = encode_cursor_token(&keyset, &values, None);
+
+      assert!(!token.contains('.'));
+      assert_eq!(decode_cursor_token(&token, &keyset, None).unwrap(), values);
+   }
+
+   #[test]
+   fn cursor_token_rejects_different_keyset() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let token = encode_cursor_token(&keyset, &[json!(1)], None);
+
+      let err = decode_cursor_token(&token, &[KeysetColumn::desc("id")], None).unwrap_err();
+      assert_eq!(err.error_code(), "INVALID_CURSOR_TOKEN");
+   }
+
+   #[test]
+   fn cursor_token_rejects_garbage() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      assert!(decode_cursor_token("not base64!", &keyset, None).is_err());
+      assert!(decode_cursor_token("bm90IGpzb24", &keyset, None).is_err());
+   }
+
+   #[test]
+   fn signed_cursor_token_round_trip() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let token = encode_cursor_token(&keyset, &[json!(42)], Some(b"secret"));
+
+      assert_eq!(
+         decode_cursor_token(&token, &keyset, Some(b"secret")).unwrap(),
+         vec![json!(42)]
+      );
+   }
+
+   #[test]
+   fn signed_cursor_token_rejects_tampering() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let token = encode_cursor_token(&keyset, &[json!(42)], Some(b"secret"));
+      let (_, signature) = token.split_once('.').unwrap();
+      let forged_payload = encode_cursor_token(&keyset, &[json!(0)], None);
+      let forged = format!("{forged_payload}.{signature}");
+
+      assert!(decode_cursor_token(&forged, &keyset, Some(b"secret")).is_err());
+      assert!(decode_cursor_token(&token, &keyset, Some(b"other")).is_err());
+   }
+
+   #[test]
+   fn signed_cursor_token_required_when_key_set() {
+      let keyset = vec![KeysetColumn::asc("id")];
+      let token = encode_cursor_token(&keyset, &[json!(42)], None);
+
+      let err = decode_cursor_token(&token, &keyset, Some(b"secret")).unwrap_err();
+      assert!(err.to_string().contains("not signed"));
+   }
+
+   #[test]
+   fn cursor_deserializes_from_array_or_string() {
+      let values: Cursor = serde_json::from_str("[1, \"a\"]").unwrap();
+      let token: Cursor = serde_json::from_str("\"abc\"").unwrap();
+      assert_eq!(values, Cursor::Values(vec![json!(1), json!("a")]));
+      assert_eq!(token, Cursor::Token("abc".into()));
+   }
 }
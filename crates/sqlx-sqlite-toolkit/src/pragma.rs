@@ -0,0 +1,164 @@
+//! Temporary PRAGMA overrides for a single read query
+
+use std::ops::DerefMut;
+
+use sqlx::Row;
+use sqlx::sqlite::{SqliteConnection, SqliteRow};
+use sqlx_sqlite_conn_mgr::{AttachedReadConnection, ReadGuard};
+
+use crate::Error;
+
+/// A read connection checked out for one query
+pub(crate) trait ReaderConnection: DerefMut<Target = SqliteConnection> {
+   /// Close the connection instead of returning it to the pool
+   fn close_on_drop(&mut self);
+}
+
+impl ReaderConnection for ReadGuard {
+   fn close_on_drop(&mut self) {
+      ReadGuard::close_on_drop(self);
+   }
+}
+
+impl ReaderConnection for AttachedReadConnection {
+   fn close_on_drop(&mut self) {
+      AttachedReadConnection::close_on_drop(self);
+   }
+}
+
+/// Closes the connection on drop unless every override was restored,
+/// including when the query future is dropped part-way through
+struct RestoreScope<'c, C: ReaderConnection> {
+   conn: &'c mut C,
+   restored: bool,
+}
+
+impl<C: ReaderConnection> Drop for RestoreScope<'_, C> {
+   fn drop(&mut self) {
+      if !self.restored {
+         self.conn.close_on_drop();
+      }
+   }
+}
+
+/// A PRAGMA's value before it was overridden
+enum PreviousValue {
+   Integer(i64),
+   Text(String),
+}
+
+/// Run `run` on `conn` with each `(name, value)` PRAGMA in `pragmas` set, then
+/// set them back to their previous values before the connection is released.
+///
+/// Only pragmas whose current value can be read with `PRAGMA <name>` can be
+/// overridden. If restoring fails, the connection is closed rather than
+/// returned to the pool with the overrides still in effect.
+pub(crate) async fn with_pragmas<C, T>(
+   conn: &mut C,
+   pragmas: &[(String, String)],
+   run: impl AsyncFnOnce(&mut SqliteConnection) -> Result<T, Error>,
+) -> Result<T, Error>
+where
+   C: ReaderConnection,
+{
+   if pragmas.is_empty() {
+      return run(&mut **conn).await;
+   }
+   for (name, value) in pragmas {
+      validate_pragma(name, value)?;
+   }
+
+   let mut scope = RestoreScope {
+      conn,
+      restored: false,
+   };
+
+   let mut previous = Vec::with_capacity(pragmas.len());
+   for (name, value) in pragmas {
+      let row: Option<SqliteRow> = sqlx::query(&format!("PRAGMA {}", name))
+         .fetch_optional(&mut **scope.conn)
+         .await?;
+      let value_before = match row {
+         Some(row) => match row.try_get::<i64, _>(0) {
+            Ok(n) => PreviousValue::Integer(n),
+            Err(_) => PreviousValue::Text(row.try_get(0)?),
+         },
+         None => {
+            return Err(Error::InvalidPragma(format!(
+               "'{}' has no readable value to restore",
+               name
+            )));
+         }
+      };
+      previous.push((name, value_before));
+
+      sqlx::query(&format!("PRAGMA {} = {}", name, value))
+         .execute(&mut **scope.conn)
+         .await?;
+   }
+
+   let result = run(&mut **scope.conn).await;
+
+   let mut restored = true;
+   for (name, value) in previous.iter().rev() {
+      let sql = match value {
+         PreviousValue::Integer(n) => format!("PRAGMA {} = {}", name, n),
+         PreviousValue::Text(text) => {
+            format!("PRAGMA {} = '{}'", name, text.replace('\'', "''"))
+         }
+      };
+      if sqlx::query(&sql).execute(&mut **scope.conn).await.is_err() {
+         restored = false;
+      }
+   }
+   scope.restored = restored;
+
+   result
+}
+
+/// Check that a PRAGMA name and value are safe to interpolate into SQL
+///
+/// Names must match `[a-zA-Z_][a-zA-Z0-9_]*`; values must be an optionally
+/// negative integer or a keyword such as `ON`, `OFF` or `MEMORY`.
+fn validate_pragma(name: &str, value: &str) -> Result<(), Error> {
+   let mut name_chars = name.chars();
+   let valid_name = name_chars
+      .next()
+      .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+      && name_chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+   let digits = value.strip_prefix('-').unwrap_or(value);
+   let valid_value = !digits.is_empty()
+      && digits
+         .chars()
+         .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+      && (digits.len() == value.len() || digits.chars().all(|ch| ch.is_ascii_digit()));
+
+   if valid_name && valid_value {
+      Ok(())
+   } else {
+      Err(Error::InvalidPragma(format!("'{} = {}'", name, value)))
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn accepts_integers_and_keywords() {
+      assert!(validate_pragma("cache_size", "-20000").is_ok());
+      assert!(validate_pragma("query_only", "ON").is_ok());
+      assert!(validate_pragma("temp_store", "MEMORY").is_ok());
+   }
+
+   #[test]
+   fn rejects_unsafe_names_and_values() {
+      assert!(validate_pragma("", "1").is_err());
+      assert!(validate_pragma("main.cache_size", "1").is_err());
+      assert!(validate_pragma("cache_size", "").is_err());
+      assert!(validate_pragma("cache_size", "-OFF").is_err());
+      assert!(validate_pragma("query_only", "1; DROP TABLE posts").is_err());
+      assert!(validate_pragma("query_only", "'on'").is_err());
+   }
+}
@@ -0,0 +1,266 @@
+//! Retrying statements that fail because the database is busy
+
+use std::future::Future;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Error;
+
+/// SQLite primary result codes that mean "try again later"
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+
+/// Backoff policy for retrying statements that fail with `SQLITE_BUSY` or
+/// `SQLITE_LOCKED`
+///
+/// Attempt `n` (counting from 1) that fails is followed by a delay of
+/// `initial_backoff * multiplier^(n - 1)`, capped at `max_backoff`. With
+/// `jitter` enabled, each delay is drawn at random from the upper half of that
+/// range so that competing processes don't retry in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+   /// Total number of attempts, including the first. Values below 1 are
+   /// treated as 1.
+   pub max_attempts: u32,
+   /// Delay after the first failed attempt
+   pub initial_backoff: Duration,
+   /// Upper bound for any single delay
+   pub max_backoff: Duration,
+   /// Factor the delay grows by after each failed attempt
+   pub multiplier: f64,
+   /// Randomize each delay to spread out concurrent retries
+   pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+   fn default() -> Self {
+      Self {
+         max_attempts: 5,
+         initial_backoff: Duration::from_millis(20),
+         max_backoff: Duration::from_secs(1),
+         multiplier: 2.0,
+         jitter: true,
+      }
+   }
+}
+
+impl RetryPolicy {
+   /// Delay to wait after failed attempt number `attempt` (counting from 1)
+   pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+      let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+      let delay = self
+         .initial_backoff
+         .mul_f64(self.multiplier.max(1.0).powi(exponent).min(u32::MAX as f64))
+         .min(self.max_backoff);
+
+      if self.jitter {
+         let half = delay / 2;
+         half + half.mul_f64(random_fraction())
+      } else {
+         delay
+      }
+   }
+}
+
+/// A value produced by a retried operation, with how many attempts it took
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retried<T> {
+   /// The operation's result
+   pub value: T,
+   /// Number of attempts made, including the successful one
+   pub attempts: u32,
+}
+
+/// Run `op` until it succeeds, fails with an error that isn't busy/locked, or
+/// `policy.max_attempts` is used up
+pub(crate) async fn with_retry<T, F, Fut>(
+   policy: RetryPolicy,
+   mut op: F,
+) -> Result<Retried<T>, Error>
+where
+   F: FnMut() -> Fut,
+   Fut: Future<Output = Result<T, Error>>,
+{
+   let max_attempts = policy.max_attempts.max(1);
+   let mut attempts = 0;
+
+   loop {
+      attempts += 1;
+      match op().await {
+         Ok(value) => return Ok(Retried { value, attempts }),
+         Err(err) if is_busy(&err) => {
+            if attempts >= max_attempts {
+               return Err(Error::RetriesExhausted {
+                  attempts,
+                  source: Box::new(err),
+               });
+            }
+            tokio::time::sleep(policy.backoff(attempts)).await;
+         }
+         Err(err) => return Err(err),
+      }
+   }
+}
+
+/// Whether `err` is SQLite reporting `SQLITE_BUSY` or `SQLITE_LOCKED`,
+/// including their extended result codes
+pub(crate) fn is_busy(err: &Error) -> bool {
+   let sqlx_err = match err {
+      Error::Sqlx(e) => e,
+      Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::Sqlx(e)) => e,
+      Error::ConnectionManager(sqlx_sqlite_conn_mgr::Error::AttachFailed { source, .. }) => source,
+      _ => return false,
+   };
+
+   sqlx_err
+      .as_database_error()
+      .and_then(|db_err| db_err.code())
+      .and_then(|code| code.parse::<i32>().ok())
+      .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+/// A random number in `[0, 1)` for jittering delays
+fn random_fraction() -> f64 {
+   (uuid::Uuid::new_v4().as_u128() as u64 >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+   use sqlx::{ConnectOptions, Connection};
+   use std::sync::Arc;
+   use std::sync::atomic::{AtomicU32, Ordering};
+   use tokio::sync::Mutex;
+
+   fn fixed_policy(max_attempts: u32) -> RetryPolicy {
+      RetryPolicy {
+         max_attempts,
+         initial_backoff: Duration::from_millis(1),
+         max_backoff: Duration::from_millis(4),
+         multiplier: 2.0,
+         jitter: false,
+      }
+   }
+
+   #[test]
+   fn backoff_grows_and_caps() {
+      let policy = fixed_policy(10);
+
+      assert_eq!(policy.backoff(1), Duration::from_millis(1));
+      assert_eq!(policy.backoff(2), Duration::from_millis(2));
+      assert_eq!(policy.backoff(3), Duration::from_millis(4));
+      assert_eq!(policy.backoff(4), Duration::from_millis(4));
+      assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(4));
+   }
+
+   #[test]
+   fn jittered_backoff_stays_in_upper_half() {
+      let policy = RetryPolicy {
+         jitter: true,
+         ..fixed_policy(10)
+      };
+
+      for _ in 0..100 {
+         let delay = policy.backoff(3);
+         assert!(delay >= Duration::from_millis(2) && delay <= Duration::from_millis(4));
+      }
+   }
+
+   /// Two raw connections to the same file that fail immediately instead of
+   /// waiting on each other's locks
+   async fn contending_connections() -> (tempfile::TempDir, SqliteConnection, SqliteConnection) {
+      let dir = tempfile::TempDir::new().unwrap();
+      let options = SqliteConnectOptions::new()
+         .filename(dir.path().join("busy.db"))
+         .create_if_missing(true)
+         .busy_timeout(Duration::ZERO);
+
+      let mut holder = options.connect().await.unwrap();
+      sqlx::query("CREATE TABLE t (x INTEGER)")
+         .execute(&mut holder)
+         .await
+         .unwrap();
+      let contender = options.connect().await.unwrap();
+
+      (dir, holder, contender)
+   }
+
+   #[tokio::test]
+   async fn retries_busy_errors_until_lock_is_released() {
+      let (_dir, mut holder, contender) = contending_connections().await;
+      sqlx::query("BEGIN EXCLUSIVE")
+         .execute(&mut holder)
+         .await
+         .unwrap();
+
+      let holder = Arc::new(Mutex::new(Some(holder)));
+      let contender = Arc::new(Mutex::new(contender));
+      let calls = Arc::new(AtomicU32::new(0));
+
+      let result = with_retry(fixed_policy(5), || {
+         let holder = Arc::clone(&holder);
+         let contender = Arc::clone(&contender);
+         let calls = Arc::clone(&calls);
+         async move {
+            if calls.fetch_add(1, Ordering::SeqCst) == 2 {
+               let mut holder = holder.lock().await.take().unwrap();
+               sqlx::query("COMMIT").execute(&mut holder).await?;
+               holder.close().await?;
+            }
+            let mut contender = contender.lock().await;
+            let result = sqlx::query("INSERT INTO t VALUES (1)")
+               .execute(&mut *contender)
+               .await?;
+            Ok(result.rows_affected())
+         }
+      })
+      .await
+      .unwrap();
+
+      assert_eq!(result.value, 1);
+      assert_eq!(result.attempts, 3);
+   }
+
+   #[tokio::test]
+   async fn gives_up_after_max_attempts() {
+      let (_dir, mut holder, contender) = contending_connections().await;
+      sqlx::query("BEGIN EXCLUSIVE")
+         .execute(&mut holder)
+         .await
+         .unwrap();
+
+      let contender = Arc::new(Mutex::new(contender));
+      let err = with_retry(fixed_policy(3), || {
+         let contender = Arc::clone(&contender);
+         async move {
+            let mut contender = contender.lock().await;
+            sqlx::query("SELECT * FROM t")
+               .fetch_all(&mut *contender)
+               .await
+               .map(|rows| rows.len())
+               .map_err(Error::from)
+         }
+      })
+      .await
+      .unwrap_err();
+
+      assert!(matches!(&err, Error::RetriesExhausted { attempts: 3, source } if is_busy(source)));
+   }
+
+   #[tokio::test]
+   async fn other_errors_are_not_retried() {
+      let calls = AtomicU32::new(0);
+      let err = with_retry(fixed_policy(5), || {
+         calls.fetch_add(1, Ordering::SeqCst);
+         async { Err::<(), _>(Error::Other("boom".into())) }
+      })
+      .await
+      .unwrap_err();
+
+      assert!(matches!(err, Error::Other(_)));
+      assert_eq!(calls.load(Ordering::SeqCst), 1);
+   }
+}
@@ -0,0 +1,145 @@
+//! Splitting of multi-statement SQL scripts
+
+use crate::pagination::{skip_block_comment, skip_line_comment, skip_quoted};
+
+/// Split `sql` into individual statements on top-level semicolons.
+///
+/// Semicolons inside string literals, quoted identifiers (`"…"`, `` `…` ``,
+/// `[…]`), comments and the `BEGIN … END` body of `CREATE TRIGGER` are not
+/// statement boundaries. Statements are returned trimmed, without their
+/// terminating semicolon; empty statements and ones consisting only of
+/// comments are dropped.
+pub(crate) fn split_statements(sql: &str) -> Vec<&str> {
+   let upper = sql.to_ascii_uppercase();
+   let bytes = upper.as_bytes();
+   let len = bytes.len();
+
+   let mut statements = Vec::new();
+   let mut start = 0;
+   let mut has_content = false;
+   let mut words = 0;
+   let mut starts_with_create = false;
+   let mut is_trigger = false;
+   let mut begin_depth = 0usize;
+   let mut case_depth = 0usize;
+   let mut i = 0;
+
+   while i < len {
+      match bytes[i] {
+         b'\'' | b'"' | b'`' => {
+            has_content = true;
+            i = skip_quoted(bytes, len, i, bytes[i]);
+         }
+         b'[' => {
+            has_content = true;
+            while i + 1 < len && bytes[i + 1] != b']' {
+               i += 1;
+            }
+            i += 1;
+         }
+         b'-' if i + 1 < len && bytes[i + 1] == b'-' => i = skip_line_comment(bytes, len, i),
+         b'/' if i + 1 < len && bytes[i + 1] == b'*' => i = skip_block_comment(bytes, len, i),
+         b';' if begin_depth == 0 => {
+            if has_content {
+               statements.push(sql[start..i].trim());
+            }
+            start = i + 1;
+            has_content = false;
+            words = 0;
+            starts_with_create = false;
+            is_trigger = false;
+            case_depth = 0;
+         }
+         b if b.is_ascii_alphabetic() || b == b'_' => {
+            has_content = true;
+            let word_start = i;
+            while i + 1 < len && (bytes[i + 1].is_ascii_alphanumeric() || bytes[i + 1] == b'_') {
+               i += 1;
+            }
+            let word = &bytes[word_start..=i];
+            words += 1;
+
+            if words == 1 {
+               starts_with_create = word == b"CREATE";
+            } else if starts_with_create && words <= 3 && word == b"TRIGGER" {
+               is_trigger = true;
+            } else if is_trigger {
+               match word {
+                  b"BEGIN" => begin_depth += 1,
+                  b"CASE" => case_depth += 1,
+                  b"END" if case_depth > 0 => case_depth -= 1,
+                  b"END" => begin_depth = begin_depth.saturating_sub(1),
+                  _ => {}
+               }
+            }
+         }
+         b if b.is_ascii_whitespace() => {}
+         _ => has_content = true,
+      }
+      i += 1;
+   }
+
+   if has_content {
+      statements.push(sql[start..].trim());
+   }
+
+   statements
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn splits_on_top_level_semicolons() {
+      assert_eq!(
+         split_statements("CREATE TABLE a (x);\n INSERT INTO a VALUES (1) ;SELECT 1"),
+         vec!["CREATE TABLE a (x)", "INSERT INTO a VALUES (1)", "SELECT 1"]
+      );
+   }
+
+   #[test]
+   fn ignores_semicolons_in_literals_and_comments() {
+      assert_eq!(
+         split_statements(
+            "INSERT INTO a VALUES ('x;y', \"c;d\", [e;f], `g;h`); -- trailing; comment\n\
+             /* block; comment */ SELECT ';'"
+         ),
+         vec![
+            "INSERT INTO a VALUES ('x;y', \"c;d\", [e;f], `g;h`)",
+            "-- trailing; comment\n/* block; comment */ SELECT ';'"
+         ]
+      );
+   }
+
+   #[test]
+   fn drops_empty_and_comment_only_statements() {
+      assert_eq!(
+         split_statements(";; SELECT 1;\n  -- nothing here;\n;"),
+         vec!["SELECT 1"]
+      );
+      assert!(split_statements("  /* only a comment */ ").is_empty());
+   }
+
+   #[test]
+   fn keeps_trigger_bodies_together() {
+      let sql = "CREATE TEMP TRIGGER IF NOT EXISTS t AFTER INSERT ON a BEGIN \
+                    UPDATE a SET x = CASE WHEN new.x > 0 THEN 1 ELSE 0 END WHERE rowid = new.rowid; \
+                    INSERT INTO log VALUES ('begin;end'); \
+                 END; SELECT 1";
+      let statements = split_statements(sql);
+
+      assert_eq!(statements.len(), 2);
+      assert!(statements[0].starts_with("CREATE TEMP TRIGGER"));
+      assert!(statements[0].ends_with("END"));
+      assert_eq!(statements[1], "SELECT 1");
+   }
+
+   #[test]
+   fn begin_outside_triggers_is_an_ordinary_statement() {
+      assert_eq!(
+         split_statements("BEGIN; INSERT INTO a VALUES (1); END;"),
+         vec!["BEGIN", "INSERT INTO a VALUES (1)", "END"]
+      );
+   }
+}
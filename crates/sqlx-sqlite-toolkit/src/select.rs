@@ -0,0 +1,550 @@
+//! Fluent builder for simple SELECT queries
+//!
+//! [`SelectBuilder`] assembles a base query from validated identifiers and bound
+//! values, numbering `$N` placeholders automatically. The result is a plain
+//! `(String, Vec<JsonValue>)` pair accepted by `fetch_all`, `fetch_one` and
+//! `fetch_page`:
+//!
+//! ```no_run
+//! # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+//! use serde_json::json;
+//! use sqlx_sqlite_toolkit::{CompareOp, KeysetColumn, SelectBuilder};
+//!
+//! let (query, values) = SelectBuilder::from("posts")
+//!    .alias("p")
+//!    .columns(["p.id", "p.title"])
+//!    .column_as("u.name", "author")
+//!    .join_as("users", "u", "u.id", "p.author_id")
+//!    .where_eq("p.status", json!("published"))
+//!    .where_cmp("p.score", CompareOp::Ge, json!(10))
+//!    .build()?;
+//!
+//! let page = db
+//!    .fetch_page(query, values, vec![KeysetColumn::asc("id")], 25)
+//!    .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde_json::Value as JsonValue;
+
+use crate::Error;
+use crate::pagination::{quote_identifier, skip_quoted, validate_base_query, validate_column_name};
+
+/// Comparison operator for [`SelectBuilder::where_cmp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+   /// `=`
+   Eq,
+   /// `<>`
+   Ne,
+   /// `<`
+   Lt,
+   /// `<=`
+   Le,
+   /// `>`
+   Gt,
+   /// `>=`
+   Ge,
+   /// `LIKE`
+   Like,
+}
+
+impl CompareOp {
+   fn as_sql(self) -> &'static str {
+      match self {
+         CompareOp::Eq => "=",
+         CompareOp::Ne => "<>",
+         CompareOp::Lt => "<",
+         CompareOp::Le => "<=",
+         CompareOp::Gt => ">",
+         CompareOp::Ge => ">=",
+         CompareOp::Like => "LIKE",
+      }
+   }
+}
+
+#[derive(Debug, Clone)]
+struct SelectColumn {
+   name: String,
+   alias: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Join {
+   left: bool,
+   table: String,
+   alias: Option<String>,
+   on_left: String,
+   on_right: String,
+}
+
+/// A WHERE condition, shared with the builders that generate their own SQL
+#[derive(Debug, Clone)]
+pub(crate) enum Condition {
+   Compare {
+      column: String,
+      op: CompareOp,
+      value: JsonValue,
+   },
+   Null {
+      column: String,
+      negated: bool,
+   },
+   Sql {
+      fragment: String,
+      values: Vec<JsonValue>,
+   },
+}
+
+/// Fluent builder for a SELECT query with automatically numbered binds
+///
+/// Table, column and alias names are validated like keyset column names and
+/// emitted as quoted identifiers. WHERE conditions are combined with `AND`.
+/// Invalid names are reported by [`build`](Self::build), so the builder methods
+/// themselves never fail.
+#[derive(Debug, Clone)]
+pub struct SelectBuilder {
+   table: String,
+   alias: Option<String>,
+   columns: Vec<SelectColumn>,
+   joins: Vec<Join>,
+   conditions: Vec<Condition>,
+   group_by: Vec<String>,
+}
+
+impl SelectBuilder {
+   /// Start a query selecting from `table` (optionally `schema.table`)
+   pub fn from(table: impl Into<String>) -> Self {
+      Self {
+         table: table.into(),
+         alias: None,
+         columns: Vec::new(),
+         joins: Vec::new(),
+         conditions: Vec::new(),
+         group_by: Vec::new(),
+      }
+   }
+
+   /// Give the FROM table an alias for use in qualified column names
+   pub fn alias(mut self, alias: impl Into<String>) -> Self {
+      self.alias = Some(alias.into());
+      self
+   }
+
+   /// Select the given columns. `*` and `table.*` are accepted. Without any
+   /// columns the query selects `*`.
+   pub fn columns<I, S>(mut self, columns: I) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      self
+         .columns
+         .extend(columns.into_iter().map(|name| SelectColumn {
+            name: name.into(),
+            alias: None,
+         }));
+      self
+   }
+
+   /// Select a column under a different result name
+   pub fn column_as(mut self, column: impl Into<String>, alias: impl Into<String>) -> Self {
+      self.columns.push(SelectColumn {
+         name: column.into(),
+         alias: Some(alias.into()),
+      });
+      self
+   }
+
+   /// Add `JOIN table ON on_left = on_right`
+   pub fn join(
+      self,
+      table: impl Into<String>,
+      on_left: impl Into<String>,
+      on_right: impl Into<String>,
+   ) -> Self {
+      self.push_join(false, table.into(), None, on_left.into(), on_right.into())
+   }
+
+   /// Add `JOIN table AS alias ON on_left = on_right`
+   pub fn join_as(
+      self,
+      table: impl Into<String>,
+      alias: impl Into<String>,
+      on_left: impl Into<String>,
+      on_right: impl Into<String>,
+   ) -> Self {
+      self.push_join(
+         false,
+         table.into(),
+         Some(alias.into()),
+         on_left.into(),
+         on_right.into(),
+      )
+   }
+
+   /// Add `LEFT JOIN table ON on_left = on_right`
+   pub fn left_join(
+      self,
+      table: impl Into<String>,
+      on_left: impl Into<String>,
+      on_right: impl Into<String>,
+   ) -> Self {
+      self.push_join(true, table.into(), None, on_left.into(), on_right.into())
+   }
+
+   /// Add `LEFT JOIN table AS alias ON on_left = on_right`
+   pub fn left_join_as(
+      self,
+      table: impl Into<String>,
+      alias: impl Into<String>,
+      on_left: impl Into<String>,
+      on_right: impl Into<String>,
+   ) -> Self {
+      self.push_join(
+         true,
+         table.into(),
+         Some(alias.into()),
+         on_left.into(),
+         on_right.into(),
+      )
+   }
+
+   fn push_join(
+      mut self,
+      left: bool,
+      table: String,
+      alias: Option<String>,
+      on_left: String,
+      on_right: String,
+   ) -> Self {
+      self.joins.push(Join {
+         left,
+         table,
+         alias,
+         on_left,
+         on_right,
+      });
+      self
+   }
+
+   /// Add `column = value`
+   pub fn where_eq(self, column: impl Into<String>, value: JsonValue) -> Self {
+      self.where_cmp(column, CompareOp::Eq, value)
+   }
+
+   /// Add `column <op> value`
+   pub fn where_cmp(mut self, column: impl Into<String>, op: CompareOp, value: JsonValue) -> Self {
+      self.conditions.push(Condition::Compare {
+         column: column.into(),
+         op,
+         value,
+      });
+      self
+   }
+
+   /// Add `column IS NULL`
+   pub fn where_null(mut self, column: impl Into<String>) -> Self {
+      self.conditions.push(Condition::Null {
+         column: column.into(),
+         negated: false,
+      });
+      self
+   }
+
+   /// Add `column IS NOT NULL`
+   pub fn where_not_null(mut self, column: impl Into<String>) -> Self {
+      self.conditions.push(Condition::Null {
+         column: column.into(),
+         negated: true,
+      });
+      self
+   }
+
+   /// Add a hand-written condition using `?` placeholders, one per value.
+   ///
+   /// Placeholders are renumbered to fit the rest of the query; a `?` inside a
+   /// quoted literal or identifier is left alone. The fragment is wrapped in
+   /// parentheses so an `OR` inside it cannot escape the surrounding `AND`s.
+   pub fn where_sql(mut self, fragment: impl Into<String>, values: Vec<JsonValue>) -> Self {
+      self.conditions.push(Condition::Sql {
+         fragment: fragment.into(),
+         values,
+      });
+      self
+   }
+
+   /// Add columns to GROUP BY
+   pub fn group_by<I, S>(mut self, columns: I) -> Self
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      self.group_by.extend(columns.into_iter().map(Into::into));
+      self
+   }
+
+   /// Render the query and its bind values.
+   ///
+   /// Fails if any name is not a valid identifier, if a `where_sql` fragment's
+   /// placeholder count does not match its values, or if the result is not a
+   /// valid pagination base query (for example because a fragment smuggles in a
+   /// top-level ORDER BY or LIMIT).
+   pub fn build(self) -> Result<(String, Vec<JsonValue>), Error> {
+      let mut values = Vec::new();
+
+      let columns = if self.columns.is_empty() {
+         "*".to_string()
+      } else {
+         self
+            .columns
+            .iter()
+            .map(|column| {
+               let mut sql = select_column_sql(&column.name)?;
+               if let Some(alias) = &column.alias {
+                  sql.push_str(" AS ");
+                  sql.push_str(&unqualified_sql(alias)?);
+               }
+               Ok(sql)
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .join(", ")
+      };
+
+      let mut query = format!(
+         "SELECT {} FROM {}",
+         columns,
+         table_sql(&self.table, &self.alias)?
+      );
+
+      for join in &self.joins {
+         query.push_str(if join.left { " LEFT JOIN " } else { " JOIN " });
+         query.push_str(&table_sql(&join.table, &join.alias)?);
+         query.push_str(" ON ");
+         query.push_str(&column_sql(&join.on_left)?);
+         query.push_str(" = ");
+         query.push_str(&column_sql(&join.on_right)?);
+      }
+
+      if !self.conditions.is_empty() {
+         let conditions = self
+            .conditions
+            .into_iter()
+            .map(|condition| condition_sql(condition, &mut values))
+            .collect::<Result<Vec<_>, Error>>()?;
+         query.push_str(" WHERE ");
+         query.push_str(&conditions.join(" AND "));
+      }
+
+      if !self.group_by.is_empty() {
+         let columns = self
+            .group_by
+            .iter()
+            .map(|column| column_sql(column))
+            .collect::<Result<Vec<_>, Error>>()?;
+         query.push_str(" GROUP BY ");
+         query.push_str(&columns.join(", "));
+      }
+
+      validate_base_query(&query)?;
+      Ok((query, values))
+   }
+}
+
+/// Validate and quote a (possibly qualified) column name
+pub(crate) fn column_sql(name: &str) -> Result<String, Error> {
+   validate_column_name(name)?;
+   Ok(quote_identifier(name))
+}
+
+/// Like [`column_sql`], but also accepts `*` and `table.*`
+fn select_column_sql(name: &str) -> Result<String, Error> {
+   if name == "*" {
+      return Ok("*".to_string());
+   }
+   match name.strip_suffix(".*") {
+      Some(table) => Ok(format!("{}.*", column_sql(table)?)),
+      None => column_sql(name),
+   }
+}
+
+/// Validate and quote an unqualified name such as an alias
+pub(crate) fn unqualified_sql(name: &str) -> Result<String, Error> {
+   if name.contains('.') {
+      return Err(Error::InvalidColumnName {
+         name: name.to_string(),
+      });
+   }
+   column_sql(name)
+}
+
+fn table_sql(table: &str, alias: &Option<String>) -> Result<String, Error> {
+   let mut sql = column_sql(table)?;
+   if let Some(alias) = alias {
+      sql.push_str(" AS ");
+      sql.push_str(&unqualified_sql(alias)?);
+   }
+   Ok(sql)
+}
+
+/// Render one condition, appending its bind values and numbering its
+/// placeholders after the ones already in `values`
+pub(crate) fn condition_sql(
+   condition: Condition,
+   values: &mut Vec<JsonValue>,
+) -> Result<String, Error> {
+   match condition {
+      Condition::Compare { column, op, value } => {
+         values.push(value);
+         Ok(format!(
+            "{} {} ${}",
+            column_sql(&column)?,
+            op.as_sql(),
+            values.len()
+         ))
+      }
+      Condition::Null { column, negated } => Ok(format!(
+         "{} IS {}NULL",
+         column_sql(&column)?,
+         if negated { "NOT " } else { "" }
+      )),
+      Condition::Sql {
+         fragment,
+         values: fragment_values,
+      } => {
+         let sql = number_placeholders(&fragment, values.len(), fragment_values.len())?;
+         values.extend(fragment_values);
+         Ok(format!("({})", sql))
+      }
+   }
+}
+
+/// Replace each `?` outside quotes with `$N`, numbering from `offset + 1`.
+fn number_placeholders(fragment: &str, offset: usize, expected: usize) -> Result<String, Error> {
+   let bytes = fragment.as_bytes();
+   let len = bytes.len();
+   let mut sql = String::with_capacity(len + expected * 2);
+   let mut count = 0;
+   let mut start = 0;
+   let mut i = 0;
+
+   while i < len {
+      match bytes[i] {
+         b'\'' | b'"' => i = skip_quoted(bytes, len, i, bytes[i]),
+         b'?' => {
+            count += 1;
+            sql.push_str(&fragment[start..i]);
+            sql.push_str(&format!("${}", offset + count));
+            start = i + 1;
+         }
+         _ => {}
+      }
+      i += 1;
+   }
+   sql.push_str(&fragment[start.min(len)..]);
+
+   if count != expected {
+      return Err(Error::BindCountMismatch {
+         placeholders: count,
+         values: expected,
+      });
+   }
+
+   Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use serde_json::json;
+
+   // ─── build ───
+
+   #[test]
+   fn build_defaults_to_star() {
+      let (query, values) = SelectBuilder::from("posts").build().unwrap();
+      assert_eq!(query, "SELECT * FROM \"posts\"");
+      assert!(values.is_empty());
+   }
+
+   #[test]
+   fn build_numbers_binds_in_order() {
+      let (query, values) = SelectBuilder::from("posts")
+         .alias("p")
+         .columns(["p.id", "u.*"])
+         .column_as("p.title", "headline")
+         .join_as("users", "u", "u.id", "p.author_id")
+         .where_eq("p.status", json!("published"))
+         .where_sql("p.score > ? OR p.title = '?'", vec![json!(10)])
+         .where_cmp("p.views", CompareOp::Lt, json!(100))
+         .where_not_null("u.name")
+         .build()
+         .unwrap();
+
+      assert_eq!(
+         query,
+         "SELECT \"p\".\"id\", \"u\".*, \"p\".\"title\" AS \"headline\" \
+          FROM \"posts\" AS \"p\" JOIN \"users\" AS \"u\" ON \"u\".\"id\" = \"p\".\"author_id\" \
+          WHERE \"p\".\"status\" = $1 AND (p.score > $2 OR p.title = '?') \
+          AND \"p\".\"views\" < $3 AND \"u\".\"name\" IS NOT NULL"
+      );
+      assert_eq!(values, vec![json!("published"), json!(10), json!(100)]);
+   }
+
+   #[test]
+   fn build_left_join_and_group_by() {
+      let (query, _) = SelectBuilder::from("posts")
+         .columns(["category"])
+         .left_join("tags", "tags.post_id", "posts.id")
+         .where_null("posts.deleted_at")
+         .group_by(["category"])
+         .build()
+         .unwrap();
+
+      assert_eq!(
+         query,
+         "SELECT \"category\" FROM \"posts\" \
+          LEFT JOIN \"tags\" ON \"tags\".\"post_id\" = \"posts\".\"id\" \
+          WHERE \"posts\".\"deleted_at\" IS NULL GROUP BY \"category\""
+      );
+   }
+
+   #[test]
+   fn build_rejects_invalid_identifiers() {
+      let err = SelectBuilder::from("posts; DROP TABLE posts")
+         .build()
+         .unwrap_err();
+      assert!(matches!(err, Error::InvalidColumnName { .. }));
+
+      let err = SelectBuilder::from("posts")
+         .column_as("id", "a.b")
+         .build()
+         .unwrap_err();
+      assert!(matches!(err, Error::InvalidColumnName { .. }));
+   }
+
+   #[test]
+   fn build_rejects_placeholder_count_mismatch() {
+      let err = SelectBuilder::from("posts")
+         .where_sql("score > ? AND score < ?", vec![json!(1)])
+         .build()
+         .unwrap_err();
+      assert!(matches!(
+         err,
+         Error::BindCountMismatch {
+            placeholders: 2,
+            values: 1
+         }
+      ));
+   }
+
+   #[test]
+   fn build_rejects_fragment_escaping_its_parentheses() {
+      let err = SelectBuilder::from("posts")
+         .where_sql("1 = 1) ORDER BY (id", vec![])
+         .build()
+         .unwrap_err();
+      assert!(matches!(err, Error::InvalidPaginationQuery));
+   }
+}
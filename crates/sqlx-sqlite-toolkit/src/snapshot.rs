@@ -0,0 +1,233 @@
+//! Read snapshots: several reads that all see the database as of one moment
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+use serde_json::Value as JsonValue;
+use sqlx_sqlite_conn_mgr::ReadGuard;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::binds::expand_in_lists;
+use crate::builders::decode_rows;
+use crate::decode::DecodeOptions;
+use crate::describe::DeclaredTypes;
+use crate::transactions::DEFAULT_TRANSACTION_TIMEOUT;
+use crate::{Error, Result};
+
+/// A read connection pinned inside a `BEGIN DEFERRED` transaction.
+///
+/// Every fetch sees the same WAL snapshot, taken when the snapshot began, so
+/// a multi-query report can't see a write land between its queries. Writes
+/// carry on meanwhile; they just aren't visible here.
+///
+/// Holds one of the read pool's connections until [`release`](Self::release)
+/// is called or the snapshot is dropped. Keep snapshots short: a WAL file
+/// can't be checkpointed past a snapshot that is still open.
+#[must_use = "if unused, the snapshot is immediately released"]
+pub struct ReadSnapshot {
+   conn: Option<ReadGuard>,
+   decode_options: DecodeOptions,
+   created_at: Instant,
+   // Dropping a pool connection spawns onto the runtime, which panics on a
+   // thread without one (e.g. Tauri teardown), so Drop enters this handle.
+   runtime_handle: tokio::runtime::Handle,
+}
+
+impl ReadSnapshot {
+   pub(crate) async fn begin(
+      db: &sqlx_sqlite_conn_mgr::SqliteDatabase,
+      decode_options: DecodeOptions,
+   ) -> Result<Self> {
+      let mut conn = db.acquire_reader().await?;
+
+      // A deferred transaction only takes its snapshot at the first read, so
+      // read something now rather than at the caller's first fetch.
+      let begun = async {
+         sqlx::query("BEGIN DEFERRED").execute(&mut *conn).await?;
+         sqlx::query("SELECT 1 FROM sqlite_master LIMIT 1")
+            .execute(&mut *conn)
+            .await?;
+         Ok::<(), sqlx::Error>(())
+      }
+      .await;
+
+      if let Err(err) = begun {
+         conn.close_on_drop();
+         return Err(err.into());
+      }
+
+      Ok(Self {
+         conn: Some(conn),
+         decode_options,
+         created_at: Instant::now(),
+         runtime_handle: tokio::runtime::Handle::current(),
+      })
+   }
+
+   /// Fetch all rows as of the snapshot
+   pub async fn fetch_all(
+      &mut self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Result<Vec<IndexMap<String, JsonValue>>> {
+      let query = expand_in_lists(query);
+      let mut q = sqlx::query(&query);
+      for value in values {
+         q = crate::wrapper::bind_value(q, value);
+      }
+
+      let conn = self
+         .conn
+         .as_mut()
+         .ok_or(Error::TransactionAlreadyFinalized)?;
+      let types = DeclaredTypes::read(conn, &query, &self.decode_options).await?;
+      let rows = q.fetch_all(&mut **conn).await?;
+      decode_rows(&rows, &self.decode_options, &types)
+   }
+
+   /// Fetch zero or one row as of the snapshot
+   ///
+   /// Returns [`Error::MultipleRowsReturned`] if more than one row matches.
+   pub async fn fetch_one(
+      &mut self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Result<Option<IndexMap<String, JsonValue>>> {
+      let mut rows = self.fetch_all(query, values).await?;
+      match rows.len() {
+         0 | 1 => Ok(rows.pop()),
+         count => Err(Error::MultipleRowsReturned(count)),
+      }
+   }
+
+   /// End the snapshot and return its connection to the read pool
+   pub async fn release(mut self) -> Result<()> {
+      let Some(mut conn) = self.conn.take() else {
+         return Err(Error::TransactionAlreadyFinalized);
+      };
+
+      if let Err(err) = sqlx::query("ROLLBACK").execute(&mut *conn).await {
+         conn.close_on_drop();
+         return Err(err.into());
+      }
+      Ok(())
+   }
+}
+
+impl Drop for ReadSnapshot {
+   fn drop(&mut self) {
+      // There's no async context to end the transaction in, so close the
+      // connection instead of returning it to the pool still inside one.
+      if let Some(mut conn) = self.conn.take() {
+         debug!("Read snapshot dropped without release, closing its connection");
+         conn.close_on_drop();
+         let _runtime = self.runtime_handle.enter();
+         drop(conn);
+      }
+   }
+}
+
+/// Global state tracking the open read snapshots, keyed by snapshot ID.
+///
+/// Snapshots open for longer than the timeout are released lazily on the next
+/// `remove()` of any snapshot, so a frontend that forgets one can't pin a read
+/// connection forever.
+#[derive(Clone)]
+pub struct ActiveReadSnapshots {
+   inner: Arc<Mutex<HashMap<String, (String, ReadSnapshot)>>>,
+   timeout: Duration,
+}
+
+impl Default for ActiveReadSnapshots {
+   fn default() -> Self {
+      Self::new(DEFAULT_TRANSACTION_TIMEOUT)
+   }
+}
+
+impl ActiveReadSnapshots {
+   /// Create a new instance with the given snapshot timeout.
+   pub fn new(timeout: Duration) -> Self {
+      Self {
+         inner: Arc::new(Mutex::new(HashMap::new())),
+         timeout,
+      }
+   }
+
+   pub async fn insert(&self, db_path: String, snapshot_id: String, snapshot: ReadSnapshot) {
+      let mut snapshots = self.inner.lock().await;
+      snapshots.insert(snapshot_id, (db_path, snapshot));
+   }
+
+   /// Remove and return a snapshot to fetch from or release.
+   ///
+   /// Returns `Err(Error::TransactionTimedOut)` if the snapshot has exceeded the
+   /// configured timeout, after releasing it.
+   pub async fn remove(&self, db_path: &str, snapshot_id: &str) -> Result<ReadSnapshot> {
+      let mut snapshots = self.inner.lock().await;
+
+      let expired: Vec<String> = snapshots
+         .iter()
+         .filter(|(_, (_, snapshot))| snapshot.created_at.elapsed() >= self.timeout)
+         .map(|(id, _)| id.clone())
+         .collect();
+      let mut timed_out = false;
+      for id in expired {
+         if let Some((path, snapshot)) = snapshots.remove(&id) {
+            warn!("Releasing expired read snapshot for db: {path}");
+            timed_out |= id == snapshot_id;
+            // Dropping closes the connection; no need to wait on a ROLLBACK
+            drop(snapshot);
+         }
+      }
+      if timed_out {
+         return Err(Error::TransactionTimedOut(db_path.to_string()));
+      }
+
+      match snapshots.get(snapshot_id) {
+         Some((path, _)) if path == db_path => {}
+         _ => return Err(Error::NoActiveSnapshot(db_path.to_string())),
+      }
+
+      // Safe unwrap: we just confirmed the key exists above.
+      Ok(snapshots.remove(snapshot_id).unwrap().1)
+   }
+
+   /// Release every snapshot of a database, e.g. before closing it
+   pub async fn remove_for_db(&self, db_path: &str) {
+      let removed: Vec<ReadSnapshot> = {
+         let mut snapshots = self.inner.lock().await;
+         let ids: Vec<String> = snapshots
+            .iter()
+            .filter(|(_, (path, _))| path == db_path)
+            .map(|(id, _)| id.clone())
+            .collect();
+         ids.iter()
+            .filter_map(|id| snapshots.remove(id).map(|(_, snapshot)| snapshot))
+            .collect()
+      };
+
+      for snapshot in removed {
+         if let Err(err) = snapshot.release().await {
+            warn!("release of read snapshot failed (db: {db_path}): {err}");
+         }
+      }
+   }
+
+   /// Release every snapshot
+   pub async fn abort_all(&self) {
+      let drained: Vec<(String, ReadSnapshot)> = {
+         let mut snapshots = self.inner.lock().await;
+         debug!("Releasing {} read snapshot(s)", snapshots.len());
+         snapshots.drain().map(|(_, entry)| entry).collect()
+      };
+
+      for (db_path, snapshot) in drained {
+         if let Err(err) = snapshot.release().await {
+            warn!("release of read snapshot failed (db: {db_path}): {err}");
+         }
+      }
+   }
+}
@@ -0,0 +1,584 @@
+//! Excluding soft-deleted rows from SELECT queries
+
+use std::collections::HashMap;
+
+use crate::pagination::{
+   is_keyword_at, is_keyword_pair_at, is_order_by_at, main_select_offset, scan_top_level,
+   skip_block_comment, skip_line_comment, skip_quoted,
+};
+
+/// Words that can join one table to the next in a FROM clause
+const JOIN_WORDS: [&str; 8] = [
+   "NATURAL", "LEFT", "RIGHT", "FULL", "OUTER", "INNER", "CROSS", "JOIN",
+];
+
+/// Words that can follow a table name in a FROM clause without being its alias
+const NOT_ALIASES: [&str; 4] = ["ON", "USING", "INDEXED", "NOT"];
+
+/// Top-level position in a query that matters for placing the filter
+enum Mark {
+   Select,
+   From,
+   Where,
+   /// Start of a clause that follows WHERE, or the end of the statement
+   ClauseEnd,
+   /// `UNION`, `INTERSECT` or `EXCEPT`, which end one SELECT and start another
+   Compound,
+}
+
+/// The parts of one SELECT (of possibly several in a compound query)
+#[derive(Default)]
+struct Segment {
+   from_at: Option<usize>,
+   where_at: Option<usize>,
+   end: Option<usize>,
+}
+
+/// Add `<table>.<column> IS NULL` for every soft-delete table read by a
+/// top-level SELECT in `query`.
+///
+/// `tables` maps lowercase table names to their validated, quoted column. The
+/// table is referred to by its alias if it has one. The filter goes in the
+/// table's `ON` constraint when it is joined with one, so outer joins keep
+/// their unmatched rows, and in the WHERE clause otherwise, where an existing
+/// condition is parenthesized so an `OR` in it cannot bypass the filter.
+/// Subqueries, CTE bodies and CTEs named like a table are left alone, as are
+/// queries without a top-level SELECT.
+pub(crate) fn exclude_deleted(query: &str, tables: &HashMap<String, String>) -> String {
+   let Some(main) = main_select_offset(query) else {
+      return query.to_string();
+   };
+
+   let mut marks = Vec::new();
+   scan_top_level::<()>(query, |bytes, len, i| {
+      let mark = if is_keyword_at(bytes, len, i, b"SELECT") {
+         Mark::Select
+      } else if is_keyword_at(bytes, len, i, b"FROM") {
+         Mark::From
+      } else if is_keyword_at(bytes, len, i, b"WHERE") {
+         Mark::Where
+      } else if is_keyword_pair_at(bytes, len, i, b"GROUP", b"BY")
+         || is_keyword_at(bytes, len, i, b"HAVING")
+         || is_keyword_at(bytes, len, i, b"WINDOW")
+         || is_order_by_at(bytes, len, i)
+         || is_keyword_at(bytes, len, i, b"LIMIT")
+         || bytes[i] == b';'
+      {
+         Mark::ClauseEnd
+      } else if is_keyword_at(bytes, len, i, b"UNION")
+         || is_keyword_at(bytes, len, i, b"INTERSECT")
+         || is_keyword_at(bytes, len, i, b"EXCEPT")
+      {
+         Mark::Compound
+      } else {
+         return None;
+      };
+      if i >= main {
+         marks.push((i, mark));
+      }
+      None
+   });
+
+   let mut segments = vec![Segment::default()];
+   for (i, mark) in marks {
+      let segment = segments.last_mut().unwrap();
+      match mark {
+         Mark::Select | Mark::From | Mark::Where if segment.end.is_some() => {}
+         Mark::Select => {}
+         Mark::From => segment.from_at = segment.from_at.or(Some(i)),
+         Mark::Where => segment.where_at = segment.where_at.or(Some(i)),
+         Mark::ClauseEnd => segment.end = segment.end.or(Some(i)),
+         Mark::Compound => {
+            segment.end = segment.end.or(Some(i));
+            segments.push(Segment::default());
+         }
+      }
+   }
+
+   let ctes = cte_names(query, main);
+   // Replacements of `query[start..end]`, in order
+   let mut edits: Vec<(usize, usize, String)> = Vec::new();
+   for segment in &segments {
+      let Some(from_at) = segment.from_at else {
+         continue;
+      };
+      let end = segment.end.unwrap_or(query.len());
+      let from_end = segment.where_at.unwrap_or(end);
+
+      let mut conditions = Vec::new();
+      for table in table_refs(query, from_at + "FROM".len(), from_end) {
+         let name = table.name.to_ascii_lowercase();
+         if ctes.contains(&name) {
+            continue;
+         }
+         let Some(column) = tables.get(&name) else {
+            continue;
+         };
+         let condition = format!("{}.{column} IS NULL", table.qualifier);
+         match table.on {
+            Some((on_start, on_end)) => {
+               edits.push((on_start, on_start, "(".to_string()));
+               edits.push((on_end, on_end, format!(") AND {condition}")));
+            }
+            None => conditions.push(condition),
+         }
+      }
+      if conditions.is_empty() {
+         continue;
+      }
+      let conditions = conditions.join(" AND ");
+      let space = if end < query.len() { " " } else { "" };
+
+      match segment.where_at {
+         Some(where_at) => {
+            let body_start = where_at + "WHERE".len();
+            let body = &query[body_start..end];
+            let trimmed_start = body_start + body.len() - body.trim_start().len();
+            let trimmed_end = body_start + body.trim_end().len();
+            // A trailing line comment would swallow the closing parenthesis
+            let newline = if ends_in_line_comment(&query[..trimmed_end]) {
+               "\n"
+            } else {
+               ""
+            };
+            edits.push((body_start, trimmed_start, " (".to_string()));
+            edits.push((
+               trimmed_end,
+               end,
+               format!("{newline}) AND {conditions}{space}"),
+            ));
+         }
+         None => {
+            let trimmed_end = query[..end].trim_end().len();
+            let newline = if ends_in_line_comment(&query[..trimmed_end]) {
+               "\n"
+            } else {
+               ""
+            };
+            edits.push((
+               trimmed_end,
+               end,
+               format!("{newline} WHERE {conditions}{space}"),
+            ));
+         }
+      }
+   }
+
+   edits.sort_by_key(|&(start, end, _)| (start, end));
+   let mut filtered = String::with_capacity(query.len() + 64 * edits.len());
+   let mut copied = 0;
+   for (start, end, text) in edits {
+      filtered.push_str(&query[copied..start]);
+      filtered.push_str(&text);
+      copied = end;
+   }
+   filtered.push_str(&query[copied..]);
+
+   filtered
+}
+
+fn ends_in_line_comment(sql: &str) -> bool {
+   sql.rsplit('\n')
+      .next()
+      .is_some_and(|line| line.contains("--"))
+}
+
+/// A table read by a FROM clause
+struct TableRef {
+   name: String,
+   /// How columns of the table are qualified: its alias, or its name
+   qualifier: String,
+   /// Byte range of the expression of the `ON` constraint joining the table
+   on: Option<(usize, usize)>,
+}
+
+/// The tables in the FROM clause `query[start..end]`, skipping subqueries and
+/// table-valued functions
+fn table_refs(query: &str, start: usize, end: usize) -> Vec<TableRef> {
+   let tokens = tokenize(query, start, end);
+   let is = |k: usize, word: &str| tokens.get(k).is_some_and(|t| t.is_word(query, word));
+   let is_join_word = |k: usize| JOIN_WORDS.iter().any(|word| is(k, word));
+
+   let mut tables = Vec::new();
+   let mut k = 0;
+   while k < tokens.len() {
+      let mut table = None;
+      match tokens[k].identifier(query) {
+         Some(first) => {
+            k += 1;
+            let qualified = match tokens.get(k + 1) {
+               Some(next) if tokens[k].kind == Kind::Dot => next.identifier(query),
+               _ => None,
+            };
+            table = match qualified {
+               Some(name) => {
+                  k += 2;
+                  Some((Some(first), name))
+               }
+               None => Some((None, first)),
+            };
+            // A table-valued function, such as `json_each(...)`
+            if tokens.get(k).is_some_and(|t| t.kind == Kind::Group) {
+               table = None;
+               k += 1;
+            }
+         }
+         None => k += 1,
+      }
+
+      let alias = if is(k, "AS") {
+         k += 2;
+         tokens.get(k - 1).and_then(|t| t.identifier(query))
+      } else {
+         match tokens.get(k) {
+            Some(t)
+               if t.kind == Kind::Quoted
+                  || (t.kind == Kind::Word
+                     && !is_join_word(k)
+                     && !NOT_ALIASES.iter().any(|word| is(k, word))) =>
+            {
+               k += 1;
+               t.identifier(query)
+            }
+            _ => None,
+         }
+      };
+
+      if is(k, "INDEXED") {
+         k += 3;
+      } else if is(k, "NOT") {
+         k += 2;
+      }
+
+      let mut on = None;
+      if is(k, "ON") {
+         k += 1;
+         let first = k;
+         while k < tokens.len() && tokens[k].kind != Kind::Comma && !is_join_word(k) {
+            k += 1;
+         }
+         if k > first {
+            on = Some((tokens[first].start, tokens[k - 1].end));
+         }
+      } else if is(k, "USING") {
+         k += 2;
+      }
+
+      if let Some((schema, name)) = table {
+         let qualifier = match (alias, schema) {
+            (Some(alias), _) => quoted(&alias),
+            (None, Some(schema)) => format!("{}.{}", quoted(&schema), quoted(&name)),
+            (None, None) => quoted(&name),
+         };
+         tables.push(TableRef {
+            name,
+            qualifier,
+            on,
+         });
+      }
+
+      while k < tokens.len() && (tokens[k].kind == Kind::Comma || is_join_word(k)) {
+         k += 1;
+      }
+   }
+
+   tables
+}
+
+/// Lowercase names of the CTEs defined before the main SELECT at `main`
+fn cte_names(query: &str, main: usize) -> Vec<String> {
+   let tokens = tokenize(query, 0, main);
+   tokens
+      .iter()
+      .enumerate()
+      .filter_map(|(k, token)| {
+         let name = token.identifier(query)?;
+         let mut next = k + 1;
+         if tokens.get(next).is_some_and(|t| t.kind == Kind::Group) {
+            next += 1;
+         }
+         tokens
+            .get(next)
+            .is_some_and(|t| t.is_word(query, "AS"))
+            .then(|| name.to_ascii_lowercase())
+      })
+      .collect()
+}
+
+fn quoted(name: &str) -> String {
+   format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+   /// A keyword or bare identifier
+   Word,
+   /// A `"quoted"`, `` `quoted` `` or `[quoted]` identifier
+   Quoted,
+   Dot,
+   Comma,
+   /// A parenthesized group, such as a subquery or function arguments
+   Group,
+   Other,
+}
+
+struct Token {
+   kind: Kind,
+   start: usize,
+   end: usize,
+}
+
+impl Token {
+   fn is_word(&self, query: &str, word: &str) -> bool {
+      self.kind == Kind::Word && query[self.start..self.end].eq_ignore_ascii_case(word)
+   }
+
+   /// The identifier this token names, unquoted
+   fn identifier(&self, query: &str) -> Option<String> {
+      let text = &query[self.start..self.end];
+      match self.kind {
+         Kind::Word => Some(text.to_string()),
+         Kind::Quoted if text.len() >= 2 => {
+            let inner = &text[1..text.len() - 1];
+            Some(match text.as_bytes()[0] {
+               b'"' => inner.replace("\"\"", "\""),
+               b'`' => inner.replace("``", "`"),
+               _ => inner.to_string(),
+            })
+         }
+         _ => None,
+      }
+   }
+}
+
+/// Split `query[start..end]` into tokens, skipping whitespace and comments
+fn tokenize(query: &str, start: usize, end: usize) -> Vec<Token> {
+   let bytes = query.as_bytes();
+   let mut tokens = Vec::new();
+   let mut i = start;
+   while i < end {
+      let token_start = i;
+      let kind = match bytes[i] {
+         b if b.is_ascii_whitespace() => {
+            i += 1;
+            continue;
+         }
+         b'-' if bytes.get(i + 1) == Some(&b'-') => {
+            i = skip_line_comment(bytes, end, i);
+            continue;
+         }
+         b'/' if bytes.get(i + 1) == Some(&b'*') => {
+            i = skip_block_comment(bytes, end, i) + 1;
+            continue;
+         }
+         quote @ (b'"' | b'`') => {
+            i = skip_quoted(bytes, end, i, quote) + 1;
+            Kind::Quoted
+         }
+         b'[' => {
+            i = skip_bracketed(bytes, end, i) + 1;
+            Kind::Quoted
+         }
+         b'\'' => {
+            i = skip_quoted(bytes, end, i, b'\'') + 1;
+            Kind::Other
+         }
+         b'(' => {
+            i = skip_group(bytes, end, i);
+            Kind::Group
+         }
+         b'.' => {
+            i += 1;
+            Kind::Dot
+         }
+         b',' => {
+            i += 1;
+            Kind::Comma
+         }
+         b if is_identifier_byte(b) => {
+            while i < end && is_identifier_byte(bytes[i]) {
+               i += 1;
+            }
+            Kind::Word
+         }
+         _ => {
+            i += 1;
+            Kind::Other
+         }
+      };
+      tokens.push(Token {
+         kind,
+         start: token_start,
+         end: i.min(end),
+      });
+   }
+   tokens
+}
+
+fn is_identifier_byte(b: u8) -> bool {
+   b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+/// Position of the `]` closing the identifier opened at `i`, or `end`
+fn skip_bracketed(bytes: &[u8], end: usize, i: usize) -> usize {
+   bytes[i..end]
+      .iter()
+      .position(|&b| b == b']')
+      .map_or(end, |offset| i + offset)
+}
+
+/// Position just past the `)` matching the `(` at `i`, or `end`
+fn skip_group(bytes: &[u8], end: usize, i: usize) -> usize {
+   let mut depth = 0;
+   let mut j = i;
+   while j < end {
+      match bytes[j] {
+         b'(' => depth += 1,
+         b')' => {
+            depth -= 1;
+            if depth == 0 {
+               return j + 1;
+            }
+         }
+         quote @ (b'\'' | b'"' | b'`') => j = skip_quoted(bytes, end, j, quote),
+         b'[' => j = skip_bracketed(bytes, end, j),
+         b'-' if bytes.get(j + 1) == Some(&b'-') => j = skip_line_comment(bytes, end, j),
+         b'/' if bytes.get(j + 1) == Some(&b'*') => j = skip_block_comment(bytes, end, j),
+         _ => {}
+      }
+      j += 1;
+   }
+   end
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn tables(entries: &[(&str, &str)]) -> HashMap<String, String> {
+      entries
+         .iter()
+         .map(|(table, column)| (table.to_string(), format!("\"{column}\"")))
+         .collect()
+   }
+
+   fn posts() -> HashMap<String, String> {
+      tables(&[("posts", "deleted_at")])
+   }
+
+   #[test]
+   fn adds_where_clause() {
+      assert_eq!(
+         exclude_deleted("SELECT * FROM posts", &posts()),
+         "SELECT * FROM posts WHERE \"posts\".\"deleted_at\" IS NULL"
+      );
+      assert_eq!(
+         exclude_deleted("SELECT * FROM posts ORDER BY id LIMIT 5;", &posts()),
+         "SELECT * FROM posts WHERE \"posts\".\"deleted_at\" IS NULL ORDER BY id LIMIT 5;"
+      );
+   }
+
+   #[test]
+   fn parenthesizes_existing_condition() {
+      assert_eq!(
+         exclude_deleted(
+            "SELECT category, COUNT(*) FROM posts WHERE a = 1 OR b = 2 GROUP BY category",
+            &posts()
+         ),
+         "SELECT category, COUNT(*) FROM posts WHERE (a = 1 OR b = 2) \
+          AND \"posts\".\"deleted_at\" IS NULL GROUP BY category"
+      );
+   }
+
+   #[test]
+   fn leaves_subqueries_and_ctes_alone() {
+      assert_eq!(
+         exclude_deleted(
+            "WITH recent AS (SELECT id FROM posts WHERE id > 5) \
+             SELECT * FROM posts WHERE id IN (SELECT id FROM recent WHERE id < 9)",
+            &posts()
+         ),
+         "WITH recent AS (SELECT id FROM posts WHERE id > 5) \
+          SELECT * FROM posts WHERE (id IN (SELECT id FROM recent WHERE id < 9)) \
+          AND \"posts\".\"deleted_at\" IS NULL"
+      );
+      assert_eq!(
+         exclude_deleted(
+            "WITH posts (id) AS (SELECT 1) SELECT * FROM posts",
+            &posts()
+         ),
+         "WITH posts (id) AS (SELECT 1) SELECT * FROM posts"
+      );
+   }
+
+   #[test]
+   fn filters_every_compound_member() {
+      assert_eq!(
+         exclude_deleted(
+            "SELECT id FROM posts WHERE pinned UNION ALL SELECT id FROM drafts ORDER BY id",
+            &tables(&[("posts", "deleted_at"), ("drafts", "removed_at")])
+         ),
+         "SELECT id FROM posts WHERE (pinned) AND \"posts\".\"deleted_at\" IS NULL UNION ALL \
+          SELECT id FROM drafts WHERE \"drafts\".\"removed_at\" IS NULL ORDER BY id"
+      );
+   }
+
+   #[test]
+   fn qualifies_with_alias_and_filters_joins_in_on() {
+      assert_eq!(
+         exclude_deleted(
+            "SELECT p.title, c.body FROM posts AS p LEFT JOIN comments c ON c.post_id = p.id \
+             WHERE p.pinned",
+            &tables(&[("posts", "deleted_at"), ("comments", "deleted_at")])
+         ),
+         "SELECT p.title, c.body FROM posts AS p LEFT JOIN comments c \
+          ON (c.post_id = p.id) AND \"c\".\"deleted_at\" IS NULL \
+          WHERE (p.pinned) AND \"p\".\"deleted_at\" IS NULL"
+      );
+      assert_eq!(
+         exclude_deleted(
+            "SELECT * FROM main.posts, \"Users\" u JOIN json_each(u.tags)",
+            &tables(&[("posts", "deleted_at"), ("users", "deleted_at")])
+         ),
+         "SELECT * FROM main.posts, \"Users\" u JOIN json_each(u.tags) \
+          WHERE \"main\".\"posts\".\"deleted_at\" IS NULL AND \"u\".\"deleted_at\" IS NULL"
+      );
+   }
+
+   #[test]
+   fn leaves_other_tables_alone() {
+      assert_eq!(
+         exclude_deleted("SELECT * FROM users WHERE id = 1", &posts()),
+         "SELECT * FROM users WHERE id = 1"
+      );
+      assert_eq!(
+         exclude_deleted(
+            "SELECT * FROM users u JOIN posts p ON p.author = u.id",
+            &posts()
+         ),
+         "SELECT * FROM users u JOIN posts p ON (p.author = u.id) \
+          AND \"p\".\"deleted_at\" IS NULL"
+      );
+   }
+
+   #[test]
+   fn ignores_keywords_in_literals_and_comments() {
+      assert_eq!(
+         exclude_deleted(
+            "SELECT 'ORDER BY' FROM posts WHERE title = 'x' -- LIMIT\n",
+            &posts()
+         ),
+         "SELECT 'ORDER BY' FROM posts WHERE (title = 'x' -- LIMIT\n) \
+          AND \"posts\".\"deleted_at\" IS NULL"
+      );
+   }
+
+   #[test]
+   fn leaves_queries_without_tables_alone() {
+      assert_eq!(exclude_deleted("SELECT 1", &posts()), "SELECT 1");
+      assert_eq!(
+         exclude_deleted("PRAGMA user_version", &posts()),
+         "PRAGMA user_version"
+      );
+   }
+}
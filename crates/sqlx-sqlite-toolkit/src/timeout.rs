@@ -0,0 +1,75 @@
+//! Per-builder statement time limits
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use sqlx_sqlite_conn_mgr::InterruptHandle;
+
+use crate::Error;
+
+/// Time limit for a builder's statement, started when the builder is executed
+///
+/// Without a limit every method simply awaits its future.
+#[derive(Clone, Copy)]
+pub(crate) struct QueryDeadline {
+   limit: Option<(Instant, Duration)>,
+}
+
+impl QueryDeadline {
+   /// Start the clock for `timeout`, if any
+   pub(crate) fn start(timeout: Option<Duration>) -> Self {
+      Self {
+         limit: timeout.map(|timeout| (Instant::now(), timeout)),
+      }
+   }
+
+   /// Wait for a connection, giving up once the deadline passes
+   ///
+   /// Nothing has run yet while waiting, so the wait is simply abandoned.
+   pub(crate) async fn acquire<T, E>(
+      &self,
+      acquire: impl Future<Output = Result<T, E>>,
+   ) -> Result<T, Error>
+   where
+      Error: From<E>,
+   {
+      let Some((started, timeout)) = self.limit else {
+         return Ok(acquire.await?);
+      };
+
+      match tokio::time::timeout(timeout.saturating_sub(started.elapsed()), acquire).await {
+         Ok(result) => Ok(result?),
+         Err(_) => Err(Error::QueryTimeout(started.elapsed())),
+      }
+   }
+
+   /// Run a statement, interrupting it through `interrupt` if the deadline passes
+   ///
+   /// After interrupting, the statement is still awaited so the connection is idle
+   /// again before it goes back to the pool. If it finished successfully in the
+   /// meantime its result is kept; otherwise the error becomes
+   /// [`Error::QueryTimeout`].
+   pub(crate) async fn run<T, E>(
+      &self,
+      interrupt: InterruptHandle,
+      statement: impl Future<Output = Result<T, E>>,
+   ) -> Result<T, Error>
+   where
+      Error: From<E>,
+   {
+      let Some((started, timeout)) = self.limit else {
+         return Ok(statement.await?);
+      };
+
+      let mut statement = std::pin::pin!(statement);
+      match tokio::time::timeout(timeout.saturating_sub(started.elapsed()), &mut statement).await {
+         Ok(result) => Ok(result?),
+         Err(_) => {
+            interrupt.interrupt();
+            statement
+               .await
+               .map_err(|_| Error::QueryTimeout(started.elapsed()))
+         }
+      }
+   }
+}
@@ -1,24 +1,64 @@
 //! Transaction management for interruptible transactions
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
+use futures::future::BoxFuture;
 use indexmap::IndexMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use sqlx::{Column, Row};
-use sqlx_sqlite_conn_mgr::{AttachedWriteGuard, WriteGuard};
-use tokio::sync::{Mutex, RwLock};
+use sqlx::sqlite::SqliteConnection;
+use sqlx_sqlite_conn_mgr::{AttachedWriteGuard, InterruptHandle, WriteGuard};
+use tokio::sync::{Mutex, OwnedSemaphorePermit, RwLock, Semaphore};
 use tokio::task::AbortHandle;
 use tracing::{debug, warn};
 
 #[cfg(feature = "observer")]
 use sqlx_sqlite_observer::ObservableWriteGuard;
 
+use crate::binds::expand_in_lists;
+use crate::builders::decode_rows;
+use crate::decode::DecodeOptions;
+use crate::describe::DeclaredTypes;
+use crate::retry::{RetryPolicy, is_busy};
 use crate::wrapper::WriterGuard;
 use crate::{Error, Result, WriteQueryResult};
 
+/// How a transaction begins, deciding when it takes its locks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionMode {
+   /// Takes no lock until the first read or write. Suits read-mostly
+   /// transactions, but a later write can fail with `SQLITE_BUSY`.
+   Deferred,
+   /// Takes the write lock straight away (the default)
+   #[default]
+   Immediate,
+   /// Takes the write lock straight away and, outside WAL mode, keeps other
+   /// connections from reading too
+   Exclusive,
+}
+
+impl TransactionMode {
+   fn begin_sql(self) -> &'static str {
+      match self {
+         Self::Deferred => "BEGIN DEFERRED",
+         Self::Immediate => "BEGIN IMMEDIATE",
+         Self::Exclusive => "BEGIN EXCLUSIVE",
+      }
+   }
+}
+
+/// Callback run just before a transaction commits; returning an error rolls
+/// the transaction back instead.
+///
+/// Gets the transaction's connection with `PRAGMA query_only` set, so it sees
+/// the uncommitted changes but can't write. Registered with
+/// `DatabaseWrapper::add_pre_commit_hook()`.
+pub type PreCommitHook =
+   Arc<dyn for<'c> Fn(&'c mut SqliteConnection) -> BoxFuture<'c, Result<()>> + Send + Sync>;
+
 /// Wrapper around WriteGuard, ObservableWriteGuard, or AttachedWriteGuard
 /// to unify transaction handling.
 pub enum TransactionWriter {
@@ -55,12 +95,95 @@ impl TransactionWriter {
       }
    }
 
+   /// Get a handle that interrupts statements running on this writer
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      match self {
+         Self::Regular(w) => w.interrupt_handle(),
+         Self::Attached(w) => w.interrupt_handle(),
+         #[cfg(feature = "observer")]
+         Self::Observable(w) => w.interrupt_handle(),
+      }
+   }
+
+   fn connection(&mut self) -> &mut SqliteConnection {
+      match self {
+         Self::Regular(w) => w,
+         Self::Attached(w) => w,
+         #[cfg(feature = "observer")]
+         Self::Observable(w) => w,
+      }
+   }
+
+   /// Run `hooks` in order against the open transaction with writes
+   /// disabled, stopping at the first error
+   pub async fn run_pre_commit_hooks(&mut self, hooks: &[PreCommitHook]) -> Result<()> {
+      if hooks.is_empty() {
+         return Ok(());
+      }
+
+      self
+         .execute_query(sqlx::query("PRAGMA query_only = ON"))
+         .await?;
+      let mut result = Ok(());
+      for hook in hooks {
+         result = hook(self.connection()).await;
+         if result.is_err() {
+            break;
+         }
+      }
+      // Always lift the flag: this is the database's only writer
+      let reset = self
+         .execute_query(sqlx::query("PRAGMA query_only = OFF"))
+         .await;
+
+      result?;
+      reset?;
+      Ok(())
+   }
+
    /// Begin an immediate transaction
    pub async fn begin_immediate(&mut self) -> Result<()> {
-      self.execute_query(sqlx::query("BEGIN IMMEDIATE")).await?;
+      self.begin(TransactionMode::Immediate).await
+   }
+
+   /// Begin a transaction in `mode`
+   pub async fn begin(&mut self, mode: TransactionMode) -> Result<()> {
+      self.execute_query(sqlx::query(mode.begin_sql())).await?;
       Ok(())
    }
 
+   /// Begin a transaction in `mode`, retrying with backoff while another
+   /// connection or process holds the lock
+   ///
+   /// Fails with [`Error::RetriesExhausted`] once `policy.max_attempts` busy
+   /// attempts are used up.
+   pub async fn begin_with_retry(
+      &mut self,
+      mode: TransactionMode,
+      policy: RetryPolicy,
+   ) -> Result<()> {
+      let max_attempts = policy.max_attempts.max(1);
+      let mut attempts = 0;
+
+      loop {
+         attempts += 1;
+         match self.begin(mode).await {
+            Ok(()) => return Ok(()),
+            Err(err) if is_busy(&err) => {
+               if attempts >= max_attempts {
+                  return Err(Error::RetriesExhausted {
+                     attempts,
+                     source: Box::new(err),
+                  });
+               }
+               debug!("{} busy on attempt {attempts}, retrying", mode.begin_sql());
+               tokio::time::sleep(policy.backoff(attempts)).await;
+            }
+            Err(err) => return Err(err),
+         }
+      }
+   }
+
    /// Commit the current transaction
    pub async fn commit(&mut self) -> Result<()> {
       self.execute_query(sqlx::query("COMMIT")).await?;
@@ -68,9 +191,19 @@ impl TransactionWriter {
    }
 
    /// Rollback the current transaction
+   ///
+   /// Succeeds when SQLite already rolled the transaction back itself, as it
+   /// does when an interrupted write aborts it.
    pub async fn rollback(&mut self) -> Result<()> {
-      self.execute_query(sqlx::query("ROLLBACK")).await?;
-      Ok(())
+      match self.execute_query(sqlx::query("ROLLBACK")).await {
+         Ok(_) => Ok(()),
+         Err(Error::Sqlx(sqlx::Error::Database(e)))
+            if e.message().contains("no transaction is active") =>
+         {
+            Ok(())
+         }
+         Err(err) => Err(err),
+      }
    }
 
    /// Detach all attached databases if this is an attached writer
@@ -92,13 +225,67 @@ impl From<WriterGuard> for TransactionWriter {
    }
 }
 
+/// Progress of an interruptible transaction, returned by `heartbeat()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionStats {
+   /// Statements run in the transaction so far
+   pub statements_executed: u64,
+   /// Milliseconds since the transaction began
+   pub elapsed_ms: u64,
+}
+
+/// Snapshot of an active interruptible transaction, from
+/// `ActiveInterruptibleTransactions::list()`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptibleTransactionInfo {
+   pub db_path: String,
+   /// First characters of the transaction ID, enough to tell transactions
+   /// apart without handing out a usable token
+   pub token_prefix: String,
+   /// Milliseconds since the transaction began
+   pub age_ms: u64,
+   pub statements_executed: u64,
+}
+
+/// Snapshot of an in-flight regular transaction, from
+/// `ActiveRegularTransactions::list()`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegularTransactionInfo {
+   /// Key the transaction was tracked under
+   pub key: String,
+   /// Milliseconds since the transaction was tracked
+   pub age_ms: u64,
+}
+
+/// Length of `InterruptibleTransactionInfo::token_prefix`
+const TOKEN_PREFIX_LEN: usize = 8;
+
 /// Active transaction state holding the writer and metadata
 #[must_use = "if unused, the transaction is immediately rolled back"]
 pub struct ActiveInterruptibleTransaction {
    db_path: String,
    transaction_id: String,
    writer: Option<TransactionWriter>,
+   /// Names of the active savepoints, innermost last
+   savepoints: Vec<String>,
+   /// Reservation from `ActiveInterruptibleTransactions::reserve()`, released
+   /// when the transaction ends
+   slot: Option<TransactionSlot>,
+   /// Run by `commit()` before `COMMIT`
+   pre_commit_hooks: Vec<PreCommitHook>,
+   /// How `read()` and `fetch_one()` convert values to JSON
+   decode_options: DecodeOptions,
+   /// Statements run through `read()`, `fetch_one()` and `continue_with()`
+   statements_executed: u64,
    created_at: Instant,
+   /// When the timeout starts counting from; reset by `heartbeat()`
+   last_heartbeat: Instant,
+   /// Dropped with the transaction, so `ActiveInterruptibleTransactions` can
+   /// tell whether one it handed out is still running
+   alive: Arc<()>,
    // Captured at construction so Drop can always spawn the rollback task on a
    // valid runtime, even when the struct is dropped from a thread that has no
    // tokio thread-local (e.g., Tauri teardown on the main thread). Without a
@@ -119,11 +306,37 @@ impl ActiveInterruptibleTransaction {
          db_path,
          transaction_id,
          writer: Some(writer),
+         savepoints: Vec::new(),
+         slot: None,
+         pre_commit_hooks: Vec::new(),
+         decode_options: DecodeOptions::default(),
+         statements_executed: 0,
          created_at: Instant::now(),
+         last_heartbeat: Instant::now(),
+         alive: Arc::new(()),
          runtime_handle: tokio::runtime::Handle::current(),
       }
    }
 
+   /// Hold a slot reserved with `ActiveInterruptibleTransactions::reserve()`
+   /// until this transaction commits, rolls back or is dropped.
+   pub fn with_slot(mut self, slot: TransactionSlot) -> Self {
+      self.slot = Some(slot);
+      self
+   }
+
+   /// Run `hooks` on commit, rolling back instead if one returns an error
+   pub fn with_pre_commit_hooks(mut self, hooks: Vec<PreCommitHook>) -> Self {
+      self.pre_commit_hooks = hooks;
+      self
+   }
+
+   /// Decode the rows of `read()` and `fetch_one()` with `options`
+   pub fn with_decode_options(mut self, options: DecodeOptions) -> Self {
+      self.decode_options = options;
+      self
+   }
+
    fn writer_mut(&mut self) -> Result<&mut TransactionWriter> {
       self
          .writer
@@ -143,31 +356,73 @@ impl ActiveInterruptibleTransaction {
       &self.transaction_id
    }
 
+   fn info(&self) -> InterruptibleTransactionInfo {
+      let stats = self.stats();
+      InterruptibleTransactionInfo {
+         db_path: self.db_path.clone(),
+         token_prefix: self.transaction_id.chars().take(TOKEN_PREFIX_LEN).collect(),
+         age_ms: stats.elapsed_ms,
+         statements_executed: stats.statements_executed,
+      }
+   }
+
+   /// Statements executed so far and time since the transaction began
+   pub fn stats(&self) -> TransactionStats {
+      TransactionStats {
+         statements_executed: self.statements_executed,
+         elapsed_ms: self.created_at.elapsed().as_millis() as u64,
+      }
+   }
+
+   /// Restart the inactivity timeout, keeping a long but live transaction
+   /// from being rolled back by `ActiveInterruptibleTransactions`
+   pub fn heartbeat(&mut self) -> TransactionStats {
+      self.last_heartbeat = Instant::now();
+      self.stats()
+   }
+
+   fn idle_time(&self) -> Duration {
+      self.last_heartbeat.elapsed()
+   }
+
    /// Execute a read query within this transaction and return decoded results
    pub async fn read(
       &mut self,
       query: String,
       values: Vec<JsonValue>,
    ) -> Result<Vec<IndexMap<String, JsonValue>>> {
+      let query = expand_in_lists(query);
       let mut q = sqlx::query(&query);
       for value in values {
          q = crate::wrapper::bind_value(q, value);
       }
 
-      let rows = self.writer_mut()?.fetch_all(q).await?;
+      // Borrow the field directly so the decode options stay readable
+      let writer = self
+         .writer
+         .as_mut()
+         .ok_or(Error::TransactionAlreadyFinalized)?;
+      let types = DeclaredTypes::read(writer.connection(), &query, &self.decode_options).await?;
+      let rows = writer.fetch_all(q).await?;
+      self.statements_executed += 1;
+
+      decode_rows(&rows, &self.decode_options, &types)
+   }
 
-      let mut results = Vec::new();
-      for row in rows {
-         let mut value = IndexMap::default();
-         for (i, column) in row.columns().iter().enumerate() {
-            let v = row.try_get_raw(i)?;
-            let v = crate::decode::to_json(v)?;
-            value.insert(column.name().to_string(), v);
-         }
-         results.push(value);
+   /// Execute a read query within this transaction expecting zero or one row
+   ///
+   /// Returns [`Error::MultipleRowsReturned`] if more than one row matches,
+   /// leaving the transaction open.
+   pub async fn fetch_one(
+      &mut self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Result<Option<IndexMap<String, JsonValue>>> {
+      let mut rows = self.read(query, values).await?;
+      match rows.len() {
+         0 | 1 => Ok(rows.pop()),
+         count => Err(Error::MultipleRowsReturned(count)),
       }
-
-      Ok(results)
    }
 
    /// Continue transaction with additional statements
@@ -178,14 +433,19 @@ impl ActiveInterruptibleTransaction {
       statements: I,
    ) -> Result<Vec<WriteQueryResult>> {
       let mut results = Vec::new();
-      let writer = self.writer_mut()?;
+      let writer = self
+         .writer
+         .as_mut()
+         .ok_or(Error::TransactionAlreadyFinalized)?;
       for statement in statements {
          let statement = statement.into();
-         let mut q = sqlx::query(&statement.query);
+         let query = expand_in_lists(statement.query);
+         let mut q = sqlx::query(&query);
          for value in statement.values {
             q = crate::wrapper::bind_value(q, value);
          }
          let exec_result = writer.execute_query(q).await?;
+         self.statements_executed += 1;
          results.push(WriteQueryResult {
             rows_affected: exec_result.rows_affected(),
             last_insert_id: exec_result.last_insert_rowid(),
@@ -194,9 +454,74 @@ impl ActiveInterruptibleTransaction {
       Ok(results)
    }
 
+   /// Names of the active savepoints, outermost first
+   pub fn savepoints(&self) -> &[String] {
+      &self.savepoints
+   }
+
+   /// Mark a savepoint that `rollback_to(name)` can undo the transaction back
+   /// to.
+   ///
+   /// `name` must be a plain identifier. Reusing the name of an active
+   /// savepoint shadows it until the newer one is released.
+   pub async fn savepoint(&mut self, name: &str) -> Result<()> {
+      validate_savepoint_name(name)?;
+      let sql = format!("SAVEPOINT \"{name}\"");
+      self.writer_mut()?.execute_query(sqlx::query(&sql)).await?;
+      self.savepoints.push(name.to_string());
+      Ok(())
+   }
+
+   /// Release savepoint `name` and every savepoint created after it, keeping
+   /// their changes as part of the transaction.
+   pub async fn release(&mut self, name: &str) -> Result<()> {
+      let depth = self.savepoint_depth(name)?;
+      let sql = format!("RELEASE \"{name}\"");
+      self.writer_mut()?.execute_query(sqlx::query(&sql)).await?;
+      self.savepoints.truncate(depth);
+      Ok(())
+   }
+
+   /// Undo every change made since savepoint `name`, releasing the
+   /// savepoints created after it. `name` itself stays active, so it can be
+   /// rolled back to again.
+   pub async fn rollback_to(&mut self, name: &str) -> Result<()> {
+      let depth = self.savepoint_depth(name)?;
+      let sql = format!("ROLLBACK TO \"{name}\"");
+      self.writer_mut()?.execute_query(sqlx::query(&sql)).await?;
+      self.savepoints.truncate(depth + 1);
+      Ok(())
+   }
+
+   /// Position of the innermost active savepoint named `name`
+   fn savepoint_depth(&self, name: &str) -> Result<usize> {
+      validate_savepoint_name(name)?;
+      self
+         .savepoints
+         .iter()
+         .rposition(|active| active == name)
+         .ok_or_else(|| Error::SavepointNotFound(name.to_string()))
+   }
+
    /// Commit this transaction
+   ///
+   /// If a pre-commit hook returns an error, the transaction is rolled back
+   /// and that error returned.
    pub async fn commit(mut self) -> Result<()> {
       let mut writer = self.take_writer()?;
+
+      if let Err(err) = writer.run_pre_commit_hooks(&self.pre_commit_hooks).await {
+         writer.rollback().await?;
+         if let Err(detach_err) = writer.detach_if_attached().await {
+            tracing::error!("detach_all failed after rollback: {}", detach_err);
+         }
+         debug!(
+            "Pre-commit hook rejected transaction for db: {}",
+            self.db_path
+         );
+         return Err(err);
+      }
+
       writer.commit().await?;
 
       let db_path = self.db_path.clone();
@@ -221,6 +546,90 @@ impl ActiveInterruptibleTransaction {
    }
 }
 
+/// Savepoint names are interpolated into SQL, so only plain identifiers
+/// are accepted.
+fn validate_savepoint_name(name: &str) -> Result<()> {
+   let mut chars = name.chars();
+   let valid = chars
+      .next()
+      .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+      && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+   if valid {
+      Ok(())
+   } else {
+      Err(Error::InvalidSavepointName(name.to_string()))
+   }
+}
+
+/// A statement in `execute_transaction` that opens or closes a nested
+/// transaction, run as a savepoint within the outer one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NestingMarker {
+   /// `BEGIN [TRANSACTION]`
+   Begin,
+   /// `COMMIT [TRANSACTION]` or `END [TRANSACTION]`
+   Commit,
+   /// `ROLLBACK [TRANSACTION]`
+   Rollback,
+}
+
+impl NestingMarker {
+   /// The marker `query` is, if it's one. Case, surrounding whitespace and a
+   /// trailing semicolon are ignored; `BEGIN` with a mode such as `IMMEDIATE`
+   /// isn't a marker.
+   pub(crate) fn parse(query: &str) -> Option<Self> {
+      let query = query.trim().trim_end_matches(';').to_ascii_uppercase();
+      let mut words = query.split_ascii_whitespace();
+      let marker = match words.next()? {
+         "BEGIN" => Self::Begin,
+         "COMMIT" | "END" => Self::Commit,
+         "ROLLBACK" => Self::Rollback,
+         _ => return None,
+      };
+      match (words.next(), words.next()) {
+         (None, _) | (Some("TRANSACTION"), None) => Some(marker),
+         _ => None,
+      }
+   }
+
+   /// Statements running this marker for the nested transaction at `depth`
+   /// (the outermost nested one being 1)
+   pub(crate) fn statements(self, depth: usize) -> Vec<String> {
+      match self {
+         Self::Begin => vec![format!("SAVEPOINT nested_{depth}")],
+         Self::Commit => vec![format!("RELEASE nested_{depth}")],
+         Self::Rollback => vec![
+            format!("ROLLBACK TO nested_{depth}"),
+            format!("RELEASE nested_{depth}"),
+         ],
+      }
+   }
+}
+
+/// Check every nested transaction opened in `queries` is closed, and none is
+/// closed without being opened.
+pub(crate) fn validate_nesting<'a>(queries: impl IntoIterator<Item = &'a str>) -> Result<()> {
+   let mut depth = 0usize;
+   for (idx, query) in queries.into_iter().enumerate() {
+      match NestingMarker::parse(query) {
+         Some(NestingMarker::Begin) => depth += 1,
+         Some(_) if depth == 0 => {
+            return Err(Error::UnbalancedNesting(format!(
+               "statement {idx} closes a nested transaction that isn't open"
+            )));
+         }
+         Some(_) => depth -= 1,
+         None => {}
+      }
+   }
+   if depth > 0 {
+      return Err(Error::UnbalancedNesting(format!(
+         "{depth} nested transaction(s) left open"
+      )));
+   }
+   Ok(())
+}
+
 /// Statement in a transaction with query and bind values
 #[derive(Debug, Deserialize)]
 pub struct Statement {
@@ -303,12 +712,13 @@ impl Drop for ActiveInterruptibleTransaction {
 }
 
 /// Default transaction timeout (5 minutes).
-const DEFAULT_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(300);
+pub(crate) const DEFAULT_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Global state tracking all active interruptible transactions.
 ///
 /// Enforces one interruptible transaction per database path and applies a configurable
-/// timeout. Expired transactions are cleaned up lazily on the next `insert()` or
+/// inactivity timeout, counted from when the transaction began or last sent a
+/// `heartbeat()`. Expired transactions are cleaned up lazily on the next `insert()` or
 /// `remove()` call — no background task is needed.
 ///
 /// Uses `Mutex` rather than `RwLock` because all operations require write access,
@@ -318,9 +728,32 @@ const DEFAULT_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(300);
 #[derive(Clone)]
 pub struct ActiveInterruptibleTransactions {
    inner: Arc<Mutex<HashMap<String, ActiveInterruptibleTransaction>>>,
+   /// Transactions handed out by `remove()`, so `list()` still reports them
+   /// while they run statements
+   busy: Arc<std::sync::Mutex<HashMap<String, BusyTransaction>>>,
+   /// One single-permit semaphore per database path, queueing callers of
+   /// `reserve()` in FIFO order
+   slots: Arc<std::sync::Mutex<HashMap<String, Arc<Semaphore>>>>,
    timeout: Duration,
 }
 
+/// A database's reserved interruptible transaction slot.
+///
+/// Obtained from `ActiveInterruptibleTransactions::reserve()` and handed to
+/// the transaction with `ActiveInterruptibleTransaction::with_slot()`. The
+/// next queued caller gets the slot once this is dropped.
+pub struct TransactionSlot {
+   _permit: OwnedSemaphorePermit,
+}
+
+/// A transaction taken out by `ActiveInterruptibleTransactions::remove()`,
+/// listed until it is inserted again or dropped
+struct BusyTransaction {
+   info: InterruptibleTransactionInfo,
+   created_at: Instant,
+   alive: Weak<()>,
+}
+
 impl Default for ActiveInterruptibleTransactions {
    fn default() -> Self {
       Self::new(DEFAULT_TRANSACTION_TIMEOUT)
@@ -332,13 +765,99 @@ impl ActiveInterruptibleTransactions {
    pub fn new(timeout: Duration) -> Self {
       Self {
          inner: Arc::new(Mutex::new(HashMap::new())),
+         busy: Arc::new(std::sync::Mutex::new(HashMap::new())),
+         slots: Arc::new(std::sync::Mutex::new(HashMap::new())),
          timeout,
       }
    }
 
+   /// Reserve the database's interruptible transaction slot before beginning
+   /// a transaction.
+   ///
+   /// With `wait` of `None`, fails with `Error::TransactionAlreadyActive` if
+   /// another transaction holds the slot. Otherwise waits in FIFO order for up
+   /// to `wait` and then fails with `Error::TransactionWaitTimeout`. Either
+   /// way, a holder that has exceeded the transaction timeout is rolled back
+   /// and its slot handed over.
+   pub async fn reserve(&self, db_path: &str, wait: Option<Duration>) -> Result<TransactionSlot> {
+      let slot = {
+         let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+         Arc::clone(
+            slots
+               .entry(db_path.to_string())
+               .or_insert_with(|| Arc::new(Semaphore::new(1))),
+         )
+      };
+      let deadline = wait.map(|wait| Instant::now() + wait);
+
+      loop {
+         if let Ok(permit) = Arc::clone(&slot).try_acquire_owned() {
+            return Ok(TransactionSlot { _permit: permit });
+         }
+
+         if self.evict_expired(db_path).await {
+            continue;
+         }
+
+         let Some(deadline) = deadline else {
+            return Err(Error::TransactionAlreadyActive(db_path.to_string()));
+         };
+
+         let remaining = deadline.saturating_duration_since(Instant::now());
+         if remaining.is_zero() {
+            return Err(Error::TransactionWaitTimeout(db_path.to_string()));
+         }
+
+         // Wake up when the holder expires, if that comes first, so it can be
+         // evicted rather than left to block the queue.
+         let until_expired = self.time_until_expired(db_path).await;
+         let wait = until_expired.map_or(remaining, |d| d.min(remaining));
+
+         if let Ok(permit) = tokio::time::timeout(wait, Arc::clone(&slot).acquire_owned()).await {
+            // The semaphores are never closed, so acquiring can only time out.
+            let permit = permit.expect("transaction slot semaphore closed");
+            return Ok(TransactionSlot { _permit: permit });
+         }
+      }
+   }
+
+   /// Roll back the database's active transaction if it has expired,
+   /// returning whether one was evicted.
+   async fn evict_expired(&self, db_path: &str) -> bool {
+      let expired = {
+         let mut txs = self.inner.lock().await;
+         match txs.get(db_path) {
+            Some(tx) if tx.idle_time() >= self.timeout => txs.remove(db_path),
+            _ => None,
+         }
+      };
+
+      let Some(expired) = expired else {
+         return false;
+      };
+
+      warn!(
+         "Evicting expired transaction for db: {} (idle: {:?}, timeout: {:?})",
+         db_path,
+         expired.idle_time(),
+         self.timeout,
+      );
+      if let Err(err) = expired.rollback().await {
+         warn!("rollback of expired transaction failed (db: {db_path}): {err}");
+      }
+      true
+   }
+
+   async fn time_until_expired(&self, db_path: &str) -> Option<Duration> {
+      let txs = self.inner.lock().await;
+      txs.get(db_path)
+         .map(|tx| self.timeout.saturating_sub(tx.idle_time()))
+   }
+
    pub async fn insert(&self, db_path: String, tx: ActiveInterruptibleTransaction) -> Result<()> {
       use std::collections::hash_map::Entry;
       let mut txs = self.inner.lock().await;
+      self.busy_entries().remove(&db_path);
 
       match txs.entry(db_path.clone()) {
          Entry::Vacant(e) => {
@@ -350,11 +869,11 @@ impl ActiveInterruptibleTransactions {
             // with the new one. We rollback explicitly (rather than relying on
             // Drop) so the writer is guaranteed to return to the pool clean
             // before the caller tries to start a new transaction on it.
-            if e.get().created_at.elapsed() >= self.timeout {
+            if e.get().idle_time() >= self.timeout {
                warn!(
-                  "Evicting expired transaction for db: {} (age: {:?}, timeout: {:?})",
+                  "Evicting expired transaction for db: {} (idle: {:?}, timeout: {:?})",
                   db_path,
-                  e.get().created_at.elapsed(),
+                  e.get().idle_time(),
                   self.timeout,
                );
                let expired = e.insert(tx);
@@ -369,6 +888,68 @@ impl ActiveInterruptibleTransactions {
       }
    }
 
+   /// Restart a transaction's inactivity timeout and return its stats.
+   ///
+   /// Fails like `remove()` if the token is wrong or the transaction has
+   /// already timed out.
+   pub async fn heartbeat(&self, db_path: &str, token_id: &str) -> Result<TransactionStats> {
+      let mut tx = self.remove(db_path, token_id).await?;
+      let stats = tx.heartbeat();
+      self.insert(db_path.to_string(), tx).await?;
+      Ok(stats)
+   }
+
+   /// Snapshot the active transactions, sorted by database path.
+   ///
+   /// A transaction that is mid-statement is listed with the statement count
+   /// it had when the statement started.
+   pub async fn list(&self) -> Vec<InterruptibleTransactionInfo> {
+      let txs = self.inner.lock().await;
+      let mut list: Vec<_> = txs.values().map(|tx| tx.info()).collect();
+
+      let mut busy = self.busy_entries();
+      busy.retain(|_, tx| tx.alive.strong_count() > 0);
+      list.extend(
+         busy
+            .iter()
+            .filter(|(db_path, _)| !txs.contains_key(*db_path))
+            .map(|(_, tx)| InterruptibleTransactionInfo {
+               age_ms: tx.created_at.elapsed().as_millis() as u64,
+               ..tx.info.clone()
+            }),
+      );
+
+      list.sort_by(|a, b| a.db_path.cmp(&b.db_path));
+      list
+   }
+
+   fn busy_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, BusyTransaction>> {
+      self.busy.lock().unwrap_or_else(|e| e.into_inner())
+   }
+
+   /// Roll back and remove a single transaction.
+   ///
+   /// Returns `Ok(false)`, leaving the database untouched, if it has no
+   /// active transaction or its transaction has a different ID, e.g. because
+   /// the one the token belonged to already committed and another began. A
+   /// transaction that has timed out is still rolled back rather than reported
+   /// as `Error::TransactionTimedOut`.
+   pub async fn abort(&self, db_path: &str, token_id: &str) -> Result<bool> {
+      let mut txs = self.inner.lock().await;
+
+      if txs.get(db_path).map(|tx| tx.transaction_id()) != Some(token_id) {
+         return Ok(false);
+      }
+
+      // Safe unwrap: we just confirmed the key exists above.
+      let tx = txs.remove(db_path).unwrap();
+      drop(txs);
+
+      debug!("Aborting interruptible transaction for database: {db_path}");
+      tx.rollback().await?;
+      Ok(true)
+   }
+
    pub async fn abort_all(&self) {
       // Drain under the lock, then release it before awaiting rollbacks so we
       // don't hold the mutex across a chain of awaits.
@@ -409,8 +990,17 @@ impl ActiveInterruptibleTransactions {
          return Err(Error::InvalidTransactionToken);
       }
 
-      // Happy path: not expired, hand it back to the caller.
-      if tx.created_at.elapsed() < self.timeout {
+      // Happy path: not expired, hand it back to the caller, remembering it
+      // for `list()` until it's inserted again or dropped.
+      if tx.idle_time() < self.timeout {
+         self.busy_entries().insert(
+            db_path.to_string(),
+            BusyTransaction {
+               info: tx.info(),
+               created_at: tx.created_at,
+               alive: Arc::downgrade(&tx.alive),
+            },
+         );
          // Safe unwrap: we just confirmed the key exists above.
          return Ok(txs.remove(db_path).unwrap());
       }
@@ -418,9 +1008,9 @@ impl ActiveInterruptibleTransactions {
       // Expired: take it out, release the lock, then rollback without holding
       // it so other callers aren't blocked on an unrelated cleanup.
       warn!(
-         "Transaction timed out for db: {} (age: {:?}, timeout: {:?})",
+         "Transaction timed out for db: {} (idle: {:?}, timeout: {:?})",
          db_path,
-         tx.created_at.elapsed(),
+         tx.idle_time(),
          self.timeout,
       );
       let expired = txs.remove(db_path).unwrap();
@@ -437,12 +1027,37 @@ impl ActiveInterruptibleTransactions {
 ///
 /// Holds abort handles so transactions can be cancelled on app exit.
 #[derive(Clone, Default)]
-pub struct ActiveRegularTransactions(Arc<RwLock<HashMap<String, AbortHandle>>>);
+pub struct ActiveRegularTransactions(Arc<RwLock<HashMap<String, RegularTransaction>>>);
+
+struct RegularTransaction {
+   abort_handle: AbortHandle,
+   started_at: Instant,
+}
 
 impl ActiveRegularTransactions {
    pub async fn insert(&self, key: String, abort_handle: AbortHandle) {
       let mut txs = self.0.write().await;
-      txs.insert(key, abort_handle);
+      txs.insert(
+         key,
+         RegularTransaction {
+            abort_handle,
+            started_at: Instant::now(),
+         },
+      );
+   }
+
+   /// Snapshot the in-flight transactions, oldest first
+   pub async fn list(&self) -> Vec<RegularTransactionInfo> {
+      let txs = self.0.read().await;
+      let mut list: Vec<_> = txs
+         .iter()
+         .map(|(key, tx)| RegularTransactionInfo {
+            key: key.clone(),
+            age_ms: tx.started_at.elapsed().as_millis() as u64,
+         })
+         .collect();
+      list.sort_by(|a, b| b.age_ms.cmp(&a.age_ms));
+      list
    }
 
    pub async fn remove(&self, key: &str) {
@@ -454,9 +1069,9 @@ impl ActiveRegularTransactions {
       let mut txs = self.0.write().await;
       debug!("Aborting {} active regular transaction(s)", txs.len());
 
-      for (key, abort_handle) in txs.iter() {
+      for (key, tx) in txs.iter() {
          debug!("Aborting regular transaction: {}", key);
-         abort_handle.abort();
+         tx.abort_handle.abort();
       }
 
       txs.clear();
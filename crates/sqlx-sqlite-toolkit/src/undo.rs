@@ -0,0 +1,293 @@
+//! Undo/redo of committed changes, built on the observer's captured values
+
+use sqlx::Row;
+use sqlx::sqlite::SqliteArguments;
+use sqlx_sqlite_observer::{
+   ChangeOperation, ColumnValue, ObservableSqliteDatabase, TableChange, TransactionChangeSet,
+};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use crate::Error;
+use crate::pagination::quote_identifier;
+use crate::transactions::TransactionWriter;
+use crate::wrapper::DatabaseWrapper;
+
+/// Number of commits an `UndoManager` remembers unless told otherwise
+const DEFAULT_UNDO_LIMIT: usize = 100;
+
+/// Undo and redo history for commits to a set of tables.
+///
+/// Created with [`DatabaseWrapper::undo_manager`]. Every commit that changes
+/// one of the tables is recorded with the values it replaced, and
+/// [`undo`](Self::undo) writes them back through the writer in a transaction
+/// of its own. That transaction is recorded in turn, so [`redo`](Self::redo)
+/// can reapply what was undone. A new commit to the tables clears the redo
+/// history.
+///
+/// Commits are picked up from the observer when the manager is next used, so
+/// only those made while it exists are recorded, and only changes to its
+/// tables are undone. If the observer drops notifications because the
+/// manager wasn't used for a long time, the history is cleared rather than
+/// left with gaps.
+pub struct UndoManager {
+   db: DatabaseWrapper,
+   tables: Vec<String>,
+   rx: broadcast::Receiver<TransactionChangeSet>,
+   /// Changes of each undoable commit, oldest first
+   undo_stack: Vec<Vec<TableChange>>,
+   /// Changes of each undo that can be redone, oldest first
+   redo_stack: Vec<Vec<TableChange>>,
+   limit: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+   Undo,
+   Redo,
+}
+
+impl UndoManager {
+   pub(crate) fn new(
+      db: DatabaseWrapper,
+      observable: &ObservableSqliteDatabase,
+      tables: Vec<String>,
+   ) -> Self {
+      let rx = observable.subscribe_transactions(tables.iter().cloned());
+      Self {
+         db,
+         tables,
+         rx,
+         undo_stack: Vec::new(),
+         redo_stack: Vec::new(),
+         limit: DEFAULT_UNDO_LIMIT,
+      }
+   }
+
+   /// Remember at most `limit` commits, forgetting the oldest beyond that
+   ///
+   /// Defaults to 100.
+   pub fn with_limit(mut self, limit: usize) -> Self {
+      self.limit = limit;
+      self
+   }
+
+   /// Whether there is a commit to undo
+   pub fn can_undo(&mut self) -> bool {
+      self.record_commits();
+      !self.undo_stack.is_empty()
+   }
+
+   /// Whether there is an undone commit to redo
+   pub fn can_redo(&mut self) -> bool {
+      self.record_commits();
+      !self.redo_stack.is_empty()
+   }
+
+   /// Forget all undo and redo history
+   pub fn clear(&mut self) {
+      self.record_commits();
+      self.undo_stack.clear();
+      self.redo_stack.clear();
+   }
+
+   /// Revert the most recent recorded commit
+   ///
+   /// Returns `false` if there was nothing to undo. If the revert fails, for
+   /// example because a later change the manager doesn't track conflicts
+   /// with it, it is rolled back and the commit stays undoable.
+   pub async fn undo(&mut self) -> Result<bool, Error> {
+      self.apply(Direction::Undo).await
+   }
+
+   /// Reapply the most recently undone commit
+   ///
+   /// Returns `false` if there was nothing to redo.
+   pub async fn redo(&mut self) -> Result<bool, Error> {
+      self.apply(Direction::Redo).await
+   }
+
+   async fn apply(&mut self, direction: Direction) -> Result<bool, Error> {
+      // Holding the writer means every earlier commit has been published
+      // and no other can slip in before ours
+      let mut writer = TransactionWriter::from(self.db.acquire_writer().await?);
+      self.record_commits();
+
+      let stack = match direction {
+         Direction::Undo => &mut self.undo_stack,
+         Direction::Redo => &mut self.redo_stack,
+      };
+      let Some(changes) = stack.pop() else {
+         return Ok(false);
+      };
+
+      writer.begin_immediate().await?;
+      let result = async {
+         for change in changes.iter().rev() {
+            revert(&mut writer, change).await?;
+         }
+         writer.commit().await
+      }
+      .await;
+      if let Err(err) = result {
+         if let Err(rollback_err) = writer.rollback().await {
+            tracing::error!("rollback failed after undo error: {}", rollback_err);
+         }
+         stack.push(changes);
+         return Err(err);
+      }
+
+      // Commits are published before COMMIT returns, so ours is waiting
+      let reverted = match self.rx.try_recv() {
+         Ok(set) => scoped(&self.tables, set),
+         Err(_) => Vec::new(),
+      };
+      tracing::debug!(changes = changes.len(), "Reverted commit");
+      if !reverted.is_empty() {
+         let stack = match direction {
+            Direction::Undo => &mut self.redo_stack,
+            Direction::Redo => &mut self.undo_stack,
+         };
+         push_limited(stack, reverted, self.limit);
+      }
+      Ok(true)
+   }
+
+   /// Add commits published since the last call to the undo history
+   fn record_commits(&mut self) {
+      loop {
+         match self.rx.try_recv() {
+            Ok(set) => {
+               let changes = scoped(&self.tables, set);
+               if !changes.is_empty() {
+                  push_limited(&mut self.undo_stack, changes, self.limit);
+                  self.redo_stack.clear();
+               }
+            }
+            Err(TryRecvError::Lagged(missed)) => {
+               tracing::warn!(missed, "Undo history missed commits; clearing it");
+               self.undo_stack.clear();
+               self.redo_stack.clear();
+            }
+            Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+         }
+      }
+   }
+}
+
+/// The changes in `set` to `tables`, or to any table if `tables` is empty
+fn scoped(tables: &[String], set: TransactionChangeSet) -> Vec<TableChange> {
+   set.changes
+      .into_iter()
+      .filter(|change| tables.is_empty() || tables.contains(&change.table))
+      .collect()
+}
+
+fn push_limited(stack: &mut Vec<Vec<TableChange>>, changes: Vec<TableChange>, limit: usize) {
+   stack.push(changes);
+   if stack.len() > limit {
+      stack.drain(..stack.len() - limit);
+   }
+}
+
+/// Write the inverse of `change`
+async fn revert(writer: &mut TransactionWriter, change: &TableChange) -> Result<(), Error> {
+   let missing = || {
+      Error::Other(format!(
+         "cannot undo change to '{}': its column values were not captured",
+         change.table
+      ))
+   };
+   if change.columns.is_empty() {
+      return Err(missing());
+   }
+
+   // Rows of rowid tables are found by rowid, others by primary key
+   let (key_columns, key_values) = match change.rowid {
+      Some(rowid) => (vec!["rowid".to_string()], vec![ColumnValue::Integer(rowid)]),
+      None => {
+         let rows = writer
+            .fetch_all(
+               sqlx::query("SELECT name FROM pragma_table_info(?) WHERE pk > 0 ORDER BY pk")
+                  .bind(&change.table),
+            )
+            .await?;
+         let names = rows.iter().map(|row| row.get("name")).collect();
+         (names, change.primary_key.clone())
+      }
+   };
+   let table = quote_identifier(&change.table);
+   let key = key_columns
+      .iter()
+      .map(|column| format!("{} = ?", quote_identifier(column)))
+      .collect::<Vec<_>>()
+      .join(" AND ");
+
+   let (sql, values) = match change.operation {
+      Some(ChangeOperation::Insert) => (format!("DELETE FROM {} WHERE {}", table, key), key_values),
+      Some(ChangeOperation::Delete) => {
+         let old = change.old_values.as_ref().ok_or_else(missing)?;
+         let mut columns: Vec<String> = change
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect();
+         let mut values = old.clone();
+         // Restore the rowid too, which isn't a column unless aliased
+         if let Some(rowid) = change.rowid {
+            columns.push("rowid".to_string());
+            values.push(ColumnValue::Integer(rowid));
+         }
+         let placeholders = vec!["?"; columns.len()].join(", ");
+         (
+            format!(
+               "INSERT INTO {} ({}) VALUES ({})",
+               table,
+               columns.join(", "),
+               placeholders
+            ),
+            values,
+         )
+      }
+      Some(ChangeOperation::Update) => {
+         let old = change.old_values.as_ref().ok_or_else(missing)?;
+         let assignments = change
+            .columns
+            .iter()
+            .map(|column| format!("{} = ?", quote_identifier(column)))
+            .collect::<Vec<_>>()
+            .join(", ");
+         let mut values = old.clone();
+         values.extend(key_values);
+         (
+            format!("UPDATE {} SET {} WHERE {}", table, assignments, key),
+            values,
+         )
+      }
+      None => {
+         return Err(Error::Other(format!(
+            "cannot undo change to '{}': its operation is unknown",
+            change.table
+         )));
+      }
+   };
+
+   let mut q = sqlx::query(&sql);
+   for value in values {
+      q = bind_column_value(q, value);
+   }
+   writer.execute_query(q).await?;
+   Ok(())
+}
+
+fn bind_column_value<'a>(
+   query: sqlx::query::Query<'a, sqlx::Sqlite, SqliteArguments<'a>>,
+   value: ColumnValue,
+) -> sqlx::query::Query<'a, sqlx::Sqlite, SqliteArguments<'a>> {
+   match value {
+      ColumnValue::Null => query.bind(None::<i64>),
+      ColumnValue::Integer(i) => query.bind(i),
+      ColumnValue::Real(r) => query.bind(r),
+      ColumnValue::Text(s) => query.bind(s),
+      ColumnValue::Blob(b) => query.bind(b),
+   }
+}
@@ -0,0 +1,199 @@
+//! Live queries that re-run when the tables they read change
+
+use std::future::Future;
+use std::time::Duration;
+
+use futures::FutureExt;
+use futures::future::{self, Either};
+use futures::stream::{self, BoxStream, StreamExt};
+use indexmap::IndexMap;
+use serde_json::Value as JsonValue;
+use sqlx::Row;
+use sqlx_sqlite_observer::{ObservableSqliteDatabase, TableChangeStream};
+use tokio::sync::mpsc;
+
+use crate::Error;
+use crate::binds::expand_in_lists;
+use crate::builders::FetchPageBuilder;
+use crate::pagination::KeysetPage;
+use crate::wrapper::{DatabaseWrapper, bind_value};
+
+/// Number of results a watch buffers ahead of the consumer
+const WATCH_BUFFER: usize = 4;
+
+type Rows = Vec<IndexMap<String, JsonValue>>;
+
+/// Stream of a watched query's results, returned by
+/// [`DatabaseWrapper::watch_query`]
+pub type QueryWatch = BoxStream<'static, Result<Rows, Error>>;
+
+/// Stream of a watched page, returned by [`DatabaseWrapper::watch_page`]
+pub type PageWatch = BoxStream<'static, Result<KeysetPage, Error>>;
+
+/// Stream the results of `query` now and again whenever a table it reads
+/// changes, skipping re-runs whose results didn't change
+///
+/// The watch runs on a background task that starts on the first poll;
+/// dropping the stream stops it. An error ends the stream after being
+/// yielded.
+pub(crate) fn watch(
+   db: DatabaseWrapper,
+   observable: ObservableSqliteDatabase,
+   query: String,
+   values: Vec<JsonValue>,
+) -> QueryWatch {
+   let fetch = {
+      let db = db.clone();
+      let (query, values) = (query.clone(), values.clone());
+      move || db.fetch_all(query.clone(), values.clone()).execute()
+   };
+   spawn_watch(db, observable, query, values, Duration::ZERO, fetch)
+}
+
+/// Like [`watch`], for the page `page` fetches, re-fetched `debounce` after
+/// the first of a burst of changes
+pub(crate) fn watch_page(
+   db: DatabaseWrapper,
+   observable: ObservableSqliteDatabase,
+   page: FetchPageBuilder,
+   debounce: Duration,
+) -> PageWatch {
+   let (query, values) = page.query_and_values();
+   let (query, values) = (query.to_string(), values.to_vec());
+   spawn_watch(db, observable, query, values, debounce, move || {
+      page.clone().execute()
+   })
+}
+
+fn spawn_watch<T, F, Fut>(
+   db: DatabaseWrapper,
+   observable: ObservableSqliteDatabase,
+   query: String,
+   values: Vec<JsonValue>,
+   debounce: Duration,
+   fetch: F,
+) -> BoxStream<'static, Result<T, Error>>
+where
+   T: PartialEq + Clone + Send + 'static,
+   F: Fn() -> Fut + Send + 'static,
+   Fut: Future<Output = Result<T, Error>> + Send,
+{
+   stream::once(async move {
+      let (tx, rx) = mpsc::channel(WATCH_BUFFER);
+      tokio::spawn(async move {
+         if let Err(err) = run(db, observable, query, values, debounce, fetch, &tx).await {
+            let _ = tx.send(Err(err)).await;
+         }
+      });
+      rx
+   })
+   .flat_map(|rx| {
+      stream::unfold(rx, |mut rx| async move {
+         rx.recv().await.map(|result| (result, rx))
+      })
+   })
+   .boxed()
+}
+
+async fn run<T, F, Fut>(
+   db: DatabaseWrapper,
+   observable: ObservableSqliteDatabase,
+   query: String,
+   values: Vec<JsonValue>,
+   debounce: Duration,
+   fetch: F,
+   tx: &mpsc::Sender<Result<T, Error>>,
+) -> Result<(), Error>
+where
+   T: PartialEq + Clone,
+   F: Fn() -> Fut,
+   Fut: Future<Output = Result<T, Error>>,
+{
+   let tables = referenced_tables(&db, &query, &values).await?;
+   tracing::debug!(tables = ?tables, "Watching query");
+
+   // Subscribe before the first run so no change after it is missed
+   let mut changes = (!tables.is_empty()).then(|| observable.subscribe_stream(tables));
+
+   let mut last = fetch().await?;
+   if tx.send(Ok(last.clone())).await.is_err() {
+      return Ok(());
+   }
+
+   let Some(changes) = changes.as_mut() else {
+      // Reads no tables, so the results can never change
+      return Ok(());
+   };
+   loop {
+      // Stop as soon as the stream is dropped rather than at the next change
+      let changed = std::pin::pin!(wait_for_changes(changes, debounce));
+      match future::select(changed, std::pin::pin!(tx.closed())).await {
+         Either::Left((true, _)) => {}
+         _ => break,
+      }
+
+      let result = fetch().await?;
+      if result == last {
+         continue;
+      }
+      last = result;
+      if tx.send(Ok(last.clone())).await.is_err() {
+         break;
+      }
+   }
+   Ok(())
+}
+
+/// Wait for a change (or lag) notification, then `debounce`, then drain any
+/// others already queued so a burst of commits causes one re-run
+///
+/// Returns `false` once observation has stopped.
+async fn wait_for_changes(changes: &mut TableChangeStream, debounce: Duration) -> bool {
+   if changes.next().await.is_none() {
+      return false;
+   }
+   if !debounce.is_zero() {
+      tokio::time::sleep(debounce).await;
+   }
+   while let Some(next) = changes.next().now_or_never() {
+      if next.is_none() {
+         return false;
+      }
+   }
+   true
+}
+
+/// Names of the main database's tables that `query` reads, found from the
+/// cursors its compiled program opens
+async fn referenced_tables(
+   db: &DatabaseWrapper,
+   query: &str,
+   values: &[JsonValue],
+) -> Result<Vec<String>, Error> {
+   let explain = format!("EXPLAIN {}", expand_in_lists(query.to_string()));
+   let mut q = sqlx::query(&explain);
+   for value in values {
+      q = bind_value(q, value.clone());
+   }
+   let program = q.fetch_all(db.inner().read_pool()?).await?;
+
+   // OpenRead's p2 is the root page of the table or index read and p3 the
+   // database it's in, 0 being main
+   let root_pages: Vec<i64> = program
+      .iter()
+      .filter(|op| op.get::<String, _>("opcode") == "OpenRead" && op.get::<i64, _>("p3") == 0)
+      .map(|op| op.get("p2"))
+      .collect();
+   if root_pages.is_empty() {
+      return Ok(Vec::new());
+   }
+
+   let tables = sqlx::query_scalar(
+      "SELECT DISTINCT tbl_name FROM sqlite_schema
+       WHERE rootpage IN (SELECT value FROM json_each(?)) ORDER BY tbl_name",
+   )
+   .bind(JsonValue::from(root_pages).to_string())
+   .fetch_all(db.inner().read_pool()?)
+   .await?;
+   Ok(tables)
+}
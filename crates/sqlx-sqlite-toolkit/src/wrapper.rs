@@ -1,18 +1,29 @@
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::Duration;
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
-use sqlx::sqlite::SqliteConnection;
-use sqlx_sqlite_conn_mgr::{SqliteDatabase, SqliteDatabaseConfig, WriteGuard};
+use sqlx::sqlite::{SqliteConnection, SqliteValueRef};
+use sqlx_sqlite_conn_mgr::{InterruptHandle, SqliteDatabase, SqliteDatabaseConfig, WriteGuard};
 
 #[cfg(feature = "observer")]
 use sqlx_sqlite_observer::{ObservableSqliteDatabase, ObservableWriteGuard, ObserverConfig};
 
 use crate::Error;
+use crate::binds::expand_in_lists;
+use crate::decode::{DecodeOptions, DecoderTarget};
+use crate::retry::RetryPolicy;
+use crate::timeout::QueryDeadline;
+use crate::transactions::PreCommitHook;
 
 /// Result returned from write operations (e.g. INSERT, UPDATE, DELETE).
+///
+/// Serializes as `{ rowsAffected, lastInsertId }`, matching the frontend type.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WriteQueryResult {
    /// The number of rows affected by the write operation.
    pub rows_affected: u64,
@@ -23,6 +34,78 @@ pub struct WriteQueryResult {
    pub last_insert_id: i64,
 }
 
+/// Result returned from write operations with a `RETURNING` clause.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteReturningResult {
+   /// The number of rows affected by the write operation.
+   pub rows_affected: u64,
+   /// The last inserted row ID (SQLite ROWID), as in [`WriteQueryResult`].
+   pub last_insert_id: i64,
+   /// The decoded rows produced by the `RETURNING` clause.
+   pub rows: Vec<IndexMap<String, JsonValue>>,
+}
+
+/// Query results in columnar form: column names once, then each row's values
+/// in column order.
+///
+/// Much smaller than per-row maps for wide result sets, since column names
+/// aren't repeated for every row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnarRows {
+   /// Result column names, in query order.
+   pub columns: Vec<String>,
+   /// One entry per row, with values in the same order as `columns`.
+   pub rows: Vec<Vec<JsonValue>>,
+   /// Columns holding TEXT that wasn't valid UTF-8 and was decoded with
+   /// [`InvalidUtf8Format::Lossy`](crate::InvalidUtf8Format::Lossy).
+   #[serde(default)]
+   pub lossy_columns: Vec<String>,
+}
+
+/// Query results along with the type of each result column, so callers can
+/// tell dates, booleans and the like apart without introspecting the schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowsWithColumnTypes {
+   /// Result columns, in query order.
+   pub columns: Vec<ColumnType>,
+   /// The decoded rows.
+   pub rows: Vec<IndexMap<String, JsonValue>>,
+   /// Columns holding TEXT that wasn't valid UTF-8 and was decoded with
+   /// [`InvalidUtf8Format::Lossy`](crate::InvalidUtf8Format::Lossy).
+   #[serde(default)]
+   pub lossy_columns: Vec<String>,
+}
+
+/// A result column's name and type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnType {
+   /// Column name (or alias), as the rows are keyed by it.
+   pub name: String,
+   /// Declared type as written in the table's schema, e.g. `VARCHAR(80)`.
+   /// `None` for columns that aren't a table column, such as expressions.
+   pub declared_type: Option<String>,
+   /// SQLite type affinity of the declared type: `INTEGER`, `TEXT`, `BLOB`,
+   /// `REAL` or `NUMERIC`. Columns without a declared type have `BLOB`
+   /// affinity, as in SQLite.
+   pub affinity: String,
+}
+
+impl ColumnType {
+   pub(crate) fn new(
+      column: &crate::describe::DescribedColumn,
+      options: &crate::decode::DecodeOptions,
+   ) -> Self {
+      Self {
+         name: options.column_name_format().apply(&column.name),
+         declared_type: column.declared_type.clone(),
+         affinity: crate::decode::affinity(column.declared_type.as_deref()).to_string(),
+      }
+   }
+}
+
 /// Unified writer guard that routes through observer when enabled.
 ///
 /// Derefs to `SqliteConnection` so it can be used with `sqlx::query().execute()`.
@@ -34,6 +117,17 @@ pub enum WriterGuard {
    Observable(ObservableWriteGuard),
 }
 
+impl WriterGuard {
+   /// Get a handle that can interrupt this writer's statements from another task
+   pub fn interrupt_handle(&self) -> InterruptHandle {
+      match self {
+         WriterGuard::Regular(w) => w.interrupt_handle(),
+         #[cfg(feature = "observer")]
+         WriterGuard::Observable(w) => w.interrupt_handle(),
+      }
+   }
+}
+
 impl Deref for WriterGuard {
    type Target = SqliteConnection;
 
@@ -67,6 +161,15 @@ impl DerefMut for WriterGuard {
 #[derive(Clone)]
 pub struct DatabaseWrapper {
    inner: Arc<SqliteDatabase>,
+   /// Lowercase table names mapped to the quoted column whose non-NULL value
+   /// marks a row of the table as deleted
+   soft_delete: HashMap<String, String>,
+   /// Background task coalescing plain `execute()` calls, when enabled
+   batcher: Option<Arc<crate::batch::WriteBatcher>>,
+   /// Checks run before every `execute_transaction()` and interruptible commit
+   pre_commit_hooks: Vec<PreCommitHook>,
+   /// How fetched values are converted to JSON unless a query overrides it
+   decode_options: DecodeOptions,
    #[cfg(feature = "observer")]
    observer: Option<ObservableSqliteDatabase>,
 }
@@ -135,6 +238,30 @@ impl DatabaseWrapper {
       InterruptibleTransactionBuilder::new(self.clone())
    }
 
+   /// Pin a read connection inside a `BEGIN DEFERRED` transaction so that a
+   /// series of fetches all see the database as of this moment, without
+   /// writes that land in between.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// # use sqlx_sqlite_toolkit::DatabaseWrapper;
+   /// # async fn example(db: &DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// let mut snapshot = db.begin_read_snapshot().await?;
+   ///
+   /// let orders = snapshot.fetch_all("SELECT * FROM orders".into(), vec![]).await?;
+   /// let totals = snapshot
+   ///    .fetch_one("SELECT SUM(total) AS sum FROM orders".into(), vec![])
+   ///    .await?;
+   ///
+   /// snapshot.release().await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub async fn begin_read_snapshot(&self) -> Result<crate::snapshot::ReadSnapshot, Error> {
+      crate::snapshot::ReadSnapshot::begin(&self.inner, self.decode_options.clone()).await
+   }
+
    /// Connect to a SQLite database with an absolute path.
    ///
    /// This is the core connection method. It connects to the database at the given
@@ -163,6 +290,10 @@ impl DatabaseWrapper {
 
       Ok(Self {
          inner: db,
+         soft_delete: HashMap::new(),
+         batcher: None,
+         pre_commit_hooks: Vec::new(),
+         decode_options: DecodeOptions::default(),
          #[cfg(feature = "observer")]
          observer: None,
       })
@@ -191,6 +322,132 @@ impl DatabaseWrapper {
       crate::builders::ExecuteBuilder::new(self.clone(), query, values)
    }
 
+   /// Create a builder that inserts one row into `table`.
+   ///
+   /// Column names are validated and quoted, and values are bound as
+   /// parameters. Use `.on_conflict_do_nothing()` or `.on_conflict_update(target)`
+   /// to turn the insert into an upsert.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use serde_json::json;
+   ///
+   /// let result = db.insert("users")
+   ///     .value("email", json!("alice@example.com"))
+   ///     .value("name", json!("Alice"))
+   ///     .on_conflict_update(["email"])
+   ///     .await?;
+   ///
+   /// println!("Affected {} row(s)", result.rows_affected);
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn insert(&self, table: impl Into<String>) -> crate::builders::InsertBuilder {
+      crate::builders::InsertBuilder::new(self.clone(), table.into())
+   }
+
+   /// Create a builder reading the value at a JSON `path` (e.g. `$.theme`) from a
+   /// JSON-typed TEXT column of every matching row.
+   ///
+   /// The path is bound as a parameter and the table and column names are
+   /// validated and quoted. Extracted objects, arrays and booleans are decoded as
+   /// JSON before being deserialized into `T`.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use serde_json::json;
+   ///
+   /// let themes: Vec<Option<String>> = db.json_get("users", "settings", "$.theme")
+   ///     .where_eq("id", json!(42))
+   ///     .await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn json_get<T>(
+      &self,
+      table: impl Into<String>,
+      column: impl Into<String>,
+      path: impl Into<String>,
+   ) -> crate::builders::JsonGetBuilder<T>
+   where
+      T: serde::de::DeserializeOwned + Send + 'static,
+   {
+      crate::builders::JsonGetBuilder::new(self.clone(), table.into(), column.into(), path.into())
+   }
+
+   /// Create a builder that sets values at JSON paths inside a JSON-typed TEXT
+   /// column, using SQLite's `json_set()`.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use serde_json::json;
+   ///
+   /// db.json_set("users", "settings")
+   ///     .set("$.theme", json!("dark"))
+   ///     .set("$.notifications", json!({ "email": false }))
+   ///     .where_eq("id", json!(42))
+   ///     .await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn json_set(
+      &self,
+      table: impl Into<String>,
+      column: impl Into<String>,
+   ) -> crate::builders::JsonSetBuilder {
+      crate::builders::JsonSetBuilder::new(self.clone(), table.into(), column.into())
+   }
+
+   /// Create a builder that inserts many rows into `table` in one transaction.
+   ///
+   /// Each row holds one value per entry in `columns`. Rows are split into
+   /// multi-row INSERT statements that stay under SQLite's bind parameter limit
+   /// (see [`InsertManyBuilder::max_bind_params`](crate::builders::InsertManyBuilder::max_bind_params)),
+   /// and the builder resolves to the total number of rows inserted.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use serde_json::json;
+   ///
+   /// let inserted = db.insert_many(
+   ///     "users",
+   ///     ["name", "age"],
+   ///     vec![
+   ///         vec![json!("Alice"), json!(30)],
+   ///         vec![json!("Bob"), json!(25)],
+   ///     ],
+   /// ).await?;
+   ///
+   /// assert_eq!(inserted, 2);
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn insert_many<I, S>(
+      &self,
+      table: impl Into<String>,
+      columns: I,
+      rows: Vec<Vec<JsonValue>>,
+   ) -> crate::builders::InsertManyBuilder
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      crate::builders::InsertManyBuilder::new(
+         self.clone(),
+         table.into(),
+         columns.into_iter().map(Into::into).collect(),
+         rows,
+      )
+   }
+
    /// Execute multiple statements atomically within a transaction.
    ///
    /// Returns a builder that allows attaching databases before executing the transaction.
@@ -221,6 +478,35 @@ impl DatabaseWrapper {
       TransactionExecutionBuilder::new(self.clone(), statements)
    }
 
+   /// Execute a multi-statement SQL script atomically within a transaction.
+   ///
+   /// The script is split into statements on semicolons, ignoring those inside
+   /// string literals, quoted identifiers, comments and `CREATE TRIGGER` bodies.
+   /// The statements then run in order on the writer exactly as with
+   /// [`execute_transaction`](Self::execute_transaction), so the script must not
+   /// contain its own `BEGIN`/`COMMIT`. Returns one result per statement.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// db.execute_script(
+   ///     "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);
+   ///      CREATE INDEX users_name ON users (name);
+   ///      INSERT INTO users (name) VALUES ('Alice; the first');",
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn execute_script(&self, sql: &str) -> TransactionExecutionBuilder {
+      let statements = crate::script::split_statements(sql)
+         .into_iter()
+         .map(|statement| (statement, Vec::new()))
+         .collect();
+
+      TransactionExecutionBuilder::new(self.clone(), statements)
+   }
+
    /// Create a builder for SELECT queries returning multiple rows.
    ///
    /// Returns a builder that can optionally attach databases before executing.
@@ -245,7 +531,44 @@ impl DatabaseWrapper {
       query: String,
       values: Vec<JsonValue>,
    ) -> crate::builders::FetchAllBuilder {
+      let filtered = self.without_deleted(&query);
       crate::builders::FetchAllBuilder::new(Arc::clone(&self.inner), query, values)
+         .filtered(filtered)
+         .decode_options(self.decode_options.clone())
+   }
+
+   /// Create a builder for SELECT queries whose rows are deserialized into `T`.
+   ///
+   /// Columns are matched to fields by name, so use column aliases or
+   /// `#[serde(rename)]` where they differ. A row that does not fit `T` fails
+   /// the whole query with [`Error::RowDeserialization`].
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// #[derive(serde::Deserialize)]
+   /// struct User {
+   ///     name: String,
+   ///     age: i64,
+   /// }
+   ///
+   /// let users: Vec<User> = db.fetch_all_as(
+   ///     "SELECT name, age FROM users WHERE age > ?".into(),
+   ///     vec![serde_json::json!(21)],
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn fetch_all_as<T>(
+      &self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> crate::builders::FetchAllAsBuilder<T>
+   where
+      T: serde::de::DeserializeOwned + Send + 'static,
+   {
+      crate::builders::FetchAllAsBuilder::new(self.fetch_all(query, values))
    }
 
    /// Create a builder for paginated SELECT queries using keyset (cursor-based) pagination.
@@ -306,6 +629,7 @@ impl DatabaseWrapper {
       keyset: Vec<crate::pagination::KeysetColumn>,
       page_size: usize,
    ) -> crate::builders::FetchPageBuilder {
+      let filtered = self.without_deleted(&query);
       crate::builders::FetchPageBuilder::new(
          Arc::clone(&self.inner),
          query,
@@ -313,6 +637,88 @@ impl DatabaseWrapper {
          keyset,
          page_size,
       )
+      .filtered(filtered)
+      .decode_options(self.decode_options.clone())
+   }
+
+   /// Create a builder for a ranked full-text search over `index`.
+   ///
+   /// `query` uses FTS5 query syntax and is bound as a parameter. Matches are
+   /// ordered by bm25 rank, best first, and paginated by rank and rowid; pass
+   /// a page's `next_cursor` to `.after()` for the next one.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use sqlx_sqlite_toolkit::FtsIndex;
+   ///
+   /// let index = FtsIndex::new("posts", ["title", "body"]);
+   /// let page = db.search(&index, "rust", 20)
+   ///    .highlight("title")
+   ///    .snippet("body", 16)
+   ///    .await?;
+   ///
+   /// if let Some(cursor) = page.next_cursor {
+   ///    let next = db.search(&index, "rust", 20).after(cursor).await?;
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn search(
+      &self,
+      index: &crate::fts::FtsIndex,
+      query: impl Into<String>,
+      page_size: usize,
+   ) -> crate::builders::SearchBuilder {
+      crate::builders::SearchBuilder::new(
+         Arc::clone(&self.inner),
+         index.clone(),
+         query.into(),
+         page_size,
+      )
+      .decode_options(self.decode_options.clone())
+   }
+
+   /// Create the FTS5 table for `index` if it doesn't exist, with triggers
+   /// that keep it in sync with inserts, updates and deletes on the content
+   /// table, and index the rows already there.
+   ///
+   /// Runs in one write transaction. Calling it again for an existing index
+   /// rebuilds it.
+   pub async fn create_fts_index(&self, index: &crate::fts::FtsIndex) -> Result<(), Error> {
+      let statements = index.create_statements()?;
+      self
+         .execute_transaction(
+            statements
+               .iter()
+               .map(|sql| (sql.as_str(), Vec::new()))
+               .collect(),
+         )
+         .await?;
+      Ok(())
+   }
+
+   /// Re-index every row of the content table, e.g. after it was modified
+   /// while the sync triggers didn't exist
+   pub async fn rebuild_fts_index(&self, index: &crate::fts::FtsIndex) -> Result<(), Error> {
+      self.execute(index.rebuild_statement()?, Vec::new()).await?;
+      Ok(())
+   }
+
+   /// Drop the FTS5 table for `index` and its sync triggers, leaving the
+   /// content table untouched
+   pub async fn drop_fts_index(&self, index: &crate::fts::FtsIndex) -> Result<(), Error> {
+      let statements = index.drop_statements()?;
+      self
+         .execute_transaction(
+            statements
+               .iter()
+               .map(|sql| (sql.as_str(), Vec::new()))
+               .collect(),
+         )
+         .await?;
+      Ok(())
    }
 
    /// Create a builder for SELECT queries returning zero or one row.
@@ -341,7 +747,383 @@ impl DatabaseWrapper {
       query: String,
       values: Vec<JsonValue>,
    ) -> crate::builders::FetchOneBuilder {
+      let filtered = self.without_deleted(&query);
       crate::builders::FetchOneBuilder::new(Arc::clone(&self.inner), query, values)
+         .filtered(filtered)
+         .decode_options(self.decode_options.clone())
+   }
+
+   /// Create a builder for SELECT queries returning zero or one row deserialized
+   /// into `T`.
+   ///
+   /// See [`fetch_all_as`](Self::fetch_all_as) for how columns map to fields.
+   /// Returns an error if the query returns more than one row.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// #[derive(serde::Deserialize)]
+   /// struct User {
+   ///     name: String,
+   /// }
+   ///
+   /// let user: Option<User> = db.fetch_one_as(
+   ///     "SELECT name FROM users WHERE id = ?".into(),
+   ///     vec![serde_json::json!(1)],
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn fetch_one_as<T>(
+      &self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> crate::builders::FetchOneAsBuilder<T>
+   where
+      T: serde::de::DeserializeOwned + Send + 'static,
+   {
+      crate::builders::FetchOneAsBuilder::new(self.fetch_one(query, values))
+   }
+
+   /// Create a builder for SELECT queries whose rows are returned in a map keyed
+   /// by `key_column`.
+   ///
+   /// The key column's value is deserialized into `K` (e.g. `i64` or `String`).
+   /// Fails with [`Error::DuplicateMapKey`] if two rows share a key.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use std::collections::HashMap;
+   ///
+   /// let users: HashMap<i64, _> = db.fetch_map(
+   ///     "SELECT id, name FROM users".into(),
+   ///     vec![],
+   ///     "id",
+   /// ).await?;
+   ///
+   /// if let Some(user) = users.get(&42) {
+   ///     println!("{}", user["name"]);
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn fetch_map<K>(
+      &self,
+      query: String,
+      values: Vec<JsonValue>,
+      key_column: impl Into<String>,
+   ) -> crate::builders::FetchMapBuilder<K>
+   where
+      K: serde::de::DeserializeOwned + Eq + std::hash::Hash + Send + 'static,
+   {
+      crate::builders::FetchMapBuilder::new(self.fetch_all(query, values), key_column.into())
+   }
+
+   /// Create a builder for queries returning a single value, such as a count,
+   /// a sum or an `EXISTS(...)` flag.
+   ///
+   /// The first column of the first row is deserialized into `T`. Fails with
+   /// [`Error::NoRowsReturned`] when there is no row and with
+   /// [`Error::RowDeserialization`] when the value does not fit `T`; use
+   /// `Option<T>` to accept a NULL value.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// let count: i64 = db.fetch_scalar("SELECT COUNT(*) FROM users".into(), vec![]).await?;
+   ///
+   /// let exists: bool = db.fetch_scalar(
+   ///     "SELECT EXISTS(SELECT 1 FROM users WHERE email = ?)".into(),
+   ///     vec![serde_json::json!("alice@example.com")],
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn fetch_scalar<T>(
+      &self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> crate::builders::FetchScalarBuilder<T>
+   where
+      T: serde::de::DeserializeOwned + Send + 'static,
+   {
+      let filtered = self.without_deleted(&query).map(Ok);
+      crate::builders::FetchScalarBuilder::new(Arc::clone(&self.inner), query, values)
+         .filtered(filtered)
+         .decode_options(self.decode_options.clone())
+   }
+
+   /// Create a builder counting the rows a SELECT query returns
+   ///
+   /// The query is wrapped as `SELECT COUNT(*) FROM (<query>)`, so DISTINCT,
+   /// GROUP BY and compound selects are counted by their result rows. Like a
+   /// pagination base query, it must be a SELECT without top-level ORDER BY or
+   /// LIMIT clauses; otherwise executing the builder fails with
+   /// [`Error::InvalidPaginationQuery`].
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// let active = db.count(
+   ///     "SELECT id FROM users WHERE active = ?".into(),
+   ///     vec![serde_json::json!(true)],
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn count(
+      &self,
+      base_query: String,
+      values: Vec<JsonValue>,
+   ) -> crate::builders::FetchScalarBuilder<u64> {
+      let filtered = self
+         .without_deleted(&base_query)
+         .map(|filtered| crate::pagination::build_count_query(&filtered));
+      crate::builders::FetchScalarBuilder::wrapping(
+         Arc::clone(&self.inner),
+         crate::pagination::build_count_query(&base_query),
+         values,
+      )
+      .filtered(filtered)
+   }
+
+   /// Create a builder checking whether a SELECT query returns any rows
+   ///
+   /// The query is wrapped as `SELECT EXISTS (<query>)`, which stops at the
+   /// first matching row. It is validated the same way as for
+   /// [`count`](Self::count).
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// let taken = db.exists(
+   ///     "SELECT 1 FROM users WHERE email = ?".into(),
+   ///     vec![serde_json::json!("alice@example.com")],
+   /// ).await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn exists(
+      &self,
+      base_query: String,
+      values: Vec<JsonValue>,
+   ) -> crate::builders::FetchScalarBuilder<bool> {
+      let filtered = self
+         .without_deleted(&base_query)
+         .map(|filtered| crate::pagination::build_exists_query(&filtered));
+      crate::builders::FetchScalarBuilder::wrapping(
+         Arc::clone(&self.inner),
+         crate::pagination::build_exists_query(&base_query),
+         values,
+      )
+      .filtered(filtered)
+   }
+
+   /// Convert fetched values to JSON per `options` by default, e.g. to return
+   /// `DATETIME` columns as ISO-8601 strings.
+   ///
+   /// Applies to fetches, pages, searches, `RETURNING` rows, read snapshots and
+   /// interruptible transaction reads started from this wrapper or clones
+   /// made from it afterwards. Fetch builders can override it per query with
+   /// `.decode_options()`. Decoders added with
+   /// [`add_decoder`](Self::add_decoder) are kept.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # fn example(mut db: sqlx_sqlite_toolkit::DatabaseWrapper) {
+   /// use sqlx_sqlite_toolkit::{DatetimeFormat, DecodeOptions};
+   ///
+   /// db.set_decode_options(DecodeOptions::new().with_datetime_format(DatetimeFormat::Iso8601));
+   /// # }
+   /// ```
+   pub fn set_decode_options(&mut self, options: DecodeOptions) {
+      self.decode_options = options.with_decoders_from(&self.decode_options);
+   }
+
+   /// Convert non-NULL values matching `target` with `decoder` wherever rows
+   /// are decoded, e.g. to map a money column from integer cents to a string.
+   ///
+   /// Reaches the same places as [`set_decode_options`](Self::set_decode_options).
+   /// A decoder for a column name wins over one for a declared type; among
+   /// decoders for the same target, the first added wins.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # fn example(mut db: sqlx_sqlite_toolkit::DatabaseWrapper) {
+   /// use serde_json::json;
+   /// use sqlx::{Value, ValueRef};
+   /// use sqlx_sqlite_toolkit::DecoderTarget;
+   ///
+   /// db.add_decoder(DecoderTarget::Column("price".into()), |value| {
+   ///     let cents = value.to_owned().try_decode::<i64>().unwrap_or_default();
+   ///     Ok(json!(format!("{}.{:02}", cents / 100, cents % 100)))
+   /// });
+   /// # }
+   /// ```
+   pub fn add_decoder<F>(&mut self, target: DecoderTarget, decoder: F)
+   where
+      F: Fn(SqliteValueRef<'_>) -> Result<JsonValue, Error> + Send + Sync + 'static,
+   {
+      self.decode_options = std::mem::take(&mut self.decode_options).with_decoder(target, decoder);
+   }
+
+   /// The options fetched values are decoded with by default
+   pub fn decode_options(&self) -> &DecodeOptions {
+      &self.decode_options
+   }
+
+   /// Exclude soft-deleted rows of `table` from fetch queries by default.
+   ///
+   /// A row counts as deleted when `column` (e.g. `deleted_at`) is not NULL.
+   /// `fetch_all`, `fetch_one`, `fetch_scalar`, `count`, `exists`,
+   /// `fetch_page` and their typed variants then add `<table>.<column> IS NULL`
+   /// to each top-level SELECT of their query that reads `table`, including
+   /// every member of a compound SELECT, qualified with the table's alias if
+   /// it has one. When `table` is joined with an `ON` constraint the filter is
+   /// added there, so a `LEFT JOIN` still returns rows without a live match;
+   /// otherwise it is added to the WHERE clause. Subqueries and CTE bodies are
+   /// not changed. Call `.include_deleted()` on a builder to run its query as
+   /// written.
+   ///
+   /// Registering a table again replaces its column. The setting applies to
+   /// this wrapper and clones made from it afterwards. Writes, transactions,
+   /// searches and JSON helpers are not affected.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(mut db: sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// db.enable_soft_delete("posts", "deleted_at")?;
+   ///
+   /// // Adds `WHERE "p"."deleted_at" IS NULL`
+   /// let live = db.fetch_all("SELECT * FROM posts p".into(), vec![]).await?;
+   /// let all = db
+   ///     .fetch_all("SELECT * FROM posts".into(), vec![])
+   ///     .include_deleted()
+   ///     .await?;
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn enable_soft_delete(
+      &mut self,
+      table: impl Into<String>,
+      column: impl Into<String>,
+   ) -> Result<(), Error> {
+      let table = table.into();
+      crate::select::unqualified_sql(&table)?;
+      let column = crate::select::unqualified_sql(&column.into())?;
+      self.soft_delete.insert(table.to_ascii_lowercase(), column);
+      Ok(())
+   }
+
+   /// Stop excluding soft-deleted rows of `table` from fetch queries
+   pub fn disable_soft_delete(&mut self, table: &str) {
+      self.soft_delete.remove(&table.to_ascii_lowercase());
+   }
+
+   /// `query` with soft-deleted rows excluded, if any table has soft deletes
+   fn without_deleted(&self, query: &str) -> Option<String> {
+      (!self.soft_delete.is_empty())
+         .then(|| crate::soft_delete::exclude_deleted(query, &self.soft_delete))
+   }
+
+   /// Register a check to run just before every
+   /// [`execute_transaction()`](Self::execute_transaction) and interruptible
+   /// transaction commits, for cross-table invariants SQLite can't express
+   ///
+   /// The hook gets the transaction's connection with `PRAGMA query_only` set,
+   /// so it can read the uncommitted changes but not write. Returning an error,
+   /// typically [`Error::PreCommitRejected`], rolls the transaction back and
+   /// fails the commit with that error. Hooks run in the order registered.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(mut db: sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use sqlx_sqlite_toolkit::Error;
+   ///
+   /// db.add_pre_commit_hook(|conn| {
+   ///     Box::pin(async move {
+   ///         let overdrawn: i64 =
+   ///             sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE balance < 0")
+   ///                 .fetch_one(&mut *conn)
+   ///                 .await?;
+   ///         if overdrawn > 0 {
+   ///             return Err(Error::PreCommitRejected("account overdrawn".into()));
+   ///         }
+   ///         Ok(())
+   ///     })
+   /// });
+   /// # Ok(())
+   /// # }
+   /// ```
+   pub fn add_pre_commit_hook<F>(&mut self, hook: F)
+   where
+      F: for<'c> Fn(&'c mut SqliteConnection) -> futures::future::BoxFuture<'c, Result<(), Error>>
+         + Send
+         + Sync
+         + 'static,
+   {
+      self.pre_commit_hooks.push(Arc::new(hook));
+   }
+
+   /// Hooks registered with [`add_pre_commit_hook()`](Self::add_pre_commit_hook),
+   /// e.g. to hand to an `ActiveInterruptibleTransaction`
+   pub fn pre_commit_hooks(&self) -> &[PreCommitHook] {
+      &self.pre_commit_hooks
+   }
+
+   /// Coalesce plain [`execute()`](Self::execute) calls that arrive within a
+   /// short window into a single transaction, committed by a background task.
+   ///
+   /// Greatly raises throughput for apps that fire many tiny independent
+   /// writes, such as event logging, at the cost of delaying each write by up
+   /// to the window. Each write runs in its own savepoint, so a failing
+   /// statement is reported to its caller alone; a caller gets its result only
+   /// once the whole batch has committed. Writes with attached databases or a
+   /// `timeout()` are never batched.
+   ///
+   /// Replaces any batching already enabled, after its queued writes commit.
+   /// Must be called from within a Tokio runtime.
+   pub async fn enable_write_batching(&mut self, config: crate::batch::WriteBatchConfig) {
+      self.disable_write_batching().await;
+      self.batcher = Some(Arc::new(crate::batch::WriteBatcher::spawn(config)));
+   }
+
+   /// Stop batching writes, waiting for the queued ones to commit
+   ///
+   /// Clones of this wrapper made while batching was enabled go back to
+   /// executing writes directly.
+   pub async fn disable_write_batching(&mut self) {
+      if let Some(batcher) = self.batcher.take() {
+         batcher.shutdown().await;
+      }
+   }
+
+   /// Returns true if write batching is currently enabled on this database.
+   pub fn is_batching_writes(&self) -> bool {
+      self.batcher.is_some()
+   }
+
+   /// The write batcher, if batching is enabled
+   pub(crate) fn write_batcher(&self) -> Option<&crate::batch::WriteBatcher> {
+      self.batcher.as_deref()
+   }
+
+   /// A clone of this wrapper that executes writes directly
+   pub(crate) fn unbatched(&self) -> Self {
+      Self {
+         batcher: None,
+         ..self.clone()
+      }
    }
 
    /// Run database migrations
@@ -362,6 +1144,7 @@ impl DatabaseWrapper {
    /// If observation is enabled, it is disabled first to unregister SQLite hooks
    /// and allow the write connection to close cleanly.
    pub async fn close(mut self) -> Result<(), Error> {
+      self.disable_write_batching().await;
       #[cfg(feature = "observer")]
       self.disable_observation();
 
@@ -374,7 +1157,11 @@ impl DatabaseWrapper {
    /// Removes the main database file, WAL, and SHM files.
    /// If observation is enabled, it is disabled first to unregister SQLite hooks
    /// and allow the write connection to close cleanly.
+   ///
+   /// Returns a `DatabaseInUse` connection manager error if other handles to the
+   /// database (e.g. clones of this wrapper) are still alive. See `force_remove()`.
    pub async fn remove(mut self) -> Result<(), Error> {
+      self.disable_write_batching().await;
       #[cfg(feature = "observer")]
       self.disable_observation();
 
@@ -382,6 +1169,17 @@ impl DatabaseWrapper {
       Ok(())
    }
 
+   /// Close the database connection and remove all database files, even if other
+   /// handles to the database are still alive.
+   pub async fn force_remove(mut self) -> Result<(), Error> {
+      self.disable_write_batching().await;
+      #[cfg(feature = "observer")]
+      self.disable_observation();
+
+      self.inner.force_remove().await?;
+      Ok(())
+   }
+
    /// Enable observation on this database for the specified tables.
    ///
    /// After calling this, write operations will be tracked and subscribers
@@ -429,6 +1227,130 @@ impl DatabaseWrapper {
    pub fn is_observing(&self) -> bool {
       self.observer.is_some()
    }
+
+   /// Watch the results of a SELECT query, for reactive UI bindings.
+   ///
+   /// The stream yields the query's rows right away, then again after each
+   /// commit that changes the tables it reads, found from the query's compiled
+   /// program (so tables read through views count too). Commits that arrive
+   /// together cause one re-run, and re-runs returning the same rows as the
+   /// previous one yield nothing. The query runs on a background task that
+   /// starts on the first poll; dropping the stream stops it. A failed re-run
+   /// yields the error and ends the stream.
+   ///
+   /// Only writes made through this wrapper's observer are seen, so the
+   /// stream stops updating once observation is re-enabled or disabled.
+   ///
+   /// Requires the `observer` feature and returns
+   /// [`Error::ObservationNotEnabled`] unless `enable_observation()` was called.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use futures::StreamExt;
+   ///
+   /// let mut results = db.watch_query(
+   ///     "SELECT id, title FROM todos WHERE done = ?".into(),
+   ///     vec![serde_json::json!(false)],
+   /// )?;
+   ///
+   /// while let Some(rows) = results.next().await {
+   ///     println!("{} open todos", rows?.len());
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   #[cfg(feature = "observer")]
+   pub fn watch_query(
+      &self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Result<crate::watch::QueryWatch, Error> {
+      let observable = self.observer.clone().ok_or(Error::ObservationNotEnabled)?;
+      Ok(crate::watch::watch(self.clone(), observable, query, values))
+   }
+
+   /// Stream the page `page` fetches now and again whenever a table its
+   /// query reads changes, re-fetching once `debounce` has passed since the
+   /// first of a burst of changes.
+   ///
+   /// Behaves like [`watch_query`](Self::watch_query) otherwise: pages equal
+   /// to the previous one are skipped. Requires the `observer` feature and
+   /// observation enabled.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// use futures::StreamExt;
+   /// use sqlx_sqlite_toolkit::KeysetColumn;
+   /// use std::time::Duration;
+   ///
+   /// let page = db.fetch_page(
+   ///     "SELECT id, title FROM todos".into(),
+   ///     vec![],
+   ///     vec![KeysetColumn::asc("id")],
+   ///     20,
+   /// );
+   /// let mut pages = db.watch_page(page, Duration::from_millis(50))?;
+   ///
+   /// while let Some(page) = pages.next().await {
+   ///     println!("{} todos on the first page", page?.rows.len());
+   /// }
+   /// # Ok(())
+   /// # }
+   /// ```
+   #[cfg(feature = "observer")]
+   pub fn watch_page(
+      &self,
+      page: crate::builders::FetchPageBuilder,
+      debounce: std::time::Duration,
+   ) -> Result<crate::watch::PageWatch, Error> {
+      let observable = self.observer.clone().ok_or(Error::ObservationNotEnabled)?;
+      Ok(crate::watch::watch_page(
+         self.clone(),
+         observable,
+         page,
+         debounce,
+      ))
+   }
+
+   /// Create an [`UndoManager`](crate::UndoManager) recording commits to
+   /// `tables` (or to every observed table if empty) from now on.
+   ///
+   /// Undo relies on the values the observer captures, so observation must
+   /// be enabled with value capture on. Returns
+   /// [`Error::ObservationNotEnabled`] otherwise.
+   ///
+   /// Requires the `observer` feature.
+   ///
+   /// # Examples
+   ///
+   /// ```no_run
+   /// # async fn example(db: &sqlx_sqlite_toolkit::DatabaseWrapper) -> Result<(), sqlx_sqlite_toolkit::Error> {
+   /// let mut history = db.undo_manager(["todos"])?;
+   ///
+   /// db.execute("DELETE FROM todos WHERE done = 1".into(), vec![]).await?;
+   ///
+   /// history.undo().await?; // The deleted todos are back
+   /// history.redo().await?; // And gone again
+   /// # Ok(())
+   /// # }
+   /// ```
+   #[cfg(feature = "observer")]
+   pub fn undo_manager<I, S>(&self, tables: I) -> Result<crate::UndoManager, Error>
+   where
+      I: IntoIterator<Item = S>,
+      S: Into<String>,
+   {
+      let observable = self.observer.as_ref().ok_or(Error::ObservationNotEnabled)?;
+      Ok(crate::undo::UndoManager::new(
+         self.clone(),
+         observable,
+         tables.into_iter().map(Into::into).collect(),
+      ))
+   }
 }
 
 /// Builder for interruptible transactions with optional attached databases
@@ -479,7 +1401,9 @@ impl InterruptibleTransactionBuilder {
          "direct_rust_api".to_string(),
          uuid::Uuid::new_v4().to_string(),
          writer,
-      );
+      )
+      .with_pre_commit_hooks(self.db.pre_commit_hooks.clone())
+      .with_decode_options(self.db.decode_options.clone());
 
       active_tx.continue_with(initial_statements).await?;
 
@@ -518,6 +1442,44 @@ impl InterruptibleTransaction {
       self.inner.read(query, values).await
    }
 
+   /// Execute a read query within this transaction expecting zero or one row
+   ///
+   /// Fails with [`Error::MultipleRowsReturned`] if more rows match.
+   pub async fn fetch_one(
+      &mut self,
+      query: String,
+      values: Vec<JsonValue>,
+   ) -> Result<Option<indexmap::IndexMap<String, JsonValue>>, Error> {
+      self.inner.fetch_one(query, values).await
+   }
+
+   /// Statements executed so far and time since the transaction began
+   pub fn stats(&self) -> crate::transactions::TransactionStats {
+      self.inner.stats()
+   }
+
+   /// Names of the active savepoints, outermost first
+   pub fn savepoints(&self) -> &[String] {
+      self.inner.savepoints()
+   }
+
+   /// Mark a savepoint to roll back to with `rollback_to(name)`
+   ///
+   /// `name` must be a plain identifier.
+   pub async fn savepoint(&mut self, name: &str) -> Result<(), Error> {
+      self.inner.savepoint(name).await
+   }
+
+   /// Release a savepoint and those created after it, keeping their changes
+   pub async fn release(&mut self, name: &str) -> Result<(), Error> {
+      self.inner.release(name).await
+   }
+
+   /// Undo the changes made since a savepoint, which stays active
+   pub async fn rollback_to(&mut self, name: &str) -> Result<(), Error> {
+      self.inner.rollback_to(name).await
+   }
+
    /// Commit this transaction
    ///
    /// Consumes the transaction, making all changes permanent.
@@ -538,6 +1500,9 @@ pub struct TransactionExecutionBuilder {
    db: DatabaseWrapper,
    statements: Vec<(String, Vec<JsonValue>)>,
    attached: Vec<sqlx_sqlite_conn_mgr::AttachedSpec>,
+   mode: crate::transactions::TransactionMode,
+   begin_retry: Option<RetryPolicy>,
+   timeout: Option<Duration>,
 }
 
 impl TransactionExecutionBuilder {
@@ -546,9 +1511,12 @@ impl TransactionExecutionBuilder {
          db,
          statements: statements
             .into_iter()
-            .map(|(query, values)| (query.to_string(), values))
+            .map(|(query, values)| (expand_in_lists(query.to_string()), values))
             .collect(),
          attached: Vec::new(),
+         mode: Default::default(),
+         begin_retry: None,
+         timeout: None,
       }
    }
 
@@ -558,44 +1526,122 @@ impl TransactionExecutionBuilder {
       self
    }
 
+   /// Set how the transaction begins (default: `BEGIN IMMEDIATE`)
+   pub fn mode(mut self, mode: crate::transactions::TransactionMode) -> Self {
+      self.mode = mode;
+      self
+   }
+
+   /// Retry starting the transaction with backoff while `BEGIN` fails with
+   /// `SQLITE_BUSY` or `SQLITE_LOCKED`, e.g. because another process holds the
+   /// file's write lock
+   ///
+   /// Only `BEGIN` is retried; a statement that fails once the transaction has
+   /// started still rolls it back.
+   pub fn retry_begin(mut self, policy: RetryPolicy) -> Self {
+      self.begin_retry = Some(policy);
+      self
+   }
+
+   /// Interrupt the running statement, roll the transaction back and fail with
+   /// [`Error::QueryTimeout`] if it hasn't committed within `timeout`, counting
+   /// time spent waiting for the writer
+   pub fn timeout(mut self, timeout: Duration) -> Self {
+      self.timeout = Some(timeout);
+      self
+   }
+
    /// Execute the transaction atomically
    ///
    /// All statements execute within a single transaction. If any statement fails,
    /// all changes are rolled back automatically.
+   ///
+   /// Statements that are just `BEGIN`, `COMMIT`/`END` or `ROLLBACK` open and
+   /// close nested transactions, run as savepoints: a nested `ROLLBACK` undoes
+   /// only the statements since its `BEGIN`, and the outer transaction carries
+   /// on. Markers must pair up, or [`Error::UnbalancedNesting`] is returned
+   /// before anything runs. Each marker's result has no rows affected.
+   ///
+   /// Pre-commit hooks run after the last statement; one returning an error
+   /// rolls the transaction back like a failing statement.
    pub async fn execute(self) -> Result<Vec<WriteQueryResult>, Error> {
-      use crate::transactions::TransactionWriter;
+      use crate::transactions::{NestingMarker, TransactionWriter, validate_nesting};
+
+      validate_nesting(self.statements.iter().map(|(query, _)| query.as_str()))?;
+
+      let deadline = QueryDeadline::start(self.timeout);
 
       // Acquire appropriate writer based on whether databases are attached
       let mut writer = if self.attached.is_empty() {
-         let guard = self.db.acquire_writer().await?;
+         let guard = deadline.acquire(self.db.acquire_writer()).await?;
          TransactionWriter::from(guard)
       } else {
-         let guard =
-            sqlx_sqlite_conn_mgr::acquire_writer_with_attached(self.db.inner(), self.attached)
-               .await?;
+         let guard = deadline
+            .acquire(sqlx_sqlite_conn_mgr::acquire_writer_with_attached(
+               self.db.inner(),
+               self.attached,
+            ))
+            .await?;
          TransactionWriter::Attached(guard)
       };
+      let interrupt = writer.interrupt_handle();
 
       // Begin transaction
-      writer.begin_immediate().await?;
+      match self.begin_retry {
+         Some(policy) => {
+            deadline
+               .run(
+                  interrupt.clone(),
+                  writer.begin_with_retry(self.mode, policy),
+               )
+               .await?
+         }
+         None => {
+            deadline
+               .run(interrupt.clone(), writer.begin(self.mode))
+               .await?
+         }
+      }
 
       // Execute all statements
-      let exec_result = async {
-         let mut results = Vec::new();
-         for (query, values) in self.statements {
-            let mut q = sqlx::query(&query);
-            for value in values {
-               q = bind_value(q, value);
+      let exec_result = deadline
+         .run(interrupt, async {
+            let mut results = Vec::new();
+            let mut depth = 0;
+            for (query, values) in self.statements {
+               if let Some(marker) = NestingMarker::parse(&query) {
+                  if marker == NestingMarker::Begin {
+                     depth += 1;
+                  }
+                  for sql in marker.statements(depth) {
+                     writer.execute_query(sqlx::query(&sql)).await?;
+                  }
+                  if marker != NestingMarker::Begin {
+                     depth -= 1;
+                  }
+                  results.push(WriteQueryResult {
+                     rows_affected: 0,
+                     last_insert_id: 0,
+                  });
+                  continue;
+               }
+
+               let mut q = sqlx::query(&query);
+               for value in values {
+                  q = bind_value(q, value);
+               }
+               let exec_result = writer.execute_query(q).await?;
+               results.push(WriteQueryResult {
+                  rows_affected: exec_result.rows_affected(),
+                  last_insert_id: exec_result.last_insert_rowid(),
+               });
             }
-            let exec_result = writer.execute_query(q).await?;
-            results.push(WriteQueryResult {
-               rows_affected: exec_result.rows_affected(),
-               last_insert_id: exec_result.last_insert_rowid(),
-            });
-         }
-         Ok::<Vec<WriteQueryResult>, Error>(results)
-      }
-      .await;
+            writer
+               .run_pre_commit_hooks(&self.db.pre_commit_hooks)
+               .await?;
+            Ok::<Vec<WriteQueryResult>, Error>(results)
+         })
+         .await;
 
       // Commit or rollback
       match exec_result {
@@ -625,6 +1671,12 @@ impl std::future::IntoFuture for TransactionExecutionBuilder {
 }
 
 /// Helper function to bind a JSON value to a SQLx query
+///
+/// A `{ "$bigint": "<digits>" }` object, as returned by
+/// [`BigIntFormat::Tagged`](crate::BigIntFormat::Tagged), is bound as an integer.
+/// A `{ "$uuid": "<uuid>" }` object is bound as the UUID's 16-byte BLOB.
+/// `{ "$blob": [<byte>, ...] }` and `{ "$base64": "<base64>" }` objects are
+/// bound as BLOBs.
 pub fn bind_value<'a>(
    query: sqlx::query::Query<'a, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'a>>,
    value: JsonValue,
@@ -649,6 +1701,12 @@ pub fn bind_value<'a>(
          // Not an integer, bind as f64
          query.bind(number.as_f64().unwrap_or_default())
       }
+   } else if let Some(int_val) = crate::decode::tagged_bigint(&value) {
+      query.bind(int_val)
+   } else if let Some(uuid) = crate::decode::tagged_uuid(&value) {
+      query.bind(uuid.as_bytes().to_vec())
+   } else if let Some(bytes) = crate::decode::tagged_blob(&value) {
+      query.bind(bytes)
    } else {
       query.bind(value)
    }
@@ -1,5 +1,5 @@
-use serde_json::json;
-use sqlx_sqlite_toolkit::{DatabaseWrapper, Statement};
+use serde_json::{Value as JsonValue, json};
+use sqlx_sqlite_toolkit::{DatabaseWrapper, Error, RetryPolicy, Statement, TransactionMode};
 use tempfile::TempDir;
 
 async fn create_test_db(name: &str) -> (DatabaseWrapper, TempDir) {
@@ -378,6 +378,201 @@ async fn test_execute_transaction_rollback_on_failure() {
    db.remove().await.unwrap();
 }
 
+#[tokio::test]
+async fn test_execute_transaction_modes() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   for mode in [
+      TransactionMode::Deferred,
+      TransactionMode::Immediate,
+      TransactionMode::Exclusive,
+   ] {
+      db.execute_transaction(vec![(
+         "INSERT INTO users (name) VALUES (?)",
+         vec![json!(format!("{mode:?}"))],
+      )])
+      .mode(mode)
+      .await
+      .unwrap();
+   }
+
+   let rows = db
+      .fetch_all("SELECT name FROM users ORDER BY id".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 3);
+   assert_eq!(rows[2].get("name"), Some(&json!("Exclusive")));
+
+   // The writer is free again after an exclusive transaction
+   db.execute("DELETE FROM users".into(), vec![])
+      .await
+      .unwrap();
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_transaction_retries_busy_begin() {
+   use sqlx::ConnectOptions;
+   use std::time::Duration;
+
+   let temp_dir = TempDir::new().unwrap();
+   let db_path = temp_dir.path().join("busy.db");
+   let db = DatabaseWrapper::connect(&db_path, None).await.unwrap();
+
+   db.execute("CREATE TABLE t (x INTEGER)".into(), vec![])
+      .await
+      .unwrap();
+
+   // Make the writer fail straight away on a held lock rather than waiting
+   let mut writer = db.acquire_writer().await.unwrap();
+   sqlx::query("PRAGMA busy_timeout = 0")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   // Another "process" holds the file's write lock
+   let mut holder = sqlx::sqlite::SqliteConnectOptions::new()
+      .filename(&db_path)
+      .connect()
+      .await
+      .unwrap();
+   sqlx::query("BEGIN IMMEDIATE")
+      .execute(&mut holder)
+      .await
+      .unwrap();
+
+   let policy = RetryPolicy {
+      max_attempts: 3,
+      initial_backoff: Duration::from_millis(1),
+      max_backoff: Duration::from_millis(2),
+      multiplier: 2.0,
+      jitter: false,
+   };
+
+   let err = db
+      .execute_transaction(vec![("INSERT INTO t VALUES (1)", vec![])])
+      .retry_begin(policy)
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::RetriesExhausted { attempts: 3, .. }));
+
+   // Release the lock partway through the retries
+   let release = tokio::spawn(async move {
+      tokio::time::sleep(Duration::from_millis(50)).await;
+      sqlx::query("COMMIT").execute(&mut holder).await.unwrap();
+   });
+
+   db.execute_transaction(vec![("INSERT INTO t VALUES (2)", vec![])])
+      .retry_begin(RetryPolicy {
+         max_attempts: 50,
+         initial_backoff: Duration::from_millis(10),
+         max_backoff: Duration::from_millis(20),
+         ..policy
+      })
+      .await
+      .unwrap();
+   release.await.unwrap();
+
+   let count: i64 = db
+      .fetch_scalar("SELECT COUNT(*) FROM t".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(count, 1);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_transaction_nested_markers() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let insert = "INSERT INTO users (name) VALUES (?)";
+   let results = db
+      .execute_transaction(vec![
+         (insert, vec![json!("Alice")]),
+         ("BEGIN", vec![]),
+         (insert, vec![json!("Bob")]),
+         ("begin transaction;", vec![]),
+         (insert, vec![json!("Carol")]),
+         ("ROLLBACK", vec![]),
+         ("COMMIT", vec![]),
+         ("BEGIN", vec![]),
+         (insert, vec![json!("Dave")]),
+         ("ROLLBACK TRANSACTION", vec![]),
+      ])
+      .await
+      .unwrap();
+
+   assert_eq!(results.len(), 10);
+   assert_eq!(results[1].rows_affected, 0);
+   assert_eq!(results[2].rows_affected, 1);
+
+   // Only the innermost nested transaction and the last one were undone
+   let names: Vec<_> = db
+      .fetch_all("SELECT name FROM users ORDER BY id".into(), vec![])
+      .await
+      .unwrap()
+      .into_iter()
+      .map(|row| row["name"].clone())
+      .collect();
+   assert_eq!(names, vec![json!("Alice"), json!("Bob")]);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_transaction_unbalanced_markers() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   for statements in [
+      vec![
+         ("BEGIN", vec![]),
+         ("INSERT INTO users (name) VALUES ('Alice')", vec![]),
+      ],
+      vec![
+         ("INSERT INTO users (name) VALUES ('Alice')", vec![]),
+         ("COMMIT", vec![]),
+      ],
+   ] {
+      assert!(matches!(
+         db.execute_transaction(statements).await,
+         Err(Error::UnbalancedNesting(_))
+      ));
+   }
+
+   // Nothing ran
+   let rows = db
+      .fetch_all("SELECT * FROM users".into(), vec![])
+      .await
+      .unwrap();
+   assert!(rows.is_empty());
+
+   db.remove().await.unwrap();
+}
+
 /// How long to wait for the post-drop rollback to complete before failing
 /// the test. Comfortably larger than the drop-path rollback timeout, so a
 /// regression (stuck rollback, leaked writer permit) fails fast rather than
@@ -515,3 +710,386 @@ async fn test_dropped_attached_transaction_releases_writer_and_detaches() {
    main_db.remove().await.unwrap();
    attached_db.remove().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_interruptible_transaction_savepoints() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let mut tx = db
+      .begin_interruptible_transaction()
+      .execute(vec![(
+         "INSERT INTO users (name) VALUES (?)",
+         vec![json!("Alice")],
+      )])
+      .await
+      .unwrap();
+
+   tx.savepoint("step_1").await.unwrap();
+   tx.continue_with(vec![
+      ("INSERT INTO users (name) VALUES (?)", vec![json!("Bob")]).into(),
+   ])
+   .await
+   .unwrap();
+   tx.savepoint("step_2").await.unwrap();
+   tx.continue_with(vec![
+      ("INSERT INTO users (name) VALUES (?)", vec![json!("Carol")]).into(),
+   ])
+   .await
+   .unwrap();
+   assert_eq!(tx.savepoints(), ["step_1", "step_2"]);
+
+   // Undoes Bob and Carol, releasing step_2 but keeping step_1
+   tx.rollback_to("step_1").await.unwrap();
+   assert_eq!(tx.savepoints(), ["step_1"]);
+
+   tx.continue_with(vec![
+      ("INSERT INTO users (name) VALUES (?)", vec![json!("Dave")]).into(),
+   ])
+   .await
+   .unwrap();
+   tx.release("step_1").await.unwrap();
+   assert!(tx.savepoints().is_empty());
+
+   tx.commit().await.unwrap();
+
+   let names: Vec<_> = db
+      .fetch_all("SELECT name FROM users ORDER BY id".into(), vec![])
+      .await
+      .unwrap()
+      .into_iter()
+      .map(|row| row["name"].clone())
+      .collect();
+   assert_eq!(names, vec![json!("Alice"), json!("Dave")]);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_interruptible_transaction_savepoint_validation() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   let mut tx = db
+      .begin_interruptible_transaction()
+      .execute(vec![])
+      .await
+      .unwrap();
+
+   assert!(matches!(
+      tx.savepoint("bad\"; DROP TABLE users; --").await,
+      Err(Error::InvalidSavepointName(_))
+   ));
+   assert!(matches!(
+      tx.release("missing").await,
+      Err(Error::SavepointNotFound(_))
+   ));
+   assert!(matches!(
+      tx.rollback_to("missing").await,
+      Err(Error::SavepointNotFound(_))
+   ));
+
+   // A failed savepoint call leaves the transaction usable
+   tx.savepoint("ok").await.unwrap();
+   tx.rollback().await.unwrap();
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_interruptible_transaction_fetch_one() {
+   let (db, _temp) = create_test_db("test.db").await;
+
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let mut tx = db
+      .begin_interruptible_transaction()
+      .execute(vec![
+         ("INSERT INTO users (name) VALUES (?)", vec![json!("Alice")]),
+         ("INSERT INTO users (name) VALUES (?)", vec![json!("Bob")]),
+      ])
+      .await
+      .unwrap();
+
+   let row = tx
+      .fetch_one(
+         "SELECT name FROM users WHERE name = ?".into(),
+         vec![json!("Bob")],
+      )
+      .await
+      .unwrap();
+   assert_eq!(row.unwrap().get("name"), Some(&json!("Bob")));
+
+   let missing = tx
+      .fetch_one(
+         "SELECT name FROM users WHERE name = ?".into(),
+         vec![json!("Carol")],
+      )
+      .await
+      .unwrap();
+   assert!(missing.is_none());
+
+   assert!(matches!(
+      tx.fetch_one("SELECT name FROM users".into(), vec![]).await,
+      Err(Error::MultipleRowsReturned(2))
+   ));
+
+   // Still usable after the row count check failed
+   tx.commit().await.unwrap();
+
+   db.remove().await.unwrap();
+}
+
+/// Create an `accounts` table guarded by a hook rejecting negative balances
+async fn create_guarded_accounts(db: &mut DatabaseWrapper) {
+   db.execute(
+      "CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.add_pre_commit_hook(|conn| {
+      Box::pin(async move {
+         let overdrawn: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE balance < 0")
+            .fetch_one(&mut *conn)
+            .await?;
+         if overdrawn > 0 {
+            return Err(Error::PreCommitRejected("account overdrawn".into()));
+         }
+         Ok(())
+      })
+   });
+}
+
+#[tokio::test]
+async fn test_pre_commit_hook_rejects_execute_transaction() {
+   let (mut db, _temp) = create_test_db("test.db").await;
+   create_guarded_accounts(&mut db).await;
+
+   db.execute_transaction(vec![("INSERT INTO accounts VALUES (1, 100)", vec![])])
+      .await
+      .unwrap();
+
+   let err = db
+      .execute_transaction(vec![
+         ("UPDATE accounts SET balance = balance - 150", vec![]),
+         ("INSERT INTO accounts VALUES (2, 150)", vec![]),
+      ])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "PRE_COMMIT_REJECTED");
+
+   let rows = db
+      .fetch_all("SELECT balance FROM accounts".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 1);
+   assert_eq!(rows[0].get("balance"), Some(&json!(100)));
+
+   // The writer isn't left read-only
+   db.execute("UPDATE accounts SET balance = 50".into(), vec![])
+      .await
+      .unwrap();
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pre_commit_hook_rejects_interruptible_commit() {
+   let (mut db, _temp) = create_test_db("test.db").await;
+   create_guarded_accounts(&mut db).await;
+
+   let mut tx = db
+      .begin_interruptible_transaction()
+      .execute(vec![("INSERT INTO accounts VALUES (1, 100)", vec![])])
+      .await
+      .unwrap();
+   tx.continue_with(vec![Statement {
+      query: "UPDATE accounts SET balance = -1".to_string(),
+      values: vec![],
+   }])
+   .await
+   .unwrap();
+
+   let err = tx.commit().await.unwrap_err();
+   assert!(matches!(err, Error::PreCommitRejected(_)));
+
+   let rows = db
+      .fetch_all("SELECT * FROM accounts".into(), vec![])
+      .await
+      .unwrap();
+   assert!(rows.is_empty());
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pre_commit_hook_cannot_write() {
+   let (mut db, _temp) = create_test_db("test.db").await;
+
+   db.execute("CREATE TABLE log (message TEXT)".into(), vec![])
+      .await
+      .unwrap();
+   db.add_pre_commit_hook(|conn| {
+      Box::pin(async move {
+         sqlx::query("INSERT INTO log VALUES ('from hook')")
+            .execute(&mut *conn)
+            .await?;
+         Ok(())
+      })
+   });
+
+   let err = db
+      .execute_transaction(vec![("INSERT INTO log VALUES ('from caller')", vec![])])
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::Sqlx(_)), "{err}");
+
+   let rows = db
+      .fetch_all("SELECT * FROM log".into(), vec![])
+      .await
+      .unwrap();
+   assert!(rows.is_empty());
+
+   db.remove().await.unwrap();
+}
+
+/// Create `orders` in `main_db` and a one-row `stats` table in `stats_db`
+async fn create_orders_and_stats(main_db: &DatabaseWrapper, stats_db: &DatabaseWrapper) {
+   main_db
+      .execute(
+         "CREATE TABLE orders (id INTEGER PRIMARY KEY, total REAL NOT NULL)".into(),
+         vec![],
+      )
+      .await
+      .unwrap();
+   stats_db
+      .execute("CREATE TABLE stats (order_count INTEGER)".into(), vec![])
+      .await
+      .unwrap();
+   stats_db
+      .execute("INSERT INTO stats VALUES (0)".into(), vec![])
+      .await
+      .unwrap();
+}
+
+fn stats_spec(stats_db: &DatabaseWrapper) -> sqlx_sqlite_conn_mgr::AttachedSpec {
+   sqlx_sqlite_conn_mgr::AttachedSpec {
+      database: std::sync::Arc::clone(stats_db.inner_for_testing()),
+      schema_name: "stats".to_string(),
+      mode: sqlx_sqlite_conn_mgr::AttachedMode::ReadWrite,
+   }
+}
+
+async fn order_count(stats_db: &DatabaseWrapper) -> JsonValue {
+   stats_db
+      .fetch_one("SELECT order_count FROM stats".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap()["order_count"]
+      .clone()
+}
+
+#[tokio::test]
+async fn test_attached_transaction_rolls_back_both_databases() {
+   let (main_db, _temp_main) = create_test_db("main.db").await;
+   let (stats_db, _temp_stats) = create_test_db("stats.db").await;
+   create_orders_and_stats(&main_db, &stats_db).await;
+
+   // The attached write succeeds, then the main one fails
+   let result = main_db
+      .execute_transaction(vec![
+         (
+            "UPDATE stats.stats SET order_count = order_count + 1",
+            vec![],
+         ),
+         ("INSERT INTO orders (total) VALUES ($1)", vec![json!(null)]),
+      ])
+      .attach(vec![stats_spec(&stats_db)])
+      .await;
+   assert!(result.is_err());
+
+   assert_eq!(order_count(&stats_db).await, json!(0));
+   let orders = main_db
+      .fetch_all("SELECT * FROM orders".into(), vec![])
+      .await
+      .unwrap();
+   assert!(orders.is_empty());
+
+   main_db.remove().await.unwrap();
+   stats_db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_attached_interruptible_transaction_spans_databases() {
+   let (main_db, _temp_main) = create_test_db("main.db").await;
+   let (stats_db, _temp_stats) = create_test_db("stats.db").await;
+   create_orders_and_stats(&main_db, &stats_db).await;
+
+   // Rolled back: neither file changes
+   let mut tx = main_db
+      .begin_interruptible_transaction()
+      .attach(vec![stats_spec(&stats_db)])
+      .execute(vec![(
+         "INSERT INTO orders (total) VALUES ($1)",
+         vec![json!(10.0)],
+      )])
+      .await
+      .unwrap();
+   tx.continue_with(vec![Statement {
+      query: "UPDATE stats.stats SET order_count = order_count + 1".to_string(),
+      values: vec![],
+   }])
+   .await
+   .unwrap();
+   tx.rollback().await.unwrap();
+
+   assert_eq!(order_count(&stats_db).await, json!(0));
+
+   // Committed: both files change
+   let mut tx = main_db
+      .begin_interruptible_transaction()
+      .attach(vec![stats_spec(&stats_db)])
+      .execute(vec![(
+         "INSERT INTO orders (total) VALUES ($1)",
+         vec![json!(20.0)],
+      )])
+      .await
+      .unwrap();
+   let rows = tx
+      .read(
+         "SELECT COUNT(*) AS n FROM orders, stats.stats".to_string(),
+         vec![],
+      )
+      .await
+      .unwrap();
+   assert_eq!(rows[0]["n"], json!(1));
+   tx.continue_with(vec![Statement {
+      query: "UPDATE stats.stats SET order_count = order_count + 1".to_string(),
+      values: vec![],
+   }])
+   .await
+   .unwrap();
+   tx.commit().await.unwrap();
+
+   assert_eq!(order_count(&stats_db).await, json!(1));
+   let orders = main_db
+      .fetch_all("SELECT total FROM orders".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(orders.len(), 1);
+
+   main_db.remove().await.unwrap();
+   stats_db.remove().await.unwrap();
+}
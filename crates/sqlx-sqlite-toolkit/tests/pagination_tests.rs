@@ -1,5 +1,8 @@
 use serde_json::json;
-use sqlx_sqlite_toolkit::{DatabaseWrapper, Error, KeysetColumn, KeysetPage};
+use sqlx_sqlite_toolkit::{
+   Cursor, DatabaseWrapper, Error, FtsIndex, KeysetColumn, KeysetPage, NullsOrder, SelectBuilder,
+   SortDirection,
+};
 use tempfile::TempDir;
 
 async fn create_test_db() -> (DatabaseWrapper, TempDir) {
@@ -678,3 +681,929 @@ async fn error_query_contains_limit() {
 
    db.remove().await.unwrap();
 }
+
+// ─── Opaque Cursor Tokens ───
+
+#[tokio::test]
+async fn opaque_cursor_tokens_paginate_forward_and_backward() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::desc("score"), KeysetColumn::asc("id")];
+   let query = "SELECT id, score FROM posts";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .opaque_cursors()
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page1), vec![1, 3, 6]);
+   assert_eq!(page1.next_cursor, None);
+   let token = page1.next_cursor_token.unwrap();
+
+   let page2 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .opaque_cursors()
+      .after(token.as_str())
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page2), vec![4, 2, 5]);
+   assert!(page2.next_cursor_token.is_some());
+
+   // Tokens are interchangeable between directions, just like raw cursors
+   let back = db
+      .fetch_page(query.into(), vec![], keyset, 3)
+      .opaque_cursors()
+      .before(token)
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&back), vec![1, 3]);
+   assert_eq!(back.next_cursor_token, None);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn opaque_cursor_token_rejected_for_different_keyset() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let page = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::asc("id")],
+         3,
+      )
+      .opaque_cursors()
+      .await
+      .unwrap();
+
+   let err = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::desc("id")],
+         3,
+      )
+      .after(page.next_cursor_token.unwrap())
+      .await
+      .unwrap_err();
+
+   assert!(matches!(err, Error::InvalidCursorToken(_)));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn signed_cursors_reject_raw_values_and_foreign_tokens() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "SELECT id FROM posts";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .signed_cursors("secret")
+      .await
+      .unwrap();
+   let token = page1.next_cursor_token.unwrap();
+
+   let page2 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .signed_cursors("secret")
+      .after(token.clone())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page2), vec![4, 5, 6]);
+
+   let err = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .signed_cursors("secret")
+      .after(vec![json!(3)])
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::InvalidCursorToken(_)));
+
+   let err = db
+      .fetch_page(query.into(), vec![], keyset, 3)
+      .signed_cursors("another secret")
+      .after(token)
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::InvalidCursorToken(_)));
+
+   db.remove().await.unwrap();
+}
+
+// ─── Total Count ───
+
+#[tokio::test]
+async fn with_total_count_ignores_cursor() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "SELECT id FROM posts WHERE category = $1";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![json!("tech")], keyset.clone(), 2)
+      .with_total_count()
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page1), vec![3, 4]);
+   assert_eq!(page1.total, Some(3));
+
+   let page2 = db
+      .fetch_page(query.into(), vec![json!("tech")], keyset, 2)
+      .with_total_count()
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page2), vec![5]);
+   assert_eq!(page2.total, Some(3));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn total_count_omitted_by_default() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let page = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::asc("id")],
+         2,
+      )
+      .await
+      .unwrap();
+
+   assert_eq!(page.total, None);
+   assert!(!serde_json::to_string(&page).unwrap().contains("total"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn with_window_total_matches_count_query() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::desc("score"), KeysetColumn::asc("id")];
+   let query = "SELECT id, score FROM posts WHERE category != $1";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![json!("art")], keyset.clone(), 2)
+      .with_window_total()
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page1), vec![1, 3]);
+   assert_eq!(page1.total, Some(5));
+   assert!(page1.rows.iter().all(|row| row.len() == 2));
+
+   let page2 = db
+      .fetch_page(query.into(), vec![json!("art")], keyset.clone(), 2)
+      .with_window_total()
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page2), vec![4, 2]);
+   assert_eq!(page2.total, Some(5));
+
+   // An empty page past the end falls back to a count query
+   let past_end = db
+      .fetch_page(query.into(), vec![json!("art")], keyset.clone(), 2)
+      .with_window_total()
+      .after(vec![json!(0), json!(0)])
+      .await
+      .unwrap();
+
+   assert!(past_end.rows.is_empty());
+   assert_eq!(past_end.total, Some(5));
+
+   let none = db
+      .fetch_page(
+         "SELECT id, score FROM posts WHERE category = $1".into(),
+         vec![json!("music")],
+         keyset,
+         2,
+      )
+      .with_window_total()
+      .await
+      .unwrap();
+
+   assert_eq!(none.total, Some(0));
+
+   db.remove().await.unwrap();
+}
+
+// ─── NULL-aware Keysets ───
+
+/// Page through the whole result set in both directions and check that every
+/// row appears exactly once, in the same order SQLite itself produces.
+async fn assert_paginates_like_order_by(db: &DatabaseWrapper, keyset: Vec<KeysetColumn>) {
+   let query = "SELECT id, rank FROM ranked";
+   let order_by = keyset
+      .iter()
+      .map(|k| {
+         let dir = match k.direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+         };
+         let nulls = match k.nulls {
+            Some(NullsOrder::First) => " NULLS FIRST",
+            Some(NullsOrder::Last) => " NULLS LAST",
+            None => "",
+         };
+         format!("{} {}{}", k.name, dir, nulls)
+      })
+      .collect::<Vec<_>>()
+      .join(", ");
+   let expected: Vec<i64> = db
+      .fetch_all(format!("{} ORDER BY {}", query, order_by), vec![])
+      .await
+      .unwrap()
+      .iter()
+      .map(|r| r["id"].as_i64().unwrap())
+      .collect();
+
+   let mut forward = Vec::new();
+   let mut cursor = None;
+   loop {
+      let mut builder = db.fetch_page(query.into(), vec![], keyset.clone(), 2);
+      if let Some(c) = cursor.take() {
+         builder = builder.after(c);
+      }
+      let page = builder.await.unwrap();
+      forward.extend(row_ids(&page));
+      match page.next_cursor {
+         Some(next) => cursor = Some(next),
+         None => break,
+      }
+   }
+   assert_eq!(forward, expected, "forward pagination for {}", order_by);
+
+   // Walk backward from the last row
+   let last = db
+      .fetch_all(
+         format!("{} WHERE id = $1", query),
+         vec![json!(expected.last().unwrap())],
+      )
+      .await
+      .unwrap();
+   let mut cursor: Vec<_> = keyset.iter().map(|k| last[0][&k.name].clone()).collect();
+   let mut backward = vec![*expected.last().unwrap()];
+   loop {
+      let page = db
+         .fetch_page(query.into(), vec![], keyset.clone(), 2)
+         .before(cursor.clone())
+         .await
+         .unwrap();
+      let mut ids = row_ids(&page);
+      ids.extend(backward);
+      backward = ids;
+      match page.next_cursor {
+         Some(next) => cursor = next,
+         None => break,
+      }
+   }
+   assert_eq!(backward, expected, "backward pagination for {}", order_by);
+}
+
+#[tokio::test]
+async fn null_keyset_values_are_not_skipped() {
+   let (db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE ranked (id INTEGER PRIMARY KEY, rank INTEGER)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   for (id, rank) in [
+      (1, json!(3)),
+      (2, json!(null)),
+      (3, json!(1)),
+      (4, json!(null)),
+      (5, json!(3)),
+      (6, json!(2)),
+      (7, json!(null)),
+   ] {
+      db.execute(
+         "INSERT INTO ranked (id, rank) VALUES ($1, $2)".into(),
+         vec![json!(id), rank],
+      )
+      .await
+      .unwrap();
+   }
+
+   for keyset in [
+      vec![KeysetColumn::asc("rank"), KeysetColumn::asc("id")],
+      vec![KeysetColumn::desc("rank"), KeysetColumn::asc("id")],
+      vec![KeysetColumn::desc("rank"), KeysetColumn::desc("id")],
+      vec![
+         KeysetColumn::asc("rank").nulls_last(),
+         KeysetColumn::asc("id"),
+      ],
+      vec![
+         KeysetColumn::desc("rank").nulls_first(),
+         KeysetColumn::desc("id"),
+      ],
+      vec![
+         KeysetColumn::asc("rank").nulls_last(),
+         KeysetColumn::desc("id").not_null(),
+      ],
+   ] {
+      assert_paginates_like_order_by(&db, keyset).await;
+   }
+
+   db.remove().await.unwrap();
+}
+
+// ─── Collation ───
+
+#[tokio::test]
+async fn nocase_collation_paginates_case_insensitively() {
+   let (db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE names (id INTEGER PRIMARY KEY, name TEXT NOT NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   for (id, name) in [
+      (1, "banana"),
+      (2, "Apple"),
+      (3, "cherry"),
+      (4, "apple"),
+      (5, "Banana"),
+   ] {
+      db.execute(
+         "INSERT INTO names (id, name) VALUES ($1, $2)".into(),
+         vec![json!(id), json!(name)],
+      )
+      .await
+      .unwrap();
+   }
+
+   let keyset = vec![
+      KeysetColumn::asc("name").with_collation("NOCASE"),
+      KeysetColumn::asc("id"),
+   ];
+   let query = "SELECT id, name FROM names";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page1), vec![2, 4]);
+
+   let page2 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page2), vec![1, 5]);
+
+   let page3 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .after(page2.next_cursor.clone().unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page3), vec![3]);
+   assert!(!page3.has_more);
+
+   let back = db
+      .fetch_page(query.into(), vec![], keyset, 2)
+      .before(page2.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&back), vec![4, 1]);
+
+   db.remove().await.unwrap();
+}
+
+// ─── CTE Base Queries ───
+
+#[tokio::test]
+async fn recursive_cte_base_query() {
+   let (db, _temp) = create_test_db().await;
+
+   let keyset = vec![KeysetColumn::desc("id").not_null()];
+   let query = "WITH RECURSIVE cnt(id) AS (SELECT 1 UNION ALL SELECT id + 1 FROM cnt WHERE id < 10) \
+                SELECT id FROM cnt WHERE id % 2 = 1";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page1), vec![9, 7]);
+
+   let page2 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page2), vec![5, 3]);
+
+   let page3 = db
+      .fetch_page(query.into(), vec![], keyset, 2)
+      .after(page2.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page3), vec![1]);
+   assert!(!page3.has_more);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn cte_base_query_with_filtered_body() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "WITH good AS (SELECT * FROM posts WHERE score >= $1 ORDER BY score LIMIT 5) \
+                SELECT id, score FROM good";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![json!(80)], keyset.clone(), 2)
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page1), vec![1, 2]);
+
+   let page2 = db
+      .fetch_page(query.into(), vec![json!(80)], keyset, 2)
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page2), vec![3, 4]);
+   assert!(page2.has_more);
+
+   db.remove().await.unwrap();
+}
+
+// ─── GROUP BY / HAVING Base Queries ───
+
+#[tokio::test]
+async fn grouped_base_query_paginates_over_groups() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   // Category totals: tech 245, science 175, art 148
+   let keyset = vec![KeysetColumn::desc("total").not_null()];
+   let query = "SELECT category, SUM(score) AS total FROM posts \
+                WHERE score >= $1 GROUP BY category HAVING COUNT(*) >= $2";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![json!(0), json!(2)], keyset.clone(), 2)
+      .await
+      .unwrap();
+   let categories: Vec<_> = page1.rows.iter().map(|r| r["category"].clone()).collect();
+   assert_eq!(categories, vec![json!("tech"), json!("science")]);
+   assert_eq!(page1.next_cursor, Some(vec![json!(175)]));
+
+   let page2 = db
+      .fetch_page(query.into(), vec![json!(0), json!(2)], keyset, 2)
+      .after(page1.next_cursor.unwrap())
+      .with_total_count()
+      .await
+      .unwrap();
+   assert_eq!(page2.rows.len(), 1);
+   assert_eq!(page2.rows[0]["category"], json!("art"));
+   assert_eq!(page2.total, Some(3));
+   assert!(!page2.has_more);
+
+   db.remove().await.unwrap();
+}
+
+// ─── DISTINCT / UNION Base Queries ───
+
+#[tokio::test]
+async fn compound_base_query_paginates_over_all_arms() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   // Top science posts and all art posts: ids 1, 2, 6, 7
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "SELECT id, title FROM posts WHERE category = $1 \
+                UNION SELECT id, title FROM posts WHERE category = 'art'";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![json!("science")], keyset.clone(), 3)
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page1), vec![1, 2, 6]);
+
+   // Without wrapping, the cursor would only filter the second arm and page 2
+   // would repeat the science posts
+   let page2 = db
+      .fetch_page(query.into(), vec![json!("science")], keyset, 3)
+      .after(page1.next_cursor.unwrap())
+      .with_total_count()
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page2), vec![7]);
+   assert_eq!(page2.total, Some(4));
+   assert!(!page2.has_more);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn distinct_base_query_paginates_over_result_rows() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("label")];
+   let query = "SELECT DISTINCT upper(category) AS label FROM posts";
+
+   let page1 = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 2)
+      .await
+      .unwrap();
+   let labels: Vec<_> = page1.rows.iter().map(|r| r["label"].clone()).collect();
+   assert_eq!(labels, vec![json!("ART"), json!("SCIENCE")]);
+
+   let page2 = db
+      .fetch_page(query.into(), vec![], keyset, 2)
+      .after(page1.next_cursor.unwrap())
+      .await
+      .unwrap();
+   let labels: Vec<_> = page2.rows.iter().map(|r| r["label"].clone()).collect();
+   assert_eq!(labels, vec![json!("TECH")]);
+
+   db.remove().await.unwrap();
+}
+
+// ─── Page Containing a Row ───
+
+#[tokio::test]
+async fn containing_returns_aligned_page_with_offset() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   // Order: 1 (95), 3 (90), 6 (88) | 4 (85), 2 (80), 5 (70) | 7 (60)
+   let keyset = vec![KeysetColumn::desc("score"), KeysetColumn::asc("id")];
+   let query = "SELECT id, score FROM posts";
+
+   let page = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .containing([("id", json!(2))])
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page), vec![4, 2, 5]);
+   assert_eq!(page.offset, Some(3));
+   assert!(page.has_more);
+
+   // Continuing from the located page works like regular pagination
+   let next = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .after(page.next_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&next), vec![7]);
+
+   let first = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .containing([("id", json!(6))])
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&first), vec![1, 3, 6]);
+   assert_eq!(first.offset, Some(0));
+
+   let last = db
+      .fetch_page(query.into(), vec![], keyset, 3)
+      .containing([("id", json!(7))])
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&last), vec![7]);
+   assert_eq!(last.offset, Some(6));
+   assert!(!last.has_more);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn containing_respects_base_query_filter() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "SELECT id, category FROM posts WHERE category = $1";
+
+   let page = db
+      .fetch_page(query.into(), vec![json!("tech")], keyset.clone(), 2)
+      .containing([("id", json!(5))])
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page), vec![5]);
+   assert_eq!(page.offset, Some(2));
+
+   // Row 1 exists in the table but not in this result set
+   let err = db
+      .fetch_page(query.into(), vec![json!("tech")], keyset, 2)
+      .containing([("id", json!(1))])
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::RowNotFound));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn containing_rejects_ambiguous_key() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let err = db
+      .fetch_page(
+         "SELECT id, category FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::asc("id")],
+         2,
+      )
+      .containing([("category", json!("tech"))])
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::AmbiguousRowKey));
+
+   db.remove().await.unwrap();
+}
+
+// ─── Page Info ───
+
+#[tokio::test]
+async fn page_info_supports_both_directions() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let query = "SELECT id FROM posts";
+
+   let first = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .await
+      .unwrap();
+   assert_eq!(first.start_cursor, Some(Cursor::Values(vec![json!(1)])));
+   assert_eq!(first.end_cursor, Some(Cursor::Values(vec![json!(3)])));
+   assert!(!first.has_previous);
+   assert!(first.has_next);
+
+   let second = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .after(first.end_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&second), vec![4, 5, 6]);
+   assert!(second.has_previous);
+   assert!(second.has_next);
+
+   // Back from the second page using its own start cursor
+   let back = db
+      .fetch_page(query.into(), vec![], keyset.clone(), 3)
+      .before(second.start_cursor.unwrap())
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&back), vec![1, 2, 3]);
+   assert!(!back.has_previous);
+   assert!(back.has_next);
+
+   let last = db
+      .fetch_page(query.into(), vec![], keyset, 3)
+      .after(second.end_cursor.unwrap())
+      .opaque_cursors()
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&last), vec![7]);
+   assert!(last.has_previous);
+   assert!(!last.has_next);
+   assert!(matches!(last.start_cursor, Some(Cursor::Token(_))));
+   assert_eq!(last.start_cursor, last.end_cursor);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn page_info_empty_page() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let page = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::asc("id")],
+         3,
+      )
+      .after(vec![json!(7)])
+      .await
+      .unwrap();
+
+   assert!(page.rows.is_empty());
+   assert_eq!(page.start_cursor, None);
+   assert_eq!(page.end_cursor, None);
+   assert!(page.has_previous);
+   assert!(!page.has_next);
+
+   db.remove().await.unwrap();
+}
+
+// ─── Streams ───
+
+#[tokio::test]
+async fn into_stream_yields_every_row() {
+   use futures::TryStreamExt;
+
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let rows: Vec<_> = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![KeysetColumn::desc("id").not_null()],
+         3,
+      )
+      .into_stream()
+      .try_collect()
+      .await
+      .unwrap();
+
+   let ids: Vec<i64> = rows.iter().map(|r| r["id"].as_i64().unwrap()).collect();
+   assert_eq!(ids, vec![7, 6, 5, 4, 3, 2, 1]);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn into_page_stream_chains_cursors() {
+   use futures::TryStreamExt;
+
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let keyset = vec![KeysetColumn::asc("id")];
+
+   let pages: Vec<KeysetPage> = db
+      .fetch_page("SELECT id FROM posts".into(), vec![], keyset.clone(), 3)
+      .opaque_cursors()
+      .with_total_count()
+      .into_page_stream()
+      .try_collect()
+      .await
+      .unwrap();
+
+   let ids: Vec<Vec<i64>> = pages.iter().map(row_ids).collect();
+   assert_eq!(ids, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+   assert_eq!(pages[0].total, Some(7));
+   assert_eq!(pages[1].total, None);
+
+   // Backward streams run from the cursor toward the start
+   let pages: Vec<KeysetPage> = db
+      .fetch_page("SELECT id FROM posts".into(), vec![], keyset, 3)
+      .before(vec![json!(6)])
+      .into_page_stream()
+      .try_collect()
+      .await
+      .unwrap();
+
+   let ids: Vec<Vec<i64>> = pages.iter().map(row_ids).collect();
+   assert_eq!(ids, vec![vec![3, 4, 5], vec![1, 2]]);
+
+   db.remove().await.unwrap();
+}
+
+// ─── SelectBuilder ───
+
+#[tokio::test]
+async fn select_builder_query_paginates() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+
+   let (query, values) = SelectBuilder::from("posts")
+      .columns(["id", "title"])
+      .where_eq("category", json!("tech"))
+      .where_sql("score >= ?", vec![json!(80)])
+      .build()
+      .unwrap();
+
+   let keyset = vec![KeysetColumn::asc("id")];
+   let page = db
+      .fetch_page(query.clone(), values.clone(), keyset.clone(), 1)
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page), vec![3]);
+
+   let page = db
+      .fetch_page(query, values, keyset, 1)
+      .after(page.next_cursor.unwrap())
+      .await
+      .unwrap();
+
+   assert_eq!(row_ids(&page), vec![4]);
+   assert!(!page.has_more);
+
+   db.remove().await.unwrap();
+}
+
+// ─── Full-text search ───
+
+#[tokio::test]
+async fn search_ranks_and_paginates_matches() {
+   let (db, _temp) = create_test_db().await;
+   seed_posts_table(&db).await;
+   db.execute(
+      "UPDATE posts SET title = title || ' about rust' WHERE id IN (2, 4, 6)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "UPDATE posts SET title = 'rust rust rust' WHERE id = 5".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let index = FtsIndex::new("posts", ["title", "category"]).content_rowid("id");
+   db.create_fts_index(&index).await.unwrap();
+
+   // Rows inserted after creation are indexed by the triggers
+   db.execute(
+      "INSERT INTO posts (id, title, category, score) VALUES (8, 'Rust tips', 'tech', 50)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let page = db
+      .search(&index, "rust", 2)
+      .columns(["id", "title"])
+      .highlight("title")
+      .markers("[", "]")
+      .with_total_count()
+      .await
+      .unwrap();
+
+   // The densest match ranks first
+   assert_eq!(row_ids(&page)[0], 5);
+   assert_eq!(
+      page.rows[0]["title_highlight"],
+      json!("[rust] [rust] [rust]")
+   );
+   assert_eq!(page.total, Some(5));
+   assert!(page.has_more);
+
+   let mut seen = row_ids(&page);
+   let mut cursor = page.next_cursor;
+   while let Some(after) = cursor {
+      let page = db.search(&index, "rust", 2).after(after).await.unwrap();
+      seen.extend(row_ids(&page));
+      cursor = page.next_cursor;
+   }
+   seen.sort();
+   assert_eq!(seen, vec![2, 4, 5, 6, 8]);
+
+   // Content table conditions, snippets and index maintenance
+   let page = db
+      .search(&index, "rust", 10)
+      .where_eq("posts.category", json!("art"))
+      .snippet("title", 2)
+      .ellipsis("...")
+      .await
+      .unwrap();
+   assert_eq!(row_ids(&page), vec![6]);
+   assert_eq!(page.rows[0]["title_snippet"], json!("...about <b>rust</b>"));
+
+   db.execute("DELETE FROM posts WHERE id = 5".into(), vec![])
+      .await
+      .unwrap();
+   let page = db.search(&index, "rust", 10).await.unwrap();
+   assert_eq!(page.rows.len(), 4);
+
+   let err = db
+      .search(&index, "rust", 10)
+      .snippet("score", 8)
+      .await
+      .unwrap_err();
+   assert!(err.to_string().contains("not indexed"));
+
+   db.drop_fts_index(&index).await.unwrap();
+   assert!(db.search(&index, "rust", 10).await.is_err());
+
+   db.remove().await.unwrap();
+}
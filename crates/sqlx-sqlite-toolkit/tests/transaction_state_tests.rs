@@ -2,8 +2,8 @@
 
 use serde_json::json;
 use sqlx_sqlite_toolkit::{
-   ActiveInterruptibleTransactions, ActiveRegularTransactions, DatabaseWrapper, Error,
-   cleanup_all_transactions,
+   ActiveInterruptibleTransactions, ActiveReadSnapshots, ActiveRegularTransactions,
+   DatabaseWrapper, Error, cleanup_all_transactions,
 };
 use tempfile::TempDir;
 
@@ -121,6 +121,43 @@ async fn test_remove_wrong_token() {
    assert_eq!(err.error_code(), "INVALID_TRANSACTION_TOKEN");
 }
 
+#[tokio::test]
+async fn test_abort_single_transaction() {
+   let (db, _temp) = create_test_db("abort.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::default();
+   let mut tx = begin_transaction(&db, "abort.db").await;
+   let tx_id = tx.transaction_id().to_string();
+   tx.continue_with(vec![("INSERT INTO t (id) VALUES (1)", vec![])])
+      .await
+      .unwrap();
+   state.insert("abort.db".into(), tx).await.unwrap();
+
+   // A stale or foreign token leaves the transaction alone
+   assert!(!state.abort("abort.db", "other-id").await.unwrap());
+   assert_eq!(state.list().await.len(), 1);
+
+   assert!(state.abort("abort.db", &tx_id).await.unwrap());
+   assert!(state.list().await.is_empty());
+
+   // Aborting again is a no-op
+   assert!(!state.abort("abort.db", &tx_id).await.unwrap());
+
+   // The insert was rolled back and the writer released
+   let count: i64 = db
+      .fetch_scalar("SELECT COUNT(*) FROM t".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(count, 0);
+   db.execute("INSERT INTO t (id) VALUES (2)".into(), vec![])
+      .await
+      .unwrap();
+}
+
 #[tokio::test]
 async fn test_abort_all_clears_transactions() {
    let (db, _temp) = create_test_db("abort.db").await;
@@ -276,6 +313,236 @@ async fn test_non_expired_transaction_not_evicted() {
    assert_eq!(err.error_code(), "TRANSACTION_ALREADY_ACTIVE");
 }
 
+#[tokio::test]
+async fn test_heartbeat_restarts_timeout() {
+   let (db, _temp) = create_test_db("heartbeat.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::new(std::time::Duration::from_millis(150));
+
+   let mut tx = begin_transaction(&db, "heartbeat.db").await;
+   let tx_id = tx.transaction_id().to_string();
+   tx.continue_with(vec![("INSERT INTO t (id) VALUES (1)", vec![])])
+      .await
+      .unwrap();
+   tx.read("SELECT * FROM t".into(), vec![]).await.unwrap();
+   state.insert("heartbeat.db".into(), tx).await.unwrap();
+
+   // Keep the transaction alive past the timeout with heartbeats
+   for _ in 0..3 {
+      tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+      let stats = state.heartbeat("heartbeat.db", &tx_id).await.unwrap();
+      assert_eq!(stats.statements_executed, 2);
+   }
+
+   let tx = state.remove("heartbeat.db", &tx_id).await.unwrap();
+   assert!(tx.stats().elapsed_ms >= 300);
+   tx.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_heartbeat_after_timeout_fails() {
+   let (db, _temp) = create_test_db("heartbeat.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::new(std::time::Duration::from_millis(1));
+
+   let tx = begin_transaction(&db, "heartbeat.db").await;
+   let tx_id = tx.transaction_id().to_string();
+   state.insert("heartbeat.db".into(), tx).await.unwrap();
+
+   tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+   let err = state.heartbeat("heartbeat.db", &tx_id).await.unwrap_err();
+   assert_eq!(err.error_code(), "TRANSACTION_TIMED_OUT");
+}
+
+#[tokio::test]
+async fn test_list_transactions() {
+   let (db, _temp) = create_test_db("list.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::default();
+   assert!(state.list().await.is_empty());
+
+   let mut tx = begin_transaction(&db, "list.db").await;
+   let tx_id = tx.transaction_id().to_string();
+   tx.continue_with(vec![("INSERT INTO t (id) VALUES (1)", vec![])])
+      .await
+      .unwrap();
+   state.insert("list.db".into(), tx).await.unwrap();
+
+   let list = state.list().await;
+   assert_eq!(list.len(), 1);
+   assert_eq!(list[0].db_path, "list.db");
+   assert_eq!(list[0].statements_executed, 1);
+   assert_eq!(list[0].token_prefix, &tx_id[..8]);
+
+   // A transaction taken out to run statements is still listed
+   let mut tx = state.remove("list.db", &tx_id).await.unwrap();
+   let busy = state.list().await;
+   assert_eq!(busy.len(), 1);
+   assert_eq!(busy[0].token_prefix, list[0].token_prefix);
+   assert_eq!(busy[0].statements_executed, 1);
+   tx.continue_with(vec![("INSERT INTO t (id) VALUES (2)", vec![])])
+      .await
+      .unwrap();
+   state.insert("list.db".into(), tx).await.unwrap();
+   assert_eq!(state.list().await[0].statements_executed, 2);
+
+   // ...until it ends
+   let tx = state.remove("list.db", &tx_id).await.unwrap();
+   tx.rollback().await.unwrap();
+   assert!(state.list().await.is_empty());
+}
+
+// ============================================================================
+// ActiveInterruptibleTransactions queueing tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_reserve_without_wait_rejects_while_held() {
+   let state = ActiveInterruptibleTransactions::default();
+
+   let _slot = state.reserve("test.db", None).await.unwrap();
+
+   let err = state
+      .reserve("test.db", None)
+      .await
+      .err()
+      .expect("expected Err, got Ok");
+   assert_eq!(err.error_code(), "TRANSACTION_ALREADY_ACTIVE");
+
+   // Other databases have their own slot
+   assert!(state.reserve("other.db", None).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_reserve_waits_for_transaction_to_end() {
+   let (db, _temp) = create_test_db("queue.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::default();
+   let slot = state.reserve("queue.db", None).await.unwrap();
+   let tx = begin_transaction(&db, "queue.db").await.with_slot(slot);
+   let tx_id = tx.transaction_id().to_string();
+   state.insert("queue.db".into(), tx).await.unwrap();
+
+   let waiter = {
+      let state = state.clone();
+      tokio::spawn(async move {
+         state
+            .reserve("queue.db", Some(std::time::Duration::from_secs(5)))
+            .await
+            .is_ok()
+      })
+   };
+
+   tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+   assert!(!waiter.is_finished());
+
+   let tx = state.remove("queue.db", &tx_id).await.unwrap();
+   tx.commit().await.unwrap();
+
+   assert!(waiter.await.unwrap());
+}
+
+#[tokio::test]
+async fn test_reserve_wait_times_out() {
+   let state = ActiveInterruptibleTransactions::default();
+
+   let _slot = state.reserve("test.db", None).await.unwrap();
+
+   let err = state
+      .reserve("test.db", Some(std::time::Duration::from_millis(20)))
+      .await
+      .err()
+      .expect("expected Err, got Ok");
+   assert_eq!(err.error_code(), "TRANSACTION_WAIT_TIMEOUT");
+}
+
+#[tokio::test]
+async fn test_reserve_evicts_expired_holder() {
+   let (db, _temp) = create_test_db("expire.db").await;
+
+   db.execute("CREATE TABLE t (id INTEGER PRIMARY KEY)".into(), vec![])
+      .await
+      .unwrap();
+
+   let state = ActiveInterruptibleTransactions::new(std::time::Duration::from_millis(50));
+   let slot = state.reserve("expire.db", None).await.unwrap();
+   let tx = begin_transaction(&db, "expire.db").await.with_slot(slot);
+   state.insert("expire.db".into(), tx).await.unwrap();
+
+   // The waiter is woken when the holder expires, well before its own timeout
+   let started = std::time::Instant::now();
+   state
+      .reserve("expire.db", Some(std::time::Duration::from_secs(5)))
+      .await
+      .unwrap();
+   assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+   // The expired transaction was rolled back, releasing the writer
+   db.execute("INSERT INTO t (id) VALUES (1)".into(), vec![])
+      .await
+      .unwrap();
+}
+
+// ============================================================================
+// ActiveReadSnapshots tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_read_snapshot_insert_and_remove() {
+   let (db, _temp) = create_test_db("snapshot.db").await;
+
+   let state = ActiveReadSnapshots::default();
+   let snapshot = db.begin_read_snapshot().await.unwrap();
+   state
+      .insert("snapshot.db".into(), "snap-1".into(), snapshot)
+      .await;
+
+   // Wrong database or ID
+   let err = state.remove("other.db", "snap-1").await.err().unwrap();
+   assert_eq!(err.error_code(), "NO_ACTIVE_SNAPSHOT");
+   let err = state.remove("snapshot.db", "snap-2").await.err().unwrap();
+   assert_eq!(err.error_code(), "NO_ACTIVE_SNAPSHOT");
+
+   let snapshot = state.remove("snapshot.db", "snap-1").await.unwrap();
+   snapshot.release().await.unwrap();
+
+   let err = state.remove("snapshot.db", "snap-1").await.err().unwrap();
+   assert_eq!(err.error_code(), "NO_ACTIVE_SNAPSHOT");
+}
+
+#[tokio::test]
+async fn test_expired_read_snapshot_times_out() {
+   let (db, _temp) = create_test_db("snapshot.db").await;
+
+   let state = ActiveReadSnapshots::new(std::time::Duration::from_millis(1));
+   let snapshot = db.begin_read_snapshot().await.unwrap();
+   state
+      .insert("snapshot.db".into(), "snap-1".into(), snapshot)
+      .await;
+
+   tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+   let err = state.remove("snapshot.db", "snap-1").await.err().unwrap();
+   assert_eq!(err.error_code(), "TRANSACTION_TIMED_OUT");
+}
+
 // ============================================================================
 // ActiveRegularTransactions tests
 // ============================================================================
@@ -294,6 +561,21 @@ async fn test_regular_insert_and_remove() {
    state.remove("tx-1").await;
 }
 
+#[tokio::test]
+async fn test_regular_list() {
+   let state = ActiveRegularTransactions::default();
+
+   let handle = tokio::spawn(async { /* no-op */ });
+   state.insert("a.db:1".into(), handle.abort_handle()).await;
+
+   let list = state.list().await;
+   assert_eq!(list.len(), 1);
+   assert_eq!(list[0].key, "a.db:1");
+
+   state.remove("a.db:1").await;
+   assert!(state.list().await.is_empty());
+}
+
 #[tokio::test]
 async fn test_regular_abort_all_cancels_tasks() {
    let state = ActiveRegularTransactions::default();
@@ -0,0 +1,129 @@
+//! Tests for `UndoManager`.
+//!
+//! Run with: cargo test --features observer
+
+#![cfg(feature = "observer")]
+
+use serde_json::{Value as JsonValue, json};
+use sqlx_sqlite_observer::ObserverConfig;
+use sqlx_sqlite_toolkit::{DatabaseWrapper, Error};
+use tempfile::TempDir;
+
+async fn create_test_db() -> (DatabaseWrapper, TempDir) {
+   let temp_dir = TempDir::new().expect("Failed to create temp directory");
+   let db_path = temp_dir.path().join("test.db");
+   let mut db = DatabaseWrapper::connect(&db_path, None)
+      .await
+      .expect("Failed to connect to test database");
+
+   db.execute_script(
+      "CREATE TABLE todos (id INTEGER PRIMARY KEY, title TEXT NOT NULL);
+       CREATE TABLE tags (name TEXT PRIMARY KEY, color TEXT) WITHOUT ROWID;
+       CREATE TABLE log (line TEXT);",
+   )
+   .await
+   .unwrap();
+   db.enable_observation(ObserverConfig::new());
+
+   (db, temp_dir)
+}
+
+async fn titles(db: &DatabaseWrapper) -> Vec<JsonValue> {
+   db.fetch_all("SELECT title FROM todos ORDER BY id".into(), vec![])
+      .await
+      .unwrap()
+      .into_iter()
+      .map(|row| row["title"].clone())
+      .collect()
+}
+
+#[tokio::test]
+async fn test_undo_and_redo_insert_update_delete() {
+   let (db, _temp) = create_test_db().await;
+   let mut history = db.undo_manager(["todos"]).unwrap();
+
+   db.execute_transaction(vec![
+      ("INSERT INTO todos (title) VALUES ('a')", vec![]),
+      ("INSERT INTO todos (title) VALUES ('b')", vec![]),
+   ])
+   .await
+   .unwrap();
+   db.execute("UPDATE todos SET title = 'B' WHERE id = 2".into(), vec![])
+      .await
+      .unwrap();
+   db.execute("DELETE FROM todos WHERE id = 1".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(titles(&db).await, vec![json!("B")]);
+
+   assert!(history.undo().await.unwrap());
+   assert_eq!(titles(&db).await, vec![json!("a"), json!("B")]);
+   assert!(history.undo().await.unwrap());
+   assert_eq!(titles(&db).await, vec![json!("a"), json!("b")]);
+   assert!(history.undo().await.unwrap());
+   assert!(titles(&db).await.is_empty());
+   assert!(!history.undo().await.unwrap(), "nothing left to undo");
+
+   assert!(history.redo().await.unwrap());
+   assert_eq!(titles(&db).await, vec![json!("a"), json!("b")]);
+   assert!(history.redo().await.unwrap());
+   assert_eq!(titles(&db).await, vec![json!("a"), json!("B")]);
+
+   // A new commit clears what's left to redo
+   db.execute("INSERT INTO todos (title) VALUES ('c')".into(), vec![])
+      .await
+      .unwrap();
+   assert!(!history.can_redo());
+   assert!(history.can_undo());
+}
+
+#[tokio::test]
+async fn test_undo_without_rowid_table_and_ignores_other_tables() {
+   let (db, _temp) = create_test_db().await;
+   let mut history = db.undo_manager(["tags"]).unwrap();
+
+   db.execute(
+      "INSERT INTO tags (name, color) VALUES ('urgent', 'red')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "UPDATE tags SET color = 'orange' WHERE name = 'urgent'".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute("INSERT INTO log (line) VALUES ('untracked')".into(), vec![])
+      .await
+      .unwrap();
+
+   assert!(history.undo().await.unwrap());
+   let color: String = db
+      .fetch_scalar(
+         "SELECT color FROM tags WHERE name = 'urgent'".into(),
+         vec![],
+      )
+      .await
+      .unwrap();
+   assert_eq!(color, "red");
+
+   let logged: i64 = db
+      .fetch_scalar("SELECT COUNT(*) FROM log".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(logged, 1, "changes to other tables are not undone");
+}
+
+#[tokio::test]
+async fn test_undo_manager_requires_observation() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = DatabaseWrapper::connect(&temp_dir.path().join("test.db"), None)
+      .await
+      .unwrap();
+
+   assert!(matches!(
+      db.undo_manager(["todos"]),
+      Err(Error::ObservationNotEnabled)
+   ));
+}
@@ -0,0 +1,159 @@
+//! Tests for `DatabaseWrapper::watch_query`.
+//!
+//! Run with: cargo test --features observer
+
+#![cfg(feature = "observer")]
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::json;
+use sqlx_sqlite_observer::ObserverConfig;
+use sqlx_sqlite_toolkit::{DatabaseWrapper, Error, KeysetColumn, QueryWatch};
+use tempfile::TempDir;
+use tokio::time::timeout;
+
+async fn create_test_db() -> (DatabaseWrapper, TempDir) {
+   let temp_dir = TempDir::new().expect("Failed to create temp directory");
+   let db_path = temp_dir.path().join("test.db");
+   let mut db = DatabaseWrapper::connect(&db_path, None)
+      .await
+      .expect("Failed to connect to test database");
+
+   db.execute_script(
+      "CREATE TABLE todos (id INTEGER PRIMARY KEY, title TEXT NOT NULL, done INTEGER NOT NULL DEFAULT 0);
+       CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT);
+       CREATE VIEW open_todos AS SELECT id, title FROM todos WHERE done = 0;",
+   )
+   .await
+   .unwrap();
+   db.enable_observation(ObserverConfig::new());
+
+   (db, temp_dir)
+}
+
+async fn next_titles(watch: &mut QueryWatch) -> Vec<String> {
+   let rows = timeout(Duration::from_secs(2), watch.next())
+      .await
+      .expect("watch should yield results")
+      .expect("watch should not end")
+      .unwrap();
+   rows
+      .iter()
+      .map(|row| row["title"].as_str().unwrap().to_string())
+      .collect()
+}
+
+#[tokio::test]
+async fn test_watch_query_reemits_on_change() {
+   let (db, _temp) = create_test_db().await;
+
+   let mut watch = db
+      .watch_query(
+         "SELECT title FROM todos WHERE done = ? ORDER BY id".into(),
+         vec![json!(0)],
+      )
+      .unwrap();
+   assert!(next_titles(&mut watch).await.is_empty());
+
+   db.execute(
+      "INSERT INTO todos (title) VALUES (?)".into(),
+      vec![json!("Write tests")],
+   )
+   .await
+   .unwrap();
+   assert_eq!(next_titles(&mut watch).await, vec!["Write tests"]);
+
+   // A change that leaves the results as they were yields nothing, so the
+   // next results seen are those of the following insert
+   db.execute("INSERT INTO notes (body) VALUES ('x')".into(), vec![])
+      .await
+      .unwrap();
+   db.execute(
+      "INSERT INTO todos (title, done) VALUES ('Already done', 1)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO todos (title) VALUES ('Ship it')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   assert_eq!(
+      next_titles(&mut watch).await,
+      vec!["Write tests", "Ship it"]
+   );
+}
+
+#[tokio::test]
+async fn test_watch_query_follows_tables_behind_views() {
+   let (db, _temp) = create_test_db().await;
+
+   let mut watch = db
+      .watch_query("SELECT title FROM open_todos".into(), vec![])
+      .unwrap();
+   assert!(next_titles(&mut watch).await.is_empty());
+
+   db.execute(
+      "INSERT INTO todos (title) VALUES ('Via view')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   assert_eq!(next_titles(&mut watch).await, vec!["Via view"]);
+}
+
+#[tokio::test]
+async fn test_watch_page_debounces_bursts() {
+   let (db, _temp) = create_test_db().await;
+
+   let page = db
+      .fetch_page(
+         "SELECT id, title FROM todos".into(),
+         vec![],
+         vec![KeysetColumn::asc("id")],
+         2,
+      )
+      .after(vec![json!(1)]);
+   let mut watch = db.watch_page(page, Duration::from_millis(100)).unwrap();
+   let first = timeout(Duration::from_secs(2), watch.next())
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+   assert!(first.rows.is_empty());
+
+   for title in ["One", "Two", "Three"] {
+      db.execute(
+         "INSERT INTO todos (title) VALUES (?)".into(),
+         vec![json!(title)],
+      )
+      .await
+      .unwrap();
+   }
+
+   // The burst lands within the debounce, so one page has all of it
+   let page = timeout(Duration::from_secs(2), watch.next())
+      .await
+      .unwrap()
+      .unwrap()
+      .unwrap();
+   let titles: Vec<_> = page.rows.iter().map(|row| row["title"].clone()).collect();
+   assert_eq!(titles, vec![json!("Two"), json!("Three")]);
+   assert!(!page.has_more);
+}
+
+#[tokio::test]
+async fn test_watch_query_requires_observation() {
+   let temp_dir = TempDir::new().unwrap();
+   let db = DatabaseWrapper::connect(&temp_dir.path().join("test.db"), None)
+      .await
+      .unwrap();
+
+   assert!(matches!(
+      db.watch_query("SELECT 1".into(), vec![]),
+      Err(Error::ObservationNotEnabled)
+   ));
+}
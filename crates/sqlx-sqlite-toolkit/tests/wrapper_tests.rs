@@ -1,5 +1,9 @@
 use serde_json::{Value as JsonValue, json};
-use sqlx_sqlite_toolkit::DatabaseWrapper;
+use sqlx_sqlite_toolkit::{
+   BigIntFormat, BooleanFormat, ColumnNameFormat, DatabaseWrapper, DatetimeFormat, DecodeOptions,
+   DecoderTarget, InvalidUtf8Format, JsonFormat, KeysetColumn, NonFiniteFormat, UuidFormat,
+   WriteBatchConfig,
+};
 use tempfile::TempDir;
 
 async fn create_test_db() -> (DatabaseWrapper, TempDir) {
@@ -59,6 +63,55 @@ async fn test_execute_and_write_result() {
    db.remove().await.unwrap();
 }
 
+#[tokio::test]
+async fn test_execute_returning() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, n INT DEFAULT 7)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let result = db
+      .execute(
+         "INSERT INTO t (name) VALUES ($1), ($2) RETURNING id, n".into(),
+         vec![json!("Alice"), json!("Bob")],
+      )
+      .returning()
+      .await
+      .unwrap();
+
+   assert_eq!((result.rows_affected, result.last_insert_id), (2, 2));
+   assert_eq!(result.rows.len(), 2);
+   assert_eq!(result.rows[1]["id"], json!(2));
+   assert_eq!(result.rows[1]["n"], json!(7));
+
+   let result = db
+      .execute(
+         "UPDATE t SET n = n + 1 WHERE name = $1 RETURNING name, n".into(),
+         vec![json!("Bob")],
+      )
+      .returning()
+      .await
+      .unwrap();
+
+   assert_eq!(result.rows_affected, 1);
+   assert_eq!(result.rows[0]["n"], json!(8));
+
+   // Without a RETURNING clause there are no rows, but counters still work
+   let result = db
+      .execute("DELETE FROM t".into(), vec![])
+      .returning()
+      .await
+      .unwrap();
+
+   assert_eq!(result.rows_affected, 2);
+   assert!(result.rows.is_empty());
+
+   db.remove().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_fetch_all() {
    let (db, _temp) = create_test_db().await;
@@ -161,162 +214,2119 @@ async fn test_fetch_one() {
 }
 
 #[tokio::test]
-async fn test_transactions() {
+async fn test_fetch_stream() {
+   use futures::{StreamExt, TryStreamExt};
+
    let (db, _temp) = create_test_db().await;
    db.execute(
-      "CREATE TABLE t (id INTEGER PRIMARY KEY, val INTEGER NOT NULL)".into(),
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, n INT)".into(),
       vec![],
    )
    .await
    .unwrap();
 
+   let rows: Vec<Vec<JsonValue>> = (0..500).map(|i| vec![json!(i)]).collect();
+   db.insert_many("t", ["n"], rows).await.unwrap();
+
+   // Every row arrives, in order
+   let rows: Vec<_> = db
+      .fetch_all(
+         "SELECT n FROM t WHERE n >= $1 ORDER BY id".into(),
+         vec![json!(100)],
+      )
+      .fetch_stream()
+      .try_collect()
+      .await
+      .unwrap();
+
+   assert_eq!(rows.len(), 400);
+   assert_eq!(rows[0]["n"], json!(100));
+   assert_eq!(rows[399]["n"], json!(499));
+
+   // Stopping early releases the connection for later queries
+   let first: Vec<_> = db
+      .fetch_all("SELECT n FROM t ORDER BY id".into(), vec![])
+      .fetch_stream()
+      .take(3)
+      .collect()
+      .await;
+
+   assert_eq!(first.len(), 3);
+
+   // Query errors surface as a stream item
+   let mut stream = db
+      .fetch_all("SELECT * FROM missing".into(), vec![])
+      .fetch_stream();
+
+   assert!(stream.next().await.unwrap().is_err());
+   assert!(stream.next().await.is_none());
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_fetch_scalar() {
+   let (db, _temp) = create_test_db().await;
    db.execute(
-      "INSERT INTO t (id, val) VALUES (1, 100), (2, 50)".into(),
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, score REAL)".into(),
       vec![],
    )
    .await
    .unwrap();
 
-   // Successful transaction commits
-   let results = db
-      .execute_transaction(vec![
-         ("UPDATE t SET val = val - 30 WHERE id = 1", vec![]),
-         ("UPDATE t SET val = val + 30 WHERE id = 2", vec![]),
-      ])
+   db.execute(
+      "INSERT INTO t (name, score) VALUES ($1, $2), ($3, $4)".into(),
+      vec![json!("Alice"), json!(1.5), json!("Bob"), json!(2.0)],
+   )
+   .await
+   .unwrap();
+
+   let count: i64 = db
+      .fetch_scalar("SELECT COUNT(*) FROM t".into(), vec![])
       .await
       .unwrap();
+   assert_eq!(count, 2);
 
-   assert_eq!(results.len(), 2);
-
-   let rows = db
-      .fetch_all("SELECT val FROM t ORDER BY id".into(), vec![])
+   let total: f64 = db
+      .fetch_scalar("SELECT SUM(score) FROM t".into(), vec![])
       .await
       .unwrap();
+   assert_eq!(total, 3.5);
 
-   assert_eq!(rows[0].get("val"), Some(&json!(70)));
-   assert_eq!(rows[1].get("val"), Some(&json!(80)));
-
-   // Failed transaction rolls back (NULL violates NOT NULL)
-   let err = db
-      .execute_transaction(vec![
-         ("UPDATE t SET val = 999 WHERE id = 1", vec![]),
-         ("INSERT INTO t (id, val) VALUES (3, NULL)", vec![]),
-      ])
-      .await;
+   let exists: bool = db
+      .fetch_scalar(
+         "SELECT EXISTS(SELECT 1 FROM t WHERE name = $1)".into(),
+         vec![json!("Bob")],
+      )
+      .await
+      .unwrap();
+   assert!(exists);
 
-   assert!(err.is_err());
+   // First column of the first row wins
+   let name: String = db
+      .fetch_scalar("SELECT name, id FROM t ORDER BY id DESC".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(name, "Bob");
 
-   // Verify rollback: id=1 should still be 70
-   let row = db
-      .fetch_one("SELECT val FROM t WHERE id = 1".into(), vec![])
+   // NULL needs an Option
+   let missing: Option<f64> = db
+      .fetch_scalar("SELECT MAX(score) FROM t WHERE id > 99".into(), vec![])
       .await
-      .unwrap()
       .unwrap();
+   assert_eq!(missing, None);
 
-   assert_eq!(row.get("val"), Some(&json!(70)));
+   let err = db
+      .fetch_scalar::<i64>("SELECT id FROM t WHERE id > 99".into(), vec![])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "NO_ROWS_RETURNED");
+
+   let err = db
+      .fetch_scalar::<i64>("SELECT name FROM t".into(), vec![])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "ROW_DESERIALIZATION_FAILED");
 
    db.remove().await.unwrap();
 }
 
 #[tokio::test]
-async fn test_type_binding_and_decoding() {
+async fn test_count_and_exists() {
    let (db, _temp) = create_test_db().await;
    db.execute(
-      "CREATE TABLE t (id INTEGER PRIMARY KEY, txt TEXT, num REAL, big INTEGER, flag BOOLEAN, data BLOB)".into(),
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, category TEXT)".into(),
       vec![],
    )
    .await
    .unwrap();
 
-   let large_int: i64 = 9_007_199_254_740_992; // 2^53
+   db.execute(
+      "INSERT INTO t (category) VALUES ('a'), ('a'), ('b')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
 
-   // Insert with various types including NULL
+   let count = db
+      .count(
+         "SELECT id FROM t WHERE category = $1".into(),
+         vec![json!("a")],
+      )
+      .await
+      .unwrap();
+   assert_eq!(count, 2);
+
+   // Counts result rows, not table rows
+   let count = db
+      .count("SELECT DISTINCT category FROM t;".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(count, 2);
+
+   // Binds and IN-list expansion work through the wrapper
+   assert!(
+      db.exists(
+         "SELECT 1 FROM t WHERE category IN $1".into(),
+         vec![json!(["b", "c"])],
+      )
+      .await
+      .unwrap()
+   );
+   assert!(
+      !db.exists(
+         "SELECT 1 FROM t WHERE category = $1".into(),
+         vec![json!("c")]
+      )
+      .await
+      .unwrap()
+   );
+
+   // Same top-level clause validation as pagination base queries
+   let err = db
+      .count("SELECT id FROM t ORDER BY id".into(), vec![])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_PAGINATION_QUERY");
+
+   let err = db
+      .exists("SELECT id FROM t LIMIT 1".into(), vec![])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_PAGINATION_QUERY");
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_soft_delete_filter() {
+   let (mut db, _temp) = create_test_db().await;
    db.execute(
-      "INSERT INTO t (txt) VALUES ($1)".into(),
-      vec![JsonValue::Null],
+      "CREATE TABLE posts (id INTEGER PRIMARY KEY, pinned INTEGER, deleted_at TEXT)".into(),
+      vec![],
    )
    .await
    .unwrap();
 
    db.execute(
-      "INSERT INTO t (txt, num) VALUES ($1, $2)".into(),
-      vec![json!("hello"), json!(1.23456)],
+      "INSERT INTO posts (id, pinned, deleted_at) VALUES \
+       (1, 1, NULL), (2, 0, NULL), (3, 1, '2024-01-01'), (4, 0, '2024-01-02')"
+         .into(),
+      vec![],
    )
    .await
    .unwrap();
 
    db.execute(
-      "INSERT INTO t (big) VALUES ($1)".into(),
-      vec![json!(large_int)],
+      "CREATE TABLE comments (id INTEGER PRIMARY KEY, post_id INTEGER, deleted_at TEXT); \
+       CREATE TABLE tags (post_id INTEGER, name TEXT)"
+         .into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO comments (id, post_id, deleted_at) VALUES \
+       (10, 1, NULL), (11, 1, '2024-01-03'), (12, 2, '2024-01-04'); \
+       INSERT INTO tags (post_id, name) VALUES (1, 'a'), (3, 'b')"
+         .into(),
+      vec![],
    )
    .await
    .unwrap();
 
-   // Boolean
-   db.execute("INSERT INTO t (flag) VALUES (TRUE)".into(), vec![])
-      .await
-      .unwrap();
+   db.enable_soft_delete("posts", "deleted_at").unwrap();
+   db.enable_soft_delete("Comments", "deleted_at").unwrap();
 
-   // BLOB ("Hello" in hex)
-   db.execute("INSERT INTO t (data) VALUES (X'48656C6C6F')".into(), vec![])
+   let ids = |rows: Vec<indexmap::IndexMap<String, JsonValue>>| {
+      rows.iter().map(|row| row["id"].clone()).collect::<Vec<_>>()
+   };
+
+   // An OR in the caller's condition cannot bypass the filter
+   let rows = db
+      .fetch_all(
+         "SELECT id FROM posts WHERE pinned = 1 OR id = 4 ORDER BY id".into(),
+         vec![],
+      )
       .await
       .unwrap();
+   assert_eq!(ids(rows), vec![json!(1)]);
 
    let rows = db
-      .fetch_all("SELECT * FROM t ORDER BY id".into(), vec![])
+      .fetch_all("SELECT id FROM posts ORDER BY id".into(), vec![])
+      .include_deleted()
       .await
       .unwrap();
+   assert_eq!(rows.len(), 4);
 
-   // NULL decoding
-   assert_eq!(rows[0].get("txt"), Some(&JsonValue::Null));
+   assert!(
+      db.fetch_one("SELECT id FROM posts WHERE id = 3".into(), vec![])
+         .await
+         .unwrap()
+         .is_none()
+   );
+   assert_eq!(
+      db.count("SELECT id FROM posts".into(), vec![])
+         .await
+         .unwrap(),
+      2
+   );
+   assert_eq!(
+      db.count("SELECT id FROM posts".into(), vec![])
+         .include_deleted()
+         .await
+         .unwrap(),
+      4
+   );
+   assert!(
+      !db.exists("SELECT 1 FROM posts WHERE id = 4".into(), vec![])
+         .await
+         .unwrap()
+   );
+   let max: i64 = db
+      .fetch_scalar("SELECT MAX(id) FROM posts".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(max, 2);
 
-   // Float decoding (with tolerance)
-   let num = rows[1].get("num").unwrap().as_f64().unwrap();
-   assert!((num - 1.23456).abs() < 0.0001);
+   let page = db
+      .fetch_page(
+         "SELECT id FROM posts".into(),
+         vec![],
+         vec![sqlx_sqlite_toolkit::KeysetColumn::desc("id")],
+         10,
+      )
+      .await
+      .unwrap();
+   assert_eq!(ids(page.rows), vec![json!(2), json!(1)]);
 
-   // Large integer precision
-   assert_eq!(rows[2].get("big"), Some(&json!(large_int)));
+   // Each table is filtered through its alias; deleted comments don't drop
+   // their post from a LEFT JOIN, and tables without the column are untouched
+   let rows = db
+      .fetch_all(
+         "SELECT p.id, c.id AS comment, t.name FROM posts p \
+          LEFT JOIN comments AS c ON c.post_id = p.id \
+          LEFT JOIN tags t ON t.post_id = p.id ORDER BY p.id"
+            .into(),
+         vec![],
+      )
+      .await
+      .unwrap();
+   assert_eq!(
+      rows
+         .iter()
+         .map(|row| (
+            row["id"].clone(),
+            row["comment"].clone(),
+            row["name"].clone()
+         ))
+         .collect::<Vec<_>>(),
+      vec![
+         (json!(1), json!(10), json!("a")),
+         (json!(2), JsonValue::Null, JsonValue::Null),
+      ]
+   );
+   assert_eq!(
+      db.count("SELECT name FROM tags".into(), vec![])
+         .await
+         .unwrap(),
+      2
+   );
 
-   // Boolean stored as integer
-   assert_eq!(rows[3].get("flag"), Some(&json!(1)));
+   // Clones copy the setting; disabling it on one leaves the other alone
+   let mut unfiltered = db.clone();
+   unfiltered.disable_soft_delete("posts");
+   assert_eq!(
+      unfiltered
+         .count("SELECT id FROM posts".into(), vec![])
+         .await
+         .unwrap(),
+      4
+   );
+   assert_eq!(
+      db.count("SELECT id FROM posts".into(), vec![])
+         .await
+         .unwrap(),
+      2
+   );
+   drop(unfiltered);
 
-   // BLOB as base64
-   assert_eq!(rows[4].get("data").unwrap().as_str(), Some("SGVsbG8="));
+   let err = db
+      .enable_soft_delete("posts", "deleted_at IS NULL OR 1")
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_COLUMN_NAME");
 
    db.remove().await.unwrap();
 }
 
 #[tokio::test]
-async fn test_column_order_preserved() {
+async fn test_fetch_map() {
+   use std::collections::HashMap;
+
    let (db, _temp) = create_test_db().await;
-   db.execute("CREATE TABLE t (z TEXT, a TEXT, m TEXT)".into(), vec![])
-      .await
-      .unwrap();
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, code TEXT, team TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
 
    db.execute(
-      "INSERT INTO t VALUES ($1, $2, $3)".into(),
-      vec![json!("z"), json!("a"), json!("m")],
+      "INSERT INTO t (code, team) VALUES ($1, $2), ($3, $4), ($5, $6)".into(),
+      vec![
+         json!("a"),
+         json!("red"),
+         json!("b"),
+         json!("blue"),
+         json!("c"),
+         json!("red"),
+      ],
    )
    .await
    .unwrap();
 
-   let rows = db
-      .fetch_all("SELECT z, a, m FROM t".into(), vec![])
+   let by_id: HashMap<i64, _> = db
+      .fetch_map("SELECT * FROM t".into(), vec![], "id")
       .await
       .unwrap();
 
-   let keys: Vec<&String> = rows[0].keys().collect();
-   assert_eq!(keys, vec!["z", "a", "m"]);
+   assert_eq!(by_id.len(), 3);
+   assert_eq!(by_id[&2]["code"], json!("b"));
+
+   let by_code: HashMap<String, _> = db
+      .fetch_map("SELECT code, team FROM t".into(), vec![], "code")
+      .await
+      .unwrap();
+
+   assert_eq!(by_code["c"]["team"], json!("red"));
+
+   let err = db
+      .fetch_map::<String>("SELECT * FROM t".into(), vec![], "team")
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "DUPLICATE_MAP_KEY");
+   assert!(err.to_string().contains("\"red\""));
+
+   let err = db
+      .fetch_map::<i64>("SELECT code FROM t".into(), vec![], "id")
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "MAP_KEY_COLUMN_NOT_FOUND");
 
    db.remove().await.unwrap();
 }
 
 #[tokio::test]
-async fn test_close() {
+async fn test_in_list_binds() {
    let (db, _temp) = create_test_db().await;
-   db.execute("CREATE TABLE t (id INTEGER)".into(), vec![])
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.insert_many(
+      "t",
+      ["name"],
+      ["a", "b", "c", "d"].map(|name| vec![json!(name)]).to_vec(),
+   )
+   .await
+   .unwrap();
+
+   let rows = db
+      .fetch_all(
+         "SELECT id FROM t WHERE id IN $1 AND name NOT IN $2 ORDER BY id".into(),
+         vec![json!([1, 2, 4]), json!(["b"])],
+      )
       .await
       .unwrap();
 
-   db.close().await.expect("close should succeed");
+   let ids: Vec<_> = rows.iter().map(|row| row["id"].clone()).collect();
+   assert_eq!(ids, vec![json!(1), json!(4)]);
+
+   // An empty list matches nothing
+   let rows = db
+      .fetch_all("SELECT id FROM t WHERE id IN $1".into(), vec![json!([])])
+      .await
+      .unwrap();
+   assert!(rows.is_empty());
+
+   let result = db
+      .execute(
+         "DELETE FROM t WHERE name IN $1".into(),
+         vec![json!(["a", "c", "zzz"])],
+      )
+      .await
+      .unwrap();
+   assert_eq!(result.rows_affected, 2);
+
+   let mut tx = db
+      .begin_interruptible_transaction()
+      .execute(vec![("DELETE FROM t WHERE id IN $1", vec![json!([2])])])
+      .await
+      .unwrap();
+   let rows = tx
+      .read(
+         "SELECT name FROM t WHERE id IN $1".into(),
+         vec![json!([2, 4])],
+      )
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 1);
+   tx.rollback().await.unwrap();
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_fetch_typed_rows() {
+   #[derive(Debug, PartialEq, serde::Deserialize)]
+   struct User {
+      id: i64,
+      name: String,
+      nickname: Option<String>,
+   }
+
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, nickname TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.execute(
+      "INSERT INTO t (name, nickname) VALUES ($1, $2), ($3, NULL)".into(),
+      vec![json!("Alice"), json!("Al"), json!("Bob")],
+   )
+   .await
+   .unwrap();
+
+   let users: Vec<User> = db
+      .fetch_all_as("SELECT * FROM t ORDER BY id".into(), vec![])
+      .await
+      .unwrap();
+
+   assert_eq!(
+      users,
+      vec![
+         User {
+            id: 1,
+            name: "Alice".into(),
+            nickname: Some("Al".into()),
+         },
+         User {
+            id: 2,
+            name: "Bob".into(),
+            nickname: None,
+         },
+      ]
+   );
+
+   let user: Option<User> = db
+      .fetch_one_as("SELECT * FROM t WHERE id = $1".into(), vec![json!(2)])
+      .await
+      .unwrap();
+
+   assert_eq!(user.map(|u| u.name), Some("Bob".to_string()));
+
+   let missing: Option<User> = db
+      .fetch_one_as("SELECT * FROM t WHERE id = $1".into(), vec![json!(999)])
+      .await
+      .unwrap();
+
+   assert!(missing.is_none());
+
+   // A column that doesn't fit the target type fails the query
+   let err = db
+      .fetch_all_as::<User>(
+         "SELECT id, id AS name, NULL AS nickname FROM t".into(),
+         vec![],
+      )
+      .await
+      .unwrap_err();
+
+   assert_eq!(err.error_code(), "ROW_DESERIALIZATION_FAILED");
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_insert_and_upsert() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, email TEXT UNIQUE, name TEXT, visits INT DEFAULT 0)"
+         .into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let result = db
+      .insert("t")
+      .value("email", json!("alice@example.com"))
+      .value("name", json!("Alice"))
+      .await
+      .unwrap();
+
+   assert_eq!((result.rows_affected, result.last_insert_id), (1, 1));
+
+   // Plain insert of a duplicate fails on the UNIQUE constraint
+   let err = db
+      .insert("t")
+      .values([("email", json!("alice@example.com"))])
+      .await
+      .unwrap_err();
+
+   assert_eq!(err.error_code(), "SQLITE_2067");
+
+   // DO NOTHING skips the conflicting row
+   let result = db
+      .insert("t")
+      .values([
+         ("email", json!("alice@example.com")),
+         ("name", json!("Ignored")),
+      ])
+      .on_conflict_do_nothing()
+      .await
+      .unwrap();
+
+   assert_eq!(result.rows_affected, 0);
+
+   // Upsert overwrites every non-target column
+   db.insert("t")
+      .value("email", json!("alice@example.com"))
+      .value("name", json!("Alicia"))
+      .value("visits", json!(5))
+      .on_conflict_update(["email"])
+      .await
+      .unwrap();
+
+   // Upsert limited to specific columns
+   db.insert("t")
+      .value("email", json!("alice@example.com"))
+      .value("name", json!("Not applied"))
+      .value("visits", json!(6))
+      .on_conflict_update_columns(["email"], ["visits"])
+      .await
+      .unwrap();
+
+   let rows = db
+      .fetch_all("SELECT id, name, visits FROM t".into(), vec![])
+      .await
+      .unwrap();
+
+   assert_eq!(rows.len(), 1);
+   assert_eq!(rows[0]["name"], json!("Alicia"));
+   assert_eq!(rows[0]["visits"], json!(6));
+
+   // Identifiers are validated before anything runs
+   let err = db
+      .insert("t")
+      .value("name\" = 1; --", json!("x"))
+      .await
+      .unwrap_err();
+
+   assert_eq!(err.error_code(), "INVALID_COLUMN_NAME");
+
+   // An upsert with nothing left to update is rejected
+   let err = db
+      .insert("t")
+      .value("email", json!("alice@example.com"))
+      .on_conflict_update(["email"])
+      .build()
+      .unwrap_err();
+
+   assert!(err.to_string().contains("no columns to update"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_json_get_and_set() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, settings TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.execute(
+      "INSERT INTO users (id, settings) VALUES (1, $1), (2, NULL)".into(),
+      vec![json!(r#"{"theme":"light","beta":true,"tags":["a"]}"#)],
+   )
+   .await
+   .unwrap();
+
+   let themes: Vec<Option<String>> = db
+      .json_get("users", "settings", "$.theme")
+      .where_sql("id IN (?, ?)", vec![json!(1), json!(2)])
+      .await
+      .unwrap();
+   assert_eq!(themes, vec![Some("light".to_string()), None]);
+
+   // Booleans and arrays decode as JSON rather than 1 and text
+   let beta: Vec<bool> = db
+      .json_get("users", "settings", "$.beta")
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   assert_eq!(beta, vec![true]);
+
+   let tags: Vec<Vec<String>> = db
+      .json_get("users", "settings", "$.tags")
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   assert_eq!(tags, vec![vec!["a".to_string()]]);
+
+   let result = db
+      .json_set("users", "settings")
+      .set("$.theme", json!("dark"))
+      .set("$.limits", json!({ "max": 3 }))
+      .set("$.tags[#]", json!("it's"))
+      .await
+      .unwrap();
+   assert_eq!(result.rows_affected, 2);
+
+   // NULL columns start out as an empty object
+   let limits: Vec<JsonValue> = db
+      .json_get("users", "settings", "$")
+      .where_eq("id", json!(2))
+      .await
+      .unwrap();
+   assert_eq!(
+      limits,
+      vec![json!({ "theme": "dark", "limits": { "max": 3 }, "tags": ["it's"] })]
+   );
+
+   let max: Vec<i64> = db
+      .json_get("users", "settings", "$.limits.max")
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   assert_eq!(max, vec![3]);
+
+   let tags: Vec<Vec<String>> = db
+      .json_get("users", "settings", "$.tags")
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   assert_eq!(tags, vec![vec!["a".to_string(), "it's".to_string()]]);
+
+   // Reads use the wrapper's decode options
+   db.json_set("users", "settings")
+      .set("$.big", json!(9_007_199_254_740_993_i64))
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   db.set_decode_options(DecodeOptions::new().with_bigint_format(BigIntFormat::String));
+   let big: Vec<String> = db
+      .json_get("users", "settings", "$.big")
+      .where_eq("id", json!(1))
+      .await
+      .unwrap();
+   assert_eq!(big, vec!["9007199254740993".to_string()]);
+
+   // Identifiers are validated, paths are bound
+   let err = db
+      .json_get::<JsonValue>("users", "settings)--", "$.theme")
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_COLUMN_NAME");
+
+   let err = db.json_set("users", "settings").build().unwrap_err();
+   assert!(err.to_string().contains("at least one path"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_insert_many_chunks_rows() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT NOT NULL, n INT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   // No rows is a no-op
+   assert_eq!(db.insert_many("t", ["name", "n"], vec![]).await.unwrap(), 0);
+
+   // 2 columns with a limit of 999 binds → 499 rows per statement, 3 statements
+   let rows: Vec<Vec<JsonValue>> = (0..1200)
+      .map(|i| vec![json!(format!("row {}", i)), json!(i)])
+      .collect();
+
+   let builder = db
+      .insert_many("t", ["name", "n"], rows.clone())
+      .max_bind_params(999);
+   let statements = builder.build().unwrap();
+
+   assert_eq!(statements.len(), 3);
+   assert_eq!(statements[0].1.len(), 998);
+   assert_eq!(statements[2].1.len(), 404);
+
+   let inserted = db
+      .insert_many("t", ["name", "n"], rows)
+      .max_bind_params(999)
+      .await
+      .unwrap();
+
+   assert_eq!(inserted, 1200);
+
+   let row = db
+      .fetch_one("SELECT COUNT(*) AS c, SUM(n) AS s FROM t".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+
+   assert_eq!(row["c"], json!(1200));
+   assert_eq!(row["s"], json!((0..1200).sum::<i64>()));
+
+   // A failing chunk rolls back the earlier ones
+   let rows: Vec<Vec<JsonValue>> = (0..10)
+      .map(|i| vec![json!(format!("new {}", i)), json!(i)])
+      .chain([vec![JsonValue::Null, json!(0)]])
+      .collect();
+
+   let err = db
+      .insert_many("t", ["name", "n"], rows)
+      .max_bind_params(4)
+      .await
+      .unwrap_err();
+
+   assert_eq!(err.error_code(), "SQLITE_1299");
+
+   let row = db
+      .fetch_one("SELECT COUNT(*) AS c FROM t".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+
+   assert_eq!(row["c"], json!(1200));
+
+   // Ragged rows are rejected up front
+   let err = db
+      .insert_many("t", ["name", "n"], vec![vec![json!("x")]])
+      .await
+      .unwrap_err();
+
+   assert!(err.to_string().contains("row 0 has 1 values but 2 columns"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_execute_script() {
+   let (db, _temp) = create_test_db().await;
+
+   let results = db
+      .execute_script(
+         "-- schema
+         CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);
+         CREATE TABLE log (msg TEXT);
+         CREATE TRIGGER t_insert AFTER INSERT ON t BEGIN
+            INSERT INTO log VALUES ('inserted; ' || new.name);
+         END;
+         INSERT INTO t (name) VALUES ('a;b'), ('c');",
+      )
+      .await
+      .unwrap();
+
+   assert_eq!(results.len(), 4);
+   assert_eq!(results[3].rows_affected, 2);
+
+   let log = db
+      .fetch_all("SELECT msg FROM log ORDER BY rowid".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(log[0]["msg"], json!("inserted; a;b"));
+
+   // A failing statement rolls back the whole script
+   let err = db
+      .execute_script("INSERT INTO t (name) VALUES ('d'); INSERT INTO missing VALUES (1);")
+      .await
+      .unwrap_err();
+   assert!(err.to_string().contains("missing"));
+
+   let rows = db
+      .fetch_all("SELECT id FROM t".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 2);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_transactions() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, val INTEGER NOT NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.execute(
+      "INSERT INTO t (id, val) VALUES (1, 100), (2, 50)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   // Successful transaction commits
+   let results = db
+      .execute_transaction(vec![
+         ("UPDATE t SET val = val - 30 WHERE id = 1", vec![]),
+         ("UPDATE t SET val = val + 30 WHERE id = 2", vec![]),
+      ])
+      .await
+      .unwrap();
+
+   assert_eq!(results.len(), 2);
+
+   let rows = db
+      .fetch_all("SELECT val FROM t ORDER BY id".into(), vec![])
+      .await
+      .unwrap();
+
+   assert_eq!(rows[0].get("val"), Some(&json!(70)));
+   assert_eq!(rows[1].get("val"), Some(&json!(80)));
+
+   // Failed transaction rolls back (NULL violates NOT NULL)
+   let err = db
+      .execute_transaction(vec![
+         ("UPDATE t SET val = 999 WHERE id = 1", vec![]),
+         ("INSERT INTO t (id, val) VALUES (3, NULL)", vec![]),
+      ])
+      .await;
+
+   assert!(err.is_err());
+
+   // Verify rollback: id=1 should still be 70
+   let row = db
+      .fetch_one("SELECT val FROM t WHERE id = 1".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+
+   assert_eq!(row.get("val"), Some(&json!(70)));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_type_binding_and_decoding() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE t (id INTEGER PRIMARY KEY, txt TEXT, num REAL, big INTEGER, flag BOOLEAN, data BLOB)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let large_int: i64 = 9_007_199_254_740_992; // 2^53
+
+   // Insert with various types including NULL
+   db.execute(
+      "INSERT INTO t (txt) VALUES ($1)".into(),
+      vec![JsonValue::Null],
+   )
+   .await
+   .unwrap();
+
+   db.execute(
+      "INSERT INTO t (txt, num) VALUES ($1, $2)".into(),
+      vec![json!("hello"), json!(1.23456)],
+   )
+   .await
+   .unwrap();
+
+   db.execute(
+      "INSERT INTO t (big) VALUES ($1)".into(),
+      vec![json!(large_int)],
+   )
+   .await
+   .unwrap();
+
+   // Boolean
+   db.execute("INSERT INTO t (flag) VALUES (TRUE)".into(), vec![])
+      .await
+      .unwrap();
+
+   // BLOB ("Hello" in hex)
+   db.execute("INSERT INTO t (data) VALUES (X'48656C6C6F')".into(), vec![])
+      .await
+      .unwrap();
+
+   let rows = db
+      .fetch_all("SELECT * FROM t ORDER BY id".into(), vec![])
+      .await
+      .unwrap();
+
+   // NULL decoding
+   assert_eq!(rows[0].get("txt"), Some(&JsonValue::Null));
+
+   // Float decoding (with tolerance)
+   let num = rows[1].get("num").unwrap().as_f64().unwrap();
+   assert!((num - 1.23456).abs() < 0.0001);
+
+   // Large integer precision
+   assert_eq!(rows[2].get("big"), Some(&json!(large_int)));
+
+   // Boolean stored as integer
+   assert_eq!(rows[3].get("flag"), Some(&json!(1)));
+
+   // BLOB as base64
+   assert_eq!(rows[4].get("data").unwrap().as_str(), Some("SGVsbG8="));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_datetime_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE events (id INTEGER PRIMARY KEY, at DATETIME, stamp TIMESTAMP, note TEXT)"
+         .into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO events (at, stamp, note) VALUES ($1, $2, $3), ($4, NULL, NULL)".into(),
+      vec![
+         json!("2024-01-15 10:30:00"),
+         json!(1_705_314_600),
+         json!("2024-01-15 10:30:00"),
+         json!("not a date"),
+      ],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT at, stamp, note FROM events ORDER BY id";
+
+   // Values are returned as stored by default
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["at"], json!("2024-01-15 10:30:00"));
+   assert_eq!(rows[0]["stamp"], json!(1_705_314_600));
+
+   db.set_decode_options(DecodeOptions::new().with_datetime_format(DatetimeFormat::Iso8601));
+
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["at"], json!("2024-01-15T10:30:00Z"));
+   assert_eq!(rows[0]["stamp"], json!("2024-01-15T10:30:00Z"));
+   // Only declared date-time columns are converted
+   assert_eq!(rows[0]["note"], json!("2024-01-15 10:30:00"));
+   // Unparseable values and NULLs come back as stored
+   assert_eq!(rows[1]["at"], json!("not a date"));
+   assert_eq!(rows[1]["stamp"], JsonValue::Null);
+
+   // A query's own options take precedence over the database's
+   let row = db
+      .fetch_one("SELECT at, stamp FROM events WHERE id = 1".into(), vec![])
+      .decode_options(DecodeOptions::new().with_datetime_format(DatetimeFormat::EpochMillis))
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["at"], json!(1_705_314_600_000_i64));
+   assert_eq!(row["stamp"], json!(1_705_314_600_000_i64));
+
+   // Page rows are converted, but cursors keep the stored values so they can
+   // be bound into the next page's query
+   let page = db
+      .fetch_page(
+         "SELECT id, at FROM events".into(),
+         vec![],
+         vec![KeysetColumn::asc("at"), KeysetColumn::asc("id")],
+         1,
+      )
+      .await
+      .unwrap();
+   assert_eq!(page.rows[0]["at"], json!("2024-01-15T10:30:00Z"));
+   let cursor = page.next_cursor.unwrap();
+   assert_eq!(cursor, vec![json!("2024-01-15 10:30:00"), json!(1)]);
+
+   let next = db
+      .fetch_page(
+         "SELECT id, at FROM events".into(),
+         vec![],
+         vec![KeysetColumn::asc("at"), KeysetColumn::asc("id")],
+         1,
+      )
+      .after(cursor)
+      .await
+      .unwrap();
+   assert_eq!(next.rows[0]["at"], json!("not a date"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_bigint_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE ids (id INTEGER PRIMARY KEY, n INTEGER)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO ids (id, n) VALUES (9007199254740993, 1), (-9007199254740993, NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT id, n FROM ids ORDER BY id DESC";
+
+   db.set_decode_options(DecodeOptions::new().with_bigint_format(BigIntFormat::String));
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["id"], json!("9007199254740993"));
+   assert_eq!(rows[1]["id"], json!("-9007199254740993"));
+   // Safe integers stay numbers
+   assert_eq!(rows[0]["n"], json!(1));
+
+   let rows = db
+      .fetch_all(query.into(), vec![])
+      .decode_options(DecodeOptions::new().with_bigint_format(BigIntFormat::Tagged))
+      .await
+      .unwrap();
+   let tagged = rows[0]["id"].clone();
+   assert_eq!(tagged, json!({ "$bigint": "9007199254740993" }));
+
+   // Tagged values bind back as integers
+   let row = db
+      .fetch_one("SELECT n FROM ids WHERE id = $1".into(), vec![tagged])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["n"], json!(1));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_json_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE docs (id INTEGER PRIMARY KEY, body JSON, note TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO docs (body, note) VALUES \
+         (json_object('tags', json_array('a', 'b')), json_array(1, 2)), \
+         ($1, $2), ($3, $4), ($5, $6)"
+         .into(),
+      vec![
+         json!("[not json"),
+         json!("true"),
+         json!("42"),
+         json!("42"),
+         json!(r#""quoted""#),
+         json!("plain"),
+      ],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT body, note FROM docs ORDER BY id";
+
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["body"], json!(r#"{"tags":["a","b"]}"#));
+   // JSON has NUMERIC affinity, so numeric text is stored as a number
+   assert_eq!(rows[2]["body"], json!(42));
+   assert_eq!(rows[2]["note"], json!("42"));
+
+   db.set_decode_options(DecodeOptions::new().with_json_format(JsonFormat::Parsed));
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["body"], json!({ "tags": ["a", "b"] }));
+   assert_eq!(rows[0]["note"], json!([1, 2]));
+   // Invalid documents are returned as stored
+   assert_eq!(rows[1]["body"], json!("[not json"));
+   // Scalars are parsed only in columns declared JSON
+   assert_eq!(rows[1]["note"], json!("true"));
+   assert_eq!(rows[2]["body"], json!(42));
+   assert_eq!(rows[2]["note"], json!("42"));
+   assert_eq!(rows[3]["body"], json!("quoted"));
+   assert_eq!(rows[3]["note"], json!("plain"));
+
+   // Also through fetch_one and its single-connection path
+   let row = db
+      .fetch_one("SELECT body FROM docs WHERE id = 4".into(), vec![])
+      .timeout(std::time::Duration::from_secs(5))
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["body"], json!("quoted"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_non_finite_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   let query = "SELECT 9e999 AS inf, -9e999 AS ninf, 1.5 AS x";
+
+   // Null by default, since JSON has no infinity
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row["inf"], JsonValue::Null);
+   assert_eq!(row["x"], json!(1.5));
+
+   db.set_decode_options(DecodeOptions::new().with_non_finite_format(NonFiniteFormat::String));
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row["inf"], json!("Infinity"));
+   assert_eq!(row["ninf"], json!("-Infinity"));
+   assert_eq!(row["x"], json!(1.5));
+
+   let err = db
+      .fetch_one(query.into(), vec![])
+      .decode_options(DecodeOptions::new().with_non_finite_format(NonFiniteFormat::Error))
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "NON_FINITE_FLOAT");
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_invalid_utf8_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute("CREATE TABLE legacy (name TEXT, note TEXT)".into(), vec![])
+      .await
+      .unwrap();
+   // 'caf' followed by a Latin-1 e-acute, which isn't valid UTF-8
+   db.execute(
+      "INSERT INTO legacy VALUES (CAST(X'636166E9' AS TEXT), 'ok')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT name, note FROM legacy";
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row["name"], JsonValue::Null);
+   assert_eq!(row["note"], json!("ok"));
+
+   db.set_decode_options(DecodeOptions::new().with_invalid_utf8_format(InvalidUtf8Format::Lossy));
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row["name"], json!("caf\u{fffd}"));
+   assert_eq!(row["note"], json!("ok"));
+
+   // The lossily decoded columns are reported with the rows
+   let columnar = db.fetch_all(query.into(), vec![]).columnar().await.unwrap();
+   assert_eq!(columnar.rows[0][0], json!("caf\u{fffd}"));
+   assert_eq!(columnar.lossy_columns, ["name"]);
+   let typed = db
+      .fetch_all(query.into(), vec![])
+      .with_column_types()
+      .await
+      .unwrap();
+   assert_eq!(typed.lossy_columns, ["name"]);
+   let typed = db
+      .fetch_all("SELECT note FROM legacy".into(), vec![])
+      .with_column_types()
+      .await
+      .unwrap();
+   assert!(typed.lossy_columns.is_empty());
+
+   let err = db
+      .fetch_one(query.into(), vec![])
+      .decode_options(DecodeOptions::new().with_invalid_utf8_format(InvalidUtf8Format::Error))
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_UTF8");
+   assert!(err.to_string().contains("'name'"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_camel_case_column_names() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE users (user_id INTEGER PRIMARY KEY, display_name TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO users (display_name) VALUES ($1)".into(),
+      vec![json!("Ada")],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT user_id, display_name FROM users";
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row["display_name"], json!("Ada"));
+
+   db.set_decode_options(DecodeOptions::new().with_column_name_format(ColumnNameFormat::CamelCase));
+   let row = db.fetch_one(query.into(), vec![]).await.unwrap().unwrap();
+   assert_eq!(row.keys().collect::<Vec<_>>(), ["userId", "displayName"]);
+
+   // The map key column is still named as in SQL
+   let map = db
+      .fetch_map::<i64>(query.into(), vec![], "user_id")
+      .await
+      .unwrap();
+   assert_eq!(map[&1]["displayName"], json!("Ada"));
+
+   let columnar = db.fetch_all(query.into(), vec![]).columnar().await.unwrap();
+   assert_eq!(columnar.columns, ["userId", "displayName"]);
+
+   // Names that convert to the same key fail rather than overwrite each other
+   let err = db
+      .fetch_one("SELECT user_id, 2 AS userId FROM users".into(), vec![])
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "COLUMN_KEY_COLLISION");
+   assert!(err.to_string().contains("'user_id' and 'userId'"));
+
+   // A repeated column name isn't a collision
+   let row = db
+      .fetch_one("SELECT user_id, user_id FROM users".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["userId"], json!(1));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_boolean_decoding() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE flags (id INTEGER PRIMARY KEY, active BOOLEAN, count INTEGER)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO flags (active, count) VALUES (1, 1), (0, 0), (NULL, NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let query = "SELECT active, count FROM flags ORDER BY id";
+
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["active"], json!(1));
+
+   db.set_decode_options(DecodeOptions::new().with_boolean_format(BooleanFormat::Boolean));
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["active"], json!(true));
+   assert_eq!(rows[1]["active"], json!(false));
+   assert_eq!(rows[2]["active"], JsonValue::Null);
+   // Only columns declared BOOLEAN are converted
+   assert_eq!(rows[0]["count"], json!(1));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_tagged_blob_binds() {
+   let (db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE files (id INTEGER PRIMARY KEY, data BLOB)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO files (data) VALUES ($1), ($2)".into(),
+      vec![
+         json!({ "$blob": [0, 1, 255] }),
+         json!({ "$base64": "AAH/" }),
+      ],
+   )
+   .await
+   .unwrap();
+
+   let rows = db
+      .fetch_all(
+         "SELECT typeof(data) AS type, hex(data) AS hex, data FROM files ORDER BY id".into(),
+         vec![],
+      )
+      .await
+      .unwrap();
+   for row in &rows {
+      assert_eq!(row["type"], json!("blob"));
+      assert_eq!(row["hex"], json!("0001FF"));
+      // Decoded back as base64, so it can be bound again as is
+      assert_eq!(row["data"], json!("AAH/"));
+   }
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_uuid_blobs() {
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE accounts (id UUID PRIMARY KEY, name TEXT, digest BLOB)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+   db.execute(
+      "INSERT INTO accounts (id, name, digest) VALUES ($1, 'a', $2)".into(),
+      vec![json!({ "$uuid": id }), json!({ "$uuid": id })],
+   )
+   .await
+   .unwrap();
+
+   let length: i64 = db
+      .fetch_scalar("SELECT length(id) FROM accounts".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(length, 16);
+
+   // Returned as base64 by default
+   let row = db
+      .fetch_one("SELECT id FROM accounts".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["id"], json!("Z+VQRBCxQm+SR7toDl/gyA=="));
+
+   db.set_decode_options(DecodeOptions::new().with_uuid_format(UuidFormat::String));
+   let row = db
+      .fetch_one(
+         "SELECT id, name FROM accounts WHERE id = $1".into(),
+         vec![json!({ "$uuid": id.to_uppercase() })],
+      )
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["id"], json!(id));
+   assert_eq!(row["name"], json!("a"));
+
+   // 16-byte BLOBs in columns not declared UUID stay base64
+   let row = db
+      .fetch_one(
+         "SELECT digest, randomblob(16) AS r FROM accounts".into(),
+         vec![],
+      )
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["digest"], json!("Z+VQRBCxQm+SR7toDl/gyA=="));
+   // Base64 of 16 bytes, not a 36-character UUID string
+   assert_eq!(row["r"].as_str().unwrap().len(), 24);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_custom_decoders() {
+   use sqlx::{Value, ValueRef};
+
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE items (id INTEGER PRIMARY KEY, price INTEGER, status TEXT, note TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO items (price, status, note) VALUES (1999, 'a', 'x'), (NULL, 'b', NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   db.add_decoder(DecoderTarget::Column("price".into()), |value| {
+      let cents = value.to_owned().try_decode::<i64>().unwrap();
+      Ok(json!(format!("{}.{:02}", cents / 100, cents % 100)))
+   });
+   db.add_decoder(DecoderTarget::DeclaredType("text".into()), |value| {
+      let text = value.to_owned().try_decode::<String>().unwrap();
+      Ok(json!(text.to_uppercase()))
+   });
+   // The column decoder wins over the declared type one
+   db.add_decoder(DecoderTarget::Column("status".into()), |value| {
+      let status = value.to_owned().try_decode::<String>().unwrap();
+      Ok(json!(if status == "a" { "active" } else { "blocked" }))
+   });
+
+   let query = "SELECT price, status, note FROM items ORDER BY id";
+
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["price"], json!("19.99"));
+   assert_eq!(rows[0]["status"], json!("active"));
+   assert_eq!(rows[0]["note"], json!("X"));
+   // NULLs skip decoders
+   assert_eq!(rows[1]["price"], JsonValue::Null);
+   assert_eq!(rows[1]["note"], JsonValue::Null);
+
+   // Per-query options and new database options keep the decoders
+   let row = db
+      .fetch_one("SELECT price FROM items WHERE id = 1".into(), vec![])
+      .decode_options(
+         DecodeOptions::new().with_decoder(DecoderTarget::Column("price".into()), |_| {
+            Ok(json!("overridden"))
+         }),
+      )
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["price"], json!("overridden"));
+
+   db.set_decode_options(DecodeOptions::new().with_json_format(JsonFormat::Parsed));
+   let price: JsonValue = db
+      .fetch_scalar("SELECT price FROM items WHERE id = 1".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(price, json!("19.99"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_custom_declared_type_decoder() {
+   use sqlx::{Value, ValueRef};
+
+   let (mut db, _temp) = create_test_db().await;
+   db.execute(
+      "CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance MONEY, owner TEXT)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO accounts (balance, owner) VALUES (1999, 'ada')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   // A type sqlx doesn't know, matched as declared in the schema
+   db.add_decoder(DecoderTarget::DeclaredType("money".into()), |value| {
+      let cents = value.to_owned().try_decode::<i64>().unwrap();
+      Ok(json!(format!("{}.{:02}", cents / 100, cents % 100)))
+   });
+
+   let query = "SELECT balance, balance + 0 AS raw, owner FROM accounts";
+   let rows = db.fetch_all(query.into(), vec![]).await.unwrap();
+   assert_eq!(rows[0]["balance"], json!("19.99"));
+   // Expressions have no declared type
+   assert_eq!(rows[0]["raw"], json!(1999));
+   assert_eq!(rows[0]["owner"], json!("ada"));
+
+   let balance: String = db
+      .fetch_scalar("SELECT balance FROM accounts".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(balance, "19.99");
+
+   let columnar = db.fetch_all(query.into(), vec![]).columnar().await.unwrap();
+   assert_eq!(columnar.rows[0][0], json!("19.99"));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_column_order_preserved() {
+   let (db, _temp) = create_test_db().await;
+   db.execute("CREATE TABLE t (z TEXT, a TEXT, m TEXT)".into(), vec![])
+      .await
+      .unwrap();
+
+   db.execute(
+      "INSERT INTO t VALUES ($1, $2, $3)".into(),
+      vec![json!("z"), json!("a"), json!("m")],
+   )
+   .await
+   .unwrap();
+
+   let rows = db
+      .fetch_all("SELECT z, a, m FROM t".into(), vec![])
+      .await
+      .unwrap();
+
+   let keys: Vec<&String> = rows[0].keys().collect();
+   assert_eq!(keys, vec!["z", "a", "m"]);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_close() {
+   let (db, _temp) = create_test_db().await;
+   db.execute("CREATE TABLE t (id INTEGER)".into(), vec![])
+      .await
+      .unwrap();
+
+   db.close().await.expect("close should succeed");
+}
+
+#[tokio::test]
+async fn test_retry_reports_attempts() {
+   use sqlx_sqlite_toolkit::{Error, RetryPolicy};
+
+   let (db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE items (id INTEGER PRIMARY KEY, name TEXT NOT NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let result = db
+      .execute(
+         "INSERT INTO items (name) VALUES (?)".into(),
+         vec![json!("first")],
+      )
+      .retry(RetryPolicy::default())
+      .await
+      .unwrap();
+   assert_eq!(result.attempts, 1);
+   assert_eq!(result.value.rows_affected, 1);
+
+   let rows = db
+      .fetch_all("SELECT name FROM items".into(), vec![])
+      .retry(RetryPolicy::default())
+      .await
+      .unwrap();
+   assert_eq!(rows.attempts, 1);
+   assert_eq!(rows.value.len(), 1);
+
+   // Errors other than SQLITE_BUSY/SQLITE_LOCKED are returned without retrying
+   let err = db
+      .execute(
+         "INSERT INTO items (name) VALUES (?)".into(),
+         vec![JsonValue::Null],
+      )
+      .retry(RetryPolicy::default())
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::Sqlx(_)));
+
+   db.remove().await.unwrap();
+}
+
+const RUNAWAY_QUERY: &str =
+   "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT count(*) FROM c";
+
+#[tokio::test]
+async fn test_timeout_interrupts_slow_reads() {
+   use futures::{StreamExt, TryStreamExt};
+   use sqlx_sqlite_toolkit::Error;
+   use std::time::Duration;
+
+   let (db, _temp) = create_test_db().await;
+
+   let started = std::time::Instant::now();
+   let err = db
+      .fetch_all(RUNAWAY_QUERY.into(), vec![])
+      .timeout(Duration::from_millis(100))
+      .await
+      .unwrap_err();
+   match err {
+      Error::QueryTimeout(elapsed) => assert!(elapsed >= Duration::from_millis(100)),
+      other => panic!("expected QueryTimeout, got {other:?}"),
+   }
+   assert!(started.elapsed() < Duration::from_secs(10));
+
+   let err = db
+      .fetch_scalar::<i64>(RUNAWAY_QUERY.into(), vec![])
+      .timeout(Duration::from_millis(50))
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "QUERY_TIMEOUT");
+
+   // Pages and streams are limited too
+   let err = db
+      .fetch_page(
+         "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT x FROM c".into(),
+         vec![],
+         vec![KeysetColumn::asc("x")],
+         10,
+      )
+      .with_total_count()
+      .timeout(Duration::from_millis(50))
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::QueryTimeout(_)));
+   let mut stream = db
+      .fetch_all(RUNAWAY_QUERY.into(), vec![])
+      .timeout(Duration::from_millis(50))
+      .fetch_stream();
+   assert!(matches!(
+      stream.next().await,
+      Some(Err(Error::QueryTimeout(_)))
+   ));
+   assert!(stream.next().await.is_none());
+
+   // Fast queries finish normally and the connections are reusable
+   let value = db
+      .fetch_scalar::<i64>("SELECT 42".into(), vec![])
+      .timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+   assert_eq!(value, 42);
+   let row = db
+      .fetch_one("SELECT 1 AS one".into(), vec![])
+      .timeout(Duration::from_secs(5))
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["one"], json!(1));
+   let page = db
+      .fetch_page(
+         "SELECT 1 AS one".into(),
+         vec![],
+         vec![KeysetColumn::asc("one")],
+         10,
+      )
+      .with_total_count()
+      .timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+   assert_eq!(page.total, Some(1));
+   let rows: Vec<_> = db
+      .fetch_all("SELECT 1 AS one".into(), vec![])
+      .timeout(Duration::from_secs(5))
+      .fetch_stream()
+      .try_collect()
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 1);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pragma_overrides_are_restored() {
+   let (db, _temp) = create_test_db().await;
+   let cache_size = "SELECT cache_size FROM pragma_cache_size()";
+
+   let default = db
+      .fetch_one(cache_size.into(), vec![])
+      .await
+      .unwrap()
+      .unwrap()["cache_size"]
+      .clone();
+
+   let rows = db
+      .fetch_all(cache_size.into(), vec![])
+      .pragma("cache_size", -8192)
+      .pragma("query_only", "ON")
+      .await
+      .unwrap();
+   assert_eq!(rows[0]["cache_size"], json!(-8192));
+
+   let row = db
+      .fetch_one("SELECT query_only FROM pragma_query_only()".into(), vec![])
+      .pragma("query_only", "ON")
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(row["query_only"], json!(1));
+
+   let scalar: i64 = db
+      .fetch_scalar(cache_size.into(), vec![])
+      .pragma("cache_size", -4096)
+      .await
+      .unwrap();
+   assert_eq!(scalar, -4096);
+
+   let page = db
+      .fetch_page(
+         cache_size.into(),
+         vec![],
+         vec![KeysetColumn::asc("cache_size")],
+         10,
+      )
+      .with_total_count()
+      .pragma("cache_size", -2048)
+      .await
+      .unwrap();
+   assert_eq!(page.rows[0]["cache_size"], json!(-2048));
+   assert_eq!(page.total, Some(1));
+
+   // Every read connection is back to its defaults afterwards
+   let readers = futures::future::try_join_all(
+      (0..4).map(|_| db.fetch_one(cache_size.into(), vec![]).into_future()),
+   )
+   .await
+   .unwrap();
+   for row in readers {
+      assert_eq!(row.unwrap()["cache_size"], default);
+   }
+   let query_only = db
+      .fetch_one("SELECT query_only FROM pragma_query_only()".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(query_only["query_only"], json!(0));
+
+   let err = db
+      .fetch_all(cache_size.into(), vec![])
+      .pragma("cache_size", "1; DROP TABLE t")
+      .await
+      .unwrap_err();
+   assert_eq!(err.error_code(), "INVALID_PRAGMA");
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timeout_rolls_back_slow_writes() {
+   use sqlx_sqlite_toolkit::Error;
+   use std::time::Duration;
+
+   let (db, _temp) = create_test_db().await;
+
+   db.execute("CREATE TABLE nums (n INTEGER)".into(), vec![])
+      .await
+      .unwrap();
+
+   let err = db
+      .execute(
+         "INSERT INTO nums WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+          SELECT x FROM c"
+            .into(),
+         vec![],
+      )
+      .timeout(Duration::from_millis(100))
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::QueryTimeout(_)));
+
+   // A timed-out transaction rolls back the statements before the slow one
+   let err = db
+      .execute_transaction(vec![
+         ("INSERT INTO nums VALUES (0)", vec![]),
+         (
+            "INSERT INTO nums WITH RECURSIVE c(x) AS \
+             (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT x FROM c",
+            vec![],
+         ),
+      ])
+      .timeout(Duration::from_millis(100))
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::QueryTimeout(_)));
+
+   // The interrupted statements left nothing behind and the writer still works
+   let count: i64 = db
+      .fetch_scalar("SELECT COUNT(*) FROM nums".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(count, 0);
+   db.insert("nums")
+      .value("n", json!(1))
+      .timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+   let inserted = db
+      .insert_many("nums", ["n"], vec![vec![json!(-1)], vec![json!(-2)]])
+      .timeout(Duration::from_secs(5))
+      .await
+      .unwrap();
+   assert_eq!(inserted, 2);
+   db.execute("DELETE FROM nums WHERE n < 0".into(), vec![])
+      .await
+      .unwrap();
+
+   // Waiting for a busy writer counts against the limit without interrupting
+   // whoever holds it
+   let mut writer = db.acquire_writer().await.unwrap();
+   let err = db
+      .execute("INSERT INTO nums VALUES (2)".into(), vec![])
+      .timeout(Duration::from_millis(50))
+      .await
+      .unwrap_err();
+   assert!(matches!(err, Error::QueryTimeout(_)));
+   sqlx::query("INSERT INTO nums VALUES (3)")
+      .execute(&mut *writer)
+      .await
+      .unwrap();
+   drop(writer);
+
+   let rows = db
+      .fetch_all("SELECT n FROM nums ORDER BY n".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 2);
+   assert_eq!(rows[1]["n"], json!(3));
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_fetch_all_columnar() {
+   let (db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT, age INTEGER)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO people (name, age) VALUES ('Alice', 30), ('Bob', NULL)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let result = db
+      .fetch_all(
+         "SELECT id, name, age FROM people ORDER BY id".into(),
+         vec![],
+      )
+      .columnar()
+      .await
+      .unwrap();
+   assert_eq!(result.columns, vec!["id", "name", "age"]);
+   assert_eq!(
+      result.rows,
+      vec![
+         vec![json!(1), json!("Alice"), json!(30)],
+         vec![json!(2), json!("Bob"), JsonValue::Null],
+      ]
+   );
+
+   // Column names are still reported when nothing matches
+   let empty = db
+      .fetch_all(
+         "SELECT name AS who, age FROM people WHERE age > ?".into(),
+         vec![json!(100)],
+      )
+      .columnar()
+      .await
+      .unwrap();
+   assert_eq!(empty.columns, vec!["who", "age"]);
+   assert!(empty.rows.is_empty());
+
+   assert_eq!(
+      serde_json::to_value(&result).unwrap(),
+      json!({
+         "columns": ["id", "name", "age"],
+         "rows": [[1, "Alice", 30], [2, "Bob", null]],
+         "lossyColumns": [],
+      })
+   );
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_fetch_all_with_column_types() {
+   let (db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE tasks (id INTEGER PRIMARY KEY, title VARCHAR(80), done BOOLEAN, due DATETIME)"
+         .into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute(
+      "INSERT INTO tasks (title, done, due) VALUES ('Write', 0, '2024-01-15 10:30:00')".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+
+   let result = db
+      .fetch_all(
+         "SELECT id, title, done, due, length(title) AS len FROM tasks".into(),
+         vec![],
+      )
+      .with_column_types()
+      .await
+      .unwrap();
+   let types: Vec<_> = result
+      .columns
+      .iter()
+      .map(|c| {
+         (
+            c.name.as_str(),
+            c.declared_type.as_deref(),
+            c.affinity.as_str(),
+         )
+      })
+      .collect();
+   // Expressions have no declared type, so BLOB affinity as in SQLite
+   assert_eq!(
+      types,
+      vec![
+         ("id", Some("INTEGER"), "INTEGER"),
+         ("title", Some("VARCHAR(80)"), "TEXT"),
+         ("done", Some("BOOLEAN"), "NUMERIC"),
+         ("due", Some("DATETIME"), "NUMERIC"),
+         ("len", None, "BLOB"),
+      ]
+   );
+   assert_eq!(result.rows[0]["title"], json!("Write"));
+
+   // Column types are still reported when nothing matches
+   let empty = db
+      .fetch_all("SELECT done FROM tasks WHERE id > 100".into(), vec![])
+      .with_column_types()
+      .await
+      .unwrap();
+   assert_eq!(empty.columns[0].declared_type.as_deref(), Some("BOOLEAN"));
+   assert!(empty.rows.is_empty());
+
+   assert_eq!(
+      serde_json::to_value(&empty).unwrap(),
+      json!({
+         "columns": [{ "name": "done", "declaredType": "BOOLEAN", "affinity": "NUMERIC" }],
+         "rows": [],
+         "lossyColumns": [],
+      })
+   );
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_read_snapshot_ignores_later_writes() {
+   let (db, _temp) = create_test_db().await;
+
+   db.execute("CREATE TABLE orders (total INTEGER)".into(), vec![])
+      .await
+      .unwrap();
+   db.execute("INSERT INTO orders VALUES (10)".into(), vec![])
+      .await
+      .unwrap();
+
+   let mut snapshot = db.begin_read_snapshot().await.unwrap();
+
+   // Writes carry on while the snapshot is open
+   db.execute("INSERT INTO orders VALUES (20)".into(), vec![])
+      .await
+      .unwrap();
+
+   let rows = snapshot
+      .fetch_all("SELECT total FROM orders".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 1);
+   let sum = snapshot
+      .fetch_one("SELECT SUM(total) AS sum FROM orders".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(sum["sum"], json!(10));
+
+   snapshot.release().await.unwrap();
+
+   // A fresh snapshot sees the write
+   let mut snapshot = db.begin_read_snapshot().await.unwrap();
+   let rows = snapshot
+      .fetch_all("SELECT total FROM orders".into(), vec![])
+      .await
+      .unwrap();
+   assert_eq!(rows.len(), 2);
+   drop(snapshot);
+
+   db.remove().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_write_batching_commits_concurrent_writes() {
+   let (mut db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE events (id INTEGER PRIMARY KEY, name TEXT UNIQUE)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.enable_write_batching(
+      WriteBatchConfig::new().with_window(std::time::Duration::from_millis(20)),
+   )
+   .await;
+   assert!(db.is_batching_writes());
+
+   let writes: Vec<_> = (0..20)
+      .map(|i| {
+         db.execute(
+            "INSERT INTO events (name) VALUES (?)".into(),
+            vec![json!(format!("event-{i}"))],
+         )
+         .execute()
+      })
+      .collect();
+   let results = futures::future::join_all(writes).await;
+
+   for result in &results {
+      assert_eq!(result.as_ref().unwrap().rows_affected, 1);
+   }
+   let mut ids: Vec<i64> = results
+      .iter()
+      .map(|result| result.as_ref().unwrap().last_insert_id)
+      .collect();
+   ids.sort();
+   assert_eq!(ids, (1..=20).collect::<Vec<i64>>());
+
+   db.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_write_batching_isolates_failing_write() {
+   let (mut db, _temp) = create_test_db().await;
+
+   db.execute(
+      "CREATE TABLE events (id INTEGER PRIMARY KEY, name TEXT UNIQUE)".into(),
+      vec![],
+   )
+   .await
+   .unwrap();
+   db.execute("INSERT INTO events (name) VALUES ('taken')".into(), vec![])
+      .await
+      .unwrap();
+   db.enable_write_batching(
+      WriteBatchConfig::new().with_window(std::time::Duration::from_millis(20)),
+   )
+   .await;
+
+   let (first, duplicate, last) = tokio::join!(
+      db.execute("INSERT INTO events (name) VALUES ('a')".into(), vec![])
+         .execute(),
+      db.execute("INSERT INTO events (name) VALUES ('taken')".into(), vec![])
+         .execute(),
+      db.execute("INSERT INTO events (name) VALUES ('b')".into(), vec![])
+         .execute(),
+   );
+
+   first.unwrap();
+   last.unwrap();
+   let err = duplicate.unwrap_err();
+   assert!(err.error_code().starts_with("SQLITE_"), "{err}");
+
+   let count = db
+      .fetch_one("SELECT COUNT(*) AS n FROM events".into(), vec![])
+      .await
+      .unwrap()
+      .unwrap();
+   assert_eq!(count["n"], json!(3));
+
+   // Writes go straight to the database again once batching is disabled
+   db.disable_write_batching().await;
+   assert!(!db.is_batching_writes());
+   db.execute("INSERT INTO events (name) VALUES ('c')".into(), vec![])
+      .await
+      .unwrap();
+
+   db.remove().await.unwrap();
 }
@@ -11,6 +11,9 @@ import Database, {
    TableChange,
    TableChangeEvent,
    KeysetColumn,
+   QueryWatch,
+   InterruptibleTransaction,
+   changeEventName,
 } from './index';
 
 let lastCmd = '',
@@ -30,16 +33,37 @@ beforeEach(() => {
          return [];
       }
       if (cmd === 'plugin:sqlite|begin_interruptible_transaction') {
-         return { dbPath: (args as { db: string }).db, transactionId: 'test-tx-id' };
+         return { dbPath: (args as { db: string }).db, transactionId: 'test-tx-id', results: [ { rowsAffected: 1, lastInsertId: 1 } ] };
       }
       if (cmd === 'plugin:sqlite|transaction_continue') {
          const action = (args as { action: { type: string } }).action;
 
          if (action.type === 'Continue') {
-            return { dbPath: 'test.db', transactionId: 'test-tx-id' };
+            return { dbPath: 'test.db', transactionId: 'test-tx-id', results: [ { rowsAffected: 1, lastInsertId: 2 } ] };
+         }
+         if (action.type !== 'Commit' && action.type !== 'Rollback') {
+            return { dbPath: 'test.db', transactionId: 'test-tx-id', results: [] };
          }
          return undefined;
       }
+      if (cmd === 'plugin:sqlite|begin_read_snapshot') {
+         return { dbPath: (args as { db: string }).db, snapshotId: 'test-snapshot-id' };
+      }
+      if (cmd === 'plugin:sqlite|snapshot_fetch_one') {
+         return { sum: 42 };
+      }
+      if (cmd === 'plugin:sqlite|snapshot_fetch_all') {
+         return [ { id: 1 } ];
+      }
+      if (cmd === 'plugin:sqlite|transaction_abort') {
+         return true;
+      }
+      if (cmd === 'plugin:sqlite|transaction_heartbeat') {
+         return { statementsExecuted: 2, elapsedMs: 150 };
+      }
+      if (cmd === 'plugin:sqlite|transaction_fetch_one') {
+         return { id: 1, name: 'Alice' };
+      }
       if (cmd === 'plugin:sqlite|transaction_read') {
          return [];
       }
@@ -55,6 +79,12 @@ beforeEach(() => {
       if (cmd === 'plugin:sqlite|close') {
          return true;
       }
+      if (cmd === 'plugin:sqlite|list_transactions') {
+         return {
+            interruptible: [ { dbPath: 'test.db', tokenPrefix: 'test-tx-', ageMs: 120, statementsExecuted: 3 } ],
+            regular: [],
+         };
+      }
       if (cmd === 'plugin:sqlite|close_all') {
          return undefined;
       }
@@ -76,6 +106,12 @@ beforeEach(() => {
       if (cmd === 'plugin:sqlite|unobserve') {
          return undefined;
       }
+      if (cmd === 'plugin:sqlite|watch_query') {
+         return 'watch-123';
+      }
+      if (cmd === 'plugin:sqlite|unwatch_query') {
+         return true;
+      }
       return undefined;
    });
 });
@@ -95,6 +131,16 @@ describe('Database commands', () => {
       expect(lastArgs).toMatchObject({ db: 't.db', query: 'INSERT INTO t VALUES ($1)', values: [ 1 ], attached: null });
    });
 
+   it('execute with a Uint8Array value', async () => {
+      await Database.get('t.db').execute('INSERT INTO files (data) VALUES ($1)', [ new Uint8Array([ 0, 1, 255 ]) ]);
+      expect(lastArgs).toMatchObject({ values: [ { $base64: 'AAH/' } ] });
+   });
+
+   it('execute_transaction with a Uint8Array value', async () => {
+      await Database.get('t.db').executeTransaction([ [ 'INSERT INTO files (data) VALUES ($1)', [ new Uint8Array([ 7 ]) ] ] ]);
+      expect(lastArgs.statements).toEqual([ { query: 'INSERT INTO files (data) VALUES ($1)', values: [ { $base64: 'Bw==' } ] } ]);
+   });
+
    it('execute with attached databases', async () => {
       await Database.get('main.db')
          .execute('UPDATE todos SET status = $1 WHERE id IN (SELECT todo_id FROM archive.completed)', [ 'archived' ])
@@ -116,11 +162,67 @@ describe('Database commands', () => {
       ]);
    });
 
+   it('enable_write_batching', async () => {
+      await Database.get('t.db').enableWriteBatching({ windowMs: 10, maxBatchSize: 50 });
+      expect(lastCmd).toBe('plugin:sqlite|enable_write_batching');
+      expect(lastArgs).toEqual({ db: 't.db', options: { windowMs: 10, maxBatchSize: 50 } });
+   });
+
+   it('enable_write_batching with defaults', async () => {
+      await Database.get('t.db').enableWriteBatching();
+      expect(lastCmd).toBe('plugin:sqlite|enable_write_batching');
+      expect(lastArgs.options).toBeNull();
+   });
+
+   it('disable_write_batching', async () => {
+      await Database.get('t.db').disableWriteBatching();
+      expect(lastCmd).toBe('plugin:sqlite|disable_write_batching');
+      expect(lastArgs).toEqual({ db: 't.db' });
+   });
+
+   it('set_decode_options', async () => {
+      await Database.get('t.db').setDecodeOptions({ datetime: 'iso8601' });
+      expect(lastCmd).toBe('plugin:sqlite|set_decode_options');
+      expect(lastArgs).toEqual({ db: 't.db', options: { datetime: 'iso8601' } });
+   });
+
+   it('set_decode_options with bigint format', async () => {
+      await Database.get('t.db').setDecodeOptions({ bigint: 'tagged' });
+      expect(lastCmd).toBe('plugin:sqlite|set_decode_options');
+      expect(lastArgs).toEqual({ db: 't.db', options: { bigint: 'tagged' } });
+   });
+
+   it('set_decode_options with boolean format', async () => {
+      await Database.get('t.db').setDecodeOptions({ boolean: 'boolean' });
+      expect(lastArgs).toEqual({ db: 't.db', options: { boolean: 'boolean' } });
+   });
+
+   it('set_decode_options with non-finite format', async () => {
+      await Database.get('t.db').setDecodeOptions({ nonFinite: 'string' });
+      expect(lastArgs).toEqual({ db: 't.db', options: { nonFinite: 'string' } });
+   });
+
    it('execute_transaction', async () => {
       await Database.get('t.db').executeTransaction([ [ 'DELETE FROM t' ] ]);
       expect(lastCmd).toBe('plugin:sqlite|execute_transaction');
       expect(lastArgs.statements).toEqual([ { query: 'DELETE FROM t', values: [] } ]);
       expect(lastArgs.attached).toBe(null);
+      expect(lastArgs.mode).toBe(null);
+      expect(lastArgs.beginRetry).toBe(null);
+   });
+
+   it('execute_transaction with begin retry', async () => {
+      await Database.get('t.db')
+         .executeTransaction([ [ 'DELETE FROM t' ] ])
+         .retryBegin({ maxAttempts: 10, initialBackoffMs: 50 });
+      expect(lastCmd).toBe('plugin:sqlite|execute_transaction');
+      expect(lastArgs.beginRetry).toEqual({ maxAttempts: 10, initialBackoffMs: 50 });
+   });
+
+   it('execute_transaction with mode', async () => {
+      await Database.get('t.db').executeTransaction([ [ 'DELETE FROM t' ] ]).mode('deferred');
+      expect(lastCmd).toBe('plugin:sqlite|execute_transaction');
+      expect(lastArgs.mode).toBe('deferred');
    });
 
    it('execute_transaction with attached databases', async () => {
@@ -153,6 +255,11 @@ describe('Database commands', () => {
       expect(lastArgs).toMatchObject({ db: 't.db', query: 'SELECT * FROM t', attached: null });
    });
 
+   it('fetch_all with an IN-list value', async () => {
+      await Database.get('t.db').fetchAll('SELECT * FROM t WHERE id IN $1', [ [ 1, 2, 3 ] ]);
+      expect(lastArgs).toMatchObject({ query: 'SELECT * FROM t WHERE id IN $1', values: [ [ 1, 2, 3 ] ] });
+   });
+
    it('fetch_all with attached databases', async () => {
       await Database.get('main.db')
          .fetchAll('SELECT u.name, o.total FROM users u JOIN orders.orders o ON u.id = o.user_id', [])
@@ -174,6 +281,46 @@ describe('Database commands', () => {
       ]);
    });
 
+   it('fetch_all in columnar form', async () => {
+      await Database.get('t.db').fetchAll('SELECT id, name FROM t', []).columnar();
+      expect(lastCmd).toBe('plugin:sqlite|fetch_all');
+      expect(lastArgs).toMatchObject({ db: 't.db', query: 'SELECT id, name FROM t', columnar: true });
+   });
+
+   it('fetch_all with column types', async () => {
+      await Database.get('t.db').fetchAll('SELECT id, at FROM events', []).withColumnTypes();
+      expect(lastCmd).toBe('plugin:sqlite|fetch_all');
+      expect(lastArgs).toMatchObject({ db: 't.db', query: 'SELECT id, at FROM events', columnTypes: true });
+      expect(lastArgs.columnar).toBeUndefined();
+   });
+
+   it('fetch_all with decode options', async () => {
+      await Database.get('t.db').fetchAll('SELECT at FROM events', []).decode({ datetime: 'epochMillis' });
+      expect(lastCmd).toBe('plugin:sqlite|fetch_all');
+      expect(lastArgs).toMatchObject({ decode: { datetime: 'epochMillis' } });
+   });
+
+   it('fetch_all with json format', async () => {
+      await Database.get('t.db').fetchAll('SELECT body FROM docs', []).decode({ json: 'parsed' });
+      expect(lastCmd).toBe('plugin:sqlite|fetch_all');
+      expect(lastArgs).toMatchObject({ decode: { json: 'parsed' } });
+   });
+
+   it('fetch_one with a uuid bind value and format', async () => {
+      const id = '67e55044-10b1-426f-9247-bb680e5fe0c8';
+
+      await Database.get('t.db')
+         .fetchOne('SELECT * FROM accounts WHERE id = $1', [ { $uuid: id } ])
+         .decode({ uuid: 'string' });
+      expect(lastCmd).toBe('plugin:sqlite|fetch_one');
+      expect(lastArgs).toMatchObject({ values: [ { $uuid: id } ], decode: { uuid: 'string' } });
+   });
+
+   it('fetch_all without decode options', async () => {
+      await Database.get('t.db').fetchAll('SELECT at FROM events', []);
+      expect(lastArgs.decode).toBeNull();
+   });
+
    it('fetch_one', async () => {
       await Database.get('t.db').fetchOne('SELECT * FROM t WHERE id = $1', [ 1 ]);
       expect(lastCmd).toBe('plugin:sqlite|fetch_one');
@@ -216,10 +363,54 @@ describe('Database commands', () => {
          pageSize: 25,
          after: null,
          before: null,
+         containing: null,
+         opaqueCursor: false,
+         withTotalCount: false,
          attached: null,
       });
    });
 
+   it('fetch_page with opaque cursor token', async () => {
+      const keyset: KeysetColumn[] = [
+         { name: 'id', direction: 'asc' },
+      ];
+
+      await Database.get('t.db')
+         .fetchPage('SELECT * FROM posts', [], keyset, 25)
+         .opaqueCursor()
+         .after('eyJrIjpbXX0');
+
+      expect(lastCmd).toBe('plugin:sqlite|fetch_page');
+      expect(lastArgs.opaqueCursor).toBe(true);
+      expect(lastArgs.after).toBe('eyJrIjpbXX0');
+   });
+
+   it('fetch_page containing a row', async () => {
+      const keyset: KeysetColumn[] = [
+         { name: 'id', direction: 'asc' },
+      ];
+
+      await Database.get('t.db')
+         .fetchPage('SELECT * FROM posts', [], keyset, 25)
+         .containing({ id: 42 });
+
+      expect(lastCmd).toBe('plugin:sqlite|fetch_page');
+      expect(lastArgs.containing).toEqual({ id: 42 });
+   });
+
+   it('fetch_page with total count', async () => {
+      const keyset: KeysetColumn[] = [
+         { name: 'id', direction: 'asc' },
+      ];
+
+      await Database.get('t.db')
+         .fetchPage('SELECT * FROM posts', [], keyset, 25)
+         .withTotalCount();
+
+      expect(lastCmd).toBe('plugin:sqlite|fetch_page');
+      expect(lastArgs.withTotalCount).toBe(true);
+   });
+
    it('fetch_page with cursor via .after()', async () => {
       const keyset: KeysetColumn[] = [
          { name: 'id', direction: 'asc' },
@@ -332,6 +523,16 @@ describe('Database commands', () => {
       expect(lastCmd).toBe('plugin:sqlite|close_all');
    });
 
+   it('listTransactions', async () => {
+      const list = await Database.listTransactions();
+
+      expect(lastCmd).toBe('plugin:sqlite|list_transactions');
+      expect(list.interruptible).toEqual([
+         { dbPath: 'test.db', tokenPrefix: 'test-tx-', ageMs: 120, statementsExecuted: 3 },
+      ]);
+      expect(list.regular).toEqual([]);
+   });
+
    it('remove', async () => {
       await Database.get('t.db').remove();
       expect(lastCmd).toBe('plugin:sqlite|remove');
@@ -379,7 +580,20 @@ describe('Database commands', () => {
          { query: 'INSERT INTO users (name) VALUES ($1)', values: [ 'Alice' ] },
       ]);
       expect(lastArgs.attached).toBe(null);
+      expect(lastArgs.waitMs).toBe(null);
       expect(tx).toBeInstanceOf(Object);
+      expect(tx.results).toEqual([ { rowsAffected: 1, lastInsertId: 1 } ]);
+   });
+
+   it('beginInterruptibleTransaction with wait', async () => {
+      await Database.get('t.db')
+         .beginInterruptibleTransaction([
+            [ 'INSERT INTO users (name) VALUES ($1)', [ 'Alice' ] ],
+         ])
+         .wait(5000);
+
+      expect(lastCmd).toBe('plugin:sqlite|begin_interruptible_transaction');
+      expect(lastArgs.waitMs).toBe(5000);
    });
 
    it('beginInterruptibleTransaction with attached databases', async () => {
@@ -423,6 +637,26 @@ describe('Database commands', () => {
       expect(lastArgs.token).toEqual({ dbPath: 'test.db', transactionId: 'test-tx-id' });
       expect((lastArgs.action as { type: string }).type).toBe('Continue');
       expect(tx2).toBeInstanceOf(Object);
+      expect(tx2.results).toEqual([ { rowsAffected: 1, lastInsertId: 2 } ]);
+   });
+
+   it('InterruptibleTransaction savepoints', async () => {
+      const tx = await Database.get('test.db').beginInterruptibleTransaction([
+         [ 'INSERT INTO users (name) VALUES ($1)', [ 'Alice' ] ],
+      ]);
+
+      const tx2 = await tx.savepoint('step_1');
+
+      expect(lastCmd).toBe('plugin:sqlite|transaction_continue');
+      expect(lastArgs.token).toEqual({ dbPath: 'test.db', transactionId: 'test-tx-id' });
+      expect(lastArgs.action).toEqual({ type: 'Savepoint', name: 'step_1' });
+      expect(tx2).toBeInstanceOf(InterruptibleTransaction);
+
+      await tx2.rollbackTo('step_1');
+      expect(lastArgs.action).toEqual({ type: 'RollbackTo', name: 'step_1' });
+
+      await tx2.release('step_1');
+      expect(lastArgs.action).toEqual({ type: 'Release', name: 'step_1' });
    });
 
    it('InterruptibleTransaction.commit()', async () => {
@@ -459,6 +693,65 @@ describe('Database commands', () => {
       expect(lastArgs.values).toEqual([ 'Alice' ]);
    });
 
+   it('InterruptibleTransaction.fetchOne()', async () => {
+      const tx = await Database.get('test.db').beginInterruptibleTransaction([
+         [ 'INSERT INTO users (name) VALUES ($1)', [ 'Alice' ] ],
+      ]);
+
+      const row = await tx.fetchOne('SELECT * FROM users WHERE name = $1', [ 'Alice' ]);
+      expect(lastCmd).toBe('plugin:sqlite|transaction_fetch_one');
+      expect(lastArgs.token).toEqual({ dbPath: 'test.db', transactionId: 'test-tx-id' });
+      expect(lastArgs.query).toBe('SELECT * FROM users WHERE name = $1');
+      expect(lastArgs.values).toEqual([ 'Alice' ]);
+      expect(row).toEqual({ id: 1, name: 'Alice' });
+   });
+
+   it('beginReadSnapshot', async () => {
+      const snapshot = await Database.get('test.db').beginReadSnapshot();
+      expect(lastCmd).toBe('plugin:sqlite|begin_read_snapshot');
+      expect(lastArgs.db).toBe('test.db');
+
+      const token = { dbPath: 'test.db', snapshotId: 'test-snapshot-id' };
+
+      const rows = await snapshot.fetchAll('SELECT id FROM orders WHERE id = $1', [ 1 ]);
+      expect(lastCmd).toBe('plugin:sqlite|snapshot_fetch_all');
+      expect(lastArgs.token).toEqual(token);
+      expect(lastArgs.query).toBe('SELECT id FROM orders WHERE id = $1');
+      expect(lastArgs.values).toEqual([ 1 ]);
+      expect(rows).toEqual([ { id: 1 } ]);
+
+      const total = await snapshot.fetchOne('SELECT SUM(total) AS sum FROM orders');
+      expect(lastCmd).toBe('plugin:sqlite|snapshot_fetch_one');
+      expect(lastArgs.values).toEqual([]);
+      expect(total).toEqual({ sum: 42 });
+
+      await snapshot.release();
+      expect(lastCmd).toBe('plugin:sqlite|release_read_snapshot');
+      expect(lastArgs.token).toEqual(token);
+   });
+
+   it('InterruptibleTransaction.abort()', async () => {
+      const tx = await Database.get('test.db').beginInterruptibleTransaction([
+         [ 'INSERT INTO users (name) VALUES ($1)', [ 'Alice' ] ],
+      ]);
+
+      const aborted = await tx.abort();
+      expect(lastCmd).toBe('plugin:sqlite|transaction_abort');
+      expect(lastArgs.token).toEqual({ dbPath: 'test.db', transactionId: 'test-tx-id' });
+      expect(aborted).toBe(true);
+   });
+
+   it('InterruptibleTransaction.heartbeat()', async () => {
+      const tx = await Database.get('test.db').beginInterruptibleTransaction([
+         [ 'INSERT INTO users (name) VALUES ($1)', [ 'Alice' ] ],
+      ]);
+
+      const stats = await tx.heartbeat();
+      expect(lastCmd).toBe('plugin:sqlite|transaction_heartbeat');
+      expect(lastArgs.token).toEqual({ dbPath: 'test.db', transactionId: 'test-tx-id' });
+      expect(stats).toEqual({ statementsExecuted: 2, elapsedMs: 150 });
+   });
+
    it('handles errors from backend', async () => {
       mockIPC(() => {
          throw new Error('Database error');
@@ -524,6 +817,71 @@ describe('Observer commands', () => {
       expect(lastCmd).toBe('plugin:sqlite|unobserve');
       expect(lastArgs.db).toBe('t.db');
    });
+
+   it('observe with emitEvents', async () => {
+      await Database.get('t.db').observe([ 'users' ], { emitEvents: true });
+      expect(lastCmd).toBe('plugin:sqlite|observe');
+      expect(lastArgs.config).toEqual({ emitEvents: true });
+   });
+
+   it('onChange', async () => {
+      const unlisten = await Database.get('t.db').onChange('users', () => {});
+
+      expect(lastCmd).toBe('plugin:event|listen');
+      expect(lastArgs.event).toBe('sqlite://change/t_2edb/users');
+      expect(typeof unlisten).toBe('function');
+   });
+
+   it('watchQuery', async () => {
+      const keyset: KeysetColumn[] = [ { name: 'id', direction: 'asc' } ];
+
+      const watch = await Database.get('t.db').watchQuery(
+         'SELECT * FROM users WHERE active = $1',
+         [ 1 ],
+         keyset,
+         20,
+         () => {},
+         { after: [ 5 ], debounceMs: 50 }
+      );
+
+      expect(lastCmd).toBe('plugin:sqlite|watch_query');
+      expect(lastArgs.db).toBe('t.db');
+      expect(lastArgs.query).toBe('SELECT * FROM users WHERE active = $1');
+      expect(lastArgs.values).toEqual([ 1 ]);
+      expect(lastArgs.keyset).toEqual(keyset);
+      expect(lastArgs.pageSize).toBe(20);
+      expect(lastArgs.after).toEqual([ 5 ]);
+      expect(lastArgs.debounceMs).toBe(50);
+      expect(lastArgs.onEvent).toBeDefined();
+      expect(watch).toBeInstanceOf(QueryWatch);
+      expect(watch.id).toBe('watch-123');
+   });
+
+   it('watchQuery without options', async () => {
+      await Database.get('t.db').watchQuery('SELECT * FROM users', [], [ { name: 'id', direction: 'asc' } ], 20, () => {});
+
+      expect(lastArgs.after).toBeNull();
+      expect(lastArgs.debounceMs).toBeNull();
+   });
+
+   it('unwatchQuery', async () => {
+      const result = await new QueryWatch('watch-456').unwatch();
+
+      expect(lastCmd).toBe('plugin:sqlite|unwatch_query');
+      expect(lastArgs.watchId).toBe('watch-456');
+      expect(result).toBe(true);
+   });
+});
+
+describe('changeEventName', () => {
+   it('keeps letters, digits and dashes', () => {
+      expect(changeEventName('app-1', 'Users2')).toBe('sqlite://change/app-1/Users2');
+   });
+
+   it('encodes every other byte', () => {
+      expect(changeEventName('data/main.db', 'user_posts')).toBe('sqlite://change/data_2fmain_2edb/user_5fposts');
+      expect(changeEventName('t.db', 'café')).toBe('sqlite://change/t_2edb/caf_c3_a9');
+   });
 });
 
 describe('MigrationEvent type', () => {
@@ -1,4 +1,5 @@
 import { Channel, invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 /**
  * Valid SQLite parameter binding value types.
@@ -8,9 +9,47 @@ import { Channel, invoke } from '@tauri-apps/api/core';
  * - `number` - INTEGER, REAL
  * - `boolean` - BOOLEAN
  * - `null` - NULL
- * - `Uint8Array` - BLOB (binary data)
+ * - `Uint8Array` - BLOB (binary data), sent as `{ $base64: string }`. IPC
+ *   arguments are JSON, so bytes grow by a third on the way and large BLOBs
+ *   are better written in chunks or from the Rust side.
+ * - `{ $blob: number[] }` or `{ $base64: string }` - BLOB, from byte values or
+ *   a base64 string such as a fetched BLOB
+ * - `{ $bigint: string }` - INTEGER, for values beyond
+ *   `Number.MAX_SAFE_INTEGER` (see {@link BigIntFormat})
+ * - `{ $uuid: string }` - BLOB, the UUID's 16 bytes (see {@link UuidFormat})
+ * - array - list for an `IN $N` placeholder (written without parentheses),
+ *   which matches any of its elements
  */
-export type SqlValue = string | number | boolean | null | Uint8Array;
+export type SqlValue = string | number | boolean | null | Uint8Array | { $blob: number[] } | { $base64: string } | { $bigint: string } | { $uuid: string } | (string | number | boolean | null)[];
+
+/**
+ * Prepare bind values for IPC, which would otherwise send a `Uint8Array` as an
+ * object keyed by index
+ */
+function encodeBindValues(values: SqlValue[]): SqlValue[] {
+   return values.map((value) => {
+      return value instanceof Uint8Array ? { $base64: encodeBase64(value) } : value;
+   });
+}
+
+const BASE64_ALPHABET = 'ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/';
+
+function encodeBase64(bytes: Uint8Array): string {
+   const chunks: string[] = [];
+
+   for (let i = 0; i < bytes.length; i += 3) {
+      const triple = (bytes[i] << 16) | ((bytes[i + 1] ?? 0) << 8) | (bytes[i + 2] ?? 0);
+
+      chunks.push(
+         BASE64_ALPHABET[(triple >> 18) & 63] +
+         BASE64_ALPHABET[(triple >> 12) & 63] +
+         (i + 1 < bytes.length ? BASE64_ALPHABET[(triple >> 6) & 63] : '=') +
+         (i + 2 < bytes.length ? BASE64_ALPHABET[triple & 63] : '=')
+      );
+   }
+
+   return chunks.join('');
+}
 
 /**
  * Access mode for attached database
@@ -55,6 +94,114 @@ export interface WriteQueryResult {
    lastInsertId: number;
 }
 
+/**
+ * Progress of an interruptible transaction, returned by `heartbeat()`
+ */
+export interface TransactionStats {
+
+   /** Statements run in the transaction so far, including reads */
+   statementsExecuted: number;
+
+   /** Milliseconds since the transaction began */
+   elapsedMs: number;
+}
+
+/**
+ * An active interruptible transaction, as reported by
+ * `Database.listTransactions()`
+ */
+export interface InterruptibleTransactionInfo {
+   dbPath: string;
+
+   /** First characters of the transaction ID, enough to tell transactions apart */
+   tokenPrefix: string;
+
+   /** Milliseconds since the transaction began */
+   ageMs: number;
+
+   statementsExecuted: number;
+}
+
+/**
+ * An in-flight `executeTransaction()` call, as reported by
+ * `Database.listTransactions()`
+ */
+export interface RegularTransactionStatus {
+   dbPath: string;
+
+   /** Milliseconds since the call started, including time waiting for the writer */
+   ageMs: number;
+}
+
+/**
+ * Transactions holding or waiting on a database's writer, across all databases
+ */
+export interface TransactionList {
+   interruptible: InterruptibleTransactionInfo[];
+   regular: RegularTransactionStatus[];
+}
+
+/**
+ * Query results in columnar form, returned by `fetchAll(...).columnar()`.
+ *
+ * Column names appear once instead of in every row, which makes wide result sets
+ * much cheaper to send to the webview.
+ */
+export interface ColumnarResult {
+
+   /** Result column names, in query order (reported even when no rows match). */
+   columns: string[];
+
+   /** One entry per row, with values in the same order as `columns`. */
+   rows: SqlValue[][];
+
+   /**
+    * Columns with TEXT values that were decoded lossily (see the `invalidUtf8`
+    * decode option), so callers know the data was altered.
+    */
+   lossyColumns: string[];
+}
+
+/**
+ * A result column's name and type.
+ */
+export interface ColumnType {
+
+   /** Column name (or alias). */
+   name: string;
+
+   /**
+    * Declared type as written in the table's schema, e.g. `'VARCHAR(80)'`, or
+    * `null` for columns that aren't a table column, such as expressions.
+    */
+   declaredType: string | null;
+
+   /**
+    * SQLite type affinity of the declared type. Columns without a declared
+    * type have `BLOB` affinity, as in SQLite.
+    */
+   affinity: 'INTEGER' | 'REAL' | 'TEXT' | 'BLOB' | 'NUMERIC';
+}
+
+/**
+ * Query results along with each column's type, returned by
+ * `fetchAll(...).withColumnTypes()`.
+ */
+export interface RowsWithColumnTypes<T> {
+
+   /** Result columns, in query order (reported even when no rows match). */
+   columns: ColumnType[];
+
+   /** The rows, as `fetchAll()` returns them. */
+   rows: T;
+
+   /**
+    * Columns with TEXT values that were decoded lossily (see the `invalidUtf8`
+    * decode option), so callers know the data was altered.
+    */
+   lossyColumns: string[];
+}
+
 /**
  * Structured error returned from SQLite operations.
  *
@@ -69,6 +216,16 @@ export interface SqliteError {
    message: string;
 }
 
+/**
+ * Token returned by the plugin for an interruptible transaction that's still
+ * open
+ */
+interface OpenTransactionToken {
+   dbPath: string;
+   transactionId: string;
+   results: WriteQueryResult[];
+}
+
 /**
  * **InterruptibleTransaction**
  *
@@ -79,10 +236,21 @@ export interface SqliteError {
 export class InterruptibleTransaction {
    private readonly _dbPath: string;
    private readonly _transactionId: string;
+   private readonly _results: WriteQueryResult[];
 
-   public constructor(dbPath: string, transactionId: string) {
+   public constructor(dbPath: string, transactionId: string, results: WriteQueryResult[] = []) {
       this._dbPath = dbPath;
       this._transactionId = transactionId;
+      this._results = results;
+   }
+
+   /**
+    * Results of the statements run by the call that returned this handle, one
+    * per statement in order, e.g. to use an inserted row's `lastInsertId` in
+    * later statements. Empty after savepoint operations.
+    */
+   public get results(): WriteQueryResult[] {
+      return this._results;
    }
 
    /**
@@ -114,7 +282,59 @@ export class InterruptibleTransaction {
       return await invoke<T>('plugin:sqlite|transaction_read', {
          token: { dbPath: this._dbPath, transactionId: this._transactionId },
          query,
-         values: bindValues ?? [],
+         values: encodeBindValues(bindValues ?? []),
+      });
+   }
+
+   /**
+    * **fetchOne**
+    *
+    * Read a single row within this transaction, seeing its uncommitted writes.
+    *
+    * Rejects with `MULTIPLE_ROWS_RETURNED` if the query returns more than one
+    * row; the transaction stays open in that case.
+    *
+    * @param query - SELECT query to execute
+    * @param bindValues - Optional parameter values
+    * @returns Promise that resolves with the row, or `undefined` if none matched
+    *
+    * @example
+    * ```ts
+    * const account = await tx.fetchOne<Account>(
+    *    'SELECT balance FROM accounts WHERE id = $1',
+    *    [ 7 ]
+    * );
+    *
+    * tx = await tx.continueWith([
+    *    [ 'UPDATE accounts SET balance = $1 WHERE id = $2', [ account!.balance - 10, 7 ] ],
+    * ]);
+    * ```
+    */
+   public async fetchOne<T>(query: string, bindValues?: SqlValue[]): Promise<T | undefined> {
+      return await invoke<T | undefined>('plugin:sqlite|transaction_fetch_one', {
+         token: { dbPath: this._dbPath, transactionId: this._transactionId },
+         query,
+         values: encodeBindValues(bindValues ?? []),
+      });
+   }
+
+   /**
+    * **heartbeat**
+    *
+    * Restart this transaction's inactivity timeout so a long interactive flow,
+    * such as one waiting on user input, isn't rolled back for being idle.
+    *
+    * @returns Promise that resolves with the statements run so far and the time
+    * since the transaction began
+    *
+    * @example
+    * ```ts
+    * const { statementsExecuted, elapsedMs } = await tx.heartbeat();
+    * ```
+    */
+   public async heartbeat(): Promise<TransactionStats> {
+      return await invoke<TransactionStats>('plugin:sqlite|transaction_heartbeat', {
+         token: { dbPath: this._dbPath, transactionId: this._transactionId },
       });
    }
 
@@ -133,11 +353,15 @@ export class InterruptibleTransaction {
     * tx = await tx.continueWith([
     *    ['INSERT INTO users (name) VALUES ($1)', ['Bob']]
     * ]);
+    * const userId = tx.results[0].lastInsertId;
+    * tx = await tx.continueWith([
+    *    ['INSERT INTO profiles (user_id) VALUES ($1)', [userId]]
+    * ]);
     * await tx.commit();
     * ```
     */
    public async continueWith(statements: Array<[string, SqlValue[]?]>): Promise<InterruptibleTransaction> {
-      const token = await invoke<{ dbPath: string; transactionId: string }>(
+      const token = await invoke<OpenTransactionToken>(
          'plugin:sqlite|transaction_continue',
          {
             token: { dbPath: this._dbPath, transactionId: this._transactionId },
@@ -146,14 +370,75 @@ export class InterruptibleTransaction {
                statements: statements.map(([ query, values ]) => {
                   return {
                      query,
-                     values: values ?? [],
+                     values: encodeBindValues(values ?? []),
                   };
                }),
             },
          }
       );
 
-      return new InterruptibleTransaction(token.dbPath, token.transactionId);
+      return new InterruptibleTransaction(token.dbPath, token.transactionId, token.results);
+   }
+
+   /**
+    * **savepoint**
+    *
+    * Mark a savepoint within this transaction that `rollbackTo(name)` can undo
+    * back to. `name` must be a plain identifier (letters, digits and `_`).
+    *
+    * @param name - Savepoint name
+    * @returns Promise that resolves with a new transaction handle
+    */
+   public async savepoint(name: string): Promise<InterruptibleTransaction> {
+      return await this._savepointAction('Savepoint', name);
+   }
+
+   /**
+    * **release**
+    *
+    * Release a savepoint and any created after it, keeping their changes as
+    * part of the transaction.
+    *
+    * @param name - Name of an active savepoint
+    * @returns Promise that resolves with a new transaction handle
+    */
+   public async release(name: string): Promise<InterruptibleTransaction> {
+      return await this._savepointAction('Release', name);
+   }
+
+   /**
+    * **rollbackTo**
+    *
+    * Undo every change made since a savepoint. The savepoint stays active, so
+    * it can be rolled back to again; savepoints created after it are released.
+    *
+    * @param name - Name of an active savepoint
+    * @returns Promise that resolves with a new transaction handle
+    *
+    * @example
+    * ```ts
+    * tx = await tx.savepoint('draft');
+    * tx = await tx.continueWith([[ 'DELETE FROM items WHERE id = $1', [ 3 ] ]]);
+    * tx = await tx.rollbackTo('draft'); // Item 3 is back
+    * ```
+    */
+   public async rollbackTo(name: string): Promise<InterruptibleTransaction> {
+      return await this._savepointAction('RollbackTo', name);
+   }
+
+   private async _savepointAction(
+      type: 'Savepoint' | 'Release' | 'RollbackTo',
+      name: string
+   ): Promise<InterruptibleTransaction> {
+      const token = await invoke<OpenTransactionToken>(
+         'plugin:sqlite|transaction_continue',
+         {
+            token: { dbPath: this._dbPath, transactionId: this._transactionId },
+            action: { type, name },
+         }
+      );
+
+      return new InterruptibleTransaction(token.dbPath, token.transactionId, token.results);
    }
 
    /**
@@ -193,6 +478,106 @@ export class InterruptibleTransaction {
          action: { type: 'Rollback' },
       });
    }
+
+   /**
+    * **abort**
+    *
+    * Roll back this transaction if it is still open. Unlike `rollback()`, this
+    * doesn't reject when the transaction has already ended or timed out, so it
+    * suits cleanup code such as `finally` blocks and unmount handlers.
+    *
+    * @returns Promise that resolves with `true` if the transaction was rolled
+    * back, or `false` if it had already ended
+    *
+    * @example
+    * ```ts
+    * let tx = await db.beginInterruptibleTransaction([...]);
+    *
+    * try {
+    *    [...]
+    *    await tx.commit();
+    * } finally {
+    *    await tx.abort(); // No-op after a successful commit
+    * }
+    * ```
+    */
+   public async abort(): Promise<boolean> {
+      return await invoke<boolean>('plugin:sqlite|transaction_abort', {
+         token: { dbPath: this._dbPath, transactionId: this._transactionId },
+      });
+   }
+}
+
+/**
+ * Token returned by the plugin for an open read snapshot
+ */
+interface SnapshotToken {
+   dbPath: string;
+   snapshotId: string;
+}
+
+/**
+ * **ReadSnapshot**
+ *
+ * A read connection pinned to one moment in the database's history. Every
+ * fetch sees the same data, even if writes land between them, until
+ * `release()` is called.
+ *
+ * Each open snapshot holds one of the database's read connections, so release
+ * it as soon as the reads are done.
+ */
+export class ReadSnapshot {
+   private readonly _token: SnapshotToken;
+
+   public constructor(token: SnapshotToken) {
+      this._token = token;
+   }
+
+   /**
+    * **fetchAll**
+    *
+    * Fetch all rows as of the snapshot.
+    *
+    * @param query - SELECT query to execute
+    * @param bindValues - Optional parameter values
+    * @returns Promise that resolves with the rows
+    */
+   public async fetchAll<T>(query: string, bindValues?: SqlValue[]): Promise<T> {
+      return await invoke<T>('plugin:sqlite|snapshot_fetch_all', {
+         token: this._token,
+         query,
+         values: encodeBindValues(bindValues ?? []),
+      });
+   }
+
+   /**
+    * **fetchOne**
+    *
+    * Fetch a single row as of the snapshot. Rejects with
+    * `MULTIPLE_ROWS_RETURNED` if the query returns more than one row.
+    *
+    * @param query - SELECT query to execute
+    * @param bindValues - Optional parameter values
+    * @returns Promise that resolves with the row, or `undefined` if none matched
+    */
+   public async fetchOne<T>(query: string, bindValues?: SqlValue[]): Promise<T | undefined> {
+      return await invoke<T | undefined>('plugin:sqlite|snapshot_fetch_one', {
+         token: this._token,
+         query,
+         values: encodeBindValues(bindValues ?? []),
+      });
+   }
+
+   /**
+    * **release**
+    *
+    * End the snapshot and return its connection to the read pool.
+    */
+   public async release(): Promise<void> {
+      await invoke<void>('plugin:sqlite|release_read_snapshot', {
+         token: this._token,
+      });
+   }
 }
 
 /**
@@ -205,6 +590,62 @@ export interface CustomConfig {
 
    /** Idle timeout in seconds for connections. Default: 30 */
    idleTimeoutSecs?: number;
+
+   /** Whether foreign key constraints are enforced on every connection. Default: true */
+   foreignKeys?: boolean;
+
+   /** Enable WAL mode at load time instead of on the first write. Default: false */
+   eagerWalInit?: boolean;
+
+   /** Number of read connections to open at load time. Default: 0 */
+   warmReadConnections?: number;
+
+   /**
+    * Maximum milliseconds a read query may run before SQLite interrupts it.
+    * Default: no limit
+    */
+   readStatementTimeoutMs?: number;
+
+   /**
+    * Run `PRAGMA quick_check` when opening an existing database file. A damaged file
+    * fails `load()` with a `CORRUPT_DATABASE` error.
+    * Default: false
+    */
+   quickCheckOnConnect?: boolean;
+
+   /**
+    * What to do when the integrity check finds a damaged database. `'recover'` moves
+    * the damaged file aside as a backup and rebuilds the database from the rows that
+    * can still be read (also enables the integrity check).
+    * Default: 'fail'
+    */
+   corruptionRecovery?: 'fail' | 'recover';
+
+   /**
+    * Where SQLite keeps temporary tables and indices for large sorts and joins.
+    * Default: 'default' (the compile-time setting)
+    */
+   tempStore?: 'default' | 'file' | 'memory';
+
+   /**
+    * Directory SQLite uses for temporary files. This is process-wide, so every
+    * database should use the same value.
+    * Default: the platform temp directory
+    */
+   tempDirectory?: string;
+
+   /**
+    * Page size in bytes (power of two, 512-65536) used when creating a new database
+    * file. Existing databases keep their page size.
+    * Default: 4096
+    */
+   pageSize?: number;
+
+   /**
+    * Overwrite deleted content with zeros instead of leaving it in free pages.
+    * Default: false
+    */
+   secureDelete?: boolean;
 }
 
 /**
@@ -281,6 +722,24 @@ export interface KeysetColumn {
 
    /** Sort direction for this column */
    direction: SortDirection;
+
+   /**
+    * Where NULLs sort in this column. Defaults to SQLite's ordering: first when
+    * ascending, last when descending.
+    */
+   nulls?: 'first' | 'last';
+
+   /**
+    * Declares that the column never contains NULL (e.g. a primary key), which
+    * keeps the generated cursor condition index-friendly
+    */
+   notNull?: boolean;
+
+   /**
+    * Collating sequence (e.g. `'NOCASE'`) applied to both the ORDER BY and the
+    * cursor comparisons for this column
+    */
+   collation?: string;
 }
 
 /**
@@ -293,11 +752,56 @@ export interface KeysetPage<T = Record<string, SqlValue>> {
    /** The rows in this page */
    rows: T[];
 
-   /** Cursor values to continue pagination, or null if there are no more pages */
+   /**
+    * Cursor values to continue pagination, or null if there are no more pages.
+    * Always null when the page was fetched with `opaqueCursor()`.
+    */
    nextCursor: SqlValue[] | null;
 
+   /**
+    * Opaque cursor token to continue pagination, present only when the page
+    * was fetched with `opaqueCursor()` and there are more pages
+    */
+   nextCursorToken?: string;
+
    /** Whether there are more rows in the current pagination direction */
    hasMore: boolean;
+
+   /**
+    * Cursor of the first row on this page (pass to `before()` for the previous
+    * page), or null if the page is empty. A token with `opaqueCursor()`.
+    */
+   startCursor: SqlValue[] | string | null;
+
+   /**
+    * Cursor of the last row on this page (pass to `after()` for the next
+    * page), or null if the page is empty. A token with `opaqueCursor()`.
+    */
+   endCursor: SqlValue[] | string | null;
+
+   /**
+    * Whether rows precede this page. Exact when paginating backward; going
+    * forward, true whenever the page was positioned past the start.
+    */
+   hasPrevious: boolean;
+
+   /**
+    * Whether rows follow this page. Exact when paginating forward; going
+    * backward, always true.
+    */
+   hasNext: boolean;
+
+   /**
+    * Number of rows preceding this page in the full ordering. Present only
+    * when the page was fetched with `containing()`.
+    */
+   offset?: number;
+
+   /**
+    * Total number of rows matched by the query, ignoring the cursor. Present
+    * only when the page was fetched with `withTotalCount()`.
+    */
+   total?: number;
 }
 
 // ─── Observer Types ───
@@ -323,6 +827,13 @@ export interface ObserverConfig {
     * reducing memory usage per notification.
     */
    captureValues?: boolean;
+
+   /**
+    * Whether to also emit each change as a Tauri event named
+    * `sqlite://change/{db}/{table}` (see `changeEventName()`), for listening
+    * with `onChange()`. Default: false.
+    */
+   emitEvents?: boolean;
 }
 
 /**
@@ -377,6 +888,74 @@ export type TableChangeEvent =
    | { event: 'change'; data: TableChange }
    | { event: 'lagged'; data: { count: number } };
 
+/**
+ * A column's values before and after a change, in a `ChangeEvent`.
+ */
+export interface ColumnChange {
+
+   /** Column name */
+   name: string;
+
+   /** Value before the change (for update and delete) */
+   oldValue?: ColumnValue;
+
+   /** Value after the change (for insert and update) */
+   newValue?: ColumnValue;
+}
+
+/**
+ * Payload of the `sqlite://change/{db}/{table}` events emitted when
+ * observation is enabled with `emitEvents`.
+ */
+export interface ChangeEvent {
+
+   /** The database path, as passed to `Database.load()` */
+   database: string;
+
+   /** Name of the table that was changed */
+   table: string;
+
+   /** The type of change operation, or null when not known */
+   operation: ChangeOperation | null;
+
+   /** Primary key value(s) for the affected row */
+   primaryKey: ColumnValue[];
+
+   /** The SQLite internal rowid (null for WITHOUT ROWID tables) */
+   rowId: number | null;
+
+   /** The row's columns in schema order; empty when values aren't captured */
+   columns: ColumnChange[];
+}
+
+function encodeEventSegment(segment: string): string {
+   let encoded = '';
+
+   for (const byte of new TextEncoder().encode(segment)) {
+      const char = String.fromCharCode(byte);
+
+      if (/^[A-Za-z0-9-]$/.test(char)) {
+         encoded += char;
+      } else {
+         encoded += '_' + byte.toString(16).padStart(2, '0');
+      }
+   }
+
+   return encoded;
+}
+
+/**
+ * Name of the Tauri event changes to `table` in database `db` are emitted
+ * under: `sqlite://change/{db}/{table}`.
+ *
+ * Event names only allow a few characters, so each segment keeps ASCII
+ * letters, digits and `-`, and encodes every other UTF-8 byte as `_` and two
+ * hex digits (`main.db` becomes `main_2edb`).
+ */
+export function changeEventName(db: string, table: string): string {
+   return `sqlite://change/${encodeEventSegment(db)}/${encodeEventSegment(table)}`;
+}
+
 /**
  * Represents an active subscription to table change notifications.
  *
@@ -390,43 +969,255 @@ export class Subscription {
    }
 
    /**
-    * Get the subscription ID.
+    * Get the subscription ID.
+    */
+   public get id(): string {
+      return this._subscriptionId;
+   }
+
+   /**
+    * Stop receiving change notifications for this subscription.
+    *
+    * @returns `true` if the subscription was active and removed
+    */
+   public async unsubscribe(): Promise<boolean> {
+      return await invoke<boolean>('plugin:sqlite|unsubscribe', {
+         subscriptionId: this._subscriptionId,
+      });
+   }
+}
+
+/**
+ * Event yielded by a watched query.
+ *
+ * A `page` event carries the page as of the latest relevant change. An `error`
+ * event means re-running the query failed; the watch stops after it.
+ */
+export type QueryWatchEvent<T = Record<string, SqlValue>> =
+   | { event: 'page'; data: KeysetPage<T> }
+   | { event: 'error'; data: SqliteError };
+
+/**
+ * Options for `watchQuery()`.
+ */
+export interface WatchQueryOptions {
+   /** Cursor to watch the page after, as passed to `FetchPageBuilder.after()` */
+   after?: SqlValue[] | string;
+
+   /**
+    * Milliseconds to wait after the first of a burst of changes before
+    * re-running the query. Default: 0
+    */
+   debounceMs?: number;
+}
+
+/**
+ * Represents an active live query started by `watchQuery()`.
+ *
+ * Use `unwatch()` to stop receiving pages.
+ */
+export class QueryWatch {
+   private readonly _watchId: string;
+
+   public constructor(watchId: string) {
+      this._watchId = watchId;
+   }
+
+   /**
+    * Get the watch ID.
+    */
+   public get id(): string {
+      return this._watchId;
+   }
+
+   /**
+    * Stop re-running the query and receiving pages.
+    *
+    * @returns `true` if the watch was active and stopped
+    */
+   public async unwatch(): Promise<boolean> {
+      return await invoke<boolean>('plugin:sqlite|unwatch_query', {
+         watchId: this._watchId,
+      });
+   }
+}
+
+/**
+ * Builder for SELECT queries returning multiple rows
+ */
+class FetchAllBuilder<T> implements PromiseLike<T> {
+   private readonly _db: Database;
+   private readonly _query: string;
+   private readonly _bindValues: SqlValue[];
+   private _attached: AttachedDatabaseSpec[];
+   private _decode: DecodeOptions | null = null;
+
+   public constructor(
+      db: Database,
+      query: string,
+      bindValues: SqlValue[],
+      attached: AttachedDatabaseSpec[] = []
+   ) {
+      this._db = db;
+      this._query = query;
+      this._bindValues = bindValues;
+      this._attached = attached;
+   }
+
+   /**
+    * Attach databases for cross-database queries
+    */
+   public attach(specs: AttachedDatabaseSpec[]): this {
+      this._attached = specs;
+      return this;
+   }
+
+   /**
+    * Convert this query's values to JSON with `options` instead of the
+    * database's {@link DecodeOptions}. Unset options keep the database's
+    * setting.
+    */
+   public decode(options: DecodeOptions): this {
+      this._decode = options;
+      return this;
+   }
+
+   /**
+    * Make the builder directly awaitable
+    */
+   public then<TResult1 = T, TResult2 = never>(
+      onfulfilled?: ((value: T) => TResult1 | PromiseLike<TResult1>) | null,
+      onrejected?: ((reason: unknown) => TResult2 | PromiseLike<TResult2>) | null
+   ): PromiseLike<TResult1 | TResult2> {
+      return this._execute().then(onfulfilled, onrejected);
+   }
+
+   /**
+    * Return the rows as a {@link ColumnarResult} (`{ columns, rows }`) instead of
+    * one object per row
+    */
+   public columnar(): FetchColumnarBuilder {
+      return new FetchColumnarBuilder(
+         this._db,
+         this._query,
+         this._bindValues,
+         this._attached,
+         this._decode
+      );
+   }
+
+   /**
+    * Return the rows along with each column's name, declared type and affinity
+    * as {@link RowsWithColumnTypes} (`{ columns, rows }`), e.g. to tell dates
+    * and booleans apart without a second query
+    */
+   public withColumnTypes(): FetchWithColumnTypesBuilder<T> {
+      return new FetchWithColumnTypesBuilder<T>(
+         this._db,
+         this._query,
+         this._bindValues,
+         this._attached,
+         this._decode
+      );
+   }
+
+   private async _execute(): Promise<T> {
+      return await invoke<T>('plugin:sqlite|fetch_all', {
+         db: this._db.path,
+         query: this._query,
+         values: encodeBindValues(this._bindValues),
+         attached: this._attached.length > 0 ? this._attached : null,
+         decode: this._decode,
+      });
+   }
+}
+
+/**
+ * Builder for SELECT queries returning their rows along with each column's type
+ */
+class FetchWithColumnTypesBuilder<T> implements PromiseLike<RowsWithColumnTypes<T>> {
+   private readonly _db: Database;
+   private readonly _query: string;
+   private readonly _bindValues: SqlValue[];
+   private _attached: AttachedDatabaseSpec[];
+   private _decode: DecodeOptions | null;
+
+   public constructor(
+      db: Database,
+      query: string,
+      bindValues: SqlValue[],
+      attached: AttachedDatabaseSpec[] = [],
+      decode: DecodeOptions | null = null
+   ) {
+      this._db = db;
+      this._query = query;
+      this._bindValues = bindValues;
+      this._attached = attached;
+      this._decode = decode;
+   }
+
+   /**
+    * Attach databases for cross-database queries
+    */
+   public attach(specs: AttachedDatabaseSpec[]): this {
+      this._attached = specs;
+      return this;
+   }
+
+   /**
+    * Convert this query's values to JSON with `options` instead of the
+    * database's {@link DecodeOptions}. Unset options keep the database's
+    * setting.
     */
-   public get id(): string {
-      return this._subscriptionId;
+   public decode(options: DecodeOptions): this {
+      this._decode = options;
+      return this;
    }
 
    /**
-    * Stop receiving change notifications for this subscription.
-    *
-    * @returns `true` if the subscription was active and removed
+    * Make the builder directly awaitable
     */
-   public async unsubscribe(): Promise<boolean> {
-      return await invoke<boolean>('plugin:sqlite|unsubscribe', {
-         subscriptionId: this._subscriptionId,
+   public then<TResult1 = RowsWithColumnTypes<T>, TResult2 = never>(
+      onfulfilled?: ((value: RowsWithColumnTypes<T>) => TResult1 | PromiseLike<TResult1>) | null,
+      onrejected?: ((reason: unknown) => TResult2 | PromiseLike<TResult2>) | null
+   ): PromiseLike<TResult1 | TResult2> {
+      return this._execute().then(onfulfilled, onrejected);
+   }
+
+   private async _execute(): Promise<RowsWithColumnTypes<T>> {
+      return await invoke<RowsWithColumnTypes<T>>('plugin:sqlite|fetch_all', {
+         db: this._db.path,
+         query: this._query,
+         values: encodeBindValues(this._bindValues),
+         attached: this._attached.length > 0 ? this._attached : null,
+         columnTypes: true,
+         decode: this._decode,
       });
    }
 }
 
 /**
- * Builder for SELECT queries returning multiple rows
+ * Builder for SELECT queries returning their rows in columnar form
  */
-class FetchAllBuilder<T> implements PromiseLike<T> {
+class FetchColumnarBuilder implements PromiseLike<ColumnarResult> {
    private readonly _db: Database;
    private readonly _query: string;
    private readonly _bindValues: SqlValue[];
    private _attached: AttachedDatabaseSpec[];
+   private _decode: DecodeOptions | null;
 
    public constructor(
       db: Database,
       query: string,
       bindValues: SqlValue[],
-      attached: AttachedDatabaseSpec[] = []
+      attached: AttachedDatabaseSpec[] = [],
+      decode: DecodeOptions | null = null
    ) {
       this._db = db;
       this._query = query;
       this._bindValues = bindValues;
       this._attached = attached;
+      this._decode = decode;
    }
 
    /**
@@ -437,22 +1228,34 @@ class FetchAllBuilder<T> implements PromiseLike<T> {
       return this;
    }
 
+   /**
+    * Convert this query's values to JSON with `options` instead of the
+    * database's {@link DecodeOptions}. Unset options keep the database's
+    * setting.
+    */
+   public decode(options: DecodeOptions): this {
+      this._decode = options;
+      return this;
+   }
+
    /**
     * Make the builder directly awaitable
     */
-   public then<TResult1 = T, TResult2 = never>(
-      onfulfilled?: ((value: T) => TResult1 | PromiseLike<TResult1>) | null,
+   public then<TResult1 = ColumnarResult, TResult2 = never>(
+      onfulfilled?: ((value: ColumnarResult) => TResult1 | PromiseLike<TResult1>) | null,
       onrejected?: ((reason: unknown) => TResult2 | PromiseLike<TResult2>) | null
    ): PromiseLike<TResult1 | TResult2> {
       return this._execute().then(onfulfilled, onrejected);
    }
 
-   private async _execute(): Promise<T> {
-      return await invoke<T>('plugin:sqlite|fetch_all', {
+   private async _execute(): Promise<ColumnarResult> {
+      return await invoke<ColumnarResult>('plugin:sqlite|fetch_all', {
          db: this._db.path,
          query: this._query,
-         values: this._bindValues,
+         values: encodeBindValues(this._bindValues),
          attached: this._attached.length > 0 ? this._attached : null,
+         columnar: true,
+         decode: this._decode,
       });
    }
 }
@@ -465,6 +1268,7 @@ class FetchOneBuilder<T> implements PromiseLike<T | undefined> {
    private readonly _query: string;
    private readonly _bindValues: SqlValue[];
    private _attached: AttachedDatabaseSpec[];
+   private _decode: DecodeOptions | null = null;
 
    public constructor(
       db: Database,
@@ -486,6 +1290,16 @@ class FetchOneBuilder<T> implements PromiseLike<T | undefined> {
       return this;
    }
 
+   /**
+    * Convert this query's values to JSON with `options` instead of the
+    * database's {@link DecodeOptions}. Unset options keep the database's
+    * setting.
+    */
+   public decode(options: DecodeOptions): this {
+      this._decode = options;
+      return this;
+   }
+
    /**
     * Make the builder directly awaitable
     */
@@ -500,8 +1314,9 @@ class FetchOneBuilder<T> implements PromiseLike<T | undefined> {
       return await invoke<T | undefined>('plugin:sqlite|fetch_one', {
          db: this._db.path,
          query: this._query,
-         values: this._bindValues,
+         values: encodeBindValues(this._bindValues),
          attached: this._attached.length > 0 ? this._attached : null,
+         decode: this._decode,
       });
    }
 }
@@ -515,9 +1330,13 @@ class FetchPageBuilder<T> implements PromiseLike<KeysetPage<T>> {
    private readonly _bindValues: SqlValue[];
    private readonly _keyset: KeysetColumn[];
    private readonly _pageSize: number;
-   private _after: SqlValue[] | null;
-   private _before: SqlValue[] | null;
+   private _after: SqlValue[] | string | null;
+   private _before: SqlValue[] | string | null;
+   private _containing: Record<string, SqlValue> | null;
+   private _opaqueCursor: boolean;
+   private _withTotalCount: boolean;
    private _attached: AttachedDatabaseSpec[];
+   private _decode: DecodeOptions | null = null;
 
    public constructor(
       db: Database,
@@ -533,16 +1352,19 @@ class FetchPageBuilder<T> implements PromiseLike<KeysetPage<T>> {
       this._pageSize = pageSize;
       this._after = null;
       this._before = null;
+      this._containing = null;
+      this._opaqueCursor = false;
+      this._withTotalCount = false;
       this._attached = [];
    }
 
    /**
     * Set the cursor for fetching the next page (forward pagination).
     *
-    * Pass the `nextCursor` from a previous `KeysetPage` to fetch the page
-    * that follows it in the original sort order.
+    * Pass the `nextCursor` (or `nextCursorToken`) from a previous `KeysetPage`
+    * to fetch the page that follows it in the original sort order.
     */
-   public after(cursor: SqlValue[]): this {
+   public after(cursor: SqlValue[] | string): this {
       this._after = cursor;
       return this;
    }
@@ -553,11 +1375,43 @@ class FetchPageBuilder<T> implements PromiseLike<KeysetPage<T>> {
     * Pass a cursor to fetch the page that precedes it in the original sort
     * order. Rows are returned in the original sort order (not reversed).
     */
-   public before(cursor: SqlValue[]): this {
+   public before(cursor: SqlValue[] | string): this {
       this._before = cursor;
       return this;
    }
 
+   /**
+    * Fetch the page containing the row whose columns equal `key` (typically
+    * its primary key, e.g. `{ id: 42 }`), for "jump to item" navigation.
+    *
+    * The page is the one forward pagination from the first page would reach;
+    * its `offset` reports how many rows precede it. Takes precedence over
+    * `after()`/`before()`.
+    */
+   public containing(key: Record<string, SqlValue>): this {
+      this._containing = key;
+      return this;
+   }
+
+   /**
+    * Return the continuation cursor as an opaque string in `nextCursorToken`
+    * instead of raw values in `nextCursor`. The token is bound to this
+    * keyset and is rejected if replayed against a different one.
+    */
+   public opaqueCursor(): this {
+      this._opaqueCursor = true;
+      return this;
+   }
+
+   /**
+    * Also count every row matched by the query and return it as `total`, for
+    * "page X of Y" displays. The count scans the full result set.
+    */
+   public withTotalCount(): this {
+      this._withTotalCount = true;
+      return this;
+   }
+
    /**
     * Attach databases for cross-database queries
     */
@@ -566,6 +1420,16 @@ class FetchPageBuilder<T> implements PromiseLike<KeysetPage<T>> {
       return this;
    }
 
+   /**
+    * Convert this query's values to JSON with `options` instead of the
+    * database's {@link DecodeOptions}. Unset options keep the database's
+    * setting.
+    */
+   public decode(options: DecodeOptions): this {
+      this._decode = options;
+      return this;
+   }
+
    /**
     * Make the builder directly awaitable
     */
@@ -580,12 +1444,16 @@ class FetchPageBuilder<T> implements PromiseLike<KeysetPage<T>> {
       return await invoke<KeysetPage<T>>('plugin:sqlite|fetch_page', {
          db: this._db.path,
          query: this._query,
-         values: this._bindValues,
+         values: encodeBindValues(this._bindValues),
          keyset: this._keyset,
          pageSize: this._pageSize,
          after: this._after,
          before: this._before,
+         containing: this._containing,
+         opaqueCursor: this._opaqueCursor,
+         withTotalCount: this._withTotalCount,
          attached: this._attached.length > 0 ? this._attached : null,
+         decode: this._decode,
       });
    }
 }
@@ -635,7 +1503,7 @@ class ExecuteBuilder implements PromiseLike<WriteQueryResult> {
          {
             db: this._db.path,
             query: this._query,
-            values: this._bindValues,
+            values: encodeBindValues(this._bindValues),
             attached: this._attached.length > 0 ? this._attached : null,
          }
       );
@@ -654,6 +1522,7 @@ class InterruptibleTransactionBuilder implements PromiseLike<InterruptibleTransa
    private readonly _db: Database;
    private readonly _initialStatements: Array<[string, SqlValue[]?]>;
    private _attached: AttachedDatabaseSpec[];
+   private _waitMs: number | null = null;
 
    public constructor(
       db: Database,
@@ -673,6 +1542,17 @@ class InterruptibleTransactionBuilder implements PromiseLike<InterruptibleTransa
       return this;
    }
 
+   /**
+    * Wait up to `ms` milliseconds for the database's active interruptible
+    * transaction to end instead of failing with `TRANSACTION_ALREADY_ACTIVE`.
+    * Callers waiting on the same database begin in the order they asked.
+    * Fails with `TRANSACTION_WAIT_TIMEOUT` if the wait runs out.
+    */
+   public wait(ms: number): this {
+      this._waitMs = ms;
+      return this;
+   }
+
    /**
     * Make the builder directly awaitable
     */
@@ -684,24 +1564,184 @@ class InterruptibleTransactionBuilder implements PromiseLike<InterruptibleTransa
    }
 
    private async _execute(): Promise<InterruptibleTransaction> {
-      const token = await invoke<{ dbPath: string; transactionId: string }>(
+      const token = await invoke<OpenTransactionToken>(
          'plugin:sqlite|begin_interruptible_transaction',
          {
             db: this._db.path,
             initialStatements: this._initialStatements.map(([ query, values ]) => {
                return {
                   query,
-                  values: values ?? [],
+                  values: encodeBindValues(values ?? []),
                };
             }),
             attached: this._attached.length > 0 ? this._attached : null,
+            waitMs: this._waitMs,
          }
       );
 
-      return new InterruptibleTransaction(token.dbPath, token.transactionId);
+      return new InterruptibleTransaction(token.dbPath, token.transactionId, token.results);
    }
 }
 
+/**
+ * How a transaction begins:
+ * - `deferred` - takes no lock until the first read or write; suits
+ *   read-mostly transactions
+ * - `immediate` - takes the write lock straight away (default)
+ * - `exclusive` - like `immediate`, and outside WAL mode also keeps other
+ *   connections from reading
+ */
+export type TransactionMode = 'deferred' | 'immediate' | 'exclusive';
+
+/**
+ * Backoff for retrying a transaction's `BEGIN` while the database is busy.
+ * Unset fields use the defaults: 5 attempts, starting at 20ms and doubling up
+ * to 1000ms between attempts.
+ */
+export interface RetryOptions {
+
+   /** Total attempts, including the first */
+   maxAttempts?: number;
+
+   /** Delay after the first failed attempt, in milliseconds */
+   initialBackoffMs?: number;
+
+   /** Upper bound for any single delay, in milliseconds */
+   maxBackoffMs?: number;
+}
+
+/**
+ * Settings for `Database.enableWriteBatching()`. Unset fields use the
+ * defaults: a 5ms window and at most 100 writes per batch.
+ */
+export interface WriteBatchOptions {
+
+   /**
+    * How long a batch waits for more writes after its first one arrives, in
+    * milliseconds. Each write can be delayed by up to this long.
+    */
+   windowMs?: number;
+
+   /** Commit a batch as soon as it holds this many writes */
+   maxBatchSize?: number;
+}
+
+/**
+ * How values in columns declared `DATETIME` or `TIMESTAMP` are returned:
+ * - `raw` - as stored, TEXT as a string and INTEGER as a number (default)
+ * - `iso8601` - as an ISO-8601 string in UTC, e.g. `2024-01-15T10:30:00Z`
+ * - `epochMillis` - as milliseconds since the Unix epoch, ready for `new Date()`
+ *
+ * TEXT values are parsed as SQLite date-time strings, taken as UTC when they
+ * have no offset, and INTEGER values as Unix seconds. Values that can't be
+ * parsed are returned as stored.
+ */
+export type DatetimeFormat = 'raw' | 'iso8601' | 'epochMillis';
+
+/**
+ * How INTEGER values outside JavaScript's safe integer range (±2^53 - 1) are
+ * returned. Integers inside the range are always returned as numbers.
+ *
+ * - `number` - as a number, which may lose precision (default)
+ * - `string` - as a decimal string, e.g. `'9007199254740993'`
+ * - `tagged` - as `{ $bigint: '9007199254740993' }`, which can be passed
+ *   back as a bind value
+ */
+export type BigIntFormat = 'number' | 'string' | 'tagged';
+
+/**
+ * How TEXT values holding JSON are returned:
+ *
+ * - `text` - as stored, a string (default)
+ * - `parsed` - parsed, saving a `JSON.parse()` per value: any valid JSON in
+ *   columns declared `JSON`, and objects or arrays in other columns. Invalid
+ *   documents, and JSON scalars such as `'true'` outside `JSON` columns, are
+ *   still returned as strings
+ */
+export type JsonFormat = 'text' | 'parsed';
+
+/**
+ * How 16-byte BLOBs in columns declared `UUID` are returned (other BLOBs
+ * stay base64):
+ *
+ * - `base64` - base64-encoded, like any other BLOB (default)
+ * - `string` - as a canonical UUID string, e.g.
+ *   `'67e55044-10b1-426f-9247-bb680e5fe0c8'`
+ *
+ * Bind `{ $uuid: '…' }` to store a UUID string as a 16-byte BLOB.
+ */
+export type UuidFormat = 'base64' | 'string';
+
+/**
+ * How integers in columns declared `BOOLEAN` or `BOOL` are returned:
+ *
+ * - `integer` - as stored, `0` or `1` (default)
+ * - `boolean` - as `false` for `0` and `true` otherwise
+ */
+export type BooleanFormat = 'integer' | 'boolean';
+
+/**
+ * How NaN and infinite REAL values, which JSON can't represent, are returned:
+ *
+ * - `null` - as `null` (default)
+ * - `string` - as `'NaN'`, `'Infinity'` or `'-Infinity'`, which `Number()`
+ *   parses back
+ * - `error` - the query fails with `NON_FINITE_FLOAT`
+ */
+export type NonFiniteFormat = 'null' | 'string' | 'error';
+
+/**
+ * How TEXT values that aren't valid UTF-8 are returned:
+ *
+ * - `null` - as `null` (default)
+ * - `lossy` - with `'\uFFFD'` replacing invalid bytes, logging a warning that
+ *   names the column once per query. Only columnar and `withColumnTypes()`
+ *   results also list such columns, in `lossyColumns`
+ * - `error` - the query fails with `INVALID_UTF8`, naming the column
+ */
+export type InvalidUtf8Format = 'null' | 'lossy' | 'error';
+
+/**
+ * How column names are turned into row keys:
+ *
+ * - `asIs` - as named in the result set (default)
+ * - `camelCase` - with `snake_case` names converted, e.g. `created_at` to
+ *   `createdAt`. SQL still refers to columns by their own names, and queries
+ *   with two names converted to the same key fail with `COLUMN_KEY_COLLISION`
+ */
+export type ColumnNameFormat = 'asIs' | 'camelCase';
+
+/**
+ * How fetched values are converted to JSON, set per database with
+ * `Database.setDecodeOptions()` or per query with `.decode()`.
+ */
+export interface DecodeOptions {
+
+   /** How `DATETIME`/`TIMESTAMP` columns are returned */
+   datetime?: DatetimeFormat;
+
+   /** How integers beyond `Number.MAX_SAFE_INTEGER` are returned */
+   bigint?: BigIntFormat;
+
+   /** How TEXT values holding JSON are returned */
+   json?: JsonFormat;
+
+   /** How 16-byte BLOBs in columns declared `UUID` are returned */
+   uuid?: UuidFormat;
+
+   /** How integers in `BOOLEAN` columns are returned */
+   boolean?: BooleanFormat;
+
+   /** How NaN and infinite floats are returned */
+   nonFinite?: NonFiniteFormat;
+
+   /** How TEXT values that aren't valid UTF-8 are returned */
+   invalidUtf8?: InvalidUtf8Format;
+
+   /** How column names are turned into row keys */
+   columnNames?: ColumnNameFormat;
+}
+
 /**
  * Builder for transaction operations
  */
@@ -709,6 +1749,8 @@ class TransactionBuilder implements PromiseLike<WriteQueryResult[]> {
    private readonly _db: Database;
    private readonly _statements: Array<[string, SqlValue[]?]>;
    private _attached: AttachedDatabaseSpec[];
+   private _mode: TransactionMode | null = null;
+   private _beginRetry: RetryOptions | null = null;
 
    public constructor(
       db: Database,
@@ -728,6 +1770,35 @@ class TransactionBuilder implements PromiseLike<WriteQueryResult[]> {
       return this;
    }
 
+   /**
+    * Set how the transaction begins (default: `immediate`)
+    *
+    * @example
+    * ```ts
+    * await db.executeTransaction(statements).mode('exclusive');
+    * ```
+    */
+   public mode(mode: TransactionMode): this {
+      this._mode = mode;
+      return this;
+   }
+
+   /**
+    * Retry starting the transaction with backoff when `BEGIN` fails because
+    * another connection or process holds the database's lock. Only `BEGIN` is
+    * retried; a statement failing later still rolls the transaction back. Once
+    * the attempts run out, rejects with `BUSY_RETRIES_EXHAUSTED`.
+    *
+    * @example
+    * ```ts
+    * await db.executeTransaction(statements).retryBegin({ maxAttempts: 10 });
+    * ```
+    */
+   public retryBegin(options: RetryOptions = {}): this {
+      this._beginRetry = options;
+      return this;
+   }
+
    /**
     * Make the builder directly awaitable
     */
@@ -744,10 +1815,12 @@ class TransactionBuilder implements PromiseLike<WriteQueryResult[]> {
          statements: this._statements.map(([ query, values ]) => {
             return {
                query,
-               values: values ?? [],
+               values: encodeBindValues(values ?? []),
             };
          }),
          attached: this._attached.length > 0 ? this._attached : null,
+         mode: this._mode,
+         beginRetry: this._beginRetry,
       });
    }
 }
@@ -838,6 +1911,29 @@ export default class Database {
       await invoke<void>('plugin:sqlite|close_all');
    }
 
+   /**
+    * **listTransactions**
+    *
+    * Lists the active interruptible transactions and in-flight
+    * `executeTransaction()` calls across all databases. Useful for diagnosing
+    * "database is locked" errors and transactions that were never committed.
+    *
+    * An interruptible transaction that is running statements at that moment
+    * is listed with the statement count it had before they started.
+    *
+    * @example
+    * ```ts
+    * const { interruptible } = await Database.listTransactions();
+    *
+    * for (const tx of interruptible) {
+    *    console.log(`${tx.dbPath}: ${tx.tokenPrefix} open for ${tx.ageMs}ms`);
+    * }
+    * ```
+    */
+   public static async listTransactions(): Promise<TransactionList> {
+      return await invoke<TransactionList>('plugin:sqlite|list_transactions');
+   }
+
    /**
     * **execute**
     *
@@ -876,6 +1972,77 @@ export default class Database {
       return new ExecuteBuilder(this, query, bindValues ?? []);
    }
 
+   /**
+    * **enableWriteBatching**
+    *
+    * Coalesces plain `execute()` calls that arrive within a short window into
+    * a single transaction, committed in the background. This greatly raises
+    * throughput for apps that fire many tiny independent writes, such as event
+    * logging, at the cost of delaying each write by up to the window.
+    *
+    * Each write still resolves with its own result, and a failing statement
+    * rejects only its own promise; a write resolves once its whole batch has
+    * committed. Writes with attached databases are never batched.
+    *
+    * Calling this again replaces the previous settings.
+    *
+    * @param options - Optional window and batch size
+    *
+    * @example
+    * ```ts
+    * await db.enableWriteBatching({ windowMs: 10 });
+    *
+    * // Inserts fired together commit in one transaction
+    * await Promise.all(events.map((e) => {
+    *    return db.execute('INSERT INTO events (name) VALUES ($1)', [ e.name ]);
+    * }));
+    * ```
+    */
+   public async enableWriteBatching(options?: WriteBatchOptions): Promise<void> {
+      await invoke<void>('plugin:sqlite|enable_write_batching', {
+         db: this.path,
+         options: options ?? null,
+      });
+   }
+
+   /**
+    * **disableWriteBatching**
+    *
+    * Stops batching writes, resolving once the queued ones have committed.
+    */
+   public async disableWriteBatching(): Promise<void> {
+      await invoke<void>('plugin:sqlite|disable_write_batching', {
+         db: this.path,
+      });
+   }
+
+   /**
+    * **setDecodeOptions**
+    *
+    * Sets how values fetched from this database are converted to JSON. Applies
+    * to fetches, pages, read snapshots and interruptible transactions begun
+    * afterwards; a query's own `.decode()` options take precedence. Unset
+    * options go back to their defaults.
+    *
+    * @param options - The decode options
+    *
+    * @example
+    * ```ts
+    * await db.setDecodeOptions({ datetime: 'epochMillis' });
+    *
+    * const [ event ] = await db.fetchAll<{ at: number }[]>(
+    *    'SELECT at FROM events LIMIT 1'
+    * );
+    * const at = new Date(event.at);
+    * ```
+    */
+   public async setDecodeOptions(options: DecodeOptions): Promise<void> {
+      await invoke<void>('plugin:sqlite|set_decode_options', {
+         db: this.path,
+         options,
+      });
+   }
+
    /**
     * **executeTransaction**
     *
@@ -952,6 +2119,9 @@ export default class Database {
     *    [id]
     * );
     *
+    * // Columnar results: { columns: ['id', 'title'], rows: [[1, 'Buy milk'], ...] }
+    * const table = await db.fetchAll("SELECT id, title FROM todos", []).columnar();
+    *
     * // Cross-database query with attached database
     * const results = await db.fetchAll(
     *    "SELECT u.name, o.total FROM users u JOIN orders.orders o ON u.id = o.user_id",
@@ -1161,6 +2331,104 @@ export default class Database {
       return new Subscription(subscriptionId);
    }
 
+   /**
+    * **watchQuery**
+    *
+    * Watch a paginated SELECT query, re-running it whenever a table it reads
+    * changes.
+    *
+    * The current page is sent to `onEvent` straight away, then again after each
+    * change that alters it; re-runs that return the same page are skipped. Set
+    * `debounceMs` to coalesce bursts of changes into one re-run.
+    *
+    * Requires `observe()` to have been called for the tables the query reads.
+    * Watches count toward the same per-database limit as subscriptions.
+    *
+    * @param query - SELECT query, without ORDER BY or LIMIT
+    * @param bindValues - Values for the query's placeholders
+    * @param keyset - Columns the page is ordered and paginated by
+    * @param pageSize - Maximum number of rows in the page
+    * @param onEvent - Callback invoked with each new page
+    * @param options - Cursor to watch from and debounce interval
+    * @returns A QueryWatch that can be used to stop watching
+    *
+    * @example
+    * ```ts
+    * await db.observe(['todos']);
+    *
+    * const watch = await db.watchQuery<Todo>(
+    *    'SELECT id, title FROM todos WHERE done = $1',
+    *    [ 0 ],
+    *    [ { name: 'id', direction: 'asc' } ],
+    *    50,
+    *    (event) => {
+    *       if (event.event === 'page') {
+    *          renderTodos(event.data.rows);
+    *       }
+    *    },
+    *    { debounceMs: 50 },
+    * );
+    *
+    * // Later, stop watching
+    * await watch.unwatch();
+    * ```
+    */
+   public async watchQuery<T = Record<string, SqlValue>>(
+      query: string,
+      bindValues: SqlValue[],
+      keyset: KeysetColumn[],
+      pageSize: number,
+      onEvent: (event: QueryWatchEvent<T>) => void,
+      options?: WatchQueryOptions
+   ): Promise<QueryWatch> {
+      const channel = new Channel<QueryWatchEvent<T>>();
+
+      channel.onmessage = onEvent;
+
+      const watchId = await invoke<string>('plugin:sqlite|watch_query', {
+         db: this.path,
+         query,
+         values: encodeBindValues(bindValues),
+         keyset,
+         pageSize,
+         after: options?.after ?? null,
+         debounceMs: options?.debounceMs ?? null,
+         onEvent: channel,
+      });
+
+      return new QueryWatch(watchId);
+   }
+
+   /**
+    * **onChange**
+    *
+    * Listen for the change events emitted for `table`.
+    *
+    * Events are only emitted once `observe()` has been called with
+    * `emitEvents: true` for a set of tables including `table`.
+    *
+    * @param table - Table to receive change events for
+    * @param handler - Callback invoked for each change
+    * @returns A function that stops listening
+    *
+    * @example
+    * ```ts
+    * await db.observe(['users'], { emitEvents: true });
+    *
+    * const unlisten = await db.onChange('users', (change) => {
+    *    console.log(`${change.operation} of user`, change.primaryKey);
+    * });
+    *
+    * // Later, stop listening
+    * unlisten();
+    * ```
+    */
+   public async onChange(table: string, handler: (change: ChangeEvent) => void): Promise<UnlistenFn> {
+      return await listen<ChangeEvent>(changeEventName(this.path, table), (event) => {
+         handler(event.payload);
+      });
+   }
+
    /**
     * **unobserve**
     *
@@ -1214,6 +2482,9 @@ export default class Database {
     * **Warning:** This permanently deletes the database files from disk.
     * Use with caution!
     *
+    * Rejects with a `DATABASE_IN_USE` error (and leaves the files in place) if the
+    * database is still being used elsewhere, e.g. by an in-flight transaction.
+    *
     * @returns `true` if the database was loaded and successfully removed,
     *          `false` if the database was not loaded (nothing to remove)
     *
@@ -1257,6 +2528,8 @@ export default class Database {
     *
     * **Important:** Only one transaction can be active per database at a time. The
     * writer connection is held for the entire duration - keep transactions short.
+    * A second call fails with `TRANSACTION_ALREADY_ACTIVE` unless it queues
+    * with `.wait(ms)`.
     *
     * @param initialStatements - Array of [query, values?] tuples to execute initially
     * @returns Builder for setting up the transaction with optional attached databases
@@ -1303,6 +2576,16 @@ export default class Database {
     *
     * await tx.commit();
     * ```
+    *
+    * @example
+    * ```ts
+    * // Wait up to 5 seconds for another interruptible transaction to finish
+    * const tx = await db.beginInterruptibleTransaction([
+    *    ['UPDATE counters SET value = value + 1 WHERE name = $1', ['visits']]
+    * ]).wait(5000);
+    *
+    * await tx.commit();
+    * ```
     */
    public beginInterruptibleTransaction(
       initialStatements: Array<[string, SqlValue[]?]>
@@ -1310,6 +2593,40 @@ export default class Database {
       return new InterruptibleTransactionBuilder(this, initialStatements);
    }
 
+   /**
+    * **beginReadSnapshot**
+    *
+    * Begins a read snapshot: a sequence of reads that all see the database as
+    * of this moment, without any writes that land in between. Use it for
+    * multi-query reports whose numbers must agree with each other.
+    *
+    * Writes are not blocked while the snapshot is open. Snapshots left open
+    * longer than the transaction timeout are released automatically.
+    *
+    * @returns Promise that resolves with the open snapshot
+    *
+    * @example
+    * ```ts
+    * const snapshot = await db.beginReadSnapshot();
+    *
+    * try {
+    *    const orders = await snapshot.fetchAll<Order[]>('SELECT * FROM orders');
+    *    const total = await snapshot.fetchOne<{ sum: number }>(
+    *       'SELECT SUM(total) AS sum FROM orders'
+    *    );
+    * } finally {
+    *    await snapshot.release();
+    * }
+    * ```
+    */
+   public async beginReadSnapshot(): Promise<ReadSnapshot> {
+      const token = await invoke<SnapshotToken>('plugin:sqlite|begin_read_snapshot', {
+         db: this.path,
+      });
+
+      return new ReadSnapshot(token);
+   }
+
    /**
     * **getMigrationEvents**
     *
@@ -8,23 +8,32 @@ use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use sqlx_sqlite_conn_mgr::SqliteDatabaseConfig;
+use sqlx_sqlite_observer::TableChangeEvent;
 use sqlx_sqlite_toolkit::{
-   ActiveInterruptibleTransaction, ActiveInterruptibleTransactions, ActiveRegularTransactions,
-   DatabaseWrapper, Statement, TransactionWriter, WriteQueryResult,
+   ActiveInterruptibleTransaction, ActiveInterruptibleTransactions, ActiveReadSnapshots,
+   ActiveRegularTransactions, BigIntFormat, BooleanFormat, ColumnNameFormat, DatabaseWrapper,
+   DatetimeFormat, DecodeOptions, InterruptibleTransactionInfo, InvalidUtf8Format, JsonFormat,
+   NonFiniteFormat, RetryPolicy, Statement, TransactionMode, TransactionStats, TransactionWriter,
+   UuidFormat, WriteBatchConfig, WriteQueryResult,
 };
 use std::sync::Arc;
 use tauri::ipc::Channel;
-use tauri::{AppHandle, Runtime, State};
+use tauri::{AppHandle, Emitter, Runtime, State};
 use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-   DbInstances, Error, MigrationEvent, MigrationStates, MigrationStatus, Result,
+   DbInstances, Decoders, Error, MigrationEvent, MigrationStates, MigrationStatus, PreCommitHooks,
+   Result,
    subscriptions::{
-      ActiveSubscriptions, ObserverConfigParams, TableChangePayload, event_to_payload,
+      ActiveSubscriptions, ObserverConfigParams, TableChangePayload, WatchQueryPayload,
+      change_event_name, change_to_event, event_to_payload,
    },
 };
 
+/// Maximum number of subscriptions and query watches per database
+const MAX_SUBSCRIPTIONS_PER_DATABASE: usize = 100;
+
 /// Token representing an active interruptible transaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -33,15 +42,149 @@ pub struct TransactionToken {
    pub transaction_id: String,
 }
 
+/// Token representing an open read snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotToken {
+   pub db_path: String,
+   pub snapshot_id: String,
+}
+
+/// Token for an interruptible transaction that's still open, with the results
+/// of the statements that just ran in it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenTransaction {
+   #[serde(flatten)]
+   pub token: TransactionToken,
+   /// One result per statement, in order. Empty after savepoint actions.
+   pub results: Vec<WriteQueryResult>,
+}
+
+/// Transactions currently holding or waiting on a database's writer, from
+/// `list_transactions`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionList {
+   pub interruptible: Vec<InterruptibleTransactionInfo>,
+   pub regular: Vec<RegularTransactionStatus>,
+}
+
+/// An in-flight `execute_transaction` call
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegularTransactionStatus {
+   pub db_path: String,
+   /// Milliseconds since the call started, including time spent waiting for
+   /// the writer
+   pub age_ms: u64,
+}
+
 /// Actions that can be taken on an interruptible transaction
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum TransactionAction {
    Continue { statements: Vec<Statement> },
+   Savepoint { name: String },
+   Release { name: String },
+   RollbackTo { name: String },
    Commit,
    Rollback,
 }
 
+/// Backoff for retrying a busy `BEGIN`. Unset fields keep the
+/// `RetryPolicy::default()` values.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryOptions {
+   pub max_attempts: Option<u32>,
+   pub initial_backoff_ms: Option<u64>,
+   pub max_backoff_ms: Option<u64>,
+}
+
+impl From<RetryOptions> for RetryPolicy {
+   fn from(options: RetryOptions) -> Self {
+      let default = RetryPolicy::default();
+      RetryPolicy {
+         max_attempts: options.max_attempts.unwrap_or(default.max_attempts),
+         initial_backoff: options
+            .initial_backoff_ms
+            .map_or(default.initial_backoff, std::time::Duration::from_millis),
+         max_backoff: options
+            .max_backoff_ms
+            .map_or(default.max_backoff, std::time::Duration::from_millis),
+         ..default
+      }
+   }
+}
+
+/// Write batching settings. Unset fields keep the `WriteBatchConfig::default()`
+/// values.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteBatchOptions {
+   pub window_ms: Option<u64>,
+   pub max_batch_size: Option<usize>,
+}
+
+/// How fetched values are converted to JSON. Unset fields keep the values of
+/// the options they're applied to.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodeParams {
+   pub datetime: Option<DatetimeFormat>,
+   pub bigint: Option<BigIntFormat>,
+   pub json: Option<JsonFormat>,
+   pub uuid: Option<UuidFormat>,
+   pub boolean: Option<BooleanFormat>,
+   pub non_finite: Option<NonFiniteFormat>,
+   pub invalid_utf8: Option<InvalidUtf8Format>,
+   pub column_names: Option<ColumnNameFormat>,
+}
+
+impl DecodeParams {
+   fn apply(self, mut options: DecodeOptions) -> DecodeOptions {
+      if let Some(datetime) = self.datetime {
+         options = options.with_datetime_format(datetime);
+      }
+      if let Some(bigint) = self.bigint {
+         options = options.with_bigint_format(bigint);
+      }
+      if let Some(json) = self.json {
+         options = options.with_json_format(json);
+      }
+      if let Some(uuid) = self.uuid {
+         options = options.with_uuid_format(uuid);
+      }
+      if let Some(boolean) = self.boolean {
+         options = options.with_boolean_format(boolean);
+      }
+      if let Some(non_finite) = self.non_finite {
+         options = options.with_non_finite_format(non_finite);
+      }
+      if let Some(invalid_utf8) = self.invalid_utf8 {
+         options = options.with_invalid_utf8_format(invalid_utf8);
+      }
+      if let Some(column_names) = self.column_names {
+         options = options.with_column_name_format(column_names);
+      }
+      options
+   }
+}
+
+impl From<WriteBatchOptions> for WriteBatchConfig {
+   fn from(options: WriteBatchOptions) -> Self {
+      let mut config = WriteBatchConfig::new();
+      if let Some(window_ms) = options.window_ms {
+         config = config.with_window(std::time::Duration::from_millis(window_ms));
+      }
+      if let Some(max_batch_size) = options.max_batch_size {
+         config = config.with_max_batch_size(max_batch_size);
+      }
+      config
+   }
+}
+
 /// Serializable attached database specification for TypeScript interface
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -63,7 +206,7 @@ pub enum AttachedDatabaseMode {
 }
 
 /// Convert serializable specs to internal specs by resolving database references
-fn resolve_attached_specs(
+async fn resolve_attached_specs(
    specs: Vec<AttachedDatabaseSpec>,
    db_instances: &std::collections::HashMap<String, DatabaseWrapper>,
 ) -> Result<Vec<sqlx_sqlite_conn_mgr::AttachedSpec>> {
@@ -74,16 +217,15 @@ fn resolve_attached_specs(
          .get(&spec.database_path)
          .ok_or_else(|| Error::DatabaseNotLoaded(spec.database_path.clone()))?;
 
-      let mode = match spec.mode {
-         AttachedDatabaseMode::ReadOnly => sqlx_sqlite_conn_mgr::AttachedMode::ReadOnly,
-         AttachedDatabaseMode::ReadWrite => sqlx_sqlite_conn_mgr::AttachedMode::ReadWrite,
-      };
+      let mut builder = sqlx_sqlite_conn_mgr::AttachedSpec::builder()
+         .database(Arc::clone(wrapper.inner()))
+         .schema_name(spec.schema_name);
 
-      resolved.push(sqlx_sqlite_conn_mgr::AttachedSpec {
-         database: Arc::clone(wrapper.inner()),
-         schema_name: spec.schema_name,
-         mode,
-      });
+      if matches!(spec.mode, AttachedDatabaseMode::ReadOnly) {
+         builder = builder.read_only();
+      }
+
+      resolved.push(builder.build().await?);
    }
 
    Ok(resolved)
@@ -106,6 +248,8 @@ pub async fn load<R: Runtime>(
    app: AppHandle<R>,
    db_instances: State<'_, DbInstances>,
    migration_states: State<'_, MigrationStates>,
+   pre_commit_hooks: State<'_, PreCommitHooks>,
+   decoders: State<'_, Decoders>,
    db: String,
    custom_config: Option<SqliteDatabaseConfig>,
 ) -> Result<String> {
@@ -140,7 +284,9 @@ pub async fn load<R: Runtime>(
       }
       Entry::Vacant(entry) => {
          // We won the race, create and insert the wrapper
-         let wrapper = crate::resolve::connect(&db, &app, custom_config).await?;
+         let mut wrapper = crate::resolve::connect(&db, &app, custom_config).await?;
+         pre_commit_hooks.apply(&db, &mut wrapper);
+         decoders.apply(&db, &mut wrapper);
          entry.insert(wrapper);
          Ok(db)
       }
@@ -203,7 +349,7 @@ pub async fn execute(
    let mut builder = wrapper.execute(query, values);
 
    if let Some(specs) = attached {
-      let resolved_specs = resolve_attached_specs(specs, &instances)?;
+      let resolved_specs = resolve_attached_specs(specs, &instances).await?;
       builder = builder.attach(resolved_specs);
    }
 
@@ -212,7 +358,68 @@ pub async fn execute(
    Ok((result.rows_affected, result.last_insert_id))
 }
 
+/// Coalesce plain `execute` calls that arrive within a short window into one
+/// transaction per batch, for apps that fire many tiny independent writes.
+///
+/// Replaces any batching already enabled on the database, after its queued
+/// writes commit.
+#[tauri::command]
+pub async fn enable_write_batching(
+   db_instances: State<'_, DbInstances>,
+   db: String,
+   options: Option<WriteBatchOptions>,
+) -> Result<()> {
+   let mut instances = db_instances.inner.write().await;
+
+   let wrapper = instances
+      .get_mut(&db)
+      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+
+   wrapper
+      .enable_write_batching(options.unwrap_or_default().into())
+      .await;
+   Ok(())
+}
+
+/// Stop batching writes on a database, waiting for the queued ones to commit.
+#[tauri::command]
+pub async fn disable_write_batching(
+   db_instances: State<'_, DbInstances>,
+   db: String,
+) -> Result<()> {
+   let mut instances = db_instances.inner.write().await;
+
+   let wrapper = instances
+      .get_mut(&db)
+      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+
+   wrapper.disable_write_batching().await;
+   Ok(())
+}
+
+/// Set how values fetched from a database are converted to JSON, e.g. to
+/// return `DATETIME` columns as ISO-8601 strings. Unset options go back to
+/// their defaults; queries can override them with their own `decode` options.
+#[tauri::command]
+pub async fn set_decode_options(
+   db_instances: State<'_, DbInstances>,
+   db: String,
+   options: DecodeParams,
+) -> Result<()> {
+   let mut instances = db_instances.inner.write().await;
+
+   let wrapper = instances
+      .get_mut(&db)
+      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+
+   wrapper.set_decode_options(options.apply(DecodeOptions::default()));
+   Ok(())
+}
+
 /// Execute multiple write statements atomically within a transaction
+///
+/// With `begin_retry`, a `BEGIN` that fails because another connection or
+/// process holds the lock is retried with backoff.
 #[tauri::command]
 pub async fn execute_transaction(
    db_instances: State<'_, DbInstances>,
@@ -220,6 +427,8 @@ pub async fn execute_transaction(
    db: String,
    statements: Vec<Statement>,
    attached: Option<Vec<AttachedDatabaseSpec>>,
+   mode: Option<TransactionMode>,
+   begin_retry: Option<RetryOptions>,
 ) -> Result<Vec<WriteQueryResult>> {
    let instances = db_instances.inner.read().await;
 
@@ -238,7 +447,7 @@ pub async fn execute_transaction(
 
    // Resolve attached specs if provided
    let resolved_specs = if let Some(specs) = attached {
-      Some(resolve_attached_specs(specs, &instances)?)
+      Some(resolve_attached_specs(specs, &instances).await?)
    } else {
       None
    };
@@ -255,12 +464,18 @@ pub async fn execute_transaction(
          .map(|(query, values)| (query.as_str(), values.clone()))
          .collect();
 
-      let mut builder = wrapper_clone.execute_transaction(stmt_refs);
+      let mut builder = wrapper_clone
+         .execute_transaction(stmt_refs)
+         .mode(mode.unwrap_or_default());
 
       if let Some(specs) = resolved_specs {
          builder = builder.attach(specs);
       }
 
+      if let Some(options) = begin_retry {
+         builder = builder.retry_begin(options.into());
+      }
+
       let result = builder.execute().await;
 
       // Remove from tracking when complete (even if result is Err)
@@ -290,10 +505,24 @@ pub async fn execute_transaction(
    }
 }
 
+/// Rows returned by `fetch_all`: one map per row, a columnar table, or row
+/// maps along with the column types
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum FetchAllResult {
+   Rows(Vec<IndexMap<String, JsonValue>>),
+   Columnar(sqlx_sqlite_toolkit::ColumnarRows),
+   WithColumnTypes(sqlx_sqlite_toolkit::RowsWithColumnTypes),
+}
+
 /// Execute a SELECT query returning all matching rows.
 ///
 /// Returns the entire result set in a single response. For large or unbounded queries,
-/// prefer `fetch_page` with keyset pagination to keep memory usage bounded.
+/// prefer `fetch_page` with keyset pagination to keep memory usage bounded. With
+/// `columnar`, rows come back as `{ columns, rows }` so column names aren't repeated
+/// for every row. With `column_types`, rows come back as `{ columns, rows }` with
+/// each column's name, declared type and affinity. The two can't be combined.
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 pub async fn fetch_all(
    db_instances: State<'_, DbInstances>,
@@ -301,7 +530,18 @@ pub async fn fetch_all(
    query: String,
    values: Vec<JsonValue>,
    attached: Option<Vec<AttachedDatabaseSpec>>,
-) -> Result<Vec<IndexMap<String, JsonValue>>> {
+   columnar: Option<bool>,
+   column_types: Option<bool>,
+   decode: Option<DecodeParams>,
+) -> Result<FetchAllResult> {
+   let columnar = columnar.unwrap_or(false);
+   let column_types = column_types.unwrap_or(false);
+   if columnar && column_types {
+      return Err(Error::InvalidConfig(
+         "columnar and column_types can't be combined".into(),
+      ));
+   }
+
    let instances = db_instances.inner.read().await;
 
    let wrapper = instances
@@ -310,14 +550,28 @@ pub async fn fetch_all(
 
    let mut builder = wrapper.fetch_all(query, values);
 
+   if let Some(decode) = decode {
+      builder = builder.decode_options(decode.apply(wrapper.decode_options().clone()));
+   }
+
    if let Some(specs) = attached {
-      let resolved_specs = resolve_attached_specs(specs, &instances)?;
+      let resolved_specs = resolve_attached_specs(specs, &instances).await?;
       builder = builder.attach(resolved_specs);
    }
 
-   let result = builder.execute().await?;
+   if columnar {
+      return Ok(FetchAllResult::Columnar(
+         builder.columnar().execute().await?,
+      ));
+   }
 
-   Ok(result)
+   if column_types {
+      return Ok(FetchAllResult::WithColumnTypes(
+         builder.with_column_types().execute().await?,
+      ));
+   }
+
+   Ok(FetchAllResult::Rows(builder.execute().await?))
 }
 
 /// Execute a SELECT query expecting zero or one result
@@ -328,6 +582,7 @@ pub async fn fetch_one(
    query: String,
    values: Vec<JsonValue>,
    attached: Option<Vec<AttachedDatabaseSpec>>,
+   decode: Option<DecodeParams>,
 ) -> Result<Option<IndexMap<String, JsonValue>>> {
    let instances = db_instances.inner.read().await;
 
@@ -337,8 +592,12 @@ pub async fn fetch_one(
 
    let mut builder = wrapper.fetch_one(query, values);
 
+   if let Some(decode) = decode {
+      builder = builder.decode_options(decode.apply(wrapper.decode_options().clone()));
+   }
+
    if let Some(specs) = attached {
-      let resolved_specs = resolve_attached_specs(specs, &instances)?;
+      let resolved_specs = resolve_attached_specs(specs, &instances).await?;
       builder = builder.attach(resolved_specs);
    }
 
@@ -357,9 +616,13 @@ pub async fn fetch_page(
    values: Vec<JsonValue>,
    keyset: Vec<sqlx_sqlite_toolkit::KeysetColumn>,
    page_size: usize,
-   after: Option<Vec<JsonValue>>,
-   before: Option<Vec<JsonValue>>,
+   after: Option<sqlx_sqlite_toolkit::Cursor>,
+   before: Option<sqlx_sqlite_toolkit::Cursor>,
+   containing: Option<IndexMap<String, JsonValue>>,
+   opaque_cursor: Option<bool>,
+   with_total_count: Option<bool>,
    attached: Option<Vec<AttachedDatabaseSpec>>,
+   decode: Option<DecodeParams>,
 ) -> Result<sqlx_sqlite_toolkit::KeysetPage> {
    if after.is_some() && before.is_some() {
       return Err(Error::Toolkit(
@@ -375,14 +638,29 @@ pub async fn fetch_page(
 
    let mut builder = wrapper.fetch_page(query, values, keyset, page_size);
 
-   if let Some(cursor_values) = after {
-      builder = builder.after(cursor_values);
-   } else if let Some(cursor_values) = before {
-      builder = builder.before(cursor_values);
+   // `containing` positions the page itself and takes precedence over cursors
+   if let Some(key) = containing {
+      builder = builder.containing(key);
+   } else if let Some(cursor) = after {
+      builder = builder.after(cursor);
+   } else if let Some(cursor) = before {
+      builder = builder.before(cursor);
+   }
+
+   if opaque_cursor.unwrap_or(false) {
+      builder = builder.opaque_cursors();
+   }
+
+   if with_total_count.unwrap_or(false) {
+      builder = builder.with_total_count();
+   }
+
+   if let Some(decode) = decode {
+      builder = builder.decode_options(decode.apply(wrapper.decode_options().clone()));
    }
 
    if let Some(specs) = attached {
-      let resolved_specs = resolve_attached_specs(specs, &instances)?;
+      let resolved_specs = resolve_attached_specs(specs, &instances).await?;
       builder = builder.attach(resolved_specs);
    }
 
@@ -395,14 +673,17 @@ pub async fn fetch_page(
 ///
 /// Returns `true` if the database was loaded and successfully closed.
 /// Returns `false` if the database was not loaded (nothing to close).
-/// Any active subscriptions for this database are aborted before closing.
+/// Any active subscriptions and read snapshots for this database are ended before
+/// closing.
 #[tauri::command]
 pub async fn close(
    db_instances: State<'_, DbInstances>,
    active_subs: State<'_, ActiveSubscriptions>,
+   active_snapshots: State<'_, ActiveReadSnapshots>,
    db: String,
 ) -> Result<bool> {
    active_subs.remove_for_db(&db).await;
+   active_snapshots.remove_for_db(&db).await;
 
    let mut instances = db_instances.inner.write().await;
 
@@ -416,14 +697,16 @@ pub async fn close(
 
 /// Close all database connections
 ///
-/// All active subscriptions are aborted before closing. Each wrapper's
+/// All active subscriptions and read snapshots are ended before closing. Each wrapper's
 /// `close()` handles disabling its own observer at the crate level.
 #[tauri::command]
 pub async fn close_all(
    db_instances: State<'_, DbInstances>,
    active_subs: State<'_, ActiveSubscriptions>,
+   active_snapshots: State<'_, ActiveReadSnapshots>,
 ) -> Result<()> {
    active_subs.abort_all().await;
+   active_snapshots.abort_all().await;
 
    let mut instances = db_instances.inner.write().await;
 
@@ -448,14 +731,17 @@ pub async fn close_all(
 ///
 /// Returns `true` if the database was loaded and successfully removed.
 /// Returns `false` if the database was not loaded (nothing to remove).
-/// Any active subscriptions for this database are aborted before removing.
+/// Any active subscriptions and read snapshots for this database are ended before
+/// removing.
 #[tauri::command]
 pub async fn remove(
    db_instances: State<'_, DbInstances>,
    active_subs: State<'_, ActiveSubscriptions>,
+   active_snapshots: State<'_, ActiveReadSnapshots>,
    db: String,
 ) -> Result<bool> {
    active_subs.remove_for_db(&db).await;
+   active_snapshots.remove_for_db(&db).await;
 
    let mut instances = db_instances.inner.write().await;
 
@@ -491,6 +777,10 @@ pub async fn get_migration_events(
 /// This begins a transaction, executes the initial statements, and returns a token
 /// that can be used to continue, commit, or rollback the transaction.
 /// The writer connection is held for the entire transaction duration.
+///
+/// Fails with `TRANSACTION_ALREADY_ACTIVE` if the database already has one,
+/// unless `wait_ms` is given, in which case it waits up to that long for the
+/// active transaction to end.
 #[tauri::command]
 pub async fn begin_interruptible_transaction(
    db_instances: State<'_, DbInstances>,
@@ -498,19 +788,35 @@ pub async fn begin_interruptible_transaction(
    db: String,
    initial_statements: Vec<Statement>,
    attached: Option<Vec<AttachedDatabaseSpec>>,
-) -> Result<TransactionToken> {
-   let instances = db_instances.inner.read().await;
+   wait_ms: Option<u64>,
+) -> Result<OpenTransaction> {
+   // Take what's needed out of the instances map up front so waiting for the
+   // transaction slot doesn't hold the lock that `load` and `close` need
+   let (wrapper, resolved_specs) = {
+      let instances = db_instances.inner.read().await;
+
+      let wrapper = instances
+         .get(&db)
+         .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?
+         .clone();
+      let resolved_specs = match attached {
+         Some(specs) => Some(resolve_attached_specs(specs, &instances).await?),
+         None => None,
+      };
+      (wrapper, resolved_specs)
+   };
 
-   let wrapper = instances
-      .get(&db)
-      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+   // Reserve the database's transaction slot, queueing behind the active
+   // transaction if the caller asked to wait
+   let slot = active_txs
+      .reserve(&db, wait_ms.map(std::time::Duration::from_millis))
+      .await?;
 
    // Generate unique transaction ID
    let transaction_id = Uuid::new_v4().to_string();
 
    // Acquire appropriate writer based on whether databases are attached
-   let mut writer = if let Some(specs) = attached {
-      let resolved_specs = resolve_attached_specs(specs, &instances)?;
+   let mut writer = if let Some(resolved_specs) = resolved_specs {
       let guard =
          sqlx_sqlite_conn_mgr::acquire_writer_with_attached(wrapper.inner(), resolved_specs)
             .await?;
@@ -524,28 +830,35 @@ pub async fn begin_interruptible_transaction(
 
    // Execute initial statements
    let mut active_tx =
-      ActiveInterruptibleTransaction::new(db.clone(), transaction_id.clone(), writer);
+      ActiveInterruptibleTransaction::new(db.clone(), transaction_id.clone(), writer)
+         .with_slot(slot)
+         .with_pre_commit_hooks(wrapper.pre_commit_hooks().to_vec())
+         .with_decode_options(wrapper.decode_options().clone());
 
-   active_tx.continue_with(initial_statements).await?;
+   let results = active_tx.continue_with(initial_statements).await?;
 
    // Store transaction state
    active_txs.insert(db.clone(), active_tx).await?;
 
-   Ok(TransactionToken {
-      db_path: db,
-      transaction_id,
+   Ok(OpenTransaction {
+      token: TransactionToken {
+         db_path: db,
+         transaction_id,
+      },
+      results,
    })
 }
 
 /// Continue, commit, or rollback an interruptible transaction.
 ///
-/// Returns a new token if continuing with more statements, or None if committed/rolled back.
+/// Returns a new token, with each statement's result when continuing, if the
+/// transaction is still open, or None if committed/rolled back.
 #[tauri::command]
 pub async fn transaction_continue(
    active_txs: State<'_, ActiveInterruptibleTransactions>,
    token: TransactionToken,
    action: TransactionAction,
-) -> Result<Option<TransactionToken>> {
+) -> Result<Option<OpenTransaction>> {
    match action {
       TransactionAction::Continue { statements } => {
          // Remove transaction to get mutable access
@@ -555,10 +868,10 @@ pub async fn transaction_continue(
 
          // Execute statements on the transaction
          match tx.continue_with(statements).await {
-            Ok(_results) => {
+            Ok(results) => {
                // Re-insert transaction - if this fails, tx is dropped and auto-rolled back
                match active_txs.insert(token.db_path.clone(), tx).await {
-                  Ok(()) => Ok(Some(token)),
+                  Ok(()) => Ok(Some(OpenTransaction { token, results })),
                   Err(e) => {
                      // Transaction lost but will auto-rollback via Drop
                      Err(e.into())
@@ -573,6 +886,30 @@ pub async fn transaction_continue(
          }
       }
 
+      TransactionAction::Savepoint { name } => {
+         let mut tx = active_txs
+            .remove(&token.db_path, &token.transaction_id)
+            .await?;
+         let result = tx.savepoint(&name).await;
+         reinsert_after_savepoint_action(&active_txs, token, tx, result).await
+      }
+
+      TransactionAction::Release { name } => {
+         let mut tx = active_txs
+            .remove(&token.db_path, &token.transaction_id)
+            .await?;
+         let result = tx.release(&name).await;
+         reinsert_after_savepoint_action(&active_txs, token, tx, result).await
+      }
+
+      TransactionAction::RollbackTo { name } => {
+         let mut tx = active_txs
+            .remove(&token.db_path, &token.transaction_id)
+            .await?;
+         let result = tx.rollback_to(&name).await;
+         reinsert_after_savepoint_action(&active_txs, token, tx, result).await
+      }
+
       TransactionAction::Commit => {
          // Remove transaction and commit
          let tx = active_txs
@@ -595,6 +932,26 @@ pub async fn transaction_continue(
    }
 }
 
+/// Put a transaction back after a savepoint action.
+///
+/// Unlike failed statements, a failed savepoint action (e.g. an unknown name)
+/// leaves the transaction as it was, so it stays open and the error is
+/// returned.
+async fn reinsert_after_savepoint_action(
+   active_txs: &ActiveInterruptibleTransactions,
+   token: TransactionToken,
+   tx: ActiveInterruptibleTransaction,
+   result: sqlx_sqlite_toolkit::Result<()>,
+) -> Result<Option<OpenTransaction>> {
+   // If re-inserting fails, tx is dropped and auto-rolled back
+   active_txs.insert(token.db_path.clone(), tx).await?;
+   result?;
+   Ok(Some(OpenTransaction {
+      token,
+      results: Vec::new(),
+   }))
+}
+
 /// Read from database within an interruptible transaction to see uncommitted writes.
 ///
 /// This executes a SELECT query on the same connection as the transaction,
@@ -631,6 +988,192 @@ pub async fn transaction_read(
    }
 }
 
+/// Read a single row within an interruptible transaction, seeing uncommitted
+/// writes.
+///
+/// Like `fetch_one`, fails if the query returns more than one row. That
+/// leaves the transaction open; any other failure rolls it back, as with
+/// `transaction_read`.
+#[tauri::command]
+pub async fn transaction_fetch_one(
+   active_txs: State<'_, ActiveInterruptibleTransactions>,
+   token: TransactionToken,
+   query: String,
+   values: Vec<JsonValue>,
+) -> Result<Option<IndexMap<String, JsonValue>>> {
+   // Remove transaction to get mutable access
+   let mut tx = active_txs
+      .remove(&token.db_path, &token.transaction_id)
+      .await?;
+
+   match tx.fetch_one(query, values).await {
+      Ok(row) => {
+         // Re-insert transaction - if this fails, tx is dropped and auto-rolled back
+         active_txs.insert(token.db_path.clone(), tx).await?;
+         Ok(row)
+      }
+      Err(e @ sqlx_sqlite_toolkit::Error::MultipleRowsReturned(_)) => {
+         active_txs.insert(token.db_path.clone(), tx).await?;
+         Err(e.into())
+      }
+      Err(e) => {
+         // Read failed, explicitly rollback before returning error
+         let _ = tx.rollback().await;
+         Err(e.into())
+      }
+   }
+}
+
+/// Keep an interruptible transaction alive by restarting its inactivity
+/// timeout, returning how many statements it has run and how long it has been
+/// open.
+#[tauri::command]
+pub async fn transaction_heartbeat(
+   active_txs: State<'_, ActiveInterruptibleTransactions>,
+   token: TransactionToken,
+) -> Result<TransactionStats> {
+   Ok(active_txs
+      .heartbeat(&token.db_path, &token.transaction_id)
+      .await?)
+}
+
+/// Roll back and remove a single interruptible transaction.
+///
+/// Returns `false` without touching the database if the token's transaction
+/// is no longer active, e.g. because it already committed. Unlike a `Rollback`
+/// action, a transaction that has timed out is rolled back without an error.
+#[tauri::command]
+pub async fn transaction_abort(
+   active_txs: State<'_, ActiveInterruptibleTransactions>,
+   token: TransactionToken,
+) -> Result<bool> {
+   Ok(active_txs
+      .abort(&token.db_path, &token.transaction_id)
+      .await?)
+}
+
+/// List the active interruptible transactions and in-flight regular
+/// transactions across all databases, for diagnosing lock contention.
+///
+/// Interruptible transactions are identified by a token prefix rather than the
+/// full token, so the listing can't be used to take them over.
+#[tauri::command]
+pub async fn list_transactions(
+   active_txs: State<'_, ActiveInterruptibleTransactions>,
+   regular_txs: State<'_, ActiveRegularTransactions>,
+) -> Result<TransactionList> {
+   let regular = regular_txs
+      .list()
+      .await
+      .into_iter()
+      .map(|tx| RegularTransactionStatus {
+         // Keys are "<db>:<uuid>", see `execute_transaction`
+         db_path: tx
+            .key
+            .rsplit_once(':')
+            .map_or(tx.key.as_str(), |(db, _)| db)
+            .to_string(),
+         age_ms: tx.age_ms,
+      })
+      .collect();
+
+   Ok(TransactionList {
+      interruptible: active_txs.list().await,
+      regular,
+   })
+}
+
+/// Begin a read snapshot and return a token.
+///
+/// Pins a read connection inside a `BEGIN DEFERRED` transaction so that every
+/// `snapshot_fetch_all`/`snapshot_fetch_one` with the token sees the database
+/// as of this moment, until `release_read_snapshot`. Snapshots time out like
+/// interruptible transactions.
+#[tauri::command]
+pub async fn begin_read_snapshot(
+   db_instances: State<'_, DbInstances>,
+   active_snapshots: State<'_, ActiveReadSnapshots>,
+   db: String,
+) -> Result<SnapshotToken> {
+   let instances = db_instances.inner.read().await;
+
+   let wrapper = instances
+      .get(&db)
+      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+
+   let snapshot = wrapper.begin_read_snapshot().await?;
+   let snapshot_id = Uuid::new_v4().to_string();
+
+   active_snapshots
+      .insert(db.clone(), snapshot_id.clone(), snapshot)
+      .await;
+
+   Ok(SnapshotToken {
+      db_path: db,
+      snapshot_id,
+   })
+}
+
+/// Fetch all rows as of a read snapshot.
+///
+/// A failed query leaves the snapshot open.
+#[tauri::command]
+pub async fn snapshot_fetch_all(
+   active_snapshots: State<'_, ActiveReadSnapshots>,
+   token: SnapshotToken,
+   query: String,
+   values: Vec<JsonValue>,
+) -> Result<Vec<IndexMap<String, JsonValue>>> {
+   let mut snapshot = active_snapshots
+      .remove(&token.db_path, &token.snapshot_id)
+      .await?;
+
+   let result = snapshot.fetch_all(query, values).await;
+
+   active_snapshots
+      .insert(token.db_path, token.snapshot_id, snapshot)
+      .await;
+
+   Ok(result?)
+}
+
+/// Fetch a single row as of a read snapshot.
+///
+/// Like `fetch_one`, fails if the query returns more than one row. A failed
+/// query leaves the snapshot open.
+#[tauri::command]
+pub async fn snapshot_fetch_one(
+   active_snapshots: State<'_, ActiveReadSnapshots>,
+   token: SnapshotToken,
+   query: String,
+   values: Vec<JsonValue>,
+) -> Result<Option<IndexMap<String, JsonValue>>> {
+   let mut snapshot = active_snapshots
+      .remove(&token.db_path, &token.snapshot_id)
+      .await?;
+
+   let result = snapshot.fetch_one(query, values).await;
+
+   active_snapshots
+      .insert(token.db_path, token.snapshot_id, snapshot)
+      .await;
+
+   Ok(result?)
+}
+
+/// End a read snapshot and return its connection to the read pool.
+#[tauri::command]
+pub async fn release_read_snapshot(
+   active_snapshots: State<'_, ActiveReadSnapshots>,
+   token: SnapshotToken,
+) -> Result<()> {
+   let snapshot = active_snapshots
+      .remove(&token.db_path, &token.snapshot_id)
+      .await?;
+
+   Ok(snapshot.release().await?)
+}
+
 /// Enable observation on a database for change notifications.
 ///
 /// Must be called before `subscribe()`. Configures the observer with the
@@ -639,8 +1182,12 @@ pub async fn transaction_read(
 /// If observation is already enabled, this will abort all existing subscriptions
 /// for this database, disable the previous observer, and enable a new one with
 /// the provided configuration. Callers must re-subscribe after re-calling this.
+///
+/// With `emit_events`, each change is also emitted as a Tauri event named by
+/// [`change_event_name`]. The task emitting them counts as one subscription.
 #[tauri::command]
-pub async fn observe(
+pub async fn observe<R: Runtime>(
+   app: AppHandle<R>,
    db_instances: State<'_, DbInstances>,
    active_subs: State<'_, ActiveSubscriptions>,
    db: String,
@@ -667,7 +1214,9 @@ pub async fn observe(
       .get_mut(&db)
       .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
 
-   let mut observer_config = sqlx_sqlite_observer::ObserverConfig::new().with_tables(tables);
+   let mut observer_config =
+      sqlx_sqlite_observer::ObserverConfig::new().with_tables(tables.clone());
+   let mut emit_events = false;
 
    if let Some(params) = config {
       if let Some(capacity) = params.channel_capacity {
@@ -681,9 +1230,48 @@ pub async fn observe(
       if let Some(capture) = params.capture_values {
          observer_config = observer_config.with_capture_values(capture);
       }
+      emit_events = params.emit_events.unwrap_or(false);
    }
 
    wrapper.enable_observation(observer_config);
+
+   if emit_events {
+      let observable = wrapper
+         .observable()
+         .ok_or_else(|| Error::ObservationNotEnabled(db.clone()))?;
+      let mut stream = observable.subscribe_stream(tables);
+      let db_path = db.clone();
+
+      let handle = tokio::spawn(async move {
+         while let Some(event) = stream.next().await {
+            match event {
+               TableChangeEvent::Change(change) => {
+                  let name = change_event_name(&db_path, &change.table);
+                  if let Err(e) = app.emit(&name, change_to_event(&db_path, &change)) {
+                     debug!("Failed to emit {}: {}", name, e);
+                  }
+               }
+               TableChangeEvent::Lagged(count) => {
+                  debug!(
+                     "Change events for db {} missed {} change(s)",
+                     db_path, count
+                  );
+               }
+            }
+         }
+
+         debug!("Change events for db {} ended", db_path);
+      });
+
+      active_subs
+         .insert(
+            Uuid::new_v4().to_string(),
+            db.clone(),
+            handle.abort_handle(),
+         )
+         .await;
+   }
+
    Ok(())
 }
 
@@ -701,8 +1289,6 @@ pub async fn subscribe(
    tables: Vec<String>,
    on_event: Channel<TableChangePayload>,
 ) -> Result<String> {
-   const MAX_SUBSCRIPTIONS_PER_DATABASE: usize = 100;
-
    let sub_count = active_subs.count_for_db(&db).await;
    if sub_count >= MAX_SUBSCRIPTIONS_PER_DATABASE {
       return Err(Error::TooManySubscriptions(MAX_SUBSCRIPTIONS_PER_DATABASE));
@@ -749,6 +1335,91 @@ pub async fn subscribe(
    Ok(subscription_id)
 }
 
+/// Watch a paginated SELECT query, pushing its page to `on_event` now and
+/// again whenever a table it reads changes.
+///
+/// Re-runs wait `debounce_ms` (default 0) after the first of a burst of
+/// changes, and pages that didn't change aren't sent. Watches count toward
+/// the per-database subscription limit. Returns the watch ID to pass to
+/// `unwatch_query`.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn watch_query(
+   db_instances: State<'_, DbInstances>,
+   active_subs: State<'_, ActiveSubscriptions>,
+   db: String,
+   query: String,
+   values: Vec<JsonValue>,
+   keyset: Vec<sqlx_sqlite_toolkit::KeysetColumn>,
+   page_size: usize,
+   after: Option<sqlx_sqlite_toolkit::Cursor>,
+   debounce_ms: Option<u64>,
+   on_event: Channel<WatchQueryPayload>,
+) -> Result<String> {
+   let sub_count = active_subs.count_for_db(&db).await;
+   if sub_count >= MAX_SUBSCRIPTIONS_PER_DATABASE {
+      return Err(Error::TooManySubscriptions(MAX_SUBSCRIPTIONS_PER_DATABASE));
+   }
+
+   let instances = db_instances.inner.read().await;
+
+   let wrapper = instances
+      .get(&db)
+      .ok_or_else(|| Error::DatabaseNotLoaded(db.clone()))?;
+
+   if wrapper.observable().is_none() {
+      return Err(Error::ObservationNotEnabled(db.clone()));
+   }
+
+   let mut builder = wrapper.fetch_page(query, values, keyset, page_size);
+   if let Some(cursor) = after {
+      builder = builder.after(cursor);
+   }
+
+   let debounce = std::time::Duration::from_millis(debounce_ms.unwrap_or(0));
+   let mut pages = wrapper.watch_page(builder, debounce)?;
+
+   let watch_id = Uuid::new_v4().to_string();
+
+   // Spawn task to forward pages to the Tauri Channel
+   let id = watch_id.clone();
+   let db_path = db.clone();
+
+   let handle = tokio::spawn(async move {
+      while let Some(page) = pages.next().await {
+         let payload = match page {
+            Ok(page) => WatchQueryPayload::Page(page),
+            Err(e) => WatchQueryPayload::Error(Error::Toolkit(e)),
+         };
+         if on_event.send(payload).is_err() {
+            // Channel closed (frontend disconnected)
+            debug!("Query watch {} channel closed, stopping", id);
+            break;
+         }
+      }
+
+      debug!("Query watch {} for db {} ended", id, db_path);
+   });
+
+   // Tracked with subscriptions so unobserve and close stop it too
+   active_subs
+      .insert(watch_id.clone(), db.clone(), handle.abort_handle())
+      .await;
+
+   Ok(watch_id)
+}
+
+/// Stop watching a query.
+///
+/// Returns `true` if the watch was found and stopped.
+#[tauri::command]
+pub async fn unwatch_query(
+   active_subs: State<'_, ActiveSubscriptions>,
+   watch_id: String,
+) -> Result<bool> {
+   Ok(active_subs.remove(&watch_id).await)
+}
+
 /// Unsubscribe from change notifications.
 ///
 /// Returns `true` if the subscription was found and removed.
@@ -18,8 +18,9 @@ pub use sqlx_sqlite_conn_mgr::{
    AttachedMode, AttachedSpec, Migrator as SqliteMigrator, SqliteDatabaseConfig,
 };
 pub use sqlx_sqlite_toolkit::{
-   ActiveInterruptibleTransactions, ActiveRegularTransactions, DatabaseWrapper,
-   InterruptibleTransaction, InterruptibleTransactionBuilder, Statement,
+   ActiveInterruptibleTransactions, ActiveReadSnapshots, ActiveRegularTransactions,
+   DatabaseWrapper, Decoder, DecoderTarget, InterruptibleTransaction,
+   InterruptibleTransactionBuilder, PreCommitHook, ReadSnapshot, Statement,
    TransactionExecutionBuilder, WriteQueryResult,
 };
 
@@ -79,6 +80,36 @@ impl DbInstances {
    }
 }
 
+/// Pre-commit hooks registered with `Builder::add_pre_commit_hook()`, applied
+/// to each database when it's loaded.
+#[derive(Clone, Default)]
+pub struct PreCommitHooks(Arc<HashMap<String, Vec<PreCommitHook>>>);
+
+impl PreCommitHooks {
+   /// Register the hooks for `path` on a freshly connected wrapper
+   pub(crate) fn apply(&self, path: &str, wrapper: &mut DatabaseWrapper) {
+      for hook in self.0.get(path).into_iter().flatten() {
+         let hook = Arc::clone(hook);
+         wrapper.add_pre_commit_hook(move |conn| hook(conn));
+      }
+   }
+}
+
+/// Decoders registered with `Builder::add_decoder()`, applied to each database
+/// when it's loaded.
+#[derive(Clone, Default)]
+pub struct Decoders(Arc<HashMap<String, Vec<(DecoderTarget, Decoder)>>>);
+
+impl Decoders {
+   /// Register the decoders for `path` on a freshly connected wrapper
+   pub(crate) fn apply(&self, path: &str, wrapper: &mut DatabaseWrapper) {
+      for (target, decoder) in self.0.get(path).into_iter().flatten() {
+         let decoder = Arc::clone(decoder);
+         wrapper.add_decoder(target.clone(), move |value| decoder(value));
+      }
+   }
+}
+
 /// Migration status for a database.
 #[derive(Debug, Clone)]
 pub enum MigrationStatus {
@@ -180,7 +211,7 @@ pub struct MigrationEvent {
 ///     .expect("error while running tauri application");
 /// # }
 /// ```
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct Builder {
    /// Migrations registered per database path
    migrations: HashMap<String, Arc<Migrator>>,
@@ -188,6 +219,23 @@ pub struct Builder {
    transaction_timeout: Option<std::time::Duration>,
    /// Maximum number of concurrently loaded databases. Defaults to 50.
    max_databases: Option<usize>,
+   /// Pre-commit hooks registered per database path
+   pre_commit_hooks: HashMap<String, Vec<PreCommitHook>>,
+   /// Custom value decoders registered per database path
+   decoders: HashMap<String, Vec<(DecoderTarget, Decoder)>>,
+}
+
+impl std::fmt::Debug for Builder {
+   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      // Hooks and decoders are closures, so only their database paths are shown
+      f.debug_struct("Builder")
+         .field("migrations", &self.migrations)
+         .field("transaction_timeout", &self.transaction_timeout)
+         .field("max_databases", &self.max_databases)
+         .field("pre_commit_hooks", &self.pre_commit_hooks.keys())
+         .field("decoders", &self.decoders.keys())
+         .finish()
+   }
 }
 
 impl Builder {
@@ -197,6 +245,8 @@ impl Builder {
          migrations: HashMap::new(),
          transaction_timeout: None,
          max_databases: None,
+         pre_commit_hooks: HashMap::new(),
+         decoders: HashMap::new(),
       }
    }
 
@@ -226,10 +276,100 @@ impl Builder {
       self
    }
 
+   /// Register a check to run just before every transaction on a database
+   /// commits, both `executeTransaction()` and interruptible commits.
+   ///
+   /// The hook gets the transaction's connection with `PRAGMA query_only` set,
+   /// so it can read the uncommitted changes but not write. Returning an error
+   /// rolls the transaction back and rejects the commit with that error's code,
+   /// `PRE_COMMIT_REJECTED` for `Error::PreCommitRejected`. Useful for
+   /// cross-table invariants SQLite can't express. Multiple hooks for a path
+   /// run in the order registered.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx_sqlite_toolkit::Error;
+   /// use tauri_plugin_sqlite::Builder;
+   ///
+   /// # fn example() {
+   /// Builder::new()
+   ///     .add_pre_commit_hook("main.db", |conn| {
+   ///         Box::pin(async move {
+   ///             let overdrawn: i64 =
+   ///                 sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE balance < 0")
+   ///                     .fetch_one(&mut *conn)
+   ///                     .await?;
+   ///             if overdrawn > 0 {
+   ///                 return Err(Error::PreCommitRejected("account overdrawn".into()));
+   ///             }
+   ///             Ok(())
+   ///         })
+   ///     })
+   ///     .build::<tauri::Wry>();
+   /// # }
+   /// ```
+   pub fn add_pre_commit_hook<F>(mut self, path: &str, hook: F) -> Self
+   where
+      F: for<'c> Fn(
+            &'c mut sqlx::SqliteConnection,
+         ) -> futures::future::BoxFuture<'c, sqlx_sqlite_toolkit::Result<()>>
+         + Send
+         + Sync
+         + 'static,
+   {
+      self
+         .pre_commit_hooks
+         .entry(path.to_string())
+         .or_default()
+         .push(Arc::new(hook));
+      self
+   }
+
+   /// Register a custom decoder for a database, converting non-NULL values of
+   /// a column name or declared type to JSON wherever its rows are decoded.
+   /// Useful for domain types such as money, enums or encrypted cells.
+   ///
+   /// A decoder for a column name wins over one for a declared type; among
+   /// decoders for the same target, the first registered wins.
+   ///
+   /// # Example
+   ///
+   /// ```no_run
+   /// use sqlx::{Value, ValueRef};
+   /// use tauri_plugin_sqlite::{Builder, DecoderTarget};
+   ///
+   /// # fn example() {
+   /// Builder::new()
+   ///     .add_decoder("main.db", DecoderTarget::Column("price".into()), |value| {
+   ///         let cents = value.to_owned().try_decode::<i64>().unwrap_or_default();
+   ///         Ok(serde_json::json!(format!("{}.{:02}", cents / 100, cents % 100)))
+   ///     })
+   ///     .build::<tauri::Wry>();
+   /// # }
+   /// ```
+   pub fn add_decoder<F>(mut self, path: &str, target: DecoderTarget, decoder: F) -> Self
+   where
+      F: Fn(sqlx::sqlite::SqliteValueRef<'_>) -> sqlx_sqlite_toolkit::Result<serde_json::Value>
+         + Send
+         + Sync
+         + 'static,
+   {
+      let decoder: Decoder = Arc::new(decoder);
+      self
+         .decoders
+         .entry(path.to_string())
+         .or_default()
+         .push((target, decoder));
+      self
+   }
+
    /// Set the timeout for interruptible transactions.
    ///
    /// If an interruptible transaction exceeds this duration, it will be automatically
-   /// rolled back on the next access attempt. Defaults to 5 minutes.
+   /// rolled back on the next access attempt. A `transaction_heartbeat` restarts the
+   /// clock. Read snapshots left open this long are released the same way. Defaults
+   /// to 5 minutes.
    ///
    /// Returns `Err(Error::InvalidConfig)` if `timeout` is zero.
    pub fn transaction_timeout(mut self, timeout: std::time::Duration) -> Result<Self> {
@@ -263,15 +403,28 @@ impl Builder {
       let migrations = Arc::new(self.migrations);
       let transaction_timeout = self.transaction_timeout;
       let max_databases = self.max_databases;
+      let pre_commit_hooks = PreCommitHooks(Arc::new(self.pre_commit_hooks));
+      let decoders = Decoders(Arc::new(self.decoders));
 
       PluginBuilder::<R>::new("sqlite")
          .invoke_handler(tauri::generate_handler![
             commands::load,
             commands::execute,
             commands::execute_transaction,
+            commands::enable_write_batching,
+            commands::disable_write_batching,
+            commands::set_decode_options,
             commands::begin_interruptible_transaction,
             commands::transaction_continue,
             commands::transaction_read,
+            commands::transaction_fetch_one,
+            commands::transaction_heartbeat,
+            commands::transaction_abort,
+            commands::list_transactions,
+            commands::begin_read_snapshot,
+            commands::snapshot_fetch_all,
+            commands::snapshot_fetch_one,
+            commands::release_read_snapshot,
             commands::fetch_all,
             commands::fetch_one,
             commands::fetch_page,
@@ -283,6 +436,8 @@ impl Builder {
             commands::subscribe,
             commands::unsubscribe,
             commands::unobserve,
+            commands::watch_query,
+            commands::unwatch_query,
          ])
          .setup(move |app, _api| {
             app.manage(match max_databases {
@@ -290,11 +445,17 @@ impl Builder {
                None => DbInstances::default(),
             });
             app.manage(MigrationStates::default());
+            app.manage(pre_commit_hooks);
+            app.manage(decoders);
             app.manage(match transaction_timeout {
                Some(timeout) => ActiveInterruptibleTransactions::new(timeout),
                None => ActiveInterruptibleTransactions::default(),
             });
             app.manage(ActiveRegularTransactions::default());
+            app.manage(match transaction_timeout {
+               Some(timeout) => ActiveReadSnapshots::new(timeout),
+               None => ActiveReadSnapshots::default(),
+            });
             app.manage(subscriptions::ActiveSubscriptions::default());
 
             // Initialize migration states as Pending for all registered databases
@@ -370,6 +531,7 @@ impl Builder {
                   let instances_clone = app.state::<DbInstances>().inner().clone();
                   let interruptible_txs_clone = app.state::<ActiveInterruptibleTransactions>().inner().clone();
                   let regular_txs_clone = app.state::<ActiveRegularTransactions>().inner().clone();
+                  let snapshots_clone = app.state::<ActiveReadSnapshots>().inner().clone();
                   let active_subs_clone = app.state::<subscriptions::ActiveSubscriptions>().inner().clone();
 
                   // Run cleanup on the async runtime (without blocking the event loop),
@@ -389,6 +551,7 @@ impl Builder {
                               debug!("Aborting active subscriptions and transactions");
                               active_subs_clone.abort_all().await;
                               sqlx_sqlite_toolkit::cleanup_all_transactions(&interruptible_txs_clone, &regular_txs_clone).await;
+                              snapshots_clone.abort_all().await;
 
                               // Close databases (each wrapper's close() disables its own
                               // observer at the crate level, unregistering SQLite hooks)
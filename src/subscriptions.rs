@@ -99,6 +99,101 @@ fn change_to_data(change: &TableChange) -> TableChangeData {
    }
 }
 
+/// Prefix of the per-table change event names.
+pub const CHANGE_EVENT_PREFIX: &str = "sqlite://change";
+
+/// Name of the event table changes are emitted under:
+/// `sqlite://change/{db}/{table}`.
+///
+/// Tauri event names only allow alphanumerics, `-`, `/`, `:` and `_`, so
+/// `db` and `table` are encoded: ASCII alphanumerics and `-` are kept, every
+/// other UTF-8 byte becomes `_` followed by two lowercase hex digits (e.g.
+/// `main.db` becomes `main_2edb`). Mirrored by `changeEventName()` in
+/// guest-js.
+pub fn change_event_name(db: &str, table: &str) -> String {
+   format!(
+      "{CHANGE_EVENT_PREFIX}/{}/{}",
+      encode_event_segment(db),
+      encode_event_segment(table)
+   )
+}
+
+fn encode_event_segment(segment: &str) -> String {
+   let mut encoded = String::with_capacity(segment.len());
+   for byte in segment.bytes() {
+      if byte.is_ascii_alphanumeric() || byte == b'-' {
+         encoded.push(byte as char);
+      } else {
+         encoded.push_str(&format!("_{byte:02x}"));
+      }
+   }
+   encoded
+}
+
+/// A column's values before and after a change, in a [`ChangeEventPayload`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnChangePayload {
+   pub name: String,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub old_value: Option<ColumnValuePayload>,
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub new_value: Option<ColumnValuePayload>,
+}
+
+/// Payload of the per-table change events emitted when observation is
+/// configured with `emit_events`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEventPayload {
+   /// The database as the frontend loaded it
+   pub database: String,
+   pub table: String,
+   /// `"insert"`, `"update"` or `"delete"`, or `None` when not known
+   pub operation: Option<String>,
+   pub primary_key: Vec<ColumnValuePayload>,
+   pub row_id: Option<i64>,
+   /// The row's columns in schema order. Empty when values aren't captured.
+   pub columns: Vec<ColumnChangePayload>,
+}
+
+/// Convert an observer `TableChange` to the payload emitted for `db`.
+pub fn change_to_event(db: &str, change: &TableChange) -> ChangeEventPayload {
+   let data = change_to_data(change);
+   let value_at = |values: &Option<Vec<ColumnValuePayload>>, idx: usize| {
+      values.as_ref().and_then(|vals| vals.get(idx).cloned())
+   };
+
+   ChangeEventPayload {
+      database: db.to_string(),
+      table: data.table,
+      operation: data.operation,
+      primary_key: data.primary_key,
+      row_id: data.rowid,
+      columns: change
+         .columns
+         .iter()
+         .enumerate()
+         .map(|(idx, name)| ColumnChangePayload {
+            name: name.clone(),
+            old_value: value_at(&data.old_values, idx),
+            new_value: value_at(&data.new_values, idx),
+         })
+         .collect(),
+   }
+}
+
+/// Event payload sent to the frontend for a watched query.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "camelCase")]
+pub enum WatchQueryPayload {
+   /// The page as of the latest relevant change
+   Page(sqlx_sqlite_toolkit::KeysetPage),
+   /// Re-running the query failed; no further events follow
+   Error(crate::Error),
+}
+
 /// Observer config params from the frontend.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -107,6 +202,9 @@ pub struct ObserverConfigParams {
    pub channel_capacity: Option<usize>,
    /// Whether to capture column values in change notifications. Default: true.
    pub capture_values: Option<bool>,
+   /// Whether to emit each change as a `sqlite://change/{db}/{table}` event.
+   /// Default: false.
+   pub emit_events: Option<bool>,
 }
 
 /// Tracks an active subscription's abort handle.
@@ -176,3 +274,20 @@ impl ActiveSubscriptions {
       }
    }
 }
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_change_event_name_encodes_segments() {
+      assert_eq!(
+         change_event_name("main.db", "user_posts"),
+         "sqlite://change/main_2edb/user_5fposts"
+      );
+      assert_eq!(
+         change_event_name("data/app-1.db", "café"),
+         "sqlite://change/data_2fapp-1_2edb/caf_c3_a9"
+      );
+   }
+}